@@ -0,0 +1,24 @@
+//! Exposes the locked `ironshield-core` version to the binary, for
+//! recording alongside benchmark results, and the optimization level,
+//! for warning about debug builds.
+
+fn main() {
+    println!("cargo:rerun-if-changed=Cargo.lock");
+
+    let opt_level = std::env::var("OPT_LEVEL").unwrap_or_else(|_| "unknown".to_string());
+    println!("cargo:rustc-env=IRONSHIELD_OPT_LEVEL={opt_level}");
+
+    let version = std::fs::read_to_string("Cargo.lock")
+        .ok()
+        .and_then(|lock| core_version(&lock))
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=IRONSHIELD_CORE_VERSION={version}");
+}
+
+/// Finds `version = "…"` right after `name = "ironshield-core"`.
+fn core_version(lock: &str) -> Option<String> {
+    let mut lines = lock.lines().skip_while(|line| line.trim() != r#"name = "ironshield-core""#);
+    lines.next()?;
+    let version = lines.next()?.trim().strip_prefix("version = ")?;
+    Some(version.trim_matches('"').to_string())
+}
@@ -0,0 +1,28 @@
+// Validates an endpoint from inside another program, printing progress
+// as it goes:
+//
+//     cargo run --example embedded -- https://example.com/protected
+
+use ironshield_cli::{CliClient, ClientConfig, Event, SolveOptions, Strategy};
+
+#[tokio::main]
+async fn main() -> color_eyre::Result<()> {
+    let endpoint = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "https://example.com/protected".to_string());
+
+    let client = CliClient::new(ClientConfig::default())?
+        .with_solve_options(SolveOptions { strategy: Strategy::Efficient, ..SolveOptions::default() })
+        .on_event(|event| match event {
+            Event::ChallengeFetched { difficulty, .. } => eprintln!("Fetched a challenge of difficulty {difficulty}"),
+            Event::RateLimited { wait }                => eprintln!("Rate limited; waiting {wait:?}"),
+            Event::SolveStarted { thread_count, .. }   => eprintln!("Solving on {thread_count} threads"),
+            Event::ThreadsGranted { granted, .. }      => eprintln!("Got {granted} threads"),
+            Event::Solved { elapsed }                  => eprintln!("Solved in {elapsed:?}"),
+            Event::Validated { .. }                    => eprintln!("Validated"),
+        });
+
+    let token = client.validate(&endpoint).await?;
+    println!("Token valid until {}", token.valid_for);
+    Ok(())
+}
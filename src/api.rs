@@ -0,0 +1,185 @@
+use color_eyre::eyre::eyre;
+use ironshield::{ClientConfig, IronShieldChallenge, IronShieldChallengeResponse};
+use ironshield_types::IronShieldToken;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::api_version::Negotiated;
+use crate::metadata::ChallengeMetadata;
+
+/// What `/request` answered: the challenge and whatever the API sent
+/// next to it.
+#[derive(Debug, Clone)]
+pub struct ChallengeEnvelope {
+    pub challenge:  IronShieldChallenge,
+    /// `None` when the API sent no `metadata` object.
+    pub metadata:   Option<ChallengeMetadata>,
+    /// How the envelope's `api_version` compares with this CLI's.
+    pub negotiated: Negotiated,
+}
+
+/// A submit the API answered without a token: with an error status,
+/// or with a body that isn't a token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoToken {
+    pub status: StatusCode,
+    /// The response body, or why a successful one isn't a token.
+    pub reason: String,
+}
+
+impl std::fmt::Display for NoToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.status.is_success() {
+            true  => write!(f, "The API sent a token that can't be read: {}", self.reason),
+            false => write!(f, "The API rejected the solution ({}): {}", self.status, self.reason),
+        }
+    }
+}
+
+impl std::error::Error for NoToken {}
+
+/// The body of a `/request` POST, as the IronShield API defines it.
+#[derive(Debug, Serialize)]
+struct ChallengeRequest<'a> {
+    endpoint:  &'a str,
+    /// When the request was made, in Unix milliseconds.
+    timestamp: i64,
+}
+
+/// Builds the HTTP client API calls go out over, with the timeout and
+/// user agent from `config`.
+pub(crate) fn http_client(config: &ClientConfig) -> color_eyre::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(config.timeout)
+        .user_agent(config.user_agent.clone())
+        .build()
+        .map_err(|e| eyre!("Cannot build the API client: {e}"))
+}
+
+/// Fetches a challenge for `endpoint`.
+///
+/// The CLI makes this request itself rather than through
+/// `IronShieldClient::fetch_challenge`, which keeps only the challenge,
+/// so the rest of the envelope can be read. An incompatible API
+/// version is an error.
+///
+/// # Arguments
+/// * `http`:     The client to send the request over.
+/// * `config`:   The API base URL to use.
+/// * `endpoint`: The protected endpoint URL.
+pub async fn fetch_challenge(
+    http:     &reqwest::Client,
+    config:   &ClientConfig,
+    endpoint: &str,
+) -> color_eyre::Result<ChallengeEnvelope> {
+    read_envelope(&request(http, config, endpoint).await?)
+}
+
+/// The API version `/request` answers with, for `doctor`. Only the
+/// `api_version` is read, so this works whatever the challenge looks like.
+///
+/// # Arguments
+/// * `http`:     The client to send the request over.
+/// * `config`:   The API base URL to use.
+/// * `endpoint`: Any endpoint the API issues challenges for.
+pub async fn api_version(http: &reqwest::Client, config: &ClientConfig, endpoint: &str) -> color_eyre::Result<Negotiated> {
+    let body = request(http, config, endpoint).await?;
+    let envelope: Value = serde_json::from_slice(&body).map_err(|e| eyre!("The API sent a challenge that can't be read: {e}"))?;
+    Ok(crate::api_version::negotiate(crate::api_version::from_envelope(&envelope))?)
+}
+
+/// POSTs `solution` to `{base_url}/response` over `http` and reads the
+/// token, recording the exchange under `--har`. An answer without a
+/// token is a [`NoToken`].
+pub(crate) async fn submit(
+    http:     &reqwest::Client,
+    base_url: &str,
+    solution: &IronShieldChallengeResponse,
+) -> color_eyre::Result<IronShieldToken> {
+    let (response, mut exchange) = crate::har::send(http.post(format!("{base_url}/response")).json(solution)).await;
+    let response = response.map_err(|e| eyre!("Cannot submit the solution: {e}"))?;
+    let status = response.status();
+    let body = response.bytes().await.map_err(|e| eyre!("Cannot read the API's answer to the submit: {e}"))?;
+    exchange.content(&body);
+    if !status.is_success() {
+        return Err(NoToken { status, reason: String::from_utf8_lossy(&body).trim().to_string() }.into());
+    }
+    serde_json::from_slice(&body).map_err(|e| NoToken { status, reason: e.to_string() }.into())
+}
+
+/// POSTs a challenge request for `endpoint`, recording it under `--har`.
+///
+/// # Returns
+/// * `Result<Vec<u8>>`: The body of a successful response.
+async fn request(http: &reqwest::Client, config: &ClientConfig, endpoint: &str) -> color_eyre::Result<Vec<u8>> {
+    let url = format!("{}/request", config.api_base_url.trim_end_matches('/'));
+    let request = ChallengeRequest { endpoint, timestamp: chrono::Utc::now().timestamp_millis() };
+    let (response, mut exchange) = crate::har::send(http.post(url).json(&request)).await;
+    let response = response.map_err(|e| eyre!("Cannot request a challenge: {e}"))?;
+    let status = response.status();
+    let body = response.bytes().await.map_err(|e| eyre!("Cannot read the API's challenge: {e}"))?;
+    exchange.content(&body);
+    if !status.is_success() {
+        return Err(eyre!("The API refused the challenge request ({status}): {}", String::from_utf8_lossy(&body).trim()));
+    }
+    Ok(body.to_vec())
+}
+
+/// Reads a `/request` body: an envelope with the challenge under
+/// `challenge`, or the challenge on its own as older APIs send it.
+/// The `api_version` is checked first, so an incompatible API is
+/// reported as such rather than as a challenge that can't be read.
+fn read_envelope(body: &[u8]) -> color_eyre::Result<ChallengeEnvelope> {
+    let envelope: Value = serde_json::from_slice(body).map_err(|e| eyre!("The API sent a challenge that can't be read: {e}"))?;
+    let negotiated = crate::api_version::negotiate(crate::api_version::from_envelope(&envelope))?;
+    let challenge = envelope.get("challenge").unwrap_or(&envelope);
+    let challenge = IronShieldChallenge::deserialize(challenge)
+        .map_err(|e| eyre!("The API sent a challenge that can't be read: {e}"))?;
+    Ok(ChallengeEnvelope { challenge, metadata: ChallengeMetadata::from_envelope(&envelope), negotiated })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn challenge() -> IronShieldChallenge {
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(&[7; 32]);
+        let public_key = signing_key.verifying_key().to_bytes();
+        IronShieldChallenge::new("test".to_string(), 1_000, signing_key, public_key)
+    }
+
+    #[test]
+    fn test_bare_challenge() {
+        let challenge = challenge();
+        let body = serde_json::to_vec(&challenge).unwrap();
+
+        let envelope = read_envelope(&body).unwrap();
+        assert_eq!(envelope.challenge.random_nonce, challenge.random_nonce);
+        assert_eq!(envelope.metadata, None);
+        assert_eq!(envelope.negotiated, Negotiated::Unversioned);
+    }
+
+    #[test]
+    fn test_envelope_with_metadata() {
+        let challenge = challenge();
+        let body = serde_json::to_vec(&json!({ "challenge": challenge, "metadata": { "suggested_threads": 2 } })).unwrap();
+
+        let envelope = read_envelope(&body).unwrap();
+        assert_eq!(envelope.challenge.random_nonce, challenge.random_nonce);
+        assert_eq!(envelope.metadata.unwrap().suggested_threads, Some(2));
+    }
+
+    #[test]
+    fn test_incompatible_version_is_reported_before_the_challenge() {
+        let error = read_envelope(br#"{"api_version": "2.0", "challenge": {"renamed": true}}"#).unwrap_err();
+        assert!(error.downcast_ref::<crate::api_version::Incompatible>().is_some(), "{error}");
+    }
+
+    #[test]
+    fn test_unreadable_challenge() {
+        let error = read_envelope(br#"{"challenge": {"website_id": 5}}"#).unwrap_err();
+        assert!(error.to_string().starts_with("The API sent a challenge that can't be read"), "{error}");
+    }
+}
@@ -0,0 +1,206 @@
+use serde::Serialize;
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::logging::LogCategory;
+use crate::warnings::{Warning, WarningCode};
+
+/// The newest API version this CLI was built against. Servers on the
+/// same major version are compatible; a newer minor version may add
+/// fields this CLI ignores.
+pub const CURRENT: ApiVersion = ApiVersion { major: 1, minor: 1 };
+
+/// An `api_version` such as `1.1`; a bare `1` means `1.0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(into = "String")]
+pub struct ApiVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl FromStr for ApiVersion {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("'{value}' is not an API version like 1.1");
+        let (major, minor) = value.trim().split_once('.').unwrap_or((value.trim(), "0"));
+        Ok(Self {
+            major: major.parse().map_err(|_| invalid())?,
+            minor: minor.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl From<ApiVersion> for String {
+    fn from(version: ApiVersion) -> Self {
+        version.to_string()
+    }
+}
+
+/// How the server's `api_version` compares with [`CURRENT`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "negotiation", content = "api_version", rename_all = "snake_case")]
+pub enum Negotiated {
+    /// The server didn't say; servers from before versioning are 1.0.
+    Unversioned,
+    Matching(ApiVersion),
+    /// An earlier minor version; everything this CLI needs is there.
+    Older(ApiVersion),
+    /// A later minor version; it works, but an upgrade is due.
+    NewerMinor(ApiVersion),
+}
+
+impl Negotiated {
+    /// The version both sides speak, e.g. for verbose output.
+    pub fn version(self) -> ApiVersion {
+        match self {
+            Self::Unversioned => ApiVersion { major: 1, minor: 0 },
+            Self::Matching(version) | Self::Older(version) | Self::NewerMinor(version) => version,
+        }
+    }
+
+    /// e.g. "1.1 (matching)", for verbose output and `doctor`.
+    pub fn describe(self) -> String {
+        let how = match self {
+            Self::Unversioned   => "unversioned",
+            Self::Matching(_)   => "matching",
+            Self::Older(_)      => "older than this CLI's, compatible",
+            Self::NewerMinor(_) => "newer than this CLI's, compatible",
+        };
+        format!("{} ({how})", self.version())
+    }
+
+    /// What to tell the user, if anything.
+    pub fn warning(self) -> Option<String> {
+        match self {
+            Self::NewerMinor(version) => Some(format!(
+                "The API speaks version {version}, newer than the {CURRENT} this ironshield-cli {} was built for; \
+                 consider upgrading ironshield-cli",
+                env!("CARGO_PKG_VERSION"),
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// A server this CLI cannot talk to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Incompatible {
+    /// A different major version.
+    Major(ApiVersion),
+    /// An `api_version` that doesn't parse.
+    Unreadable(String),
+}
+
+impl fmt::Display for Incompatible {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cli = env!("CARGO_PKG_VERSION");
+        match self {
+            Self::Major(version) if version.major > CURRENT.major => write!(
+                f,
+                "The API speaks version {version}, which ironshield-cli {cli} cannot read (it supports {}.x); \
+                 upgrade ironshield-cli",
+                CURRENT.major,
+            ),
+            Self::Major(version) => write!(
+                f,
+                "The API speaks version {version}, older than the {}.x ironshield-cli {cli} supports; \
+                 use an older ironshield-cli or upgrade the server",
+                CURRENT.major,
+            ),
+            Self::Unreadable(value) => write!(f, "The API sent an unreadable api_version '{value}'"),
+        }
+    }
+}
+
+impl std::error::Error for Incompatible {}
+
+/// Compares a server's `api_version`, if it sent one, with [`CURRENT`].
+pub fn negotiate(server: Option<&str>) -> Result<Negotiated, Incompatible> {
+    let Some(value) = server else {
+        return Ok(Negotiated::Unversioned);
+    };
+    let version: ApiVersion = value.parse().map_err(|_| Incompatible::Unreadable(value.to_string()))?;
+    if version.major != CURRENT.major {
+        return Err(Incompatible::Major(version));
+    }
+    Ok(match version.minor.cmp(&CURRENT.minor) {
+        std::cmp::Ordering::Less    => Negotiated::Older(version),
+        std::cmp::Ordering::Equal   => Negotiated::Matching(version),
+        std::cmp::Ordering::Greater => Negotiated::NewerMinor(version),
+    })
+}
+
+/// The `api_version` of a `/request` envelope, read before the rest
+/// so a mismatch is reported as one rather than as a missing field.
+pub fn from_envelope(envelope: &serde_json::Value) -> Option<&str> {
+    envelope.get("api_version")?.as_str()
+}
+
+/// Logs what a `/request` negotiated at verbose level. A newer server
+/// gets a [`WarningCode::ApiNewer`] warning, shown once per process so
+/// a long-running `proxy` doesn't repeat it on every refresh.
+pub fn report(negotiated: Negotiated, verbose: bool) {
+    crate::logging::log_event(verbose, LogCategory::Network, format_args!("API version: {}", negotiated.describe()));
+    if let Some(message) = negotiated.warning() {
+        crate::warnings::emit_once(Warning::new(WarningCode::ApiNewer, message).with_data(serde_json::json!(negotiated)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_older_and_matching_versions_are_quiet() {
+        assert_eq!(negotiate(Some("1.0")), Ok(Negotiated::Older(ApiVersion { major: 1, minor: 0 })));
+        assert_eq!(negotiate(Some("1.1")), Ok(Negotiated::Matching(CURRENT)));
+        assert_eq!(negotiate(None), Ok(Negotiated::Unversioned));
+        assert_eq!(negotiate(Some("1")).unwrap().warning(), None);
+    }
+
+    #[test]
+    fn test_newer_minor_suggests_upgrading() {
+        let negotiated = negotiate(Some("1.4")).unwrap();
+
+        assert_eq!(negotiated, Negotiated::NewerMinor(ApiVersion { major: 1, minor: 4 }));
+        assert!(negotiated.warning().unwrap().contains("consider upgrading ironshield-cli"));
+    }
+
+    #[test]
+    fn test_other_majors_are_incompatible() {
+        let newer = negotiate(Some("2.0")).unwrap_err();
+        assert!(newer.to_string().contains("cannot read (it supports 1.x); upgrade ironshield-cli"), "{newer}");
+
+        assert!(negotiate(Some("0.9")).unwrap_err().to_string().contains("older than the 1.x"));
+        assert_eq!(negotiate(Some("v2")), Err(Incompatible::Unreadable("v2".to_string())));
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(Negotiated::Unversioned.describe(), "1.0 (unversioned)");
+        assert_eq!(Negotiated::Matching(CURRENT).describe(), "1.1 (matching)");
+        assert_eq!(negotiate(Some("1.4")).unwrap().describe(), "1.4 (newer than this CLI's, compatible)");
+    }
+
+    #[test]
+    fn test_version_is_read_from_the_envelope_and_serialized_for_json() {
+        let envelope = json!({ "api_version": "1.1", "challenge": {} });
+        assert_eq!(from_envelope(&envelope), Some("1.1"));
+        assert_eq!(from_envelope(&json!({ "challenge": {} })), None);
+
+        let negotiated = negotiate(from_envelope(&envelope)).unwrap();
+        assert_eq!(
+            serde_json::to_value(negotiated).unwrap(),
+            json!({ "negotiation": "matching", "api_version": "1.1" }),
+        );
+    }
+}
@@ -0,0 +1,637 @@
+use chrono::Utc;
+use ironshield::{IronShieldChallenge, ProgressTracker};
+use tokio::sync::mpsc::{self, UnboundedSender};
+
+use futures::{Stream, StreamExt};
+
+use std::collections::BTreeMap;
+use std::io::{self, BufRead};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::client::CliClient;
+use crate::display::format_duration;
+use crate::estimate::attempts_from_nonce;
+use crate::history::{self, ErrorKind, RunCommand, RunRecord};
+use crate::logging::{LogCategory, log_event};
+use crate::metrics;
+use crate::schedule::{self, Candidate, Schedule};
+
+/// Where one endpoint is in its fetch → solve → validate run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stage {
+    Pending,
+    Fetching,
+    /// Fetched ahead of time, waiting for a free solver.
+    Prefetched,
+    /// Fetched and waiting for its turn; 1 is solved first.
+    Scheduled { position: usize },
+    Solving { attempts: u64, recommended_attempts: u64 },
+    Validating,
+    Done { token: String, valid_for: i64 },
+    Failed { message: String, kind: ErrorKind },
+}
+
+impl Stage {
+    /// Whether the endpoint is being worked on right now.
+    pub fn is_in_flight(&self) -> bool {
+        matches!(
+            self,
+            Self::Fetching | Self::Prefetched | Self::Scheduled { .. } | Self::Solving { .. } | Self::Validating
+        )
+    }
+
+    /// e.g. `solving`, for reports.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Pending          => "pending",
+            Self::Fetching         => "fetching",
+            Self::Prefetched       => "prefetched",
+            Self::Scheduled { .. } => "scheduled",
+            Self::Solving { .. }   => "solving",
+            Self::Validating       => "validating",
+            Self::Done { .. }      => "done",
+            Self::Failed { .. }    => "failed",
+        }
+    }
+}
+
+/// A stage change for the job with the caller's `id`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchUpdate {
+    pub id:    usize,
+    pub stage: Stage,
+}
+
+/// Parses an endpoint list: one per line, with blank lines and
+/// `#` comments skipped.
+pub fn parse_endpoints(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// One line of an endpoint list read with [`read_endpoint_list`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListedEndpoint {
+    Valid   { line: usize, endpoint: String },
+    Invalid { line: usize, text: String, error: String },
+}
+
+/// Checks one line of an endpoint list with the shared validator.
+///
+/// # Arguments
+/// * `line`: The 1-based line number, for error reports.
+/// * `text`: The line as read.
+///
+/// # Returns
+/// * `Option<ListedEndpoint>`: `None` for blank lines and `#` comments.
+pub fn parse_endpoint_line(line: usize, text: &str) -> Option<ListedEndpoint> {
+    let text = text.trim();
+    if text.is_empty() || text.starts_with('#') {
+        return None;
+    }
+    Some(match crate::endpoint::check(text) {
+        Ok(())     => ListedEndpoint::Valid { line, endpoint: text.to_string() },
+        Err(error) => ListedEndpoint::Invalid { line, text: text.to_string(), error: error.to_string() },
+    })
+}
+
+/// Reads an endpoint list on a blocking thread, one line at a time,
+/// so an enormous list is never held in memory: reading stops while
+/// `capacity` checked lines wait to be taken.
+///
+/// # Arguments
+/// * `reader`:   The list, e.g. stdin.
+/// * `capacity`: How far reading may run ahead of the consumer.
+///
+/// # Returns
+/// * `Receiver`: Every endpoint line in order, then a read error if one
+///               ended the list early.
+pub fn read_endpoint_list(
+    reader:   impl BufRead + Send + 'static,
+    capacity: usize,
+) -> mpsc::Receiver<io::Result<ListedEndpoint>> {
+    let (tx, rx) = mpsc::channel(capacity.max(1));
+    tokio::task::spawn_blocking(move || {
+        for (index, text) in reader.lines().enumerate() {
+            let listed = match text {
+                Ok(text) => match parse_endpoint_line(index + 1, &text) {
+                    Some(listed) => Ok(listed),
+                    None         => continue,
+                },
+                Err(e) => Err(e),
+            };
+            let failed = listed.is_err();
+            if tx.blocking_send(listed).is_err() || failed {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// Fetched challenges allowed to wait for a free solver. Kept small
+/// because every waiting challenge is ageing towards its expiry.
+pub const LOOKAHEAD: usize = 1;
+
+/// How often the fetcher re-checks whether it may prefetch.
+const PREFETCH_POLL: Duration = Duration::from_millis(250);
+
+/// Validates each endpoint, running up to `concurrency` at once.
+///
+/// A fetcher and `concurrency` solvers are connected by a channel.
+/// While every solver is busy, the fetcher prefetches up to
+/// [`LOOKAHEAD`] challenges, but only once the soonest solve is
+/// expected to finish within a challenge's validity window. A
+/// prefetched challenge that expires before a solver takes it is
+/// fetched again transparently.
+///
+/// Every job ends with a [`Stage::Done`] or [`Stage::Failed`]
+/// update, and each is recorded in the run history like a
+/// single `validate`. The function returns once all jobs have
+/// finished; dropping its future abandons the rest.
+///
+/// # Arguments
+/// * `client`:      The API client.
+/// * `jobs`:        `(id, endpoint)` pairs; ids are echoed in updates.
+///                  Taken one at a time, so the stream may be endless.
+/// * `concurrency`: Endpoints processed at once, at least 1.
+/// * `updates`:     Receives every stage change.
+/// * `verbose`:     Whether to emit verbose log lines.
+pub async fn run(
+    client:      Arc<CliClient>,
+    jobs:        impl Stream<Item = (usize, String)>,
+    concurrency: usize,
+    updates:     UnboundedSender<BatchUpdate>,
+    verbose:     bool,
+) {
+    let concurrency = concurrency.max(1);
+    let pipeline = Arc::new(Mutex::new(Pipeline::default()));
+    let (fetched_tx, fetched_rx) = mpsc::channel::<Fetched>(LOOKAHEAD);
+    let fetched_rx = &tokio::sync::Mutex::new(fetched_rx);
+    let (client, updates, pipeline) = (&client, &updates, &pipeline);
+
+    let fetcher = async move {
+        let mut jobs = std::pin::pin!(jobs);
+        while let Some((id, endpoint)) = jobs.next().await {
+            let prefetching = wait_for_fetch_slot(pipeline, concurrency).await;
+            if prefetching {
+                pipeline.lock().unwrap().stats.prefetched += 1;
+                log_event(verbose, LogCategory::Compute, format_args!("Prefetching the challenge for {endpoint}"));
+            }
+
+            let mut job = Job::new(id, endpoint);
+            job.send(updates, Stage::Fetching);
+            match fetch(client, &mut job, verbose).await {
+                Ok(challenge) => {
+                    {
+                        let mut pipeline = pipeline.lock().unwrap();
+                        pipeline.set_validity(&challenge);
+                        pipeline.queued += 1;
+                    }
+                    if prefetching {
+                        job.send(updates, Stage::Prefetched);
+                    }
+                    if fetched_tx.send(Fetched { job, challenge }).await.is_err() {
+                        break;
+                    }
+                }
+                Err(message) => job.finish(updates, Err(message)),
+            }
+        }
+    };
+
+    let solvers = (0..concurrency).map(|_| async move {
+        loop {
+            let Some(Fetched { mut job, challenge }) = fetched_rx.lock().await.recv().await else {
+                break;
+            };
+            {
+                let mut pipeline = pipeline.lock().unwrap();
+                pipeline.queued -= 1;
+                pipeline.busy += 1;
+            }
+
+            let outcome = solve_and_submit(client, &mut job, challenge, updates, pipeline, verbose).await;
+            job.finish(updates, outcome);
+            pipeline.lock().unwrap().busy -= 1;
+        }
+    });
+
+    futures::join!(fetcher, futures::future::join_all(solvers));
+
+    let stats = pipeline.lock().unwrap().stats;
+    log_event(verbose, LogCategory::Info, format_args!(
+        "Batch finished: {} challenge(s) prefetched, {} fetched again after expiring",
+        stats.prefetched,
+        stats.refetched,
+    ));
+}
+
+/// Fetches every endpoint's challenge at once, then solves them one
+/// at a time in the order `schedule` picks, each with every thread.
+///
+/// Each fetched job gets a [`Stage::Scheduled`] update with its place
+/// in the order before solving starts. Otherwise this behaves like
+/// [`run`], including refetching challenges that expired while waiting.
+///
+/// # Arguments
+/// * `client`:   The API client.
+/// * `jobs`:     `(id, endpoint)` pairs; ids are echoed in updates.
+/// * `schedule`: The solve order.
+/// * `updates`:  Receives every stage change.
+/// * `verbose`:  Whether to emit verbose log lines.
+pub async fn run_scheduled(
+    client:   Arc<CliClient>,
+    jobs:     Vec<(usize, String)>,
+    schedule: Schedule,
+    updates:  UnboundedSender<BatchUpdate>,
+    verbose:  bool,
+) {
+    let pipeline = Arc::new(Mutex::new(Pipeline::default()));
+
+    let (client, updates, pipeline) = (&client, &updates, &pipeline);
+
+    let fetches = jobs.into_iter().map(|(id, endpoint)| async move {
+        let mut job = Job::new(id, endpoint);
+        job.send(updates, Stage::Fetching);
+        match fetch(client, &mut job, verbose).await {
+            Ok(challenge) => Some(Fetched { job, challenge }),
+            Err(message)  => {
+                job.finish(updates, Err(message));
+                None
+            }
+        }
+    });
+    let mut fetched: Vec<Option<Fetched>> = futures::future::join_all(fetches).await;
+    fetched.retain(Option::is_some);
+
+    let candidates: Vec<Candidate> = fetched
+        .iter()
+        .flatten()
+        .map(|Fetched { challenge, .. }| Candidate {
+            recommended_attempts: challenge.recommended_attempts,
+            expiration_time:      challenge.expiration_time,
+        })
+        .collect();
+    let order = schedule::order(schedule, &candidates);
+
+    log_event(verbose, LogCategory::Compute, format_args!(
+        "Solving {} challenge(s) in {} order: {}",
+        order.len(),
+        schedule.name(),
+        order.iter().filter_map(|&i| fetched[i].as_ref()).map(|f| f.job.endpoint.as_str()).collect::<Vec<_>>().join(", "),
+    ));
+    for (position, &index) in order.iter().enumerate() {
+        if let Some(Fetched { job, .. }) = &fetched[index] {
+            job.send(updates, Stage::Scheduled { position: position + 1 });
+        }
+    }
+
+    for index in order {
+        let Some(Fetched { mut job, challenge }) = fetched[index].take() else {
+            continue;
+        };
+        let outcome = solve_and_submit(client, &mut job, challenge, updates, pipeline, verbose).await;
+        job.finish(updates, outcome);
+    }
+
+    let stats = pipeline.lock().unwrap().stats;
+    log_event(verbose, LogCategory::Info, format_args!(
+        "Batch finished: {} challenge(s) fetched again after expiring",
+        stats.refetched,
+    ));
+}
+
+/// Counts kept while a batch runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BatchStats {
+    /// Challenges fetched while every solver was busy.
+    pub prefetched: usize,
+    /// Challenges that expired while waiting for a solver and were fetched again.
+    pub refetched:  usize,
+}
+
+/// A solve in progress, for estimating when its solver frees up.
+#[derive(Debug, Clone, Copy)]
+struct SolveProgress {
+    difficulty: u64,
+    attempts:   u64,
+    started:    Instant,
+}
+
+/// What the fetcher needs to know about the solvers.
+#[derive(Debug, Default)]
+struct Pipeline {
+    /// Solvers working on a job, including refetching and submitting.
+    busy:     usize,
+    /// Challenges fetched but not yet taken by a solver.
+    queued:   usize,
+    solving:  BTreeMap<usize, SolveProgress>,
+    /// Validity window of the most recently fetched challenge.
+    validity: Option<Duration>,
+    stats:    BatchStats,
+}
+
+impl Pipeline {
+    fn free_solvers(&self, concurrency: usize) -> usize {
+        concurrency.saturating_sub(self.busy + self.queued)
+    }
+
+    fn set_validity(&mut self, challenge: &IronShieldChallenge) {
+        let window = (challenge.expiration_time - challenge.created_time).max(0) as u64;
+        self.validity = Some(Duration::from_millis(window));
+    }
+
+    /// Expected time until the first running solve finishes.
+    fn soonest_eta(&self, now: Instant) -> Option<Duration> {
+        self.solving
+            .values()
+            .filter_map(|solve| solve_eta(solve.difficulty, solve.attempts, now.duration_since(solve.started)))
+            .min()
+    }
+}
+
+/// Expected time left in a solve, from its average rate so far.
+/// `None` until the first progress report.
+fn solve_eta(difficulty: u64, attempts: u64, elapsed: Duration) -> Option<Duration> {
+    if attempts == 0 || elapsed.is_zero() {
+        return None;
+    }
+    let rate = attempts as f64 / elapsed.as_secs_f64();
+    Some(Duration::from_secs_f64(difficulty.saturating_sub(attempts) as f64 / rate))
+}
+
+/// Whether to fetch ahead of a free solver: only when the next one
+/// should free up before a challenge fetched now would expire.
+fn prefetch_allowed(soonest_eta: Option<Duration>, validity: Option<Duration>) -> bool {
+    matches!((soonest_eta, validity), (Some(eta), Some(validity)) if eta < validity)
+}
+
+/// Waits until a solver is free or prefetching is allowed.
+///
+/// # Returns
+/// * `bool`: Whether the fetch is a prefetch, i.e. no solver is free.
+async fn wait_for_fetch_slot(pipeline: &Mutex<Pipeline>, concurrency: usize) -> bool {
+    loop {
+        {
+            let pipeline = pipeline.lock().unwrap();
+            if pipeline.free_solvers(concurrency) > 0 {
+                return false;
+            }
+            if pipeline.queued < LOOKAHEAD && prefetch_allowed(pipeline.soonest_eta(Instant::now()), pipeline.validity) {
+                return true;
+            }
+        }
+        tokio::time::sleep(PREFETCH_POLL).await;
+    }
+}
+
+/// One endpoint's run, from its first fetch to its final update.
+struct Job {
+    id:       usize,
+    endpoint: String,
+    record:   RunRecord,
+    start:    Instant,
+}
+
+impl Job {
+    fn new(id: usize, endpoint: String) -> Self {
+        Self { id, record: RunRecord::new(RunCommand::Validate, &endpoint), endpoint, start: Instant::now() }
+    }
+
+    fn send(&self, updates: &UnboundedSender<BatchUpdate>, stage: Stage) {
+        let _ = updates.send(BatchUpdate { id: self.id, stage });
+    }
+
+    /// Records the run in the history and sends the final update.
+    fn finish(mut self, updates: &UnboundedSender<BatchUpdate>, outcome: Result<(String, i64), String>) {
+        self.record.finish(self.start.elapsed(), outcome.as_ref().err().cloned());
+        history::record(&self.record);
+        let kind = self.record.error_kind.unwrap_or(ErrorKind::Other);
+        match outcome {
+            Ok((token, valid_for)) => self.send(updates, Stage::Done { token, valid_for }),
+            Err(message)           => self.send(updates, Stage::Failed { message, kind }),
+        }
+    }
+}
+
+/// A challenge on its way from the fetcher to a solver.
+struct Fetched {
+    job:       Job,
+    challenge: IronShieldChallenge,
+}
+
+async fn fetch(client: &CliClient, job: &mut Job, verbose: bool) -> Result<IronShieldChallenge, String> {
+    let endpoint = &job.endpoint;
+    log_event(verbose, LogCategory::Network, format_args!("Requesting challenge for endpoint: {endpoint}"));
+    let fetch_start = Instant::now();
+    let envelope = client.fetch(endpoint).await.map_err(|e| {
+        job.record.error_kind = Some(ErrorKind::Fetch);
+        log_event(verbose, LogCategory::Error, format_args!("Challenge fetch for {endpoint} failed: {e}"));
+        e.to_string()
+    })?;
+    job.record.fetch_ms = Some(fetch_start.elapsed().as_millis() as u64);
+    Ok(envelope.challenge)
+}
+
+/// Solves and submits one fetched challenge, fetching a fresh one
+/// first if it expired while waiting.
+///
+/// # Returns
+/// * `Result<(String, i64), String>`: The token as `validate` prints
+///                                    it and its `valid_for`, or the
+///                                    error message.
+async fn solve_and_submit(
+    client:    &CliClient,
+    job:       &mut Job,
+    challenge: IronShieldChallenge,
+    updates:   &UnboundedSender<BatchUpdate>,
+    pipeline:  &Arc<Mutex<Pipeline>>,
+    verbose:   bool,
+) -> Result<(String, i64), String> {
+    let endpoint = job.endpoint.clone();
+
+    let challenge = if challenge.expiration_time <= Utc::now().timestamp_millis() {
+        log_event(verbose, LogCategory::Warning, format_args!(
+            "Challenge for {endpoint} expired while waiting to be solved; fetching a new one"
+        ));
+        pipeline.lock().unwrap().stats.refetched += 1;
+        metrics::record_refetch();
+        job.send(updates, Stage::Fetching);
+        fetch(client, job, verbose).await?
+    } else {
+        challenge
+    };
+
+    let thread_plan = client.thread_plan(true);
+    if let Err(refused) = crate::presolve::check(&challenge, thread_plan.thread_count).await {
+        job.record.error_kind = Some(ErrorKind::Refused);
+        log_event(verbose, LogCategory::Error, format_args!("{endpoint}: {refused}"));
+        return Err(refused.to_string());
+    }
+
+    let recommended_attempts = challenge.recommended_attempts;
+    let thread_count = thread_plan.thread_count;
+    job.record.difficulty = Some(recommended_attempts / 2);
+    job.record.thread_count = Some(thread_count);
+    job.record.strategy = Some(thread_plan.strategy);
+
+    job.send(updates, Stage::Solving { attempts: 0, recommended_attempts });
+    let solve_start = Instant::now();
+    pipeline.lock().unwrap().solving.insert(job.id, SolveProgress {
+        difficulty: recommended_attempts / 2,
+        attempts:   0,
+        started:    solve_start,
+    });
+    let tracker = Arc::new(StageProgressTracker {
+        id:       job.id,
+        recommended_attempts,
+        updates:  updates.clone(),
+        threads:  Mutex::new(BTreeMap::new()),
+        pipeline: Arc::clone(pipeline),
+    }) as Arc<dyn ProgressTracker>;
+
+    let result = client.solve_with_plan(challenge, &thread_plan, Some(tracker)).await;
+    pipeline.lock().unwrap().solving.remove(&job.id);
+    let solution = result.map_err(|e| {
+        job.record.error_kind = Some(ErrorKind::Solve);
+        metrics::record_solve_failure(solve_start.elapsed());
+        log_event(verbose, LogCategory::Error, format_args!("Challenge solving for {endpoint} failed: {e}"));
+        e.to_string()
+    })?;
+
+    job.record.solve_ms = Some(solve_start.elapsed().as_millis() as u64);
+    job.record.attempts = Some(attempts_from_nonce(solution.solution as u64, &thread_plan));
+    metrics::record_solve_success(solve_start.elapsed(), job.record.hash_rate().unwrap_or_default());
+    log_event(verbose, LogCategory::Success, format_args!(
+        "Challenge for {endpoint} solved in {}",
+        format_duration(solve_start.elapsed())
+    ));
+
+    job.send(updates, Stage::Validating);
+    let margin = crate::margin::assess(solution.solved_challenge.expiration_time, &job.record);
+    if margin.is_thin() {
+        log_event(verbose, LogCategory::Warning, format_args!("{endpoint}: {}", margin.describe()));
+    }
+    crate::prewarm::log_reuse();
+    let submit_start = Instant::now();
+    let result = client.submit(&solution).await;
+    let margin = margin.landed(Utc::now().timestamp_millis());
+    margin.record(&mut job.record);
+    // The margin goes in the message, and so in the failure summary.
+    let token = result.map_err(|e| {
+        job.record.error_kind = Some(ErrorKind::Submit);
+        log_event(verbose, LogCategory::Error, format_args!("Solution submission for {endpoint} failed ({}): {e}", margin.brief()));
+        format!("{e} ({})", margin.brief())
+    })?;
+
+    metrics::record_submit(submit_start.elapsed(), token.valid_for);
+    job.record.token_valid_for = Some(token.valid_for);
+    Ok((format!("{token:?}"), token.valid_for))
+}
+
+/// Turns per-thread progress into [`Stage::Solving`] updates with
+/// the total across threads, and keeps the pipeline's ETA current.
+struct StageProgressTracker {
+    id:                   usize,
+    recommended_attempts: u64,
+    updates:              UnboundedSender<BatchUpdate>,
+    /// Latest running total from each thread.
+    threads:              Mutex<BTreeMap<usize, u64>>,
+    pipeline:             Arc<Mutex<Pipeline>>,
+}
+
+impl ProgressTracker for StageProgressTracker {
+    fn on_progress(&self, thread_id: usize, total_attempts: u64, _hash_rate: u64, _elapsed: Duration) {
+        let attempts = {
+            let mut threads = self.threads.lock().unwrap_or_else(|e| e.into_inner());
+            threads.insert(thread_id, total_attempts);
+            threads.values().sum()
+        };
+        if let Some(solve) = self.pipeline.lock().unwrap_or_else(|e| e.into_inner()).solving.get_mut(&self.id) {
+            solve.attempts = attempts;
+        }
+
+        let _ = self.updates.send(BatchUpdate {
+            id:    self.id,
+            stage: Stage::Solving { attempts, recommended_attempts: self.recommended_attempts },
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_endpoints() {
+        let contents = "# staging\nhttps://a.example\n\n  https://b.example  \n";
+
+        assert_eq!(parse_endpoints(contents), ["https://a.example", "https://b.example"]);
+    }
+
+    #[test]
+    fn test_parse_endpoint_line() {
+        assert_eq!(parse_endpoint_line(1, "  # staging"), None);
+        assert_eq!(parse_endpoint_line(2, ""), None);
+        assert_eq!(
+            parse_endpoint_line(3, " https://a.example "),
+            Some(ListedEndpoint::Valid { line: 3, endpoint: "https://a.example".to_string() }),
+        );
+        assert!(matches!(
+            parse_endpoint_line(4, "a.example"),
+            Some(ListedEndpoint::Invalid { line: 4, ref text, .. }) if text == "a.example"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_read_endpoint_list() {
+        let list = "https://a.example\n\n# skip\nftp://b.example\nhttps://c.example\n";
+        let mut listed = read_endpoint_list(io::Cursor::new(list), 1);
+
+        let mut lines = Vec::new();
+        while let Some(entry) = listed.recv().await {
+            lines.push(match entry.unwrap() {
+                ListedEndpoint::Valid { line, .. }   => (line, true),
+                ListedEndpoint::Invalid { line, .. } => (line, false),
+            });
+        }
+        assert_eq!(lines, [(1, true), (4, false), (5, true)]);
+    }
+
+    #[test]
+    fn test_solve_eta() {
+        assert_eq!(solve_eta(1_000, 0, Duration::from_secs(1)), None);
+        assert_eq!(solve_eta(1_000, 250, Duration::from_secs(1)), Some(Duration::from_secs(3)));
+        assert_eq!(solve_eta(1_000, 2_000, Duration::from_secs(1)), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_prefetch_waits_for_the_validity_window() {
+        let validity = Some(Duration::from_secs(30));
+
+        assert!(prefetch_allowed(Some(Duration::from_secs(10)), validity));
+        assert!(!prefetch_allowed(Some(Duration::from_secs(45)), validity));
+        assert!(!prefetch_allowed(None, validity));
+        assert!(!prefetch_allowed(Some(Duration::from_secs(1)), None));
+    }
+
+    #[test]
+    fn test_pipeline_tracks_the_soonest_solve() {
+        let now = Instant::now();
+        let started = now - Duration::from_secs(2);
+        let mut pipeline = Pipeline { busy: 2, queued: 1, ..Pipeline::default() };
+        pipeline.solving.insert(0, SolveProgress { difficulty: 1_000, attempts: 500, started });
+        pipeline.solving.insert(1, SolveProgress { difficulty: 1_000, attempts: 100, started });
+        pipeline.solving.insert(2, SolveProgress { difficulty: 1_000, attempts: 0, started });
+
+        assert_eq!(pipeline.free_solvers(4), 1);
+        assert_eq!(pipeline.free_solvers(2), 0);
+        assert_eq!(pipeline.soonest_eta(now), Some(Duration::from_secs(2)));
+    }
+}
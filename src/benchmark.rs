@@ -0,0 +1,286 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use std::time::Duration;
+
+use crate::estimate::probe_hash_rate;
+use crate::tuning::SolverOptions;
+
+/// Version of the file layout written by `benchmark --save`. Bump it
+/// when a field changes meaning; adding optional fields doesn't need it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// A slowdown larger than this, in percent, counts as a regression.
+pub const REGRESSION_PERCENT: f64 = 5.0;
+
+/// The `ironshield-core` version this binary was built against, from
+/// `Cargo.lock` via the build script.
+const CORE_VERSION: &str = env!("IRONSHIELD_CORE_VERSION");
+
+/// A saved benchmark run.
+///
+/// Every field but `schema_version` has a default and unknown fields
+/// are ignored, so files written by newer releases still load.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkFile {
+    pub schema_version: u32,
+    #[serde(default)]
+    pub created_at:     Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub machine:        Machine,
+    #[serde(default)]
+    pub versions:       Versions,
+    #[serde(default)]
+    pub results:        Vec<ThreadResult>,
+    /// The `[solver]` options in effect, so a tuning run can be repeated.
+    #[serde(default)]
+    pub solver:         SolverOptions,
+    /// Measured on an unoptimized build, so not comparable to release results.
+    #[serde(default)]
+    pub debug_build:    bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Machine {
+    pub cpu_model: Option<String>,
+    /// Logical cores.
+    pub cores:     usize,
+    pub os:        String,
+    pub arch:      String,
+}
+
+impl Machine {
+    pub fn current() -> Self {
+        Self {
+            cpu_model: cpu_model(),
+            cores:     num_cpus::get(),
+            os:        std::env::consts::OS.to_string(),
+            arch:      std::env::consts::ARCH.to_string(),
+        }
+    }
+
+    /// e.g. "AMD Ryzen 7 7840U, 16 cores, linux/x86_64".
+    pub fn describe(&self) -> String {
+        format!(
+            "{}, {} cores, {}/{}",
+            self.cpu_model.as_deref().unwrap_or("unknown CPU"),
+            self.cores,
+            self.os,
+            self.arch,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Versions {
+    pub cli:  String,
+    pub core: String,
+}
+
+impl Versions {
+    /// This binary's versions.
+    pub fn current() -> Self {
+        Self {
+            cli:  env!("CARGO_PKG_VERSION").to_string(),
+            core: CORE_VERSION.to_string(),
+        }
+    }
+}
+
+/// The combined hash rate measured with `threads` threads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThreadResult {
+    pub threads:   usize,
+    pub hash_rate: u64,
+}
+
+impl BenchmarkFile {
+    /// A file of `results`, measured with the `solver` tuning options.
+    pub fn new(results: Vec<ThreadResult>, solver: SolverOptions) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            created_at:     Some(Utc::now()),
+            machine:        Machine::current(),
+            versions:       Versions::current(),
+            results,
+            solver,
+            debug_build:    crate::build_profile::is_debug(),
+        }
+    }
+
+    /// Parses a saved file, rejecting anything without a schema version.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let file: Self = serde_json::from_str(contents).map_err(|e| format!("not a benchmark file: {e}"))?;
+        if file.schema_version == 0 {
+            return Err("not a benchmark file: schema_version must be at least 1".to_string());
+        }
+        Ok(file)
+    }
+}
+
+/// One row of `benchmark compare`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Delta {
+    pub threads: usize,
+    pub old:     Option<u64>,
+    pub new:     Option<u64>,
+}
+
+impl Delta {
+    /// Hash rate change in percent; negative is slower.
+    pub fn change(&self) -> Option<f64> {
+        match (self.old, self.new) {
+            (Some(old), Some(new)) if old > 0 => Some((new as f64 - old as f64) / old as f64 * 100.0),
+            _ => None,
+        }
+    }
+
+    pub fn is_regression(&self) -> bool {
+        self.change().is_some_and(|change| change < -REGRESSION_PERCENT)
+    }
+}
+
+/// Pairs up results by thread count, in ascending order.
+pub fn compare(old: &BenchmarkFile, new: &BenchmarkFile) -> Vec<Delta> {
+    let rate = |file: &BenchmarkFile, threads| {
+        file.results.iter().find(|result| result.threads == threads).map(|result| result.hash_rate)
+    };
+
+    let mut threads: Vec<usize> = old.results.iter().chain(&new.results).map(|result| result.threads).collect();
+    threads.sort_unstable();
+    threads.dedup();
+    threads
+        .into_iter()
+        .map(|threads| Delta { threads, old: rate(old, threads), new: rate(new, threads) })
+        .collect()
+}
+
+/// The fewest threads that hash within [`REGRESSION_PERCENT`] of the
+/// fastest result, so extra threads that add nothing aren't suggested.
+///
+/// # Returns
+/// * `Option<usize>`: The thread count, or `None` with no results.
+pub fn suggest_threads(results: &[ThreadResult]) -> Option<usize> {
+    let best = results.iter().map(|result| result.hash_rate).max()?;
+    let good_enough = best as f64 * (1.0 - REGRESSION_PERCENT / 100.0);
+    results
+        .iter()
+        .filter(|result| result.hash_rate as f64 >= good_enough)
+        .map(|result| result.threads)
+        .min()
+}
+
+/// Thread counts to try by default: powers of two up to the core
+/// count, plus the core count itself.
+pub fn default_thread_counts(cores: usize) -> Vec<usize> {
+    let cores = cores.max(1);
+    let mut counts: Vec<usize> = std::iter::successors(Some(1usize), |n| n.checked_mul(2))
+        .take_while(|n| *n < cores)
+        .collect();
+    counts.push(cores);
+    counts
+}
+
+/// Hashes on `threads` threads at once for `duration` and sums their rates.
+///
+/// This is blocking; call it from `spawn_blocking`.
+pub fn measure(threads: usize, duration: Duration) -> u64 {
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads.max(1))
+            .map(|_| scope.spawn(move || probe_hash_rate(duration)))
+            .collect();
+        handles.into_iter().map(|handle| handle.join().unwrap_or(0)).sum()
+    })
+}
+
+fn cpu_model() -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        let cpuinfo = std::fs::read_to_string("/proc/cpuinfo").ok()?;
+        cpuinfo
+            .lines()
+            .find(|line| line.starts_with("model name"))
+            .and_then(|line| line.split_once(':'))
+            .map(|(_, model)| model.trim().to_string())
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let output = std::process::Command::new("sysctl").args(["-n", "machdep.cpu.brand_string"]).output().ok()?;
+        let model = String::from_utf8(output.stdout).ok()?;
+        Some(model.trim().to_string()).filter(|model| !model.is_empty())
+    }
+    #[cfg(target_os = "windows")]
+    {
+        std::env::var("PROCESSOR_IDENTIFIER").ok()
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(results: &[(usize, u64)]) -> BenchmarkFile {
+        let results = results.iter().map(|&(threads, hash_rate)| ThreadResult { threads, hash_rate }).collect();
+        BenchmarkFile::new(results, SolverOptions::default())
+    }
+
+    #[test]
+    fn test_default_thread_counts() {
+        assert_eq!(default_thread_counts(1), [1]);
+        assert_eq!(default_thread_counts(8), [1, 2, 4, 8]);
+        assert_eq!(default_thread_counts(12), [1, 2, 4, 8, 12]);
+    }
+
+    #[test]
+    fn test_suggest_threads() {
+        let results = file(&[(1, 1_000), (2, 1_990), (4, 3_900), (8, 4_000)]).results;
+
+        assert_eq!(suggest_threads(&results), Some(4));
+        assert_eq!(suggest_threads(&[]), None);
+    }
+
+    #[test]
+    fn test_compare_flags_regressions() {
+        let deltas = compare(&file(&[(1, 1_000), (4, 4_000)]), &file(&[(1, 960), (4, 3_000), (8, 5_000)]));
+
+        assert_eq!(deltas.iter().map(|d| d.threads).collect::<Vec<_>>(), [1, 4, 8]);
+        assert!(!deltas[0].is_regression());
+        assert!(deltas[1].is_regression());
+        assert_eq!(deltas[1].change(), Some(-25.0));
+        assert_eq!(deltas[2].change(), None);
+    }
+
+    #[test]
+    fn test_parse_is_forward_compatible() {
+        let contents = r#"{
+            "schema_version": 2,
+            "results": [{"threads": 2, "hash_rate": 500, "p99_hash_rate": 450}],
+            "gpu": {"model": "none"}
+        }"#;
+        let file = BenchmarkFile::parse(contents).unwrap();
+
+        assert_eq!(file.schema_version, 2);
+        assert_eq!(file.results, [ThreadResult { threads: 2, hash_rate: 500 }]);
+        assert_eq!(file.machine, Machine::default());
+    }
+
+    #[test]
+    fn test_parse_requires_schema_version() {
+        assert!(BenchmarkFile::parse(r#"{"results": []}"#).is_err());
+        assert!(BenchmarkFile::parse(r#"{"schema_version": 0}"#).is_err());
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let original = file(&[(1, 1_000)]);
+        let json = serde_json::to_string(&original).unwrap();
+
+        assert_eq!(BenchmarkFile::parse(&json).unwrap(), original);
+    }
+}
@@ -0,0 +1,52 @@
+use std::sync::Once;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::warnings::{Warning, WarningCode};
+
+/// The `opt-level` this binary was compiled with, from the build script.
+const OPT_LEVEL: &str = env!("IRONSHIELD_OPT_LEVEL");
+
+pub const WARNING: &str =
+    "This is an unoptimized debug build — hash rates will be drastically lower; build with --release";
+
+static SILENCED: AtomicBool = AtomicBool::new(false);
+static WARNED: Once = Once::new();
+
+/// Whether this binary was built without optimizations, e.g. by a plain
+/// `cargo run`, so its hash rates say little about a release build.
+pub fn is_debug() -> bool {
+    is_unoptimized(cfg!(debug_assertions), OPT_LEVEL)
+}
+
+fn is_unoptimized(debug_assertions: bool, opt_level: &str) -> bool {
+    debug_assertions || opt_level == "0"
+}
+
+/// Turns off [`warn`], for `--no-build-warning`.
+pub fn silence() {
+    SILENCED.store(true, Ordering::Relaxed);
+}
+
+/// Prints [`WARNING`] on a debug build, once per process. Called
+/// before anything that hashes.
+pub fn warn() {
+    if !is_debug() || SILENCED.load(Ordering::Relaxed) {
+        return;
+    }
+    WARNED.call_once(|| {
+        crate::warnings::emit(Warning::new(WarningCode::DebugBuild, format!("{WARNING} (silence with --no-build-warning)")));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_either_signal_means_unoptimized() {
+        assert!(is_unoptimized(true, "3"));
+        assert!(is_unoptimized(false, "0"));
+        assert!(!is_unoptimized(false, "3"));
+        assert!(!is_unoptimized(false, "s"));
+    }
+}
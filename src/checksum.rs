@@ -0,0 +1,166 @@
+use sha2::{Digest, Sha256};
+
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Exit code for a download whose body didn't match `--checksum`.
+pub const MISMATCH_EXIT_CODE: i32 = 4;
+
+/// An expected SHA-256 of a downloaded body, from `--checksum
+/// sha256:<hex>` or a `sha256sum` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checksum {
+    /// Lowercase hex.
+    expected: String,
+}
+
+impl Checksum {
+    /// Parses `sha256:<hex>`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let hex = spec
+            .strip_prefix("sha256:")
+            .ok_or_else(|| format!("'{spec}' is not of the form sha256:<hex>; SHA-256 is the only supported algorithm"))?;
+        Self::from_hex(hex).ok_or_else(|| format!("'{hex}' is not 64 hex digits"))
+    }
+
+    fn from_hex(hex: &str) -> Option<Self> {
+        (hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+            .then(|| Self { expected: hex.to_ascii_lowercase() })
+    }
+
+    /// Finds the hash for the first of `names` listed in a `sha256sum`
+    /// file (`<hex>  <name>`, or `<hex> *<name>` for binary mode).
+    ///
+    /// # Arguments
+    /// * `path`:  The checksum file.
+    /// * `names`: File names to look for, most specific first.
+    pub fn from_sums_file(path: &Path, names: &[&str]) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read '{}': {e}", path.display()))?;
+        Self::from_sums(&content, names).ok_or_else(|| {
+            format!("'{}' has no SHA-256 for {}", path.display(), names.join(" or "))
+        })
+    }
+
+    fn from_sums(content: &str, names: &[&str]) -> Option<Self> {
+        let entries: Vec<(&str, &str)> = content
+            .lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .filter_map(|line| {
+                let (hex, name) = line.split_once(' ')?;
+                let name = name.strip_prefix(['*', ' ']).unwrap_or(name);
+                Some((hex, name.trim_end()))
+            })
+            .collect();
+        names.iter().find_map(|wanted| {
+            entries.iter().find(|(_, name)| {
+                name == wanted || Path::new(name).file_name().is_some_and(|file| file == *wanted)
+            })
+        })
+        .and_then(|(hex, _)| Self::from_hex(hex))
+    }
+
+    pub fn hasher(&self) -> Sha256 {
+        Sha256::new()
+    }
+
+    /// Checks a finished hash; on a mismatch the file is renamed to
+    /// `<name>.failed` so it can't be mistaken for a good download.
+    ///
+    /// # Arguments
+    /// * `hasher`: Everything in the file, fed while it was written.
+    /// * `path`:   The file that was written.
+    pub fn verify(&self, hasher: Sha256, path: &Path) -> Result<(), Mismatch> {
+        let actual = hex(&hasher.finalize());
+        if actual == self.expected {
+            return Ok(());
+        }
+        let mut failed = path.as_os_str().to_owned();
+        failed.push(".failed");
+        let failed = PathBuf::from(failed);
+        let kept = std::fs::rename(path, &failed).ok().map(|_| failed);
+        Err(Mismatch { expected: self.expected.clone(), actual, kept })
+    }
+}
+
+/// Feeds everything `reader` yields into `hasher`, a buffer at a time.
+pub fn hash_reader(hasher: &mut Sha256, mut reader: impl Read) -> io::Result<u64> {
+    let mut buffer = [0; 64 * 1024];
+    let mut total = 0;
+    loop {
+        match reader.read(&mut buffer)? {
+            0 => return Ok(total),
+            n => {
+                hasher.update(&buffer[..n]);
+                total += n as u64;
+            }
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// A downloaded body whose SHA-256 wasn't the expected one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub expected: String,
+    pub actual:   String,
+    /// Where the bad file was moved, if renaming worked.
+    pub kept:     Option<PathBuf>,
+}
+
+impl std::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Checksum mismatch: expected sha256:{}, got sha256:{}", self.expected, self.actual)?;
+        match &self.kept {
+            Some(path) => write!(f, "; the download was moved to {}", path.display()),
+            None       => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for Mismatch {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const EMPTY: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Checksum::parse(&format!("sha256:{}", EMPTY.to_uppercase())).unwrap().expected, EMPTY);
+        assert!(Checksum::parse(EMPTY).unwrap_err().contains("sha256:<hex>"));
+        assert!(Checksum::parse("sha256:abc").unwrap_err().contains("64 hex digits"));
+        assert!(Checksum::parse("md5:d41d8cd98f00b204e9800998ecf8427e").is_err());
+    }
+
+    #[test]
+    fn test_sums_file_lookup_by_name() {
+        let other = "a".repeat(64);
+        let sums = format!("# release sums\n{other}  other.tar.gz\n{EMPTY} *dist/artifact.tar.gz\n");
+
+        assert_eq!(Checksum::from_sums(&sums, &["artifact.tar.gz"]).unwrap().expected, EMPTY);
+        assert_eq!(Checksum::from_sums(&sums, &["missing", "other.tar.gz"]).unwrap().expected, other);
+        assert_eq!(Checksum::from_sums(&sums, &["missing"]), None);
+    }
+
+    #[test]
+    fn test_mismatch_renames_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("artifact.bin");
+        std::fs::File::create(&path).unwrap().write_all(b"not empty").unwrap();
+        let checksum = Checksum::parse(&format!("sha256:{EMPTY}")).unwrap();
+
+        assert_eq!(checksum.verify(checksum.hasher(), &path), Ok(()));
+
+        let mut hasher = checksum.hasher();
+        hash_reader(&mut hasher, std::fs::File::open(&path).unwrap()).unwrap();
+        let mismatch = checksum.verify(hasher, &path).unwrap_err();
+        assert_eq!(mismatch.kept, Some(dir.path().join("artifact.bin.failed")));
+        assert!(!path.exists());
+    }
+}
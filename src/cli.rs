@@ -0,0 +1,2050 @@
+use color_eyre::Result;
+use clap::{
+    Args,
+    CommandFactory,
+    FromArgMatches,
+    Parser,
+    Subcommand,
+    error::ErrorKind,
+};
+
+use ironshield::ClientConfig;
+
+use ironshield::handler::error::ErrorHandler;
+
+use chrono::{DateTime, Utc};
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::{
+    CliClient,
+    SolveOptions,
+    build_profile,
+    checksum,
+    commands,
+    daemon,
+    display,
+    energy,
+    events,
+    examples,
+    har,
+    history,
+    inject,
+    interlock,
+    logging,
+    margin,
+    metrics,
+    presolve,
+    prewarm,
+    prompt,
+    rate_limit,
+    redact,
+    refetch,
+    retry,
+    serve,
+    solve,
+    throttle,
+    tui,
+    tuning,
+    tunnel,
+    warnings,
+    status_println,
+    verbose_log,
+    verbose_section,
+};
+use crate::commands::dry_run::DryRun;
+use crate::commands::interchange::Artifact;
+use crate::commands::proxy::RenewalMargin;
+use crate::compression::Compression;
+use crate::config::{ConfigFormat, ConfigManager, DEFAULT_CONFIG_FILE};
+use crate::display::{NumberFormat, ProgressMode};
+use crate::history::RunCommand;
+use crate::schedule::Schedule;
+use crate::solve::{Strategy, WorkSplit};
+use crate::logging::{CategorySet, ColorChoice, LogFormat, LogOptions, LogTimestamps};
+use crate::output::OutputFormat;
+
+/// Runs the `ironshield` binary: parses the arguments, runs the
+/// command and returns once it is done.
+pub async fn run() -> Result<()> {
+    color_eyre::install()?;
+    logging::init_clock();
+
+    let args: CliArgs = CliArgs::parse()?;
+
+    let effective = resolve_args(&args).unwrap_or_else(|e| e.exit());
+    // Logging isn't set up yet.
+    for conflict in &effective.conflicts {
+        eprintln!("Warning: {conflict}");
+    }
+    let final_config_path = effective.config_path.as_ref().map(|path| path.value.clone());
+
+    // A daemon in a container: nobody to ask, and its logs are collected from stdout.
+    let daemon_mode = matches!(args.command, Some(Commands::Proxy { .. }));
+    if daemon_mode {
+        prompt::disable();
+    }
+
+    let config_start = Instant::now();
+    let cli_config = ConfigManager::load_cli_config(final_config_path.as_deref())?;
+
+    let mut config: ClientConfig = match &final_config_path {
+        Some(config_path) => ConfigManager::load_client_config(config_path)?,
+        None              => ClientConfig::default(),
+    };
+    let config_load = config_start.elapsed();
+
+    if let Some(timeout) = &effective.timeout {
+        config.set_timeout(timeout.value)
+            .map_err(|e| ErrorHandler::config_error(format!("Invalid `--timeout`: {e}")))?;
+    }
+    // Only `-v` in either place overrides the config file's `verbose`.
+    if let Some(verbose) = &effective.verbose {
+        config.set_verbose(verbose.value);
+    }
+
+    // Quiet wins over a config file that turns verbose on.
+    if args.quiet {
+        config.set_verbose(false);
+    }
+
+    // Registered before logging starts so no line escapes redaction.
+    redact::set_enabled(!args.log_secrets);
+    for secret in &cli_config.secrets {
+        if !redact::add_secret(secret) {
+            eprintln!("Warning: ignoring a `secrets` entry shorter than {} characters", redact::MIN_SECRET_LEN);
+        }
+    }
+
+    // Installed as soon as verbosity is known so the log file captures the whole run.
+    let _log_file_guard = logging::init(&LogOptions {
+        verbose:    config.verbose,
+        quiet:      args.quiet,
+        filter:     args.log_filter.unwrap_or(cli_config.log_filter),
+        timestamps: args.log_timestamps.unwrap_or(cli_config.log_timestamps),
+        format:     args.log_format.unwrap_or(if daemon_mode { LogFormat::Json } else { cli_config.log_format }),
+        to_stdout:  args.log_stdout || daemon_mode,
+        log_file:   args.log_file.or(cli_config.log_file),
+        color:      if daemon_mode { ColorChoice::Never } else { args.color.unwrap_or(cli_config.color) },
+    })?;
+    effective.log_sources(config.verbose);
+    display::set_ascii_glyphs(args.ascii || cli_config.ascii_glyphs || !display::console_supports_unicode());
+
+    #[cfg(unix)]
+    if let Some(fd) = args.progress_fd {
+        events::open_fd(fd).map_err(|e| ErrorHandler::config_error(format!("Invalid `--progress-fd`: {e}")))?;
+    }
+    #[cfg(windows)]
+    if let Some(name) = &args.progress_pipe {
+        events::open_pipe(name).map_err(|e| ErrorHandler::config_error(format!("Invalid `--progress-pipe`: {e}")))?;
+    }
+    display::set_progress_mode(if daemon_mode { ProgressMode::Never } else { args.progress.unwrap_or(cli_config.progress) });
+    // JSON consumers parse numbers; only people get grouping or SI.
+    let number_format = match args.output {
+        OutputFormat::Json    => NumberFormat::Plain,
+        OutputFormat::Console => args.number_format.unwrap_or(cli_config.number_format),
+    };
+    display::set_number_format(number_format, cli_config.group_separator.unwrap_or(','));
+    warnings::set_format(args.output);
+    warnings::set_denied(
+        warnings::DenyList::resolve(args.deny_warnings.as_deref(), &cli_config.deny_warnings)
+            .map_err(|e| ErrorHandler::config_error(format!("Invalid `--deny-warnings` or `deny_warnings`: {e}")))?,
+    );
+    history::set_enabled(cli_config.history.enabled);
+    throttle::set_config(cli_config.throttle.clone());
+    let tuning = tuning::SolverOptions::resolve(&cli_config.solver, &args.solver_opts).map_err(ErrorHandler::config_error)?;
+    if args.no_build_warning {
+        build_profile::silence();
+    }
+    if args.yes || args.quiet || matches!(args.output, OutputFormat::Json) {
+        retry::bypass();
+    }
+    if args.prewarm {
+        prewarm::enable(&config).map_err(|e| ErrorHandler::config_error(e.to_string()))?;
+    }
+    if config.verbose {
+        tunnel::enable(&config, &cli_config.tunnel);
+    }
+    // Dropped when `main` returns, so an error still leaves a file behind.
+    let _har_guard = args.har.map(har::arm);
+    if cli_config.threading.is_some() && config.num_threads.is_some() {
+        warnings::emit(warnings::Warning::new(
+            warnings::WarningCode::ConfigConflict,
+            "Both `threading` and `num_threads` are set in the config file; using `threading`",
+        ));
+    }
+    let solver = solve::Solver {
+        threading:         cli_config.threading,
+        work_split:        args.work_split,
+        tuning,
+        skip_local_verify: args.skip_local_verify,
+        ..solve::Solver::default()
+    }.with_total_threads(args.total_threads.map(|total| total as usize));
+    let solve_options = match args.command.as_ref().and_then(Commands::solver_args) {
+        Some(solver) => SolveOptions {
+            strategy: solver.strategy,
+            threads:  effective.threads.as_ref().map(|threads| threads.value),
+            ..SolveOptions::default()
+        },
+        None         => SolveOptions::default(),
+    };
+    if !args.no_rate_limit {
+        rate_limit::set_limit(cli_config.rate_limit.max_requests_per_minute);
+    }
+    let max_expected_time = match (args.max_expected_time, &cli_config.limits.max_expected_time) {
+        (Some(max), _)    => Some(max),
+        (None, Some(max)) => Some(display::parse_duration(max)
+            .map_err(|e| ErrorHandler::config_error(format!("Invalid `max_expected_time`: {e}")))?),
+        (None, None)      => None,
+    };
+    let confirm_expected_time = cli_config.limits.confirm_expected_time.as_deref()
+        .map(display::parse_duration)
+        .transpose()
+        .map_err(|e| ErrorHandler::config_error(format!("Invalid `confirm_expected_time`: {e}")))?;
+    presolve::set_limits(presolve::Limits {
+        max_difficulty: args.max_difficulty.or(cli_config.limits.max_difficulty),
+        max_expected_time,
+        force:          args.force,
+        confirm_expected_time,
+        assume_yes:     args.yes,
+    });
+    refetch::set_policy(refetch::RefetchPolicy::from_config(&cli_config.refetch).map_err(ErrorHandler::config_error)?);
+    energy::set_model(energy::PowerModel::from_config(&cli_config.power).map_err(ErrorHandler::config_error)?);
+    margin::set_policy(
+        margin::MarginPolicy::from_config(&cli_config.margin, args.auto_refresh_on_thin_margin).map_err(ErrorHandler::config_error)?,
+    );
+    if let Some(address) = args.statsd.or(cli_config.statsd) {
+        metrics::set_statsd(metrics::StatsdTarget {
+            address,
+            raw_tags: args.statsd_raw_tags || cli_config.statsd_raw_tags,
+        });
+    }
+
+    // `parse` already refused a missing endpoint when nobody could answer.
+    let mut command = args.command;
+    if let Some(endpoint) = command.as_mut().and_then(Commands::endpoint_mut) {
+        match endpoint {
+            Some(argument) => *argument = history::resolve_endpoint(argument)?,
+            None => {
+                let suggestions = match history::HistoryStore::open_default() {
+                    Some(store) if history::is_enabled() => {
+                        history::recent_endpoints(&store.load().unwrap_or_default(), prompt::ENDPOINT_SUGGESTIONS)
+                    }
+                    _ => Vec::new(),
+                };
+                *endpoint = Some(prompt::endpoint(&suggestions)?);
+            }
+        }
+    }
+    if let Some(Some(endpoint)) = command.as_mut().and_then(Commands::endpoint_mut) {
+        let safeguards = interlock::tripped(&config);
+        match interlock::decide(endpoint, &cli_config.production_hosts, safeguards, args.yes, prompt::is_interactive()) {
+            interlock::Decision::Proceed => {},
+            interlock::Decision::Confirm(safeguards) => {
+                let question = format!("{endpoint} is a production host and {}. Continue?", interlock::describe(&safeguards));
+                if !prompt::confirm(&question) {
+                    return Err(ErrorHandler::config_error("Cancelled: not running against a production host".to_string()).into());
+                }
+            },
+            interlock::Decision::Abort(safeguards) => {
+                return Err(ErrorHandler::config_error(format!(
+                    "Refusing to run against production host {endpoint}: {} (listed in `production_hosts`; pass --yes to run anyway)",
+                    interlock::describe(&safeguards),
+                )).into());
+            },
+        }
+    }
+
+    match &final_config_path {
+        Some(config_path) => status_println!(
+            "Loaded configuration from: {} ({})",
+            config_path,
+            ConfigFormat::detect(config_path).name(),
+        ),
+        None              => status_println!("No config file specified, using default configuration."),
+    }
+
+    let client = CliClient::new(config.clone())
+        .map_err(|e| ErrorHandler::config_error(e.to_string()))?
+        .with_solve_options(solve_options)
+        .with_solver(solver)
+        .with_injection(inject::Injection::new(
+            args.inject_fetch_failure.unwrap_or(0),
+            args.inject_submit_status,
+            args.inject_solve_delay,
+            args.inject_corrupt_solution,
+        ))
+        .on_event(commands::presenter::console(config.verbose));
+
+    // `proxy` serves its own, alongside its token endpoints.
+    if let (None, Some(addr)) = (&command, args.metrics_listen) {
+        let addr = metrics::serve(addr).await
+            .map_err(|e| ErrorHandler::config_error(format!("Cannot listen for metrics on {addr}: {e}")))?;
+        status_println!("Serving metrics at http://{addr}/metrics");
+    }
+
+    verbose_section!(config, "Client Initialization");
+    verbose_log!(config, success, "Client initialized successfully.");
+    if command.as_ref().is_some_and(Commands::fetches_challenges) {
+        tunnel::check_once().await;
+    }
+
+    let sink = crate::output::sink(args.output, config.verbose);
+
+    let result = match command {
+        Some(Commands::Fetch { endpoint: Some(endpoint), .. }) => {
+            commands::fetch::handle_fetch(&client, &config, &endpoint, sink.as_ref()).await
+        },
+        Some(Commands::Solve { endpoint: Some(endpoint), single_threaded, dry_run: Some(mode), json, .. }) => {
+            commands::dry_run::handle_dry_run(&client, &config, RunCommand::Solve, &endpoint, !single_threaded, mode, json).await
+        },
+        Some(Commands::Validate { stdin: true, concurrency, failures_out, .. }) => {
+            commands::validate::handle_validate_stdin(client, &config, concurrency, failures_out.as_deref()).await
+        },
+        Some(Commands::Validate { endpoint: Some(endpoint), single_threaded, dry_run: Some(mode), json, .. }) => {
+            commands::dry_run::handle_dry_run(&client, &config, RunCommand::Validate, &endpoint, !single_threaded, mode, json).await
+        },
+        Some(Commands::Solve { endpoint: Some(endpoint), single_threaded, dump_repro, .. }) => {
+            if dump_repro.is_some() {
+                crate::repro::arm();
+            }
+            commands::solve::handle_solve(&client, &config, &endpoint, single_threaded, dump_repro.as_deref(), sink.as_ref()).await
+        },
+        Some(Commands::Run { endpoint: Some(endpoint), single_threaded, max_time, .. }) => {
+            commands::run::handle_run(&client, &config, &endpoint, single_threaded, max_time, sink.as_ref()).await
+        },
+        Some(Commands::Validate { endpoint: Some(endpoint), single_threaded, max_time, .. }) => {
+            commands::validate::handle_validate(&client, &config, &endpoint, single_threaded, max_time, config_load, sink.as_ref()).await
+        },
+        Some(Commands::Get {
+            endpoint: Some(endpoint), single_threaded, max_time, save_body, resume, checksum, checksum_file, no_http_cache,
+            data, data_file, no_template, ..
+        }) => {
+            let options = commands::get::GetOptions {
+                single_threaded,
+                max_time,
+                save_body: save_body.as_deref(),
+                resume,
+                no_http_cache,
+                checksum: checksum.as_deref(),
+                checksum_file: checksum_file.as_deref(),
+                data: data.as_deref(),
+                data_file: data_file.as_deref(),
+                template: !no_template,
+            };
+            commands::get::handle_get(&client, &config, &endpoint, &options, sink.as_ref()).await
+        },
+        Some(Commands::Survey { endpoints_file, samples, interval, delay, csv, .. }) => {
+            let options = commands::survey::SurveyOptions {
+                endpoints_file: &endpoints_file,
+                samples,
+                interval,
+                delay,
+                csv:            csv.as_deref(),
+            };
+            commands::survey::handle_survey(&client, &config, &options).await
+        },
+        Some(Commands::Batch { endpoints, endpoints_file, stdin, schedule, failures_out, .. }) => {
+            let (endpoints_file, failures_out) = (endpoints_file.as_deref(), failures_out.as_deref());
+            commands::batch::handle_batch(client, &config, endpoints, endpoints_file, stdin, schedule, failures_out).await
+        },
+        Some(Commands::Stream { .. }) => {
+            let supervised = daemon::Supervised { client, config: config.clone(), config_path: final_config_path };
+            commands::stream::handle_stream(supervised).await
+        },
+        Some(Commands::Serve { listen, .. }) => {
+            let policy = serve::ServePolicy::from_config(&cli_config.serve).map_err(ErrorHandler::config_error)?;
+            let supervised = daemon::Supervised { client, config: config.clone(), config_path: final_config_path };
+            commands::serve::handle_serve(supervised, listen, policy).await
+        },
+        Some(Commands::Spool { dir, poll, inotify, jobs, single_threaded, .. }) => {
+            let supervised = daemon::Supervised { client, config: config.clone(), config_path: final_config_path };
+            let watch = match (poll, inotify) {
+                (Some(interval), _) => commands::spool::Watch::Poll(interval),
+                (None, true)        => commands::spool::Watch::Native,
+                (None, false)       => commands::spool::Watch::Auto,
+            };
+            let options = commands::spool::SpoolOptions { dir: &dir, watch, jobs, single_threaded };
+            commands::spool::handle_spool(supervised, &options).await
+        },
+        Some(Commands::Proxy { endpoint, listen, ready_within, max_renewal_difficulty, renewal_margin, .. }) => {
+            let supervised = daemon::Supervised { client, config: config.clone(), config_path: final_config_path };
+            let options = commands::proxy::ProxyOptions {
+                endpoint: &endpoint,
+                listen,
+                metrics_listen: args.metrics_listen,
+                ready_within,
+                max_renewal_difficulty,
+                renewal_margin,
+            };
+            commands::proxy::handle_proxy(supervised, &options).await
+        },
+        Some(Commands::History { action, limit, endpoint, json }) => match action {
+            Some(HistoryAction::Stats)     => commands::history::handle_stats(endpoint.as_deref(), json),
+            Some(HistoryAction::Endpoints) => commands::history::handle_endpoints(endpoint.as_deref(), json),
+            Some(HistoryAction::Compare { from, to, baseline }) => {
+                commands::history::handle_compare(from, to, baseline, endpoint.as_deref(), json)
+            }
+            None                           => commands::history::handle_history(limit, endpoint.as_deref(), json),
+        },
+        Some(Commands::Benchmark { action, threads, duration, save, json }) => match action {
+            Some(BenchmarkAction::Compare { old, new }) => commands::benchmark::handle_compare(&old, &new),
+            Some(BenchmarkAction::Split { slowdown, ranges }) => {
+                let threads = threads.first().copied().unwrap_or_else(num_cpus::get);
+                commands::benchmark::handle_split(threads, slowdown, ranges).await
+            }
+            None => commands::benchmark::handle_benchmark(threads, duration, save.as_deref(), json, tuning).await,
+        },
+        Some(Commands::Fetch { endpoint: None, .. })
+        | Some(Commands::Solve { endpoint: None, .. })
+        | Some(Commands::Run { endpoint: None, .. })
+        | Some(Commands::Validate { endpoint: None, .. })
+        | Some(Commands::Get { endpoint: None, .. }) => unreachable!("a missing endpoint is asked for before dispatch"),
+        Some(Commands::Cache { action: CacheAction::Purge }) => commands::get::handle_purge(),
+        Some(Commands::State { action }) => match action {
+            StateAction::Path { json }                    => commands::state::handle_path(json),
+            StateAction::Size { json }                    => commands::state::handle_size(json),
+            StateAction::Gc { older_than, dry_run, json } => {
+                commands::state::handle_gc(older_than, cli_config.history.retention.as_deref(), dry_run, json)
+            }
+        },
+        Some(Commands::Repro { dir }) => commands::repro::handle_repro(&dir).await,
+        Some(Commands::Queue { action }) => match action {
+            QueueAction::Add { file }                  => commands::queue::handle_add(&file, &config.api_base_url),
+            QueueAction::Solve { single_threaded, .. } => commands::queue::handle_solve(&client, single_threaded).await,
+            QueueAction::Submit { concurrency }        => commands::queue::handle_submit(&client, concurrency).await,
+            QueueAction::List { json }                 => commands::queue::handle_list(json),
+        },
+        Some(Commands::Challenge { action: ChallengeAction::Generate { difficulty, expires_in, website_id, out, compress } }) => {
+            let options = commands::generate::GenerateOptions { difficulty, expires_in, website_id, force: args.force };
+            commands::generate::handle_generate(&options, out.as_deref(), compress)
+        }
+        Some(Commands::Challenge { action: ChallengeAction::Convert(action) }) => convert(Artifact::Challenge, &action),
+        Some(Commands::Solution { action })  => convert(Artifact::Solution, &action),
+        Some(Commands::Config { action }) => match action {
+            ConfigAction::Init { path, format } => commands::config::handle_init(path.as_deref(), format, args.yes),
+            ConfigAction::Set { key, value }    => {
+                let path = final_config_path.as_deref().unwrap_or(DEFAULT_CONFIG_FILE);
+                commands::config::handle_set(path, &key, &value)
+            }
+            ConfigAction::Show { compare }      => {
+                commands::config::handle_show(final_config_path.as_deref(), &config, compare.as_deref())
+            }
+        },
+        Some(Commands::Setup { defaults, api_base_url }) => {
+            let path = PathBuf::from(final_config_path.as_deref().unwrap_or(DEFAULT_CONFIG_FILE));
+            let options = commands::setup::SetupOptions { path: &path, defaults, api_base_url, assume_yes: args.yes };
+            commands::setup::handle_setup(&options).await
+        }
+        Some(Commands::Doctor { proxy, .. }) => commands::doctor::handle_doctor(&config, &cli_config.tunnel, proxy).await,
+        Some(Commands::Examples { command }) => commands::examples::handle_examples(command.as_deref()),
+        // `parse` guarantees a subcommand unless `--tui` was given.
+        None => {
+            let options = tui::TuiOptions {
+                dump_logs:    args.dump_logs,
+                config_path:  final_config_path,
+                verbose_flag: args.verbose,
+                tui:          cli_config.tui,
+            };
+            tui::run(client, config, options).await
+        }
+    };
+
+    // The console gets the full error report from color_eyre; make
+    // sure the debug log file records the failure as well.
+    if let Err(e) = &result {
+        logging::file_event(logging::LogCategory::Error, format_args!("{e:#}"));
+
+        // A refusal is a decision, not a crash: no report, and its own exit code.
+        if let Some(refused) = e.downcast_ref::<presolve::Refused>() {
+            eprintln!("{refused}");
+            logging::flush();
+            har::write();
+            std::process::exit(presolve::REFUSED_EXIT_CODE);
+        }
+        if let Some(mismatch) = e.downcast_ref::<checksum::Mismatch>() {
+            eprintln!("{mismatch}");
+            logging::flush();
+            har::write();
+            std::process::exit(checksum::MISMATCH_EXIT_CODE);
+        }
+    }
+
+    result
+}
+
+/// Runs `challenge` or `solution` `decode|encode`.
+fn convert(artifact: Artifact, action: &ConvertAction) -> Result<()> {
+    match action {
+        ConvertAction::Decode { input } => commands::interchange::handle_convert(artifact, false, input.source()),
+        ConvertAction::Encode { input } => commands::interchange::handle_convert(artifact, true, input.source()),
+    }
+}
+
+#[derive(Parser)]
+#[command(
+    name = "ironshield",
+    about = "IronShield CLI - Fetch and solve proof-of-work challenges",
+    version,
+    long_about = "A command-line interface for interacting with IronShield proof-of-work \
+                  challenge systems. Supports fetching challenges, solving them, and \
+                  verifying solutions for protected endpoints. Every command's --help ends \
+                  with examples; `ironshield examples` prints them all."
+)]
+pub struct CliArgs {
+    #[arg(
+        short,
+        long,
+        help = "Enable verbose output (overrides config file setting)."
+    )]
+    pub verbose: bool,
+    #[arg(
+        short,
+        long,
+        global = true,
+        conflicts_with = "verbose",
+        help = "Suppress all output except the final result and errors."
+    )]
+    pub quiet: bool,
+    #[arg(
+        short,
+        long,
+        help = "Path to the configuration file."
+    )]
+    pub config_path: Option<String>,
+    #[arg(
+        long = "log-stdout",
+        global = true,
+        help = "Write diagnostic output to stdout instead of stderr (legacy behavior)."
+    )]
+    pub log_stdout: bool,
+    #[arg(
+        long = "log-timestamps",
+        global = true,
+        value_enum,
+        help = "Prefix verbose log lines with a timestamp (overrides config file setting)."
+    )]
+    pub log_timestamps: Option<LogTimestamps>,
+    #[arg(
+        long = "log-filter",
+        global = true,
+        value_name = "CATEGORIES",
+        help = "Comma-separated verbose log categories to print, e.g. `network,timing` \
+                (errors and warnings are always shown)."
+    )]
+    pub log_filter: Option<CategorySet>,
+    #[arg(
+        long = "log-file",
+        global = true,
+        value_name = "PATH",
+        help = "Append full-detail logs to this file regardless of console verbosity."
+    )]
+    pub log_file: Option<String>,
+    #[arg(
+        long = "log-format",
+        global = true,
+        value_enum,
+        help = "Log output format (overrides config file setting)."
+    )]
+    pub log_format: Option<LogFormat>,
+    #[arg(
+        long = "log-secrets",
+        global = true,
+        help = "Log secrets verbatim instead of masking them (for local debugging only)."
+    )]
+    pub log_secrets: bool,
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        help = "When to color output; `auto` honors NO_COLOR and TTY detection."
+    )]
+    pub color: Option<ColorChoice>,
+    #[arg(
+        long,
+        global = true,
+        help = "Draw section headers, spinners, bars and empty table cells with ASCII only \
+                (the default when the terminal's code page or locale isn't UTF-8)."
+    )]
+    pub ascii: bool,
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        help = "When to draw the progress spinner; `auto` uses plain lines when not a TTY."
+    )]
+    pub progress: Option<ProgressMode>,
+    #[cfg(unix)]
+    #[arg(
+        long = "progress-fd",
+        global = true,
+        value_name = "FD",
+        help = "Also write progress as NDJSON events to this inherited file descriptor, e.g. for a GUI wrapper."
+    )]
+    pub progress_fd: Option<i32>,
+    #[cfg(windows)]
+    #[arg(
+        long = "progress-pipe",
+        global = true,
+        value_name = "NAME",
+        help = "Also write progress as NDJSON events to this named pipe, e.g. for a GUI wrapper."
+    )]
+    pub progress_pipe: Option<String>,
+    #[arg(
+        long = "number-format",
+        global = true,
+        value_enum,
+        help = "How counts are shown: `grouped` (1,234,567), `plain` or `si` (1.2M). JSON output is always plain."
+    )]
+    pub number_format: Option<NumberFormat>,
+    #[arg(
+        long,
+        conflicts_with = "quiet",
+        help = "Launch the interactive terminal UI instead of running a subcommand."
+    )]
+    pub tui: bool,
+    #[arg(
+        long = "dump-logs",
+        requires = "tui",
+        help = "Print the TUI's captured log lines to stdout when it exits."
+    )]
+    pub dump_logs: bool,
+    #[arg(
+        long = "metrics-listen",
+        value_name = "ADDR",
+        help = "Serve Prometheus metrics at http://ADDR/metrics while the TUI or `proxy` runs, e.g. 127.0.0.1:9188."
+    )]
+    pub metrics_listen: Option<SocketAddr>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "HOST:PORT",
+        help = "Send DogStatsD metrics over UDP when a fetch, solve or validate finishes."
+    )]
+    pub statsd: Option<String>,
+    #[arg(
+        long = "statsd-raw-tags",
+        global = true,
+        help = "Tag StatsD metrics with the endpoint itself instead of a hash of it."
+    )]
+    pub statsd_raw_tags: bool,
+    #[arg(
+        long = "no-rate-limit",
+        global = true,
+        help = "Request challenges as fast as possible, ignoring `max_requests_per_minute` (for load testing)."
+    )]
+    pub no_rate_limit: bool,
+    #[arg(
+        long = "max-difficulty",
+        global = true,
+        value_name = "N",
+        help = "Refuse to solve challenges above this difficulty (overrides config file setting)."
+    )]
+    pub max_difficulty: Option<u64>,
+    #[arg(
+        long = "max-expected-time",
+        global = true,
+        value_name = "DURATION",
+        value_parser = display::parse_duration,
+        help = "Refuse to solve challenges expected to take longer than this on this machine, e.g. `2m`."
+    )]
+    pub max_expected_time: Option<Duration>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "DURATION",
+        value_parser = display::parse_duration,
+        help = "HTTP request timeout, e.g. `30s` or `2m30s` (overrides `timeout` in the config file)."
+    )]
+    pub timeout: Option<Duration>,
+    #[arg(
+        long,
+        global = true,
+        help = "Solve even if the challenge exceeds `--max-difficulty` or `--max-expected-time`, \
+                or let `challenge generate` exceed its difficulty cap."
+    )]
+    pub force: bool,
+    #[arg(
+        short = 'y',
+        long,
+        global = true,
+        help = "Answer yes instead of asking: before slow solves (`confirm_expected_time`), \
+                before running against `production_hosts` with a risky `api_base_url`, \
+                before `setup` or `config init` overwrites a config file. Also never offers \
+                to retry a failed solve, run or validate."
+    )]
+    pub yes: bool,
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = OutputFormat::Console,
+        help = "How fetch, solve and validate print: a pretty JSON result, or one JSON record per line including status."
+    )]
+    pub output: OutputFormat,
+    #[arg(
+        long = "work-split",
+        global = true,
+        value_enum,
+        default_value_t = WorkSplit::Chunked,
+        help = "How solver threads divide the nonce space: fixed strides, or ranges taken from a shared cursor."
+    )]
+    pub work_split: WorkSplit,
+    #[arg(
+        long = "total-threads",
+        global = true,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Solver threads shared by every solve running at once; defaults to the number of cores."
+    )]
+    pub total_threads: Option<u64>,
+    #[arg(
+        long = "solver-opt",
+        global = true,
+        value_name = "KEY=VALUE",
+        hide_short_help = true,
+        help = "Set an advanced solver option, e.g. `batch_size=1000000` or `give_up_factor=50`, over the config file's `[solver]` section. Repeatable."
+    )]
+    pub solver_opts: Vec<String>,
+    #[arg(
+        long = "no-build-warning",
+        global = true,
+        hide_short_help = true,
+        help = "Don't warn that hash rates are low on an unoptimized debug build (for developers)."
+    )]
+    pub no_build_warning: bool,
+    #[arg(
+        long = "deny-warnings",
+        global = true,
+        value_name = "CODES",
+        num_args = 0..=1,
+        require_equals = true,
+        value_delimiter = ',',
+        help = "Fail the run on a warning: any warning, or only the comma-separated codes given, \
+                e.g. `--deny-warnings=W002,W003`. Adds to the config file's `deny_warnings`."
+    )]
+    pub deny_warnings: Option<Vec<String>>,
+    #[arg(
+        long = "inject-fetch-failure",
+        global = true,
+        value_name = "N",
+        help_heading = "Testing",
+        hide_short_help = true,
+        help = "Fail the first N challenge fetches with a synthetic 503, without contacting the API."
+    )]
+    pub inject_fetch_failure: Option<u64>,
+    #[arg(
+        long = "inject-submit-status",
+        global = true,
+        value_name = "CODE",
+        value_parser = inject::parse_status,
+        help_heading = "Testing",
+        hide_short_help = true,
+        help = "Fail every solution submit with this HTTP error status, without contacting the API."
+    )]
+    pub inject_submit_status: Option<reqwest::StatusCode>,
+    #[arg(
+        long = "inject-solve-delay",
+        global = true,
+        value_name = "DURATION",
+        value_parser = display::parse_duration,
+        help_heading = "Testing",
+        hide_short_help = true,
+        help = "Wait this long before every solve, e.g. `2s`, to exercise deadlines and progress."
+    )]
+    pub inject_solve_delay: Option<Duration>,
+    #[arg(
+        long = "inject-corrupt-solution",
+        global = true,
+        help_heading = "Testing",
+        hide_short_help = true,
+        help = "Replace every solution with a nonce that doesn't solve the challenge, to exercise local verification."
+    )]
+    pub inject_corrupt_solution: bool,
+    #[arg(
+        long = "skip-local-verify",
+        global = true,
+        hide_short_help = true,
+        help = "Submit solutions without checking them locally first, to debug server-side verification."
+    )]
+    pub skip_local_verify: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "While solving, resolve the API host and open a connection to it, so the submit doesn't wait for one."
+    )]
+    pub prewarm: bool,
+    #[arg(
+        long = "auto-refresh-on-thin-margin",
+        global = true,
+        help = "When a solve leaves less than `[margin] slack` for the submit, fetch and solve a fresh challenge instead of submitting."
+    )]
+    pub auto_refresh_on_thin_margin: bool,
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        help = "Record every HTTP request and response to this file in HAR 1.2 format, with secrets \
+                masked and bodies cut at 64 KiB, for the server team to see what was sent."
+    )]
+    pub har: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+/// Solver options shared by every command that solves.
+#[derive(Args, Clone, Copy)]
+pub struct SolverArgs {
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = Strategy::Balanced,
+        help = "Solver preset: every core, most cores, or a quarter of the cores at idle priority."
+    )]
+    pub strategy: Strategy,
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Solve on exactly this many threads (overrides the strategy and config file setting)."
+    )]
+    pub threads:  Option<usize>,
+}
+
+#[derive(Subcommand)]
+pub enum Commands {
+
+    // Descriptions for CLI arguments are
+    // denoted by adding a triple '/' (///)
+    // above the enum variant.
+    //
+    // Example:
+    //
+    // enum Example {
+    //     /// Command does this and that.
+    //     Command { /* whatever it's fetching */ }
+    // }
+
+    /// Fetches an IronShield request as an object.
+    Fetch {
+        /// The protected endpoint URL to request from. `@last` or `@1`..`@9` recall one from history;
+        /// asked for when omitted on a terminal.
+        endpoint: Option<String>,
+
+        #[arg(
+            short,
+            long,
+            help = "Enable verbose output (overrides config file setting)."
+        )]
+        verbose: bool,
+
+        #[arg(
+            short,
+            long,
+            help = "Path to the configuration file."
+        )]
+        config_path: Option<String>,
+    },
+
+    /// Solves an IronShield challenge for a given endpoint.
+    Solve {
+        /// The protected endpoint URL to solve for. `@last` or `@1`..`@9` recall one from history;
+        /// asked for when omitted on a terminal.
+        endpoint: Option<String>,
+
+        #[arg(
+            short = 's',
+            long = "single-threaded",
+            help = "Use single-threaded solving instead of the default multithreaded approach."
+        )]
+        single_threaded: bool,
+        #[command(flatten)]
+        solver: SolverArgs,
+        #[arg(
+            long = "dry-run",
+            value_enum,
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "online",
+            value_name = "MODE",
+            help = "Fetch the challenge and print what would happen, then exit without solving; \
+                    `--dry-run=offline` skips the fetch too."
+        )]
+        dry_run: Option<DryRun>,
+        #[arg(
+            long,
+            requires = "dry_run",
+            help = "Print the dry-run plan as JSON."
+        )]
+        json: bool,
+        #[arg(
+            long = "dump-repro",
+            value_name = "DIR",
+            conflicts_with = "dry_run",
+            help = "Write the challenge, settings and outcome to DIR so `ironshield repro DIR` can replay the solve."
+        )]
+        dump_repro: Option<PathBuf>,
+        #[arg(
+            short,
+            long,
+            help = "Enable verbose output (overrides config file setting)."
+        )]
+        verbose: bool,
+        #[arg(
+            short,
+            long,
+            help = "Path to the configuration file."
+        )]
+        config_path: Option<String>,
+    },
+
+    /// Fetches and solves a challenge without submitting it, printing both with how long each stage took.
+    Run {
+        /// The protected endpoint URL to run against. `@last` or `@1`..`@9` recall one from history;
+        /// asked for when omitted on a terminal.
+        endpoint: Option<String>,
+
+        #[arg(
+            short = 's',
+            long = "single-threaded",
+            help = "Use single-threaded solving instead of the default multithreaded approach."
+        )]
+        single_threaded: bool,
+        #[command(flatten)]
+        solver: SolverArgs,
+        #[arg(
+            long = "max-time",
+            value_name = "DURATION",
+            value_parser = display::parse_duration,
+            help = "Give up if fetching and solving take longer than this in total, e.g. `30s`."
+        )]
+        max_time: Option<Duration>,
+        #[arg(
+            short,
+            long,
+            help = "Enable verbose output (overrides config file setting)."
+        )]
+        verbose: bool,
+        #[arg(
+            short,
+            long,
+            help = "Path to the configuration file."
+        )]
+        config_path: Option<String>,
+    },
+    Validate {
+        /// The protected endpoint URL to validate a challenge with. `@last` or `@1`..`@9` recall one from history;
+        /// asked for when omitted on a terminal.
+        endpoint: Option<String>,
+
+        #[arg(
+            short = 's',
+            long = "single-threaded",
+            help = "Use single-threaded solving instead of the default multithreaded approach."
+        )]
+        single_threaded: bool,
+        #[command(flatten)]
+        solver: SolverArgs,
+        #[arg(
+            long = "max-time",
+            value_name = "DURATION",
+            value_parser = display::parse_duration,
+            help = "Give up if fetching, solving and submitting take longer than this in total, e.g. `30s`."
+        )]
+        max_time: Option<Duration>,
+        #[arg(
+            long,
+            conflicts_with_all = ["endpoint", "dry_run", "max_time"],
+            help = "Validate every endpoint listed on stdin, one per line, printing a JSON line for each; \
+                    blank lines and `#` comments are skipped."
+        )]
+        stdin: bool,
+        #[arg(
+            long,
+            value_name = "N",
+            default_value_t = 1,
+            requires = "stdin",
+            help = "With `--stdin`, validate this many endpoints at once."
+        )]
+        concurrency: usize,
+        #[arg(
+            long = "failures-out",
+            value_name = "PATH",
+            requires = "stdin",
+            help = "With `--stdin`, write every failure (endpoint, stage, error kind, message, time) to this JSON file."
+        )]
+        failures_out: Option<PathBuf>,
+        #[arg(
+            long = "dry-run",
+            value_enum,
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "online",
+            value_name = "MODE",
+            help = "Fetch the challenge and print what would happen, then exit without solving; \
+                    `--dry-run=offline` skips the fetch too."
+        )]
+        dry_run: Option<DryRun>,
+        #[arg(
+            long,
+            requires = "dry_run",
+            help = "Print the dry-run plan as JSON."
+        )]
+        json: bool,
+        #[arg(
+            short,
+            long,
+            help = "Enable verbose output (overrides config file setting)."
+        )]
+        verbose: bool,
+        #[arg(
+            short,
+            long,
+            help = "Path to the configuration file."
+        )]
+        config_path: Option<String>,
+    },
+
+    /// Validates against a protected endpoint, then downloads it with the token attached.
+    Get {
+        /// The protected endpoint URL to download. `@last` or `@1`..`@9` recall one from history;
+        /// asked for when omitted on a terminal.
+        endpoint: Option<String>,
+
+        #[arg(
+            short = 's',
+            long = "single-threaded",
+            help = "Use single-threaded solving instead of the default multithreaded approach."
+        )]
+        single_threaded: bool,
+        #[command(flatten)]
+        solver: SolverArgs,
+        #[arg(
+            long = "max-time",
+            value_name = "DURATION",
+            value_parser = display::parse_duration,
+            help = "Give up if fetching, solving and submitting take longer than this in total, e.g. `30s`."
+        )]
+        max_time: Option<Duration>,
+        #[arg(
+            long = "save-body",
+            value_name = "PATH",
+            help = "Write the response body to this file instead of stdout."
+        )]
+        save_body: Option<PathBuf>,
+        #[arg(
+            long = "continue",
+            requires = "save_body",
+            help = "Resume into an existing --save-body file with a Range request instead of downloading it again."
+        )]
+        resume: bool,
+        #[arg(
+            long = "checksum",
+            value_name = "sha256:HEX",
+            requires = "save_body",
+            conflicts_with = "checksum_file",
+            help = "Fail, renaming the file to <file>.failed, unless the saved body has this SHA-256."
+        )]
+        checksum: Option<String>,
+        #[arg(
+            long = "checksum-file",
+            value_name = "PATH",
+            requires = "save_body",
+            help = "Like --checksum, with the hash looked up by file name in a sha256sum file."
+        )]
+        checksum_file: Option<PathBuf>,
+        #[arg(
+            long = "no-http-cache",
+            help = "Don't send If-None-Match/If-Modified-Since or cache the response."
+        )]
+        no_http_cache: bool,
+        #[arg(
+            long = "data",
+            value_name = "BODY",
+            conflicts_with_all = ["data_file", "resume"],
+            help = "POST this body instead of sending a GET. {{token}}, {{response_header}}, {{timestamp_ms}} and \
+                    {{endpoint}} are filled in after solving; write {{{{ for a literal {{."
+        )]
+        data: Option<String>,
+        #[arg(
+            long = "data-file",
+            value_name = "PATH",
+            conflicts_with = "resume",
+            help = "Like --data, with the body read from this file."
+        )]
+        data_file: Option<PathBuf>,
+        #[arg(
+            long = "no-template",
+            help = "Send the --data or --data-file body exactly as given, without filling in placeholders."
+        )]
+        no_template: bool,
+        #[arg(
+            short,
+            long,
+            help = "Enable verbose output (overrides config file setting)."
+        )]
+        verbose: bool,
+        #[arg(
+            short,
+            long,
+            help = "Path to the configuration file."
+        )]
+        config_path: Option<String>,
+    },
+
+    /// Replays a solve written by `solve --dump-repro` and checks that it ends the same way.
+    Repro {
+        /// The bundle directory.
+        dir: PathBuf,
+    },
+
+    /// Manages responses cached by `get`.
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+
+    /// Queues challenges to solve offline now and submit later, while they are still valid.
+    Queue {
+        #[command(subcommand)]
+        action: QueueAction,
+    },
+
+    /// Inspects and cleans up what ironshield keeps on disk between runs.
+    State {
+        #[command(subcommand)]
+        action: StateAction,
+    },
+
+    /// Repeatedly fetches (never solves) challenges and summarises their difficulty.
+    Survey {
+        #[arg(
+            long = "endpoints-file",
+            value_name = "PATH",
+            help = "File with one endpoint per line; blank lines and `#` comments are skipped."
+        )]
+        endpoints_file: PathBuf,
+        #[arg(
+            long,
+            default_value_t = 5,
+            help = "Fetch every endpoint this many times."
+        )]
+        samples: usize,
+        #[arg(
+            long,
+            default_value = "60s",
+            value_parser = display::parse_duration,
+            help = "Time between rounds of fetches, e.g. `60s` or `5m`."
+        )]
+        interval: Duration,
+        #[arg(
+            long,
+            default_value = "1s",
+            value_parser = display::parse_duration,
+            help = "Politeness delay between two fetches within a round."
+        )]
+        delay: Duration,
+        #[arg(
+            long,
+            value_name = "PATH",
+            help = "Also write every sample to this CSV file."
+        )]
+        csv: Option<PathBuf>,
+        #[arg(
+            short,
+            long,
+            help = "Enable verbose output (overrides config file setting)."
+        )]
+        verbose: bool,
+        #[arg(
+            short,
+            long,
+            help = "Path to the configuration file."
+        )]
+        config_path: Option<String>,
+    },
+
+    /// Reads JSON commands from stdin, one per line, and writes one JSON result per line.
+    ///
+    /// Each line is an object such as `{"cmd":"validate","endpoint":"https://..."}`;
+    /// `cmd` is `fetch`, `solve` or `validate`, and an optional `id` is echoed back.
+    Stream {
+        #[command(flatten)]
+        solver: SolverArgs,
+        #[arg(
+            short,
+            long,
+            help = "Enable verbose output (overrides config file setting)."
+        )]
+        verbose: bool,
+        #[arg(
+            short,
+            long,
+            help = "Path to the configuration file."
+        )]
+        config_path: Option<String>,
+    },
+
+    /// Solves challenges dropped into a directory, writing each solution next to its challenge.
+    ///
+    /// Picks up every `<name>.challenge.json`, writes `<name>.solution.json`
+    /// and moves the challenge to `done/` or `failed/`. Writers must rename
+    /// challenges into place so half-written ones are never read.
+    /// `SIGTERM` finishes the solves in flight and stops; `SIGHUP` reloads the config file.
+    Spool {
+        #[arg(
+            long,
+            value_name = "PATH",
+            help = "The directory to watch."
+        )]
+        dir: PathBuf,
+        #[arg(
+            long,
+            value_parser = display::parse_duration,
+            conflicts_with = "inotify",
+            help = "Rescan the directory at this interval, e.g. `2s`, instead of waiting for notifications."
+        )]
+        poll: Option<Duration>,
+        #[arg(
+            long,
+            help = "Use the platform's file notifications only, failing rather than falling back to polling."
+        )]
+        inotify: bool,
+        #[arg(
+            long,
+            value_name = "N",
+            default_value_t = 1,
+            help = "Solve this many challenges at once, sharing the `--total-threads` budget."
+        )]
+        jobs: usize,
+        #[arg(
+            short = 's',
+            long = "single-threaded",
+            help = "Use single-threaded solving instead of the default multithreaded approach."
+        )]
+        single_threaded: bool,
+        #[command(flatten)]
+        solver: SolverArgs,
+        #[arg(
+            short,
+            long,
+            help = "Enable verbose output (overrides config file setting)."
+        )]
+        verbose: bool,
+        #[arg(
+            short,
+            long,
+            help = "Path to the configuration file."
+        )]
+        config_path: Option<String>,
+    },
+
+    /// Solves and validates over a small JSON HTTP API, for tools in other languages.
+    ///
+    /// `POST /solve` takes a challenge and returns its solution; `POST /validate`
+    /// takes `{"endpoint": "..."}` and returns a token; `GET /status` lists the jobs
+    /// in flight. Both POSTs take `?max_time=30s`. Authentication and limits are set
+    /// under `[serve]` in the config file. `SIGTERM` finishes the jobs in flight and stops.
+    Serve {
+        #[arg(
+            long,
+            value_name = "ADDR",
+            value_parser = commands::proxy::parse_listen,
+            default_value = "127.0.0.1:9099",
+            help = "Address to serve on; anything but loopback needs `token` under [serve]."
+        )]
+        listen: SocketAddr,
+        #[command(flatten)]
+        solver: SolverArgs,
+        #[arg(
+            short,
+            long,
+            help = "Enable verbose output (overrides config file setting)."
+        )]
+        verbose: bool,
+        #[arg(
+            short,
+            long,
+            help = "Path to the configuration file."
+        )]
+        config_path: Option<String>,
+    },
+
+    /// Keeps a fresh token for an endpoint and serves it over HTTP, for running as a sidecar.
+    ///
+    /// Serves `GET /token`, `/healthz` and `/readyz`. Logs go to stdout as
+    /// JSON unless `--log-format` says otherwise, and nothing is ever prompted.
+    /// `SIGTERM` stops it; `SIGHUP` reloads the config file.
+    Proxy {
+        /// The protected endpoint to obtain tokens for.
+        endpoint: String,
+        #[arg(
+            long,
+            value_name = "ADDR",
+            value_parser = commands::proxy::parse_listen,
+            default_value = "127.0.0.1:8787",
+            help = "Address to serve on, e.g. 0.0.0.0:8787 or [::]:8787."
+        )]
+        listen: SocketAddr,
+        #[arg(
+            long,
+            value_parser = display::parse_duration,
+            default_value = "5m",
+            help = "Report not ready once no token has been fetched for this long."
+        )]
+        ready_within: Duration,
+        #[arg(
+            long,
+            value_name = "DIFFICULTY",
+            help = "Keep serving the current token instead of solving a harder challenge, until it is close to expiry."
+        )]
+        max_renewal_difficulty: Option<u64>,
+        #[arg(
+            long,
+            value_name = "MARGIN",
+            value_parser = commands::proxy::parse_renewal_margin,
+            default_value = "20%",
+            help = "Refresh this long before the token expires: a duration such as `30s`, or a share of its lifetime such as `20%`."
+        )]
+        renewal_margin: RenewalMargin,
+        #[command(flatten)]
+        solver: SolverArgs,
+        #[arg(
+            short,
+            long,
+            help = "Enable verbose output (overrides config file setting)."
+        )]
+        verbose: bool,
+        #[arg(
+            short,
+            long,
+            help = "Path to the configuration file."
+        )]
+        config_path: Option<String>,
+    },
+
+    /// Validates several endpoints: fetches every challenge at once, then solves them one at a time.
+    Batch {
+        /// The protected endpoint URLs to validate.
+        endpoints: Vec<String>,
+
+        #[arg(
+            long = "endpoints-file",
+            value_name = "PATH",
+            help = "Also read endpoints from this file, one per line; blank lines and `#` comments are skipped."
+        )]
+        endpoints_file: Option<PathBuf>,
+        #[arg(
+            long,
+            help = "Also read endpoints from stdin, one per line; invalid lines are reported with their line number."
+        )]
+        stdin: bool,
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = Schedule::Fifo,
+            help = "Solve order: input order, fewest recommended attempts first, or soonest expiry first."
+        )]
+        schedule: Schedule,
+        #[arg(
+            long = "failures-out",
+            value_name = "PATH",
+            help = "Write every failure (endpoint, stage, error kind, message, time) to this JSON file."
+        )]
+        failures_out: Option<PathBuf>,
+        #[command(flatten)]
+        solver: SolverArgs,
+        #[arg(
+            short,
+            long,
+            help = "Enable verbose output (overrides config file setting)."
+        )]
+        verbose: bool,
+        #[arg(
+            short,
+            long,
+            help = "Path to the configuration file."
+        )]
+        config_path: Option<String>,
+    },
+
+    /// Lists recorded fetch, solve and validate runs, newest first.
+    History {
+        #[command(subcommand)]
+        action: Option<HistoryAction>,
+
+        #[arg(
+            short = 'n',
+            long,
+            default_value_t = 20,
+            help = "Show at most this many runs."
+        )]
+        limit: usize,
+        #[arg(
+            long,
+            global = true,
+            value_name = "FILTER",
+            help = "Only include runs whose endpoint contains FILTER (case-insensitive)."
+        )]
+        endpoint: Option<String>,
+        #[arg(
+            long,
+            global = true,
+            help = "Print JSON instead of a table."
+        )]
+        json: bool,
+    },
+
+    /// Measures the local hash rate at several thread counts.
+    Benchmark {
+        #[command(subcommand)]
+        action: Option<BenchmarkAction>,
+
+        #[arg(
+            long,
+            value_delimiter = ',',
+            value_name = "COUNTS",
+            help = "Comma-separated thread counts to measure; defaults to powers of two up to the core count."
+        )]
+        threads: Vec<usize>,
+        #[arg(
+            long,
+            default_value = "2s",
+            value_parser = display::parse_duration,
+            help = "How long to hash at each thread count."
+        )]
+        duration: Duration,
+        #[arg(
+            long,
+            value_name = "PATH",
+            help = "Save the results, machine details and versions to a JSON file for `benchmark compare`."
+        )]
+        save: Option<PathBuf>,
+        #[arg(
+            long,
+            help = "Print the results as JSON instead of a table."
+        )]
+        json: bool,
+    },
+
+    /// Converts challenges between the base64url header the API sends and JSON, or generates one offline.
+    Challenge {
+        #[command(subcommand)]
+        action: ChallengeAction,
+    },
+
+    /// Converts solutions between the base64url header the API expects and JSON.
+    Solution {
+        #[command(subcommand)]
+        action: ConvertAction,
+    },
+
+    /// Writes a default config file or shows the one in effect.
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Creates a config file step by step: API URL, connectivity check, thread count, verbosity and color.
+    Setup {
+        #[arg(
+            long,
+            help = "Take every suggestion without asking, for provisioning scripts."
+        )]
+        defaults: bool,
+        #[arg(
+            long,
+            value_name = "URL",
+            help = "Suggest this API base URL instead of the default."
+        )]
+        api_base_url: Option<String>,
+    },
+
+    /// Checks that the API answers and, with `--proxy`, how the proxy from
+    /// HTTPS_PROXY or ALL_PROXY tunnels to it: the CONNECT, and whether the
+    /// certificate at the far end is the server's or the proxy's.
+    Doctor {
+        #[arg(
+            long,
+            help = "Also check the proxy, for both the API and the `[tunnel] canary`."
+        )]
+        proxy: bool,
+        #[arg(
+            short,
+            long,
+            help = "Enable verbose output (overrides config file setting)."
+        )]
+        verbose: bool,
+        #[arg(
+            short,
+            long,
+            help = "Path to the configuration file."
+        )]
+        config_path: Option<String>,
+    },
+
+    /// Prints the usage examples shown under each command's `--help`.
+    Examples {
+        /// Only show the examples for this command, e.g. `validate`.
+        command: Option<String>,
+    },
+}
+
+impl Commands {
+    /// The `-c` and `-v` given after the subcommand, for commands that take them.
+    fn scoped_flags(&self) -> Option<(Option<&String>, bool)> {
+        match self {
+            Commands::Fetch { config_path, verbose, .. }
+            | Commands::Solve { config_path, verbose, .. }
+            | Commands::Run { config_path, verbose, .. }
+            | Commands::Validate { config_path, verbose, .. }
+            | Commands::Get { config_path, verbose, .. }
+            | Commands::Survey { config_path, verbose, .. }
+            | Commands::Batch { config_path, verbose, .. }
+            | Commands::Stream { config_path, verbose, .. }
+            | Commands::Spool { config_path, verbose, .. }
+            | Commands::Serve { config_path, verbose, .. }
+            | Commands::Proxy { config_path, verbose, .. }
+            | Commands::Doctor { config_path, verbose, .. } => Some((config_path.as_ref(), *verbose)),
+            _                                               => None,
+        }
+    }
+
+    /// The solver options, for commands that solve.
+    fn solver_args(&self) -> Option<SolverArgs> {
+        match self {
+            Commands::Solve { solver, .. }
+            | Commands::Run { solver, .. }
+            | Commands::Validate { solver, .. }
+            | Commands::Get { solver, .. }
+            | Commands::Batch { solver, .. }
+            | Commands::Stream { solver, .. }
+            | Commands::Spool { solver, .. }
+            | Commands::Serve { solver, .. }
+            | Commands::Proxy { solver, .. }
+            | Commands::Queue { action: QueueAction::Solve { solver, .. } } => Some(*solver),
+            _                                                               => None,
+        }
+    }
+
+    /// Whether the command asks the API for challenges, so a verbose
+    /// run diagnoses the proxy tunnel first.
+    fn fetches_challenges(&self) -> bool {
+        match self {
+            Commands::Solve { dry_run, .. } | Commands::Validate { dry_run, .. } => *dry_run != Some(DryRun::Offline),
+            Commands::Fetch { .. }
+            | Commands::Run { .. }
+            | Commands::Get { .. }
+            | Commands::Survey { .. }
+            | Commands::Batch { .. }
+            | Commands::Stream { .. }
+            | Commands::Serve { .. }
+            | Commands::Proxy { .. } => true,
+            _                        => false,
+        }
+    }
+
+    /// The endpoint argument of a fetch, solve, run, validate or get.
+    fn endpoint_mut(&mut self) -> Option<&mut Option<String>> {
+        match self {
+            Commands::Fetch { endpoint, .. }
+            | Commands::Solve { endpoint, .. }
+            | Commands::Run { endpoint, .. }
+            | Commands::Get { endpoint, .. }
+            | Commands::Validate { endpoint, stdin: false, .. } => Some(endpoint),
+            _                                                   => None,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum BenchmarkAction {
+    /// Compares two saved benchmark files; fails if any thread count got more than 5% slower.
+    Compare {
+        /// The baseline file.
+        old: PathBuf,
+        /// The file to check against the baseline.
+        new: PathBuf,
+    },
+    /// Simulates `--work-split stride` and `chunked` with one slow thread and compares how long each takes.
+    Split {
+        #[arg(
+            long,
+            default_value_t = 4,
+            help = "How many times slower the slow thread runs."
+        )]
+        slowdown: u32,
+        #[arg(
+            long,
+            default_value_t = 64,
+            help = "Nonce ranges to cover; uses the first `--threads` value as the thread count."
+        )]
+        ranges: u64,
+    },
+}
+
+/// Where `challenge` and `solution` read their input.
+#[derive(Args)]
+pub struct ConvertInput {
+    /// The header or JSON itself; read from stdin when omitted or `-`.
+    #[arg(conflicts_with = "file")]
+    pub value:      Option<String>,
+    #[arg(
+        short,
+        long,
+        value_name = "PATH",
+        help = "Read the header or JSON from this file."
+    )]
+    pub file:       Option<PathBuf>,
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        conflicts_with = "file",
+        help = "Decompress stdin; files are decompressed by their `.gz` or `.zst` extension."
+    )]
+    pub decompress: Option<Compression>,
+}
+
+impl ConvertInput {
+    fn source(&self) -> commands::interchange::Input<'_> {
+        use commands::interchange::Input;
+
+        match (&self.value, &self.file) {
+            (_, Some(path))                     => Input::File(path),
+            (Some(value), None) if value != "-" => Input::Argument(value),
+            _                                   => Input::Stdin(self.decompress),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum ConvertAction {
+    /// Prints a base64url header as pretty JSON.
+    Decode {
+        #[command(flatten)]
+        input: ConvertInput,
+    },
+    /// Prints JSON as a base64url header.
+    Encode {
+        #[command(flatten)]
+        input: ConvertInput,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ChallengeAction {
+    #[command(flatten)]
+    Convert(ConvertAction),
+    /// Prints a freshly signed challenge as JSON, without contacting the API.
+    Generate {
+        #[arg(
+            long,
+            help = "The challenge difficulty; above 100,000,000 needs `--force`."
+        )]
+        difficulty: u64,
+        #[arg(
+            long,
+            value_name = "DURATION",
+            default_value = "120s",
+            value_parser = display::parse_duration,
+            help = "How long the challenge stays valid, e.g. `30s` or `5m`."
+        )]
+        expires_in: Duration,
+        #[arg(
+            long,
+            default_value = "demo",
+            help = "The website ID to put in the challenge."
+        )]
+        website_id: String,
+        #[arg(
+            short,
+            long,
+            value_name = "PATH",
+            help = "Write the JSON to this file instead of stdout, compressed if it ends in `.gz` or `.zst`."
+        )]
+        out: Option<PathBuf>,
+        #[arg(
+            long,
+            value_enum,
+            value_name = "FORMAT",
+            conflicts_with = "out",
+            help = "Compress the JSON written to stdout."
+        )]
+        compress: Option<Compression>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CacheAction {
+    /// Deletes every cached response.
+    Purge,
+}
+
+#[derive(Subcommand)]
+pub enum QueueAction {
+    /// Adds a challenge to the queue as pending.
+    Add {
+        /// The challenge as JSON or a base64url header, decompressed if it ends in `.gz` or `.zst`.
+        file: PathBuf,
+    },
+    /// Solves every pending challenge without contacting the API, skipping expired ones.
+    Solve {
+        #[arg(
+            short = 's',
+            long = "single-threaded",
+            help = "Use single-threaded solving instead of the default multithreaded approach."
+        )]
+        single_threaded: bool,
+        #[command(flatten)]
+        solver: SolverArgs,
+    },
+    /// Submits every solved challenge that hasn't expired, keeping the tokens.
+    /// Rejected solutions move to `failed/` in the queue directory.
+    Submit {
+        #[arg(
+            long,
+            value_name = "N",
+            default_value_t = commands::queue::DEFAULT_SUBMIT_CONCURRENCY,
+            help = "Submit this many solutions at once to each API, over one pooled client."
+        )]
+        concurrency: usize,
+    },
+    /// Lists every queued challenge with its state, age and expiry.
+    List {
+        #[arg(
+            long,
+            help = "Print JSON instead of a table."
+        )]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum StateAction {
+    /// Prints the data and cache directories ironshield keeps state in.
+    Path {
+        #[arg(
+            long,
+            help = "Print JSON instead of text."
+        )]
+        json: bool,
+    },
+    /// Prints the disk the history, queue and HTTP cache each take up.
+    Size {
+        #[arg(
+            long,
+            help = "Print JSON instead of a table."
+        )]
+        json: bool,
+    },
+    /// Removes finished queue items, stale cached responses, history past
+    /// `[history] retention` and leftovers of interrupted writes.
+    /// Files it doesn't recognize are left alone.
+    Gc {
+        #[arg(
+            long = "older-than",
+            value_name = "AGE",
+            default_value = "30d",
+            value_parser = display::parse_duration,
+            help = "Only remove queue items and cached responses at least this old, e.g. 7d or 12h."
+        )]
+        older_than: Duration,
+        #[arg(
+            long = "dry-run",
+            help = "Print what would be removed without removing anything."
+        )]
+        dry_run: bool,
+        #[arg(
+            long,
+            help = "Print a JSON array of removals instead of text."
+        )]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Writes a config file with every setting at its default.
+    Init {
+        /// The file to write; `ironshield.toml` (or `.json`/`.yaml`) when omitted.
+        path: Option<PathBuf>,
+        #[arg(
+            long,
+            value_enum,
+            help = "File format; taken from the path's extension when omitted."
+        )]
+        format: Option<ConfigFormat>,
+    },
+    /// Changes one setting in the config file, printing what changed; invalid values are refused.
+    Set {
+        /// The setting: `api_base_url`, `num_threads`, `timeout`, `user_agent` or `verbose`.
+        key:   String,
+        /// Its new value, e.g. `4` or `auto` for `num_threads`, `30s` for `timeout`.
+        value: String,
+    },
+    /// Prints which file and format were loaded, then the settings in effect.
+    Show {
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Only print the settings that differ in FILE, as `field: this → other`."
+        )]
+        compare: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum HistoryAction {
+    /// Prints the run count, success rate, p50/p95 solve time and average hash rate.
+    Stats,
+    /// Lists the recent endpoints that `@1`..`@9` stand for.
+    Endpoints,
+    /// Compares runs in a time window against the runs before it.
+    Compare {
+        #[arg(
+            long,
+            value_name = "DATE",
+            value_parser = history::parse_date,
+            help = "Start of the window to compare (YYYY-MM-DD or RFC 3339); earlier runs are the baseline."
+        )]
+        from: DateTime<Utc>,
+        #[arg(
+            long,
+            value_name = "DATE",
+            value_parser = history::parse_date,
+            help = "End of the window to compare (exclusive); defaults to now."
+        )]
+        to: Option<DateTime<Utc>>,
+        #[arg(
+            long,
+            value_name = "RUNS",
+            help = "Use only the last RUNS runs before the window as the baseline."
+        )]
+        baseline: Option<usize>,
+    },
+}
+
+impl CliArgs {
+    pub fn parse() -> Result<Self, ErrorHandler> {
+        let matches = Self::command_with_examples().get_matches();
+        let args = Self::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+        match (&args.command, args.tui) {
+            (None, false) => {
+                Self::command()
+                    .error(ErrorKind::MissingSubcommand, "a subcommand is required unless `--tui` is given")
+                    .exit()
+            },
+            (Some(_), true) => {
+                Self::command()
+                    .error(ErrorKind::ArgumentConflict, "`--tui` cannot be combined with a subcommand")
+                    .exit()
+            },
+            (Some(command), false) if args.metrics_listen.is_some() && !matches!(command, Commands::Proxy { .. }) => {
+                Self::command()
+                    .error(ErrorKind::ArgumentConflict, "`--metrics-listen` only works with `--tui` or the `proxy` command")
+                    .exit()
+            },
+            (Some(Commands::Fetch { endpoint: None, .. }
+                | Commands::Solve { endpoint: None, .. }
+                | Commands::Run { endpoint: None, .. }
+                | Commands::Get { endpoint: None, .. }
+                | Commands::Validate { endpoint: None, stdin: false, .. }), false) if !prompt::is_interactive() => {
+                Self::command()
+                    .error(ErrorKind::MissingRequiredArgument, "the <ENDPOINT> argument is required")
+                    .exit()
+            },
+            _ => Ok(args),
+        }
+    }
+
+    /// The argument definitions with each command's examples at the end
+    /// of its `--help`.
+    fn command_with_examples() -> clap::Command {
+        examples::commands().into_iter().fold(Self::command(), |command, name| {
+            let help = examples::render(name).expect("every listed command has examples");
+            command.mut_subcommand(name, |subcommand| subcommand.after_help(help))
+        })
+    }
+}
+
+/// Which side of the subcommand a flag was given on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    /// e.g. `ironshield solve -c a.toml`.
+    Subcommand,
+    /// e.g. `ironshield -c a.toml solve`.
+    Global,
+}
+
+/// A setting given on the command line, and where.
+#[derive(Debug, Clone, PartialEq)]
+struct Setting<T> {
+    value:  T,
+    source: Source,
+}
+
+impl<T> Setting<T> {
+    fn describe_source(&self) -> &'static str {
+        match self.source {
+            Source::Subcommand => "given after the subcommand",
+            Source::Global     => "given before the subcommand",
+        }
+    }
+}
+
+/// The settings that can be given in more than one place, each
+/// resolved by the same rule: a flag after the subcommand beats one
+/// before it, and either beats the config file. `None` leaves the
+/// config file's value alone.
+#[derive(Debug, Default, PartialEq)]
+struct EffectiveArgs {
+    config_path: Option<Setting<String>>,
+    /// Only ever `true`; without `-v` the config file decides.
+    verbose:     Option<Setting<bool>>,
+    threads:     Option<Setting<usize>>,
+    timeout:     Option<Setting<Duration>>,
+    /// One warning per setting given differently on both sides.
+    conflicts:   Vec<String>,
+}
+
+impl EffectiveArgs {
+    /// Logs where each setting from the command line came from.
+    fn log_sources(&self, verbose: bool) {
+        let lines = [
+            self.config_path.as_ref().map(|path| format!("config file {} ({})", path.value, path.describe_source())),
+            self.verbose.as_ref().map(|flag| format!("verbose output ({})", flag.describe_source())),
+            self.threads.as_ref().map(|threads| format!("{} solver threads ({})", threads.value, threads.describe_source())),
+            self.timeout.as_ref().map(|timeout| {
+                format!("a {} timeout ({})", display::format_duration(timeout.value), timeout.describe_source())
+            }),
+        ];
+        for line in lines.into_iter().flatten() {
+            logging::log_event(verbose, logging::LogCategory::Info, format_args!("Using {line}"));
+        }
+    }
+}
+
+/// Merges the flags given before and after the subcommand.
+fn resolve_args(args: &CliArgs) -> Result<EffectiveArgs, clap::Error> {
+    let mut conflicts = Vec::new();
+    let (config_path, verbose) = args.command.as_ref().and_then(Commands::scoped_flags).unwrap_or((None, false));
+    let threads = args.command.as_ref().and_then(Commands::solver_args).and_then(|solver| solver.threads);
+
+    let effective = EffectiveArgs {
+        config_path: pick("-c/--config-path", config_path.cloned(), args.config_path.clone(), &mut conflicts),
+        verbose:     pick("-v/--verbose", verbose.then_some(true), args.verbose.then_some(true), &mut conflicts),
+        threads:     pick("--threads", threads, None, &mut conflicts),
+        timeout:     pick("--timeout", None, args.timeout, &mut conflicts),
+        conflicts,
+    };
+
+    // Clap's `conflicts_with` only sees the top-level `-v`; a subcommand's
+    // own `-v` is merged in above.
+    if args.quiet && effective.verbose.is_some() {
+        return Err(CliArgs::command().error(
+            ErrorKind::ArgumentConflict,
+            "the argument '--quiet' cannot be used with '--verbose'",
+        ));
+    }
+    Ok(effective)
+}
+
+/// The value of one setting, preferring the subcommand's.
+///
+/// # Arguments
+/// * `flag`:       The flag's name, for the warning.
+/// * `subcommand`: Its value after the subcommand.
+/// * `global`:     Its value before the subcommand.
+/// * `conflicts`:  Gets a warning if both are given and differ.
+fn pick<T: PartialEq + std::fmt::Debug>(
+    flag:       &str,
+    subcommand: Option<T>,
+    global:     Option<T>,
+    conflicts:  &mut Vec<String>,
+) -> Option<Setting<T>> {
+    match (subcommand, global) {
+        (Some(value), Some(global)) => {
+            if value != global {
+                conflicts.push(format!(
+                    "{flag} is {global:?} before the subcommand and {value:?} after it; using {value:?}",
+                ));
+            }
+            Some(Setting { value, source: Source::Subcommand })
+        }
+        (Some(value), None) => Some(Setting { value, source: Source::Subcommand }),
+        (None, Some(value)) => Some(Setting { value, source: Source::Global }),
+        (None, None)        => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(argv: &[&str]) -> EffectiveArgs {
+        try_resolve(argv).unwrap()
+    }
+
+    fn try_resolve(argv: &[&str]) -> Result<EffectiveArgs, clap::Error> {
+        resolve_args(&CliArgs::try_parse_from(std::iter::once("ironshield").chain(argv.iter().copied())).unwrap())
+    }
+
+    #[test]
+    fn test_subcommand_flags_win_and_conflicts_are_reported() {
+        let effective = resolve(&["-c", "global.toml", "solve", "https://a.example", "-c", "local.toml"]);
+
+        assert_eq!(effective.config_path, Some(Setting { value: "local.toml".to_string(), source: Source::Subcommand }));
+        assert_eq!(effective.conflicts.len(), 1);
+        assert!(effective.conflicts[0].contains("\"global.toml\" before the subcommand"), "{}", effective.conflicts[0]);
+    }
+
+    #[test]
+    fn test_agreeing_or_one_sided_flags_are_not_conflicts() {
+        let effective = resolve(&["-c", "a.toml", "-v", "fetch", "https://a.example", "-c", "a.toml", "-v"]);
+        assert!(effective.conflicts.is_empty());
+        assert_eq!(effective.verbose.map(|verbose| verbose.source), Some(Source::Subcommand));
+
+        let effective = resolve(&["-c", "a.toml", "--timeout", "5s", "history"]);
+        assert_eq!(effective.config_path.map(|path| path.source), Some(Source::Global));
+        assert_eq!(effective.timeout.map(|timeout| timeout.value), Some(Duration::from_secs(5)));
+        assert_eq!(effective.verbose, None);
+        assert!(effective.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_quiet_conflicts_with_a_subcommand_verbose() {
+        let error = try_resolve(&["solve", "https://a.example", "-v", "-q"]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ArgumentConflict);
+        assert!(try_resolve(&["solve", "https://a.example", "-q"]).is_ok());
+    }
+
+    #[test]
+    fn test_threads_come_from_the_solver_flags() {
+        let effective = resolve(&["validate", "https://a.example", "--threads", "3"]);
+        assert_eq!(effective.threads, Some(Setting { value: 3, source: Source::Subcommand }));
+        assert_eq!(resolve(&["history"]).threads, None);
+    }
+
+    #[test]
+    fn test_examples_parse_with_the_real_arguments() {
+        for example in examples::EXAMPLES {
+            let argv = std::iter::once("ironshield").chain(example.args.split(' '));
+            let matches = CliArgs::command().try_get_matches_from(argv)
+                .unwrap_or_else(|e| panic!("`ironshield {}` does not parse: {e}", example.args));
+            assert_eq!(matches.subcommand_name(), Some(example.command), "`ironshield {}`", example.args);
+        }
+    }
+
+    #[test]
+    fn test_every_command_has_examples() {
+        for subcommand in CliArgs::command().get_subcommands() {
+            let name = subcommand.get_name();
+            assert!(examples::render(name).is_some(), "`{name}` has no examples");
+        }
+        // Panics if the table names a command that doesn't exist.
+        CliArgs::command_with_examples().debug_assert();
+    }
+}
@@ -0,0 +1,359 @@
+use color_eyre::eyre::eyre;
+use ironshield::{
+    ClientConfig,
+    IronShieldChallenge,
+    IronShieldChallengeResponse,
+    ProgressTracker,
+};
+use ironshield_types::IronShieldToken;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::api::ChallengeEnvelope;
+use crate::api_version::Negotiated;
+use crate::inject::Injection;
+use crate::metadata::ChallengeMetadata;
+use crate::presolve::{Limits, Refused};
+use crate::solve::{Solver, Strategy, ThreadPlan};
+
+/// How a solve picks its threads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SolveOptions {
+    /// The thread count and priority preset, as `--strategy`.
+    pub strategy:        Strategy,
+    /// An explicit thread count, as `--threads`; wins over the strategy
+    /// and over `num_threads` in the client configuration.
+    pub threads:         Option<usize>,
+    /// Solve on one thread, as `--single-threaded`.
+    pub single_threaded: bool,
+}
+
+/// What a [`CliClient`] is doing, for callers that show progress.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// A challenge arrived.
+    ChallengeFetched {
+        endpoint:   String,
+        difficulty: u64,
+        elapsed:    Duration,
+        /// The API version the answer came with.
+        negotiated: Negotiated,
+    },
+    /// The rate limit holds back a challenge request for `wait`.
+    RateLimited {
+        wait: Duration,
+    },
+    /// A solve got its threads; it may get fewer than it asked for
+    /// while other solves are running.
+    ThreadsGranted {
+        granted:   usize,
+        requested: usize,
+        /// Threads granted to every running solve, this one included.
+        in_use:    usize,
+        total:     usize,
+    },
+    /// Solving started.
+    SolveStarted {
+        strategy:     Strategy,
+        thread_count: usize,
+    },
+    /// A solution was found.
+    Solved {
+        elapsed: Duration,
+    },
+    /// The solution was accepted and a token issued.
+    Validated {
+        elapsed: Duration,
+    },
+}
+
+type EventHandler = Arc<dyn Fn(&Event) + Send + Sync>;
+
+/// Fetches, solves and validates IronShield challenges the way the
+/// `ironshield` binary does, without printing anything. Progress is
+/// reported through [`CliClient::on_event`] and the solve's tracker.
+///
+/// Limits set with [`CliClient::with_limits`] are checked before every
+/// solve, like `--max-difficulty` and `--max-expected-time`. Clones
+/// share the connection pool, the event handler and the solver threads.
+#[derive(Clone)]
+pub struct CliClient {
+    /// Every API call goes out over this, so `--har` sees them all.
+    http:      reqwest::Client,
+    config:    ClientConfig,
+    options:   SolveOptions,
+    limits:    Limits,
+    events:    Option<EventHandler>,
+    solver:    Solver,
+    injection: Arc<Injection>,
+}
+
+impl CliClient {
+    /// Creates a client.
+    ///
+    /// # Arguments
+    /// * `config`: The client configuration.
+    ///
+    /// # Returns
+    /// * `Result<Self>`: An error if the HTTP client can't be built.
+    pub fn new(config: ClientConfig) -> color_eyre::Result<Self> {
+        let http = crate::api::http_client(&config).map_err(|e| eyre!("Failed to initialize client: {e}"))?;
+
+        Ok(Self {
+            http,
+            config,
+            options:   SolveOptions::default(),
+            limits:    Limits::default(),
+            events:    None,
+            solver:    Solver::default(),
+            injection: Arc::new(Injection::default()),
+        })
+    }
+
+    /// Sets the options [`CliClient::validate`] solves with.
+    pub fn with_solve_options(mut self, options: SolveOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Refuses challenges over these limits instead of solving them.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Calls `handler` for every [`Event`].
+    pub fn on_event(mut self, handler: impl Fn(&Event) + Send + Sync + 'static) -> Self {
+        self.events = Some(Arc::new(handler));
+        self
+    }
+
+    /// Solves the way `solver` says, past the strategy and thread count.
+    pub(crate) fn with_solver(mut self, solver: Solver) -> Self {
+        self.solver = solver;
+        self
+    }
+
+    /// Fakes the failures `injection` lists, for the `--inject-*` flags.
+    pub(crate) fn with_injection(mut self, injection: Injection) -> Self {
+        self.injection = Arc::new(injection);
+        self
+    }
+
+    /// Solves on a pool sized for a multithreaded solve, for modes that
+    /// solve repeatedly. One-shot commands don't call this.
+    pub(crate) fn with_pool(mut self) -> Self {
+        let plan = self.thread_plan(true);
+        self.solver = self.solver.with_pool(&plan);
+        self
+    }
+
+    /// Uses `threads` for every later solve, as picked at a retry prompt.
+    pub(crate) fn with_retry_threads(mut self, threads: usize) -> Self {
+        self.solver.retry_threads = Some(threads.max(1));
+        self
+    }
+
+    /// This client with `config` in place of its own, as after a reload.
+    /// Keeps the solver, the injected failures and the event handler.
+    pub(crate) fn reconfigured(&self, config: ClientConfig) -> color_eyre::Result<Self> {
+        let http = crate::api::http_client(&config).map_err(|e| eyre!("Failed to initialize client: {e}"))?;
+
+        Ok(Self { http, config, ..self.clone() })
+    }
+
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    /// The threads a solve will use, from the strategy and thread count
+    /// set with [`CliClient::with_solve_options`].
+    pub(crate) fn thread_plan(&self, use_multithreaded: bool) -> ThreadPlan {
+        let options = SolveOptions { single_threaded: self.options.single_threaded || !use_multithreaded, ..self.options };
+        self.solver.plan(options, &self.config)
+    }
+
+    /// [`CliClient::thread_plan`], with the thread count the server
+    /// suggested next to the challenge unless the count was set
+    /// explicitly or the solve is single-threaded.
+    ///
+    /// # Arguments
+    /// * `use_multithreaded`: Whether to use more than one thread.
+    /// * `metadata`:          What the API sent next to the challenge, if anything.
+    pub(crate) fn suggested_thread_plan(&self, use_multithreaded: bool, metadata: Option<&ChallengeMetadata>) -> ThreadPlan {
+        let plan = self.thread_plan(use_multithreaded);
+        if !use_multithreaded {
+            return plan;
+        }
+        match metadata.and_then(|metadata| metadata.thread_hint(plan.overridden.then_some(plan.thread_count))) {
+            Some(thread_count) => ThreadPlan { thread_count, ..plan },
+            None               => plan,
+        }
+    }
+
+    /// Fetches a challenge for `endpoint`, with the metadata and API
+    /// version the API sent next to it. Waits for the rate limit first.
+    pub async fn fetch(&self, endpoint: &str) -> color_eyre::Result<ChallengeEnvelope> {
+        self.wait_for_rate_limit().await;
+        let start_time = Instant::now();
+        let envelope = self.injection.fetch_challenge(&self.http, &self.config, endpoint).await?;
+        crate::metrics::record_fetch(start_time.elapsed());
+
+        self.emit(Event::ChallengeFetched {
+            endpoint:   endpoint.to_string(),
+            difficulty: envelope.challenge.recommended_attempts / 2,
+            elapsed:    start_time.elapsed(),
+            negotiated: envelope.negotiated,
+        });
+        Ok(envelope)
+    }
+
+    /// The API version `/request` answers with for `endpoint`, however
+    /// the challenge next to it looks.
+    pub async fn api_version(&self, endpoint: &str) -> color_eyre::Result<Negotiated> {
+        self.wait_for_rate_limit().await;
+        crate::api::api_version(&self.http, &self.config, endpoint).await
+    }
+
+    /// Solves a challenge.
+    ///
+    /// # Arguments
+    /// * `challenge`: The challenge to solve.
+    /// * `options`:   How many threads to use.
+    /// * `tracker`:   Receives per-thread progress.
+    ///
+    /// # Returns
+    /// * `Result<IronShieldChallengeResponse>`: The solution, or an error
+    ///   wrapping [`Refused`] if the challenge is over the limits.
+    pub async fn solve(
+        &self,
+        challenge: IronShieldChallenge,
+        options:   SolveOptions,
+        tracker:   impl ProgressTracker + 'static,
+    ) -> color_eyre::Result<IronShieldChallengeResponse> {
+        self.solve_with(challenge, options, Some(Arc::new(tracker))).await
+    }
+
+    /// Fetches, solves and submits, returning the token.
+    pub async fn validate(&self, endpoint: &str) -> color_eyre::Result<IronShieldToken> {
+        let challenge = self.fetch(endpoint).await?.challenge;
+        let solution = self.solve_with(challenge, self.options, None).await?;
+
+        let start_time = Instant::now();
+        let token = self.submit(&solution).await?;
+        crate::metrics::record_submit(start_time.elapsed(), token.valid_for);
+
+        self.emit(Event::Validated { elapsed: start_time.elapsed() });
+        Ok(token)
+    }
+
+    /// Submits a solution for a token, over the same connection pool
+    /// and recording as the fetch, or over the prewarmed connection
+    /// under `--prewarm`.
+    pub async fn submit(&self, solution: &IronShieldChallengeResponse) -> color_eyre::Result<IronShieldToken> {
+        self.injection.check_submit()?;
+        if let Some(result) = crate::prewarm::submit(solution).await {
+            return result;
+        }
+        crate::api::submit(&self.http, self.config.api_base_url.trim_end_matches('/'), solution).await
+    }
+
+    /// Submits a solution to the API at `base_url` rather than the
+    /// configured one, as `queue submit` does for challenges queued
+    /// from another API. An answer without a token is a [`NoToken`].
+    ///
+    /// [`NoToken`]: crate::api::NoToken
+    pub(crate) async fn submit_to(&self, base_url: &str, solution: &IronShieldChallengeResponse) -> color_eyre::Result<IronShieldToken> {
+        self.injection.check_submit()?;
+        crate::api::submit(&self.http, base_url.trim_end_matches('/'), solution).await
+    }
+
+    /// Solves with `plan`, as the CLI does, without the limits or the
+    /// solve events. Checks the solution with the core.
+    pub(crate) async fn solve_with_plan(
+        &self,
+        challenge: IronShieldChallenge,
+        plan:      &ThreadPlan,
+        tracker:   Option<Arc<dyn ProgressTracker>>,
+    ) -> color_eyre::Result<IronShieldChallengeResponse> {
+        let challenge = Arc::new(challenge);
+        let mut solution = self.search(Arc::clone(&challenge), plan, tracker).await?;
+        self.check(&challenge, &mut solution)?;
+        Ok(solution)
+    }
+
+    /// [`CliClient::solve_with_plan`] with [`CliClient::thread_plan`].
+    pub(crate) async fn solve_threaded(
+        &self,
+        challenge:         IronShieldChallenge,
+        use_multithreaded: bool,
+        tracker:           Option<Arc<dyn ProgressTracker>>,
+    ) -> color_eyre::Result<IronShieldChallengeResponse> {
+        self.solve_with_plan(challenge, &self.thread_plan(use_multithreaded), tracker).await
+    }
+
+    /// [`CliClient::solve_with_plan`] without the check, for callers
+    /// that time it separately; pass the solution to
+    /// [`CliClient::check`] before using it. The plan's threads come out
+    /// of the solver's [`ThreadBudget`], so the solve may get fewer
+    /// while others are running.
+    ///
+    /// [`ThreadBudget`]: crate::solve::ThreadBudget
+    pub(crate) async fn search(
+        &self,
+        challenge: Arc<IronShieldChallenge>,
+        plan:      &ThreadPlan,
+        tracker:   Option<Arc<dyn ProgressTracker>>,
+    ) -> color_eyre::Result<IronShieldChallengeResponse> {
+        self.injection.solve_delay().await;
+        let budget = self.solver.budget();
+        let grant = budget.grant(plan.thread_count).await;
+        self.emit(Event::ThreadsGranted {
+            granted:   grant.threads(),
+            requested: grant.wanted(),
+            in_use:    budget.in_use(),
+            total:     budget.total(),
+        });
+        self.solver.search(challenge, &grant.apply(plan), tracker).await
+    }
+
+    /// Checks a solution from [`CliClient::search`] with the core, so a
+    /// bad one fails here instead of burning the challenge.
+    pub(crate) fn check(&self, challenge: &IronShieldChallenge, solution: &mut IronShieldChallengeResponse) -> color_eyre::Result<()> {
+        self.injection.corrupt_solution(challenge, solution);
+        self.solver.check(challenge, solution)?;
+        Ok(())
+    }
+
+    async fn solve_with(
+        &self,
+        challenge: IronShieldChallenge,
+        options:   SolveOptions,
+        tracker:   Option<Arc<dyn ProgressTracker>>,
+    ) -> color_eyre::Result<IronShieldChallengeResponse> {
+        let plan = self.solver.plan(options, &self.config);
+        crate::presolve::check_limits(&self.limits, &challenge, plan.thread_count).await?;
+
+        self.emit(Event::SolveStarted { strategy: plan.strategy, thread_count: plan.thread_count });
+        let start_time = Instant::now();
+        let solution = self.solve_with_plan(challenge, &plan, tracker).await?;
+        self.emit(Event::Solved { elapsed: start_time.elapsed() });
+        Ok(solution)
+    }
+
+    /// Waits out the process-wide rate limit before a challenge request.
+    async fn wait_for_rate_limit(&self) {
+        let wait = crate::rate_limit::reserve();
+        if !wait.is_zero() {
+            self.emit(Event::RateLimited { wait });
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    fn emit(&self, event: Event) {
+        if let Some(handler) = &self.events {
+            handler(&event);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use chrono::{DateTime, Local};
+use color_eyre::eyre::eyre;
+use ironshield::ClientConfig;
+use tokio::sync::mpsc;
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::batch::{self, BatchUpdate, ListedEndpoint, Stage};
+use crate::client::CliClient;
+use crate::failures::{FailureReport, SUMMARY_LIMIT};
+use crate::schedule::Schedule;
+
+/// Handles `batch`: fetches a challenge for every endpoint at once, then
+/// solves and submits them one at a time in the order `schedule` picks.
+///
+/// Prints one result line per endpoint, in input order, with its place
+/// in the solve order. Failures are then summarized on stderr by error
+/// kind. Fails if any endpoint failed.
+///
+/// # Arguments
+/// * `client`:         The API client.
+/// * `config`:         The client configuration.
+/// * `endpoints`:      The endpoints to validate.
+/// * `endpoints_file`: A file listing more endpoints, one per line.
+/// * `stdin`:          Also read endpoints from stdin. The whole list is
+///                     read first, since the schedule needs every challenge;
+///                     `validate --stdin` streams instead.
+/// * `schedule`:       The solve order.
+/// * `failures_out`:   Where to write every failure as JSON.
+pub async fn handle_batch(
+    client:         CliClient,
+    config:         &ClientConfig,
+    mut endpoints:  Vec<String>,
+    endpoints_file: Option<&Path>,
+    stdin:          bool,
+    schedule:       Schedule,
+    failures_out:   Option<&Path>,
+) -> color_eyre::Result<()> {
+    if let Some(path) = endpoints_file {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| eyre!("Cannot read endpoints from '{}': {e}", path.display()))?;
+        endpoints.extend(batch::parse_endpoints(&contents));
+    }
+    // Invalid stdin lines are reported up front and count as failures.
+    let mut invalid = 0;
+    let mut report = FailureReport::default();
+    if stdin {
+        let mut listed = batch::read_endpoint_list(std::io::BufReader::new(std::io::stdin()), 64);
+        while let Some(entry) = listed.recv().await {
+            match entry.map_err(|e| eyre!("Cannot read endpoints from stdin: {e}"))? {
+                ListedEndpoint::Valid { endpoint, .. }        => endpoints.push(endpoint),
+                ListedEndpoint::Invalid { line, text, error } => {
+                    invalid += 1;
+                    report.add(&text, "listed", "invalid", &format!("stdin line {line}: {error}"));
+                    println!("{:>4}  {text}  invalid (stdin line {line}): {error}", "-");
+                }
+            }
+        }
+    }
+    if endpoints.is_empty() && invalid == 0 {
+        return Err(eyre!("No endpoints given"));
+    }
+
+    let jobs: Vec<(usize, String)> = endpoints.iter().cloned().enumerate().collect();
+    let (updates_tx, mut updates_rx) = mpsc::unbounded_channel();
+    let run = batch::run_scheduled(Arc::new(client.with_pool()), jobs, schedule, updates_tx, config.verbose);
+
+    let mut positions: HashMap<usize, usize> = HashMap::new();
+    let mut outcomes: HashMap<usize, Stage> = HashMap::new();
+    let collect = async {
+        while let Some(update) = updates_rx.recv().await {
+            let endpoint = &endpoints[update.id];
+            report.observe(&update, endpoint);
+            let BatchUpdate { id, stage } = update;
+            match stage {
+                Stage::Scheduled { position } => {
+                    positions.insert(id, position);
+                }
+                Stage::Solving { attempts: 0, .. } => crate::status_println!("Solving {endpoint}..."),
+                Stage::Done { .. } | Stage::Failed { .. } => {
+                    outcomes.insert(id, stage);
+                }
+                _ => {}
+            }
+        }
+    };
+    tokio::join!(run, collect);
+
+    let mut failures = invalid;
+    for (id, endpoint) in endpoints.iter().enumerate() {
+        let position = positions.get(&id).map(|p| format!("#{p}")).unwrap_or_else(|| "-".to_string());
+        let result = match outcomes.get(&id) {
+            Some(Stage::Done { valid_for, .. }) => match DateTime::from_timestamp_millis(*valid_for) {
+                Some(valid_until) => format!("ok, valid until {}", valid_until.with_timezone(&Local).format("%H:%M:%S")),
+                None              => "ok".to_string(),
+            },
+            Some(Stage::Failed { message, .. }) => {
+                failures += 1;
+                format!("failed: {message}")
+            }
+            _ => {
+                failures += 1;
+                report.add(endpoint, Stage::Pending.name(), "other", "not run");
+                "not run".to_string()
+            }
+        };
+        println!("{position:>4}  {endpoint}  {result}");
+    }
+
+    finish_report(&report, endpoints.len() + invalid, failures_out)?;
+    crate::logging::flush();
+    if failures > 0 {
+        return Err(eyre!("{failures} of {} endpoints failed", endpoints.len() + invalid));
+    }
+    Ok(())
+}
+
+/// Prints the failure summary on stderr, if anything failed, and
+/// writes the full list to `failures_out`.
+pub fn finish_report(report: &FailureReport, total: usize, failures_out: Option<&Path>) -> color_eyre::Result<()> {
+    if !report.failures().is_empty() {
+        crate::status_println!("{}", report.summary(total, SUMMARY_LIMIT));
+    }
+    if let Some(path) = failures_out {
+        report.write_json(path)?;
+    }
+    Ok(())
+}
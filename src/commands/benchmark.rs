@@ -0,0 +1,143 @@
+use color_eyre::eyre::eyre;
+use crossterm::style::Stylize;
+
+use std::path::Path;
+use std::time::Duration;
+
+use crate::benchmark::{self, BenchmarkFile, ThreadResult, REGRESSION_PERCENT};
+use crate::display::{format_duration, format_hash_rate};
+use crate::solve::WorkSplit;
+use crate::tuning::SolverOptions;
+
+/// How long one simulated range takes on a full-speed thread in `benchmark split`.
+const SIMULATED_RANGE_TIME: Duration = Duration::from_millis(5);
+
+/// Handles `benchmark`: measures the hash rate at each thread count.
+///
+/// # Arguments
+/// * `threads`:  Thread counts to try; empty means powers of two up to the core count.
+/// * `duration`: How long to hash at each thread count.
+/// * `save`:     Write the results as a benchmark file here.
+/// * `json`:     Print the benchmark file instead of a table.
+/// * `solver`:   The `[solver]` tuning options, recorded in the file.
+pub async fn handle_benchmark(
+    threads:  Vec<usize>,
+    duration: Duration,
+    save:     Option<&Path>,
+    json:     bool,
+    solver:   SolverOptions,
+) -> color_eyre::Result<()> {
+    let threads = if threads.is_empty() { benchmark::default_thread_counts(num_cpus::get()) } else { threads };
+    crate::build_profile::warn();
+
+    let mut results = Vec::new();
+    for count in threads {
+        crate::status_println!("Hashing on {count} thread(s) for {}...", format_duration(duration));
+        let hash_rate = tokio::task::spawn_blocking(move || benchmark::measure(count, duration)).await?;
+        results.push(ThreadResult { threads: count, hash_rate });
+    }
+    let file = BenchmarkFile::new(results, solver);
+
+    if let Some(path) = save {
+        std::fs::write(path, serde_json::to_string_pretty(&file)?)
+            .map_err(|e| eyre!("Cannot write benchmark results to '{}': {e}", path.display()))?;
+        crate::status_println!("Saved results to {}", path.display());
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&file)?);
+        return Ok(());
+    }
+
+    println!("{}", file.machine.describe());
+    println!("Solver options: {}", file.solver.describe());
+    println!("{:>7}  {:>12}  {:>12}", "Threads", "Hash rate", "Per thread");
+    for result in &file.results {
+        println!(
+            "{:>7}  {:>12}  {:>12}",
+            result.threads,
+            format_hash_rate(result.hash_rate),
+            format_hash_rate(result.hash_rate / result.threads.max(1) as u64),
+        );
+    }
+    Ok(())
+}
+
+/// Handles `benchmark compare`: prints the change per thread count and
+/// fails if any got more than [`REGRESSION_PERCENT`] slower.
+///
+/// # Arguments
+/// * `old`: The baseline benchmark file.
+/// * `new`: The benchmark file to check against it.
+pub fn handle_compare(old: &Path, new: &Path) -> color_eyre::Result<()> {
+    let (old, new) = (load(old)?, load(new)?);
+
+    if old.machine != new.machine {
+        crate::status_println!(
+            "Note: the files come from different machines ({} vs {}).",
+            old.machine.describe(),
+            new.machine.describe(),
+        );
+    }
+    if old.solver != new.solver {
+        crate::status_println!(
+            "Note: the files use different solver options ({} vs {}).",
+            old.solver.describe(),
+            new.solver.describe(),
+        );
+    }
+    println!("Versions: {} (core {}) -> {} (core {})", old.versions.cli, old.versions.core, new.versions.cli, new.versions.core);
+    let debug_builds = old.debug_build || new.debug_build;
+    if debug_builds {
+        crate::status_println!("Note: at least one file comes from a debug build, so slowdowns don't count as regressions.");
+    }
+
+    let deltas = benchmark::compare(&old, &new);
+    let color = crate::logging::colors_enabled();
+    println!("{:>7}  {:>12}  {:>12}  {:>8}", "Threads", "Old", "New", "Change");
+    for delta in &deltas {
+        let missing = crate::display::glyphs().missing;
+        let rate = |rate: Option<u64>| rate.map(format_hash_rate).unwrap_or_else(|| missing.to_string());
+        let change = format!("{:>8}", delta.change().map(|c| format!("{c:+.1}%")).unwrap_or_else(|| missing.to_string()));
+        let change = if delta.is_regression() && color { change.red().to_string() } else { change };
+        println!("{:>7}  {:>12}  {:>12}  {change}", delta.threads, rate(delta.old), rate(delta.new));
+    }
+
+    let regressions = deltas.iter().filter(|delta| delta.is_regression()).count();
+    if regressions > 0 && !debug_builds {
+        return Err(eyre!("{regressions} configuration(s) got more than {REGRESSION_PERCENT}% slower"));
+    }
+    Ok(())
+}
+
+/// Handles `benchmark split`: simulates both work splits with one thread
+/// slowed down and prints how long each takes to cover the same ranges.
+///
+/// # Arguments
+/// * `threads`:  Simulated threads, at least 2.
+/// * `slowdown`: How many times slower the slow thread runs.
+/// * `ranges`:   Nonce ranges to cover.
+pub async fn handle_split(threads: usize, slowdown: u32, ranges: u64) -> color_eyre::Result<()> {
+    let threads = threads.max(2);
+    println!("Covering {ranges} ranges on {threads} threads, one running {slowdown}x slower:");
+    for split in [WorkSplit::Stride, WorkSplit::Chunked] {
+        let elapsed = tokio::task::spawn_blocking(move || {
+            crate::solve::simulate_coverage(split, threads, slowdown, ranges, SIMULATED_RANGE_TIME)
+        }).await?;
+        println!("  {:<8} {:>8}", split.name(), format_duration(elapsed));
+    }
+    Ok(())
+}
+
+fn load(path: &Path) -> color_eyre::Result<BenchmarkFile> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| eyre!("Cannot read '{}': {e}", path.display()))?;
+    let file = BenchmarkFile::parse(&contents).map_err(|e| eyre!("'{}' is {e}", path.display()))?;
+    if file.schema_version > benchmark::SCHEMA_VERSION {
+        crate::status_println!(
+            "Note: '{}' uses a newer format (v{}); fields this version doesn't know are ignored.",
+            path.display(),
+            file.schema_version,
+        );
+    }
+    Ok(file)
+}
@@ -0,0 +1,109 @@
+use color_eyre::eyre::eyre;
+use ironshield::ClientConfig;
+
+use std::path::Path;
+
+use crate::config::{self, ConfigFormat, ConfigManager, Field};
+
+/// Handles `config init`: writes a default config file.
+///
+/// # Arguments
+/// * `path`:       Where to write; `ironshield.<extension>` if `None`.
+/// * `format`:     `--format`; otherwise taken from the path's extension.
+/// * `assume_yes`: Whether `--yes` allows overwriting an existing file.
+pub fn handle_init(path: Option<&Path>, format: Option<ConfigFormat>, assume_yes: bool) -> color_eyre::Result<()> {
+    let format = format.unwrap_or_else(|| {
+        path.map(|path| ConfigFormat::detect(&path.to_string_lossy())).unwrap_or_default()
+    });
+    let path = path
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| format!("ironshield.{}", format.extension()).into());
+
+    if path.exists() && !assume_yes {
+        return Err(eyre!("'{}' already exists; pass --yes to overwrite it", path.display()));
+    }
+    let path_str = path.to_str().ok_or_else(|| eyre!("'{}' is not valid UTF-8", path.display()))?;
+    ConfigManager::create_default_config_as(path_str, format)?;
+    Ok(())
+}
+
+/// Handles `config set`: changes one setting in the config file and
+/// prints each changed field as `old → new`. A value that fails
+/// [`ClientConfig::validate`] is refused and the file left untouched.
+///
+/// # Arguments
+/// * `path`:  The config file to change; created if missing.
+/// * `key`:   The setting, e.g. `num_threads`.
+/// * `value`: Its new value.
+pub fn handle_set(path: &str, key: &str, value: &str) -> color_eyre::Result<()> {
+    let field = Field::from_key(key).ok_or_else(|| {
+        eyre!("Unknown setting '{key}'; expected one of {}", Field::ALL.map(Field::key).join(", "))
+    })?;
+    let current = ConfigManager::load_client_config(path).map_err(|e| eyre!("{e}"))?;
+    let updated = config::apply(&current, field, value)
+        .map_err(|e| eyre!("Refusing to set {key}; '{path}' is unchanged: {e}"))?;
+
+    let changes = config::diff(&current, &updated);
+    if changes.is_empty() {
+        crate::status_println!("Nothing changed; '{path}' is untouched.");
+        return Ok(());
+    }
+    ConfigManager::save_client_config(&updated, path).map_err(|e| eyre!("Cannot save '{path}': {e}"))?;
+    crate::status_println!("Updated {path}:");
+    for change in changes {
+        println!("  {change}");
+    }
+    Ok(())
+}
+
+/// Handles `config show`: names the file and format that were loaded
+/// on stderr, then prints the settings in effect in that format, or
+/// only how they differ from `compare`.
+///
+/// # Arguments
+/// * `path`:    `--config`, if given.
+/// * `config`:  The configuration loaded from it, with overrides applied.
+/// * `compare`: `--compare`: another config file to diff against.
+pub fn handle_show(path: Option<&str>, config: &ClientConfig, compare: Option<&Path>) -> color_eyre::Result<()> {
+    if let Some(other) = compare {
+        return show_compared(path, config, other);
+    }
+    let format = match path {
+        Some(path) if Path::new(path).exists() => {
+            let format = ConfigFormat::detect(path);
+            crate::status_println!("Loaded from {path} ({})", format.name());
+            format
+        }
+        Some(path) => {
+            crate::status_println!("{path} does not exist; showing the defaults");
+            ConfigFormat::detect(path)
+        }
+        None => {
+            crate::status_println!("No config file given; showing the defaults");
+            ConfigFormat::Toml
+        }
+    };
+    print!("{}", format.render(config).map_err(|e| eyre!("Cannot show the config as {}: {e}", format.name()))?);
+    Ok(())
+}
+
+/// Prints one `field: this → other` line per setting that differs.
+fn show_compared(path: Option<&str>, config: &ClientConfig, other: &Path) -> color_eyre::Result<()> {
+    if !other.exists() {
+        return Err(eyre!("'{}' does not exist", other.display()));
+    }
+    let other_str = other.to_str().ok_or_else(|| eyre!("'{}' is not valid UTF-8", other.display()))?;
+    let other_config = ConfigManager::load_client_config(other_str).map_err(|e| eyre!("{e}"))?;
+
+    let changes = config::diff(config, &other_config);
+    let this = path.unwrap_or("the defaults");
+    if changes.is_empty() {
+        crate::status_println!("{this} and {other_str} have the same settings.");
+        return Ok(());
+    }
+    crate::status_println!("From {this} to {other_str}:");
+    for change in changes {
+        println!("  {change}");
+    }
+    Ok(())
+}
@@ -0,0 +1,188 @@
+use color_eyre::eyre::eyre;
+use ironshield::ClientConfig;
+use reqwest::Url;
+
+use std::time::Duration;
+
+use crate::api_version::Negotiated;
+use crate::client::CliClient;
+use crate::tunnel::{self, TunnelConfig, Verdict};
+
+/// How long the direct connection compared against the proxy's has.
+/// Short, since networks that need a proxy usually drop it.
+const DIRECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// The endpoint the version check asks a challenge for; the challenge
+/// itself is thrown away.
+const VERSION_PROBE_ENDPOINT: &str = "https://example.com/";
+
+/// One line of the report.
+struct Check {
+    name:    &'static str,
+    /// What was found, or what went wrong.
+    outcome: Result<String, String>,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, outcome: Ok(detail.into()) }
+    }
+
+    fn failed(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, outcome: Err(detail.into()) }
+    }
+}
+
+/// Handles `doctor`: checks that the API answers with a version this
+/// CLI can read and, with `--proxy`,
+/// how the configured proxy carries requests to the API and to the
+/// `[tunnel] canary`: whether `CONNECT` opens a tunnel, the status
+/// line if not, and whose certificate is at the far end.
+///
+/// # Arguments
+/// * `config`: The client configuration.
+/// * `tunnel`: The `[tunnel]` settings.
+/// * `proxy`:  Whether to check the proxy.
+pub async fn handle_doctor(config: &ClientConfig, tunnel: &TunnelConfig, proxy: bool) -> color_eyre::Result<()> {
+    let mut targets = vec![("API", config.api_base_url.as_str())];
+    if proxy {
+        targets.push(("Canary", tunnel.canary.as_str()));
+    }
+
+    let mut failures = 0;
+    for (label, url) in targets {
+        println!("{label} {}", crate::redact::mask_url_password(url));
+        let checks = match Url::parse(url) {
+            Ok(url) => {
+                let mut checks = check(&url, config, tunnel, proxy).await;
+                if label == "API" {
+                    checks.push(check_version(config).await);
+                }
+                checks
+            }
+            Err(e) => vec![Check::failed("url", format!("not a URL: {e}"))],
+        };
+        for check in checks {
+            let (status, detail) = match &check.outcome {
+                Ok(detail)  => ("ok", detail),
+                Err(detail) => {
+                    failures += 1;
+                    ("FAIL", detail)
+                }
+            };
+            println!("  {status:<4}  {:<7}  {detail}", check.name);
+        }
+    }
+
+    match failures {
+        0 => Ok(()),
+        _ => Err(eyre!("{failures} check(s) failed")),
+    }
+}
+
+async fn check(url: &Url, config: &ClientConfig, tunnel: &TunnelConfig, proxy: bool) -> Vec<Check> {
+    let mut checks = Vec::new();
+    if proxy {
+        checks.extend(check_proxy(url, config, tunnel).await);
+    }
+    checks.push(check_request(url, config).await);
+    checks
+}
+
+/// The proxy for `url`, the tunnel it opens and the certificate at
+/// the far end.
+async fn check_proxy(url: &Url, config: &ClientConfig, tunnel: &TunnelConfig) -> Vec<Check> {
+    let Some(proxy) = tunnel::proxy_for(url) else {
+        return vec![Check::failed("proxy", "none configured: set HTTPS_PROXY or ALL_PROXY, and check NO_PROXY")];
+    };
+    let mut checks = vec![Check::ok("proxy", tunnel::describe_proxy(&proxy))];
+    if url.scheme() != "https" {
+        checks.push(Check::ok("CONNECT", "not used: plain http is sent to the proxy as ordinary requests"));
+        return checks;
+    }
+
+    let timeout = config.timeout.min(tunnel::CONNECT_TIMEOUT);
+    let diagnosis = tunnel::diagnose(url, &proxy, timeout).await;
+    checks.push(match diagnosis.connect {
+        Ok(status_line) => Check::ok("CONNECT", format!("{}: {status_line}", diagnosis.target)),
+        Err(e)          => Check::failed("CONNECT", format!("{}: {e}", diagnosis.target)),
+    });
+    match diagnosis.leaf {
+        Some(Ok(leaf)) => {
+            // Expected issuers settle it without another connection.
+            let direct = match tunnel.expected_issuers.is_empty() {
+                true  => direct_leaf(url).await,
+                false => None,
+            };
+            let host = url.host_str().unwrap_or_default();
+            checks.push(match tunnel::judge(&leaf, &tunnel.expected_issuers, direct.as_ref()) {
+                Verdict::Expected            => Check::ok("TLS", format!("issued by {}", leaf.issuer)),
+                Verdict::Unknown             => Check::ok("TLS", format!(
+                    "issued by {}; set `expected_issuers` under [tunnel] to check for interception",
+                    leaf.issuer,
+                )),
+                Verdict::Intercepted(reason) => {
+                    crate::warnings::emit(tunnel::interception_warning(&proxy, host, &leaf, &reason));
+                    Check::failed("TLS", format!("{reason}; the proxy is intercepting TLS"))
+                }
+            });
+        }
+        Some(Err(e)) => checks.push(Check::failed("TLS", e)),
+        None         => {}
+    }
+    checks
+}
+
+/// The certificate `url`'s host presents without the proxy, if it can
+/// be reached that way.
+async fn direct_leaf(url: &Url) -> Option<tunnel::LeafCert> {
+    let host = url.host_str()?;
+    let port = url.port_or_known_default()?;
+    let direct = async {
+        let stream = tokio::net::TcpStream::connect((host.trim_start_matches('[').trim_end_matches(']'), port)).await.ok()?;
+        tunnel::leaf_certificate(stream, host).await.ok()
+    };
+    tokio::time::timeout(DIRECT_TIMEOUT, direct).await.ok().flatten()
+}
+
+/// A request to `url` the way the client makes them, through whatever
+/// proxy the environment sets. Any HTTP response counts.
+async fn check_request(url: &Url, config: &ClientConfig) -> Check {
+    let client = match reqwest::Client::builder().timeout(config.timeout).user_agent(config.user_agent.clone()).build() {
+        Ok(client) => client,
+        Err(e)     => return Check::failed("request", e.to_string()),
+    };
+    let (response, _exchange) = crate::har::send(client.get(url.clone())).await;
+    match response {
+        Ok(response) => Check::ok("request", format!("HTTP {}", response.status().as_u16())),
+        Err(e)       => Check::failed("request", error_chain(&e)),
+    }
+}
+
+/// The API version a challenge request answers with, and whether this
+/// CLI can read it.
+async fn check_version(config: &ClientConfig) -> Check {
+    let client = match CliClient::new(config.clone()) {
+        Ok(client) => client,
+        Err(e)     => return Check::failed("version", e.to_string()),
+    };
+    match client.api_version(VERSION_PROBE_ENDPOINT).await {
+        Ok(negotiated @ Negotiated::NewerMinor(_)) => {
+            Check::ok("version", format!("{}; consider upgrading ironshield-cli", negotiated.describe()))
+        }
+        Ok(negotiated) => Check::ok("version", negotiated.describe()),
+        Err(e) => Check::failed("version", format!("{e:#}")),
+    }
+}
+
+/// `error` and its sources, which is where reqwest says what actually
+/// went wrong, e.g. `unsuccessful tunnel` or a certificate error.
+fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut text = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        text.push_str(&format!(": {cause}"));
+        source = cause.source();
+    }
+    text
+}
@@ -0,0 +1,207 @@
+use clap::ValueEnum;
+use ironshield::ClientConfig;
+use serde::Serialize;
+
+use std::time::Duration;
+
+use crate::client::CliClient;
+use crate::display::{format_count, format_duration};
+use crate::history::RunCommand;
+use crate::presolve::{expiry_risk, ExpiryRisk};
+use crate::solve::{Priority, Strategy};
+
+/// Paths the client library posts to, relative to `api_base_url`.
+const REQUEST_PATH:  &str = "/request";
+const RESPONSE_PATH: &str = "/response";
+
+/// How far `--dry-run` goes before stopping.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum DryRun {
+    /// Fetch the challenge, then stop before solving.
+    #[default]
+    Online,
+    /// Stop before the network: print only the configuration and request plan.
+    Offline,
+}
+
+/// One HTTP request the real run would make.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct PlannedRequest {
+    method:  &'static str,
+    url:     String,
+    headers: Vec<(String, String)>,
+}
+
+/// What a fetched challenge would cost to solve.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct ChallengePlan {
+    difficulty:           u64,
+    recommended_attempts: u64,
+    /// Unix milliseconds.
+    expiration_time:      i64,
+    estimate_low_ms:      u64,
+    estimate_high_ms:     u64,
+    /// Set when the estimate is close to or over the challenge's remaining lifetime.
+    expiry_risk:          Option<ExpiryRisk>,
+}
+
+/// Everything a solve or validate run would do.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct Plan {
+    command:       &'static str,
+    endpoint:      String,
+    api_base_url:  String,
+    timeout_ms:    u64,
+    strategy:      Strategy,
+    thread_count:  usize,
+    priority:      Priority,
+    requests:      Vec<PlannedRequest>,
+    /// `None` for `--dry-run=offline`.
+    challenge:     Option<ChallengePlan>,
+}
+
+/// Handles `solve --dry-run` and `validate --dry-run`: resolves the
+/// configuration, checks the endpoint and (unless offline) fetches the
+/// challenge, then prints what the run would do without solving or
+/// submitting anything.
+///
+/// # Arguments
+/// * `client`:            The API client, for the fetch.
+/// * `config`:            The client configuration.
+/// * `command`:           `Solve` or `Validate`.
+/// * `endpoint`:          The protected endpoint URL.
+/// * `use_multithreaded`: Whether the solve would be multithreaded.
+/// * `mode`:              Whether to fetch the challenge.
+/// * `json`:              Print the plan as JSON instead of text.
+pub async fn handle_dry_run(
+    client:            &CliClient,
+    config:            &ClientConfig,
+    command:           RunCommand,
+    endpoint:          &str,
+    use_multithreaded: bool,
+    mode:              DryRun,
+    json:              bool,
+) -> color_eyre::Result<()> {
+    crate::endpoint::check(endpoint)?;
+    let thread_plan = client.thread_plan(use_multithreaded);
+
+    let mut plan = Plan {
+        command:       command.name(),
+        endpoint:      endpoint.to_string(),
+        api_base_url:  config.api_base_url.clone(),
+        timeout_ms:    config.timeout.as_millis() as u64,
+        strategy:      thread_plan.strategy,
+        thread_count:  thread_plan.thread_count,
+        priority:      thread_plan.priority,
+        requests:      planned_requests(config, command),
+        challenge:     None,
+    };
+
+    if mode == DryRun::Online {
+        let envelope = client.fetch(endpoint).await?;
+        let challenge = &envelope.challenge;
+        let difficulty = challenge.recommended_attempts / 2;
+        let estimate = crate::estimate::estimate_solve(difficulty, thread_plan.thread_count).await;
+        plan.challenge = Some(ChallengePlan {
+            difficulty,
+            recommended_attempts: challenge.recommended_attempts,
+            expiration_time:      challenge.expiration_time,
+            estimate_low_ms:      estimate.low.as_millis() as u64,
+            estimate_high_ms:     estimate.high.as_millis() as u64,
+            expiry_risk:          expiry_risk(challenge.expiration_time, chrono::Utc::now().timestamp_millis(), estimate.low),
+        });
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&plan)?);
+    } else {
+        print!("{}", describe(&plan));
+    }
+    crate::status_println!("Dry run: nothing was solved or submitted.");
+    Ok(())
+}
+
+fn planned_requests(config: &ClientConfig, command: RunCommand) -> Vec<PlannedRequest> {
+    let base = config.api_base_url.trim_end_matches('/');
+    let headers: Vec<(String, String)> = [("User-Agent", config.user_agent.as_str()), ("Content-Type", "application/json")]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), redact_header(name, value)))
+        .collect();
+    let request = |path: &str| PlannedRequest { method: "POST", url: format!("{base}{path}"), headers: headers.clone() };
+
+    match command {
+        RunCommand::Validate => vec![request(REQUEST_PATH), request(RESPONSE_PATH)],
+        _                    => vec![request(REQUEST_PATH)],
+    }
+}
+
+/// The value to show for a header, hiding credentials.
+fn redact_header(name: &str, value: &str) -> String {
+    if crate::redact::is_secret_header(name) {
+        "[redacted]".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn describe(plan: &Plan) -> String {
+    let mut text = format!("Dry run: {} {}\n", plan.command, plan.endpoint);
+    text.push_str(&format!(
+        "  API:        {} (timeout {})\n",
+        plan.api_base_url,
+        format_duration(Duration::from_millis(plan.timeout_ms)),
+    ));
+    text.push_str(&format!(
+        "  Threads:    {} ({} strategy, {} priority)\n",
+        plan.thread_count,
+        plan.strategy.name(),
+        plan.priority.name(),
+    ));
+    text.push_str("  Requests:\n");
+    for request in &plan.requests {
+        text.push_str(&format!("    {} {}\n", request.method, request.url));
+        for (name, value) in &request.headers {
+            text.push_str(&format!("      {name}: {value}\n"));
+        }
+    }
+
+    match &plan.challenge {
+        Some(challenge) => {
+            text.push_str(&format!(
+                "  Difficulty: {} ({} recommended attempts)\n",
+                format_count(challenge.difficulty),
+                format_count(challenge.recommended_attempts),
+            ));
+            text.push_str(&format!(
+                "  Estimate:   {}–{}\n",
+                format_duration(Duration::from_millis(challenge.estimate_low_ms)),
+                format_duration(Duration::from_millis(challenge.estimate_high_ms)),
+            ));
+            if let Some(risk) = &challenge.expiry_risk {
+                text.push_str(&format!("  Warning:    {risk}\n"));
+            }
+        }
+        None => text.push_str("  Challenge:  not fetched (offline)\n"),
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_plans_both_requests() {
+        let config = ClientConfig { api_base_url: "https://api.example/".to_string(), ..ClientConfig::default() };
+
+        let urls: Vec<String> = planned_requests(&config, RunCommand::Validate).into_iter().map(|r| r.url).collect();
+        assert_eq!(urls, ["https://api.example/request", "https://api.example/response"]);
+        assert_eq!(planned_requests(&config, RunCommand::Solve).len(), 1);
+    }
+
+    #[test]
+    fn test_secret_headers_are_redacted() {
+        assert_eq!(redact_header("Authorization", "Bearer abc"), "[redacted]");
+        assert_eq!(redact_header("User-Agent", "curl/8.4.0"), "curl/8.4.0");
+    }
+}
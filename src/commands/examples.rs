@@ -0,0 +1,25 @@
+use color_eyre::eyre::eyre;
+
+use crate::examples;
+
+/// Handles `examples`: prints the usage examples from every command's
+/// `--help`, or only those for `command`.
+///
+/// # Arguments
+/// * `command`: The subcommand to show examples for, or `None` for all.
+pub fn handle_examples(command: Option<&str>) -> color_eyre::Result<()> {
+    let commands = match command {
+        Some(command) if examples::render(command).is_none() => {
+            return Err(eyre!("No examples for '{command}'; try one of: {}", examples::commands().join(", ")));
+        }
+        Some(command) => vec![command],
+        None          => examples::commands(),
+    };
+
+    let sections: Vec<String> = commands
+        .into_iter()
+        .filter_map(|command| examples::render(command).map(|section| format!("{command}\n{section}")))
+        .collect();
+    println!("{}", sections.join("\n\n"));
+    Ok(())
+}
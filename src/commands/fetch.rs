@@ -1,30 +1,81 @@
-use ironshield::{IronShieldClient, ClientConfig};
+use ironshield::ClientConfig;
 use std::time::Instant;
 
+use crate::api::ChallengeEnvelope;
+use crate::client::CliClient;
+use crate::display::{format_count, format_duration, format_hash_rate};
+use crate::history::{self, ErrorKind, RunCommand, RunRecord};
+use crate::logging::LogCategory;
+use crate::output::OutputSink;
+
 pub async fn handle_fetch(
-    client: &IronShieldClient, 
+    client: &CliClient,
     config: &ClientConfig,
-    endpoint: &str
+    endpoint: &str,
+    sink: &dyn OutputSink,
 ) -> color_eyre::Result<()> {
-    crate::verbose_section!(config, "Challenge Fetching");
+    let mut record = RunRecord::new(RunCommand::Fetch, endpoint);
+    let start_time = Instant::now();
+
+    let result = fetch(client, config, endpoint, &mut record, sink).await;
+    history::record_result(&mut record, start_time.elapsed(), &result);
+    crate::metrics::send_statsd(&record, config.verbose);
+    let envelope = result?;
+
+    // The challenge itself is the command's result; its metadata was
+    // reported with the status lines.
+    sink.result_json(serde_json::to_value(&envelope.challenge)?, Some(envelope.negotiated));
+
+    crate::logging::flush();
+
+    crate::har::write();
+    std::process::exit(0);
+}
+
+/// Fetches a challenge, filling in `record`. Status lines, including
+/// any metadata the API sent, go to `sink`; the challenge is returned,
+/// not emitted.
+pub async fn fetch(
+    client:   &CliClient,
+    config:   &ClientConfig,
+    endpoint: &str,
+    record:   &mut RunRecord,
+    sink:     &dyn OutputSink,
+) -> color_eyre::Result<ChallengeEnvelope> {
+    sink.section("Challenge Fetching");
     crate::verbose_log!(config, network, "Requesting challenge for endpoint: {}", endpoint);
 
     let start_time = Instant::now();
-    let challenge = client.fetch_challenge(endpoint).await?;
+    let envelope = client.fetch(endpoint).await
+        .inspect_err(|_| record.error_kind = Some(ErrorKind::Fetch))?;
+    let challenge = &envelope.challenge;
+    record.difficulty = Some(challenge.recommended_attempts / 2);
+    record.fetch_ms = Some(start_time.elapsed().as_millis() as u64);
 
     crate::verbose_log!(
         config,
         timing,
-        "Challenge fetch completed in {:?}",
-        start_time.elapsed()
+        "Challenge fetch completed in {}",
+        format_duration(start_time.elapsed())
     );
 
-    println!("Challenge fetched successfully!");
-    println!("Recommended attempts: {}", challenge.recommended_attempts);
+    sink.info("Challenge fetched successfully!");
+    crate::logging::file_event(LogCategory::Receive, format_args!("Challenge: {challenge:?}"));
+    sink.info(&format!("Recommended attempts: {}", format_count(challenge.recommended_attempts)));
 
-    crate::verbose_kv!(config, "Random Nonce", format!("{:?}", challenge.random_nonce));
-    crate::verbose_kv!(config, "Difficulty", challenge.recommended_attempts / 2);
-    crate::verbose_kv!(config, "Recommended Attempts", challenge.recommended_attempts);
+    // The probe costs ~200ms, so skip it when nobody will see the hint.
+    if !crate::logging::is_quiet() {
+        let estimate = crate::estimate::estimate_solve(challenge.recommended_attempts / 2, client.thread_plan(true).thread_count).await;
+        sink.info(&estimate.interpretation());
+        sink.kv("Expected Attempts", &format_count(estimate.expected_attempts));
+        sink.kv("Estimated Hash Rate", &format_hash_rate(estimate.hash_rate));
+    }
 
-    std::process::exit(0);
+    sink.kv("Random Nonce", &format!("{:?}", challenge.random_nonce));
+    sink.kv("Difficulty", &format_count(challenge.recommended_attempts / 2));
+    sink.kv("Recommended Attempts", &format_count(challenge.recommended_attempts));
+    if let Some(metadata) = &envelope.metadata {
+        metadata.report(sink);
+    }
+    Ok(envelope)
 } 
\ No newline at end of file
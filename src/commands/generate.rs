@@ -0,0 +1,148 @@
+use color_eyre::eyre::eyre;
+use ed25519_dalek::{Signer, SigningKey};
+use ironshield::IronShieldChallenge;
+use sha2::{Digest, Sha256};
+
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::compression::{self, Compression};
+use crate::display::{format_count, format_duration};
+
+/// Difficulties above this take minutes to hours to solve and are
+/// almost always a typo; `--force` generates them anyway.
+pub const DIFFICULTY_CAP: u64 = 100_000_000;
+
+/// What `challenge generate` builds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenerateOptions {
+    pub difficulty: u64,
+    pub expires_in: Duration,
+    pub website_id: String,
+    /// Generate even above [`DIFFICULTY_CAP`].
+    pub force:      bool,
+}
+
+/// A throwaway signing key, different on every run. Generated
+/// challenges are for demos and tests, so the key only has to make
+/// the signature check out, not be unguessable.
+fn throwaway_key() -> SigningKey {
+    let mut seed = Sha256::new();
+    seed.update(std::process::id().to_le_bytes());
+    seed.update(chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default().to_le_bytes());
+    SigningKey::from_bytes(&seed.finalize().into())
+}
+
+/// Builds and signs a challenge the way the API does, with a
+/// throwaway key.
+///
+/// # Arguments
+/// * `options`: The difficulty, lifetime and website ID.
+///
+/// # Returns
+/// * `Result<IronShieldChallenge>`: The challenge, or why the
+///                                  difficulty was refused.
+pub fn generate(options: &GenerateOptions) -> color_eyre::Result<IronShieldChallenge> {
+    if options.difficulty == 0 {
+        return Err(eyre!("Difficulty must be at least 1"));
+    }
+    if options.difficulty > DIFFICULTY_CAP && !options.force {
+        return Err(eyre!(
+            "Difficulty {} is above the sanity cap of {}; pass --force to generate it anyway",
+            format_count(options.difficulty),
+            format_count(DIFFICULTY_CAP),
+        ));
+    }
+    if options.expires_in.is_zero() {
+        return Err(eyre!("--expires-in must be longer than zero"));
+    }
+
+    let signing_key = throwaway_key();
+    let public_key = signing_key.verifying_key().to_bytes();
+    let mut challenge = IronShieldChallenge::new(
+        options.website_id.clone(),
+        options.difficulty,
+        signing_key.clone(),
+        public_key,
+    );
+
+    // `new` picks the API's default lifetime; the expiry is part of the
+    // signed message, so sign again after changing it.
+    challenge.expiration_time = challenge.created_time + options.expires_in.as_millis() as i64;
+    let message = ironshield_types::create_signing_message(&challenge);
+    challenge.challenge_signature = signing_key.sign(message.as_bytes()).to_bytes();
+
+    Ok(challenge)
+}
+
+/// Handles `challenge generate`: prints a freshly signed challenge as
+/// pretty JSON, or writes it to `out`.
+///
+/// # Arguments
+/// * `options`:  What to generate.
+/// * `out`:      A file to write instead of stdout, compressed by its
+///               extension.
+/// * `compress`: How to compress stdout.
+pub fn handle_generate(options: &GenerateOptions, out: Option<&Path>, compress: Option<Compression>) -> color_eyre::Result<()> {
+    let challenge = generate(options)?;
+    let json = serde_json::to_string_pretty(&challenge)?;
+
+    match out {
+        Some(path) => {
+            compression::write(path, format!("{json}\n").as_bytes())
+                .map_err(|e| eyre!("Cannot write '{}': {e}", path.display()))?;
+            crate::status_println!(
+                "Wrote a difficulty {} challenge for '{}' to {}, valid for {}.",
+                format_count(options.difficulty),
+                options.website_id,
+                path.display(),
+                format_duration(options.expires_in),
+            );
+        }
+        None if compress.is_none() => println!("{json}"),
+        None => {
+            let mut stdout = compression::Writer::new(std::io::stdout().lock(), compress)?;
+            writeln!(stdout, "{json}")?;
+            stdout.finish()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(difficulty: u64) -> GenerateOptions {
+        GenerateOptions {
+            difficulty,
+            expires_in: Duration::from_secs(120),
+            website_id: "demo".to_string(),
+            force:      false,
+        }
+    }
+
+    #[test]
+    fn test_generated_challenge_fields() {
+        let challenge = generate(&options(10_000)).unwrap();
+
+        assert_eq!(challenge.website_id, "demo");
+        assert_eq!(challenge.recommended_attempts, 20_000);
+        assert_eq!(challenge.expiration_time - challenge.created_time, 120_000);
+    }
+
+    #[test]
+    fn test_difficulty_cap() {
+        let error = generate(&options(DIFFICULTY_CAP + 1)).unwrap_err().to_string();
+        assert!(error.contains("pass --force"), "{error}");
+
+        assert!(generate(&GenerateOptions { force: true, ..options(DIFFICULTY_CAP + 1) }).is_ok());
+        assert!(generate(&options(0)).is_err());
+    }
+
+    #[test]
+    fn test_every_run_uses_a_new_key() {
+        assert_ne!(generate(&options(1_000)).unwrap().public_key, generate(&options(1_000)).unwrap().public_key);
+    }
+}
@@ -0,0 +1,435 @@
+use color_eyre::eyre::eyre;
+use ironshield::ClientConfig;
+use ironshield_types::IronShieldToken;
+use reqwest::StatusCode;
+use reqwest::header::{ACCEPT_RANGES, CONTENT_RANGE, ETAG, LAST_MODIFIED, RANGE};
+
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::checksum::{self, Checksum};
+use crate::client::CliClient;
+use crate::deadline::Deadline;
+use crate::display::{format_bytes, TransferProgress};
+use crate::history::{self, RunCommand, RunRecord};
+use crate::http_cache::{CacheEntry, HttpCache};
+use crate::logging::{self, LogCategory};
+use crate::output::OutputSink;
+use crate::template;
+use crate::warnings::{Warning, WarningCode};
+
+/// The request header the protection layer reads the token from.
+pub const TOKEN_HEADER: &str = "X-IronShield-Token";
+
+/// How `get` downloads the protected resource.
+pub struct GetOptions<'a> {
+    pub single_threaded: bool,
+    pub max_time:        Option<Duration>,
+    /// Write the body here instead of stdout.
+    pub save_body:       Option<&'a Path>,
+    /// Resume into an existing `save_body` file (`--continue`).
+    pub resume:          bool,
+    /// Skip conditional requests and leave the cache alone.
+    pub no_http_cache:   bool,
+    /// `sha256:<hex>` the saved body must hash to.
+    pub checksum:        Option<&'a str>,
+    /// A `sha256sum` file listing the saved body's hash.
+    pub checksum_file:   Option<&'a Path>,
+    /// POST this body instead of sending a GET (`--data`).
+    pub data:            Option<&'a str>,
+    /// POST the contents of this file (`--data-file`).
+    pub data_file:       Option<&'a Path>,
+    /// Substitute `{{token}}` and the like in the body.
+    pub template:        bool,
+}
+
+/// Handles `get`: validates against `endpoint`, then requests it with
+/// the token attached and writes the body to stdout or `--save-body`.
+///
+/// Unless `--no-http-cache` is given, the response's `ETag` and
+/// `Last-Modified` are kept so the next `get` of the same URL can be
+/// answered with `304 Not Modified` and served from the cache.
+///
+/// With `--data` or `--data-file` the body is POSTed instead, after
+/// its placeholders are filled in with the new token (see
+/// [`template::render`]); POSTs are never cached.
+pub async fn handle_get(
+    client:   &CliClient,
+    config:   &ClientConfig,
+    endpoint: &str,
+    options:  &GetOptions<'_>,
+    sink:     &dyn OutputSink,
+) -> color_eyre::Result<()> {
+    // Before solving, so a typo in the hash or body doesn't cost a solve.
+    let checksum = resolve_checksum(endpoint, options)?;
+    let data = read_data(options)?;
+
+    let mut record = RunRecord::new(RunCommand::Validate, endpoint);
+    let start_time = Instant::now();
+    let mut deadline = Deadline::start(options.max_time);
+
+    let result = super::validate::validate(client, config, endpoint, options.single_threaded, &mut deadline, &mut record, sink).await;
+    history::record_result(&mut record, start_time.elapsed(), &result);
+    crate::metrics::send_statsd(&record, config.verbose);
+    let token = result?.token;
+    let body = data.map(|data| build_body(config, endpoint, &token, data, options.template)).transpose()?;
+
+    let cache = if options.no_http_cache || body.is_some() { None } else { HttpCache::default_location() };
+    let target = match (options.save_body, options.resume) {
+        (Some(path), true)  => Target::Resume(path),
+        (Some(path), false) => Target::File(path),
+        (None, _)           => Target::Stdout,
+    };
+    let sent = Sent { token: &token, body };
+    download(config, endpoint, sent, cache.as_ref(), target, checksum.as_ref(), sink).await
+}
+
+/// The body from `--data` or `--data-file`, with its placeholders
+/// checked unless templating is off.
+fn read_data(options: &GetOptions<'_>) -> color_eyre::Result<Option<String>> {
+    let data = match (options.data, options.data_file) {
+        (Some(data), _)    => data.to_string(),
+        (None, Some(path)) => std::fs::read_to_string(path)
+            .map_err(|e| eyre!("Cannot read --data-file '{}': {e}", path.display()))?,
+        (None, None)       => return Ok(None),
+    };
+    if options.template {
+        template::check(&data).map_err(|e| eyre!("--data: {e} (pass --no-template to send it as is)"))?;
+    }
+    Ok(Some(data))
+}
+
+/// Fills in the body's placeholders now that there is a token.
+///
+/// The result carries the token, so it is only logged in verbose mode,
+/// where it is still subject to redaction.
+fn build_body(
+    config:   &ClientConfig,
+    endpoint: &str,
+    token:    &IronShieldToken,
+    data:     String,
+    enabled:  bool,
+) -> color_eyre::Result<String> {
+    if !enabled {
+        return Ok(data);
+    }
+    let token_json = serde_json::to_string(token)?;
+    let header = token.to_base64url_header();
+    let values = template::Values {
+        token:           &token_json,
+        response_header: &header,
+        timestamp_ms:    chrono::Utc::now().timestamp_millis(),
+        endpoint,
+    };
+    let body = template::render(&data, &values).map_err(|e| eyre!("--data: {e}"))?;
+    if config.verbose {
+        logging::log_event(true, LogCategory::Submit, format_args!("Request body: {body}"));
+    }
+    Ok(body)
+}
+
+/// The expected hash from `--checksum`, or from `--checksum-file` by
+/// the saved file's name, then the last segment of the URL's path.
+fn resolve_checksum(endpoint: &str, options: &GetOptions<'_>) -> color_eyre::Result<Option<Checksum>> {
+    if let Some(spec) = options.checksum {
+        return Checksum::parse(spec).map(Some).map_err(|e| eyre!("--checksum: {e}"));
+    }
+    let Some(sums) = options.checksum_file else {
+        return Ok(None);
+    };
+    let url_name = reqwest::Url::parse(endpoint).ok()
+        .and_then(|url| url.path_segments()?.next_back().map(str::to_string))
+        .filter(|name| !name.is_empty());
+    let names: Vec<&str> = options.save_body
+        .and_then(|path| path.file_name()?.to_str())
+        .into_iter()
+        .chain(url_name.as_deref())
+        .collect();
+    Checksum::from_sums_file(sums, &names).map(Some).map_err(|e| eyre!("--checksum-file: {e}"))
+}
+
+/// What `get` sends along with its request.
+#[derive(Debug, Clone)]
+pub struct Sent<'a> {
+    /// The token from validating against the endpoint.
+    pub token: &'a IronShieldToken,
+    /// A request body to POST instead of a GET.
+    pub body:  Option<String>,
+}
+
+/// Where `get` writes the body.
+#[derive(Debug, Clone, Copy)]
+pub enum Target<'a> {
+    Stdout,
+    /// Replace this file.
+    File(&'a Path),
+    /// Append to this file what it is missing (`--continue`).
+    Resume(&'a Path),
+}
+
+impl<'a> Target<'a> {
+    fn path(self) -> Option<&'a Path> {
+        match self {
+            Target::Stdout                            => None,
+            Target::File(path) | Target::Resume(path) => Some(path),
+        }
+    }
+}
+
+/// Requests `endpoint` with the token, POSTing the body if there is
+/// one, and writes the response body out.
+///
+/// Resuming sends `Range: bytes=<len>-` for a non-empty file and skips
+/// the HTTP cache. A `206` is appended and the file's final length
+/// checked against `Content-Range`; a `200` from a server that
+/// advertises `Accept-Ranges: bytes` means the resource changed, so the
+/// file is rewritten from scratch; any other `200` means ranges are
+/// ignored, which is an error, since `--continue` asked for one.
+///
+/// # Arguments
+/// * `config`:   For the timeout and user agent.
+/// * `endpoint`: The protected URL.
+/// * `sent`:     The token, and any body to POST.
+/// * `cache`:    Where bodies are cached, unless caching is off.
+/// * `target`:   Where the body goes.
+/// * `checksum`: What the saved file must hash to. The body is hashed
+///               as it streams; a mismatch fails with
+///               [`checksum::Mismatch`] and renames the file.
+/// * `sink`:     Status lines.
+pub async fn download(
+    config:   &ClientConfig,
+    endpoint: &str,
+    sent:     Sent<'_>,
+    cache:    Option<&HttpCache>,
+    target:   Target<'_>,
+    checksum: Option<&Checksum>,
+    sink:     &dyn OutputSink,
+) -> color_eyre::Result<()> {
+    sink.section("Protected Request");
+    let http = reqwest::Client::builder()
+        .timeout(config.timeout)
+        .user_agent(config.user_agent.clone())
+        .build()?;
+
+    let reused = match target {
+        Target::Resume(path) => std::fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0),
+        _                    => 0,
+    };
+    // A partial file has no validators of its own to revalidate with.
+    let cache = cache.filter(|_| reused == 0);
+
+    let cached = cache.and_then(|cache| cache.lookup(endpoint));
+    // The protection layer sees the request before any cache does, so
+    // the token goes on conditional requests too.
+    let mut request = match sent.body {
+        Some(body) => {
+            sink.info(&format!("POSTing a {} body", format_bytes(body.len() as u64)));
+            http.post(endpoint).body(body)
+        }
+        None => http.get(endpoint),
+    };
+    request = request.header(TOKEN_HEADER, sent.token.to_base64url_header());
+    for (name, value) in cached.iter().flat_map(CacheEntry::conditional_headers) {
+        request = request.header(name, value);
+    }
+    if reused > 0 {
+        request = request.header(RANGE, format!("bytes={reused}-"));
+    }
+    let (response, mut exchange) = crate::har::send(request).await;
+    let mut response = response.map_err(|e| eyre!("Request to {endpoint} failed: {e}"))?;
+
+    let status = response.status();
+    let header = |name| response.headers().get(name).and_then(|value| value.to_str().ok()).map(str::to_string);
+    if let (StatusCode::NOT_MODIFIED, Some(cache), Some(entry)) = (status, cache, &cached) {
+        let mut body = std::fs::File::open(cache.body_path(endpoint))?;
+        let mut output = open_output(target.path(), false)?;
+        std::io::copy(&mut body, &mut output)?;
+        output.flush()?;
+        drop(output);
+        sink.info(&format!("304 Not Modified; served {} from the HTTP cache (cached)", format_bytes(entry.size)));
+        return verify_file(checksum, target);
+    }
+
+    let mut expected_total = None;
+    if reused > 0 {
+        let range = header(CONTENT_RANGE).as_deref().and_then(ContentRange::parse);
+        match status {
+            StatusCode::PARTIAL_CONTENT => match range {
+                Some(ContentRange { start: Some(start), total }) if start == reused => expected_total = total,
+                _ => return Err(eyre!(
+                    "{endpoint} answered 206 without a Content-Range starting at byte {reused}; not appending to it",
+                )),
+            },
+            StatusCode::RANGE_NOT_SATISFIABLE => {
+                return match range.and_then(|range| range.total) {
+                    Some(total) if total == reused => {
+                        sink.info(&format!("Already complete: reused {}, transferred 0 B", format_bytes(reused)));
+                        verify_file(checksum, target)
+                    }
+                    Some(total) => Err(eyre!(
+                        "The existing file is {} but {endpoint} is only {}; remove it or rerun without --continue",
+                        format_bytes(reused),
+                        format_bytes(total),
+                    )),
+                    None => Err(eyre!("{endpoint} refused the range starting at byte {reused} ({status})")),
+                };
+            }
+            status if status.is_success() && header(ACCEPT_RANGES).as_deref() == Some("bytes") => {
+                sink.warning(
+                    &Warning::new(
+                        WarningCode::DownloadRestarted,
+                        format!("{endpoint} sent the whole resource instead of the rest; it changed, so starting over"),
+                    ).with_data(serde_json::json!({ "discarded_bytes": reused })),
+                );
+            }
+            status if status.is_success() => {
+                return Err(eyre!(
+                    "{endpoint} ignored the Range request ({status} without Accept-Ranges: bytes), so --continue \
+                     cannot resume; rerun without it to download from scratch",
+                ));
+            }
+            _ => {}
+        }
+    }
+    if !status.is_success() {
+        return Err(eyre!("{endpoint} answered {status}"));
+    }
+    let appending = status == StatusCode::PARTIAL_CONTENT;
+
+    let (etag, last_modified) = (header(ETAG), header(LAST_MODIFIED));
+    let mut cache_body = match cache {
+        Some(cache) if etag.is_some() || last_modified.is_some() => Some(cache.begin()?),
+        _                                                        => None,
+    };
+
+    let mut hasher = checksum.map(Checksum::hasher);
+    if let (Some(hasher), true) = (hasher.as_mut(), appending) {
+        let path = target.path().expect("only files are resumed");
+        checksum::hash_reader(hasher, std::fs::File::open(path)?)?;
+    }
+
+    let mut output = open_output(target.path(), appending)?;
+    let mut progress = TransferProgress::new(response.content_length());
+    let mut size = 0u64;
+    while let Some(chunk) = response.chunk().await.map_err(|e| eyre!("Download from {endpoint} failed: {e}"))? {
+        output.write_all(&chunk)?;
+        exchange.content(&chunk);
+        if let Some(body) = cache_body.as_mut() {
+            body.write_all(&chunk)?;
+        }
+        if let Some(hasher) = hasher.as_mut() {
+            sha2::Digest::update(hasher, &chunk);
+        }
+        size += chunk.len() as u64;
+        progress.advance(chunk.len() as u64);
+    }
+    output.flush()?;
+    drop(output);
+    sink.info(&format!("{status}: received {}", progress.finish()));
+
+    if appending {
+        let path = target.path().expect("only files are resumed");
+        let length = std::fs::metadata(path)?.len();
+        if let Some(total) = expected_total.filter(|&total| total != length) {
+            return Err(eyre!(
+                "'{}' is {} after resuming, but {endpoint} is {}; remove it and download again",
+                path.display(),
+                format_bytes(length),
+                format_bytes(total),
+            ));
+        }
+        sink.info(&format!("Resumed: reused {}, transferred {}", format_bytes(reused), format_bytes(size)));
+    }
+    if let (Some(checksum), Some(hasher), Some(path)) = (checksum, hasher, target.path()) {
+        checksum.verify(hasher, path)?;
+        sink.info("Checksum verified (sha256)");
+    }
+
+    if let (Some(cache), Some(body)) = (cache, cache_body) {
+        let entry = CacheEntry { url: endpoint.to_string(), etag, last_modified, size, stored_at: chrono::Utc::now() };
+        if let Err(e) = cache.store(&entry, body) {
+            sink.warning(
+                &Warning::new(WarningCode::CacheWriteFailed, format!("Could not cache the response: {e}"))
+                    .with_data(serde_json::json!({ "cache_error": e.to_string() })),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Verifies a file written without streaming it here, such as a body
+/// served from the HTTP cache, by reading it back a buffer at a time.
+fn verify_file(checksum: Option<&Checksum>, target: Target<'_>) -> color_eyre::Result<()> {
+    let (Some(checksum), Some(path)) = (checksum, target.path()) else {
+        return Ok(());
+    };
+    let mut hasher = checksum.hasher();
+    checksum::hash_reader(&mut hasher, std::fs::File::open(path)?)?;
+    checksum.verify(hasher, path)?;
+    Ok(())
+}
+
+/// A parsed `Content-Range`, e.g. `bytes 100-199/200` or `bytes */200`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ContentRange {
+    /// The first byte sent; `None` for `*`, as on a `416`.
+    start: Option<u64>,
+    /// The full length; `None` for `*`, when the server doesn't know it.
+    total: Option<u64>,
+}
+
+impl ContentRange {
+    fn parse(value: &str) -> Option<Self> {
+        let (range, total) = value.trim().strip_prefix("bytes ")?.split_once('/')?;
+        let start = match range {
+            "*"   => None,
+            range => Some(range.split_once('-')?.0.parse().ok()?),
+        };
+        let total = match total {
+            "*"   => None,
+            total => Some(total.parse().ok()?),
+        };
+        Some(Self { start, total })
+    }
+}
+
+fn open_output(path: Option<&Path>, append: bool) -> color_eyre::Result<Box<dyn Write>> {
+    Ok(match path {
+        Some(path) => Box::new(std::io::BufWriter::new(
+            std::fs::OpenOptions::new()
+                .write(true)
+                .create(true)
+                .append(append)
+                .truncate(!append)
+                .open(path)
+                .map_err(|e| eyre!("Cannot write '{}': {e}", path.display()))?,
+        )),
+        None => Box::new(std::io::stdout().lock()),
+    })
+}
+
+/// Handles `cache purge`: deletes every cached `get` response.
+pub fn handle_purge() -> color_eyre::Result<()> {
+    let cache = HttpCache::default_location().ok_or_else(|| eyre!("No cache directory on this platform"))?;
+    let removed = cache.purge().map_err(|e| eyre!("Cannot purge '{}': {e}", cache.dir().display()))?;
+    println!("Removed {removed} cached response(s) from {}", cache.dir().display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_range() {
+        assert_eq!(
+            ContentRange::parse("bytes 100-199/200"),
+            Some(ContentRange { start: Some(100), total: Some(200) }),
+        );
+        assert_eq!(ContentRange::parse("bytes */200"), Some(ContentRange { start: None, total: Some(200) }));
+        assert_eq!(ContentRange::parse("bytes 0-9/*"), Some(ContentRange { start: Some(0), total: None }));
+        assert_eq!(ContentRange::parse("items 0-9/10"), None);
+        assert_eq!(ContentRange::parse("bytes 0-9"), None);
+    }
+}
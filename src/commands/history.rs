@@ -0,0 +1,204 @@
+use chrono::{DateTime, Local, Utc};
+use color_eyre::eyre::eyre;
+
+use std::time::Duration;
+
+use crate::display::{format_count, format_duration, format_hash_rate};
+use crate::history::{
+    HistoryComparison,
+    HistoryStats,
+    HistoryStore,
+    RunOutcome,
+    RunRecord,
+    RECALL_SLOTS,
+    recent_endpoints,
+};
+
+/// Handles `history`: prints the most recent runs, newest first.
+///
+/// # Arguments
+/// * `limit`:    The most runs to print.
+/// * `endpoint`: Only runs whose endpoint contains this, ignoring case.
+/// * `json`:     Print a JSON array of records instead of a table.
+pub fn handle_history(limit: usize, endpoint: Option<&str>, json: bool) -> color_eyre::Result<()> {
+    let records: Vec<RunRecord> = load(endpoint)?.into_iter().rev().take(limit).collect();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&records)?);
+        return Ok(());
+    }
+    if records.is_empty() {
+        crate::status_println!("No runs recorded yet.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<19}  {:<8}  {:<14}  {:>8}  {:>14}  {:>7}  {:>11}  Endpoint",
+        "When", "Command", "Outcome", "Time", "Difficulty", "Threads", "Hash rate",
+    );
+    for record in &records {
+        println!(
+            "{:<19}  {:<8}  {:<14}  {:>8}  {:>14}  {:>7}  {:>11}  {}",
+            record.timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"),
+            record.command.name(),
+            outcome(record),
+            format_duration(Duration::from_millis(record.elapsed_ms)),
+            optional(record.difficulty.map(format_count)),
+            optional(record.thread_count.map(|count| count.to_string())),
+            optional(record.hash_rate().map(format_hash_rate)),
+            record.endpoint,
+        );
+    }
+    Ok(())
+}
+
+/// Handles `history stats`: prints aggregates over every matching run.
+///
+/// # Arguments
+/// * `endpoint`: Only runs whose endpoint contains this, ignoring case.
+/// * `json`:     Print the aggregates as a JSON object.
+pub fn handle_stats(endpoint: Option<&str>, json: bool) -> color_eyre::Result<()> {
+    let stats = HistoryStats::from_records(&load(endpoint)?);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+
+    let solve_time = |ms: Option<u64>| optional(ms.map(|ms| format_duration(Duration::from_millis(ms))));
+    println!("Runs:              {}", stats.count);
+    println!("Success rate:      {:.1}% ({}/{})", stats.success_rate * 100.0, stats.successes, stats.count);
+    println!("Solve time p50:    {}", solve_time(stats.p50_solve_ms));
+    println!("Solve time p95:    {}", solve_time(stats.p95_solve_ms));
+    println!("Average hash rate: {}", optional(stats.average_hash_rate.map(format_hash_rate)));
+    Ok(())
+}
+
+/// Handles `history endpoints`: prints the recent endpoints with the
+/// shorthand that recalls each.
+///
+/// # Arguments
+/// * `endpoint`: Only endpoints that contain this, ignoring case; the
+///               numbering stays that of the full history.
+/// * `json`:     Print a JSON array of `{"shorthand", "endpoint"}` objects.
+pub fn handle_endpoints(endpoint: Option<&str>, json: bool) -> color_eyre::Result<()> {
+    let slots: Vec<(String, String)> = recent_endpoints(&load(None)?, RECALL_SLOTS)
+        .into_iter()
+        .enumerate()
+        .map(|(index, recent)| (format!("@{}", index + 1), recent))
+        .filter(|(_, recent)| endpoint.is_none_or(|filter| recent.to_lowercase().contains(&filter.to_lowercase())))
+        .collect();
+
+    if json {
+        let slots: Vec<serde_json::Value> = slots
+            .iter()
+            .map(|(shorthand, endpoint)| serde_json::json!({ "shorthand": shorthand, "endpoint": endpoint }))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&slots)?);
+        return Ok(());
+    }
+    if slots.is_empty() {
+        crate::status_println!("No runs recorded yet.");
+        return Ok(());
+    }
+
+    for (shorthand, endpoint) in &slots {
+        println!("{shorthand:<3}  {endpoint}");
+    }
+    crate::status_println!("@last is the same as @1.");
+    Ok(())
+}
+
+/// Handles `history compare`: the runs in `[from, to)` against the runs before `from`.
+///
+/// # Arguments
+/// * `from`:     Start of the candidate window.
+/// * `to`:       End of the candidate window, or now.
+/// * `baseline`: Only compare against this many runs right before `from`.
+/// * `endpoint`: Only runs whose endpoint contains this, ignoring case.
+/// * `json`:     Print the comparison as a JSON object.
+pub fn handle_compare(
+    from:     DateTime<Utc>,
+    to:       Option<DateTime<Utc>>,
+    baseline: Option<usize>,
+    endpoint: Option<&str>,
+    json:     bool,
+) -> color_eyre::Result<()> {
+    let to = to.unwrap_or_else(Utc::now);
+    let mut records = load(endpoint)?;
+    let count = records.len();
+    records.retain(|record| record.debug_build != Some(true));
+    if records.len() < count {
+        crate::status_println!("Note: left out {} run(s) from debug builds.", count - records.len());
+    }
+
+    let mut before: Vec<RunRecord> = records.iter().filter(|r| r.timestamp < from).cloned().collect();
+    if let Some(limit) = baseline {
+        before.drain(..before.len().saturating_sub(limit));
+    }
+    let window: Vec<RunRecord> = records.into_iter().filter(|r| r.timestamp >= from && r.timestamp < to).collect();
+
+    let comparison = HistoryComparison::new(&before, &window);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&comparison)?);
+        return Ok(());
+    }
+
+    let (old, new, changes) = (&comparison.baseline, &comparison.candidate, &comparison.changes);
+    let solve_time = |ms: Option<u64>| optional(ms.map(|ms| format_duration(Duration::from_millis(ms))));
+    let failure_rate = |stats: &HistoryStats| match stats.count {
+        0 => optional(None),
+        _ => format!("{:.1}%", (1.0 - stats.success_rate) * 100.0),
+    };
+
+    println!("{:<18}  {:>14}  {:>14}  {:>8}", "", "Baseline", "Window", "Change");
+    println!("{:<18}  {:>14}  {:>14}", "Runs", old.count, new.count);
+    println!("{:<18}  {:>14}  {:>14}", "Solves", old.solves, new.solves);
+    let rows = [
+        ("Solve time p50", solve_time(old.p50_solve_ms), solve_time(new.p50_solve_ms), changes.p50_solve_ms),
+        ("Solve time p95", solve_time(old.p95_solve_ms), solve_time(new.p95_solve_ms), changes.p95_solve_ms),
+        (
+            "Average hash rate",
+            optional(old.average_hash_rate.map(format_hash_rate)),
+            optional(new.average_hash_rate.map(format_hash_rate)),
+            changes.average_hash_rate,
+        ),
+        ("Failure rate", failure_rate(old), failure_rate(new), changes.failure_rate),
+    ];
+    for (label, old, new, change) in rows {
+        println!("{label:<18}  {old:>14}  {new:>14}  {:>8}", optional(change.map(|change| format!("{change:+.1}%"))));
+    }
+
+    if comparison.is_small_sample() {
+        crate::status_println!(
+            "Note: fewer than {} solves on one side; differences this small may be noise.",
+            HistoryComparison::MIN_SAMPLES,
+        );
+    }
+    Ok(())
+}
+
+/// Loads the default store, oldest first, keeping runs that match `endpoint`.
+fn load(endpoint: Option<&str>) -> color_eyre::Result<Vec<RunRecord>> {
+    let store = HistoryStore::open_default()
+        .ok_or_else(|| eyre!("This platform has no data directory to keep run history in"))?;
+
+    let mut records = store.load()
+        .map_err(|e| eyre!("Cannot read run history from '{}': {e}", store.path().display()))?;
+    if let Some(filter) = endpoint {
+        records.retain(|record| record.matches_endpoint(filter));
+    }
+    Ok(records)
+}
+
+fn outcome(record: &RunRecord) -> String {
+    match (record.outcome, record.error_kind) {
+        (RunOutcome::Success, _)          => "ok".to_string(),
+        (RunOutcome::Failure, Some(kind)) => format!("failed ({})", kind.name()),
+        (RunOutcome::Failure, None)       => "failed".to_string(),
+    }
+}
+
+fn optional(value: Option<String>) -> String {
+    value.unwrap_or_else(|| crate::display::glyphs().missing.to_string())
+}
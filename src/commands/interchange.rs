@@ -0,0 +1,113 @@
+use color_eyre::eyre::eyre;
+use ironshield::{IronShieldChallenge, IronShieldChallengeResponse};
+
+use std::path::Path;
+
+use crate::compression::{self, Compression};
+
+/// An artifact passed between the CLI and the browser extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Artifact {
+    Challenge,
+    Solution,
+}
+
+impl Artifact {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Challenge => "challenge",
+            Self::Solution  => "solution",
+        }
+    }
+}
+
+/// Where an artifact is read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input<'a> {
+    Argument(&'a str),
+    /// Decompressed if its extension is `.gz` or `.zst`.
+    File(&'a Path),
+    /// Decompressed with the given format, for `--decompress`.
+    Stdin(Option<Compression>),
+}
+
+impl Input<'_> {
+    /// The input with surrounding whitespace trimmed.
+    fn read(&self) -> color_eyre::Result<String> {
+        let contents = match self {
+            Input::Argument(value) => value.to_string(),
+            Input::File(path)      => compression::open(path)
+                .and_then(|reader| compression::read_to_string(reader, Compression::detect(path)))
+                .map_err(|e| eyre!("Cannot read '{}': {e}", path.display()))?,
+            Input::Stdin(format)   => compression::decoder(std::io::stdin().lock(), *format)
+                .and_then(|reader| compression::read_to_string(reader, *format))
+                .map_err(|e| eyre!("Cannot read stdin: {e}"))?,
+        };
+        Ok(contents.trim().to_string())
+    }
+}
+
+/// Decodes a wire header, as `to_base64url_header` produces it, into
+/// pretty JSON.
+///
+/// # Arguments
+/// * `artifact`: What the header holds.
+/// * `header`:   The base64url header value.
+pub fn decode(artifact: Artifact, header: &str) -> color_eyre::Result<String> {
+    let json = match artifact {
+        Artifact::Challenge => IronShieldChallenge::from_base64url_header(header)
+            .map(|challenge| serde_json::to_string_pretty(&challenge))
+            .map_err(|e| eyre!("Not a valid challenge header: {e}"))?,
+        Artifact::Solution  => IronShieldChallengeResponse::from_base64url_header(header)
+            .map(|solution| serde_json::to_string_pretty(&solution))
+            .map_err(|e| eyre!("Not a valid solution header: {e}"))?,
+    };
+    Ok(json?)
+}
+
+/// Encodes JSON, as [`decode`] prints it, into a wire header.
+///
+/// # Arguments
+/// * `artifact`: What the JSON holds.
+/// * `json`:     The artifact as JSON.
+pub fn encode(artifact: Artifact, json: &str) -> color_eyre::Result<String> {
+    let invalid = |e: serde_json::Error| eyre!("Not a valid {} as JSON: {e}", artifact.name());
+    Ok(match artifact {
+        Artifact::Challenge => serde_json::from_str::<IronShieldChallenge>(json).map_err(invalid)?.to_base64url_header(),
+        Artifact::Solution  => serde_json::from_str::<IronShieldChallengeResponse>(json).map_err(invalid)?.to_base64url_header(),
+    })
+}
+
+/// Reads a challenge given either as JSON or as a wire header.
+///
+/// # Arguments
+/// * `input`: Where the challenge comes from.
+pub fn read_challenge(input: Input) -> color_eyre::Result<IronShieldChallenge> {
+    let contents = input.read()?;
+    if contents.is_empty() {
+        return Err(eyre!("No challenge given"));
+    }
+    match contents.starts_with('{') {
+        true  => serde_json::from_str(&contents).map_err(|e| eyre!("Not a valid challenge as JSON: {e}")),
+        false => IronShieldChallenge::from_base64url_header(&contents).map_err(|e| eyre!("Not a valid challenge header: {e}")),
+    }
+}
+
+/// Handles `challenge decode|encode` and `solution decode|encode`:
+/// prints the converted artifact on stdout.
+///
+/// # Arguments
+/// * `artifact`: The artifact type.
+/// * `encoding`: `true` to turn JSON into a header, `false` for the reverse.
+/// * `input`:    Where the header or JSON comes from.
+pub fn handle_convert(artifact: Artifact, encoding: bool, input: Input) -> color_eyre::Result<()> {
+    let contents = input.read()?;
+    if contents.is_empty() {
+        return Err(eyre!("No {} given", artifact.name()));
+    }
+    match encoding {
+        true  => println!("{}", encode(artifact, &contents)?),
+        false => println!("{}", decode(artifact, &contents)?),
+    }
+    Ok(())
+}
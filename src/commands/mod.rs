@@ -1,3 +1,24 @@
+pub mod batch;
+pub mod benchmark;
+pub mod config;
+pub mod doctor;
+pub mod dry_run;
+pub mod examples;
 pub mod fetch;
+pub mod generate;
+pub mod get;
+pub mod history;
+pub mod interchange;
+pub mod presenter;
+pub mod proxy;
+pub mod queue;
+pub mod repro;
+pub mod run;
+pub mod serve;
+pub mod setup;
 pub mod solve;
-pub mod validate; 
\ No newline at end of file
+pub mod spool;
+pub mod state;
+pub mod stream;
+pub mod survey;
+pub mod validate;
\ No newline at end of file
@@ -0,0 +1,28 @@
+use crate::client::Event;
+use crate::display::format_duration;
+use crate::logging::{LogCategory, log_event};
+
+/// Reports what a [`CliClient`] did the way the CLI always has: the
+/// API version, waits for the rate limit and the threads each solve
+/// got go to the verbose log, and warnings to the console.
+///
+/// [`CliClient`]: crate::CliClient
+///
+/// # Arguments
+/// * `verbose`: Whether the log lines go to the console as well as the log file.
+pub fn console(verbose: bool) -> impl Fn(&Event) + Send + Sync + 'static {
+    move |event| match event {
+        Event::ChallengeFetched { negotiated, .. } => crate::api_version::report(*negotiated, verbose),
+        Event::RateLimited { wait } => log_event(verbose, LogCategory::Network, format_args!(
+            "Rate limit reached; waiting {} before the next challenge request",
+            format_duration(*wait),
+        )),
+        Event::ThreadsGranted { granted, requested, in_use, total } => {
+            crate::build_profile::warn();
+            log_event(verbose, LogCategory::Compute, format_args!(
+                "Thread budget: granted {granted} of {requested} requested threads; {in_use} of {total} now in use",
+            ));
+        }
+        Event::SolveStarted { .. } | Event::Solved { .. } | Event::Validated { .. } => {}
+    }
+}
@@ -0,0 +1,432 @@
+use color_eyre::eyre::eyre;
+use ironshield::IronShieldToken;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use std::hash::{BuildHasher, Hasher};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::daemon::{Request as Signal, Signals, Supervised};
+use crate::deadline::Deadline;
+use crate::display::{format_count, format_duration};
+use crate::estimate::SolveEstimate;
+use crate::history::{self, RunCommand, RunRecord};
+use crate::output::{ConsoleSink, OutputSink};
+use crate::warnings::{Warning, WarningCode};
+
+/// Never refresh more often than this, however short-lived the token.
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long to wait before trying again after a failed refresh.
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// Refreshes happen up to this share of the delay early, at random, so
+/// proxies started together don't all ask the API at the same moment.
+const JITTER_SHARE: f64 = 0.1;
+
+/// A refresh over `--max-renewal-difficulty` is still done once the
+/// current token has less than this left, or less than twice the
+/// solve's 90th percentile estimate if that is longer.
+const MIN_SKIP_HEADROOM: Duration = Duration::from_secs(30);
+
+/// How long before a token expires to replace it (`--renewal-margin`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenewalMargin {
+    /// A fixed time before expiry, e.g. `30s`.
+    Fixed(Duration),
+    /// A share of the token's remaining lifetime, e.g. `20%`.
+    Share(f64),
+}
+
+impl RenewalMargin {
+    /// The margin for a token with `remaining` left.
+    fn before_expiry(self, remaining: Duration) -> Duration {
+        match self {
+            Self::Fixed(margin) => margin.min(remaining),
+            Self::Share(share)  => remaining.mul_f64(share),
+        }
+    }
+}
+
+/// Parses `--renewal-margin`: a duration such as `30s` or `2m`, or a
+/// share of the token's lifetime such as `20%` or `0.2`.
+pub fn parse_renewal_margin(value: &str) -> Result<RenewalMargin, String> {
+    let share = match value.strip_suffix('%') {
+        Some(percent) => Some(percent.trim().parse::<f64>().map(|percent| percent / 100.0)
+            .map_err(|_| format!("'{value}' is not a percentage"))?),
+        None if value.contains('.') => value.parse::<f64>().ok(),
+        None => None,
+    };
+    match share {
+        Some(share) if share > 0.0 && share < 1.0 => Ok(RenewalMargin::Share(share)),
+        Some(_) => Err(format!("'{value}' must be a share between 0% and 100% of the token's lifetime")),
+        None    => crate::display::parse_duration(value).map(RenewalMargin::Fixed),
+    }
+}
+
+/// How `proxy` runs.
+pub struct ProxyOptions<'a> {
+    /// The protected endpoint tokens are obtained for.
+    pub endpoint:               &'a str,
+    pub listen:                 SocketAddr,
+    /// Where to serve Prometheus metrics (`--metrics-listen`), if anywhere.
+    pub metrics_listen:         Option<SocketAddr>,
+    /// `/readyz` fails once the last successful fetch is older than this.
+    pub ready_within:           Duration,
+    /// Keep the current token rather than solve a challenge harder than this.
+    pub max_renewal_difficulty: Option<u64>,
+    pub renewal_margin:         RenewalMargin,
+}
+
+/// What the HTTP endpoints report, shared with the refresh loop.
+struct State {
+    /// The last token, serialized, for `GET /token`.
+    token:        Mutex<Option<String>>,
+    last_fetch:   Mutex<Option<Instant>>,
+    /// Cleared when a `SIGHUP` reload finds the config file invalid.
+    config_valid: AtomicBool,
+    ready_within: Duration,
+}
+
+impl State {
+    fn readiness(&self) -> Result<(), String> {
+        let last_fetch = *self.last_fetch.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        readiness(self.config_valid.load(Ordering::Relaxed), last_fetch.map(|at| at.elapsed()), self.ready_within)
+    }
+}
+
+/// Whether the proxy can serve tokens.
+///
+/// # Arguments
+/// * `config_valid`: Whether the config file last loaded cleanly.
+/// * `since_fetch`:  Time since the last successful upstream fetch.
+/// * `ready_within`: How recent that fetch must be.
+///
+/// # Returns
+/// * `Result<(), String>`: Why it isn't ready, if it isn't.
+fn readiness(config_valid: bool, since_fetch: Option<Duration>, ready_within: Duration) -> Result<(), String> {
+    if !config_valid {
+        return Err("the config file failed to reload".to_string());
+    }
+    match since_fetch {
+        None => Err("no token fetched yet".to_string()),
+        Some(elapsed) if elapsed > ready_within => Err(format!(
+            "last token fetched {} ago, more than {}",
+            format_duration(elapsed),
+            format_duration(ready_within),
+        )),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Parses `--listen`: `HOST:PORT`, with IPv6 hosts in brackets.
+pub fn parse_listen(value: &str) -> Result<SocketAddr, String> {
+    value.parse().map_err(|_| format!("'{value}' is not an address to listen on; expected e.g. 0.0.0.0:8787 or [::]:8787"))
+}
+
+/// Handles `proxy`: keeps a fresh token for one endpoint and serves it
+/// at `GET /token`, with `/healthz` for liveness and `/readyz` for
+/// readiness, until `SIGTERM`. With `--metrics-listen`, Prometheus
+/// metrics are served on their own address as well.
+///
+/// The token is refreshed before it expires; a failed refresh is
+/// retried after [`RETRY_DELAY`], and `/readyz` starts failing once
+/// no refresh has succeeded for `ready_within`.
+///
+/// # Arguments
+/// * `supervised`: The client and configuration; `SIGHUP` reloads them.
+/// * `options`:    The endpoint, listen address and readiness window.
+pub async fn handle_proxy(mut supervised: Supervised, options: &ProxyOptions<'_>) -> color_eyre::Result<()> {
+    let listener = TcpListener::bind(options.listen).await
+        .map_err(|e| eyre!("Cannot listen on {}: {e}", options.listen))?;
+    let local_addr = listener.local_addr()?;
+    let state = Arc::new(State {
+        token:        Mutex::new(None),
+        last_fetch:   Mutex::new(None),
+        config_valid: AtomicBool::new(true),
+        ready_within: options.ready_within,
+    });
+    crate::status_println!("Proxy for {} listening on http://{local_addr}", options.endpoint);
+    if let Some(addr) = options.metrics_listen {
+        let addr = crate::metrics::serve(addr).await
+            .map_err(|e| eyre!("Cannot listen for metrics on {addr}: {e}"))?;
+        crate::status_println!("Serving metrics at http://{addr}/metrics");
+    }
+
+    let server_state = Arc::clone(&state);
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(respond(stream, Arc::clone(&server_state)));
+        }
+    });
+
+    supervised.client = supervised.client.with_pool();
+    let mut signals = Signals::install()?;
+    let mut next_refresh = tokio::time::Instant::now();
+    let mut current_valid_for = None;
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep_until(next_refresh) => {
+                let delay = match refresh(&supervised, options, current_valid_for).await {
+                    Ok(Refresh::Renewed { token, difficulty }) => {
+                        let delay = refresh_delay(token.valid_for, chrono::Utc::now().timestamp_millis(), options.renewal_margin);
+                        let delay = jittered(delay, random_unit());
+                        current_valid_for = Some(token.valid_for);
+                        *state.token.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(serde_json::to_string(&token)?);
+                        *state.last_fetch.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Instant::now());
+                        crate::status_println!(
+                            "Refreshed the token at difficulty {}; next refresh in {}",
+                            format_count(difficulty),
+                            format_duration(delay),
+                        );
+                        delay
+                    }
+                    Ok(Refresh::Skipped { recheck_in }) => recheck_in,
+                    Err(e) => {
+                        crate::status_println!("Token refresh failed, retrying in {}: {e:#}", format_duration(RETRY_DELAY));
+                        RETRY_DELAY
+                    }
+                };
+                next_refresh = tokio::time::Instant::now() + delay;
+            }
+            signal = signals.recv() => match signal {
+                Signal::Shutdown => {
+                    crate::status_println!("SIGTERM: shutting down");
+                    break;
+                }
+                Signal::Reload => state.config_valid.store(supervised.reload(), Ordering::Relaxed),
+            },
+        }
+    }
+
+    crate::logging::flush();
+    Ok(())
+}
+
+/// What came of one refresh.
+enum Refresh {
+    Renewed { token: IronShieldToken, difficulty: u64 },
+    /// The challenge was over `--max-renewal-difficulty`; the current
+    /// token is kept and the next attempt is due in `recheck_in`.
+    Skipped { recheck_in: Duration },
+}
+
+/// Obtains a new token, recording the run like `validate` does, unless
+/// the challenge is over `--max-renewal-difficulty` and the current
+/// token can wait.
+///
+/// # Arguments
+/// * `supervised`:        The client and configuration.
+/// * `options`:           The endpoint and renewal policy.
+/// * `current_valid_for`: When the token being served expires, if there is one.
+async fn refresh(
+    supervised:        &Supervised,
+    options:           &ProxyOptions<'_>,
+    current_valid_for: Option<i64>,
+) -> color_eyre::Result<Refresh> {
+    let (client, config) = (&supervised.client, &supervised.config);
+    let mut record = RunRecord::new(RunCommand::Validate, options.endpoint);
+    let start_time = Instant::now();
+    let sink = ConsoleSink { verbose: config.verbose };
+    let mut deadline = Deadline::unbounded();
+
+    let fetched = super::solve::fetch(client, config, options.endpoint, &mut deadline, &mut record, &sink).await;
+    if fetched.is_err() {
+        history::record_result(&mut record, start_time.elapsed(), &fetched);
+        crate::metrics::send_statsd(&record, config.verbose);
+    }
+    let fetched = fetched?;
+
+    let difficulty = fetched.challenge.recommended_attempts / 2;
+    if let (Some(max), Some(valid_for)) = (options.max_renewal_difficulty, current_valid_for) {
+        if difficulty > max {
+            let remaining = remaining(valid_for, chrono::Utc::now().timestamp_millis());
+            let thread_count = client.thread_plan(true).thread_count;
+            let estimate = SolveEstimate::new(difficulty, crate::estimate::cached_probe_hash_rate().await, thread_count);
+            match skip_recheck(remaining, estimate.high) {
+                Some(recheck_in) => {
+                    crate::metrics::record_renewal_skipped();
+                    sink.warning(&Warning::new(WarningCode::DifficultyCap, format!(
+                        "Skipped the refresh: difficulty {} is over --max-renewal-difficulty {}; \
+                         serving the current token (expires in {}), checking again in {}",
+                        format_count(difficulty),
+                        format_count(max),
+                        format_duration(remaining),
+                        format_duration(recheck_in),
+                    )).with_data(serde_json::json!({
+                        "difficulty":   difficulty,
+                        "max":          max,
+                        "remaining_ms": remaining.as_millis() as u64,
+                        "skipped":      true,
+                    })));
+                    return Ok(Refresh::Skipped { recheck_in });
+                }
+                None => sink.warning(&Warning::new(WarningCode::DifficultyCap, format!(
+                    "Refreshing at difficulty {} despite --max-renewal-difficulty {}: the current token expires in {}",
+                    format_count(difficulty),
+                    format_count(max),
+                    format_duration(remaining),
+                )).with_data(serde_json::json!({
+                    "difficulty":   difficulty,
+                    "max":          max,
+                    "remaining_ms": remaining.as_millis() as u64,
+                    "skipped":      false,
+                }))),
+            }
+        }
+    }
+
+    crate::prewarm::start();
+    let result = match super::solve::solve_fetched(client, fetched, config, false, &mut deadline, &mut record, &sink).await {
+        Ok(solved) => super::validate::submit(client, config, solved, &mut deadline, &mut record, &sink).await
+            .map(|validated| validated.token),
+        Err(e)     => Err(e),
+    };
+    history::record_result(&mut record, start_time.elapsed(), &result);
+    crate::metrics::send_statsd(&record, config.verbose);
+    result.map(|token| Refresh::Renewed { token, difficulty })
+}
+
+/// How long a token valid until `valid_for` (Unix milliseconds) has left.
+fn remaining(valid_for: i64, now_ms: i64) -> Duration {
+    Duration::from_millis(valid_for.saturating_sub(now_ms).max(0) as u64)
+}
+
+/// How long until a token valid until `valid_for` (Unix milliseconds)
+/// should be replaced.
+fn refresh_delay(valid_for: i64, now_ms: i64, margin: RenewalMargin) -> Duration {
+    let remaining = remaining(valid_for, now_ms);
+    remaining.saturating_sub(margin.before_expiry(remaining)).max(MIN_REFRESH_INTERVAL)
+}
+
+/// When to look again after passing over a challenge that is over the
+/// difficulty cap, or `None` if the current token can't wait.
+///
+/// # Arguments
+/// * `remaining`:      What the current token has left.
+/// * `solve_estimate`: The 90th percentile solve time for the challenge.
+///
+/// # Returns
+/// * `Option<Duration>`: Halfway to the point where the token has just
+///                       enough left to solve in; `None` past it.
+fn skip_recheck(remaining: Duration, solve_estimate: Duration) -> Option<Duration> {
+    let headroom = (solve_estimate * 2).max(MIN_SKIP_HEADROOM);
+    let spare = remaining.checked_sub(headroom).filter(|spare| !spare.is_zero())?;
+    Some((spare / 2).max(MIN_REFRESH_INTERVAL))
+}
+
+/// `delay`, brought forward by up to [`JITTER_SHARE`] of itself.
+///
+/// # Arguments
+/// * `delay`: The scheduled delay.
+/// * `unit`:  A random number in `[0, 1)`.
+fn jittered(delay: Duration, unit: f64) -> Duration {
+    delay.saturating_sub(delay.mul_f64(JITTER_SHARE * unit)).max(MIN_REFRESH_INTERVAL)
+}
+
+/// A random number in `[0, 1)`, from the standard library's randomly
+/// seeded hasher.
+fn random_unit() -> f64 {
+    let bits = std::collections::hash_map::RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Answers one HTTP request and closes the connection.
+async fn respond(mut stream: TcpStream, state: Arc<State>) {
+    let mut request = [0; 1024];
+    let Ok(read) = stream.read(&mut request).await else {
+        return;
+    };
+
+    let request_line = String::from_utf8_lossy(&request[..read]);
+    let path = request_line.strip_prefix("GET ").and_then(|rest| rest.split(' ').next());
+    let (status, content_type, body) = match path {
+        Some("/healthz") => ("200 OK", "text/plain", "ok\n".to_string()),
+        Some("/readyz")  => match state.readiness() {
+            Ok(())      => ("200 OK", "text/plain", "ready\n".to_string()),
+            Err(reason) => ("503 Service Unavailable", "text/plain", format!("not ready: {reason}\n")),
+        },
+        Some("/token")   => match state.token.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone() {
+            Some(token) => ("200 OK", "application/json", format!("{token}\n")),
+            None        => ("503 Service Unavailable", "text/plain", "no token yet\n".to_string()),
+        },
+        _                => ("404 Not Found", "text/plain", String::new()),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len(),
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_readiness() {
+        let within = Duration::from_secs(300);
+
+        assert!(readiness(true, Some(Duration::from_secs(10)), within).is_ok());
+        assert_eq!(readiness(true, None, within), Err("no token fetched yet".to_string()));
+        assert!(readiness(true, Some(Duration::from_secs(301)), within).unwrap_err().starts_with("last token fetched"));
+        assert!(readiness(false, Some(Duration::from_secs(10)), within).unwrap_err().contains("config file"));
+    }
+
+    #[test]
+    fn test_refresh_delay() {
+        let margin = RenewalMargin::Share(0.2);
+        assert_eq!(refresh_delay(30_000, 0, margin), Duration::from_secs(24));
+        assert_eq!(refresh_delay(500, 0, margin), MIN_REFRESH_INTERVAL);
+        assert_eq!(refresh_delay(0, 1_000, margin), MIN_REFRESH_INTERVAL);
+
+        let margin = RenewalMargin::Fixed(Duration::from_secs(10));
+        assert_eq!(refresh_delay(30_000, 0, margin), Duration::from_secs(20));
+        assert_eq!(refresh_delay(5_000, 0, margin), MIN_REFRESH_INTERVAL);
+    }
+
+    #[test]
+    fn test_parse_renewal_margin() {
+        assert_eq!(parse_renewal_margin("20%"), Ok(RenewalMargin::Share(0.2)));
+        assert_eq!(parse_renewal_margin("0.25"), Ok(RenewalMargin::Share(0.25)));
+        assert_eq!(parse_renewal_margin("30s"), Ok(RenewalMargin::Fixed(Duration::from_secs(30))));
+        assert_eq!(parse_renewal_margin("90"), Ok(RenewalMargin::Fixed(Duration::from_secs(90))));
+        assert!(parse_renewal_margin("150%").is_err());
+        assert!(parse_renewal_margin("lots%").is_err());
+    }
+
+    #[test]
+    fn test_skip_recheck() {
+        let minute = Duration::from_secs(60);
+
+        // Ten minutes left and a one-minute solve: look again in four minutes.
+        assert_eq!(skip_recheck(minute * 10, minute), Some(minute * 4));
+        // The floor applies to quick solves.
+        assert_eq!(skip_recheck(Duration::from_secs(90), Duration::ZERO), Some(Duration::from_secs(30)));
+        // Too close to expiry to wait any longer.
+        assert_eq!(skip_recheck(minute * 2, minute), None);
+        assert_eq!(skip_recheck(Duration::from_secs(20), Duration::ZERO), None);
+    }
+
+    #[test]
+    fn test_jitter_only_brings_refreshes_forward() {
+        let delay = Duration::from_secs(100);
+        assert_eq!(jittered(delay, 0.0), delay);
+        assert_eq!(jittered(delay, 0.5), Duration::from_secs(95));
+        assert!(jittered(delay, 0.999) > Duration::from_secs(90));
+        assert_eq!(jittered(Duration::from_millis(500), 0.5), MIN_REFRESH_INTERVAL);
+        for _ in 0..100 {
+            assert!((0.0..1.0).contains(&random_unit()));
+        }
+    }
+
+    #[test]
+    fn test_parse_listen() {
+        assert_eq!(parse_listen("0.0.0.0:8787").unwrap(), "0.0.0.0:8787".parse::<SocketAddr>().unwrap());
+        assert!(parse_listen("[::]:8787").unwrap().is_ipv6());
+        assert!(parse_listen("localhost").unwrap_err().contains("[::]:8787"));
+    }
+}
@@ -0,0 +1,287 @@
+use chrono::{DateTime, Local, Utc};
+use color_eyre::eyre::eyre;
+use futures::StreamExt;
+use ironshield::IronShieldChallengeResponse;
+use ironshield_types::IronShieldToken;
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::api::NoToken;
+use crate::client::CliClient;
+use crate::commands::interchange::{self, Input};
+use crate::display::{format_count, format_duration};
+use crate::queue::{ItemState, Queue, QueueItem, Rejection, SubmitOutcome, SubmitTally};
+use crate::warnings::{Warning, WarningCode};
+
+/// Submits in flight per API when `queue submit` isn't given `--concurrency`.
+pub const DEFAULT_SUBMIT_CONCURRENCY: usize = 4;
+
+/// Handles `queue add`: queues the challenge in `file` to be solved later.
+///
+/// # Arguments
+/// * `file`:         The challenge as JSON or a base64url header,
+///                   decompressed if it ends in `.gz` or `.zst`.
+/// * `api_base_url`: The API its solution will be submitted to.
+pub fn handle_add(file: &Path, api_base_url: &str) -> color_eyre::Result<()> {
+    let challenge = interchange::read_challenge(Input::File(file))?;
+    let queue = open()?;
+    if challenge.expiration_time <= Utc::now().timestamp_millis() {
+        crate::warnings::emit(Warning::new(
+            WarningCode::QueuedExpired,
+            "This challenge has already expired; `queue solve` will skip it.",
+        ));
+    }
+    let item = queue.add(challenge, Some(api_base_url.to_string()))
+        .map_err(|e| eyre!("Cannot queue the challenge in '{}': {e}", queue.dir().display()))?;
+    println!("{}", item.id);
+    crate::status_println!(
+        "Queued {} (difficulty {}, expires {}).",
+        item.id,
+        format_count(item.challenge.recommended_attempts),
+        expiry(&item, Utc::now()),
+    );
+    Ok(())
+}
+
+/// Handles `queue solve`: solves every pending item without contacting
+/// the API. Items that expired before their turn are marked expired and
+/// reported rather than solved.
+///
+/// # Arguments
+/// * `client`:          Solves with its thread plan.
+/// * `single_threaded`: Solve on one thread.
+///
+/// # Returns
+/// * `Result<()>`: An error if any solve failed; those items stay pending.
+pub async fn handle_solve(client: &CliClient, single_threaded: bool) -> color_eyre::Result<()> {
+    let queue = open()?;
+    let pending: Vec<QueueItem> = load(&queue)?.into_iter().filter(|item| item.state == ItemState::Pending).collect();
+    if pending.is_empty() {
+        crate::status_println!("Nothing to solve.");
+        return Ok(());
+    }
+
+    let (mut solved, mut expired, mut failed) = (0, 0, 0);
+    for mut item in pending {
+        let now = Utc::now();
+        if item.is_expired(now) {
+            crate::status_println!("Skipped {}: expired {}.", item.id, expiry(&item, now));
+            item.state = ItemState::Expired;
+            item.error = Some("expired before it was solved".to_string());
+            save(&queue, &item)?;
+            expired += 1;
+            continue;
+        }
+
+        crate::status_println!("Solving {} (difficulty {})...", item.id, format_count(item.challenge.recommended_attempts));
+        let start = std::time::Instant::now();
+        match client.solve_threaded(item.challenge.clone(), !single_threaded, None).await {
+            Ok(solution) => {
+                crate::status_println!("Solved {} in {}.", item.id, format_duration(start.elapsed()));
+                item.state = ItemState::Solved;
+                item.solution = Some(solution);
+                item.solved_at = Some(Utc::now());
+                item.error = None;
+                solved += 1;
+            }
+            Err(e) => {
+                crate::status_println!("Failed to solve {}: {e}", item.id);
+                item.error = Some(e.to_string());
+                failed += 1;
+            }
+        }
+        save(&queue, &item)?;
+    }
+
+    crate::status_println!("Solved {solved}, skipped {expired} expired, {failed} failed.");
+    match failed {
+        0 => Ok(()),
+        _ => Err(eyre!("{failed} queued challenge(s) could not be solved; they stay pending")),
+    }
+}
+
+/// Handles `queue submit`: submits every solved item whose challenge is
+/// still valid and keeps the token. Items are grouped by the API they
+/// came from, and each API gets up to `concurrency` submits in flight
+/// over the client's connection pool. Submits aren't challenge
+/// requests, so the rate limit doesn't apply.
+///
+/// Items that expired while waiting are marked expired. Items the API
+/// rejects for good move to `failed/` with its response; items that
+/// failed on the network, or with a status worth retrying, stay solved
+/// so a later `queue submit` retries them.
+///
+/// # Arguments
+/// * `client`:      Submits to the configured API, or the one an item came from.
+/// * `concurrency`: Submits in flight per API, at least 1.
+///
+/// # Returns
+/// * `Result<()>`: An error if any solution wasn't submitted.
+pub async fn handle_submit(client: &CliClient, concurrency: usize) -> color_eyre::Result<()> {
+    let concurrency = concurrency.max(1);
+    let queue = open()?;
+    let solved: Vec<QueueItem> = load(&queue)?.into_iter().filter(|item| item.state == ItemState::Solved).collect();
+    if solved.is_empty() {
+        crate::status_println!("Nothing to submit.");
+        return Ok(());
+    }
+
+    let (mut tally, mut moved) = (SubmitTally::default(), 0);
+    let mut origins: BTreeMap<String, Vec<(QueueItem, IronShieldChallengeResponse)>> = BTreeMap::new();
+    for mut item in solved {
+        let now = Utc::now();
+        let Some(solution) = item.solution.clone().filter(|_| !item.is_expired(now)) else {
+            crate::status_println!("Skipped {}: expired {}.", item.id, expiry(&item, now));
+            item.state = ItemState::Expired;
+            item.error = Some("expired before it was submitted".to_string());
+            save(&queue, &item)?;
+            tally.record(SubmitOutcome::RejectedExpired);
+            continue;
+        };
+        let origin = item.api_base_url.clone().unwrap_or_else(|| client.config().api_base_url.clone());
+        origins.entry(origin.trim_end_matches('/').to_string()).or_default().push((item, solution));
+    }
+
+    for (origin, items) in origins {
+        crate::status_println!("Submitting {} solution(s) to {origin}, {concurrency} at a time...", items.len());
+
+        let origin = origin.as_str();
+        let mut submits = futures::stream::iter(items)
+            .map(|(item, solution)| async move {
+                (item, client.submit_to(origin, &solution).await.map_err(SubmitFailure::of))
+            })
+            .buffer_unordered(concurrency);
+        while let Some((item, result)) = submits.next().await {
+            let outcome = settle(&queue, item, result)?;
+            moved += usize::from(outcome.is_permanent());
+            tally.record(outcome);
+        }
+    }
+
+    tally.remaining = load(&queue)?.iter().filter(|item| item.state == ItemState::Solved).count();
+    crate::status_println!("{}", tally.describe());
+    if moved > 0 {
+        crate::status_println!("Moved {moved} rejected item(s), with the API's response, to '{}'.", queue.failed_dir().display());
+    }
+    match tally.rejected_invalid + tally.network_failed {
+        0      => Ok(()),
+        failed => Err(eyre!("{failed} solution(s) could not be submitted")),
+    }
+}
+
+/// Why a submit from `queue submit` got no token.
+struct SubmitFailure {
+    /// `None` when no response came back.
+    status: Option<u16>,
+    body:   String,
+}
+
+impl SubmitFailure {
+    fn of(error: color_eyre::Report) -> Self {
+        match error.downcast::<NoToken>() {
+            Ok(NoToken { status, reason }) if status.is_success() => {
+                Self { status: Some(status.as_u16()), body: format!("a token that can't be read: {reason}") }
+            }
+            Ok(NoToken { status, reason }) => Self { status: Some(status.as_u16()), body: reason },
+            Err(error)                     => Self { status: None, body: error.to_string() },
+        }
+    }
+}
+
+/// Reports and records how the submit of `item` ended.
+fn settle(queue: &Queue, mut item: QueueItem, result: Result<IronShieldToken, SubmitFailure>) -> color_eyre::Result<SubmitOutcome> {
+    let failure = match result {
+        Ok(token) => {
+            crate::status_println!("Submitted {}; token valid until {}.", item.id, token.valid_for);
+            item.state = ItemState::Submitted;
+            item.token = Some(token);
+            item.submitted_at = Some(Utc::now());
+            item.error = None;
+            save(queue, &item)?;
+            return Ok(SubmitOutcome::Submitted);
+        }
+        Err(failure) => failure,
+    };
+
+    let outcome = SubmitOutcome::of_failure(failure.status, &failure.body);
+    let reason = match failure.status {
+        Some(status) => format!("HTTP {status}: {}", failure.body),
+        None         => failure.body.clone(),
+    };
+    item.error = Some(reason.clone());
+    match (outcome.is_permanent(), failure.status) {
+        (true, Some(status)) => {
+            crate::status_println!("Rejected {}: {reason}", item.id);
+            item.state = ItemState::Rejected;
+            item.rejection = Some(Rejection { status, body: failure.body, at: Utc::now() });
+            queue.reject(&item)
+                .map_err(|e| eyre!("Cannot move {} to '{}': {e}", item.id, queue.failed_dir().display()))?;
+        }
+        _ => {
+            crate::status_println!("Failed to submit {}: {reason}; it stays queued.", item.id);
+            save(queue, &item)?;
+        }
+    }
+    Ok(outcome)
+}
+
+/// Handles `queue list`: prints every item, oldest first, with its age
+/// and when its challenge expires.
+///
+/// # Arguments
+/// * `json`: Print a JSON array of items instead of a table.
+pub fn handle_list(json: bool) -> color_eyre::Result<()> {
+    let items = load(&open()?)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&items)?);
+        return Ok(());
+    }
+    if items.is_empty() {
+        crate::status_println!("The queue is empty.");
+        return Ok(());
+    }
+
+    let now = Utc::now();
+    println!(
+        "{:<24}  {:<9}  {:<19}  {:>10}  {:<16}  {:>14}  Error",
+        "ID", "State", "Added", "Age", "Expires", "Difficulty",
+    );
+    for item in &items {
+        println!(
+            "{:<24}  {:<9}  {:<19}  {:>10}  {:<16}  {:>14}  {}",
+            item.id,
+            item.state.name(),
+            item.added_at.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"),
+            format_duration((now - item.added_at).to_std().unwrap_or_default()),
+            expiry(item, now),
+            format_count(item.challenge.recommended_attempts),
+            item.error.as_deref().unwrap_or(crate::display::glyphs().missing),
+        );
+    }
+    Ok(())
+}
+
+/// When `item` expires, relative to `now`: "in 1m 30.0s" or "12.0s ago".
+fn expiry(item: &QueueItem, now: DateTime<Utc>) -> String {
+    let Some(expires_at) = item.expires_at() else {
+        return crate::display::glyphs().missing.to_string();
+    };
+    match (expires_at - now).to_std() {
+        Ok(left) => format!("in {}", format_duration(left)),
+        Err(_)   => format!("{} ago", format_duration((now - expires_at).to_std().unwrap_or_default())),
+    }
+}
+
+fn open() -> color_eyre::Result<Queue> {
+    Queue::default_location().ok_or_else(|| eyre!("This platform has no data directory to keep the queue in"))
+}
+
+fn load(queue: &Queue) -> color_eyre::Result<Vec<QueueItem>> {
+    queue.load().map_err(|e| eyre!("Cannot read the queue in '{}': {e}", queue.dir().display()))
+}
+
+fn save(queue: &Queue, item: &QueueItem) -> color_eyre::Result<()> {
+    queue.save(item).map_err(|e| eyre!("Cannot update {} in '{}': {e}", item.id, queue.dir().display()))
+}
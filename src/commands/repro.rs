@@ -0,0 +1,110 @@
+use color_eyre::eyre::eyre;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::display::{format_count, format_duration};
+use crate::repro::Bundle;
+use crate::solve::{SolverPool, ThreadPlan};
+
+/// Handles `repro`: solves a bundle's challenge again with the same
+/// threads, priority and work split, and compares the outcome with the
+/// recorded one.
+///
+/// # Arguments
+/// * `dir`: A bundle written by `solve --dump-repro`.
+///
+/// # Returns
+/// * `Result<()>`: An error if the outcome differs.
+pub async fn handle_repro(dir: &Path) -> color_eyre::Result<()> {
+    let bundle = Bundle::load(dir)?;
+    let settings = bundle.settings;
+    let recorded = match (&bundle.outcome.solution, &bundle.outcome.error) {
+        (Some(solution), _) => Ok(solution.solution as u64),
+        (None, error)       => Err(error.clone().unwrap_or_else(|| "unknown error".to_string())),
+    };
+
+    println!(
+        "Bundle {} from ironshield {} on {}",
+        bundle.manifest.format_version,
+        bundle.manifest.versions.cli,
+        bundle.manifest.machine.describe(),
+    );
+    println!(
+        "Recorded: {} after {} ({}, {} split)",
+        describe(&recorded),
+        format_duration(Duration::from_millis(bundle.outcome.elapsed_ms)),
+        settings.plan.describe(),
+        settings.work_split.name(),
+    );
+
+    let plan = ThreadPlan { work_split: settings.work_split, ..settings.plan };
+    let pool = SolverPool::new(plan.thread_count, plan.priority);
+    let start = Instant::now();
+    let replayed = pool.solve(&plan, Arc::new(bundle.challenge), None).await
+        .map(|solution| solution.solution as u64);
+    let elapsed = start.elapsed();
+    let _ = tokio::task::spawn_blocking(move || drop(pool)).await;
+    println!("Replayed: {} after {}", describe(&replayed), format_duration(elapsed));
+
+    match compare(&recorded, &replayed, settings.plan.thread_count) {
+        Ok(verdict)  => {
+            println!("{verdict}");
+            Ok(())
+        }
+        Err(verdict) => Err(eyre!("{verdict}")),
+    }
+}
+
+fn describe(outcome: &Result<u64, String>) -> String {
+    match outcome {
+        Ok(nonce) => format!("solved at nonce {}", format_count(*nonce)),
+        Err(e)    => format!("failed ({e})"),
+    }
+}
+
+/// Whether a replay reproduced the recorded outcome.
+///
+/// Threads race to the first solution, so a multithreaded solve may
+/// find a different valid nonce each time; a single thread searches in
+/// order and must find the same one.
+///
+/// # Returns
+/// * `Result<String, String>`: The verdict, as an error if it differs.
+fn compare(recorded: &Result<u64, String>, replayed: &Result<u64, String>, threads: usize) -> Result<String, String> {
+    match (recorded, replayed) {
+        (Ok(old), Ok(new)) if old == new => Ok(format!("Reproduced: nonce {} both times", format_count(*new))),
+        (Ok(old), Ok(new)) if threads > 1 => Ok(format!(
+            "Reproduced: solved both times; nonce {} instead of {}, as expected when {threads} threads race",
+            format_count(*new),
+            format_count(*old),
+        )),
+        (Ok(old), Ok(new)) => Err(format!(
+            "Outcome differs: one thread found nonce {} but the recording has {}",
+            format_count(*new),
+            format_count(*old),
+        )),
+        (Err(_), Err(e)) => Ok(format!("Reproduced: failed both times ({e})")),
+        (Ok(old), Err(e)) => Err(format!("Outcome differs: recorded a solution at nonce {}, replay failed ({e})", format_count(*old))),
+        (Err(e), Ok(new)) => Err(format!("Outcome differs: recorded a failure ({e}), replay solved at nonce {}", format_count(*new))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare() {
+        let solved = |nonce: u64| Ok::<u64, String>(nonce);
+        let failed = || Err::<u64, String>("No solution found".to_string());
+
+        assert!(compare(&solved(7), &solved(7), 1).unwrap().starts_with("Reproduced: nonce 7"));
+        assert!(compare(&solved(7), &solved(9), 4).unwrap().contains("4 threads race"));
+        assert!(compare(&solved(7), &solved(9), 1).is_err());
+        assert!(compare(&failed(), &failed(), 1).is_ok());
+        assert!(compare(&solved(7), &failed(), 4).unwrap_err().contains("replay failed"));
+        assert!(compare(&failed(), &solved(7), 4).unwrap_err().contains("recorded a failure"));
+    }
+}
@@ -0,0 +1,74 @@
+use serde_json::json;
+use ironshield::ClientConfig;
+
+use std::time::{Duration, Instant};
+
+use super::solve::{fetch_and_solve, Solved};
+use crate::client::CliClient;
+use crate::deadline::Deadline;
+use crate::display::format_duration;
+use crate::history::{self, RunCommand, RunRecord};
+use crate::output::OutputSink;
+use crate::retry::InteractiveRetry;
+
+/// e.g. `Stage timings: fetch 120ms, solve 2.3s (total 2.4s)`.
+fn describe_timings(solved: &Solved, total: Duration) -> String {
+    format!(
+        "Stage timings: fetch {}, solve {} (total {})",
+        format_duration(solved.fetch),
+        format_duration(solved.solve),
+        format_duration(total),
+    )
+}
+
+/// Handles the run command: fetches and solves a challenge without
+/// submitting it, then emits the challenge and solution together with
+/// how long each stage took.
+///
+/// # Arguments
+/// * `client`:          The API client.
+/// * `config`:          The client configuration.
+/// * `endpoint`:        The protected endpoint.
+/// * `single_threaded`: Solve on one thread.
+/// * `max_time`:        Give up once fetching and solving take this long.
+/// * `sink`:            Where status lines and the result go.
+pub async fn handle_run(
+    client:          &CliClient,
+    config:          &ClientConfig,
+    endpoint:        &str,
+    single_threaded: bool,
+    max_time:        Option<Duration>,
+    sink:            &dyn OutputSink,
+) -> color_eyre::Result<()> {
+    let mut retry = InteractiveRetry::new(client, config);
+    let (solved, total) = loop {
+        let mut record = RunRecord::new(RunCommand::Solve, endpoint);
+        let start_time = Instant::now();
+        let mut deadline = Deadline::start(max_time);
+
+        let result = fetch_and_solve(retry.client(), retry.config(), endpoint, single_threaded, &mut deadline, &mut record, sink).await;
+        history::record_result(&mut record, start_time.elapsed(), &result);
+        crate::metrics::send_statsd(&record, config.verbose);
+        match result {
+            Ok(solved)                                   => break (solved, start_time.elapsed()),
+            Err(e) if retry.offer(&e, record.error_kind) => continue,
+            Err(e)                                       => return Err(e),
+        }
+    };
+
+    sink.info(&describe_timings(&solved, total));
+    sink.result_json(json!({
+        "challenge": solved.challenge,
+        "solution":  solved.solution,
+        "timings":   {
+            "fetch_ms": solved.fetch.as_millis() as u64,
+            "solve_ms": solved.solve.as_millis() as u64,
+            "total_ms": total.as_millis() as u64,
+        },
+    }), Some(solved.negotiated));
+
+    crate::logging::flush();
+
+    crate::har::write();
+    std::process::exit(0);
+}
@@ -0,0 +1,268 @@
+use color_eyre::eyre::eyre;
+use ironshield::IronShieldChallenge;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinSet;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::daemon::{Request as Signal, Signals, Supervised};
+use crate::deadline::{Deadline, DeadlineExceeded, Stage};
+use crate::display::{format_count, format_duration, parse_duration};
+use crate::history::{self, RunCommand, RunRecord};
+use crate::output::ConsoleSink;
+use crate::serve::{HttpRequest, JobKind, Jobs, ServePolicy};
+
+/// How long a client has to send its whole request.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The body of `POST /validate`.
+#[derive(Debug, Deserialize)]
+struct ValidateRequest {
+    endpoint: String,
+}
+
+/// What a request is answered with.
+struct Response {
+    status: u16,
+    body:   Value,
+}
+
+impl Response {
+    fn ok(body: Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self { status, body: json!({ "error": message.into() }) }
+    }
+}
+
+/// Handles `serve`: answers JSON requests over HTTP until `SIGTERM`.
+///
+/// * `POST /solve` takes an `IronShieldChallenge` and returns its
+///   `IronShieldChallengeResponse`.
+/// * `POST /validate` takes `{"endpoint": "..."}`, fetches, solves and
+///   submits like `validate`, and returns the token.
+/// * `GET /status` lists the jobs in flight.
+///
+/// Both POSTs take `?max_time=30s`, capped by `[serve] max_time`, and
+/// get 429 beyond `[serve] max_concurrent` jobs. With `[serve] token`
+/// set, every request needs `Authorization: Bearer <token>`.
+///
+/// `SIGTERM` stops accepting connections, lets the jobs in flight
+/// finish and exits 0. `SIGHUP` reloads the config file's client
+/// settings for later requests.
+///
+/// # Arguments
+/// * `supervised`: The client and configuration.
+/// * `listen`:     The address to listen on.
+/// * `policy`:     The `[serve]` settings.
+pub async fn handle_serve(mut supervised: Supervised, listen: SocketAddr, policy: ServePolicy) -> color_eyre::Result<()> {
+    if policy.token.is_none() && !listen.ip().is_loopback() {
+        return Err(eyre!(
+            "Refusing to serve on {listen} without authentication; set `token` under [serve], or listen on a loopback address",
+        ));
+    }
+    if let Some(token) = &policy.token {
+        crate::redact::add_secret(token);
+    }
+    let listener = TcpListener::bind(listen).await.map_err(|e| eyre!("Cannot listen on {listen}: {e}"))?;
+    crate::status_println!(
+        "Serving on http://{} ({} job(s) at once{})",
+        listener.local_addr()?,
+        policy.max_concurrent,
+        if policy.token.is_some() { ", bearer token required" } else { "" },
+    );
+
+    supervised.client = supervised.client.with_pool();
+    let mut service = Arc::new(supervised);
+    let policy = Arc::new(policy);
+    let jobs = Jobs::new(policy.max_concurrent);
+    let mut signals = Signals::install()?;
+    let mut connections = JoinSet::new();
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    connections.spawn(respond(stream, Arc::clone(&service), Arc::clone(&policy), Arc::clone(&jobs)));
+                }
+                Err(e) => crate::status_println!("Cannot accept a connection: {e}"),
+            },
+            Some(_) = connections.join_next() => {}
+            signal = signals.recv() => match signal {
+                Signal::Shutdown => {
+                    crate::status_println!("SIGTERM: shutting down once {} job(s) in flight finish", jobs.in_flight());
+                    break;
+                }
+                Signal::Reload => service = reload(&service),
+            },
+        }
+    }
+
+    drop(listener);
+    while connections.join_next().await.is_some() {}
+    crate::logging::flush();
+    Ok(())
+}
+
+/// Reloads the config file into a new client and configuration; the
+/// requests in flight keep the ones they started with.
+fn reload(current: &Arc<Supervised>) -> Arc<Supervised> {
+    let mut next = Supervised {
+        client:      current.client.clone(),
+        config:      current.config.clone(),
+        config_path: current.config_path.clone(),
+    };
+    next.reload();
+    Arc::new(next)
+}
+
+/// Answers one request and closes the connection.
+async fn respond(mut stream: TcpStream, service: Arc<Supervised>, policy: Arc<ServePolicy>, jobs: Arc<Jobs>) {
+    let response = match tokio::time::timeout(READ_TIMEOUT, crate::serve::read_request(&mut stream)).await {
+        Ok(Ok(request)) => route(&request, &service, &policy, &jobs).await,
+        Ok(Err(e))      => match e.response() {
+            Some((status, message)) => Response::error(status, message),
+            None                    => return,
+        },
+        Err(_)          => Response::error(408, "the request took too long to arrive"),
+    };
+
+    let body = format!("{}\n", response.body);
+    let authenticate = if response.status == 401 { "WWW-Authenticate: Bearer\r\n" } else { "" };
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{authenticate}Connection: close\r\n\r\n",
+        response.status,
+        reason(response.status),
+        body.len(),
+    );
+    let _ = stream.write_all(format!("{head}{body}").as_bytes()).await;
+}
+
+async fn route(request: &HttpRequest, service: &Supervised, policy: &ServePolicy, jobs: &Arc<Jobs>) -> Response {
+    if !crate::serve::authorized(policy.token.as_deref(), request.header("authorization")) {
+        return Response::error(401, "missing or wrong bearer token");
+    }
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/status")    => Response::ok(jobs.status()),
+        ("POST", "/solve")    => solve(request, service, policy, jobs).await,
+        ("POST", "/validate") => validate(request, service, policy, jobs).await,
+        (_, "/status" | "/solve" | "/validate") => Response::error(405, format!("{} is not allowed on {}", request.method, request.path)),
+        _ => Response::error(404, format!("no such endpoint {}; try POST /solve, POST /validate or GET /status", request.path)),
+    }
+}
+
+/// `POST /solve`: solves the challenge in the body.
+async fn solve(request: &HttpRequest, service: &Supervised, policy: &ServePolicy, jobs: &Arc<Jobs>) -> Response {
+    let challenge: IronShieldChallenge = match serde_json::from_slice(&request.body) {
+        Ok(challenge) => challenge,
+        Err(e)        => return Response::error(400, format!("the body isn't an IronShieldChallenge: {e}")),
+    };
+    let mut deadline = match deadline(request, policy) {
+        Ok(deadline)  => deadline,
+        Err(response) => return response,
+    };
+    if challenge.expiration_time <= chrono::Utc::now().timestamp_millis() {
+        return Response::error(422, "the challenge has expired");
+    }
+    let difficulty = challenge.recommended_attempts / 2;
+    let Some(job) = jobs.try_start(JobKind::Solve { difficulty }) else {
+        return busy(jobs);
+    };
+
+    deadline.tighten_to_expiry(challenge.expiration_time);
+    let start = Instant::now();
+    let solved = deadline.limit(Stage::Solve, service.client.solve_threaded(challenge, true, None)).await;
+    match solved {
+        Ok(Ok(solution)) => {
+            job.succeeded();
+            crate::status_println!(
+                "Solved a challenge at difficulty {} in {}",
+                format_count(difficulty),
+                format_duration(start.elapsed()),
+            );
+            match serde_json::to_value(solution) {
+                Ok(body) => Response::ok(body),
+                Err(e)   => Response::error(500, e.to_string()),
+            }
+        }
+        Ok(Err(e)) => Response::error(500, format!("{e:#}")),
+        Err(e)     => Response::error(504, e.to_string()),
+    }
+}
+
+/// `POST /validate`: fetches, solves and submits for the endpoint in
+/// the body, recording the run in the history like `validate`.
+async fn validate(request: &HttpRequest, service: &Supervised, policy: &ServePolicy, jobs: &Arc<Jobs>) -> Response {
+    let body: ValidateRequest = match serde_json::from_slice(&request.body) {
+        Ok(body) => body,
+        Err(e)   => return Response::error(400, format!("expected {{\"endpoint\": \"...\"}}: {e}")),
+    };
+    let mut deadline = match deadline(request, policy) {
+        Ok(deadline)  => deadline,
+        Err(response) => return response,
+    };
+    let endpoint = body.endpoint.as_str();
+    let Some(job) = jobs.try_start(JobKind::Validate { endpoint: crate::redact::mask_url_password(endpoint).into_owned() }) else {
+        return busy(jobs);
+    };
+
+    let (client, config) = (&service.client, &service.config);
+    let mut record = RunRecord::new(RunCommand::Validate, endpoint);
+    let start = Instant::now();
+    let sink = ConsoleSink { verbose: config.verbose };
+    let result = super::validate::validate(client, config, endpoint, false, &mut deadline, &mut record, &sink).await
+        .map(|validated| validated.token);
+    history::record_result(&mut record, start.elapsed(), &result);
+    crate::metrics::send_statsd(&record, config.verbose);
+
+    match result {
+        Ok(token) => {
+            job.succeeded();
+            match serde_json::to_value(token) {
+                Ok(body) => Response::ok(body),
+                Err(e)   => Response::error(500, e.to_string()),
+            }
+        }
+        Err(e) if e.downcast_ref::<DeadlineExceeded>().is_some() => Response::error(504, format!("{e:#}")),
+        Err(e) => Response::error(502, format!("{e:#}")),
+    }
+}
+
+/// The request's deadline: its `max_time`, capped by `[serve] max_time`.
+fn deadline(request: &HttpRequest, policy: &ServePolicy) -> Result<Deadline, Response> {
+    let requested = request.query("max_time")
+        .map(parse_duration)
+        .transpose()
+        .map_err(|e| Response::error(400, format!("bad max_time: {e}")))?;
+    let setting = if requested.is_some_and(|requested| requested <= policy.max_time) { "max_time" } else { "[serve] max_time" };
+    Ok(Deadline::within(policy.time_for(requested), setting))
+}
+
+fn busy(jobs: &Jobs) -> Response {
+    Response::error(429, format!("{} job(s) already running; try again later", jobs.in_flight()))
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        411 => "Length Required",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        429 => "Too Many Requests",
+        502 => "Bad Gateway",
+        504 => "Gateway Timeout",
+        _   => "Internal Server Error",
+    }
+}
@@ -0,0 +1,209 @@
+use color_eyre::eyre::eyre;
+use ironshield::ClientConfig;
+
+use std::path::Path;
+use std::time::Duration;
+
+use crate::benchmark::{self, ThreadResult};
+use crate::config::ConfigManager;
+use crate::display::{format_duration, format_hash_rate};
+use crate::logging::ColorChoice;
+use crate::prompt;
+
+/// Total time spent benchmarking to suggest `num_threads`.
+const BENCHMARK_TIME: Duration = Duration::from_secs(2);
+
+/// How long the connectivity check waits for the API.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How `setup` runs.
+pub struct SetupOptions<'a> {
+    /// Where the config file is written.
+    pub path:         &'a Path,
+    /// Take every suggestion without asking.
+    pub defaults:     bool,
+    /// Suggested instead of the library's default `api_base_url`.
+    pub api_base_url: Option<String>,
+    /// Overwrite an existing file without asking.
+    pub assume_yes:   bool,
+}
+
+/// The answers the config file is written from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Answers {
+    api_base_url: String,
+    num_threads:  Option<usize>,
+    verbose:      bool,
+    color:        ColorChoice,
+}
+
+/// Handles `setup`: asks for the API URL, checks that it answers,
+/// benchmarks to suggest a thread count, asks about verbosity and
+/// color, then writes the config file.
+///
+/// Without a terminal, or with `--defaults`, every suggestion is taken.
+pub async fn handle_setup(options: &SetupOptions<'_>) -> color_eyre::Result<()> {
+    let interactive = !options.defaults && prompt::is_interactive();
+    if !options.defaults && !interactive {
+        crate::status_println!("No terminal to ask on; taking every suggestion, as with --defaults.");
+    }
+    let path = options.path;
+    if path.exists() && !options.assume_yes {
+        let overwrite = interactive && prompt::confirm(&format!("'{}' exists. Overwrite it?", path.display()));
+        if !overwrite {
+            return Err(eyre!("'{}' already exists; pass --yes to overwrite it", path.display()));
+        }
+    }
+
+    let defaults = ClientConfig::default();
+    let suggested_url = options.api_base_url.clone().unwrap_or_else(|| defaults.api_base_url.clone());
+    let api_base_url = ask_until(interactive, "API base URL", &suggested_url, |url| {
+        reqwest::Url::parse(url).map(|_| url.to_string()).map_err(|e| format!("Not a URL: {e}"))
+    })?;
+    check_connectivity(&api_base_url).await;
+
+    let suggested_threads = suggest_threads().await?.map_or("auto".to_string(), |n| n.to_string());
+    let num_threads = ask_until(interactive, "Solver threads (`auto` for all cores)", &suggested_threads, |answer| {
+        match answer {
+            "auto" => Ok(None),
+            count  => count.parse::<usize>().ok().filter(|&n| n > 0).map(Some)
+                .ok_or_else(|| "Enter a thread count or `auto`".to_string()),
+        }
+    })?;
+
+    let verbose = interactive && prompt::confirm("Verbose output by default?");
+    let color = ask_until(interactive, "Color output (auto, always, never)", "auto", |answer| {
+        <ColorChoice as clap::ValueEnum>::from_str(answer, true).map_err(|_| "Enter auto, always or never".to_string())
+    })?;
+
+    let answers = Answers { api_base_url, num_threads, verbose, color };
+    write_config(path, &answers)?;
+
+    crate::status_println!("Wrote {}", path.display());
+    println!("{}", next_steps(path));
+    Ok(())
+}
+
+/// Asks until `parse` accepts the answer; without a terminal, parses
+/// the suggestion.
+///
+/// # Returns
+/// * `Result<T>`: The parsed answer, or an error if the suggestion
+///                itself doesn't parse and nobody can be asked.
+fn ask_until<T>(
+    interactive: bool,
+    question:    &str,
+    suggestion:  &str,
+    parse:       impl Fn(&str) -> Result<T, String>,
+) -> color_eyre::Result<T> {
+    if !interactive {
+        return parse(suggestion).map_err(|e| eyre!("{question}: {e}"));
+    }
+    loop {
+        match parse(&prompt::ask(&format!("{question}?"), suggestion)) {
+            Ok(value) => return Ok(value),
+            Err(e)    => eprintln!("{e}"),
+        }
+    }
+}
+
+/// Reports whether the API answers. Any HTTP response counts, since
+/// the base URL itself needn't serve anything; only not getting one
+/// is a problem, and even that doesn't stop setup.
+async fn check_connectivity(api_base_url: &str) {
+    crate::status_println!("Checking that {api_base_url} answers...");
+    let response = match reqwest::Client::builder().timeout(CONNECT_TIMEOUT).build() {
+        Ok(client) => crate::har::send(client.get(api_base_url)).await.0,
+        Err(e)     => {
+            crate::status_println!("  Skipped: {e}");
+            return;
+        }
+    };
+    match response {
+        Ok(response) => crate::status_println!("  Reachable (HTTP {}).", response.status().as_u16()),
+        Err(e)       => crate::status_println!(
+            "  Not reachable: {e}. The file is written anyway; check the URL, proxy and firewall before solving.",
+        ),
+    }
+}
+
+/// Benchmarks each default thread count for a share of
+/// [`BENCHMARK_TIME`] and suggests one.
+async fn suggest_threads() -> color_eyre::Result<Option<usize>> {
+    let counts = benchmark::default_thread_counts(num_cpus::get());
+    let share = BENCHMARK_TIME / counts.len() as u32;
+    crate::status_println!("Benchmarking for {} to suggest a thread count...", format_duration(BENCHMARK_TIME));
+
+    let mut results = Vec::new();
+    for threads in counts {
+        let hash_rate = tokio::task::spawn_blocking(move || benchmark::measure(threads, share)).await?;
+        results.push(ThreadResult { threads, hash_rate });
+    }
+    let suggestion = benchmark::suggest_threads(&results);
+    if let Some(best) = results.iter().find(|result| Some(result.threads) == suggestion) {
+        crate::status_println!("  {} thread(s) reach {}.", best.threads, format_hash_rate(best.hash_rate));
+    }
+    Ok(suggestion)
+}
+
+/// Writes the library's settings and the CLI-only `color` in the
+/// format the path's extension names.
+fn write_config(path: &Path, answers: &Answers) -> color_eyre::Result<()> {
+    let mut config = ClientConfig::default();
+    config.api_base_url = answers.api_base_url.clone();
+    config.num_threads = answers.num_threads;
+    config.set_verbose(answers.verbose);
+
+    let mut table = toml::Table::try_from(&config)?;
+    if answers.color != ColorChoice::Auto {
+        table.insert("color".to_string(), toml::Value::try_from(answers.color)?);
+    }
+    let path_str = path.to_str().ok_or_else(|| eyre!("'{}' is not valid UTF-8", path.display()))?;
+    ConfigManager::save_config(path_str, &table)
+        .map_err(|e| eyre!("Cannot write '{}': {e}", path.display()))?;
+    Ok(())
+}
+
+/// Commands to try next with the new file.
+fn next_steps(path: &Path) -> String {
+    let config = path.display();
+    format!(
+        "Next steps:\n  \
+           ironshield --config {config} fetch https://example.com/protected\n  \
+           ironshield --config {config} validate https://example.com/protected\n  \
+           ironshield --config {config} benchmark",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_config_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ironshield.toml");
+        let answers = Answers {
+            api_base_url: "https://api.example.test".to_string(),
+            num_threads:  Some(4),
+            verbose:      true,
+            color:        ColorChoice::Never,
+        };
+
+        write_config(&path, &answers).unwrap();
+
+        let path = path.to_str().unwrap();
+        let config = ConfigManager::load_client_config(path).unwrap();
+        assert_eq!(config.api_base_url, "https://api.example.test");
+        assert_eq!(config.num_threads, Some(4));
+        assert!(config.verbose);
+        assert_eq!(ConfigManager::load_cli_config(Some(path)).unwrap().color, ColorChoice::Never);
+    }
+
+    #[test]
+    fn test_suggestions_are_taken_without_a_terminal() {
+        let parse = |answer: &str| answer.parse::<usize>().map_err(|e| e.to_string());
+        assert_eq!(ask_until(false, "Threads", "4", parse).unwrap(), 4);
+        assert!(ask_until(false, "Threads", "many", parse).unwrap_err().to_string().starts_with("Threads: "));
+    }
+}
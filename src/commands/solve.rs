@@ -1,93 +1,202 @@
 use ironshield::{
     ClientConfig,
-    IronShieldClient, 
-    IronShieldChallenge, 
-    IronShieldChallengeResponse, 
-    SolveConfig, 
+    IronShieldChallenge,
+    IronShieldChallengeResponse,
     ProgressTracker,
-    solve_challenge,
 };
 
-use ironshield::handler::error::ErrorHandler;
-
+use crate::api::ChallengeEnvelope;
+use crate::api_version::Negotiated;
+use crate::client::CliClient;
+use crate::deadline::{Deadline, Stage};
+use crate::energy;
+use crate::first_progress::FirstProgressTracker;
+use crate::history::{self, ErrorKind, RunCommand, RunRecord};
+use crate::logging::LogCategory;
+use crate::metadata::ChallengeMetadata;
+use crate::output::OutputSink;
+use crate::refetch::StaleChallenges;
+use crate::resource;
+use crate::solve::ThreadPlan;
+use crate::retry::InteractiveRetry;
+use crate::throttle::ThrottleTracker;
+use crate::warnings::{Warning, WarningCode};
 use crate::display::{
     ProgressAnimation, 
-    format_number_with_commas
+    format_bytes,
+    format_count,
+    format_duration,
+    format_hash_rate,
 };
 
-use std::time::Instant;
+use std::path::Path;
+use std::time::{Duration, Instant};
 use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Progress summed over every thread at one moment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ProgressSummary {
+    total_attempts:   u64,
+    total_hash_rate:  u64,
+    slowest_attempts: u64,
+    fastest_attempts: u64,
+}
 
 /// Progress tracker that logs detailed per-thread progress with throttling
 struct VerboseProgressTracker {
-    last_logged: Mutex<HashMap<usize, u64>>,
-    thread_count: usize,
+    /// Each thread's latest cumulative attempts, indexed by thread id.
+    attempts:    Box<[AtomicU64]>,
+    /// Each thread's latest hash rate, indexed by thread id.
+    hash_rates:  Box<[AtomicU64]>,
+    /// Attempts at each thread's last log line, so threads only
+    /// contend for `last_total` when they are about to log.
+    last_logged: Box<[AtomicU64]>,
+    /// The last total logged; held while summing and logging so
+    /// totals are printed in order.
+    last_total:  Mutex<u64>,
 }
 
 impl VerboseProgressTracker {
     fn new(thread_count: usize) -> Self {
+        let slots = || -> Box<[AtomicU64]> { (0..thread_count.max(1)).map(|_| AtomicU64::new(0)).collect() };
         Self {
-            last_logged: Mutex::new(HashMap::new()),
-            thread_count,
+            attempts:    slots(),
+            hash_rates:  slots(),
+            last_logged: slots(),
+            last_total:  Mutex::new(0),
         }
     }
-}
 
-impl ProgressTracker for VerboseProgressTracker {
-    fn on_progress(&self, thread_id: usize, total_attempts: u64, hash_rate: u64, _elapsed: std::time::Duration) {
-        let mut last_logged_map = self.last_logged.lock().unwrap();
-        let last_logged_attempts = last_logged_map.get(&thread_id).copied().unwrap_or(0);
+    /// Records one thread's progress and, every 500,000 attempts on that
+    /// thread, passes the summed progress to `emit`. Totals passed to
+    /// `emit` never decrease.
+    fn record(&self, thread_id: usize, attempts: u64, hash_rate: u64, emit: impl FnOnce(&ProgressSummary)) {
+        let (Some(slot), Some(rate), Some(last_logged)) =
+            (self.attempts.get(thread_id), self.hash_rates.get(thread_id), self.last_logged.get(thread_id))
+        else {
+            return;
+        };
+        // A late report from a chunk that was overtaken must not pull the total back.
+        slot.fetch_max(attempts, Ordering::Relaxed);
+        rate.store(hash_rate, Ordering::Relaxed);
 
         // Only log every 500,000 attempts to avoid spam
-        if total_attempts - last_logged_attempts >= 500_000 {
-            // Calculate estimated total attempts across all threads
-            let estimated_total_attempts = total_attempts * self.thread_count as u64;
-            let estimated_total_hash_rate = hash_rate * self.thread_count as u64;
-
-            println!("COMPUTE: Total progress: {} total attempts across all threads ({} hashes/second)",
-                format_number_with_commas(estimated_total_attempts),
-                format_number_with_commas(estimated_total_hash_rate)
-            );
-            last_logged_map.insert(thread_id, total_attempts);
+        if attempts.saturating_sub(last_logged.load(Ordering::Relaxed)) < 500_000 {
+            return;
+        }
+        last_logged.store(attempts, Ordering::Relaxed);
+
+        let mut last_total = self.last_total.lock().unwrap();
+        let per_thread: Vec<u64> = self.attempts.iter().map(|slot| slot.load(Ordering::Relaxed)).collect();
+        let summary = ProgressSummary {
+            total_attempts:   per_thread.iter().sum(),
+            total_hash_rate:  self.hash_rates.iter().map(|rate| rate.load(Ordering::Relaxed)).sum(),
+            slowest_attempts: per_thread.iter().copied().min().unwrap_or(0),
+            fastest_attempts: per_thread.iter().copied().max().unwrap_or(0),
+        };
+        if summary.total_attempts > *last_total {
+            *last_total = summary.total_attempts;
+            emit(&summary);
         }
     }
 }
 
-/// CLI wrapper around the library's solve_challenge function that adds display logic
+impl ProgressTracker for VerboseProgressTracker {
+    fn on_progress(&self, thread_id: usize, total_attempts: u64, hash_rate: u64, _elapsed: std::time::Duration) {
+        self.record(thread_id, total_attempts, hash_rate, |summary| {
+            crate::logging::log_event(true, LogCategory::Compute, format_args!(
+                "Total progress: {} attempts across {} threads ({}); per thread: slowest {}, fastest {}",
+                format_count(summary.total_attempts),
+                self.attempts.len(),
+                format_hash_rate(summary.total_hash_rate),
+                format_count(summary.slowest_attempts),
+                format_count(summary.fastest_attempts),
+            ));
+        });
+    }
+}
+
+/// CLI wrapper around the library's solve_challenge function that adds display logic.
+///
+/// The difficulty, thread count, attempts, solve time, resource usage
+/// and whether the hash rate sagged go into `record` for the run history.
+/// The thread count follows the server's suggestion in `metadata`
+/// unless one was set explicitly.
 pub async fn solve_challenge_with_display(
+    client:            &CliClient,
     challenge:         IronShieldChallenge,
+    metadata:          Option<&ChallengeMetadata>,
     config:            &ClientConfig,
     use_multithreaded: bool,
-) -> Result<IronShieldChallengeResponse, ErrorHandler> {
+    deadline:          &Deadline,
+    record:            &mut RunRecord,
+    sink:              &dyn OutputSink,
+) -> color_eyre::Result<IronShieldChallengeResponse> {
     // Log configuration details
-    crate::verbose_section!(config, "Challenge Solving");
-    let solve_config = SolveConfig::new(config, use_multithreaded);
-    crate::verbose_kv!(config, "Thread Count", solve_config.thread_count);
-    crate::verbose_kv!(config, "Multithreaded", solve_config.use_multithreaded);
-    crate::verbose_kv!(config, "Recommended Attempts", challenge.recommended_attempts);
+    sink.section("Challenge Solving");
+    let plan = client.suggested_thread_plan(use_multithreaded, metadata);
+    sink.kv("Thread Count", &plan.thread_count);
+    sink.kv("Multithreaded", &(plan.thread_count > 1));
+    sink.kv("Recommended Attempts", &challenge.recommended_attempts);
 
     // Log solving strategy
-    if solve_config.use_multithreaded && solve_config.thread_count > 1 {
-        crate::verbose_log!(config, compute, "Starting multithreaded solve with {} threads", solve_config.thread_count);
+    if plan.thread_count > 1 {
+        crate::verbose_log!(config, compute, "Starting multithreaded solve with {} threads", plan.thread_count);
     } else {
         crate::verbose_log!(config, compute, "Starting single-threaded solve");
     }
 
     // Always show challenge difficulty info (both verbose and non-verbose modes)
     let difficulty: u64 = challenge.recommended_attempts / 2; // recommended_attempts = difficulty * 2
-    println!("Received proof-of-work challenge with difficulty {}", format_number_with_commas(difficulty));
+    record.difficulty = Some(difficulty);
+    record.thread_count = Some(plan.thread_count);
+    record.strategy = Some(plan.strategy);
+    sink.info(&format!("Received proof-of-work challenge with difficulty {}", format_count(difficulty)));
+    sink.info(&format!("Strategy: {}", plan.describe()));
+    if use_multithreaded && num_cpus::get() == 1 {
+        sink.warning(&Warning::new(
+            WarningCode::ParallelUnavailable,
+            "Only one CPU core is available, so the threads take turns instead of hashing in parallel",
+        ).with_data(serde_json::json!({ "cores": 1, "threads": plan.thread_count })));
+    }
+    if let Some(skew_ms) = crate::presolve::clock_skew(challenge.created_time, chrono::Utc::now().timestamp_millis()) {
+        let direction = if skew_ms > 0 { "ahead of" } else { "behind" };
+        sink.warning(&Warning::new(
+            WarningCode::ClockSkew,
+            format!(
+                "The local clock is {} {direction} the API's; expiry checks and deadlines may be off",
+                format_duration(Duration::from_millis(skew_ms.unsigned_abs())),
+            ),
+        ).with_data(serde_json::json!({ "skew_ms": skew_ms })));
+    }
+    if !crate::logging::is_quiet() {
+        let estimate = crate::estimate::estimate_solve(difficulty, plan.thread_count).await;
+        sink.info(&format!("Expected solve time: {}", estimate.describe()));
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        if let Some(risk) = crate::presolve::expiry_risk(challenge.expiration_time, now_ms, estimate.low) {
+            sink.warning(&Warning::new(WarningCode::ExpiryRisk, risk.to_string()).with_data(serde_json::to_value(risk)?));
+        }
+    }
 
     // Start the progress animation (only in non-verbose mode)
+    let recorder = crate::repro::recorder();
+    if let Some(recorder) = recorder {
+        recorder.begin(&challenge, plan);
+    }
+    crate::events::emit(&crate::events::Event::SolveStarted { difficulty, threads: plan.thread_count });
+
     let animation = ProgressAnimation::new(config.verbose);
     let animation_handle = animation.start();
 
     let start_time = Instant::now();
+    let start_usage = resource::Sample::now();
+    let energy_meter = energy::Meter::start();
 
     // For verbose mode, start a background task to show periodic progress
     let verbose_progress_handle = if config.verbose {
         let config_clone = config.clone();
-        let solve_config_clone = solve_config.clone();
+        let thread_count = plan.thread_count;
         let solve_start_time = start_time;
         Some(tokio::spawn(async move {
             let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(2));
@@ -100,9 +209,9 @@ pub async fn solve_challenge_with_display(
                 crate::verbose_log!(
                     config_clone,
                     compute,
-                    "Solving progress: {} threads running for {:?} (iteration {})",
-                    solve_config_clone.thread_count,
-                    elapsed,
+                    "Solving progress: {} threads running for {} (iteration {})",
+                    thread_count,
+                    format_duration(elapsed),
                     iteration
                 );
                 iteration += 1;
@@ -113,13 +222,70 @@ pub async fn solve_challenge_with_display(
     };
 
     // Create a progress tracker for detailed per-thread logging (throttled).
-    let progress_tracker = if config.verbose && solve_config.use_multithreaded {
-        Some(Arc::new(VerboseProgressTracker::new(solve_config.thread_count)) as Arc<dyn ProgressTracker>)
+    let verbose_tracker = if config.verbose {
+        Some(Arc::new(VerboseProgressTracker::new(plan.thread_count)) as Arc<dyn ProgressTracker>)
     } else {
         None
     };
+    // Watches for the hash rate sagging partway through, e.g. from thermal throttling.
+    let throttle_tracker = Arc::new(ThrottleTracker::new(verbose_tracker));
 
-    let result = solve_challenge(challenge, config, use_multithreaded, progress_tracker).await;
+    let mut progress_tracker: Arc<dyn ProgressTracker> = throttle_tracker.clone();
+    if let Some(recorder) = recorder {
+        progress_tracker = recorder.tracker(progress_tracker);
+    }
+    if crate::events::is_enabled() {
+        progress_tracker = crate::events::tracker(progress_tracker);
+    }
+    let first_progress = Arc::new(FirstProgressTracker::new(plan.thread_count, progress_tracker));
+
+    // Running out of time drops the solve, which cancels its threads.
+    let tracker = Arc::clone(&first_progress) as Arc<dyn ProgressTracker>;
+    let solve = deadline.limit(Stage::Solve, client.search(Arc::new(challenge.clone()), &plan, Some(tracker)));
+    tokio::pin!(solve);
+    // Slow machines take a while to report at all; say so before it looks like a hang.
+    let slow_start = Duration::from_secs(crate::throttle::config().slow_start_secs);
+    let result = tokio::select! {
+        result = &mut solve => result,
+        () = first_progress.silent_for(slow_start), if !slow_start.is_zero() => {
+            sink.info(&crate::first_progress::reassurance());
+            solve.await
+        }
+    };
+    let result = result
+        .map_err(color_eyre::Report::from)
+        .and_then(|result| result);
+    let solve_time = start_time.elapsed();
+    // Timed on its own so the solve stage is the search alone.
+    let result = match result {
+        Ok(mut solution) => {
+            let verify_start = Instant::now();
+            let checked = client.check(&challenge, &mut solution).map(|()| solution);
+            record.local_verify_ms = Some(verify_start.elapsed().as_millis() as u64);
+            checked
+        }
+        Err(e) => Err(e),
+    };
+    record_first_progress(&first_progress, record, config, sink);
+    if let Some(recorder) = recorder {
+        recorder.finish(&result);
+    }
+    crate::events::emit(&crate::events::Event::SolveFinished {
+        solved:     result.is_ok(),
+        elapsed_ms: start_time.elapsed().as_millis() as u64,
+        error:      result.as_ref().err().map(|e| format!("{e:#}")),
+    });
+    record.throttle_detected = Some(throttle_tracker.detected());
+    let usage = resource::Usage::between(&start_usage, &resource::Sample::now());
+    record.peak_rss_bytes = usage.peak_rss_bytes;
+    record.cpu_ms = usage.cpu_ms;
+    record.cpu_percent = usage.cpu_percent;
+    let energy_use = energy_meter.finish(usage.cpu_ms.map(Duration::from_millis));
+    if let Some(energy_use) = &energy_use {
+        record.energy_wh = Some(energy_use.wh);
+        record.energy_method = Some(energy_use.method);
+        record.co2_grams = energy_use.grams_co2;
+    }
 
     if let Some(handle) = verbose_progress_handle {
         handle.abort();
@@ -131,21 +297,31 @@ pub async fn solve_challenge_with_display(
     // Log timing and performance metrics
     match &result {
         Ok(solution) => {
-            log_solution_performance(solution, start_time.elapsed(), &solve_config, config);
-            if solve_config.use_multithreaded && solve_config.thread_count > 1 {
+            record.solve_ms = Some(solve_time.as_millis() as u64);
+            record.attempts = Some(crate::estimate::attempts_from_nonce(solution.solution as u64, &plan));
+            crate::metrics::record_solve_success(start_time.elapsed(), record.hash_rate().unwrap_or_default());
+            log_solution_performance(solution, start_time.elapsed(), &plan, config);
+            if plan.thread_count > 1 {
                 crate::verbose_log!(config, success, "Multithreaded solve completed successfully");
             } else {
                 crate::verbose_log!(config, success, "Single-threaded solve completed successfully");
             }
 
-            println!("Challenge solved successfully!");
+            sink.info(&format!("Challenge solved successfully in {}.", format_duration(start_time.elapsed())));
+            sink.info(&describe_usage(&usage, record.hash_rate(), plan.thread_count));
+            sink.info(&format!("Solver options: {}", plan.solver.describe()));
+            if let Some(energy_use) = &energy_use {
+                sink.info(&energy::describe(energy_use));
+                sink.metric("energy", serde_json::json!(energy_use));
+            }
         },
         Err(e) => {
+            crate::metrics::record_solve_failure(start_time.elapsed());
             crate::verbose_log!(
                 config,
                 error,
-                "Challenge solving failed after {:?}: {}",
-                start_time.elapsed(),
+                "Challenge solving failed after {}: {}",
+                format_duration(start_time.elapsed()),
                 e
             );
             // Error will be handled by the caller
@@ -155,19 +331,51 @@ pub async fn solve_challenge_with_display(
     result
 }
 
+/// Records how long the solve showed no progress, and logs each
+/// thread's first report in verbose mode.
+fn record_first_progress(tracker: &FirstProgressTracker, record: &mut RunRecord, config: &ClientConfig, sink: &dyn OutputSink) {
+    let Some(first) = tracker.time_to_first_progress() else {
+        return;
+    };
+    record.time_to_first_progress_ms = Some(first.as_millis() as u64);
+    sink.metric("time_to_first_progress_ms", serde_json::json!(first.as_millis() as u64));
+
+    let per_thread: Vec<String> = tracker.per_thread().into_iter()
+        .map(|first| first.map_or_else(|| "none".to_string(), format_duration))
+        .collect();
+    crate::verbose_log!(config, timing, "First progress report per thread: {}", per_thread.join(", "));
+}
+
+/// One line with the hash rate, CPU time, utilization and peak memory,
+/// showing `n/a` for whatever the platform couldn't measure.
+fn describe_usage(usage: &resource::Usage, hash_rate: Option<u64>, thread_count: usize) -> String {
+    let unavailable = || "n/a".to_string();
+    let utilization = match (usage.cpu_percent, usage.thread_utilization(thread_count)) {
+        (Some(percent), Some(per_thread)) => {
+            format!("{percent:.0}% CPU, {per_thread:.0}% of {thread_count} thread(s)")
+        }
+        _ => "CPU utilization n/a".to_string(),
+    };
+    format!(
+        "Hash rate: {}, CPU time: {} ({utilization}), peak memory: {}",
+        hash_rate.map(format_hash_rate).unwrap_or_else(unavailable),
+        usage.cpu_ms.map(|ms| format_duration(Duration::from_millis(ms))).unwrap_or_else(unavailable),
+        usage.peak_rss_bytes.map(format_bytes).unwrap_or_else(unavailable),
+    )
+}
+
 /// Log performance metrics for a solved challenge
 fn log_solution_performance(
     solution: &IronShieldChallengeResponse,
     elapsed: std::time::Duration,
-    solve_config: &SolveConfig,
+    plan: &ThreadPlan,
     config: &ClientConfig,
 ) {
     let elapsed_millis: u64 = elapsed.as_millis() as u64;
 
-    // Calculate estimated total attempts across all threads using thread-stride analysis
+    // Calculate estimated total attempts across all threads from how the search divided the nonce space
     let solution_nonce: u64 = solution.solution as u64;
-    let estimated_attempts_per_thread: u64 = (solution_nonce / solve_config.thread_count as u64) + 1;
-    let estimated_total_attempts: u64 = estimated_attempts_per_thread * solve_config.thread_count as u64;
+    let estimated_total_attempts: u64 = crate::estimate::attempts_from_nonce(solution_nonce, plan);
 
     let hash_rate: u64 = if elapsed_millis > 0 {
         (estimated_total_attempts * 1000) / elapsed_millis
@@ -178,52 +386,280 @@ fn log_solution_performance(
     crate::verbose_log!(
         config,
         timing,
-        "Challenge solved in {:?} (~{} estimated total attempts, ~{} h/s)",
-        elapsed,
-        estimated_total_attempts,
-        hash_rate
+        "Challenge solved in {} (~{} estimated total attempts, ~{})",
+        format_duration(elapsed),
+        format_count(estimated_total_attempts),
+        format_hash_rate(hash_rate)
     );
 
     crate::verbose_log!(
         config,
         success,
-        "Performance: {} threads achieved ~{} hashes/second (solution found at nonce {})",
-        solve_config.thread_count,
-        hash_rate,
-        solution_nonce
+        "Performance: {} threads achieved ~{} (solution found at nonce {})",
+        plan.thread_count,
+        format_hash_rate(hash_rate),
+        format_count(solution_nonce)
     );
 }
 
 /// Handles the solve command - fetches and solves a challenge from the specified endpoint
+///
+/// With `dump_repro`, the challenge, settings and outcome are written
+/// there as a bundle for `ironshield repro`, whether or not the solve
+/// succeeded.
 pub async fn handle_solve(
-    client: &IronShieldClient,
+    client: &CliClient,
     config: &ClientConfig,
     endpoint: &str,
-    single_threaded: bool
+    single_threaded: bool,
+    dump_repro: Option<&Path>,
+    sink: &dyn OutputSink,
 ) -> color_eyre::Result<()> {
-    crate::verbose_section!(config, "Challenge Fetching");
+    let mut retry = InteractiveRetry::new(client, config);
+    let solved = loop {
+        let mut record = RunRecord::new(RunCommand::Solve, endpoint);
+        let start_time = Instant::now();
+
+        let result = solve(retry.client(), retry.config(), endpoint, single_threaded, &mut record, sink).await;
+        history::record_result(&mut record, start_time.elapsed(), &result);
+        crate::metrics::send_statsd(&record, config.verbose);
+        if let (Some(dir), Some(recorder)) = (dump_repro, crate::repro::recorder()) {
+            match recorder.write_bundle(dir, retry.config()) {
+                Ok(()) => sink.info(&format!("Wrote a repro bundle to {}; replay it with `ironshield repro {}`", dir.display(), dir.display())),
+                Err(e) => sink.warning(
+                    &Warning::new(WarningCode::ReproNotWritten, format!("No repro bundle written: {e}"))
+                        .with_data(serde_json::json!({ "dump_repro": dir })),
+                ),
+            }
+        }
+        match result {
+            Ok(solved)                                   => break solved,
+            Err(e) if retry.offer(&e, record.error_kind) => continue,
+            Err(e)                                       => return Err(e),
+        }
+    };
+
+    sink.result_json(serde_json::to_value(&solved.solution)?, Some(solved.negotiated));
+
+    crate::logging::flush();
+
+    crate::har::write();
+    std::process::exit(0);
+}
+
+/// Fetches and solves, filling in `record` along the way. Status
+/// lines go to `sink`; the solution is returned, not emitted.
+pub async fn solve(
+    client:          &CliClient,
+    config:          &ClientConfig,
+    endpoint:        &str,
+    single_threaded: bool,
+    record:          &mut RunRecord,
+    sink:            &dyn OutputSink,
+) -> color_eyre::Result<Solved> {
+    fetch_and_solve(client, config, endpoint, single_threaded, &mut Deadline::unbounded(), record, sink).await
+}
+
+/// A solved challenge and how long getting it took.
+#[derive(Debug, Clone)]
+pub struct Solved {
+    pub challenge:    IronShieldChallenge,
+    pub solution:     IronShieldChallengeResponse,
+    /// The API version the challenge was fetched with.
+    pub negotiated:   Negotiated,
+    /// Fetching, including fetching again for a nearly expired challenge.
+    pub fetch:        Duration,
+    /// Solving, without the local check of the solution.
+    pub solve:        Duration,
+    pub local_verify: Duration,
+}
+
+/// A fetched challenge, not yet solved, and how long fetching took.
+#[derive(Debug, Clone)]
+pub struct Fetched {
+    pub challenge:  IronShieldChallenge,
+    /// What the API sent next to the challenge, if anything.
+    pub metadata:   Option<ChallengeMetadata>,
+    /// The API version the challenge was fetched with.
+    pub negotiated: Negotiated,
+    /// Fetching, including fetching again for a nearly expired challenge.
+    pub fetch:      Duration,
+}
+
+/// Fetches a challenge and solves it: the journey `solve`, `run` and
+/// `validate` share, with display layered on through `sink` and the
+/// progress trackers. Fills in `record` along the way.
+///
+/// Every stage runs within what is left of `deadline`, which is
+/// brought forward to the challenge's expiry once it is known.
+pub async fn fetch_and_solve(
+    client:          &CliClient,
+    config:          &ClientConfig,
+    endpoint:        &str,
+    single_threaded: bool,
+    deadline:        &mut Deadline,
+    record:          &mut RunRecord,
+    sink:            &dyn OutputSink,
+) -> color_eyre::Result<Solved> {
+    let fetched = fetch(client, config, endpoint, deadline, record, sink).await?;
+    crate::prewarm::start();
+    solve_fetched(client, fetched, config, single_threaded, deadline, record, sink).await
+}
+
+/// The fetch half of [`fetch_and_solve`], for callers that look at the
+/// challenge before deciding to solve it.
+pub async fn fetch(
+    client:   &CliClient,
+    config:   &ClientConfig,
+    endpoint: &str,
+    deadline: &mut Deadline,
+    record:   &mut RunRecord,
+    sink:     &dyn OutputSink,
+) -> color_eyre::Result<Fetched> {
+    sink.section("Challenge Fetching");
     crate::verbose_log!(config, network, "Requesting challenge for endpoint: {}", endpoint);
 
-    let fetch_start = Instant::now();
-    let challenge = client.fetch_challenge(endpoint).await?;
+    // A challenge that arrives (nearly) expired says more about the server's
+    // clock or queue than about this run, so it is fetched again a few times.
+    let policy = crate::refetch::policy();
+    let mut short_windows = Vec::new();
+    let fetch_stage_start = Instant::now();
+    let (envelope, fetch_start) = loop {
+        deadline.log_stage(config.verbose, Stage::Fetch);
+        let fetch_start = Instant::now();
+        let envelope = deadline.limit(Stage::Fetch, client.fetch(endpoint)).await
+            .map_err(color_eyre::Report::from)
+            .and_then(|result| result)
+            .inspect_err(|_| record.error_kind = Some(ErrorKind::Fetch))?;
+
+        let Some(window_ms) = policy.short_window(envelope.challenge.expiration_time, chrono::Utc::now().timestamp_millis()) else {
+            break (envelope, fetch_start);
+        };
+        short_windows.push(window_ms);
+        if short_windows.len() > policy.max_refetches as usize {
+            record.error_kind = Some(ErrorKind::Fetch);
+            return Err(StaleChallenges { windows_ms: short_windows, min_validity: policy.min_validity }.into());
+        }
+        crate::logging::log_event(config.verbose, LogCategory::Warning, format_args!(
+            "Challenge arrived with {}; fetching another ({}/{})",
+            crate::refetch::describe_window(window_ms),
+            short_windows.len(),
+            policy.max_refetches,
+        ));
+        crate::metrics::record_refetch();
+        deadline.limit(Stage::Fetch, tokio::time::sleep(policy.delay)).await?;
+    };
+    let ChallengeEnvelope { challenge, metadata, negotiated } = envelope;
+    deadline.tighten_to_expiry(challenge.expiration_time);
+    let fetch = fetch_stage_start.elapsed();
+    record.fetch_ms = Some(fetch_start.elapsed().as_millis() as u64);
 
     crate::verbose_log!(
         config,
         timing,
-        "Challenge fetch completed in {:?}",
-        fetch_start.elapsed()
+        "Challenge fetch completed in {}",
+        format_duration(fetch_start.elapsed())
     );
 
-    println!("Challenge fetched successfully!");
+    sink.info("Challenge fetched successfully!");
+    crate::logging::file_event(LogCategory::Receive, format_args!("Challenge: {challenge:?}"));
+
+    sink.kv("Random Nonce", &format!("{:?}", challenge.random_nonce));
+    sink.kv("Difficulty", &format_count(challenge.recommended_attempts / 2));
+    sink.kv("Recommended Attempts", &format_count(challenge.recommended_attempts));
+    if let Some(metadata) = &metadata {
+        metadata.report(sink);
+    }
+
+    Ok(Fetched { challenge, metadata, negotiated, fetch })
+}
 
-    crate::verbose_kv!(config, "Random Nonce", format!("{:?}", challenge.random_nonce));
-    crate::verbose_kv!(config, "Difficulty", challenge.recommended_attempts / 2);
-    crate::verbose_kv!(config, "Recommended Attempts", challenge.recommended_attempts);
+/// The solve half of [`fetch_and_solve`]: checks the challenge against
+/// the limits, then solves it.
+pub async fn solve_fetched(
+    client:          &CliClient,
+    fetched:         Fetched,
+    config:          &ClientConfig,
+    single_threaded: bool,
+    deadline:        &mut Deadline,
+    record:          &mut RunRecord,
+    sink:            &dyn OutputSink,
+) -> color_eyre::Result<Solved> {
+    let Fetched { challenge, metadata, negotiated, fetch } = fetched;
+    let thread_count = client.thread_plan(!single_threaded).thread_count;
+    crate::presolve::check(&challenge, thread_count).await
+        .inspect_err(|_| record.error_kind = Some(ErrorKind::Refused))?;
+    crate::presolve::confirm(&challenge, thread_count).await
+        .inspect_err(|_| record.error_kind = Some(ErrorKind::Refused))?;
 
     // Invert the single_threaded flag to get use_multithreaded.
-    let solution = solve_challenge_with_display(challenge, config, !single_threaded).await?;
+    deadline.log_stage(config.verbose, Stage::Solve);
+    let solve_start = Instant::now();
+    let solution = solve_challenge_with_display(client, challenge.clone(), metadata.as_ref(), config, !single_threaded, deadline, record, sink).await
+        .inspect_err(|_| record.error_kind = Some(ErrorKind::Solve))?;
 
-    println!("Solution: {solution:?}");
+    let local_verify = Duration::from_millis(record.local_verify_ms.unwrap_or_default());
+    Ok(Solved { challenge, solution, negotiated, fetch, solve: solve_start.elapsed().saturating_sub(local_verify), local_verify })
+}
 
-    std::process::exit(0);
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verbose_tracker_sums_threads() {
+        let tracker = VerboseProgressTracker::new(2);
+        let mut summaries = Vec::new();
+
+        tracker.record(0, 600_000, 1_000, |summary| summaries.push(*summary));
+        tracker.record(1, 200_000, 400, |summary| summaries.push(*summary));
+        tracker.record(1, 700_000, 500, |summary| summaries.push(*summary));
+
+        assert_eq!(summaries, [
+            ProgressSummary { total_attempts: 600_000, total_hash_rate: 1_000, slowest_attempts: 0, fastest_attempts: 600_000 },
+            ProgressSummary { total_attempts: 1_300_000, total_hash_rate: 1_500, slowest_attempts: 600_000, fastest_attempts: 700_000 },
+        ]);
+    }
+
+    #[test]
+    fn test_verbose_tracker_total_never_decreases() {
+        const THREADS: usize = 8;
+        let tracker = VerboseProgressTracker::new(THREADS);
+        let totals = Mutex::new(Vec::new());
+
+        std::thread::scope(|scope| {
+            for thread_id in 0..THREADS {
+                let (tracker, totals) = (&tracker, &totals);
+                scope.spawn(move || {
+                    // Uneven threads: each advances at its own pace.
+                    let step = 100_000 * (thread_id as u64 + 1);
+                    for report in 1..=200 {
+                        tracker.record(thread_id, report * step, step, |summary| {
+                            totals.lock().unwrap().push(summary.total_attempts);
+                        });
+                    }
+                });
+            }
+        });
+
+        let totals = totals.into_inner().unwrap();
+        assert!(!totals.is_empty());
+        assert!(totals.windows(2).all(|pair| pair[0] < pair[1]), "totals went backwards: {totals:?}");
+        assert!(*totals.last().unwrap() <= (1..=THREADS as u64).map(|t| 200 * 100_000 * t).sum::<u64>());
+    }
+
+    #[test]
+    fn test_describe_usage() {
+        let usage = resource::Usage { peak_rss_bytes: Some(1_572_864), cpu_ms: Some(3_000), cpu_percent: Some(300.0) };
+        assert_eq!(
+            describe_usage(&usage, Some(1_240_000), 4),
+            "Hash rate: 1.24 Mh/s, CPU time: 3.0s (300% CPU, 75% of 4 thread(s)), peak memory: 1.5 MiB",
+        );
+
+        let unavailable = resource::Usage { peak_rss_bytes: None, cpu_ms: None, cpu_percent: None };
+        assert_eq!(
+            describe_usage(&unavailable, None, 1),
+            "Hash rate: n/a, CPU time: n/a (CPU utilization n/a), peak memory: n/a",
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use chrono::Utc;
+use color_eyre::eyre::eyre;
+use notify::{RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::client::CliClient;
+use crate::commands::interchange::{self, Input};
+use crate::daemon::{Request as Signal, Signals, Supervised};
+use crate::display::{format_count, format_duration};
+use crate::spool::{Outcome, Spool};
+
+/// How often to rescan when notifications can't be had.
+pub const DEFAULT_POLL: Duration = Duration::from_secs(2);
+/// How often to rescan even with notifications, in case some were dropped.
+const RESCAN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How `spool` learns that a challenge has arrived.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Watch {
+    /// The platform's notifications, or polling every [`DEFAULT_POLL`]
+    /// where there are none.
+    Auto,
+    /// The platform's notifications (inotify, FSEvents, ...) or nothing:
+    /// `--inotify`.
+    Native,
+    /// Rescanning the directory at this interval: `--poll`.
+    Poll(Duration),
+}
+
+/// What `spool` was asked to do.
+pub struct SpoolOptions<'a> {
+    pub dir:             &'a Path,
+    pub watch:           Watch,
+    /// Challenges solved at once; their threads come out of the shared
+    /// `--total-threads` budget.
+    pub jobs:            usize,
+    pub single_threaded: bool,
+}
+
+/// Handles `spool`: solves every `*.challenge.json` dropped into a
+/// directory, writes `*.solution.json` next to it and moves the input
+/// to `done/` or `failed/`, until stopped.
+///
+/// Challenges already waiting are picked up at once. Writers must
+/// rename each one into place (see [`crate::spool::is_challenge_name`]);
+/// a file written under its final name may be read half-written and
+/// fail.
+///
+/// `SIGTERM` stops picking up challenges, lets the ones being solved
+/// finish, then exits 0. `SIGHUP` reloads the config file for later
+/// solves. Anything stopped some other way stays in the spool, and is
+/// solved again on the next start.
+///
+/// # Arguments
+/// * `supervised`: The configuration, reloaded on `SIGHUP`.
+/// * `options`:    The directory, how to watch it and how many to solve at once.
+pub async fn handle_spool(mut supervised: Supervised, options: &SpoolOptions<'_>) -> color_eyre::Result<()> {
+    let spool = Arc::new(
+        Spool::open(options.dir).map_err(|e| eyre!("Cannot use '{}' as a spool: {e}", options.dir.display()))?,
+    );
+    let jobs = options.jobs.max(1);
+    supervised.client = supervised.client.with_pool();
+    let mut signals = Signals::install()?;
+    let (_watcher, mut changes, interval) = watch(spool.dir(), options.watch)?;
+    let mut rescans = tokio::time::interval(interval);
+    rescans.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    // Inputs being solved, and ones that couldn't be moved out of the
+    // spool and would otherwise be solved over and over.
+    let mut in_flight: HashSet<PathBuf> = HashSet::new();
+    let mut stuck: HashSet<PathBuf> = HashSet::new();
+    let mut solves = JoinSet::new();
+    let (mut solved, mut failed) = (0, 0);
+
+    loop {
+        if solves.len() < jobs {
+            let pending = spool.pending().map_err(|e| eyre!("Cannot read the spool '{}': {e}", spool.dir().display()))?;
+            let ready = pending.into_iter().filter(|input| !in_flight.contains(input) && !stuck.contains(input));
+            for input in ready.take(jobs - solves.len()) {
+                in_flight.insert(input.clone());
+                solves.spawn(process(Arc::clone(&spool), input, supervised.client.clone(), !options.single_threaded));
+            }
+        }
+
+        // Signals are only looked at between events, and the solves in
+        // flight are always let finish.
+        tokio::select! {
+            Some(finished) = solves.join_next() => {
+                let (input, outcome, moved) = finished?;
+                in_flight.remove(&input);
+                match outcome {
+                    Outcome::Solved(_) => solved += 1,
+                    Outcome::Failed(_) => failed += 1,
+                }
+                if !moved {
+                    stuck.insert(input);
+                }
+            }
+            Some(()) = changes.recv() => {}
+            _ = rescans.tick() => {}
+            signal = signals.recv() => match signal {
+                Signal::Shutdown => {
+                    crate::status_println!("SIGTERM: shutting down once {} solve(s) in flight finish", solves.len());
+                    break;
+                }
+                Signal::Reload => {
+                    supervised.reload();
+                }
+            },
+        }
+    }
+
+    while let Some(finished) = solves.join_next().await {
+        match finished?.1 {
+            Outcome::Solved(_) => solved += 1,
+            Outcome::Failed(_) => failed += 1,
+        }
+    }
+    crate::status_println!("Solved {}, failed {}.", format_count(solved), format_count(failed));
+    crate::logging::flush();
+    Ok(())
+}
+
+/// Starts watching `dir`.
+///
+/// # Returns
+/// * `Result<(Option<RecommendedWatcher>, Receiver<()>, Duration)>`:
+///   The watcher, which stops when dropped, a channel that receives a
+///   message on every change, and how often to rescan regardless.
+fn watch(
+    dir:   &Path,
+    watch: Watch,
+) -> color_eyre::Result<(Option<notify::RecommendedWatcher>, mpsc::UnboundedReceiver<()>, Duration)> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    if let Watch::Poll(interval) = watch {
+        crate::status_println!("Polling '{}' every {} for challenges", dir.display(), format_duration(interval));
+        return Ok((None, rx, interval));
+    }
+
+    // Any event, an error included, just means it is time to rescan.
+    let watcher = notify::recommended_watcher(move |_: notify::Result<notify::Event>| {
+        let _ = tx.send(());
+    }).and_then(|mut watcher| watcher.watch(dir, RecursiveMode::NonRecursive).map(|()| watcher));
+    match (watcher, watch) {
+        (Ok(watcher), _) => {
+            crate::status_println!("Watching '{}' for challenges", dir.display());
+            Ok((Some(watcher), rx, RESCAN_INTERVAL))
+        }
+        (Err(e), Watch::Native) => Err(eyre!("Cannot watch '{}': {e}", dir.display())),
+        (Err(e), _) => {
+            crate::status_println!(
+                "Cannot watch '{}' ({e}); polling every {} instead",
+                dir.display(),
+                format_duration(DEFAULT_POLL),
+            );
+            Ok((None, rx, DEFAULT_POLL))
+        }
+    }
+}
+
+/// Solves one input and moves it out of the spool.
+///
+/// # Returns
+/// * `(PathBuf, Outcome, bool)`: The input, what became of it, and
+///   whether it was moved out of the spool.
+async fn process(
+    spool:             Arc<Spool>,
+    input:             PathBuf,
+    client:            CliClient,
+    use_multithreaded: bool,
+) -> (PathBuf, Outcome, bool) {
+    let name = input.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let start = Instant::now();
+    let outcome = match solve(&spool, &input, &client, use_multithreaded).await {
+        Ok(path) => {
+            crate::status_println!("Solved {name} in {}.", format_duration(start.elapsed()));
+            Outcome::Solved(path)
+        }
+        Err(e) => {
+            crate::status_println!("Failed {name}: {e}");
+            Outcome::Failed(e.to_string())
+        }
+    };
+    let moved = match spool.finish(&input, &outcome) {
+        Ok(_)  => true,
+        Err(e) => {
+            crate::status_println!("Cannot move {name} out of the spool ({e}); leaving it until the next start.");
+            false
+        }
+    };
+    (input, outcome, moved)
+}
+
+/// Reads, solves and answers one input.
+///
+/// # Returns
+/// * `Result<PathBuf>`: Where the solution was written.
+async fn solve(spool: &Spool, input: &Path, client: &CliClient, use_multithreaded: bool) -> color_eyre::Result<PathBuf> {
+    let challenge = interchange::read_challenge(Input::File(input))?;
+    if challenge.expiration_time <= Utc::now().timestamp_millis() {
+        return Err(eyre!("expired before it was solved"));
+    }
+    let solution = client.solve_threaded(challenge, use_multithreaded, None).await?;
+    spool.write_solution(input, &solution).map_err(|e| eyre!("Cannot write the solution: {e}"))
+}
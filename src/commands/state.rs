@@ -0,0 +1,91 @@
+use chrono::Utc;
+use color_eyre::eyre::eyre;
+
+use std::time::Duration;
+
+use crate::display::{format_bytes, format_count, parse_duration};
+use crate::state::{self, GcOptions, StateDirs};
+
+/// Handles `state path`: prints the data and cache directories.
+///
+/// # Arguments
+/// * `json`: Print them as a JSON object.
+pub fn handle_path(json: bool) -> color_eyre::Result<()> {
+    let dirs = open()?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&dirs)?);
+        return Ok(());
+    }
+    println!("data:  {}", dirs.data.display());
+    println!("cache: {}", dirs.cache.display());
+    Ok(())
+}
+
+/// Handles `state size`: prints the disk each component takes up.
+///
+/// # Arguments
+/// * `json`: Print a JSON array instead of a table.
+pub fn handle_size(json: bool) -> color_eyre::Result<()> {
+    let dirs = open()?;
+    let usage = state::usage(&dirs).map_err(|e| eyre!("Cannot measure the state directories: {e}"))?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&usage)?);
+        return Ok(());
+    }
+
+    println!("{:<10}  {:>8}  {:>10}  Path", "Component", "Files", "Size");
+    for usage in &usage {
+        println!(
+            "{:<10}  {:>8}  {:>10}  {}",
+            usage.component.name(),
+            format_count(usage.files),
+            format_bytes(usage.bytes),
+            usage.path.display(),
+        );
+    }
+    let total = usage.iter().map(|usage| usage.bytes).sum();
+    crate::status_println!("Total: {}", format_bytes(total));
+    Ok(())
+}
+
+/// Handles `state gc`: removes state nothing will use again and prints
+/// each removal, or what would be removed with `--dry-run`.
+///
+/// # Arguments
+/// * `older_than`: Queue items and cached responses must be at least this old.
+/// * `retention`:  `[history] retention`; history is kept whole when unset.
+/// * `dry_run`:    Remove nothing.
+/// * `json`:       Print a JSON array of removals instead.
+pub fn handle_gc(older_than: Duration, retention: Option<&str>, dry_run: bool, json: bool) -> color_eyre::Result<()> {
+    let history_retention = retention
+        .map(parse_duration)
+        .transpose()
+        .map_err(|e| eyre!("Invalid `history.retention`: {e}"))?;
+    let dirs = open()?;
+    let options = GcOptions { older_than, history_retention, dry_run };
+    let removals = state::gc(&dirs, &options, Utc::now()).map_err(|e| eyre!("Cannot clean up the state directories: {e}"))?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&removals)?);
+        return Ok(());
+    }
+    let verb = if dry_run { "Would remove" } else { "Removed" };
+    for removal in &removals {
+        println!("{verb} {} ({}: {})", removal.path.display(), removal.component.name(), removal.reason);
+    }
+    match removals.len() {
+        0 => crate::status_println!("Nothing to clean up."),
+        count => crate::status_println!(
+            "{verb} {count} item(s), {}.",
+            format_bytes(removals.iter().map(|removal| removal.bytes).sum()),
+        ),
+    }
+    if history_retention.is_none() {
+        crate::status_println!("History is kept whole; set `retention` under [history] to prune it.");
+    }
+    Ok(())
+}
+
+fn open() -> color_eyre::Result<StateDirs> {
+    StateDirs::default_location().ok_or_else(|| eyre!("This platform has no data or cache directory"))
+}
@@ -0,0 +1,180 @@
+use color_eyre::eyre::eyre;
+use ironshield::ClientConfig;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+use std::time::Instant;
+
+use crate::client::CliClient;
+use crate::daemon::{Request as Signal, Signals, Supervised};
+use crate::deadline::Deadline;
+use crate::history::{self, RunCommand, RunRecord};
+use crate::output::ConsoleSink;
+
+/// One line of input to `stream`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Request {
+    cmd:             String,
+    #[serde(default)]
+    endpoint:        String,
+    #[serde(default)]
+    single_threaded: bool,
+}
+
+/// Handles `stream`: reads newline-delimited JSON commands from stdin,
+/// e.g. `{"cmd":"validate","endpoint":"https://..."}`, and writes one
+/// JSON result per line to stdout until stdin closes.
+///
+/// A command that fails, or a line that isn't a command, produces an
+/// error record; the stream keeps going either way. Any `id` field on a
+/// request is echoed back so callers can match up results.
+///
+/// `SIGTERM` lets the command in flight finish and write its result,
+/// then exits 0. `SIGHUP` reloads the config file between commands.
+///
+/// # Arguments
+/// * `supervised`: The client and configuration, shared by every command.
+pub async fn handle_stream(mut supervised: Supervised) -> color_eyre::Result<()> {
+    supervised.client = supervised.client.with_pool();
+    let mut signals = Signals::install()?;
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+
+    loop {
+        // Signals are only looked at between commands, so one that
+        // arrives mid-solve waits for the solve to finish.
+        let line = tokio::select! {
+            line = lines.next_line() => line?,
+            signal = signals.recv() => match signal {
+                Signal::Shutdown => {
+                    crate::status_println!("SIGTERM: shutting down");
+                    break;
+                }
+                Signal::Reload => {
+                    supervised.reload();
+                    continue;
+                }
+            },
+        };
+        let Some(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = handle_line(&supervised.client, &supervised.config, &line).await;
+        stdout.write_all(format!("{response}\n").as_bytes()).await?;
+        stdout.flush().await?;
+    }
+
+    crate::logging::flush();
+    Ok(())
+}
+
+/// Runs the command on one input line.
+///
+/// # Returns
+/// * `Value`: The result record to write back.
+async fn handle_line(client: &CliClient, config: &ClientConfig, line: &str) -> Value {
+    let value = match serde_json::from_str::<Value>(line) {
+        Ok(value) => value,
+        Err(e)    => return response(None, None, Err(eyre!("Invalid JSON: {e}"))),
+    };
+    let id = value.get("id").cloned();
+    let request = match Request::deserialize(&value) {
+        Ok(request) => request,
+        Err(e)      => {
+            let cmd = value.get("cmd").and_then(Value::as_str);
+            return response(id, cmd, Err(eyre!("Invalid command: {e}")));
+        }
+    };
+
+    let result = run(client, config, &request).await;
+    response(id, Some(&request.cmd), result)
+}
+
+/// Runs one command, recording it in the run history like its
+/// standalone counterpart.
+async fn run(
+    client:  &CliClient,
+    config:  &ClientConfig,
+    request: &Request,
+) -> color_eyre::Result<Value> {
+    let command = match request.cmd.as_str() {
+        "fetch"    => RunCommand::Fetch,
+        "solve"    => RunCommand::Solve,
+        "validate" => RunCommand::Validate,
+        other      => return Err(eyre!("Unknown command '{other}'; expected fetch, solve or validate")),
+    };
+    let endpoint = request.endpoint.as_str();
+    if endpoint.is_empty() {
+        return Err(eyre!("'{}' needs an endpoint", request.cmd));
+    }
+    let mut record = RunRecord::new(command, endpoint);
+    let start_time = Instant::now();
+    // Status lines stay on stderr; stdout carries only the responses.
+    let sink = ConsoleSink { verbose: config.verbose };
+
+    let result = match command {
+        RunCommand::Fetch => super::fetch::fetch(client, config, endpoint, &mut record, &sink).await
+            .and_then(|envelope| Ok(serde_json::to_value(envelope.challenge)?)),
+        RunCommand::Solve => super::solve::solve(client, config, endpoint, request.single_threaded, &mut record, &sink).await
+            .and_then(|solved| Ok(serde_json::to_value(solved.solution)?)),
+        RunCommand::Validate => super::validate::validate(client, config, endpoint, request.single_threaded, &mut Deadline::unbounded(), &mut record, &sink).await
+            .and_then(|validated| Ok(serde_json::to_value(validated.token)?)),
+    };
+    history::record_result(&mut record, start_time.elapsed(), &result);
+    crate::metrics::send_statsd(&record, config.verbose);
+    result
+}
+
+/// Builds the output record for one request.
+fn response(id: Option<Value>, cmd: Option<&str>, result: color_eyre::Result<Value>) -> Value {
+    let mut response = match result {
+        Ok(result) => json!({ "ok": true, "result": result }),
+        Err(e)     => json!({ "ok": false, "error": format!("{e:#}") }),
+    };
+    if let Some(cmd) = cmd {
+        response["cmd"] = json!(cmd);
+    }
+    if let Some(id) = id {
+        response["id"] = id;
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_defaults_to_multithreaded() {
+        let request: Request = serde_json::from_str(r#"{"cmd":"validate","endpoint":"https://a.example"}"#).unwrap();
+
+        assert_eq!(request, Request {
+            cmd:             "validate".to_string(),
+            endpoint:        "https://a.example".to_string(),
+            single_threaded: false,
+        });
+    }
+
+    #[test]
+    fn test_error_response_echoes_id_and_cmd() {
+        let response = response(Some(json!(7)), Some("frobnicate"), Err(eyre!("Unknown command 'frobnicate'")));
+
+        assert_eq!(response, json!({
+            "id":    7,
+            "cmd":   "frobnicate",
+            "ok":    false,
+            "error": "Unknown command 'frobnicate'",
+        }));
+    }
+
+    #[test]
+    fn test_success_response() {
+        let response = response(None, Some("fetch"), Ok(json!({ "a": 1 })));
+
+        assert_eq!(response, json!({ "cmd": "fetch", "ok": true, "result": { "a": 1 } }));
+    }
+}
@@ -0,0 +1,223 @@
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::eyre;
+use ironshield::ClientConfig;
+
+use std::path::Path;
+use std::time::Duration;
+
+use crate::client::CliClient;
+use crate::display::{format_count, format_duration};
+
+/// Width of the longest bar in the difficulty histogram.
+const BAR_WIDTH: usize = 30;
+
+/// How often and how gently `survey` fetches.
+pub struct SurveyOptions<'a> {
+    pub endpoints_file: &'a Path,
+    /// Rounds to run; each round fetches every endpoint once.
+    pub samples:        usize,
+    /// Time between the start of one round and the next.
+    pub interval:       Duration,
+    /// Pause between two fetches within a round.
+    pub delay:          Duration,
+    pub csv:            Option<&'a Path>,
+}
+
+/// One fetched challenge. The challenge itself is never solved.
+#[derive(Debug, Clone, PartialEq)]
+struct Sample {
+    endpoint:             String,
+    timestamp:            DateTime<Utc>,
+    difficulty:           u64,
+    recommended_attempts: u64,
+    expiry_window_ms:     i64,
+}
+
+/// Handles `survey`: fetches challenges from every endpoint in a file
+/// on a schedule and summarises how their difficulty is distributed.
+/// Ctrl-C stops early and summarises whatever was collected.
+///
+/// # Arguments
+/// * `client`:  The API client to fetch with.
+/// * `config`:  The client configuration, for verbose logging.
+/// * `options`: The endpoints, schedule and CSV output.
+pub async fn handle_survey(
+    client:  &CliClient,
+    config:  &ClientConfig,
+    options: &SurveyOptions<'_>,
+) -> color_eyre::Result<()> {
+    let contents = std::fs::read_to_string(options.endpoints_file)
+        .map_err(|e| eyre!("Cannot read endpoints from '{}': {e}", options.endpoints_file.display()))?;
+    let endpoints = crate::batch::parse_endpoints(&contents);
+    if endpoints.is_empty() {
+        return Err(eyre!("'{}' lists no endpoints", options.endpoints_file.display()));
+    }
+
+    let mut samples = Vec::new();
+    let mut failures = vec![0usize; endpoints.len()];
+    tokio::select! {
+        _ = collect(client, config, &endpoints, options, &mut samples, &mut failures) => {}
+        _ = tokio::signal::ctrl_c() => {
+            crate::status_println!("Interrupted; summarising the {} samples collected so far.", samples.len());
+        }
+    }
+
+    if samples.is_empty() {
+        return Err(eyre!("No challenges were collected; every fetch failed"));
+    }
+    if let Some(path) = options.csv {
+        std::fs::write(path, to_csv(&samples))
+            .map_err(|e| eyre!("Cannot write CSV to '{}': {e}", path.display()))?;
+        crate::status_println!("Wrote {} samples to {}", samples.len(), path.display());
+    }
+
+    for (endpoint, failed) in endpoints.iter().zip(&failures) {
+        let endpoint_samples: Vec<&Sample> = samples.iter().filter(|s| &s.endpoint == endpoint).collect();
+        print_summary(endpoint, &endpoint_samples, *failed);
+    }
+    Ok(())
+}
+
+/// Fetches every endpoint once per round until `options.samples` rounds have run.
+async fn collect(
+    client:    &CliClient,
+    config:    &ClientConfig,
+    endpoints: &[String],
+    options:   &SurveyOptions<'_>,
+    samples:   &mut Vec<Sample>,
+    failures:  &mut [usize],
+) {
+    for round in 1..=options.samples {
+        let round_start = tokio::time::Instant::now();
+        crate::status_println!("Round {round}/{}", options.samples);
+
+        for (index, endpoint) in endpoints.iter().enumerate() {
+            if index > 0 {
+                tokio::time::sleep(options.delay).await;
+            }
+            crate::verbose_log!(config, network, "Requesting challenge for endpoint: {}", endpoint);
+            match client.fetch(endpoint).await.map(|envelope| envelope.challenge) {
+                Ok(challenge) => samples.push(Sample {
+                    endpoint:             endpoint.clone(),
+                    timestamp:            Utc::now(),
+                    difficulty:           challenge.recommended_attempts / 2,
+                    recommended_attempts: challenge.recommended_attempts,
+                    expiry_window_ms:     challenge.expiration_time - challenge.created_time,
+                }),
+                Err(e) => {
+                    failures[index] += 1;
+                    crate::status_println!("  {endpoint}: fetch failed: {e}");
+                }
+            }
+        }
+
+        if round < options.samples {
+            tokio::time::sleep_until(round_start + options.interval.max(options.delay)).await;
+        }
+    }
+}
+
+fn to_csv(samples: &[Sample]) -> String {
+    let mut csv = String::from("timestamp,endpoint,difficulty,recommended_attempts,expiry_window_ms\n");
+    for sample in samples {
+        csv.push_str(&format!(
+            "{},{},{},{},{}\n",
+            sample.timestamp.to_rfc3339(),
+            csv_field(&sample.endpoint),
+            sample.difficulty,
+            sample.recommended_attempts,
+            sample.expiry_window_ms,
+        ));
+    }
+    csv
+}
+
+/// Quotes a field that contains a comma or quote.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Minimum, median and maximum of `values`, which must not be empty.
+fn spread(values: &mut [u64]) -> (u64, u64, u64) {
+    values.sort_unstable();
+    (values[0], values[(values.len() - 1) / 2], values[values.len() - 1])
+}
+
+/// Counts difficulties per power of ten: `(lower bound, count)`, smallest first.
+fn histogram(difficulties: &[u64]) -> Vec<(u64, usize)> {
+    let mut buckets: Vec<(u64, usize)> = Vec::new();
+    for &difficulty in difficulties {
+        let lower = 10u64.pow(difficulty.max(1).ilog10());
+        match buckets.iter_mut().find(|(bound, _)| *bound == lower) {
+            Some((_, count)) => *count += 1,
+            None             => buckets.push((lower, 1)),
+        }
+    }
+    buckets.sort_unstable();
+    buckets
+}
+
+fn print_summary(endpoint: &str, samples: &[&Sample], failures: usize) {
+    println!("{endpoint}");
+    println!("  Samples:       {} ({failures} failed)", samples.len());
+    if samples.is_empty() {
+        println!();
+        return;
+    }
+
+    let mut difficulties: Vec<u64> = samples.iter().map(|s| s.difficulty).collect();
+    let (min, median, max) = spread(&mut difficulties);
+    println!(
+        "  Difficulty:    min {}, median {}, max {}",
+        format_count(min),
+        format_count(median),
+        format_count(max),
+    );
+
+    let mut windows: Vec<u64> = samples.iter().map(|s| s.expiry_window_ms.max(0) as u64).collect();
+    let (min, median, max) = spread(&mut windows);
+    let window = |ms: u64| format_duration(Duration::from_millis(ms));
+    println!("  Expiry window: min {}, median {}, max {}", window(min), window(median), window(max));
+
+    let buckets = histogram(&difficulties);
+    let largest = buckets.iter().map(|(_, count)| *count).max().unwrap_or(1);
+    for (lower, count) in buckets {
+        let bar = crate::display::glyphs().filled.repeat((count * BAR_WIDTH).div_ceil(largest));
+        println!("  {:>15}+  {bar} {count}", format_count(lower));
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spread_and_histogram() {
+        let mut difficulties = [50_000, 8, 120_000, 70_000];
+
+        assert_eq!(spread(&mut difficulties), (8, 50_000, 120_000));
+        assert_eq!(histogram(&difficulties), [(1, 1), (10_000, 2), (100_000, 1)]);
+        assert_eq!(histogram(&[0]), [(1, 1)]);
+    }
+
+    #[test]
+    fn test_csv_quotes_endpoints() {
+        let sample = Sample {
+            endpoint:             "https://a.example/?a=1,2".to_string(),
+            timestamp:            DateTime::from_timestamp(0, 0).unwrap(),
+            difficulty:           1_000,
+            recommended_attempts: 2_000,
+            expiry_window_ms:     30_000,
+        };
+
+        assert_eq!(
+            to_csv(&[sample]).lines().nth(1),
+            Some("1970-01-01T00:00:00+00:00,\"https://a.example/?a=1,2\",1000,2000,30000"),
+        );
+    }
+}
@@ -1,60 +1,391 @@
-use ironshield::{
-    IronShieldClient,
-    ClientConfig,
-};
-use super::solve::solve_challenge_with_display;
-use std::time::Instant;
+use ironshield::ClientConfig;
+use ironshield_types::IronShieldToken;
+use color_eyre::eyre::eyre;
+use serde_json::json;
+use tokio::sync::mpsc;
+use super::solve::{fetch_and_solve, Solved};
+use crate::api_version::Negotiated;
+use crate::batch::{self, ListedEndpoint, Stage as BatchStage};
+use crate::client::CliClient;
+use crate::deadline::{Deadline, Stage};
+use crate::display::format_duration;
+use crate::failures::FailureReport;
+use crate::history::{self, ErrorKind, RunCommand, RunRecord};
+use crate::logging::LogCategory;
+use crate::margin;
+use crate::output::OutputSink;
+use crate::retry::InteractiveRetry;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long each stage of a validate run took.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StageTimings {
+    /// Reading the config file, before the run started.
+    pub config_load:  Duration,
+    pub fetch:        Duration,
+    /// Searching for a solution, without checking it.
+    pub solve:        Duration,
+    /// The core's check of the solution before it is submitted.
+    pub local_verify: Duration,
+    pub submit:       Duration,
+    /// Recording history, sending metrics and rendering the token.
+    pub bookkeeping:  Duration,
+    /// From loading the config to the token being ready to print.
+    pub total:        Duration,
+}
+
+impl StageTimings {
+    /// Every stage, in order, then the total, by the names used in
+    /// JSON and metrics.
+    pub fn stages(&self) -> [(&'static str, Duration); 7] {
+        [
+            ("config_load",  self.config_load),
+            ("fetch",        self.fetch),
+            ("solve",        self.solve),
+            ("local_verify", self.local_verify),
+            ("submit",       self.submit),
+            ("bookkeeping",  self.bookkeeping),
+            ("total",        self.total),
+        ]
+    }
+
+    /// e.g. `{"config_load_ms": 2, "fetch_ms": 120, ..., "total_ms": 2530}`.
+    pub fn to_json(&self) -> serde_json::Value {
+        self.stages()
+            .into_iter()
+            .map(|(name, duration)| (format!("{name}_ms"), json!(duration.as_millis() as u64)))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+
+    /// A table of the stages, one per line with the times aligned,
+    /// ending with the total.
+    pub fn describe(&self) -> String {
+        let mut table = "Stage timings:".to_string();
+        for (name, duration) in self.stages() {
+            table.push_str(&format!("\n  {:<12}  {:>8}", name.replace('_', " "), format_duration(duration)));
+        }
+        table
+    }
+}
+
+/// A token and how long each stage took to get it.
+#[derive(Debug, Clone)]
+pub struct Validated {
+    pub token:      IronShieldToken,
+    /// The API version the challenge was fetched with.
+    pub negotiated: Negotiated,
+    pub timings:    StageTimings,
+}
 
 /// Handles the validate command - fetches, solves, and validates a challenge from the specified endpoint
 pub async fn handle_validate(
-    client: &IronShieldClient, 
+    client: &CliClient, 
     config: &ClientConfig,
     endpoint: &str, 
-    single_threaded: bool
+    single_threaded: bool,
+    max_time: Option<Duration>,
+    config_load: Duration,
+    sink: &dyn OutputSink,
 ) -> color_eyre::Result<()> {
-    // Fetch the challenge
-    crate::verbose_section!(config, "Challenge Fetching");
-    crate::verbose_log!(config, network, "Requesting challenge for endpoint: {}", endpoint);
+    let mut retry = InteractiveRetry::new(client, config);
+    loop {
+        let mut record = RunRecord::new(RunCommand::Validate, endpoint);
+        record.config_load_ms = Some(config_load.as_millis() as u64);
+        let start_time = Instant::now();
+        let mut deadline = Deadline::start(max_time);
 
-    let fetch_start = Instant::now();
-    let challenge = client.fetch_challenge(endpoint).await?;
+        let result = validate(retry.client(), retry.config(), endpoint, single_threaded, &mut deadline, &mut record, sink).await;
 
-    crate::verbose_log!(
-        config,
-        timing,
-        "Challenge fetch completed in {:?}",
-        fetch_start.elapsed()
+        // Nothing after the submit is on the token's critical path, so the
+        // history write, the StatsD packet and rendering the token overlap.
+        let bookkeeping_start = Instant::now();
+        history::finish_with_result(&mut record, start_time.elapsed(), &result);
+        let error_kind = record.error_kind;
+        let render = async { result.as_ref().ok().map(|validated| serde_json::to_value(&validated.token)) };
+        let (_, token_json) = tokio::join!(record_run(record, config.verbose), render);
+        let mut validated = match result {
+            Ok(validated)                         => validated,
+            Err(e) if retry.offer(&e, error_kind) => continue,
+            Err(e)                                => return Err(e),
+        };
+        validated.timings.config_load = config_load;
+        validated.timings.bookkeeping = bookkeeping_start.elapsed();
+        validated.timings.total = config_load + start_time.elapsed();
+        crate::metrics::record_validate_stages(&validated.timings.stages());
+
+        sink.info(&validated.timings.describe());
+        sink.metric("stage_timings", validated.timings.to_json());
+        sink.result_json(token_json.expect("rendered for every successful run")?, Some(validated.negotiated));
+
+        crate::logging::flush();
+
+        crate::har::write();
+        std::process::exit(0);
+    }
+}
+
+/// Handles `validate --stdin`: validates every endpoint listed on
+/// stdin, `concurrency` at a time, as the list is read.
+///
+/// Prints one JSON line per endpoint as it finishes, keyed by the
+/// endpoint and its line number. Invalid lines are reported the same
+/// way and count as failures, like failed runs in `batch`, and every
+/// failure is summarized on stderr at the end.
+///
+/// # Arguments
+/// * `client`:       The API client.
+/// * `config`:       The client configuration.
+/// * `concurrency`:  Endpoints validated at once.
+/// * `failures_out`: Where to write every failure as JSON.
+pub async fn handle_validate_stdin(
+    client:       CliClient,
+    config:       &ClientConfig,
+    concurrency:  usize,
+    failures_out: Option<&Path>,
+) -> color_eyre::Result<()> {
+    let listed = batch::read_endpoint_list(std::io::BufReader::new(std::io::stdin()), concurrency * 2);
+    let pending: Mutex<HashMap<usize, String>> = Mutex::new(HashMap::new());
+    let tally = Mutex::new(Tally::default());
+    let report = Mutex::new(FailureReport::default());
+    let read_error: Mutex<Option<std::io::Error>> = Mutex::new(None);
+
+    let jobs = futures::stream::unfold(listed, |mut listed| async {
+        loop {
+            match listed.recv().await? {
+                Ok(ListedEndpoint::Valid { line, endpoint }) => {
+                    pending.lock().unwrap().insert(line, endpoint.clone());
+                    return Some(((line, endpoint), listed));
+                }
+                Ok(ListedEndpoint::Invalid { line, text, error }) => {
+                    tally.lock().unwrap().add(false);
+                    report.lock().unwrap().add(&text, "listed", "invalid", &format!("line {line}: {error}"));
+                    println!("{}", json!({ "endpoint": text, "line": line, "outcome": "invalid", "error": error }));
+                }
+                Err(e) => {
+                    *read_error.lock().unwrap() = Some(e);
+                    return None;
+                }
+            }
+        }
+    });
+
+    let (updates_tx, mut updates_rx) = mpsc::unbounded_channel();
+    let run = batch::run(Arc::new(client.with_pool()), jobs, concurrency, updates_tx, config.verbose);
+    let report = async {
+        while let Some(update) = updates_rx.recv().await {
+            let line = update.id;
+            let endpoint = match update.stage {
+                BatchStage::Done { .. } | BatchStage::Failed { .. } => pending.lock().unwrap().remove(&line),
+                _                                                   => pending.lock().unwrap().get(&line).cloned(),
+            }.unwrap_or_default();
+            report.lock().unwrap().observe(&update, &endpoint);
+            let (outcome, (key, value)) = match update.stage {
+                BatchStage::Done { valid_for, .. }        => ("ok", ("valid_for", json!(valid_for))),
+                BatchStage::Failed { message: error, .. } => ("failed", ("error", json!(error))),
+                _                                         => continue,
+            };
+            tally.lock().unwrap().add(outcome == "ok");
+            let mut entry = json!({ "endpoint": endpoint, "line": line, "outcome": outcome });
+            entry[key] = value;
+            println!("{entry}");
+        }
+    };
+    tokio::join!(run, report);
+
+    crate::logging::flush();
+    if let Some(e) = read_error.into_inner().unwrap() {
+        return Err(eyre!("Cannot read endpoints from stdin: {e}"));
+    }
+    let Tally { total, failures } = tally.into_inner().unwrap();
+    super::batch::finish_report(&report.into_inner().unwrap(), total, failures_out)?;
+    if total == 0 {
+        return Err(eyre!("No endpoints given"));
+    }
+    if failures > 0 {
+        return Err(eyre!("{failures} of {total} endpoints failed"));
+    }
+    Ok(())
+}
+
+/// Appends a finished `record` to the history and sends it to StatsD,
+/// side by side and off the async worker threads, since both block.
+async fn record_run(record: RunRecord, verbose: bool) {
+    let statsd_record = record.clone();
+    let (history, statsd) = tokio::join!(
+        tokio::task::spawn_blocking(move || history::record(&record)),
+        tokio::task::spawn_blocking(move || crate::metrics::send_statsd(&statsd_record, verbose)),
     );
+    // Both only log their failures; a panic in either is a bug, not a
+    // reason to fail a run that already has its token.
+    if let Err(e) = history.and(statsd) {
+        crate::logging::log_event(verbose, LogCategory::Warning, format_args!("Run bookkeeping failed: {e}"));
+    }
+}
+
+/// Endpoints finished so far in `validate --stdin`.
+#[derive(Debug, Default)]
+struct Tally {
+    total:    usize,
+    failures: usize,
+}
 
-    println!("Challenge fetched successfully!");
+impl Tally {
+    fn add(&mut self, ok: bool) {
+        self.total += 1;
+        if !ok {
+            self.failures += 1;
+        }
+    }
+}
 
-    crate::verbose_kv!(config, "Random Nonce", format!("{:?}", challenge.random_nonce));
-    crate::verbose_kv!(config, "Difficulty", challenge.recommended_attempts / 2);
-    crate::verbose_kv!(config, "Recommended Attempts", challenge.recommended_attempts);
+/// Fetches, solves and submits, filling in `record` along the way.
+/// Status lines go to `sink`; the token is returned with the time each
+/// stage took, not emitted.
+///
+/// Every stage runs within what is left of `deadline`, which is
+/// brought forward to the challenge's expiry once it is known.
+///
+/// Under `--auto-refresh-on-thin-margin`, a solve that leaves less than
+/// the slack for the submit is dropped for a fresh challenge, up to
+/// `[refetch] max_refetches` times.
+pub async fn validate(
+    client:          &CliClient,
+    config:          &ClientConfig,
+    endpoint:        &str,
+    single_threaded: bool,
+    deadline:        &mut Deadline,
+    record:          &mut RunRecord,
+    sink:            &dyn OutputSink,
+) -> color_eyre::Result<Validated> {
+    // The old challenge's expiry mustn't limit the solve of a fresh one.
+    let initial_deadline = deadline.clone();
+    let max_refreshes = crate::refetch::policy().max_refetches;
+    let mut refreshes = 0;
+    loop {
+        let solved = fetch_and_solve(client, config, endpoint, single_threaded, deadline, record, sink).await?;
+        let margin = margin::assess(solved.challenge.expiration_time, record);
+        if !(margin.is_thin() && margin::policy().auto_refresh && refreshes < max_refreshes) {
+            return submit(client, config, solved, deadline, record, sink).await;
+        }
+        refreshes += 1;
+        crate::logging::log_event(config.verbose, LogCategory::Warning, format_args!(
+            "{}; fetching a fresh challenge instead of submitting ({refreshes}/{max_refreshes})",
+            margin.describe(),
+        ));
+        crate::metrics::record_refetch();
+        *deadline = initial_deadline.clone();
+    }
+}
 
-    // Solve the challenge using our display wrapper
-    let solution = solve_challenge_with_display(challenge, config, !single_threaded).await?;
+/// The submit half of [`validate`], for callers that fetched and solved
+/// the challenge themselves.
+pub async fn submit(
+    client:   &CliClient,
+    config:   &ClientConfig,
+    solved:   Solved,
+    deadline: &mut Deadline,
+    record:   &mut RunRecord,
+    sink:     &dyn OutputSink,
+) -> color_eyre::Result<Validated> {
+    let mut timings = StageTimings {
+        fetch:        solved.fetch,
+        solve:        solved.solve,
+        local_verify: solved.local_verify,
+        ..StageTimings::default()
+    };
+    let solution = solved.solution;
 
     // Submit the solution for validation
-    crate::verbose_section!(config, "Solution Submission");
+    sink.section("Solution Submission");
     crate::verbose_log!(config, network, "Submitting solution...");
 
+    let margin = margin::assess(solved.challenge.expiration_time, record);
+    crate::verbose_log!(config, timing, "{}", margin.describe());
+    if margin.is_thin() {
+        sink.warning(&margin.warning());
+    }
+
+    deadline.log_stage(config.verbose, Stage::Submit);
+    crate::prewarm::log_reuse();
     let submit_start = Instant::now();
-    let token = client.submit_solution(&solution).await?;
+    // The solution is logged while the request is in flight, the way
+    // the challenge is logged once it arrives.
+    let log_solution = async {
+        crate::logging::file_event(LogCategory::Submit, format_args!("Solution: {solution:?}"));
+    };
+    let (token, ()) = tokio::join!(deadline.limit(Stage::Submit, client.submit(&solution)), log_solution);
+    let token = token
+        .map_err(color_eyre::Report::from)
+        .and_then(|result| result);
+    let margin = margin.landed(chrono::Utc::now().timestamp_millis());
+    margin.record(record);
+    sink.metric("submit_margin", margin.to_json());
+    let token = token.inspect_err(|e| {
+        record.error_kind = Some(ErrorKind::Submit);
+        crate::logging::log_event(true, LogCategory::Error, format_args!("Submit failed ({}): {e}", margin.brief()));
+    })?;
+    timings.submit = submit_start.elapsed();
+    record.submit_ms = Some(timings.submit.as_millis() as u64);
+    record.token_valid_for = Some(token.valid_for);
+    crate::metrics::record_submit(submit_start.elapsed(), token.valid_for);
 
     crate::verbose_log!(
         config,
         timing,
-        "Solution submission completed in {:?}",
-        submit_start.elapsed()
+        "Solution submission completed in {}",
+        format_duration(submit_start.elapsed())
     );
 
-    println!("Challenge validated successfully!");
+    sink.info("Challenge validated successfully!");
     
     crate::verbose_log!(config, success, "Token generated successfully!");
-    crate::verbose_kv!(config, "Token Valid Until", token.valid_for);
+    sink.kv("Token Valid Until", &token.valid_for);
+    Ok(Validated { token, negotiated: solved.negotiated, timings })
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timings() -> StageTimings {
+        StageTimings {
+            config_load:  Duration::from_millis(2),
+            fetch:        Duration::from_millis(120),
+            solve:        Duration::from_millis(2_300),
+            local_verify: Duration::from_millis(1),
+            submit:       Duration::from_millis(95),
+            bookkeeping:  Duration::from_millis(3),
+            total:        Duration::from_millis(2_530),
+        }
+    }
 
-    println!("Token: {token:?}");
+    #[test]
+    fn test_stage_timings_name_every_stage() {
+        assert_eq!(timings().describe(), [
+            "Stage timings:",
+            "  config load        2ms",
+            "  fetch            120ms",
+            "  solve             2.3s",
+            "  local verify       1ms",
+            "  submit            95ms",
+            "  bookkeeping        3ms",
+            "  total             2.5s",
+        ].join("\n"));
+    }
 
-    std::process::exit(0);
-} 
\ No newline at end of file
+    #[test]
+    fn test_stage_timings_json() {
+        assert_eq!(timings().to_json(), json!({
+            "config_load_ms":  2,
+            "fetch_ms":        120,
+            "solve_ms":        2_300,
+            "local_verify_ms": 1,
+            "submit_ms":       95,
+            "bookkeeping_ms":  3,
+            "total_ms":        2_530,
+        }));
+    }
+}
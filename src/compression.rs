@@ -0,0 +1,192 @@
+use clap::ValueEnum;
+use flate2::Compression as GzipLevel;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// A compressed stream format, picked by file extension or, for
+/// stdin and stdout, by `--compress` and `--decompress`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Compression {
+    /// `.gz`
+    Gzip,
+    /// `.zst`; needs the `zstd` feature.
+    Zstd,
+}
+
+impl Compression {
+    /// The format a path's extension names, if any.
+    pub fn detect(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "gz"  => Some(Self::Gzip),
+            "zst" => Some(Self::Zstd),
+            _     => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+        }
+    }
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd_unavailable() -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported, "zstd support is not compiled in; rebuild with `--features zstd`")
+}
+
+/// Decompresses `inner` as it is read.
+///
+/// # Arguments
+/// * `inner`:       The raw stream.
+/// * `compression`: Its format, or `None` to pass it through.
+pub fn decoder<'a>(inner: impl BufRead + 'a, compression: Option<Compression>) -> io::Result<Box<dyn Read + 'a>> {
+    Ok(match compression {
+        None                    => Box::new(inner),
+        Some(Compression::Gzip) => Box::new(MultiGzDecoder::new(inner)),
+        #[cfg(feature = "zstd")]
+        Some(Compression::Zstd) => Box::new(zstd::Decoder::with_buffer(inner)?),
+        #[cfg(not(feature = "zstd"))]
+        Some(Compression::Zstd) => return Err(zstd_unavailable()),
+    })
+}
+
+/// Opens `path` for reading, decompressing it if its extension says so.
+pub fn open(path: &Path) -> io::Result<Box<dyn Read>> {
+    decoder(BufReader::new(File::open(path)?), Compression::detect(path))
+}
+
+/// Reads a whole stream as UTF-8, naming the format when it turns
+/// out not to be one, e.g. a truncated `.gz` file.
+pub fn read_to_string(mut reader: impl Read, compression: Option<Compression>) -> io::Result<String> {
+    let mut contents = String::new();
+    reader.read_to_string(&mut contents).map_err(|e| match compression {
+        Some(compression) => io::Error::new(e.kind(), format!("corrupt {} stream: {e}", compression.name())),
+        None              => e,
+    })?;
+    Ok(contents)
+}
+
+/// A stream compressed as it is written. Call [`Writer::finish`] to
+/// write the format's trailer; dropping it without doing so leaves a
+/// truncated stream.
+pub enum Writer<W: Write> {
+    Plain(W),
+    Gzip(GzEncoder<W>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> Writer<W> {
+    /// Compresses what is written to `inner`.
+    ///
+    /// # Arguments
+    /// * `inner`:       Where the compressed bytes go.
+    /// * `compression`: The format, or `None` to write as is.
+    pub fn new(inner: W, compression: Option<Compression>) -> io::Result<Self> {
+        Ok(match compression {
+            None                    => Self::Plain(inner),
+            Some(Compression::Gzip) => Self::Gzip(GzEncoder::new(inner, GzipLevel::default())),
+            #[cfg(feature = "zstd")]
+            Some(Compression::Zstd) => Self::Zstd(zstd::Encoder::new(inner, 0)?),
+            #[cfg(not(feature = "zstd"))]
+            Some(Compression::Zstd) => return Err(zstd_unavailable()),
+        })
+    }
+
+    /// Ends the compressed stream and flushes it.
+    pub fn finish(self) -> io::Result<W> {
+        let mut inner = match self {
+            Self::Plain(inner)  => inner,
+            Self::Gzip(encoder) => encoder.finish()?,
+            #[cfg(feature = "zstd")]
+            Self::Zstd(encoder) => encoder.finish()?,
+        };
+        inner.flush()?;
+        Ok(inner)
+    }
+}
+
+impl<W: Write> Write for Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(inner)  => inner.write(buf),
+            Self::Gzip(encoder) => encoder.write(buf),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Plain(inner)  => inner.flush(),
+            Self::Gzip(encoder) => encoder.flush(),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Creates `path`, compressing what is written if its extension says so.
+pub fn create(path: &Path) -> io::Result<Writer<BufWriter<File>>> {
+    Writer::new(BufWriter::new(File::create(path)?), Compression::detect(path))
+}
+
+/// Writes `contents` to `path`, compressed if its extension says so.
+pub fn write(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut writer = create(path)?;
+    writer.write_all(contents)?;
+    writer.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JSON: &str = "{\"challenge\": \"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\"}\n";
+
+    fn round_trip(compression: Option<Compression>) -> String {
+        let mut writer = Writer::new(Vec::new(), compression).unwrap();
+        writer.write_all(JSON.as_bytes()).unwrap();
+        let bytes = writer.finish().unwrap();
+        read_to_string(decoder(bytes.as_slice(), compression).unwrap(), compression).unwrap()
+    }
+
+    #[test]
+    fn test_round_trips() {
+        assert_eq!(round_trip(None), JSON);
+        assert_eq!(round_trip(Some(Compression::Gzip)), JSON);
+        #[cfg(feature = "zstd")]
+        assert_eq!(round_trip(Some(Compression::Zstd)), JSON);
+    }
+
+    #[test]
+    fn test_files_are_compressed_by_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("challenge.json.gz");
+
+        write(&path, JSON.as_bytes()).unwrap();
+
+        assert_eq!(&std::fs::read(&path).unwrap()[..2], [0x1f, 0x8b]);
+        assert_eq!(read_to_string(open(&path).unwrap(), Some(Compression::Gzip)).unwrap(), JSON);
+        assert_eq!(Compression::detect(Path::new("a.json")), None);
+        assert_eq!(Compression::detect(Path::new("a.json.zst")), Some(Compression::Zstd));
+    }
+
+    #[test]
+    fn test_corrupted_stream_is_an_error() {
+        let mut writer = Writer::new(Vec::new(), Some(Compression::Gzip)).unwrap();
+        writer.write_all(JSON.as_bytes()).unwrap();
+        let mut bytes = writer.finish().unwrap();
+        bytes.truncate(bytes.len() / 2);
+
+        let error = read_to_string(decoder(bytes.as_slice(), Some(Compression::Gzip)).unwrap(), Some(Compression::Gzip));
+        assert!(error.unwrap_err().to_string().starts_with("corrupt gzip stream"));
+    }
+}
@@ -0,0 +1,138 @@
+use color_eyre::eyre::eyre;
+use ironshield::ClientConfig;
+
+use crate::client::CliClient;
+use crate::config::ConfigManager;
+
+/// What a long-running command was asked to do by a signal.
+///
+/// `SIGINT` is deliberately not caught: Ctrl-C keeps stopping the
+/// process at once, as it always has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Request {
+    /// `SIGTERM`: finish the work in flight, flush, and exit 0.
+    Shutdown,
+    /// `SIGHUP`: read the config file again.
+    Reload,
+}
+
+/// `SIGTERM` and `SIGHUP`, as [`Request`]s.
+///
+/// A signal arriving while work is in flight stays pending until the
+/// next [`Signals::recv`], so callers finish what they are doing
+/// simply by not selecting on it until they are done.
+pub struct Signals {
+    #[cfg(unix)]
+    terminate: tokio::signal::unix::Signal,
+    #[cfg(unix)]
+    hangup:    tokio::signal::unix::Signal,
+}
+
+impl Signals {
+    /// Starts listening; signals before this get their default action.
+    pub fn install() -> std::io::Result<Self> {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            Ok(Self { terminate: signal(SignalKind::terminate())?, hangup: signal(SignalKind::hangup())? })
+        }
+        #[cfg(not(unix))]
+        {
+            Ok(Self {})
+        }
+    }
+
+    /// Waits for the next signal. Never returns where there are none.
+    pub async fn recv(&mut self) -> Request {
+        #[cfg(unix)]
+        {
+            tokio::select! {
+                _ = self.terminate.recv() => Request::Shutdown,
+                _ = self.hangup.recv()    => Request::Reload,
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            std::future::pending().await
+        }
+    }
+}
+
+/// The configuration and the client built from it, swapped on reload.
+pub struct Supervised {
+    pub client:      CliClient,
+    pub config:      ClientConfig,
+    /// The file to read on `SIGHUP`; nothing to reload without one.
+    pub config_path: Option<String>,
+}
+
+impl Supervised {
+    /// Re-reads the config file, logging what changed. The client is
+    /// rebuilt on the new file, keeping its solver, and says so if a
+    /// field it uses for requests changed. A file that doesn't load or
+    /// validate leaves everything as it was.
+    ///
+    /// The file is applied on its own: flags such as `--timeout` that
+    /// overrode it at startup aren't applied again.
+    ///
+    /// # Returns
+    /// * `bool`: Whether the configuration in use is valid: false only
+    ///           if the file failed to load.
+    pub fn reload(&mut self) -> bool {
+        let Some(path) = self.config_path.clone() else {
+            crate::status_println!("SIGHUP: no config file given, nothing to reload");
+            return true;
+        };
+        let result = ConfigManager::load_client_config(&path)
+            .map_err(|e| eyre!("{e}"))
+            .and_then(|config| {
+                let client = self.client.reconfigured(config.clone()).map_err(|e| eyre!("{e}"))?;
+                Ok((config, client))
+            });
+        match result {
+            Ok((config, client)) => {
+                let changes = crate::config::diff(&self.config, &config);
+                if changes.is_empty() {
+                    crate::status_println!("SIGHUP: reloaded {path}; nothing changed");
+                } else {
+                    crate::status_println!("SIGHUP: reloaded {path}:\n  {}", changes.join("\n  "));
+                }
+                if network_changed(&self.config, &config) {
+                    crate::status_println!("SIGHUP: network settings changed; the API client was rebuilt");
+                }
+                self.client = client;
+                self.config = config;
+                true
+            }
+            Err(e) => {
+                crate::status_println!("SIGHUP: keeping the current configuration; {path} is invalid: {e}");
+                false
+            }
+        }
+    }
+}
+
+/// Whether requests would go out differently with `new`.
+fn network_changed(old: &ClientConfig, new: &ClientConfig) -> bool {
+    old.api_base_url != new.api_base_url || old.timeout != new.timeout || old.user_agent != new.user_agent
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_network_changed() {
+        let old = ClientConfig::default();
+        let mut new = old.clone();
+        assert!(!network_changed(&old, &new));
+
+        new.timeout = old.timeout + Duration::from_secs(2);
+        assert!(network_changed(&old, &new));
+
+        let mut quieter = old.clone();
+        quieter.set_verbose(!old.verbose);
+        assert!(!network_changed(&old, &quieter));
+    }
+}
@@ -0,0 +1,175 @@
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use crate::display::format_duration;
+use crate::logging::{log_event, LogCategory};
+
+/// A step of a validate run that the deadline applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Fetch,
+    Solve,
+    Submit,
+}
+
+impl Stage {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Fetch  => "fetch",
+            Self::Solve  => "solve",
+            Self::Submit => "submit",
+        }
+    }
+}
+
+/// A stage ran past the deadline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeadlineExceeded {
+    pub stage:  Stage,
+    /// What the stage had left when it started.
+    pub budget: Duration,
+    /// What set the deadline, e.g. "--max-time 30s".
+    pub source: String,
+}
+
+impl std::fmt::Display for DeadlineExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Timed out during {}: it had {} left of the {}",
+            self.stage.name(),
+            format_duration(self.budget),
+            self.source,
+        )
+    }
+}
+
+impl std::error::Error for DeadlineExceeded {}
+
+/// The time a whole command has, shared by its stages so that a slow
+/// fetch leaves less for solving instead of each stage timing out on
+/// its own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deadline {
+    at:     Option<Instant>,
+    source: String,
+}
+
+impl Deadline {
+    /// No limit until [`Deadline::tighten_to_expiry`] sets one.
+    pub fn unbounded() -> Self {
+        Self { at: None, source: "no deadline".to_string() }
+    }
+
+    /// Starts the clock on a command.
+    ///
+    /// # Arguments
+    /// * `max_time`: `--max-time`, if given.
+    pub fn start(max_time: Option<Duration>) -> Self {
+        match max_time {
+            Some(max_time) => Self::within(max_time, "--max-time"),
+            None           => Self::unbounded(),
+        }
+    }
+
+    /// A deadline `max_time` from now.
+    ///
+    /// # Arguments
+    /// * `max_time`: The time allowed.
+    /// * `setting`:  What set it, e.g. "max_time", for the error message.
+    pub fn within(max_time: Duration, setting: &str) -> Self {
+        Self { at: Some(Instant::now() + max_time), source: format!("{setting} {}", format_duration(max_time)) }
+    }
+
+    /// Brings the deadline forward to the challenge's expiry, if that
+    /// comes first; a token for an expired challenge is never issued.
+    ///
+    /// # Arguments
+    /// * `expiration_time`: The challenge's expiry in Unix milliseconds.
+    pub fn tighten_to_expiry(&mut self, expiration_time: i64) {
+        let left_ms = expiration_time.saturating_sub(chrono::Utc::now().timestamp_millis()).max(0) as u64;
+        self.tighten(Instant::now() + Duration::from_millis(left_ms), "challenge's validity window");
+    }
+
+    fn tighten(&mut self, at: Instant, source: &str) {
+        if self.at.is_none_or(|current| at < current) {
+            self.at = Some(at);
+            self.source = source.to_string();
+        }
+    }
+
+    /// Time left, or `None` without a deadline.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.at.map(|at| at.saturating_duration_since(Instant::now()))
+    }
+
+    /// e.g. "12.5s (challenge's validity window)".
+    pub fn describe_remaining(&self) -> String {
+        match self.remaining() {
+            Some(remaining) => format!("{} ({})", format_duration(remaining), self.source),
+            None            => "unlimited".to_string(),
+        }
+    }
+
+    /// Logs the remaining budget as `stage` begins.
+    pub fn log_stage(&self, verbose: bool, stage: Stage) {
+        log_event(verbose, LogCategory::Timing, format_args!(
+            "Budget before {}: {}",
+            stage.name(),
+            self.describe_remaining(),
+        ));
+    }
+
+    /// Runs one stage within the remaining budget. Running out drops
+    /// `future`, which cancels the request or the solve.
+    ///
+    /// # Arguments
+    /// * `stage`:  The stage, for the error message.
+    /// * `future`: The stage's work.
+    pub async fn limit<T>(&self, stage: Stage, future: impl Future<Output = T>) -> Result<T, DeadlineExceeded> {
+        let Some(budget) = self.remaining() else {
+            return Ok(future.await);
+        };
+        tokio::time::timeout(budget, future)
+            .await
+            .map_err(|_| DeadlineExceeded { stage, budget, source: self.source.clone() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expiry_tightens_only_when_sooner() {
+        let mut deadline = Deadline::start(Some(Duration::from_secs(60)));
+        deadline.tighten_to_expiry(chrono::Utc::now().timestamp_millis() + 600_000);
+        assert!(deadline.remaining().unwrap() > Duration::from_secs(50));
+        assert!(deadline.source.starts_with("--max-time"));
+
+        deadline.tighten_to_expiry(chrono::Utc::now().timestamp_millis() + 10_000);
+        assert!(deadline.remaining().unwrap() <= Duration::from_secs(10));
+        assert_eq!(deadline.source, "challenge's validity window");
+    }
+
+    #[test]
+    fn test_expired_challenge_leaves_nothing() {
+        let mut deadline = Deadline::unbounded();
+        assert_eq!(deadline.remaining(), None);
+
+        deadline.tighten_to_expiry(chrono::Utc::now().timestamp_millis() - 5_000);
+        assert_eq!(deadline.remaining(), Some(Duration::ZERO));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_timeout_names_the_stage() {
+        let deadline = Deadline::start(Some(Duration::from_secs(30)));
+
+        let error = deadline.limit(Stage::Solve, tokio::time::sleep(Duration::from_secs(31))).await.unwrap_err();
+
+        assert_eq!(error.stage, Stage::Solve);
+        assert!(error.to_string().starts_with("Timed out during solve: it had"));
+        assert!(error.to_string().ends_with("of the --max-time 30.0s"));
+        assert!(deadline.limit(Stage::Submit, async { 7 }).await.is_ok());
+    }
+}
@@ -1,18 +1,228 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
 use tokio::task::JoinHandle;
 use tokio::time::{interval, Duration};
 
+use std::time::Instant;
 use std::sync::{
     Arc, 
     atomic::{
         AtomicBool, 
+        AtomicU8,
+        AtomicU32,
         Ordering
     }
 };
-use std::io::Write;
+
+/// How often the non-interactive fallback reports that work is still running.
+const PLAIN_PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The active [`ProgressMode`], stored as its discriminant.
+static PROGRESS_MODE: AtomicU8 = AtomicU8::new(ProgressMode::Auto as u8);
+
+/// The active [`NumberFormat`], stored as its discriminant.
+static NUMBER_FORMAT: AtomicU8 = AtomicU8::new(NumberFormat::Grouped as u8);
+
+/// The separator `grouped` puts between thousands, as a `char`.
+static GROUP_SEPARATOR: AtomicU32 = AtomicU32::new(',' as u32);
+
+/// Whether decorative output uses [`Glyphs::ASCII`].
+static ASCII_GLYPHS: AtomicBool = AtomicBool::new(false);
+
+/// How counts such as difficulties and attempts are written for people.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum NumberFormat {
+    /// Digits only: `1234567`.
+    Plain,
+    /// Digits in groups of three: `1,234,567`, or with `group_separator`.
+    #[default]
+    Grouped,
+    /// SI-abbreviated: `1.2M`.
+    Si,
+}
+
+impl NumberFormat {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::Plain,
+            2 => Self::Si,
+            _ => Self::Grouped,
+        }
+    }
+}
+
+/// Selects how [`format_count`] writes numbers for the rest of the
+/// process.
+///
+/// # Arguments
+/// * `format`:    From `--number-format` or the config file.
+/// * `separator`: Between groups of three digits with `grouped`.
+pub fn set_number_format(format: NumberFormat, separator: char) {
+    NUMBER_FORMAT.store(format as u8, Ordering::Relaxed);
+    GROUP_SEPARATOR.store(separator as u32, Ordering::Relaxed);
+}
+
+/// Whether progress is drawn as an interactive spinner.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ProgressMode {
+    /// Spinner on a terminal, plain progress lines otherwise.
+    #[default]
+    Auto,
+    /// Always draw the spinner, even when output is redirected.
+    Always,
+    /// Never draw the spinner; use plain progress lines.
+    Never,
+}
+
+impl ProgressMode {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Always,
+            2 => Self::Never,
+            _ => Self::Auto,
+        }
+    }
+
+    /// Decides whether the spinner should be drawn.
+    ///
+    /// # Arguments
+    /// * `is_terminal`: Whether the console stream is a terminal.
+    ///
+    /// # Returns
+    /// * `bool`: `true` for the interactive spinner,
+    ///           `false` for plain progress lines.
+    pub fn is_interactive(self, is_terminal: bool) -> bool {
+        match self {
+            Self::Auto   => is_terminal,
+            Self::Always => true,
+            Self::Never  => false,
+        }
+    }
+}
+
+/// Selects how progress is drawn for the rest of the process.
+///
+/// # Arguments
+/// * `mode`: The progress mode from `--progress` or the config file.
+pub fn set_progress_mode(mode: ProgressMode) {
+    PROGRESS_MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+/// The characters decorative output is drawn with: section headers,
+/// spinners, bars and empty table cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Glyphs {
+    /// Before a verbose section title.
+    pub section: &'static str,
+    /// Repeated to underline a section title.
+    pub rule:    &'static str,
+    /// Spinner frames, in order.
+    pub spinner: [&'static str; 4],
+    /// A filled cell of a progress bar or histogram.
+    pub filled:  &'static str,
+    /// An empty cell of a progress bar.
+    pub empty:   &'static str,
+    /// A table cell without a value.
+    pub missing: &'static str,
+    /// Between a setting's old and new value.
+    pub arrow:   &'static str,
+}
+
+impl Glyphs {
+    pub const UNICODE: Glyphs = Glyphs {
+        section: "🔸 ",
+        rule:    "─",
+        spinner: ["|", "/", "—", "\\"],
+        filled:  "█",
+        empty:   "░",
+        missing: "—",
+        arrow:   "→",
+    };
+
+    /// For terminals on a legacy code page or a non-UTF-8 locale.
+    pub const ASCII: Glyphs = Glyphs {
+        section: "==>",
+        rule:    "-",
+        spinner: ["|", "/", "-", "\\"],
+        filled:  "#",
+        empty:   ".",
+        missing: "-",
+        arrow:   "->",
+    };
+
+    /// A section title and the rule under it.
+    pub fn section_lines(&self, title: &str) -> [String; 2] {
+        [format!("{} {title}", self.section), self.rule.repeat(40)]
+    }
+
+    /// `filled` cells followed by empty ones up to `width`.
+    pub fn bar(&self, filled: usize, width: usize) -> String {
+        let filled = filled.min(width);
+        format!("{}{}", self.filled.repeat(filled), self.empty.repeat(width - filled))
+    }
+}
+
+/// Selects the glyph set for the rest of the process.
+///
+/// # Arguments
+/// * `ascii`: From `--ascii`, `ascii_glyphs` in the config file, or a
+///            console that fails [`console_supports_unicode`].
+pub fn set_ascii_glyphs(ascii: bool) {
+    ASCII_GLYPHS.store(ascii, Ordering::Relaxed);
+}
+
+/// The glyph set selected by [`set_ascii_glyphs`].
+pub fn glyphs() -> &'static Glyphs {
+    match ASCII_GLYPHS.load(Ordering::Relaxed) {
+        true  => &Glyphs::ASCII,
+        false => &Glyphs::UNICODE,
+    }
+}
+
+/// Whether the console can show [`Glyphs::UNICODE`]. Output that isn't
+/// a terminal goes to a file or another program and keeps Unicode; a
+/// Windows console needs the UTF-8 code page or Windows Terminal, and
+/// elsewhere the locale must not name another encoding.
+pub fn console_supports_unicode() -> bool {
+    if !crate::logging::console_is_terminal() {
+        return true;
+    }
+    #[cfg(windows)]
+    {
+        // SAFETY: GetConsoleOutputCP only reads the console's state.
+        let code_page = unsafe { windows_sys::Win32::System::Console::GetConsoleOutputCP() };
+        code_page == UTF8_CODE_PAGE || std::env::var_os("WT_SESSION").is_some()
+    }
+    #[cfg(not(windows))]
+    {
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .into_iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty());
+        locale_supports_unicode(locale.as_deref())
+    }
+}
+
+#[cfg(windows)]
+const UTF8_CODE_PAGE: u32 = 65001;
+
+/// Whether a locale such as `en_US.UTF-8` is Unicode. No locale at all
+/// is taken to be, as on most modern systems; `C` and `POSIX` are not.
+#[cfg_attr(windows, allow(dead_code))]
+fn locale_supports_unicode(locale: Option<&str>) -> bool {
+    let Some(locale) = locale else {
+        return true;
+    };
+    let locale = locale.to_ascii_lowercase();
+    locale.contains("utf-8") || locale.contains("utf8")
+}
 
 pub struct ProgressAnimation {
-    running: Arc<AtomicBool>,
-    verbose: bool,
+    running:     Arc<AtomicBool>,
+    verbose:     bool,
+    interactive: bool,
 }
 
 impl ProgressAnimation {
@@ -26,37 +236,47 @@ impl ProgressAnimation {
     /// # Returns
     /// * `Self`: A new ProgressAnimation instance
     pub fn new(verbose: bool) -> Self {
+        let mode = ProgressMode::from_u8(PROGRESS_MODE.load(Ordering::Relaxed));
         Self {
-            running: Arc::new(AtomicBool::new(false)),
+            running:     Arc::new(AtomicBool::new(false)),
             verbose,
+            interactive: mode.is_interactive(crate::logging::console_is_terminal()),
         }
     }
 
-    /// Starts the progress animation if not in verbose mode.
+    /// Starts the progress animation if not in verbose or quiet mode.
     ///
     /// # Returns
     /// * `Option<JoinHandle<()>>`: A handle to the animation 
     ///                             task if started, None if 
-    ///                             in verbose mode.
+    ///                             in verbose or quiet mode.
     ///
     /// # Example
-    /// ```
+    /// ```ignore
     /// let animation = ProgressAnimation::new(false);
     /// let handle = animation.start();
     /// // ... do work ...
     /// animation.stop(handle).await;
     /// ```
     pub fn start(&self) -> Option<JoinHandle<()>> {
-        if self.verbose {
+        if self.verbose || crate::logging::is_quiet() {
             return None;
         }
 
         self.running.store(true, Ordering::Relaxed);
         let running_clone = Arc::clone(&self.running);
-        
-        Some(tokio::spawn(async move {
-            show_progress_animation(running_clone).await;
-        }))
+
+        // Carriage-return frames are garbage in a log file,
+        // so redirected output gets plain lines instead.
+        if self.interactive {
+            Some(tokio::spawn(async move {
+                show_progress_animation(running_clone).await;
+            }))
+        } else {
+            Some(tokio::spawn(async move {
+                show_plain_progress(running_clone).await;
+            }))
+        }
     }
 
     /// Stops the progress animation and cleans up the display.
@@ -66,7 +286,7 @@ impl ProgressAnimation {
     ///             returned from `start()`
     ///
     /// # Example
-    /// ```
+    /// ```ignore
     /// let animation = ProgressAnimation::new(false);
     /// let handle = animation.start();
     /// // ... do work ...
@@ -79,9 +299,8 @@ impl ProgressAnimation {
         // Wait for the animation task to complete and clean up the line
         if let Some(animation_handle) = handle {
             let _ = animation_handle.await; // Wait for animation to stop
-            if !self.verbose {
-                print!("\r\x1b[K"); // Clear the animation line
-                std::io::stdout().flush().unwrap_or(());
+            if !self.verbose && self.interactive {
+                crate::logging::write_inline(format_args!("\r\x1b[K")); // Clear the animation line
             }
         }
     }
@@ -90,8 +309,8 @@ impl ProgressAnimation {
 /// Shows a simple spinning animation while a 
 /// long-running operation is in progress.
 /// 
-/// The animation cycles through different 
-/// characters to create a spinning effect:
+/// The animation cycles through the spinner
+/// frames of the active [`Glyphs`], e.g.
 /// | / — \
 ///
 /// # Arguments
@@ -99,22 +318,207 @@ impl ProgressAnimation {
 ///              when the animation should stop
 async fn show_progress_animation(running: Arc<AtomicBool>) {
     let mut timer = interval(Duration::from_millis(250));
-    let dots_patterns: [&'static str; 4] = ["|", "/", "—", "\\"];
+    let dots_patterns = glyphs().spinner;
     let mut pattern_index: usize = 0;
 
     // Skip the first tick (it fires immediately)
     timer.tick().await;
 
     while running.load(Ordering::Relaxed) {
-        print!("\r\x1b[KSolving Challenge {}", dots_patterns[pattern_index]);
-        std::io::stdout().flush().unwrap_or(());
-        
+        crate::logging::write_inline(
+            format_args!("\r\x1b[KSolving Challenge {}", dots_patterns[pattern_index])
+        );
+
         pattern_index = (pattern_index + 1) % dots_patterns.len(); 
         
         timer.tick().await;
     }
 }
 
+/// Reports progress without a spinner for non-interactive output:
+/// one line up front, then a line every [`PLAIN_PROGRESS_INTERVAL`].
+///
+/// # Arguments
+/// * `running`: An atomic boolean that controls
+///              when the reporting should stop
+async fn show_plain_progress(running: Arc<AtomicBool>) {
+    crate::status_println!("Solving challenge... this may take a while.");
+
+    // Tick faster than we report so that stopping is prompt.
+    let mut timer = interval(Duration::from_millis(250));
+    let start = Instant::now();
+    let mut next_report = PLAIN_PROGRESS_INTERVAL;
+
+    timer.tick().await;
+
+    while running.load(Ordering::Relaxed) {
+        timer.tick().await;
+
+        let elapsed = start.elapsed();
+        if elapsed >= next_report && running.load(Ordering::Relaxed) {
+            crate::status_println!("{}", plain_progress_line(elapsed));
+            next_report += PLAIN_PROGRESS_INTERVAL;
+        }
+    }
+}
+
+/// Builds a periodic progress line for non-interactive output.
+///
+/// # Arguments
+/// * `elapsed`: Time spent solving so far.
+///
+/// # Returns
+/// * `String`: A single line containing no control characters.
+fn plain_progress_line(elapsed: Duration) -> String {
+    format!("Still solving... {} elapsed", format_duration(elapsed))
+}
+
+/// Progress of a download: bytes so far against `Content-Length` when
+/// known, with throughput and an ETA, or a spinner and running byte
+/// count when not.
+///
+/// Drawn on the console stream (stderr), so a body piped from stdout
+/// stays clean. Updated from the download loop rather than a task, so
+/// it never draws after the last chunk.
+pub struct TransferProgress {
+    total:       Option<u64>,
+    done:        u64,
+    start:       Instant,
+    last_draw:   Option<Instant>,
+    frame:       usize,
+    interactive: bool,
+    enabled:     bool,
+}
+
+impl TransferProgress {
+    /// How often the interactive bar is redrawn.
+    const REDRAW_INTERVAL: Duration = Duration::from_millis(250);
+
+    /// Starts tracking a transfer of `total` bytes, if known.
+    pub fn new(total: Option<u64>) -> Self {
+        let mode = ProgressMode::from_u8(PROGRESS_MODE.load(Ordering::Relaxed));
+        Self {
+            total,
+            done:        0,
+            start:       Instant::now(),
+            last_draw:   None,
+            frame:       0,
+            interactive: mode.is_interactive(crate::logging::console_is_terminal()),
+            enabled:     !crate::logging::is_quiet(),
+        }
+    }
+
+    /// Records `bytes` more received and redraws when due: every
+    /// 250ms on a terminal, every [`PLAIN_PROGRESS_INTERVAL`] otherwise.
+    pub fn advance(&mut self, bytes: u64) {
+        self.done += bytes;
+        if !self.enabled {
+            return;
+        }
+        let interval = if self.interactive { Self::REDRAW_INTERVAL } else { PLAIN_PROGRESS_INTERVAL };
+        let since = self.last_draw.unwrap_or(self.start).elapsed();
+        if since < interval {
+            return;
+        }
+        self.last_draw = Some(Instant::now());
+
+        let line = transfer_line(self.done, self.total, self.start.elapsed());
+        if self.interactive {
+            let spinner = match self.total {
+                Some(_) => String::new(),
+                None    => format!("{} ", glyphs().spinner[self.frame % 4]),
+            };
+            self.frame += 1;
+            crate::logging::write_inline(format_args!("\r\x1b[K{spinner}{line}"));
+        } else {
+            crate::status_println!("{line}");
+        }
+    }
+
+    /// Clears the bar.
+    ///
+    /// # Returns
+    /// * `String`: The size, time and average throughput, e.g.
+    ///             `3.0 MiB in 2.0s (1.5 MiB/s average)`.
+    pub fn finish(self) -> String {
+        if self.enabled && self.interactive && self.last_draw.is_some() {
+            crate::logging::write_inline(format_args!("\r\x1b[K"));
+        }
+        let elapsed = self.start.elapsed();
+        format!(
+            "{} in {} ({} average)",
+            format_bytes(self.done),
+            format_duration(elapsed),
+            format_throughput(self.done, elapsed),
+        )
+    }
+}
+
+/// One progress line, e.g.
+/// `Downloading 1.5 MiB / 3.0 MiB (50%), 1.0 MiB/s, ETA 1.5s`.
+///
+/// # Arguments
+/// * `done`:    Bytes received so far.
+/// * `total`:   The expected size, if the server sent one.
+/// * `elapsed`: Time since the transfer started.
+fn transfer_line(done: u64, total: Option<u64>, elapsed: Duration) -> String {
+    let rate = format_throughput(done, elapsed);
+    match total {
+        Some(total) if total > 0 => {
+            let percent = (done as f64 / total as f64 * 100.0).min(100.0);
+            let seconds = elapsed.as_secs_f64();
+            let eta = match done {
+                0    => "unknown".to_string(),
+                done => format_duration(Duration::from_secs_f64(
+                    seconds * total.saturating_sub(done) as f64 / done as f64,
+                )),
+            };
+            format!("Downloading {} / {} ({percent:.0}%), {rate}, ETA {eta}", format_bytes(done), format_bytes(total))
+        }
+        _ => format!("Downloading {}, {rate}", format_bytes(done)),
+    }
+}
+
+/// Average throughput, e.g. `1.5 MiB/s`.
+pub fn format_throughput(bytes: u64, elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs_f64();
+    if seconds <= 0.0 {
+        return format!("{}/s", format_bytes(0));
+    }
+    format!("{}/s", format_bytes((bytes as f64 / seconds) as u64))
+}
+
+/// Formats a count for people in the configured [`NumberFormat`].
+/// Every difficulty, attempt count and nonce shown on the console goes
+/// through here; JSON output selects `plain`.
+pub fn format_count(num: u64) -> String {
+    let separator = char::from_u32(GROUP_SEPARATOR.load(Ordering::Relaxed)).unwrap_or(',');
+    format_number(num, NumberFormat::from_u8(NUMBER_FORMAT.load(Ordering::Relaxed)), separator)
+}
+
+/// Formats `num` in `format`.
+///
+/// # Example
+/// ```ignore
+/// assert_eq!(format_number(1234567, NumberFormat::Grouped, '.'), "1.234.567");
+/// assert_eq!(format_number(8_400_000, NumberFormat::Si, ','), "8.4M");
+/// ```
+pub fn format_number(num: u64, format: NumberFormat, separator: char) -> String {
+    match format {
+        NumberFormat::Plain   => num.to_string(),
+        NumberFormat::Grouped => group_digits(num, separator),
+        NumberFormat::Si      => {
+            const UNITS: [&str; 7] = ["", "k", "M", "G", "T", "P", "E"];
+            let (scaled, index) = scale_units(num, 1000.0, 1, &UNITS);
+            if index == 0 {
+                num.to_string()
+            } else {
+                format!("{scaled:.1}{}", UNITS[index])
+            }
+        }
+    }
+}
+
 /// Formats a number with comma separators for better readability.
 ///
 /// # Arguments
@@ -124,19 +528,23 @@ async fn show_progress_animation(running: Arc<AtomicBool>) {
 /// * `String`: The formatted number with comma separators
 ///
 /// # Example
-/// ```
+/// ```ignore
 /// assert_eq!(format_number_with_commas(1234567), "1,234,567");
 /// assert_eq!(format_number_with_commas(42), "42");
 /// assert_eq!(format_number_with_commas(1000), "1,000");
 /// ```
 pub fn format_number_with_commas(num: u64) -> String {
+    group_digits(num, ',')
+}
+
+fn group_digits(num: u64, separator: char) -> String {
     let num_str = num.to_string();
     let mut result = String::new();
     let chars: Vec<char> = num_str.chars().collect();
     
     for (i, ch) in chars.iter().enumerate() {
         if i > 0 && (chars.len() - i) % 3 == 0 {
-            result.push(',');
+            result.push(separator);
         }
         result.push(*ch);
     }
@@ -144,10 +552,201 @@ pub fn format_number_with_commas(num: u64) -> String {
     result
 }
 
+/// Formats a duration for humans, e.g. `532ms`, `4.2s`, or `3m 2.7s`.
+///
+/// Sub-minute values keep one decimal place; hour-scale values
+/// drop to whole seconds. The decimal separator is always '.'.
+///
+/// # Arguments
+/// * `duration`: The duration to format
+///
+/// # Returns
+/// * `String`: The formatted duration
+///
+/// # Example
+/// ```ignore
+/// assert_eq!(format_duration(Duration::from_millis(182_736)), "3m 2.7s");
+/// assert_eq!(format_duration(Duration::from_millis(532)), "532ms");
+/// ```
+pub fn format_duration(duration: Duration) -> String {
+    if duration < Duration::from_millis(1) {
+        return format!("{}µs", duration.as_micros());
+    }
+    if duration < Duration::from_secs(1) {
+        return format!("{}ms", duration.as_millis());
+    }
+
+    // Round to tenths first so that e.g. 59.96s becomes "1m 0.0s", not "60.0s".
+    let tenths = (duration.as_millis() + 50) / 100;
+    if tenths < 600 {
+        return format!("{}.{}s", tenths / 10, tenths % 10);
+    }
+    if tenths < 36_000 {
+        let seconds = tenths % 600;
+        return format!("{}m {}.{}s", tenths / 600, seconds / 10, seconds % 10);
+    }
+
+    let seconds = (duration.as_millis() + 500) / 1000;
+    format!("{}h {}m {}s", seconds / 3600, (seconds % 3600) / 60, seconds % 60)
+}
+
+/// Parses a duration flag or config value such as `500ms`, `30s`,
+/// `2m30s` or `1h`. A bare number is taken as seconds, so older
+/// integer settings keep working.
+///
+/// # Arguments
+/// * `value`: The text to parse
+///
+/// # Returns
+/// * `Result<Duration, String>`: The duration, or an error listing the accepted formats
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let invalid = || format!("invalid duration '{value}' (use e.g. 500ms, 30s, 2m30s or 1h; a bare number means seconds)");
+    if value.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut rest = value;
+    let mut total = Duration::ZERO;
+    while !rest.is_empty() {
+        let split = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+        let (number, tail) = rest.split_at(split);
+        let unit_len = tail.find(|c: char| c.is_ascii_digit() || c == '.').unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_len);
+
+        let scale = match unit.trim() {
+            "ms"                            => 0.001,
+            "s"                             => 1.0,
+            "" if rest.len() == value.len() => 1.0,
+            "m"                             => 60.0,
+            "h"                             => 3600.0,
+            "d"                             => 86_400.0,
+            _                               => return Err(invalid()),
+        };
+        let part = number
+            .parse::<f64>()
+            .ok()
+            .and_then(|number| Duration::try_from_secs_f64(number * scale).ok())
+            .ok_or_else(invalid)?;
+        total = total.checked_add(part).ok_or_else(invalid)?;
+        rest = tail;
+    }
+    Ok(total)
+}
+
+/// Scales `value` by `base` until it fits below the threshold at
+/// which rounding to `decimals` places would show as `base` itself.
+fn scale_units(value: u64, base: f64, decimals: i32, units: &[&str]) -> (f64, usize) {
+    let threshold = base - 0.5 * 10f64.powi(-decimals);
+    let mut scaled = value as f64;
+    let mut index = 0;
+
+    while scaled >= threshold && index + 1 < units.len() {
+        scaled /= base;
+        index += 1;
+    }
+
+    (scaled, index)
+}
+
+/// Formats a hash rate with SI prefixes, e.g. `1.24 Mh/s`.
+///
+/// # Arguments
+/// * `hashes_per_second`: The rate to format
+///
+/// # Returns
+/// * `String`: The formatted rate
+///
+/// # Example
+/// ```ignore
+/// assert_eq!(format_hash_rate(1_240_000), "1.24 Mh/s");
+/// assert_eq!(format_hash_rate(950), "950 h/s");
+/// ```
+pub fn format_hash_rate(hashes_per_second: u64) -> String {
+    const UNITS: [&str; 5] = ["h/s", "kh/s", "Mh/s", "Gh/s", "Th/s"];
+
+    let (scaled, index) = scale_units(hashes_per_second, 1000.0, 2, &UNITS);
+    if index == 0 {
+        format!("{hashes_per_second} {}", UNITS[0])
+    } else {
+        format!("{scaled:.2} {}", UNITS[index])
+    }
+}
+
+/// Formats a byte count with binary prefixes, e.g. `1.5 MiB`.
+///
+/// # Arguments
+/// * `bytes`: The byte count to format
+///
+/// # Returns
+/// * `String`: The formatted size
+///
+/// # Example
+/// ```ignore
+/// assert_eq!(format_bytes(1_572_864), "1.5 MiB");
+/// assert_eq!(format_bytes(512), "512 B");
+/// ```
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+
+    let (scaled, index) = scale_units(bytes, 1024.0, 1, &UNITS);
+    if index == 0 {
+        format!("{bytes} {}", UNITS[0])
+    } else {
+        format!("{scaled:.1} {}", UNITS[index])
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_unicode_glyphs() {
+        let glyphs = Glyphs::UNICODE;
+        assert_eq!(glyphs.section_lines("Challenge Fetching"), [
+            "🔸  Challenge Fetching".to_string(),
+            "────────────────────────────────────────".to_string(),
+        ]);
+        assert_eq!(glyphs.bar(3, 8), "███░░░░░");
+        assert_eq!(glyphs.spinner.concat(), "|/—\\");
+        assert_eq!(glyphs.missing, "—");
+    }
+
+    #[test]
+    fn test_ascii_glyphs() {
+        let glyphs = Glyphs::ASCII;
+        assert_eq!(glyphs.section_lines("Challenge Fetching"), [
+            "==> Challenge Fetching".to_string(),
+            "----------------------------------------".to_string(),
+        ]);
+        assert_eq!(glyphs.bar(3, 8), "###.....");
+        assert_eq!(glyphs.spinner.concat(), "|/-\\");
+        assert_eq!(glyphs.missing, "-");
+
+        let everything = [glyphs.section, glyphs.rule, glyphs.filled, glyphs.empty, glyphs.missing].concat() + &glyphs.spinner.concat();
+        assert!(everything.is_ascii());
+    }
+
+    #[test]
+    fn test_locale_detection() {
+        assert!(locale_supports_unicode(Some("en_US.UTF-8")));
+        assert!(locale_supports_unicode(Some("de_DE.utf8")));
+        assert!(locale_supports_unicode(None));
+        assert!(!locale_supports_unicode(Some("C")));
+        assert!(!locale_supports_unicode(Some("en_US.ISO-8859-1")));
+    }
+
+    #[test]
+    fn test_transfer_line() {
+        assert_eq!(
+            transfer_line(1_572_864, Some(3_145_728), Duration::from_secs(1)),
+            "Downloading 1.5 MiB / 3.0 MiB (50%), 1.5 MiB/s, ETA 1.0s",
+        );
+        assert_eq!(transfer_line(0, Some(10), Duration::ZERO), "Downloading 0 B / 10 B (0%), 0 B/s, ETA unknown");
+        assert_eq!(transfer_line(2048, None, Duration::from_secs(2)), "Downloading 2.0 KiB, 1.0 KiB/s");
+    }
+
     #[test]
     fn test_format_number_with_commas() {
         assert_eq!(format_number_with_commas(0), "0");
@@ -159,6 +758,78 @@ mod tests {
         assert_eq!(format_number_with_commas(1234567890), "1,234,567,890");
     }
 
+    #[test]
+    fn test_number_formats() {
+        let cases = [
+            (999,         "999",                  "999",                        "999"),
+            (1_000,       "1000",                 "1.000",                      "1.0k"),
+            (1_000_000,   "1000000",              "1.000.000",                  "1.0M"),
+            (8_400_000,   "8400000",              "8.400.000",                  "8.4M"),
+            (999_960,     "999960",               "999.960",                    "1.0M"),
+            (u64::MAX,    "18446744073709551615", "18.446.744.073.709.551.615", "18.4E"),
+        ];
+        for (num, plain, grouped, si) in cases {
+            assert_eq!(format_number(num, NumberFormat::Plain, '.'), plain);
+            assert_eq!(format_number(num, NumberFormat::Grouped, '.'), grouped);
+            assert_eq!(format_number(num, NumberFormat::Si, '.'), si);
+        }
+        assert_eq!(format_number(1_234_567, NumberFormat::Grouped, '\u{2009}'), "1\u{2009}234\u{2009}567");
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_micros(250)), "250µs");
+        assert_eq!(format_duration(Duration::from_millis(1)), "1ms");
+        assert_eq!(format_duration(Duration::from_millis(999)), "999ms");
+        assert_eq!(format_duration(Duration::from_secs(1)), "1.0s");
+        assert_eq!(format_duration(Duration::from_millis(4_249)), "4.2s");
+        assert_eq!(format_duration(Duration::from_millis(59_940)), "59.9s");
+        assert_eq!(format_duration(Duration::from_millis(59_960)), "1m 0.0s");
+        assert_eq!(format_duration(Duration::from_millis(182_736)), "3m 2.7s");
+        assert_eq!(format_duration(Duration::from_millis(3_599_940)), "59m 59.9s");
+        assert_eq!(format_duration(Duration::from_secs(3_600)), "1h 0m 0s");
+        assert_eq!(format_duration(Duration::from_secs(90_061)), "25h 1m 1s");
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("60s"), Ok(Duration::from_secs(60)));
+        assert_eq!(parse_duration("1.5m"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(7_200)));
+        assert_eq!(parse_duration("30d"), Ok(Duration::from_secs(2_592_000)));
+        assert_eq!(parse_duration("30"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("2m30s"), Ok(Duration::from_secs(150)));
+        assert_eq!(parse_duration("1h5m"), Ok(Duration::from_secs(3_900)));
+        assert_eq!(parse_duration("1s500ms"), Ok(Duration::from_millis(1_500)));
+        assert!(parse_duration("5 days").is_err());
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("2m30").is_err());
+        assert!(parse_duration("soon").unwrap_err().contains("2m30s"));
+    }
+
+    #[test]
+    fn test_format_hash_rate() {
+        assert_eq!(format_hash_rate(0), "0 h/s");
+        assert_eq!(format_hash_rate(999), "999 h/s");
+        assert_eq!(format_hash_rate(1_000), "1.00 kh/s");
+        assert_eq!(format_hash_rate(999_994), "999.99 kh/s");
+        assert_eq!(format_hash_rate(999_999), "1.00 Mh/s");
+        assert_eq!(format_hash_rate(1_240_000), "1.24 Mh/s");
+        assert_eq!(format_hash_rate(3_500_000_000), "3.50 Gh/s");
+        assert_eq!(format_hash_rate(u64::MAX), "18446744.07 Th/s");
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1_023), "1023 B");
+        assert_eq!(format_bytes(1_024), "1.0 KiB");
+        assert_eq!(format_bytes(1_572_864), "1.5 MiB");
+        assert_eq!(format_bytes(1_073_741_824), "1.0 GiB");
+    }
+
     #[test]
     fn test_progress_animation_verbose_mode() {
         let animation = ProgressAnimation::new(true);
@@ -166,6 +837,23 @@ mod tests {
         assert!(handle.is_none(), "Animation should not start in verbose mode");
     }
 
+    #[test]
+    fn test_progress_mode_detection() {
+        assert!(ProgressMode::Auto.is_interactive(true));
+        assert!(!ProgressMode::Auto.is_interactive(false));
+        assert!(ProgressMode::Always.is_interactive(false));
+        assert!(!ProgressMode::Never.is_interactive(true));
+    }
+
+    #[test]
+    fn test_plain_progress_line_has_no_carriage_returns() {
+        for secs in [0, 10, 20, 3_600] {
+            let line = plain_progress_line(Duration::from_secs(secs));
+            assert!(!line.contains('\r'), "carriage return in {line:?}");
+            assert!(!line.contains('\x1b'), "escape sequence in {line:?}");
+        }
+    }
+
     #[tokio::test]
     async fn test_progress_animation_non_verbose_mode() {
         let animation = ProgressAnimation::new(false);
@@ -0,0 +1,24 @@
+use color_eyre::eyre::eyre;
+
+/// Rejects endpoints that aren't absolute `http` or `https` URLs.
+/// Every place that takes an endpoint from the user checks it here.
+pub fn check(endpoint: &str) -> color_eyre::Result<()> {
+    let url = reqwest::Url::parse(endpoint).map_err(|e| eyre!("Invalid endpoint '{endpoint}': {e}"))?;
+    match url.scheme() {
+        "http" | "https" => Ok(()),
+        scheme           => Err(eyre!("Invalid endpoint '{endpoint}': expected http or https, not {scheme}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        assert!(check("https://a.example/protected").is_ok());
+        assert!(check("http://127.0.0.1:8080").is_ok());
+        assert!(check("ftp://a.example").is_err());
+        assert!(check("a.example").is_err());
+    }
+}
@@ -0,0 +1,285 @@
+use serde::{Deserialize, Serialize};
+
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Where Linux exposes RAPL energy counters.
+const RAPL_ROOT: &str = "/sys/class/powercap/intel-rapl";
+
+/// Microjoules in a watt-hour.
+const MICROJOULES_PER_WH: f64 = 3_600_000_000.0;
+
+/// The `[power]` section of the configuration file: what a solve's
+/// energy use is estimated from when it can't be measured.
+///
+/// ```toml
+/// [power]
+/// watts_per_core = 12.5
+/// grams_co2_per_kwh = 350
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PowerConfig {
+    /// Power drawn by one fully busy core, in watts.
+    pub watts_per_core:    f64,
+    /// Carbon intensity of the electricity, for a CO2 estimate; none when unset.
+    pub grams_co2_per_kwh: Option<f64>,
+}
+
+impl Default for PowerConfig {
+    fn default() -> Self {
+        Self { watts_per_core: 15.0, grams_co2_per_kwh: None }
+    }
+}
+
+/// The validated `[power]` settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PowerModel {
+    pub watts_per_core:    f64,
+    pub grams_co2_per_kwh: Option<f64>,
+}
+
+impl Default for PowerModel {
+    fn default() -> Self {
+        let config = PowerConfig::default();
+        Self { watts_per_core: config.watts_per_core, grams_co2_per_kwh: config.grams_co2_per_kwh }
+    }
+}
+
+impl PowerModel {
+    /// # Returns
+    /// * `Result<Self, String>`: The model, or which setting is invalid.
+    pub fn from_config(config: &PowerConfig) -> Result<Self, String> {
+        if !(config.watts_per_core.is_finite() && config.watts_per_core > 0.0) {
+            return Err(format!("`power.watts_per_core` must be a positive number of watts, got {}", config.watts_per_core));
+        }
+        if let Some(grams) = config.grams_co2_per_kwh.filter(|grams| !(grams.is_finite() && *grams >= 0.0)) {
+            return Err(format!("`power.grams_co2_per_kwh` must be zero or more, got {grams}"));
+        }
+        Ok(Self { watts_per_core: config.watts_per_core, grams_co2_per_kwh: config.grams_co2_per_kwh })
+    }
+}
+
+static MODEL: OnceLock<PowerModel> = OnceLock::new();
+
+/// Sets the `[power]` settings for the rest of the process.
+pub fn set_model(model: PowerModel) {
+    let _ = MODEL.set(model);
+}
+
+fn model() -> PowerModel {
+    MODEL.get().copied().unwrap_or_default()
+}
+
+/// How a solve's energy was worked out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnergyMethod {
+    /// Measured package energy from RAPL counters, which includes
+    /// everything else the CPU ran meanwhile.
+    Rapl,
+    /// CPU time multiplied by `power.watts_per_core`.
+    CpuEstimate,
+}
+
+impl EnergyMethod {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Self::Rapl        => "measured by RAPL",
+            Self::CpuEstimate => "estimated from CPU time",
+        }
+    }
+}
+
+/// The energy one solve used.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EnergyUse {
+    pub wh:        f64,
+    pub method:    EnergyMethod,
+    /// With `power.grams_co2_per_kwh` set.
+    pub grams_co2: Option<f64>,
+}
+
+impl EnergyUse {
+    fn new(wh: f64, method: EnergyMethod, model: &PowerModel) -> Self {
+        Self { wh, method, grams_co2: model.grams_co2_per_kwh.map(|grams| wh / 1000.0 * grams) }
+    }
+
+    /// e.g. "Energy: 0.0125 Wh (estimated from CPU time at 15 W/core), ~0.005 g CO2".
+    pub fn describe(&self, model: &PowerModel) -> String {
+        let method = match self.method {
+            EnergyMethod::Rapl        => self.method.describe().to_string(),
+            EnergyMethod::CpuEstimate => format!("{} at {} W/core", self.method.describe(), model.watts_per_core),
+        };
+        let co2 = self.grams_co2.map(|grams| format!(", ~{} g CO2", significant(grams))).unwrap_or_default();
+        format!("Energy: {} Wh ({method}){co2}", significant(self.wh))
+    }
+}
+
+/// `value` with three significant digits, without trailing zeros.
+fn significant(value: f64) -> String {
+    if value == 0.0 || !value.is_finite() {
+        return "0".to_string();
+    }
+    let decimals = (2 - value.abs().log10().floor() as i32).max(0) as usize;
+    let text = format!("{value:.decimals$}");
+    match text.contains('.') {
+        true  => text.trim_end_matches('0').trim_end_matches('.').to_string(),
+        false => text,
+    }
+}
+
+/// Watt-hours for `cpu_time` of busy cores at `watts_per_core`.
+pub fn estimate_wh(cpu_time: Duration, watts_per_core: f64) -> f64 {
+    cpu_time.as_secs_f64() * watts_per_core / 3600.0
+}
+
+/// One RAPL package zone's counter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Zone {
+    energy_uj:    u64,
+    /// Where the counter wraps back to zero.
+    max_range_uj: u64,
+}
+
+/// The package zones under `root`, e.g. `intel-rapl:0`, leaving out
+/// their subzones (`intel-rapl:0:0`), which the package already counts.
+///
+/// # Returns
+/// * `Option<Vec<Zone>>`: `None` if there are none or any can't be
+///   read, as on most systems without root.
+fn read_rapl(root: &Path) -> Option<Vec<Zone>> {
+    let mut names: Vec<String> = std::fs::read_dir(root).ok()?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name.strip_prefix("intel-rapl:").is_some_and(|index| !index.contains(':')))
+        .collect();
+    names.sort();
+    let read = |path: &Path| std::fs::read_to_string(path).ok()?.trim().parse::<u64>().ok();
+    let zones: Option<Vec<Zone>> = names.iter()
+        .map(|name| {
+            let zone = root.join(name);
+            Some(Zone { energy_uj: read(&zone.join("energy_uj"))?, max_range_uj: read(&zone.join("max_energy_range_uj"))? })
+        })
+        .collect();
+    zones.filter(|zones| !zones.is_empty())
+}
+
+/// Microjoules used between two readings of the same zones, allowing
+/// for each counter wrapping once.
+fn rapl_used_uj(start: &[Zone], end: &[Zone]) -> Option<u64> {
+    if start.len() != end.len() {
+        return None;
+    }
+    Some(start.iter().zip(end).map(|(start, end)| match end.energy_uj >= start.energy_uj {
+        true  => end.energy_uj - start.energy_uj,
+        false => end.max_range_uj.saturating_sub(start.energy_uj) + end.energy_uj,
+    }).sum())
+}
+
+/// Measures or estimates the energy of one solve, between [`Meter::start`]
+/// and [`Meter::finish`].
+#[derive(Debug, Clone)]
+pub struct Meter {
+    rapl: Option<Vec<Zone>>,
+}
+
+impl Meter {
+    pub fn start() -> Self {
+        let rapl = if cfg!(target_os = "linux") { read_rapl(Path::new(RAPL_ROOT)) } else { None };
+        Self { rapl }
+    }
+
+    /// # Arguments
+    /// * `cpu_time`: CPU time the solve used, for the estimate.
+    ///
+    /// # Returns
+    /// * `Option<EnergyUse>`: RAPL's measurement when both readings
+    ///   worked, else the estimate, else `None` when neither is possible.
+    pub fn finish(&self, cpu_time: Option<Duration>) -> Option<EnergyUse> {
+        let model = model();
+        let measured = self.rapl.as_deref()
+            .and_then(|start| rapl_used_uj(start, &read_rapl(Path::new(RAPL_ROOT))?));
+        match (measured, cpu_time) {
+            (Some(used_uj), _)    => Some(EnergyUse::new(used_uj as f64 / MICROJOULES_PER_WH, EnergyMethod::Rapl, &model)),
+            (None, Some(cpu))     => Some(EnergyUse::new(estimate_wh(cpu, model.watts_per_core), EnergyMethod::CpuEstimate, &model)),
+            (None, None)          => None,
+        }
+    }
+}
+
+/// The summary line for `energy`, with the `[power]` settings in effect.
+pub fn describe(energy: &EnergyUse) -> String {
+    energy.describe(&model())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_from_cpu_time() {
+        // An hour of one busy core at 15 W: 15 Wh.
+        assert_eq!(estimate_wh(Duration::from_secs(3_600), 15.0), 15.0);
+        assert_eq!(estimate_wh(Duration::ZERO, 15.0), 0.0);
+    }
+
+    #[test]
+    fn test_co2_and_description() {
+        let model = PowerModel { watts_per_core: 15.0, grams_co2_per_kwh: Some(400.0) };
+        let energy = EnergyUse::new(estimate_wh(Duration::from_secs(3), 15.0), EnergyMethod::CpuEstimate, &model);
+        assert_eq!(energy.describe(&model), "Energy: 0.0125 Wh (estimated from CPU time at 15 W/core), ~0.005 g CO2");
+
+        let energy = EnergyUse::new(2.5, EnergyMethod::Rapl, &PowerModel::default());
+        assert_eq!(energy.grams_co2, None);
+        assert_eq!(energy.describe(&PowerModel::default()), "Energy: 2.5 Wh (measured by RAPL)");
+    }
+
+    #[test]
+    fn test_invalid_config_is_rejected() {
+        assert_eq!(PowerModel::from_config(&PowerConfig::default()), Ok(PowerModel::default()));
+        for watts in [0.0, -3.0, f64::NAN] {
+            let config = PowerConfig { watts_per_core: watts, ..PowerConfig::default() };
+            assert!(PowerModel::from_config(&config).unwrap_err().contains("power.watts_per_core"));
+        }
+        let config = PowerConfig { grams_co2_per_kwh: Some(-1.0), ..PowerConfig::default() };
+        assert!(PowerModel::from_config(&config).unwrap_err().contains("power.grams_co2_per_kwh"));
+    }
+
+    fn write_zone(root: &Path, name: &str, energy_uj: u64, max_range_uj: u64) {
+        let zone = root.join(name);
+        std::fs::create_dir_all(&zone).unwrap();
+        std::fs::write(zone.join("energy_uj"), format!("{energy_uj}\n")).unwrap();
+        std::fs::write(zone.join("max_energy_range_uj"), format!("{max_range_uj}\n")).unwrap();
+    }
+
+    #[test]
+    fn test_rapl_reads_packages_and_handles_wraparound() {
+        let dir = tempfile::tempdir().unwrap();
+        write_zone(dir.path(), "intel-rapl:0", 1_000, 10_000);
+        write_zone(dir.path(), "intel-rapl:0:0", 500, 10_000);
+        write_zone(dir.path(), "intel-rapl:1", 9_000, 10_000);
+        let start = read_rapl(dir.path()).unwrap();
+        assert_eq!(start.len(), 2);
+
+        write_zone(dir.path(), "intel-rapl:0", 4_000, 10_000);
+        write_zone(dir.path(), "intel-rapl:1", 1_000, 10_000);
+        let end = read_rapl(dir.path()).unwrap();
+        // 3,000 on the first package; the second wrapped: 1,000 to the top and 1,000 after.
+        assert_eq!(rapl_used_uj(&start, &end), Some(5_000));
+    }
+
+    #[test]
+    fn test_unreadable_rapl_falls_back() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(read_rapl(&dir.path().join("missing")), None);
+        assert_eq!(read_rapl(dir.path()), None);
+        // Root-only counters: the directory is there but energy_uj isn't readable.
+        std::fs::create_dir_all(dir.path().join("intel-rapl:0")).unwrap();
+        assert_eq!(read_rapl(dir.path()), None);
+
+        let meter = Meter { rapl: None };
+        assert_eq!(meter.finish(Some(Duration::from_secs(3_600))).map(|energy| energy.method), Some(EnergyMethod::CpuEstimate));
+        assert_eq!(meter.finish(None), None);
+    }
+}
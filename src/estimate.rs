@@ -0,0 +1,283 @@
+use sha2::{Digest, Sha256};
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::display::{format_count, format_duration};
+use crate::solve::{ThreadPlan, WorkSplit};
+
+/// How long the single-core hash rate probe runs.
+pub const PROBE_DURATION: Duration = Duration::from_millis(200);
+
+/// Each attempt succeeds with probability `1 / difficulty`, so the
+/// number of attempts until success is geometrically distributed.
+/// The estimate reports the range between the median and the 90th
+/// percentile of that distribution, `ln(2)·d` and `ln(10)·d`.
+const LOW_QUANTILE_FACTOR:  f64 = std::f64::consts::LN_2;
+const HIGH_QUANTILE_FACTOR: f64 = std::f64::consts::LN_10;
+
+/// Single-core hash rate measured by [`cached_probe_hash_rate`].
+static PROBED_RATE: OnceLock<u64> = OnceLock::new();
+
+/// An estimate of how long a challenge will take to solve.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolveEstimate {
+    /// The challenge difficulty (`recommended_attempts / 2`).
+    pub difficulty:        u64,
+    /// Mean number of attempts needed to find a solution.
+    pub expected_attempts: u64,
+    /// Combined hash rate across all threads.
+    pub hash_rate:         u64,
+    /// Number of threads the rate assumes.
+    pub thread_count:      usize,
+    /// Median solve time.
+    pub low:               Duration,
+    /// 90th percentile solve time.
+    pub high:              Duration,
+}
+
+impl SolveEstimate {
+    /// Estimates the solve time for a difficulty.
+    ///
+    /// # Arguments
+    /// * `difficulty`:      The challenge difficulty.
+    /// * `per_thread_rate`: Hashes per second on one core.
+    /// * `thread_count`:    Number of solver threads.
+    ///
+    /// # Returns
+    /// * `SolveEstimate`: The estimate; times are zero if the
+    ///                    rate is unknown (zero).
+    pub fn new(difficulty: u64, per_thread_rate: u64, thread_count: usize) -> Self {
+        let hash_rate = per_thread_rate.saturating_mul(thread_count.max(1) as u64);
+        let seconds_for = |attempts: f64| {
+            if hash_rate == 0 {
+                Duration::ZERO
+            } else {
+                Duration::from_secs_f64(attempts / hash_rate as f64)
+            }
+        };
+
+        Self {
+            difficulty,
+            expected_attempts: difficulty,
+            hash_rate,
+            thread_count: thread_count.max(1),
+            low:  seconds_for(difficulty as f64 * LOW_QUANTILE_FACTOR),
+            high: seconds_for(difficulty as f64 * HIGH_QUANTILE_FACTOR),
+        }
+    }
+
+    /// Describes the estimate for humans, e.g.
+    /// "roughly 6.9s–23.0s on this machine with 12 threads".
+    pub fn describe(&self) -> String {
+        let threads = if self.thread_count == 1 {
+            "1 thread".to_string()
+        } else {
+            format!("{} threads", self.thread_count)
+        };
+
+        format!(
+            "roughly {}–{} on this machine with {threads}",
+            format_duration(self.low),
+            format_duration(self.high),
+        )
+    }
+
+    /// Describes the difficulty together with the estimate, e.g.
+    /// "Difficulty 4,194,304 — roughly 6.1s–20.3s on this machine with 12 threads".
+    pub fn interpretation(&self) -> String {
+        format!(
+            "Difficulty {} — {}",
+            format_count(self.difficulty),
+            self.describe(),
+        )
+    }
+}
+
+/// Estimates the total attempts made across all threads when a
+/// solution is found at `nonce`, for the way `plan` divided the nonce
+/// space: its threads, work split and batch size.
+///
+/// # Arguments
+/// * `nonce`: The solution nonce.
+/// * `plan`:  The plan the solve ran with.
+///
+/// # Returns
+/// * `u64`: The estimated total attempts.
+pub fn attempts_from_nonce(nonce: u64, plan: &ThreadPlan) -> u64 {
+    attempts_for_layout(nonce, plan.thread_count, plan.work_split, plan.solver.batch_size)
+}
+
+/// Estimates the total attempts made across all threads when a
+/// solution is found at `nonce`.
+///
+/// With [`WorkSplit::Stride`] each thread owns every n-th nonce, so
+/// each has made about `nonce / thread_count + 1` attempts by the time
+/// one of them reaches `nonce`. With [`WorkSplit::Chunked`] threads
+/// take `chunk`-sized ranges from a shared cursor, a round of one range
+/// per thread at a time: every earlier round is done, and each thread
+/// is about as far into its range of the solution's round.
+///
+/// # Arguments
+/// * `nonce`:        The solution nonce.
+/// * `thread_count`: Number of solver threads.
+/// * `split`:        How the nonce space was divided.
+/// * `chunk`:        Attempts per range.
+///
+/// # Returns
+/// * `u64`: The estimated total attempts.
+pub fn attempts_for_layout(nonce: u64, thread_count: usize, split: WorkSplit, chunk: u64) -> u64 {
+    let threads = thread_count.max(1) as u64;
+    match split {
+        WorkSplit::Stride  => (nonce / threads + 1) * threads,
+        WorkSplit::Chunked => {
+            let chunk = chunk.max(1);
+            let round = nonce / chunk / threads;
+            round * threads * chunk + (nonce % chunk + 1) * threads
+        }
+    }
+}
+
+/// Measures the single-core SHA-256 hash rate by hashing
+/// nonce-sized inputs for `duration`.
+///
+/// This is blocking; call it from `spawn_blocking`.
+///
+/// # Arguments
+/// * `duration`: How long to measure.
+///
+/// # Returns
+/// * `u64`: Hashes per second.
+pub fn probe_hash_rate(duration: Duration) -> u64 {
+    let mut input = [0u8; 40];
+    let mut hashes: u64 = 0;
+    let start = Instant::now();
+
+    loop {
+        // Check the clock in batches so timing doesn't dominate the probe.
+        for _ in 0..1024 {
+            input[32..].copy_from_slice(&hashes.to_le_bytes());
+            std::hint::black_box(Sha256::digest(input));
+            hashes += 1;
+        }
+
+        let elapsed = start.elapsed();
+        if elapsed >= duration {
+            return (hashes as f64 / elapsed.as_secs_f64()) as u64;
+        }
+    }
+}
+
+/// Returns the single-core hash rate, probing once per
+/// process for [`PROBE_DURATION`] and reusing the result.
+pub async fn cached_probe_hash_rate() -> u64 {
+    if let Some(rate) = PROBED_RATE.get() {
+        return *rate;
+    }
+
+    let rate = tokio::task::spawn_blocking(|| probe_hash_rate(PROBE_DURATION))
+        .await
+        .unwrap_or(0);
+
+    *PROBED_RATE.get_or_init(|| rate)
+}
+
+/// Estimates the solve time for a challenge difficulty at the cached
+/// probe hash rate.
+///
+/// # Arguments
+/// * `difficulty`:   The challenge difficulty.
+/// * `thread_count`: The threads the solve will use.
+pub async fn estimate_solve(difficulty: u64, thread_count: usize) -> SolveEstimate {
+    SolveEstimate::new(difficulty, cached_probe_hash_rate().await, thread_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Duration, expected_secs: f64) {
+        let diff = (actual.as_secs_f64() - expected_secs).abs();
+        assert!(diff < 0.01, "expected ~{expected_secs}s, got {actual:?}");
+    }
+
+    #[test]
+    fn test_estimate_single_thread() {
+        let estimate = SolveEstimate::new(1_000_000, 100_000, 1);
+
+        assert_eq!(estimate.expected_attempts, 1_000_000);
+        assert_eq!(estimate.hash_rate, 100_000);
+        assert_close(estimate.low, 6.931);
+        assert_close(estimate.high, 23.026);
+    }
+
+    #[test]
+    fn test_estimate_scales_with_threads() {
+        let estimate = SolveEstimate::new(1_000_000, 100_000, 4);
+
+        assert_eq!(estimate.hash_rate, 400_000);
+        assert_close(estimate.low, 1.733);
+        assert_close(estimate.high, 5.756);
+    }
+
+    #[test]
+    fn test_estimate_unknown_rate_is_zero() {
+        let estimate = SolveEstimate::new(1_000_000, 0, 8);
+
+        assert_eq!(estimate.low, Duration::ZERO);
+        assert_eq!(estimate.high, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_estimate_zero_threads_counts_as_one() {
+        let estimate = SolveEstimate::new(1_000, 1_000, 0);
+        assert_eq!(estimate.thread_count, 1);
+        assert_eq!(estimate.hash_rate, 1_000);
+    }
+
+    #[test]
+    fn test_estimate_interpretation() {
+        let estimate = SolveEstimate::new(4_194_304, 100_000, 12);
+        assert_eq!(
+            estimate.interpretation(),
+            "Difficulty 4,194,304 — roughly 2.4s–8.0s on this machine with 12 threads"
+        );
+    }
+
+    #[test]
+    fn test_attempts_for_a_strided_search() {
+        let attempts = |nonce, threads| attempts_for_layout(nonce, threads, WorkSplit::Stride, 500_000);
+        assert_eq!(attempts(0, 1), 1);
+        assert_eq!(attempts(999, 1), 1_000);
+        assert_eq!(attempts(1_000, 8), 1_008);
+        assert_eq!(attempts(5, 0), 6);
+    }
+
+    #[test]
+    fn test_attempts_for_a_chunked_search() {
+        let attempts = |nonce, threads| attempts_for_layout(nonce, threads, WorkSplit::Chunked, 1_000);
+        assert_eq!(attempts(999, 1), 1_000);
+        // The first round: eight threads, each 11 attempts into its range.
+        assert_eq!(attempts(3_010, 8), 88);
+        // The second round: 8,000 attempts done, then 6 into each range.
+        assert_eq!(attempts(12_005, 8), 8_048);
+        assert_eq!(attempts(5, 0), 6);
+    }
+
+    #[test]
+    fn test_attempts_follow_the_plan() {
+        use crate::solve::{Strategy, ThreadingMode};
+        use crate::tuning::SolverOptions;
+
+        let plan = ThreadPlan::derive(Strategy::Fast, 8, ThreadingMode::Fixed(8));
+        let chunked = plan.tuned(WorkSplit::Chunked, SolverOptions { batch_size: 1_000, ..SolverOptions::default() });
+        assert_eq!(attempts_from_nonce(12_005, &chunked), 8_048);
+        let strided = plan.tuned(WorkSplit::Stride, SolverOptions { batch_size: 1_000, ..SolverOptions::default() });
+        assert_eq!(attempts_from_nonce(1_000, &strided), 1_008);
+    }
+
+    #[test]
+    fn test_probe_hash_rate_is_positive() {
+        assert!(probe_hash_rate(Duration::from_millis(20)) > 0);
+    }
+}
@@ -0,0 +1,164 @@
+use ironshield::ProgressTracker;
+use serde::Serialize;
+
+use std::fs::File;
+use std::io::Write;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::warnings::{Warning, WarningCode};
+
+/// One line of NDJSON written to `--progress-fd` or `--progress-pipe`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// A solve is about to start.
+    SolveStarted {
+        difficulty: u64,
+        threads:    usize,
+    },
+    /// A solver thread's cumulative progress.
+    Progress {
+        thread_id:  usize,
+        attempts:   u64,
+        hash_rate:  u64,
+        elapsed_ms: u64,
+    },
+    /// The solve ended, with `error` set if it failed.
+    SolveFinished {
+        solved:     bool,
+        elapsed_ms: u64,
+        error:      Option<String>,
+    },
+}
+
+/// Where events go. Closed for good after the first failed write, so
+/// a wrapper that goes away costs one warning rather than the solve.
+struct Channel {
+    name: String,
+    out:  Mutex<Option<File>>,
+}
+
+static CHANNEL: OnceLock<Channel> = OnceLock::new();
+
+/// Sends events to an inherited file descriptor for the rest of the
+/// process, for `--progress-fd`.
+///
+/// # Arguments
+/// * `fd`: Open for writing by whoever started the CLI; 3 or above,
+///         since 0 to 2 are stdin, stdout and stderr.
+#[cfg(unix)]
+pub fn open_fd(fd: i32) -> Result<(), String> {
+    use std::os::fd::FromRawFd;
+
+    if fd <= 2 {
+        return Err(format!("fd {fd} is stdin, stdout or stderr; pass a descriptor of 3 or above"));
+    }
+    // SAFETY: fcntl with F_GETFD only inspects the descriptor table.
+    if unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+        return Err(format!("fd {fd} is not open"));
+    }
+    // SAFETY: the descriptor is open, and nothing else in this process
+    // knows about it, so the file is its only owner.
+    let file = unsafe { File::from_raw_fd(fd) };
+    install(format!("fd {fd}"), file);
+    Ok(())
+}
+
+/// Sends events to a named pipe for the rest of the process, for
+/// `--progress-pipe`.
+///
+/// # Arguments
+/// * `name`: The pipe's name, with or without the `\\.\pipe\` prefix.
+#[cfg(windows)]
+pub fn open_pipe(name: &str) -> Result<(), String> {
+    let path = match name.starts_with(r"\\.\pipe\") {
+        true  => name.to_string(),
+        false => format!(r"\\.\pipe\{name}"),
+    };
+    let file = std::fs::OpenOptions::new().write(true).open(&path)
+        .map_err(|e| format!("cannot open '{path}': {e}"))?;
+    install(path, file);
+    Ok(())
+}
+
+fn install(name: String, file: File) {
+    let _ = CHANNEL.set(Channel { name, out: Mutex::new(Some(file)) });
+}
+
+/// Whether `--progress-fd` or `--progress-pipe` was given.
+pub fn is_enabled() -> bool {
+    CHANNEL.get().is_some()
+}
+
+/// Writes `event` as one line, if a channel is open.
+pub fn emit(event: &Event) {
+    let Some(channel) = CHANNEL.get() else {
+        return;
+    };
+    let Ok(mut line) = serde_json::to_vec(event) else {
+        return;
+    };
+    line.push(b'\n');
+
+    let mut out = channel.out.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let Some(file) = out.as_mut() else {
+        return;
+    };
+    // One write per line, so a reader never sees half an event.
+    if let Err(e) = file.write_all(&line) {
+        *out = None;
+        crate::warnings::emit(Warning::new(
+            WarningCode::EventsStopped,
+            format!("Stopped writing progress events to {}: {e}; the solve continues", channel.name),
+        ));
+    }
+}
+
+/// Wraps `inner` so each progress report is also sent as an event.
+pub fn tracker(inner: Arc<dyn ProgressTracker>) -> Arc<dyn ProgressTracker> {
+    Arc::new(EventTracker { inner })
+}
+
+struct EventTracker {
+    inner: Arc<dyn ProgressTracker>,
+}
+
+impl ProgressTracker for EventTracker {
+    fn on_progress(&self, thread_id: usize, total_attempts: u64, hash_rate: u64, elapsed: Duration) {
+        emit(&Event::Progress {
+            thread_id,
+            attempts:   total_attempts,
+            hash_rate,
+            elapsed_ms: elapsed.as_millis() as u64,
+        });
+        self.inner.on_progress(thread_id, total_attempts, hash_rate, elapsed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_serialize_as_tagged_lines() {
+        let event = Event::Progress { thread_id: 1, attempts: 500, hash_rate: 1_000, elapsed_ms: 20 };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"progress","thread_id":1,"attempts":500,"hash_rate":1000,"elapsed_ms":20}"#,
+        );
+
+        let event = Event::SolveFinished { solved: false, elapsed_ms: 5, error: Some("timed out".to_string()) };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"solve_finished","solved":false,"elapsed_ms":5,"error":"timed out"}"#,
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_standard_streams_are_refused() {
+        assert!(open_fd(1).unwrap_err().contains("3 or above"));
+        assert!(open_fd(2).is_err());
+    }
+}
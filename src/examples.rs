@@ -0,0 +1,134 @@
+//! Usage examples shown under each command's `--help` and by
+//! `ironshield examples`. They live in one table so a test can parse
+//! every one against the real argument definitions.
+
+/// One runnable example.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Example {
+    /// The subcommand whose help shows it.
+    pub command:     &'static str,
+    /// What it does, printed as a comment above it.
+    pub description: &'static str,
+    /// Everything after `ironshield`, separated by single spaces and
+    /// without shell quoting, so it splits back into the exact arguments.
+    pub args:        &'static str,
+}
+
+const fn example(command: &'static str, description: &'static str, args: &'static str) -> Example {
+    Example { command, description, args }
+}
+
+pub const EXAMPLES: &[Example] = &[
+    example("fetch", "Print the challenge an endpoint hands out", "fetch https://example.com/protected"),
+    example("fetch", "Use a config file and print one JSON record per line", "fetch https://example.com/protected -c ironshield.toml --output json"),
+    example("solve", "Solve a challenge and print the solution", "solve https://example.com/protected"),
+    example("solve", "Solve on four threads, printing JSON records", "solve https://example.com/protected --threads 4 --output json"),
+    example("solve", "Record a single-threaded solve for `ironshield repro`", "solve https://example.com/protected -s --dump-repro ./repro"),
+    example("solve", "Try a larger batch per call into the solver core", "solve https://example.com/protected --solver-opt batch_size=2000000"),
+    example("run", "Fetch and solve without submitting, with a timing breakdown", "run https://example.com/protected"),
+    example("run", "Give up after 30 seconds, printing JSON records", "run https://example.com/protected --max-time 30s --output json"),
+    example("run", "Fail a CI run if the clock is skewed or the CPU throttles", "--deny-warnings=W002,W003 run https://example.com/protected"),
+    example("validate", "Fetch, solve and submit, then print the token", "validate https://example.com/protected"),
+    example("validate", "Re-run the most recent endpoint from history with a config file", "validate @last -c ironshield.toml"),
+    example("validate", "Validate endpoints read from stdin, four at a time", "validate --stdin --concurrency 4 --failures-out failures.json"),
+    example("validate", "Open the submit connection while the solver runs, and refetch rather than submit a challenge about to expire", "validate https://example.com/protected --prewarm --auto-refresh-on-thin-margin -v"),
+    example("get", "Download a protected page", "get https://example.com/protected --save-body page.html"),
+    example("get", "Resume a download and check it against a sha256sum file", "get https://example.com/big.iso --save-body big.iso --continue --checksum-file SHA256SUMS"),
+    example("get", "Record every request and response for the server team", "get https://example.com/protected --har debug.har"),
+    example("repro", "Replay a solve recorded with `solve --dump-repro`", "repro ./repro"),
+    example("repro", "Replay it with verbose output", "-v repro ./repro"),
+    example("cache", "Forget every response cached by `get`", "cache purge"),
+    example("cache", "Forget them, listing what is removed", "-v cache purge"),
+    example("survey", "Sample challenge difficulty ten times per endpoint", "survey --endpoints-file endpoints.txt --samples 10 --csv survey.csv"),
+    example("survey", "Sample every five minutes with a config file", "survey --endpoints-file endpoints.txt --interval 5m -c ironshield.toml"),
+    example("stream", "Answer JSON commands on stdin with a config file", "stream -c ironshield.toml"),
+    example("stream", "Solve every command on two threads", "stream --threads 2"),
+    example("spool", "Solve challenges dropped into a directory as they arrive", "spool --dir /var/spool/ironshield"),
+    example("spool", "Poll a network share every 5 seconds, two challenges at a time", "spool --dir /mnt/edge --poll 5s --jobs 2"),
+    example("serve", "Solve challenges POSTed to http://127.0.0.1:9099/solve", "serve"),
+    example("serve", "Serve other hosts, with `token` set under [serve]", "serve --listen 0.0.0.0:9099 -c ironshield.toml"),
+    example("proxy", "Serve fresh tokens to other containers", "proxy https://example.com/protected --listen 0.0.0.0:8787"),
+    example("proxy", "Report not ready after two minutes without a token", "proxy https://example.com/protected --ready-within 2m -c ironshield.toml"),
+    example("proxy", "Ride out difficulty spikes on the current token, refreshing 30s before expiry", "proxy https://example.com/protected --max-renewal-difficulty 5000000 --renewal-margin 30s"),
+    example("batch", "Validate two endpoints", "batch https://a.example.com/protected https://b.example.com/protected"),
+    example("batch", "Validate a file of endpoints, soonest expiry first", "batch --endpoints-file endpoints.txt --schedule deadline --failures-out failures.json"),
+    example("batch", "Validate endpoints read from stdin, printing JSON records", "batch --stdin --output json"),
+    example("queue", "Queue a challenge saved earlier", "queue add challenge.json.gz"),
+    example("queue", "Solve everything queued, offline, on two threads", "queue solve --threads 2"),
+    example("queue", "Submit the solutions that are still valid, eight at a time per API", "-c ironshield.toml queue submit --concurrency 8"),
+    example("queue", "Show every item with its age and expiry", "queue list"),
+    example("state", "Show where ironshield keeps its data and cache", "state path"),
+    example("state", "Show how much disk each kind of state takes", "state size"),
+    example("state", "List what a clean-up of week-old state would remove", "state gc --older-than 7d --dry-run"),
+    example("history", "Show the last five runs", "history -n 5"),
+    example("history", "Summarise runs against one host as JSON", "history stats --endpoint example.com --json"),
+    example("history", "Compare this month's runs with earlier ones", "history compare --from 2026-10-01"),
+    example("benchmark", "Measure 1, 2 and 4 threads and save the results", "benchmark --threads 1,2,4 --save before.json"),
+    example("benchmark", "Fail if a later benchmark got slower", "benchmark compare before.json after.json"),
+    example("challenge", "Generate a test challenge without the API", "challenge generate --difficulty 10000 --out challenge.json.gz"),
+    example("challenge", "Decode a challenge header saved to a file", "challenge decode --file header.txt"),
+    example("solution", "Turn a JSON solution into the header the API expects", "solution encode --file solution.json"),
+    example("solution", "Print a solution header saved to a file as JSON", "solution decode --file solution.txt"),
+    example("config", "Write a config file with every default", "config init"),
+    example("config", "Show the settings a config file results in", "-c ironshield.toml config show"),
+    example("config", "Use four solver threads from now on", "config set num_threads 4"),
+    example("config", "Compare staging settings with production", "-c staging.toml config show --compare prod.toml"),
+    example("setup", "Create a config file step by step", "setup"),
+    example("setup", "Create a config file for a provisioning script", "setup --defaults --api-base-url https://api.example.com"),
+    example("doctor", "Check that the API answers", "doctor -c ironshield.toml"),
+    example("doctor", "Check the proxy's tunnels to the API and the canary for TLS interception", "doctor --proxy"),
+    example("examples", "Print the examples for one command", "examples validate"),
+    example("examples", "Print every example", "examples"),
+];
+
+/// The examples for `command`, in table order.
+pub fn for_command(command: &str) -> impl Iterator<Item = &'static Example> + '_ {
+    EXAMPLES.iter().filter(move |example| example.command == command)
+}
+
+/// The commands that have examples, in table order.
+pub fn commands() -> Vec<&'static str> {
+    let mut commands: Vec<&str> = EXAMPLES.iter().map(|example| example.command).collect();
+    commands.dedup();
+    commands
+}
+
+/// The `Examples:` section for `command`'s help, or `None` without any.
+pub fn render(command: &str) -> Option<String> {
+    let lines: Vec<String> = for_command(command)
+        .map(|example| format!("  # {}\n  ironshield {}", example.description, example.args))
+        .collect();
+    match lines.is_empty() {
+        true  => None,
+        false => Some(format!("Examples:\n{}", lines.join("\n\n"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_command_has_two_to_four_examples() {
+        for command in commands() {
+            let count = for_command(command).count();
+            assert!((2..=4).contains(&count), "{command} has {count} examples");
+        }
+    }
+
+    #[test]
+    fn test_examples_are_grouped_by_command() {
+        let mut commands = commands();
+        let count = commands.len();
+        commands.sort_unstable();
+        commands.dedup();
+        assert_eq!(commands.len(), count);
+    }
+
+    #[test]
+    fn test_examples_split_back_into_their_arguments() {
+        for example in EXAMPLES {
+            assert!(!example.args.contains("  ") && !example.args.contains(['"', '\'', '<', '>', '|']), "{}", example.args);
+        }
+    }
+}
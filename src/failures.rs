@@ -0,0 +1,140 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::batch::{BatchUpdate, Stage};
+
+/// Endpoints listed under each error kind before "and N more".
+pub const SUMMARY_LIMIT: usize = 5;
+
+/// One endpoint that failed during a batch run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Failure {
+    pub endpoint:   String,
+    /// The last stage the endpoint reached, e.g. `solving`.
+    pub stage:      String,
+    /// The step that failed (`fetch`, `refused`, `solve`, `submit`,
+    /// `other`), or `invalid` for a list line that isn't a URL.
+    pub error_kind: String,
+    pub message:    String,
+    pub timestamp:  DateTime<Utc>,
+}
+
+/// Failures collected while a batch runs, for the summary printed at
+/// the end and `--failures-out`.
+#[derive(Debug, Default)]
+pub struct FailureReport {
+    /// Where each unfinished job last was, by update id.
+    stages:   HashMap<usize, &'static str>,
+    failures: Vec<Failure>,
+}
+
+impl FailureReport {
+    /// Follows `update` for `endpoint`, recording it if it failed.
+    pub fn observe(&mut self, update: &BatchUpdate, endpoint: &str) {
+        match &update.stage {
+            Stage::Failed { message, kind } => {
+                let stage = self.stages.remove(&update.id).unwrap_or(Stage::Pending.name());
+                self.add(endpoint, stage, kind.name(), message);
+            }
+            Stage::Done { .. } => {
+                self.stages.remove(&update.id);
+            }
+            stage => {
+                self.stages.insert(update.id, stage.name());
+            }
+        }
+    }
+
+    pub fn add(&mut self, endpoint: &str, stage: &str, error_kind: &str, message: &str) {
+        self.failures.push(Failure {
+            endpoint:   endpoint.to_string(),
+            stage:      stage.to_string(),
+            error_kind: error_kind.to_string(),
+            message:    message.to_string(),
+            timestamp:  Utc::now(),
+        });
+    }
+
+    pub fn failures(&self) -> &[Failure] {
+        &self.failures
+    }
+
+    /// Counts per error kind, most common first, each followed by up
+    /// to `limit` of its endpoints and their errors.
+    ///
+    /// # Arguments
+    /// * `total`: Endpoints in the run, for the heading.
+    /// * `limit`: Endpoints listed per kind.
+    pub fn summary(&self, total: usize, limit: usize) -> String {
+        let mut kinds: BTreeMap<&str, Vec<&Failure>> = BTreeMap::new();
+        for failure in &self.failures {
+            kinds.entry(failure.error_kind.as_str()).or_default().push(failure);
+        }
+        let mut kinds: Vec<(&str, Vec<&Failure>)> = kinds.into_iter().collect();
+        kinds.sort_by_key(|(_, failures)| std::cmp::Reverse(failures.len()));
+
+        let mut summary = format!("{} of {total} endpoints failed:", self.failures.len());
+        for (kind, failures) in kinds {
+            summary.push_str(&format!("\n  {kind}: {}", failures.len()));
+            for failure in failures.iter().take(limit) {
+                summary.push_str(&format!("\n    {} ({}): {}", failure.endpoint, failure.stage, failure.message));
+            }
+            if failures.len() > limit {
+                summary.push_str(&format!("\n    ... and {} more", failures.len() - limit));
+            }
+        }
+        summary
+    }
+
+    /// Writes every failure as a JSON array.
+    pub fn write_json(&self, path: &Path) -> color_eyre::Result<()> {
+        let json = serde_json::to_string_pretty(&self.failures)?;
+        crate::compression::write(path, format!("{json}\n").as_bytes())
+            .map_err(|e| color_eyre::eyre::eyre!("Cannot write '{}': {e}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::ErrorKind;
+
+    fn update(id: usize, stage: Stage) -> BatchUpdate {
+        BatchUpdate { id, stage }
+    }
+
+    #[test]
+    fn test_failures_keep_their_last_stage() {
+        let mut report = FailureReport::default();
+        report.observe(&update(0, Stage::Fetching), "https://a.example/");
+        report.observe(&update(0, Stage::Solving { attempts: 0, recommended_attempts: 10 }), "https://a.example/");
+        report.observe(&update(0, Stage::Failed { message: "boom".into(), kind: ErrorKind::Solve }), "https://a.example/");
+        report.observe(&update(1, Stage::Fetching), "https://b.example/");
+        report.observe(&update(1, Stage::Done { token: String::new(), valid_for: 0 }), "https://b.example/");
+
+        assert_eq!(report.failures().len(), 1);
+        assert_eq!(report.failures()[0].stage, "solving");
+        assert_eq!(report.failures()[0].error_kind, "solve");
+    }
+
+    #[test]
+    fn test_summary_groups_and_truncates() {
+        let mut report = FailureReport::default();
+        for i in 0..4 {
+            report.add(&format!("https://f{i}.example/"), "fetching", "fetch", "connection refused");
+        }
+        report.add("not a url", "listed", "invalid", "relative URL without a base");
+
+        assert_eq!(report.summary(9, 2), "\
+5 of 9 endpoints failed:
+  fetch: 4
+    https://f0.example/ (fetching): connection refused
+    https://f1.example/ (fetching): connection refused
+    ... and 2 more
+  invalid: 1
+    not a url (listed): relative URL without a base");
+    }
+}
@@ -0,0 +1,107 @@
+use ironshield::ProgressTracker;
+
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::display::{format_count, format_duration};
+
+/// Per-thread hash rate of the slowest machines we hear from, for
+/// saying how long the first report can take.
+const SLOW_THREAD_HASH_RATE: u64 = 10_000;
+
+/// When each solver thread first reported progress. Workers only
+/// report after [`crate::solve::INITIAL_REPORT_INTERVAL`] attempts, which
+/// on a slow machine can look like a hang.
+pub struct FirstProgressTracker {
+    start: Instant,
+    first: Box<[OnceLock<Duration>]>,
+    inner: Arc<dyn ProgressTracker>,
+}
+
+impl FirstProgressTracker {
+    /// Starts timing a solve on `thread_count` threads, passing every
+    /// report on to `inner`.
+    pub fn new(thread_count: usize, inner: Arc<dyn ProgressTracker>) -> Self {
+        Self {
+            start: Instant::now(),
+            first: (0..thread_count.max(1)).map(|_| OnceLock::new()).collect(),
+            inner,
+        }
+    }
+
+    /// Time from the start of the solve to each thread's first report,
+    /// `None` for threads that haven't reported.
+    pub fn per_thread(&self) -> Vec<Option<Duration>> {
+        self.first.iter().map(|first| first.get().copied()).collect()
+    }
+
+    /// Time to the first report from any thread: how long the solve
+    /// showed nothing.
+    pub fn time_to_first_progress(&self) -> Option<Duration> {
+        self.first.iter().filter_map(|first| first.get().copied()).min()
+    }
+
+    /// Resolves once `threshold` has passed without any report, and
+    /// never if one arrives first.
+    pub async fn silent_for(&self, threshold: Duration) {
+        tokio::time::sleep_until((self.start + threshold).into()).await;
+        if self.time_to_first_progress().is_some() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+impl ProgressTracker for FirstProgressTracker {
+    fn on_progress(&self, thread_id: usize, total_attempts: u64, hash_rate: u64, elapsed: Duration) {
+        if let Some(first) = self.first.get(thread_id) {
+            first.get_or_init(|| self.start.elapsed());
+        }
+        self.inner.on_progress(thread_id, total_attempts, hash_rate, elapsed);
+    }
+}
+
+/// The line shown when no thread has reported within the threshold.
+pub fn reassurance() -> String {
+    let attempts = crate::solve::INITIAL_REPORT_INTERVAL;
+    let slowest = Duration::from_secs(attempts / SLOW_THREAD_HASH_RATE);
+    format!(
+        "Workers are running; the first progress report comes after {} attempts per thread and can take up to {} on slower machines",
+        format_count(attempts),
+        format_duration(slowest),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Ignore;
+
+    impl ProgressTracker for Ignore {
+        fn on_progress(&self, _: usize, _: u64, _: u64, _: Duration) {}
+    }
+
+    #[test]
+    fn test_first_report_per_thread_is_kept() {
+        let tracker = FirstProgressTracker::new(3, Arc::new(Ignore));
+        assert_eq!(tracker.time_to_first_progress(), None);
+
+        tracker.on_progress(1, 200_000, 1_000, Duration::from_millis(5));
+        let first = tracker.per_thread()[1].unwrap();
+        tracker.on_progress(1, 400_000, 1_000, Duration::from_millis(9));
+        tracker.on_progress(7, 200_000, 1_000, Duration::from_millis(9));
+
+        assert_eq!(tracker.per_thread(), [None, Some(first), None]);
+        assert_eq!(tracker.time_to_first_progress(), Some(first));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_silence_is_noticed_only_without_reports() {
+        let tracker = FirstProgressTracker::new(1, Arc::new(Ignore));
+        tracker.silent_for(Duration::from_secs(5)).await;
+
+        tracker.on_progress(0, 200_000, 1_000, Duration::from_secs(6));
+        let waited = tokio::time::timeout(Duration::from_secs(60), tracker.silent_for(Duration::from_secs(5))).await;
+        assert!(waited.is_err());
+    }
+}
@@ -0,0 +1,792 @@
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use color_eyre::eyre::eyre;
+use serde::{Deserialize, Serialize};
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::energy::EnergyMethod;
+use crate::warnings::{Warning, WarningCode};
+use crate::solve::Strategy;
+use crate::util::{FileLock, FileMode, LOCK_TIMEOUT};
+
+/// File name of the run history inside the data directory.
+pub(crate) const HISTORY_FILE: &str = "history.jsonl";
+
+/// How many recent endpoints `@1`..`@9` can recall.
+pub const RECALL_SLOTS: usize = 9;
+
+/// Whether [`record`] writes anything, from `[history] enabled`.
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// The command a recorded run executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunCommand {
+    Fetch,
+    Solve,
+    Validate,
+}
+
+impl RunCommand {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Fetch    => "fetch",
+            Self::Solve    => "solve",
+            Self::Validate => "validate",
+        }
+    }
+}
+
+/// How a recorded run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunOutcome {
+    Success,
+    Failure,
+}
+
+/// The step a failed run stopped at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorKind {
+    /// Requesting the challenge.
+    Fetch,
+    /// Declining a challenge over the difficulty limits.
+    Refused,
+    /// Solving the proof of work.
+    Solve,
+    /// Submitting the solution for a token.
+    Submit,
+    /// Anything else, such as formatting the output.
+    Other,
+}
+
+impl ErrorKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Fetch   => "fetch",
+            Self::Refused => "refused",
+            Self::Solve   => "solve",
+            Self::Submit  => "submit",
+            Self::Other   => "other",
+        }
+    }
+
+    /// Whether running again may well succeed: a network blip, an
+    /// expired challenge, a solve that ran out of time or a rejected
+    /// solution. A refused challenge or anything else fails the same way twice.
+    pub fn is_recoverable(self) -> bool {
+        matches!(self, Self::Fetch | Self::Solve | Self::Submit)
+    }
+}
+
+/// One fetch, solve or validate run, stored as a line of JSON.
+///
+/// Every field added after the first release is optional so
+/// that older history files keep parsing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub timestamp:                 DateTime<Utc>,
+    pub command:                   RunCommand,
+    pub endpoint:                  String,
+    pub outcome:                   RunOutcome,
+    pub elapsed_ms:                u64,
+    #[serde(default)]
+    pub difficulty:                Option<u64>,
+    #[serde(default)]
+    pub thread_count:              Option<usize>,
+    /// Estimated attempts across all threads.
+    #[serde(default)]
+    pub attempts:                  Option<u64>,
+    /// Time spent solving, excluding fetch and submit.
+    #[serde(default)]
+    pub solve_ms:                  Option<u64>,
+    #[serde(default)]
+    pub error:                     Option<String>,
+    /// The token's `valid_for` timestamp (Unix milliseconds).
+    #[serde(default)]
+    pub token_valid_for:           Option<i64>,
+    #[serde(default)]
+    pub error_kind:                Option<ErrorKind>,
+    /// Time the challenge request took.
+    #[serde(default)]
+    pub fetch_ms:                  Option<u64>,
+    /// Peak resident memory of the process, measured after the solve.
+    #[serde(default)]
+    pub peak_rss_bytes:            Option<u64>,
+    /// CPU time (user + sys) spent during the solve.
+    #[serde(default)]
+    pub cpu_ms:                    Option<u64>,
+    /// `cpu_ms` as a share of `solve_ms`; 400% is four busy cores.
+    #[serde(default)]
+    pub cpu_percent:               Option<f64>,
+    /// Whether the hash rate fell well below its first-minute average.
+    #[serde(default)]
+    pub throttle_detected:         Option<bool>,
+    /// The solve strategy that picked `thread_count`.
+    #[serde(default)]
+    pub strategy:                  Option<Strategy>,
+    /// Time from the start of the solve to the first progress report
+    /// from any thread; unset if it finished before one.
+    #[serde(default)]
+    pub time_to_first_progress_ms: Option<u64>,
+    /// Set on unoptimized builds, whose timings aren't comparable.
+    #[serde(default)]
+    pub debug_build:               Option<bool>,
+    /// Time spent reading the config file before the run started.
+    #[serde(default)]
+    pub config_load_ms:            Option<u64>,
+    /// Time the core took to check the solution before it was submitted.
+    #[serde(default)]
+    pub local_verify_ms:           Option<u64>,
+    /// Time the solution submission took.
+    #[serde(default)]
+    pub submit_ms:                 Option<u64>,
+    /// Energy the solve used, in watt-hours.
+    #[serde(default)]
+    pub energy_wh:                 Option<f64>,
+    /// Whether `energy_wh` was measured or estimated.
+    #[serde(default)]
+    pub energy_method:             Option<EnergyMethod>,
+    /// CO2 for `energy_wh`, when `power.grams_co2_per_kwh` is set.
+    #[serde(default)]
+    pub co2_grams:                 Option<f64>,
+    /// Expected validity left when the submit lands: the expiry minus
+    /// the end of the solve and the estimated submit latency.
+    #[serde(default)]
+    pub submit_margin_ms:          Option<i64>,
+    /// Validity actually left when the submit's answer came back;
+    /// negative when the race was lost.
+    #[serde(default)]
+    pub landed_margin_ms:          Option<i64>,
+}
+
+impl RunRecord {
+    /// Starts a record for a run beginning now.
+    pub fn new(command: RunCommand, endpoint: &str) -> Self {
+        Self {
+            timestamp:                 Utc::now(),
+            command,
+            endpoint:                  endpoint.to_string(),
+            outcome:                   RunOutcome::Success,
+            elapsed_ms:                0,
+            difficulty:                None,
+            thread_count:              None,
+            attempts:                  None,
+            solve_ms:                  None,
+            error:                     None,
+            token_valid_for:           None,
+            error_kind:                None,
+            fetch_ms:                  None,
+            peak_rss_bytes:            None,
+            cpu_ms:                    None,
+            cpu_percent:               None,
+            throttle_detected:         None,
+            strategy:                  None,
+            time_to_first_progress_ms: None,
+            debug_build:               crate::build_profile::is_debug().then_some(true),
+            config_load_ms:            None,
+            local_verify_ms:           None,
+            submit_ms:                 None,
+            energy_wh:                 None,
+            energy_method:             None,
+            co2_grams:                 None,
+            submit_margin_ms:          None,
+            landed_margin_ms:          None,
+        }
+    }
+
+    /// Fills in the total time and the outcome.
+    pub fn finish(&mut self, elapsed: Duration, error: Option<String>) {
+        self.elapsed_ms = elapsed.as_millis() as u64;
+        self.outcome = if error.is_some() { RunOutcome::Failure } else { RunOutcome::Success };
+        self.error = error;
+    }
+
+    /// Whether the endpoint contains `filter`, ignoring case.
+    pub fn matches_endpoint(&self, filter: &str) -> bool {
+        self.endpoint.to_lowercase().contains(&filter.to_lowercase())
+    }
+
+    /// Average hash rate over the solve, if it was measured.
+    pub fn hash_rate(&self) -> Option<u64> {
+        match (self.attempts, self.solve_ms) {
+            (Some(attempts), Some(solve_ms)) if solve_ms > 0 => Some(attempts * 1000 / solve_ms),
+            _ => None,
+        }
+    }
+}
+
+/// Append-only JSONL store of [`RunRecord`]s.
+#[derive(Debug, Clone)]
+pub struct HistoryStore {
+    path: PathBuf,
+}
+
+impl HistoryStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// The store in the platform data directory, e.g.
+    /// `~/.local/share/ironshield/history.jsonl` on Linux.
+    ///
+    /// # Returns
+    /// * `Option<Self>`: `None` if the platform has no data directory.
+    pub fn open_default() -> Option<Self> {
+        dirs::data_dir().map(|dir| Self::new(dir.join("ironshield").join(HISTORY_FILE)))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends a record.
+    ///
+    /// Each record is written with a single `write_all` on a file
+    /// opened for appending, under the store's [`FileLock`], so
+    /// neither another append nor a [`Self::remove`] running in
+    /// another process can lose or split it.
+    pub fn append(&self, record: &RunRecord) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+
+        let _lock = FileLock::acquire(&self.path, LOCK_TIMEOUT)?;
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        // History names every endpoint run against; keep it to the owner.
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, FileMode::Private.bits());
+        options.open(&self.path)?.write_all(line.as_bytes())
+    }
+
+    /// Loads every record, oldest first.
+    ///
+    /// A missing file is an empty history. Lines that fail to
+    /// parse, such as a torn final write, are skipped.
+    pub fn load(&self) -> io::Result<Vec<RunRecord>> {
+        let file = match fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut records = Vec::new();
+        for line in BufReader::new(file).lines() {
+            if let Ok(record) = serde_json::from_str(&line?) {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+
+    /// Removes the first record equal to `record`.
+    ///
+    /// The file is rewritten through a temporary file in the
+    /// same directory and renamed into place, holding the store's
+    /// [`FileLock`] from the read to the rename.
+    ///
+    /// # Returns
+    /// * `io::Result<bool>`: Whether a record was removed.
+    pub fn remove(&self, record: &RunRecord) -> io::Result<bool> {
+        let _lock = FileLock::acquire(&self.path, LOCK_TIMEOUT)?;
+        let mut records = self.load()?;
+        let Some(index) = records.iter().position(|r| r == record) else {
+            return Ok(false);
+        };
+        records.remove(index);
+
+        let mut contents = String::new();
+        for record in &records {
+            contents.push_str(&serde_json::to_string(record)?);
+            contents.push('\n');
+        }
+        crate::util::atomic_write(&self.path, contents.as_bytes(), FileMode::Private)?;
+        Ok(true)
+    }
+
+    /// Removes every record from before `cutoff`, under the store's
+    /// [`FileLock`]. Lines that don't parse are kept as they are.
+    /// A dry run only reads, so it takes no lock.
+    ///
+    /// # Arguments
+    /// * `cutoff`:  Records older than this go.
+    /// * `dry_run`: Count them without rewriting the file.
+    ///
+    /// # Returns
+    /// * `io::Result<usize>`: How many records were (or would be) removed.
+    pub fn prune(&self, cutoff: DateTime<Utc>, dry_run: bool) -> io::Result<usize> {
+        let _lock = (!dry_run).then(|| FileLock::acquire(&self.path, LOCK_TIMEOUT)).transpose()?;
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+
+        let (mut kept, mut removed) = (String::new(), 0);
+        for line in contents.lines() {
+            match serde_json::from_str::<RunRecord>(line) {
+                Ok(record) if record.timestamp < cutoff => removed += 1,
+                _ => {
+                    kept.push_str(line);
+                    kept.push('\n');
+                }
+            }
+        }
+        if removed > 0 && !dry_run {
+            crate::util::atomic_write(&self.path, kept.as_bytes(), FileMode::Private)?;
+        }
+        Ok(removed)
+    }
+}
+
+/// Aggregates over a set of runs, for `history stats`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct HistoryStats {
+    pub count:             usize,
+    pub successes:         usize,
+    /// Runs with a measured solve time, the sample behind the percentiles.
+    pub solves:            usize,
+    /// Fraction of runs that succeeded, from 0 to 1.
+    pub success_rate:      f64,
+    /// Median solve time of runs that solved.
+    pub p50_solve_ms:      Option<u64>,
+    pub p95_solve_ms:      Option<u64>,
+    /// Mean of each solve's average hash rate.
+    pub average_hash_rate: Option<u64>,
+}
+
+impl HistoryStats {
+    pub fn from_records(records: &[RunRecord]) -> Self {
+        let count = records.len();
+        let successes = records.iter().filter(|r| r.outcome == RunOutcome::Success).count();
+
+        let mut solve_ms: Vec<u64> = records.iter().filter_map(|r| r.solve_ms).collect();
+        solve_ms.sort_unstable();
+        let hash_rates: Vec<u64> = records.iter().filter_map(RunRecord::hash_rate).collect();
+
+        Self {
+            count,
+            successes,
+            solves:            solve_ms.len(),
+            success_rate:      if count == 0 { 0.0 } else { successes as f64 / count as f64 },
+            p50_solve_ms:      percentile(&solve_ms, 50),
+            p95_solve_ms:      percentile(&solve_ms, 95),
+            average_hash_rate: (!hash_rates.is_empty())
+                .then(|| hash_rates.iter().sum::<u64>() / hash_rates.len() as u64),
+        }
+    }
+}
+
+/// Two sets of runs side by side, for `history compare`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistoryComparison {
+    pub baseline:  HistoryStats,
+    pub candidate: HistoryStats,
+    /// Percentage change from baseline to candidate for each metric,
+    /// or `None` when either side has no value to compare.
+    pub changes:   StatsChanges,
+}
+
+/// Percentage deltas between two [`HistoryStats`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatsChanges {
+    pub p50_solve_ms:      Option<f64>,
+    pub p95_solve_ms:      Option<f64>,
+    pub average_hash_rate: Option<f64>,
+    pub failure_rate:      Option<f64>,
+}
+
+impl HistoryComparison {
+    /// Fewer solves than this on either side makes the comparison a hint at best.
+    pub const MIN_SAMPLES: usize = 10;
+
+    pub fn new(baseline: &[RunRecord], candidate: &[RunRecord]) -> Self {
+        let baseline = HistoryStats::from_records(baseline);
+        let candidate = HistoryStats::from_records(candidate);
+        let failure_rate = |stats: &HistoryStats| (stats.count > 0).then(|| 1.0 - stats.success_rate);
+
+        let changes = StatsChanges {
+            p50_solve_ms:      percent_change(baseline.p50_solve_ms.map(|v| v as f64), candidate.p50_solve_ms.map(|v| v as f64)),
+            p95_solve_ms:      percent_change(baseline.p95_solve_ms.map(|v| v as f64), candidate.p95_solve_ms.map(|v| v as f64)),
+            average_hash_rate: percent_change(baseline.average_hash_rate.map(|v| v as f64), candidate.average_hash_rate.map(|v| v as f64)),
+            failure_rate:      percent_change(failure_rate(&baseline), failure_rate(&candidate)),
+        };
+        Self { baseline, candidate, changes }
+    }
+
+    /// Whether either side has too few solves for the deltas to mean much.
+    pub fn is_small_sample(&self) -> bool {
+        self.baseline.solves.min(self.candidate.solves) < Self::MIN_SAMPLES
+    }
+}
+
+/// The change from `old` to `new` in percent.
+fn percent_change(old: Option<f64>, new: Option<f64>) -> Option<f64> {
+    match (old, new) {
+        (Some(old), Some(new)) if old != 0.0 => Some((new - old) / old * 100.0),
+        _ => None,
+    }
+}
+
+/// Parses a `--from`/`--to` date: `YYYY-MM-DD` (local midnight)
+/// or an RFC 3339 timestamp.
+pub fn parse_date(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| Local.from_local_datetime(&date.and_hms_opt(0, 0, 0)?).earliest())
+        .map(|midnight| midnight.with_timezone(&Utc))
+        .ok_or_else(|| format!("invalid date '{value}' (expected YYYY-MM-DD or an RFC 3339 timestamp)"))
+}
+
+/// The nearest-rank percentile of `sorted`.
+fn percentile(sorted: &[u64], percent: usize) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (percent * sorted.len()).div_ceil(100).max(1);
+    Some(sorted[rank - 1])
+}
+
+/// Turns [`record`] on or off for the rest of the process.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether runs are being recorded, from `[history] enabled`.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Distinct endpoints of `records`, most recently used first.
+///
+/// # Arguments
+/// * `records`: Runs, oldest first as [`HistoryStore::load`] returns them.
+/// * `limit`:   The most endpoints to return.
+pub fn recent_endpoints(records: &[RunRecord], limit: usize) -> Vec<String> {
+    let mut endpoints: Vec<String> = Vec::new();
+    for record in records.iter().rev() {
+        if endpoints.len() == limit {
+            break;
+        }
+        if !endpoints.contains(&record.endpoint) {
+            endpoints.push(record.endpoint.clone());
+        }
+    }
+    endpoints
+}
+
+/// The recent-endpoint slot an argument names: `@last` and `@1` are
+/// the most recent endpoint, `@2` the one before, up to `@9`.
+///
+/// # Returns
+/// * `Option<Result<usize, String>>`: `None` if `argument` isn't
+///   shorthand at all, or the 1-based slot or why it is invalid.
+pub fn recall_slot(argument: &str) -> Option<Result<usize, String>> {
+    let name = argument.strip_prefix('@')?;
+    let slot = match name {
+        "last" => Ok(1),
+        _      => name.parse::<usize>()
+            .ok()
+            .filter(|slot| (1..=RECALL_SLOTS).contains(slot))
+            .ok_or_else(|| format!("Unknown endpoint shorthand '{argument}' (use @last or @1 to @{RECALL_SLOTS})")),
+    };
+    Some(slot)
+}
+
+/// Resolves `@last` and `@1`..`@9` against `records`.
+///
+/// # Arguments
+/// * `argument`: The endpoint argument as given.
+/// * `records`:  Runs, oldest first.
+///
+/// # Returns
+/// * `Result<String, String>`: The endpoint, `argument` itself if it
+///   isn't shorthand, or why the shorthand can't be resolved.
+pub fn recall(argument: &str, records: &[RunRecord]) -> Result<String, String> {
+    let Some(slot) = recall_slot(argument) else {
+        return Ok(argument.to_string());
+    };
+    let slot = slot?;
+
+    let endpoints = recent_endpoints(records, RECALL_SLOTS);
+    match endpoints.get(slot - 1) {
+        Some(endpoint) => Ok(endpoint.clone()),
+        None if endpoints.is_empty() => Err(format!("Cannot resolve '{argument}': the run history is empty")),
+        None => Err(format!(
+            "Cannot resolve '{argument}': the run history has only {} recent endpoint{}",
+            endpoints.len(),
+            if endpoints.len() == 1 { "" } else { "s" },
+        )),
+    }
+}
+
+/// Resolves endpoint shorthand from the default store, printing what
+/// it stands for. Shorthand never reaches the network unresolved: it
+/// either resolves or fails here.
+///
+/// # Arguments
+/// * `argument`: The endpoint argument as given.
+pub fn resolve_endpoint(argument: &str) -> color_eyre::Result<String> {
+    if recall_slot(argument).is_none() {
+        return Ok(argument.to_string());
+    }
+    if !is_enabled() {
+        return Err(eyre!("Cannot resolve '{argument}': run history is turned off (`[history] enabled = false`)"));
+    }
+    let store = HistoryStore::open_default()
+        .ok_or_else(|| eyre!("Cannot resolve '{argument}': this platform has no data directory for run history"))?;
+    let records = store.load()
+        .map_err(|e| eyre!("Cannot resolve '{argument}': cannot read run history from '{}': {e}", store.path().display()))?;
+
+    let endpoint = recall(argument, &records).map_err(|e| eyre!(e))?;
+    crate::status_println!("Using {argument} = {endpoint}");
+    Ok(endpoint)
+}
+
+/// Finishes `record` with a command's result and appends it.
+///
+/// # Arguments
+/// * `record`:  The run, with any step-specific fields filled in.
+/// * `elapsed`: Total time the command took.
+/// * `result`:  What the command returned.
+pub fn record_result<T>(record: &mut RunRecord, elapsed: Duration, result: &color_eyre::Result<T>) {
+    finish_with_result(record, elapsed, result);
+    self::record(record);
+}
+
+/// Fills in `record`'s outcome from a command's result without
+/// appending it, for callers that write it elsewhere or later.
+pub fn finish_with_result<T>(record: &mut RunRecord, elapsed: Duration, result: &color_eyre::Result<T>) {
+    record.finish(elapsed, result.as_ref().err().map(|e| e.to_string()));
+    if record.outcome == RunOutcome::Failure && record.error_kind.is_none() {
+        record.error_kind = Some(ErrorKind::Other);
+    }
+}
+
+/// Appends `record` to the default store, unless history
+/// is turned off in the config file.
+///
+/// History is a convenience, so failures are logged as
+/// warnings and never fail the command that produced it.
+pub fn record(record: &RunRecord) {
+    if !is_enabled() {
+        return;
+    }
+    let Some(store) = HistoryStore::open_default() else {
+        return;
+    };
+
+    if let Err(e) = store.append(record) {
+        crate::warnings::emit(Warning::new(
+            WarningCode::HistoryNotRecorded,
+            format!("Could not record run history in '{}': {e}", store.path().display()),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn sample(endpoint: &str) -> RunRecord {
+        let mut record = RunRecord::new(RunCommand::Solve, endpoint);
+        record.difficulty = Some(1_000);
+        record.attempts = Some(2_000);
+        record.solve_ms = Some(500);
+        record.finish(Duration::from_millis(750), None);
+        record
+    }
+
+    #[test]
+    fn test_append_and_load_round_trip() {
+        let dir = tempdir().unwrap();
+        let store = HistoryStore::new(dir.path().join("nested").join(HISTORY_FILE));
+
+        store.append(&sample("https://a.example")).unwrap();
+        store.append(&sample("https://b.example")).unwrap();
+
+        let records = store.load().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].endpoint, "https://b.example");
+        assert_eq!(records[0].elapsed_ms, 750);
+        assert_eq!(records[0].hash_rate(), Some(4_000));
+    }
+
+    #[test]
+    fn test_load_skips_bad_lines_and_missing_file() {
+        let dir = tempdir().unwrap();
+        let store = HistoryStore::new(dir.path().join(HISTORY_FILE));
+        assert!(store.load().unwrap().is_empty());
+
+        store.append(&sample("https://a.example")).unwrap();
+        fs::write(
+            store.path(),
+            format!("{}{{\"truncated\n", fs::read_to_string(store.path()).unwrap()),
+        ).unwrap();
+
+        assert_eq!(store.load().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_remove() {
+        let dir = tempdir().unwrap();
+        let store = HistoryStore::new(dir.path().join(HISTORY_FILE));
+        let first = sample("https://a.example");
+        store.append(&first).unwrap();
+        store.append(&sample("https://b.example")).unwrap();
+
+        assert!(store.remove(&first).unwrap());
+        assert!(!store.remove(&first).unwrap());
+
+        let records = store.load().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].endpoint, "https://b.example");
+    }
+
+    #[test]
+    fn test_concurrent_appends_and_removes_lose_nothing() {
+        let dir = tempdir().unwrap();
+        let store = HistoryStore::new(dir.path().join(HISTORY_FILE));
+        let (writers, per_writer) = (8, 20);
+
+        std::thread::scope(|scope| {
+            for writer in 0..writers {
+                let store = &store;
+                scope.spawn(move || {
+                    for n in 0..per_writer {
+                        let record = sample(&format!("https://{writer}-{n}.example"));
+                        store.append(&record).unwrap();
+                        // Every other record is removed again, racing the other writers' appends.
+                        if n % 2 == 1 {
+                            assert!(store.remove(&record).unwrap());
+                        }
+                    }
+                });
+            }
+        });
+
+        let records = store.load().unwrap();
+        let content = fs::read_to_string(store.path()).unwrap();
+        assert_eq!(content.lines().count(), records.len(), "a line failed to parse");
+        assert_eq!(records.len(), writers * per_writer / 2);
+        for writer in 0..writers {
+            for n in (0..per_writer).step_by(2) {
+                let endpoint = format!("https://{writer}-{n}.example");
+                assert!(records.iter().any(|record| record.endpoint == endpoint), "{endpoint} was lost");
+            }
+        }
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut records: Vec<RunRecord> = (1..=20).map(|n| {
+            let mut record = sample("https://a.example");
+            record.solve_ms = Some(n * 100);
+            record.attempts = Some(n * 100);
+            record
+        }).collect();
+        let mut failed = RunRecord::new(RunCommand::Fetch, "https://a.example");
+        failed.finish(Duration::from_secs(1), Some("timed out".into()));
+        records.push(failed);
+
+        let stats = HistoryStats::from_records(&records);
+        assert_eq!(stats.count, 21);
+        assert_eq!(stats.successes, 20);
+        assert_eq!(stats.p50_solve_ms, Some(1_000));
+        assert_eq!(stats.p95_solve_ms, Some(1_900));
+        assert_eq!(stats.average_hash_rate, Some(1_000));
+
+        assert_eq!(HistoryStats::from_records(&[]), HistoryStats::default());
+    }
+
+    #[test]
+    fn test_comparison() {
+        let runs = |solve_ms: u64, count: usize| -> Vec<RunRecord> {
+            (0..count).map(|_| {
+                let mut record = sample("https://a.example");
+                record.solve_ms = Some(solve_ms);
+                record
+            }).collect()
+        };
+
+        let comparison = HistoryComparison::new(&runs(1_000, 10), &runs(800, 12));
+        assert_eq!(comparison.changes.p50_solve_ms, Some(-20.0));
+        assert_eq!(comparison.changes.average_hash_rate, Some(25.0));
+        // A zero failure rate has no meaningful percentage change.
+        assert_eq!(comparison.changes.failure_rate, None);
+        assert!(!comparison.is_small_sample());
+
+        assert!(HistoryComparison::new(&runs(1_000, 3), &runs(800, 12)).is_small_sample());
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(
+            parse_date("2025-03-01T12:00:00+02:00").unwrap(),
+            Utc.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap(),
+        );
+        let midnight = parse_date("2025-03-01").unwrap().with_timezone(&Local);
+        assert_eq!(midnight.date_naive(), NaiveDate::from_ymd_opt(2025, 3, 1).unwrap());
+        assert!(parse_date("March 1st").unwrap_err().contains("invalid date 'March 1st'"));
+    }
+
+    #[test]
+    fn test_old_records_have_no_error_kind() {
+        let line = r#"{"timestamp":"2025-01-01T00:00:00Z","command":"fetch","endpoint":"https://a.example","outcome":"failure","elapsed_ms":5}"#;
+        let record: RunRecord = serde_json::from_str(line).unwrap();
+
+        assert_eq!(record.error_kind, None);
+        assert!(record.matches_endpoint("A.EXAMPLE"));
+    }
+
+    #[test]
+    fn test_recent_endpoints() {
+        let records: Vec<RunRecord> = ["a", "b", "a", "c", "b"]
+            .iter()
+            .map(|host| sample(&format!("https://{host}.example")))
+            .collect();
+
+        assert_eq!(recent_endpoints(&records, 5), ["https://b.example", "https://c.example", "https://a.example"]);
+        assert_eq!(recent_endpoints(&records, 1), ["https://b.example"]);
+        assert!(recent_endpoints(&[], 5).is_empty());
+    }
+
+    #[test]
+    fn test_recall() {
+        let records: Vec<RunRecord> = ["a", "b", "a"]
+            .iter()
+            .map(|host| sample(&format!("https://{host}.example")))
+            .collect();
+
+        assert_eq!(recall("@last", &records).unwrap(), "https://a.example");
+        assert_eq!(recall("@2", &records).unwrap(), "https://b.example");
+        assert_eq!(recall("https://c.example", &records).unwrap(), "https://c.example");
+        assert_eq!(recall("@3", &records).unwrap_err(), "Cannot resolve '@3': the run history has only 2 recent endpoints");
+        assert_eq!(recall("@last", &[]).unwrap_err(), "Cannot resolve '@last': the run history is empty");
+        assert!(recall("@10", &records).unwrap_err().starts_with("Unknown endpoint shorthand '@10'"));
+        assert!(recall("@0", &records).is_err());
+        assert!(recall("@", &records).is_err());
+    }
+
+    #[test]
+    fn test_failure_outcome() {
+        let mut record = RunRecord::new(RunCommand::Fetch, "https://a.example");
+        record.finish(Duration::from_secs(1), Some("connection refused".into()));
+
+        assert_eq!(record.outcome, RunOutcome::Failure);
+        assert_eq!(record.hash_rate(), None);
+    }
+}
@@ -0,0 +1,180 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tempfile::NamedTempFile;
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::util::{FileLock, FileMode, LOCK_TIMEOUT};
+
+/// Validators kept for one URL, next to its body.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub url:           String,
+    pub etag:          Option<String>,
+    pub last_modified: Option<String>,
+    /// Length of the cached body in bytes.
+    pub size:          u64,
+    pub stored_at:     DateTime<Utc>,
+}
+
+impl CacheEntry {
+    /// `If-None-Match` and `If-Modified-Since` for a conditional request.
+    pub fn conditional_headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = Vec::new();
+        if let Some(etag) = &self.etag {
+            headers.push(("If-None-Match", etag.clone()));
+        }
+        if let Some(last_modified) = &self.last_modified {
+            headers.push(("If-Modified-Since", last_modified.clone()));
+        }
+        headers
+    }
+}
+
+/// Response bodies of protected endpoints, keyed by URL, for
+/// conditional `get` requests.
+///
+/// Each URL has a `<hash>.json` entry and a `<hash>.body` file.
+#[derive(Debug, Clone)]
+pub struct HttpCache {
+    dir: PathBuf,
+}
+
+impl HttpCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// `ironshield/http` in the platform cache directory.
+    pub fn default_location() -> Option<Self> {
+        dirs::cache_dir().map(|dir| Self::new(dir.join("ironshield").join("http")))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The name `url`'s files are kept under.
+    pub(crate) fn key(url: &str) -> String {
+        let digest = Sha256::digest(url.as_bytes());
+        digest.iter().take(16).map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    fn entry_path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", Self::key(url)))
+    }
+
+    /// Where the cached body for `url` is kept.
+    pub fn body_path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{}.body", Self::key(url)))
+    }
+
+    /// The entry for `url`, if both it and a body of the recorded
+    /// length are on disk.
+    pub fn lookup(&self, url: &str) -> Option<CacheEntry> {
+        let entry: CacheEntry = serde_json::from_str(&fs::read_to_string(self.entry_path(url)).ok()?).ok()?;
+        let size = fs::metadata(self.body_path(url)).ok()?.len();
+        (entry.url == url && entry.size == size).then_some(entry)
+    }
+
+    /// A temporary file in the cache directory to stream a body into
+    /// before [`Self::store`].
+    pub fn begin(&self) -> io::Result<NamedTempFile> {
+        fs::create_dir_all(&self.dir)?;
+        NamedTempFile::new_in(&self.dir)
+    }
+
+    /// Moves a fully written body into place and records `entry` for it.
+    pub fn store(&self, entry: &CacheEntry, body: NamedTempFile) -> io::Result<()> {
+        let entry_path = self.entry_path(&entry.url);
+        let _lock = FileLock::acquire(&entry_path, LOCK_TIMEOUT)?;
+        body.as_file().sync_all()?;
+        body.persist(self.body_path(&entry.url)).map_err(|e| e.error)?;
+        crate::util::atomic_write(&entry_path, serde_json::to_string_pretty(entry)?.as_bytes(), FileMode::Private)
+    }
+
+    /// Deletes every cached entry and body.
+    ///
+    /// # Returns
+    /// * `io::Result<usize>`: How many entries were removed.
+    pub fn purge(&self) -> io::Result<usize> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+        let mut removed = 0;
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|extension| extension == "json") {
+                removed += 1;
+            }
+            fs::remove_file(&path)?;
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    fn entry(url: &str, size: u64) -> CacheEntry {
+        CacheEntry {
+            url:           url.to_string(),
+            etag:          Some("\"v1\"".to_string()),
+            last_modified: Some("Wed, 21 Oct 2015 07:28:00 GMT".to_string()),
+            size,
+            stored_at:     Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_store_lookup_and_purge() {
+        let dir = tempdir().unwrap();
+        let cache = HttpCache::new(dir.path().join("http"));
+        let url = "https://a.example/artifact.tar";
+        assert_eq!(cache.lookup(url), None);
+
+        let mut body = cache.begin().unwrap();
+        body.write_all(b"payload").unwrap();
+        cache.store(&entry(url, 7), body).unwrap();
+
+        assert_eq!(cache.lookup(url).unwrap().etag.as_deref(), Some("\"v1\""));
+        assert_eq!(fs::read(cache.body_path(url)).unwrap(), b"payload");
+        assert_eq!(cache.lookup("https://b.example/"), None);
+
+        assert_eq!(cache.purge().unwrap(), 1);
+        assert_eq!(cache.lookup(url), None);
+        assert_eq!(HttpCache::new(dir.path().join("missing")).purge().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_truncated_body_is_not_served() {
+        let dir = tempdir().unwrap();
+        let cache = HttpCache::new(dir.path().to_path_buf());
+        let url = "https://a.example/artifact.tar";
+
+        let mut body = cache.begin().unwrap();
+        body.write_all(b"payload").unwrap();
+        cache.store(&entry(url, 7), body).unwrap();
+        fs::write(cache.body_path(url), b"pay").unwrap();
+
+        assert_eq!(cache.lookup(url), None);
+    }
+
+    #[test]
+    fn test_conditional_headers() {
+        let headers = entry("https://a.example/", 0).conditional_headers();
+
+        assert_eq!(headers, [
+            ("If-None-Match", "\"v1\"".to_string()),
+            ("If-Modified-Since", "Wed, 21 Oct 2015 07:28:00 GMT".to_string()),
+        ]);
+    }
+}
@@ -0,0 +1,118 @@
+use color_eyre::eyre::eyre;
+use ironshield::{ClientConfig, IronShieldChallenge, IronShieldChallengeResponse};
+use reqwest::StatusCode;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::api::{ChallengeEnvelope, NoToken};
+
+/// Failures to fake, from the `--inject-*` testing flags, so retry and
+/// error reporting can be exercised end to end without a misbehaving
+/// server.
+#[derive(Debug, Default)]
+pub struct Injection {
+    /// Fetches still to fail, from `--inject-fetch-failure`.
+    fetch_failures: AtomicU64,
+    /// Status every submit fails with, from `--inject-submit-status`.
+    submit_status:  Option<StatusCode>,
+    /// Added to every solve, from `--inject-solve-delay`.
+    solve_delay:    Option<Duration>,
+    /// Whether solvers hand back a wrong nonce, from `--inject-corrupt-solution`.
+    corrupt:        bool,
+}
+
+impl Injection {
+    pub fn new(fetch_failures: u64, submit_status: Option<StatusCode>, solve_delay: Option<Duration>, corrupt: bool) -> Self {
+        Self { fetch_failures: AtomicU64::new(fetch_failures), submit_status, solve_delay, corrupt }
+    }
+
+    /// Whether the next fetch should fail, counting it if so.
+    fn take_fetch_failure(&self) -> bool {
+        self.fetch_failures.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| left.checked_sub(1)).is_ok()
+    }
+
+    /// [`crate::api::fetch_challenge`], unless `--inject-fetch-failure`
+    /// has fetches left to fail.
+    pub async fn fetch_challenge(
+        &self,
+        http:     &reqwest::Client,
+        config:   &ClientConfig,
+        endpoint: &str,
+    ) -> color_eyre::Result<ChallengeEnvelope> {
+        if self.take_fetch_failure() {
+            return Err(injected(StatusCode::SERVICE_UNAVAILABLE, "--inject-fetch-failure"));
+        }
+        crate::api::fetch_challenge(http, config, endpoint).await
+    }
+
+    /// Fails every submit the way the API would with
+    /// `--inject-submit-status`, if it is set. Called before every submit.
+    pub fn check_submit(&self) -> Result<(), NoToken> {
+        match self.submit_status {
+            Some(status) => Err(NoToken { status, reason: "injected by --inject-submit-status".to_string() }),
+            None         => Ok(()),
+        }
+    }
+
+    /// Waits out `--inject-solve-delay`, if set. Called before every solve.
+    pub async fn solve_delay(&self) {
+        if let Some(delay) = self.solve_delay {
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Swaps the solver's nonce for one that doesn't solve `challenge`,
+    /// if `--inject-corrupt-solution` is set. Called after every solve,
+    /// before the solution is checked.
+    pub fn corrupt_solution(&self, challenge: &IronShieldChallenge, solution: &mut IronShieldChallengeResponse) {
+        if !self.corrupt {
+            return;
+        }
+        // The next nonce that fails rather than simply the next one, which
+        // solves an easy challenge often enough to make tests flaky.
+        let mut nonce = solution.solution.wrapping_add(1);
+        while crate::solve::verifies(challenge, nonce) {
+            nonce = nonce.wrapping_add(1);
+        }
+        solution.solution = nonce;
+    }
+}
+
+/// Parses `--inject-submit-status`, which must be an error status.
+pub fn parse_status(value: &str) -> Result<StatusCode, String> {
+    let status = value.parse::<u16>().ok()
+        .and_then(|code| StatusCode::from_u16(code).ok())
+        .ok_or_else(|| format!("'{value}' is not an HTTP status code"))?;
+    match status.is_client_error() || status.is_server_error() {
+        true  => Ok(status),
+        false => Err(format!("{status} is not an error status; use 400 to 599")),
+    }
+}
+
+fn injected(status: StatusCode, flag: &str) -> color_eyre::Report {
+    eyre!("HTTP {status} (injected by {flag})")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_the_first_fetches_fail() {
+        let injection = Injection::new(2, None, None, false);
+
+        assert!(injection.take_fetch_failure());
+        assert!(injection.take_fetch_failure());
+        assert!(!injection.take_fetch_failure());
+        assert!(!Injection::default().take_fetch_failure());
+    }
+
+    #[test]
+    fn test_submit_status_must_be_an_error() {
+        assert_eq!(parse_status("503"), Ok(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(parse_status("200").unwrap_err().contains("not an error status"));
+        assert!(parse_status("abc").is_err());
+        assert!(parse_status("1000").is_err());
+    }
+}
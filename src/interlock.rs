@@ -0,0 +1,140 @@
+use ironshield::ClientConfig;
+
+/// A setting that makes a run against production risky.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Safeguard {
+    /// `api_base_url` isn't the library default.
+    ApiBaseUrlOverride(String),
+    /// `api_base_url` is plain `http://`, so challenges and tokens travel unencrypted.
+    PlainHttpApi,
+}
+
+impl std::fmt::Display for Safeguard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ApiBaseUrlOverride(url) => write!(f, "`api_base_url` is overridden to {url}"),
+            Self::PlainHttpApi            => write!(f, "`api_base_url` uses plain http"),
+        }
+    }
+}
+
+/// What to do before running against an endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Proceed,
+    /// Ask on the terminal first.
+    Confirm(Vec<Safeguard>),
+    /// Nobody can be asked and `--yes` wasn't given.
+    Abort(Vec<Safeguard>),
+}
+
+/// The risky settings in `config`.
+pub fn tripped(config: &ClientConfig) -> Vec<Safeguard> {
+    let mut safeguards = Vec::new();
+    if config.api_base_url != ClientConfig::default().api_base_url {
+        safeguards.push(Safeguard::ApiBaseUrlOverride(config.api_base_url.clone()));
+    }
+    if config.api_base_url.to_ascii_lowercase().starts_with("http://") {
+        safeguards.push(Safeguard::PlainHttpApi);
+    }
+    safeguards
+}
+
+/// Whether `endpoint`'s host is one of `production_hosts` or a
+/// subdomain of one, ignoring case and port. An endpoint that doesn't
+/// parse never matches; it fails validation later anyway.
+pub fn is_production(endpoint: &str, production_hosts: &[String]) -> bool {
+    let Some(host) = reqwest::Url::parse(endpoint).ok().and_then(|url| url.host_str().map(str::to_ascii_lowercase)) else {
+        return false;
+    };
+    production_hosts.iter().any(|entry| {
+        let entry = entry.trim().trim_start_matches("*.").trim_start_matches('.').to_ascii_lowercase();
+        !entry.is_empty() && (host == entry || host.ends_with(&format!(".{entry}")))
+    })
+}
+
+/// Decides whether a run against `endpoint` may go ahead.
+///
+/// # Arguments
+/// * `endpoint`:         The endpoint the command runs against.
+/// * `production_hosts`: `production_hosts` from the config file.
+/// * `safeguards`:       The risky settings in effect, from [`tripped`].
+/// * `assume_yes`:       Whether `--yes` was given.
+/// * `interactive`:      Whether someone at a terminal can be asked.
+pub fn decide(
+    endpoint:         &str,
+    production_hosts: &[String],
+    safeguards:       Vec<Safeguard>,
+    assume_yes:       bool,
+    interactive:      bool,
+) -> Decision {
+    if safeguards.is_empty() || assume_yes || !is_production(endpoint, production_hosts) {
+        return Decision::Proceed;
+    }
+    match interactive {
+        true  => Decision::Confirm(safeguards),
+        false => Decision::Abort(safeguards),
+    }
+}
+
+/// Joins safeguards for a message, e.g. "`api_base_url` is overridden
+/// to http://localhost:8080 and `api_base_url` uses plain http".
+pub fn describe(safeguards: &[Safeguard]) -> String {
+    safeguards.iter().map(Safeguard::to_string).collect::<Vec<_>>().join(" and ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hosts(entries: &[&str]) -> Vec<String> {
+        entries.iter().map(|entry| entry.to_string()).collect()
+    }
+
+    #[test]
+    fn test_production_matching() {
+        let production = hosts(&["shop.example.com", "*.payments.example"]);
+
+        assert!(is_production("https://shop.example.com/checkout", &production));
+        assert!(is_production("https://SHOP.example.com:8443/", &production));
+        assert!(is_production("https://eu.shop.example.com/", &production));
+        assert!(is_production("https://api.payments.example/", &production));
+        assert!(is_production("https://payments.example/", &production));
+        assert!(!is_production("https://staging-shop.example.com/", &production));
+        assert!(!is_production("https://example.com/", &production));
+        assert!(!is_production("shop.example.com", &production));
+        assert!(!is_production("https://shop.example.com/", &hosts(&["", " "])));
+    }
+
+    #[test]
+    fn test_tripped() {
+        assert!(tripped(&ClientConfig::default()).is_empty());
+
+        let config = ClientConfig { api_base_url: "http://localhost:8080".to_string(), ..ClientConfig::default() };
+        assert_eq!(tripped(&config), [
+            Safeguard::ApiBaseUrlOverride("http://localhost:8080".to_string()),
+            Safeguard::PlainHttpApi,
+        ]);
+    }
+
+    #[test]
+    fn test_decision() {
+        let production = hosts(&["shop.example.com"]);
+        let override_url = || vec![Safeguard::ApiBaseUrlOverride("https://patched.local".to_string())];
+        let endpoint = "https://shop.example.com/";
+
+        assert_eq!(decide(endpoint, &production, override_url(), false, false), Decision::Abort(override_url()));
+        assert_eq!(decide(endpoint, &production, override_url(), false, true), Decision::Confirm(override_url()));
+        assert_eq!(decide(endpoint, &production, override_url(), true, false), Decision::Proceed);
+        assert_eq!(decide(endpoint, &production, Vec::new(), false, false), Decision::Proceed);
+        assert_eq!(decide("https://test.example.com/", &production, override_url(), false, false), Decision::Proceed);
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(
+            describe(&[Safeguard::ApiBaseUrlOverride("http://localhost".to_string()), Safeguard::PlainHttpApi]),
+            "`api_base_url` is overridden to http://localhost and `api_base_url` uses plain http",
+        );
+    }
+}
@@ -0,0 +1,78 @@
+// The modules below back the `ironshield` binary and stay private to
+// the crate; the re-exports at the bottom are the library's API.
+pub(crate) mod api;
+pub(crate) mod api_version;
+pub(crate) mod batch;
+pub(crate) mod benchmark;
+pub(crate) mod build_profile;
+pub(crate) mod checksum;
+pub(crate) mod commands;
+pub(crate) mod compression;
+pub(crate) mod config;
+pub(crate) mod daemon;
+pub(crate) mod deadline;
+pub(crate) mod display;
+pub(crate) mod endpoint;
+pub(crate) mod energy;
+pub(crate) mod estimate;
+pub(crate) mod events;
+pub(crate) mod examples;
+pub(crate) mod failures;
+pub(crate) mod first_progress;
+pub(crate) mod har;
+pub(crate) mod history;
+pub(crate) mod http_cache;
+pub(crate) mod inject;
+pub(crate) mod interlock;
+pub(crate) mod logging;
+pub(crate) mod margin;
+pub(crate) mod metadata;
+pub(crate) mod metrics;
+pub(crate) mod output;
+pub(crate) mod presolve;
+pub(crate) mod prewarm;
+pub(crate) mod prompt;
+pub(crate) mod queue;
+pub(crate) mod rate_limit;
+pub(crate) mod redact;
+pub(crate) mod refetch;
+pub(crate) mod repro;
+pub(crate) mod resource;
+pub(crate) mod retry;
+pub(crate) mod schedule;
+pub(crate) mod serve;
+pub(crate) mod solve;
+pub(crate) mod spool;
+pub(crate) mod state;
+pub(crate) mod template;
+pub(crate) mod throttle;
+pub(crate) mod tuning;
+pub(crate) mod tunnel;
+pub(crate) mod tui;
+pub(crate) mod warnings;
+
+mod cli;
+mod client;
+mod util;
+
+pub(crate) use util::{status_println, verbose_log, verbose_section};
+
+// The `ironshield` binary's entry point, not part of the API.
+#[doc(hidden)]
+pub use cli::run;
+
+pub use api::ChallengeEnvelope;
+pub use api_version::{ApiVersion, Negotiated};
+pub use client::{CliClient, Event, SolveOptions};
+pub use estimate::SolveEstimate;
+pub use metadata::ChallengeMetadata;
+pub use presolve::{Limits, Refused};
+pub use solve::Strategy;
+
+pub use ironshield::{
+    ClientConfig,
+    IronShieldChallenge,
+    IronShieldChallengeResponse,
+    ProgressTracker,
+};
+pub use ironshield_types::IronShieldToken;
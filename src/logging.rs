@@ -0,0 +1,941 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use clap::ValueEnum;
+use crossterm::style::{style, Color, Stylize};
+use ironshield::handler::error::ErrorHandler;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tracing::{Event, Subscriber};
+use tracing::field::{Field, Visit};
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::str::FromStr;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::time::{Duration, Instant};
+
+/// When set, diagnostic output is written to stdout
+/// instead of stderr (the `--log-stdout` escape hatch).
+static LOG_TO_STDOUT: AtomicBool = AtomicBool::new(false);
+
+/// The active [`LogTimestamps`] mode, stored as its discriminant.
+static TIMESTAMPS: AtomicU8 = AtomicU8::new(LogTimestamps::None as u8);
+
+/// The debug log file, if `--log-file` or `log_file` is set.
+static LOG_FILE: Mutex<Option<BufWriter<File>>> = Mutex::new(None);
+
+/// Whether `--quiet` suppressed all non-essential console output.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Whether console labels are colored.
+static COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Reference point for `elapsed` timestamps.
+static PROCESS_START: OnceLock<Instant> = OnceLock::new();
+
+/// When set, console output is sent here instead of being
+/// written, so the TUI can render it without corrupting the screen.
+static CONSOLE_CAPTURE: Mutex<Option<UnboundedSender<LogRecord>>> = Mutex::new(None);
+
+/// Prefix prepended to each verbose log line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogTimestamps {
+    /// No prefix.
+    #[default]
+    None,
+    /// ISO-8601 wall clock time in UTC with millisecond precision.
+    Clock,
+    /// Offset since process start, e.g. `+12.345s`.
+    Elapsed,
+}
+
+impl LogTimestamps {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Clock,
+            2 => Self::Elapsed,
+            _ => Self::None,
+        }
+    }
+}
+
+/// The category attached to each `verbose_log!` line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogCategory {
+    Compute,
+    Error,
+    Info,
+    Receive,
+    Success,
+    Submit,
+    Network,
+    Timing,
+    Warning,
+}
+
+impl LogCategory {
+    /// Every category, in display order.
+    pub const ALL: [LogCategory; 9] = [
+        Self::Compute,
+        Self::Error,
+        Self::Info,
+        Self::Receive,
+        Self::Success,
+        Self::Submit,
+        Self::Network,
+        Self::Timing,
+        Self::Warning,
+    ];
+
+    /// The lowercase name used in `--log-filter`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Compute => "compute",
+            Self::Error   => "error",
+            Self::Info    => "info",
+            Self::Receive => "receive",
+            Self::Success => "success",
+            Self::Submit  => "submit",
+            Self::Network => "network",
+            Self::Timing  => "timing",
+            Self::Warning => "warning",
+        }
+    }
+
+    /// The color of the label on a terminal.
+    pub fn color(self) -> Color {
+        match self {
+            Self::Compute => Color::Magenta,
+            Self::Error   => Color::Red,
+            Self::Info    => Color::Grey,
+            Self::Receive => Color::DarkCyan,
+            Self::Success => Color::Green,
+            Self::Submit  => Color::DarkCyan,
+            Self::Network => Color::Cyan,
+            Self::Timing  => Color::Blue,
+            Self::Warning => Color::Yellow,
+        }
+    }
+
+    /// The uppercase label printed in front of the message.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Compute => "COMPUTE",
+            Self::Error   => "ERROR",
+            Self::Info    => "INFO",
+            Self::Receive => "RECEIVE",
+            Self::Success => "SUCCESS",
+            Self::Submit  => "SUBMIT",
+            Self::Network => "NETWORK",
+            Self::Timing  => "TIMING",
+            Self::Warning => "WARNING",
+        }
+    }
+
+    fn bit(self) -> u16 {
+        1 << (self as u16)
+    }
+}
+
+/// A set of [`LogCategory`] values, parsed from a
+/// comma-separated list such as `network,timing`.
+///
+/// `error` and `warning` are always part of the set so
+/// that problems are never hidden while verbose is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CategorySet(u16);
+
+impl CategorySet {
+    /// Every category.
+    pub const ALL: CategorySet = CategorySet(0x01FF);
+
+    /// Adds `category` to the set, or removes it if present.
+    pub fn toggle(&mut self, category: LogCategory) {
+        self.0 ^= category.bit();
+    }
+
+    /// Returns whether `category` passes this filter.
+    pub fn contains(self, category: LogCategory) -> bool {
+        matches!(category, LogCategory::Error | LogCategory::Warning)
+            || self.0 & category.bit() != 0
+    }
+}
+
+impl Default for CategorySet {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl FromStr for CategorySet {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut bits = 0;
+        for name in value.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            if name.eq_ignore_ascii_case("all") {
+                return Ok(Self::ALL);
+            }
+
+            let category = LogCategory::ALL
+                .into_iter()
+                .find(|category| category.name().eq_ignore_ascii_case(name))
+                .ok_or_else(|| {
+                    let accepted: Vec<&str> = LogCategory::ALL.iter().map(|c| c.name()).collect();
+                    format!("unknown log category '{name}' (accepted: all, {})", accepted.join(", "))
+                })?;
+            bits |= category.bit();
+        }
+
+        if bits == 0 {
+            return Err("log filter must name at least one category".to_string());
+        }
+
+        Ok(Self(bits))
+    }
+}
+
+impl TryFrom<String> for CategorySet {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for CategorySet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if *self == Self::ALL {
+            return f.write_str("all");
+        }
+
+        let names: Vec<&str> = LogCategory::ALL
+            .into_iter()
+            .filter(|category| self.0 & category.bit() != 0)
+            .map(LogCategory::name)
+            .collect();
+        if names.is_empty() {
+            return f.write_str("none");
+        }
+        f.write_str(&names.join(","))
+    }
+}
+
+impl From<CategorySet> for String {
+    fn from(value: CategorySet) -> Self {
+        value.to_string()
+    }
+}
+
+/// Records the process start time used by `elapsed` timestamps.
+///
+/// Should be called as early as possible in `main`; if it is
+/// never called, the first log line becomes the reference point.
+pub fn init_clock() {
+    PROCESS_START.get_or_init(Instant::now);
+}
+
+/// Returns whether the console stream (stderr, or stdout
+/// with `--log-stdout`) is a terminal.
+pub fn console_is_terminal() -> bool {
+    if LOG_TO_STDOUT.load(Ordering::Relaxed) {
+        io::stdout().is_terminal()
+    } else {
+        io::stderr().is_terminal()
+    }
+}
+
+/// Returns whether console output should be colored, for output
+/// that bypasses the logging layers such as result tables.
+pub fn colors_enabled() -> bool {
+    COLOR.load(Ordering::Relaxed)
+}
+
+/// Returns whether `--quiet` is in effect, for output
+/// that bypasses the logging layers such as the spinner.
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Builds the fixed-width prefix for a log line.
+///
+/// Both styles pad to a constant width so that the
+/// messages following them line up in columns.
+///
+/// # Arguments
+/// * `mode`:    The timestamp style.
+/// * `now`:     The current wall clock time.
+/// * `elapsed`: Time since process start.
+///
+/// # Returns
+/// * `String`: The prefix including a trailing space,
+///             or an empty string for [`LogTimestamps::None`].
+pub fn format_prefix(mode: LogTimestamps, now: DateTime<Utc>, elapsed: Duration) -> String {
+    match mode {
+        LogTimestamps::None    => String::new(),
+        LogTimestamps::Clock   => format!("{} ", now.to_rfc3339_opts(SecondsFormat::Millis, true)),
+        LogTimestamps::Elapsed => format!("{:>11} ", format!("+{:.3}s", elapsed.as_secs_f64())),
+    }
+}
+
+/// Output format for log lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines with category labels.
+    #[default]
+    Text,
+    /// One JSON object per event, for log ingestion pipelines.
+    Json,
+}
+
+/// Everything needed to install the logging subscriber.
+#[derive(Debug, Clone, Default)]
+pub struct LogOptions {
+    /// Show verbose events on the console.
+    pub verbose:    bool,
+    /// Show nothing but errors on the console.
+    pub quiet:      bool,
+    /// Verbose categories shown on the console.
+    pub filter:     CategorySet,
+    /// Prefix for console lines in text format.
+    pub timestamps: LogTimestamps,
+    /// Console and log file format.
+    pub format:     LogFormat,
+    /// Write console output to stdout instead of stderr.
+    pub to_stdout:  bool,
+    /// Debug log file that receives every event.
+    pub log_file:   Option<String>,
+    /// Whether to color console labels.
+    pub color:      ColorChoice,
+}
+
+/// When to color console output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorChoice {
+    /// Color when the console stream is a terminal and `NO_COLOR` is unset.
+    #[default]
+    Auto,
+    /// Always color, even when piped or `NO_COLOR` is set.
+    Always,
+    /// Never color.
+    Never,
+}
+
+impl ColorChoice {
+    /// Decides whether to emit color.
+    ///
+    /// An explicit `always` wins over `NO_COLOR`, following
+    /// <https://no-color.org>: command-line arguments override it.
+    ///
+    /// # Arguments
+    /// * `is_terminal`: Whether the console stream is a terminal.
+    /// * `no_color`:    The value of the `NO_COLOR` environment variable.
+    ///
+    /// # Returns
+    /// * `bool`: `true` if labels should be colored.
+    pub fn resolve(self, is_terminal: bool, no_color: Option<&str>) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never  => false,
+            Self::Auto   => is_terminal && no_color.is_none_or(str::is_empty),
+        }
+    }
+}
+
+// Targets used by the events emitted from this module. Each verbose
+// category gets its own target so that `--log-filter` and `RUST_LOG`
+// can both select categories with plain `EnvFilter` directives, e.g.
+// `RUST_LOG=ironshield::verbose::network=debug`.
+const STATUS_TARGET: &str = "ironshield::status";
+const DETAIL_TARGET: &str = "ironshield::detail";
+const FILE_TARGET:   &str = "ironshield::file";
+const VERBOSE_TARGET_PREFIX: &str = "ironshield::verbose";
+
+/// Builds the console filter directives for the given options.
+///
+/// Status lines are shown unless `quiet` is set. Verbose lines are
+/// shown when `verbose` is set and their category passes `filter`,
+/// unless `RUST_LOG` is set, in which case it decides instead.
+///
+/// # Arguments
+/// * `verbose`:  Whether verbose output was requested.
+/// * `quiet`:    Whether `--quiet` was requested.
+/// * `filter`:   The categories allowed through.
+/// * `rust_log`: The value of `RUST_LOG`, if set.
+///
+/// # Returns
+/// * `String`: Comma-separated `EnvFilter` directives.
+pub fn console_directives(
+    verbose:  bool,
+    quiet:    bool,
+    filter:   CategorySet,
+    rust_log: Option<&str>,
+) -> String {
+    let mut directives = if quiet {
+        vec!["off".to_string()]
+    } else {
+        vec![format!("{STATUS_TARGET}=info")]
+    };
+
+    if let Some(rust_log) = rust_log.filter(|value| !value.trim().is_empty()) {
+        directives.push(rust_log.to_string());
+    } else if verbose {
+        directives.push(format!("{DETAIL_TARGET}=trace"));
+        directives.extend(
+            LogCategory::ALL
+                .into_iter()
+                .filter(|category| filter.contains(*category))
+                .map(|category| format!("{VERBOSE_TARGET_PREFIX}::{}=trace", category.name()))
+        );
+    }
+
+    directives.join(",")
+}
+
+/// Installs the global logging subscriber.
+///
+/// The console gets either the text layer or a JSON fmt layer,
+/// filtered by [`console_directives`]. If a log file is configured,
+/// a second layer writes every event this crate emits to it.
+///
+/// # Arguments
+/// * `options`: The resolved logging options.
+///
+/// # Returns
+/// * `Result<LogFileGuard, ErrorHandler>`: A guard that flushes the log
+///                                         file when dropped, or a config
+///                                         error if the file cannot be
+///                                         opened or a filter is invalid.
+pub fn init(options: &LogOptions) -> Result<LogFileGuard, ErrorHandler> {
+    LOG_TO_STDOUT.store(options.to_stdout, Ordering::Relaxed);
+    TIMESTAMPS.store(options.timestamps as u8, Ordering::Relaxed);
+    QUIET.store(options.quiet, Ordering::Relaxed);
+
+    let no_color = std::env::var("NO_COLOR").ok();
+    COLOR.store(options.color.resolve(console_is_terminal(), no_color.as_deref()), Ordering::Relaxed);
+
+    let rust_log = std::env::var("RUST_LOG").ok();
+    let console_filter = EnvFilter::builder()
+        .parse(console_directives(options.verbose, options.quiet, options.filter, rust_log.as_deref()))
+        .map_err(|e| ErrorHandler::config_error(format!("Invalid log filter: {e}")))?;
+
+    let mut layers: Vec<Box<dyn Layer<Registry> + Send + Sync>> = Vec::new();
+
+    layers.push(match options.format {
+        LogFormat::Text => TextLayer { sink: Sink::Console }.with_filter(console_filter).boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_ansi(false)
+            .with_writer(|| ConsoleWriter)
+            .with_filter(console_filter)
+            .boxed(),
+    });
+
+    if let Some(path) = &options.log_file {
+        open_log_file(path)?;
+
+        let file_filter = EnvFilter::builder()
+            .parse("ironshield=trace")
+            .map_err(|e| ErrorHandler::config_error(format!("Invalid log filter: {e}")))?;
+
+        layers.push(match options.format {
+            LogFormat::Text => TextLayer { sink: Sink::File }.with_filter(file_filter).boxed(),
+            LogFormat::Json => tracing_subscriber::fmt::layer()
+                .json()
+                .with_ansi(false)
+                .with_writer(|| LogFileWriter)
+                .with_filter(file_filter)
+                .boxed(),
+        });
+    }
+
+    tracing_subscriber::registry()
+        .with(layers)
+        .try_init()
+        .map_err(|e| ErrorHandler::config_error(format!("Failed to initialize logging: {e}")))?;
+
+    Ok(LogFileGuard)
+}
+
+fn open_log_file(path: &str) -> Result<(), ErrorHandler> {
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    // Full-detail logs carry challenge and token details; keep them to the owner.
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, crate::util::FileMode::Private.bits());
+    let file = options
+        .open(path)
+        .map_err(|e| ErrorHandler::config_error(
+            format!("Cannot open log file '{path}' for writing: {e}")
+        ))?;
+
+    *lock_log_file() = Some(BufWriter::new(file));
+    Ok(())
+}
+
+/// Flushes the debug log file when dropped.
+///
+/// Keep this alive for the lifetime of `main`.
+pub struct LogFileGuard;
+
+impl Drop for LogFileGuard {
+    fn drop(&mut self) {
+        flush();
+    }
+}
+
+/// Flushes any buffered debug log output.
+///
+/// Must be called before `std::process::exit`,
+/// which skips the [`LogFileGuard`] destructor.
+pub fn flush() {
+    if let Some(writer) = lock_log_file().as_mut() {
+        let _ = writer.flush();
+    }
+}
+
+fn lock_log_file() -> MutexGuard<'static, Option<BufWriter<File>>> {
+    // A panic while logging must not disable logging for everyone else.
+    LOG_FILE.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// `io::Write` handle onto the shared debug log file,
+/// used as the writer for the JSON file layer.
+struct LogFileWriter;
+
+impl Write for LogFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        if let Some(writer) = lock_log_file().as_mut() {
+            writer.write_all(crate::redact::redact(&text).as_bytes())?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match lock_log_file().as_mut() {
+            Some(writer) => writer.flush(),
+            None         => Ok(()),
+        }
+    }
+}
+
+/// `io::Write` handle onto the console stream, used as the writer
+/// for the JSON console layer so that it honors `--log-stdout`
+/// and console capture like the text layer does.
+struct ConsoleWriter;
+
+impl Write for ConsoleWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        write_inline(format_args!("{}", crate::redact::redact(&text)));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A console line captured for display inside the TUI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogRecord {
+    /// The verbose category, or `None` for status lines and section headers.
+    pub category: Option<LogCategory>,
+    /// The message, without the category label.
+    pub message:  String,
+}
+
+/// Redirects console output into a channel until [`release_console`].
+///
+/// The debug log file is unaffected and keeps receiving every event.
+///
+/// # Returns
+/// * `UnboundedReceiver<LogRecord>`: The captured console lines.
+pub fn capture_console() -> UnboundedReceiver<LogRecord> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    *lock_console_capture() = Some(tx);
+    rx
+}
+
+/// Restores normal console output after [`capture_console`].
+pub fn release_console() {
+    *lock_console_capture() = None;
+}
+
+fn lock_console_capture() -> MutexGuard<'static, Option<UnboundedSender<LogRecord>>> {
+    CONSOLE_CAPTURE.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Sends `record` to the capture channel if one is installed.
+///
+/// # Returns
+/// * `bool`: Whether the record was captured; if not,
+///           the caller should write it to the console.
+fn capture(record: impl FnOnce() -> LogRecord) -> bool {
+    match lock_console_capture().as_ref() {
+        Some(tx) => {
+            let _ = tx.send(record());
+            true
+        }
+        None => false,
+    }
+}
+
+fn write_file_line(args: fmt::Arguments) {
+    let elapsed = PROCESS_START.get_or_init(Instant::now).elapsed();
+    if let Some(writer) = lock_log_file().as_mut() {
+        let _ = writeln!(writer, "{}{args}", format_prefix(LogTimestamps::Clock, Utc::now(), elapsed));
+    }
+}
+
+fn write_console_line(args: fmt::Arguments) {
+    let mode = LogTimestamps::from_u8(TIMESTAMPS.load(Ordering::Relaxed));
+    if mode == LogTimestamps::None {
+        write_line(args);
+        return;
+    }
+
+    let elapsed = PROCESS_START.get_or_init(Instant::now).elapsed();
+    write_line(format_args!("{}{args}", format_prefix(mode, Utc::now(), elapsed)));
+}
+
+/// Where a [`TextLayer`] writes.
+enum Sink {
+    Console,
+    File,
+}
+
+/// Renders events in the labelled text format used since
+/// before the move to `tracing`.
+struct TextLayer {
+    sink: Sink,
+}
+
+impl<S: Subscriber> Layer<S> for TextLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut fields = EventFields::default();
+        event.record(&mut fields);
+
+        // Call sites pass their config's `verbose` flag along so a
+        // non-verbose config still keeps its lines off the console.
+        if matches!(self.sink, Sink::Console) && fields.verbose == Some(false) {
+            return;
+        }
+
+        let metadata = event.metadata();
+        let category = fields.category
+            .as_deref()
+            .and_then(|name| LogCategory::ALL.into_iter().find(|c| c.name() == name));
+        let message = &crate::redact::redact(&fields.message).into_owned();
+
+        // The TUI renders labels itself, so captured records carry the category.
+        if matches!(self.sink, Sink::Console) && capture(|| {
+            let message = match (fields.kind.as_deref(), category) {
+                (Some("section"), _) => format!("== {message} =="),
+                (_, Some(_)) => message.clone(),
+                (_, None) if metadata.target().starts_with("ironshield::") => message.clone(),
+                (_, None) => format!("{} {}: {message}", metadata.level(), metadata.target()),
+            };
+            LogRecord { category, message }
+        }) {
+            return;
+        }
+
+        match (&self.sink, fields.kind.as_deref(), category) {
+            (Sink::Console, Some("section"), _) => {
+                let [title, rule] = crate::display::glyphs().section_lines(message);
+                write_line(format_args!(""));
+                write_console_line(format_args!("{title}"));
+                write_console_line(format_args!("{rule}"));
+            }
+            (Sink::File, Some("section"), _) => {
+                write_file_line(format_args!("== {message} =="));
+            }
+            (Sink::Console, _, _) if metadata.target() == STATUS_TARGET => {
+                write_line(format_args!("{message}"));
+            }
+            (sink, _, category) => {
+                let line = match category {
+                    Some(category) if matches!(sink, Sink::Console) && COLOR.load(Ordering::Relaxed) => {
+                        format!("{}: {message}", style(category.label()).with(category.color()))
+                    }
+                    Some(category) => format!("{}: {message}", category.label()),
+                    None if metadata.target().starts_with("ironshield::") => message.clone(),
+                    None => format!("{} {}: {message}", metadata.level(), metadata.target()),
+                };
+
+                match sink {
+                    Sink::Console => write_console_line(format_args!("{line}")),
+                    Sink::File    => write_file_line(format_args!("{line}")),
+                }
+            }
+        }
+    }
+}
+
+/// The fields of an event that the [`TextLayer`] cares about.
+#[derive(Default)]
+struct EventFields {
+    message:  String,
+    category: Option<String>,
+    kind:     Option<String>,
+    verbose:  Option<bool>,
+}
+
+impl Visit for EventFields {
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        if field.name() == "verbose" {
+            self.verbose = Some(value);
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "category" => self.category = Some(value.to_string()),
+            "kind"     => self.kind = Some(value.to_string()),
+            _          => self.record_debug(field, &value),
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        } else {
+            // Fields from third-party events enabled through `RUST_LOG`.
+            self.message.push_str(&format!(" {}={value:?}", field.name()));
+        }
+    }
+}
+
+/// Emits a verbose log line.
+///
+/// # Arguments
+/// * `console`: Whether the line should appear on the console,
+///              normally the config's `verbose` flag.
+/// * `args`:    The pre-formatted message.
+pub fn log_line(console: bool, args: fmt::Arguments) {
+    tracing::debug!(target: DETAIL_TARGET, verbose = console, "{args}");
+}
+
+/// Emits a categorized verbose log line. The console only shows it if
+/// its category passes the active filter; the debug log file always
+/// receives it.
+///
+/// # Arguments
+/// * `console`:  Whether the line may appear on the console,
+///               normally the config's `verbose` flag.
+/// * `category`: The category of the message.
+/// * `args`:     The pre-formatted message.
+pub fn log_event(console: bool, category: LogCategory, args: fmt::Arguments) {
+    // Targets must be constants, hence one call site per category.
+    match category {
+        LogCategory::Compute => tracing::debug!(target: "ironshield::verbose::compute", category = "compute", verbose = console, "{args}"),
+        LogCategory::Error   => tracing::error!(target: "ironshield::verbose::error",   category = "error",   verbose = console, "{args}"),
+        LogCategory::Info    => tracing::debug!(target: "ironshield::verbose::info",    category = "info",    verbose = console, "{args}"),
+        LogCategory::Receive => tracing::debug!(target: "ironshield::verbose::receive", category = "receive", verbose = console, "{args}"),
+        LogCategory::Success => tracing::debug!(target: "ironshield::verbose::success", category = "success", verbose = console, "{args}"),
+        LogCategory::Submit  => tracing::debug!(target: "ironshield::verbose::submit",  category = "submit",  verbose = console, "{args}"),
+        LogCategory::Network => tracing::debug!(target: "ironshield::verbose::network", category = "network", verbose = console, "{args}"),
+        LogCategory::Timing  => tracing::debug!(target: "ironshield::verbose::timing",  category = "timing",  verbose = console, "{args}"),
+        LogCategory::Warning => tracing::warn!(target: "ironshield::verbose::warning",  category = "warning", verbose = console, "{args}"),
+    }
+}
+
+/// Emits a section header for a group of verbose log lines.
+///
+/// # Arguments
+/// * `console`: Whether the header should appear on the console.
+/// * `title`:   The section title.
+pub fn log_section(console: bool, title: fmt::Arguments) {
+    tracing::debug!(target: DETAIL_TARGET, kind = "section", verbose = console, "{title}");
+}
+
+/// Emits a status line that is always shown on the
+/// console and also recorded in the debug log file.
+///
+/// # Arguments
+/// * `args`: The pre-formatted message.
+pub fn status_line(args: fmt::Arguments) {
+    tracing::info!(target: STATUS_TARGET, "{args}");
+}
+
+/// Emits a line only the debug log file receives, for detail
+/// that is too noisy for the console even in verbose mode.
+///
+/// # Arguments
+/// * `category`: The category of the message.
+/// * `args`:     The pre-formatted message.
+pub fn file_event(category: LogCategory, args: fmt::Arguments) {
+    tracing::trace!(target: FILE_TARGET, category = category.name(), "{args}");
+}
+
+/// Writes a diagnostic line followed by a newline.
+///
+/// Diagnostics go to stderr so that stdout only ever
+/// carries command results and stays safe to pipe.
+///
+/// # Arguments
+/// * `args`: The pre-formatted message.
+pub fn write_line(args: fmt::Arguments) {
+    if capture(|| LogRecord { category: None, message: args.to_string() }) {
+        return;
+    }
+
+    if LOG_TO_STDOUT.load(Ordering::Relaxed) {
+        let _ = writeln!(io::stdout().lock(), "{args}");
+    } else {
+        let _ = writeln!(io::stderr().lock(), "{args}");
+    }
+}
+
+/// Writes diagnostic output without a trailing newline
+/// and flushes immediately.
+///
+/// While the console is captured, complete lines are forwarded
+/// and partial output such as the spinner is dropped.
+///
+/// # Arguments
+/// * `args`: The pre-formatted message.
+pub fn write_inline(args: fmt::Arguments) {
+    let text = args.to_string();
+    if let Some(line) = text.strip_suffix('\n') {
+        if capture(|| LogRecord { category: None, message: line.to_string() }) {
+            return;
+        }
+    } else if lock_console_capture().is_some() {
+        return;
+    }
+
+    if LOG_TO_STDOUT.load(Ordering::Relaxed) {
+        let mut out = io::stdout().lock();
+        let _ = write!(out, "{args}");
+        let _ = out.flush();
+    } else {
+        let mut err = io::stderr().lock();
+        let _ = write!(err, "{args}");
+        let _ = err.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_category_set_parses_list() {
+        let filter: CategorySet = "network, timing".parse().unwrap();
+
+        assert!(filter.contains(LogCategory::Network));
+        assert!(filter.contains(LogCategory::Timing));
+        assert!(!filter.contains(LogCategory::Compute));
+        assert_eq!(filter.to_string(), "network,timing");
+    }
+
+    #[test]
+    fn test_category_set_always_includes_problems() {
+        let filter: CategorySet = "timing".parse().unwrap();
+
+        assert!(filter.contains(LogCategory::Error));
+        assert!(filter.contains(LogCategory::Warning));
+    }
+
+    #[test]
+    fn test_category_set_toggle() {
+        let mut filter: CategorySet = "network,timing".parse().unwrap();
+        filter.toggle(LogCategory::Network);
+        filter.toggle(LogCategory::Compute);
+
+        assert_eq!(filter.to_string(), "compute,timing");
+
+        filter.toggle(LogCategory::Compute);
+        filter.toggle(LogCategory::Timing);
+        assert_eq!(filter.to_string(), "none");
+    }
+
+    #[test]
+    fn test_category_set_all_and_unknown() {
+        assert_eq!("all".parse::<CategorySet>().unwrap(), CategorySet::ALL);
+        assert!("network,bogus".parse::<CategorySet>().is_err());
+        assert!("".parse::<CategorySet>().is_err());
+    }
+
+    #[test]
+    fn test_console_directives_default_shows_status_only() {
+        assert_eq!(
+            console_directives(false, false, CategorySet::ALL, None),
+            "ironshield::status=info"
+        );
+    }
+
+    #[test]
+    fn test_console_directives_follow_category_filter() {
+        let filter: CategorySet = "network".parse().unwrap();
+        let directives = console_directives(true, false, filter, None);
+
+        assert!(directives.contains("ironshield::verbose::network=trace"));
+        assert!(directives.contains("ironshield::verbose::error=trace"));
+        assert!(directives.contains("ironshield::verbose::warning=trace"));
+        assert!(!directives.contains("ironshield::verbose::compute"));
+        assert!(EnvFilter::builder().parse(&directives).is_ok());
+    }
+
+    #[test]
+    fn test_console_directives_rust_log_takes_over() {
+        let directives = console_directives(true, false, CategorySet::ALL, Some("reqwest=debug"));
+        assert_eq!(directives, "ironshield::status=info,reqwest=debug");
+    }
+
+    #[test]
+    fn test_console_directives_quiet_hides_status() {
+        assert_eq!(console_directives(false, true, CategorySet::ALL, None), "off");
+    }
+
+    #[test]
+    fn test_color_choice_resolution() {
+        assert!(ColorChoice::Auto.resolve(true, None));
+        assert!(ColorChoice::Auto.resolve(true, Some("")));
+        assert!(!ColorChoice::Auto.resolve(true, Some("1")));
+        assert!(!ColorChoice::Auto.resolve(false, None));
+        assert!(ColorChoice::Always.resolve(false, Some("1")));
+        assert!(!ColorChoice::Never.resolve(true, None));
+    }
+
+    #[test]
+    fn test_format_prefix_none_is_empty() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 2, 3, 4, 5).unwrap();
+        assert_eq!(format_prefix(LogTimestamps::None, now, Duration::ZERO), "");
+    }
+
+    #[test]
+    fn test_format_prefix_clock_is_iso8601() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 2, 3, 4, 5).unwrap()
+            + chrono::Duration::milliseconds(67);
+        assert_eq!(
+            format_prefix(LogTimestamps::Clock, now, Duration::ZERO),
+            "2025-01-02T03:04:05.067Z "
+        );
+    }
+
+    #[test]
+    fn test_format_prefix_elapsed_has_fixed_width() {
+        let now = Utc::now();
+        let short = format_prefix(LogTimestamps::Elapsed, now, Duration::from_millis(12_345));
+        let long = format_prefix(LogTimestamps::Elapsed, now, Duration::from_millis(4_512_345));
+
+        assert_eq!(short, "   +12.345s ");
+        assert_eq!(long, " +4512.345s ");
+        assert_eq!(short.len(), long.len());
+    }
+}
@@ -0,0 +1,314 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::display::format_duration;
+use crate::history::{HistoryStore, RunRecord};
+use crate::warnings::{Warning, WarningCode};
+
+/// Past submits to the same endpoint the latency estimate is the median of.
+const LATENCY_SAMPLES: usize = 20;
+
+/// The `[margin]` section of the configuration file.
+///
+/// ```toml
+/// [margin]
+/// slack = "1s"
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarginConfig {
+    /// Submit margins under this are warned about, and fetched again
+    /// under `--auto-refresh-on-thin-margin`.
+    pub slack: String,
+}
+
+impl Default for MarginConfig {
+    fn default() -> Self {
+        Self { slack: "500ms".to_string() }
+    }
+}
+
+/// [`MarginConfig`] with its duration parsed, and whether to fetch a
+/// fresh challenge rather than submit on a thin margin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarginPolicy {
+    pub slack:        Duration,
+    pub auto_refresh: bool,
+}
+
+impl Default for MarginPolicy {
+    fn default() -> Self {
+        Self { slack: Duration::from_millis(500), auto_refresh: false }
+    }
+}
+
+impl MarginPolicy {
+    /// # Arguments
+    /// * `config`:       The config section.
+    /// * `auto_refresh`: `--auto-refresh-on-thin-margin`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The policy, or why `slack` is invalid.
+    pub fn from_config(config: &MarginConfig, auto_refresh: bool) -> Result<Self, String> {
+        let slack = crate::display::parse_duration(&config.slack).map_err(|e| format!("Invalid `margin.slack`: {e}"))?;
+        Ok(Self { slack, auto_refresh })
+    }
+}
+
+static POLICY: OnceLock<MarginPolicy> = OnceLock::new();
+
+/// Sets the policy for the rest of the process.
+pub fn set_policy(policy: MarginPolicy) {
+    let _ = POLICY.set(policy);
+}
+
+/// The policy set at startup, or the defaults.
+pub fn policy() -> MarginPolicy {
+    POLICY.get().copied().unwrap_or_default()
+}
+
+/// Where the submit latency estimate came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LatencySource {
+    /// The median of past submits to the same endpoint.
+    History,
+    /// This run's challenge fetch, one round trip to the same API.
+    FetchRoundTrip,
+    /// Nothing measured; the estimate is zero.
+    Unknown,
+}
+
+/// How long the submit is expected to take.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LatencyEstimate {
+    pub ms:      u64,
+    pub source:  LatencySource,
+    /// Past submits behind a [`LatencySource::History`] estimate.
+    pub samples: usize,
+}
+
+impl LatencyEstimate {
+    /// # Arguments
+    /// * `past_submit_ms`: Earlier submits to the endpoint, oldest first.
+    /// * `fetch_ms`:       This run's fetch, when there is no history.
+    pub fn new(past_submit_ms: &[u64], fetch_ms: Option<u64>) -> Self {
+        let recent = &past_submit_ms[past_submit_ms.len().saturating_sub(LATENCY_SAMPLES)..];
+        if !recent.is_empty() {
+            let mut sorted = recent.to_vec();
+            sorted.sort_unstable();
+            return Self { ms: sorted[sorted.len() / 2], source: LatencySource::History, samples: sorted.len() };
+        }
+        match fetch_ms {
+            Some(ms) => Self { ms, source: LatencySource::FetchRoundTrip, samples: 0 },
+            None     => Self { ms: 0, source: LatencySource::Unknown, samples: 0 },
+        }
+    }
+
+    /// e.g. "the median of 12 past submits".
+    fn describe_source(&self) -> String {
+        match self.source {
+            LatencySource::History        => format!("the median of {} past submit(s)", self.samples),
+            LatencySource::FetchRoundTrip => "this run's fetch".to_string(),
+            LatencySource::Unknown        => "nothing measured".to_string(),
+        }
+    }
+}
+
+/// How much of the challenge's validity is left for the submit: its
+/// expiry minus the end of the solve and the expected submit latency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubmitMargin {
+    /// The challenge's expiry, in Unix milliseconds.
+    pub expires_at_ms: i64,
+    /// When the solution was ready to submit, in Unix milliseconds.
+    pub solve_end_ms:  i64,
+    pub latency:       LatencyEstimate,
+    pub slack:         Duration,
+    /// When the submit's answer came back, once it has.
+    pub landed_at_ms:  Option<i64>,
+}
+
+impl SubmitMargin {
+    /// Validity left when the solve ended.
+    pub fn left_at_solve_end_ms(&self) -> i64 {
+        self.expires_at_ms - self.solve_end_ms
+    }
+
+    /// What is expected to be left when the submit lands; negative if
+    /// it is expected to land after the expiry.
+    pub fn margin_ms(&self) -> i64 {
+        self.left_at_solve_end_ms() - self.latency.ms as i64
+    }
+
+    /// What was actually left when the submit's answer came back.
+    pub fn landed_margin_ms(&self) -> Option<i64> {
+        self.landed_at_ms.map(|at| self.expires_at_ms - at)
+    }
+
+    /// Whether the margin is under the slack, or negative.
+    pub fn is_thin(&self) -> bool {
+        self.margin_ms() < self.slack.as_millis() as i64
+    }
+
+    pub fn landed(self, at_ms: i64) -> Self {
+        Self { landed_at_ms: Some(at_ms), ..self }
+    }
+
+    /// e.g. "Submit margin: 1.2s (1.5s left when the solve ended, minus a
+    /// 300ms submit estimated from this run's fetch; slack 500ms)".
+    pub fn describe(&self) -> String {
+        let landed = self.landed_margin_ms().map(|ms| format!("; landed with {}", signed(ms))).unwrap_or_default();
+        format!(
+            "Submit margin: {} ({} left when the solve ended, minus a {} submit estimated from {}; slack {}){landed}",
+            signed(self.margin_ms()),
+            signed(self.left_at_solve_end_ms()),
+            format_duration(Duration::from_millis(self.latency.ms)),
+            self.latency.describe_source(),
+            format_duration(self.slack),
+        )
+    }
+
+    /// e.g. "margin -40ms, landed with -55ms", for failure messages.
+    pub fn brief(&self) -> String {
+        match self.landed_margin_ms() {
+            Some(landed) => format!("submit margin {}, landed with {}", signed(self.margin_ms()), signed(landed)),
+            None         => format!("submit margin {}", signed(self.margin_ms())),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "expires_at_ms":        self.expires_at_ms,
+            "solve_end_ms":         self.solve_end_ms,
+            "left_at_solve_end_ms": self.left_at_solve_end_ms(),
+            "submit_estimate_ms":   self.latency.ms,
+            "estimate_source":      self.latency.source,
+            "estimate_samples":     self.latency.samples,
+            "margin_ms":            self.margin_ms(),
+            "slack_ms":             self.slack.as_millis() as u64,
+            "thin":                 self.is_thin(),
+            "landed_margin_ms":     self.landed_margin_ms(),
+        })
+    }
+
+    /// The W016 warning for a thin margin.
+    pub fn warning(&self) -> Warning {
+        let risk = match self.margin_ms() {
+            ms if ms < 0 => "the submit is expected to land after the challenge expires",
+            _            => "the submit may land after the challenge expires",
+        };
+        Warning::new(WarningCode::ThinMargin, format!("{}; {risk}", self.describe())).with_data(self.to_json())
+    }
+
+    /// Stores the margins on `record`.
+    pub fn record(&self, record: &mut RunRecord) {
+        record.submit_margin_ms = Some(self.margin_ms());
+        record.landed_margin_ms = self.landed_margin_ms();
+    }
+}
+
+/// The margin for submitting now a solution to a challenge expiring at
+/// `expires_at_ms`, fetched from `record`'s endpoint.
+pub fn assess(expires_at_ms: i64, record: &RunRecord) -> SubmitMargin {
+    let past = past_submits(&record.endpoint);
+    SubmitMargin {
+        expires_at_ms,
+        solve_end_ms: chrono::Utc::now().timestamp_millis(),
+        latency:      LatencyEstimate::new(&past, record.fetch_ms),
+        slack:        policy().slack,
+        landed_at_ms: None,
+    }
+}
+
+/// Submit times recorded in the history for `endpoint`, oldest first.
+/// The history is read once per process.
+fn past_submits(endpoint: &str) -> Vec<u64> {
+    static PAST: OnceLock<Vec<(String, u64)>> = OnceLock::new();
+    let past = PAST.get_or_init(|| {
+        let records = crate::history::is_enabled()
+            .then(HistoryStore::open_default)
+            .flatten()
+            .and_then(|store| store.load().ok())
+            .unwrap_or_default();
+        records.into_iter().filter_map(|record| Some((record.endpoint, record.submit_ms?))).collect()
+    });
+    past.iter().filter(|(past, _)| past == endpoint).map(|&(_, ms)| ms).collect()
+}
+
+/// `ms` as a duration, with a minus sign when negative.
+fn signed(ms: i64) -> String {
+    let length = format_duration(Duration::from_millis(ms.unsigned_abs()));
+    if ms < 0 { format!("-{length}") } else { length }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn margin(left_ms: i64, latency_ms: u64) -> SubmitMargin {
+        SubmitMargin {
+            expires_at_ms: 1_700_000_000_000 + left_ms,
+            solve_end_ms:  1_700_000_000_000,
+            latency:       LatencyEstimate { ms: latency_ms, source: LatencySource::FetchRoundTrip, samples: 0 },
+            slack:         Duration::from_millis(500),
+            landed_at_ms:  None,
+        }
+    }
+
+    #[test]
+    fn test_latency_estimate_prefers_history() {
+        let from_history = LatencyEstimate::new(&[500, 90, 110, 100], Some(40));
+        assert_eq!(from_history, LatencyEstimate { ms: 110, source: LatencySource::History, samples: 4 });
+
+        // Only the most recent submits count.
+        let mut past = vec![10_000; 30];
+        past.extend([100; LATENCY_SAMPLES]);
+        assert_eq!(LatencyEstimate::new(&past, None).ms, 100);
+
+        assert_eq!(LatencyEstimate::new(&[], Some(40)), LatencyEstimate { ms: 40, source: LatencySource::FetchRoundTrip, samples: 0 });
+        assert_eq!(LatencyEstimate::new(&[], None).source, LatencySource::Unknown);
+    }
+
+    #[test]
+    fn test_margin_math() {
+        let comfortable = margin(1_500, 300);
+        assert_eq!(comfortable.margin_ms(), 1_200);
+        assert!(!comfortable.is_thin());
+
+        let thin = margin(700, 300);
+        assert_eq!(thin.margin_ms(), 400);
+        assert!(thin.is_thin());
+
+        let lost = margin(200, 300).landed(1_700_000_000_000 + 255);
+        assert_eq!(lost.margin_ms(), -100);
+        assert_eq!(lost.landed_margin_ms(), Some(-55));
+        assert!(lost.is_thin());
+    }
+
+    #[test]
+    fn test_describe_and_json() {
+        let lost = margin(200, 300).landed(1_700_000_000_000 + 255);
+        assert_eq!(
+            lost.describe(),
+            "Submit margin: -100ms (200ms left when the solve ended, minus a 300ms submit estimated from this run's fetch; \
+             slack 500ms); landed with -55ms",
+        );
+        assert_eq!(lost.brief(), "submit margin -100ms, landed with -55ms");
+        assert_eq!(lost.to_json()["margin_ms"], -100);
+        assert_eq!(lost.to_json()["estimate_source"], "fetch_round_trip");
+        assert_eq!(lost.to_json()["thin"], true);
+        assert!(lost.warning().message.ends_with("the submit is expected to land after the challenge expires"));
+    }
+
+    #[test]
+    fn test_policy_from_config() {
+        assert_eq!(MarginPolicy::from_config(&MarginConfig::default(), false).unwrap(), MarginPolicy::default());
+
+        let config = MarginConfig { slack: "soon".to_string() };
+        assert!(MarginPolicy::from_config(&config, false).unwrap_err().starts_with("Invalid `margin.slack`"));
+    }
+}
@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::output::OutputSink;
+use crate::warnings::{Warning, WarningCode};
+
+/// Advisory fields the API may send next to the challenge:
+///
+/// ```json
+/// { "challenge": { ... }, "metadata": { "suggested_threads": 4, "load_tier": "high" } }
+/// ```
+///
+/// Every field is optional; keys this version doesn't know are kept
+/// in `other` and shown at verbose level.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChallengeMetadata {
+    /// Threads the server suggests solving on.
+    pub suggested_threads: Option<usize>,
+    /// How busy the API is, e.g. `normal` or `high`.
+    pub load_tier:         Option<String>,
+    /// Planned maintenance or other notices for operators.
+    pub maintenance:       Option<String>,
+    #[serde(flatten)]
+    pub other:             Map<String, Value>,
+}
+
+impl ChallengeMetadata {
+    /// The `metadata` object of an API response envelope.
+    ///
+    /// # Returns
+    /// * `Option<Self>`: `None` when the envelope has no `metadata`
+    ///                   object, so runs against older APIs are unchanged.
+    pub fn from_envelope(envelope: &Value) -> Option<Self> {
+        let metadata = envelope.get("metadata")?;
+        if !metadata.is_object() {
+            return None;
+        }
+        serde_json::from_value(metadata.clone()).ok()
+    }
+
+    /// The thread count to solve on: the server's suggestion, unless
+    /// `--threads` pinned one.
+    ///
+    /// # Arguments
+    /// * `pinned`: `--threads`, if given.
+    ///
+    /// # Returns
+    /// * `Option<usize>`: A count to use instead of the strategy's, if any.
+    pub fn thread_hint(&self, pinned: Option<usize>) -> Option<usize> {
+        match pinned {
+            Some(_) => None,
+            None    => self.suggested_threads.filter(|&threads| threads > 0),
+        }
+    }
+
+    /// Shows the known fields as status lines and the rest as verbose
+    /// key-value lines, then the whole object as a `challenge_metadata`
+    /// metric for JSON consumers.
+    pub(crate) fn report(&self, sink: &dyn OutputSink) {
+        if let Some(notice) = &self.maintenance {
+            sink.warning(
+                &Warning::new(WarningCode::ServerNotice, format!("Server notice: {notice}"))
+                    .with_data(serde_json::json!({ "maintenance": notice })),
+            );
+        }
+        if let Some(tier) = &self.load_tier {
+            sink.info(&format!("Server load: {tier}"));
+        }
+        if let Some(threads) = self.suggested_threads {
+            sink.kv("Suggested Threads", &threads);
+        }
+        for (key, value) in &self.other {
+            sink.kv(&format!("Metadata {key}"), value);
+        }
+        sink.metric("challenge_metadata", serde_json::json!(self));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::{BufferSink, Record};
+    use serde_json::json;
+
+    #[test]
+    fn test_envelope_without_metadata() {
+        assert_eq!(ChallengeMetadata::from_envelope(&json!({ "challenge": {} })), None);
+        assert_eq!(ChallengeMetadata::from_envelope(&json!({ "challenge": {}, "metadata": null })), None);
+    }
+
+    #[test]
+    fn test_envelope_with_metadata() {
+        let envelope = json!({
+            "challenge": {},
+            "metadata": { "suggested_threads": 4, "load_tier": "high", "region": "eu-west" },
+        });
+        let metadata = ChallengeMetadata::from_envelope(&envelope).unwrap();
+
+        assert_eq!(metadata.suggested_threads, Some(4));
+        assert_eq!(metadata.load_tier.as_deref(), Some("high"));
+        assert_eq!(metadata.maintenance, None);
+        assert_eq!(metadata.other.get("region"), Some(&json!("eu-west")));
+    }
+
+    #[test]
+    fn test_pinned_threads_win() {
+        let metadata = ChallengeMetadata { suggested_threads: Some(4), ..ChallengeMetadata::default() };
+
+        assert_eq!(metadata.thread_hint(None), Some(4));
+        assert_eq!(metadata.thread_hint(Some(2)), None);
+        assert_eq!(ChallengeMetadata { suggested_threads: Some(0), ..metadata }.thread_hint(None), None);
+    }
+
+    #[test]
+    fn test_report() {
+        let metadata = ChallengeMetadata::from_envelope(&json!({
+            "metadata": { "maintenance": "read-only from 02:00 UTC", "region": "eu-west" },
+        })).unwrap();
+        let sink = BufferSink::default();
+
+        metadata.report(&sink);
+
+        assert_eq!(sink.records(), [
+            Record::Warning(
+                Warning::new(WarningCode::ServerNotice, "Server notice: read-only from 02:00 UTC")
+                    .with_data(json!({ "maintenance": "read-only from 02:00 UTC" })),
+            ),
+            Record::Kv("Metadata region".to_string(), "\"eu-west\"".to_string()),
+            Record::Metric(
+                "challenge_metadata".to_string(),
+                json!({ "suggested_threads": null, "load_tier": null, "maintenance": "read-only from 02:00 UTC", "region": "eu-west" }),
+            ),
+        ]);
+    }
+}
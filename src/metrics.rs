@@ -0,0 +1,408 @@
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use std::fmt::Write as _;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::{Mutex, OnceLock};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::history::{RunOutcome, RunRecord};
+use crate::logging::{LogCategory, log_event};
+
+/// Prefix of every StatsD metric name.
+const STATSD_PREFIX: &str = "ironshield";
+
+/// Hex digits of the SHA-256 kept when hashing endpoint tags.
+const ENDPOINT_HASH_LEN: usize = 12;
+
+/// Upper bounds of the solve duration buckets, in seconds.
+const SOLVE_BUCKETS: [f64; 10] = [0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0];
+
+/// Upper bounds of the API request duration buckets, in seconds.
+const REQUEST_BUCKETS: [f64; 8] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// A Prometheus histogram of durations, updated with atomics only.
+struct Histogram<const N: usize> {
+    bounds:     [f64; N],
+    /// Observations at or below each bound; `+Inf` is `count`.
+    buckets:    [AtomicU64; N],
+    count:      AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl<const N: usize> Histogram<N> {
+    const fn new(bounds: [f64; N]) -> Self {
+        Self {
+            bounds,
+            buckets:    [const { AtomicU64::new(0) }; N],
+            count:      AtomicU64::new(0),
+            sum_micros: AtomicU64::new(0),
+        }
+    }
+
+    fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        for (bound, bucket) in self.bounds.iter().zip(&self.buckets) {
+            if seconds <= *bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros.fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Appends the `_bucket`, `_sum` and `_count` series, with
+    /// `labels` (e.g. `request="fetch"`) on each.
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let separator = if labels.is_empty() { "" } else { "," };
+        let braces = if labels.is_empty() { String::new() } else { format!("{{{labels}}}") };
+        for (bound, bucket) in self.bounds.iter().zip(&self.buckets) {
+            let _ = writeln!(out, "{name}_bucket{{{labels}{separator}le=\"{bound}\"}} {}", bucket.load(Ordering::Relaxed));
+        }
+        let count = self.count.load(Ordering::Relaxed);
+        let sum = self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let _ = writeln!(out, "{name}_bucket{{{labels}{separator}le=\"+Inf\"}} {count}");
+        let _ = writeln!(out, "{name}_sum{braces} {sum}");
+        let _ = writeln!(out, "{name}_count{braces} {count}");
+    }
+}
+
+static CHALLENGES_FETCHED:   AtomicU64 = AtomicU64::new(0);
+/// Fetched challenges that expired before a solver took them.
+static CHALLENGES_REFETCHED: AtomicU64 = AtomicU64::new(0);
+static SOLVES_SUCCEEDED:     AtomicU64 = AtomicU64::new(0);
+static SOLVES_FAILED:        AtomicU64 = AtomicU64::new(0);
+static SOLVE_DURATION:       Histogram<10> = Histogram::new(SOLVE_BUCKETS);
+static FETCH_DURATION:       Histogram<8> = Histogram::new(REQUEST_BUCKETS);
+static SUBMIT_DURATION:      Histogram<8> = Histogram::new(REQUEST_BUCKETS);
+/// Average hash rate of the last successful solve.
+static HASH_RATE:            AtomicU64 = AtomicU64::new(0);
+/// `valid_for` of the last token, in Unix milliseconds; 0 before any.
+static TOKEN_VALID_FOR:      AtomicI64 = AtomicI64::new(0);
+/// Proxy refreshes passed over for being above `--max-renewal-difficulty`.
+static RENEWALS_SKIPPED:     AtomicU64 = AtomicU64::new(0);
+/// The stages of `validate` runs, in the order they were first seen.
+static VALIDATE_STAGES:      Mutex<Vec<(&'static str, Histogram<10>)>> = Mutex::new(Vec::new());
+
+/// Records a completed challenge request.
+pub fn record_fetch(elapsed: Duration) {
+    CHALLENGES_FETCHED.fetch_add(1, Ordering::Relaxed);
+    FETCH_DURATION.observe(elapsed);
+}
+
+/// Records a fetched challenge that expired while queued and had to be fetched again.
+pub fn record_refetch() {
+    CHALLENGES_REFETCHED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Records a successful solve and its average hashes per second.
+pub fn record_solve_success(elapsed: Duration, hash_rate: u64) {
+    SOLVE_DURATION.observe(elapsed);
+    SOLVES_SUCCEEDED.fetch_add(1, Ordering::Relaxed);
+    HASH_RATE.store(hash_rate, Ordering::Relaxed);
+}
+
+/// Records a solve that failed after `elapsed`.
+pub fn record_solve_failure(elapsed: Duration) {
+    SOLVE_DURATION.observe(elapsed);
+    SOLVES_FAILED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Records a completed solution submission and the token it returned.
+pub fn record_submit(elapsed: Duration, token_valid_for: i64) {
+    SUBMIT_DURATION.observe(elapsed);
+    TOKEN_VALID_FOR.store(token_valid_for, Ordering::Relaxed);
+}
+
+/// Records how long each stage of a `validate` run took.
+///
+/// # Arguments
+/// * `stages`: Each stage's name, e.g. `local_verify`, and duration.
+pub fn record_validate_stages(stages: &[(&'static str, Duration)]) {
+    let mut histograms = VALIDATE_STAGES.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    for &(stage, elapsed) in stages {
+        match histograms.iter().position(|(name, _)| *name == stage) {
+            Some(index) => histograms[index].1.observe(elapsed),
+            None => {
+                let histogram = Histogram::new(SOLVE_BUCKETS);
+                histogram.observe(elapsed);
+                histograms.push((stage, histogram));
+            }
+        }
+    }
+}
+
+/// Records a token refresh skipped because the challenge was too hard.
+pub fn record_renewal_skipped() {
+    RENEWALS_SKIPPED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Renders every metric in the Prometheus text exposition format.
+///
+/// # Arguments
+/// * `now_ms`: The current Unix time in milliseconds, for `token_expiry_seconds`.
+pub fn render(now_ms: i64) -> String {
+    let mut out = String::new();
+
+    out.push_str("# HELP challenges_fetched_total Challenges fetched from the API.\n");
+    out.push_str("# TYPE challenges_fetched_total counter\n");
+    let _ = writeln!(out, "challenges_fetched_total {}", CHALLENGES_FETCHED.load(Ordering::Relaxed));
+
+    out.push_str("# HELP challenges_refetched_total Challenges that expired while queued and were fetched again.\n");
+    out.push_str("# TYPE challenges_refetched_total counter\n");
+    let _ = writeln!(out, "challenges_refetched_total {}", CHALLENGES_REFETCHED.load(Ordering::Relaxed));
+
+    out.push_str("# HELP solves_total Solves by outcome.\n");
+    out.push_str("# TYPE solves_total counter\n");
+    let _ = writeln!(out, "solves_total{{outcome=\"success\"}} {}", SOLVES_SUCCEEDED.load(Ordering::Relaxed));
+    let _ = writeln!(out, "solves_total{{outcome=\"failure\"}} {}", SOLVES_FAILED.load(Ordering::Relaxed));
+
+    out.push_str("# HELP solve_duration_seconds Time spent solving, excluding fetch and submit.\n");
+    out.push_str("# TYPE solve_duration_seconds histogram\n");
+    SOLVE_DURATION.render(&mut out, "solve_duration_seconds", "");
+
+    out.push_str("# HELP hash_rate Average hashes per second of the last successful solve.\n");
+    out.push_str("# TYPE hash_rate gauge\n");
+    let _ = writeln!(out, "hash_rate {}", HASH_RATE.load(Ordering::Relaxed));
+
+    out.push_str("# HELP token_expiry_seconds Seconds until the last token expires; negative once expired.\n");
+    out.push_str("# TYPE token_expiry_seconds gauge\n");
+    let valid_for = TOKEN_VALID_FOR.load(Ordering::Relaxed);
+    let expiry = if valid_for == 0 { 0.0 } else { (valid_for - now_ms) as f64 / 1000.0 };
+    let _ = writeln!(out, "token_expiry_seconds {expiry}");
+
+    out.push_str("# HELP renewals_skipped_total Token refreshes skipped for a challenge over --max-renewal-difficulty.\n");
+    out.push_str("# TYPE renewals_skipped_total counter\n");
+    let _ = writeln!(out, "renewals_skipped_total {}", RENEWALS_SKIPPED.load(Ordering::Relaxed));
+
+    out.push_str("# HELP api_request_duration_seconds IronShield API request latency.\n");
+    out.push_str("# TYPE api_request_duration_seconds histogram\n");
+    FETCH_DURATION.render(&mut out, "api_request_duration_seconds", "request=\"fetch\"");
+    SUBMIT_DURATION.render(&mut out, "api_request_duration_seconds", "request=\"submit\"");
+
+    out.push_str("# HELP validate_stage_duration_seconds Time each stage of a validate run took, and the total.\n");
+    out.push_str("# TYPE validate_stage_duration_seconds histogram\n");
+    for (stage, histogram) in VALIDATE_STAGES.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).iter() {
+        histogram.render(&mut out, "validate_stage_duration_seconds", &format!("stage=\"{stage}\""));
+    }
+
+    out
+}
+
+/// Binds `addr` and serves `GET /metrics` from a background task.
+///
+/// Requests are answered on the async runtime, never on the solver
+/// threads, and reading the metrics only loads atomics.
+///
+/// # Returns
+/// * `io::Result<SocketAddr>`: The bound address, or the bind error.
+pub async fn serve(addr: SocketAddr) -> std::io::Result<SocketAddr> {
+    let listener = TcpListener::bind(addr).await?;
+    let local_addr = listener.local_addr()?;
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(respond(stream));
+        }
+    });
+    Ok(local_addr)
+}
+
+/// Answers one HTTP request and closes the connection.
+async fn respond(mut stream: TcpStream) {
+    let mut request = [0; 1024];
+    let Ok(read) = stream.read(&mut request).await else {
+        return;
+    };
+
+    let request_line = String::from_utf8_lossy(&request[..read]);
+    let response = if request_line.starts_with("GET /metrics ") {
+        let body = render(chrono::Utc::now().timestamp_millis());
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len(),
+        )
+    } else {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    };
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+/// Where `--statsd` sends the metrics of one-shot runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatsdTarget {
+    /// A `host:port` UDP address.
+    pub address:  String,
+    /// Tag runs with the endpoint itself instead of a hash of it.
+    pub raw_tags: bool,
+}
+
+static STATSD: OnceLock<StatsdTarget> = OnceLock::new();
+
+/// Sets the StatsD target for the rest of the process.
+pub fn set_statsd(target: StatsdTarget) {
+    let _ = STATSD.set(target);
+}
+
+/// Encodes a finished run as DogStatsD lines: a run counter plus
+/// solve time, fetch latency, attempts and hash rate where known.
+///
+/// # Arguments
+/// * `record`:   The finished run.
+/// * `raw_tags`: Tag with the endpoint itself rather than its hash.
+pub fn statsd_lines(record: &RunRecord, raw_tags: bool) -> Vec<String> {
+    let outcome = match record.outcome {
+        RunOutcome::Success => "success",
+        RunOutcome::Failure => "failure",
+    };
+    let mut tags = format!(
+        "command:{},outcome:{outcome},endpoint:{}",
+        record.command.name(),
+        endpoint_tag(&record.endpoint, raw_tags),
+    );
+    if let Some(kind) = record.error_kind {
+        let _ = write!(tags, ",error_kind:{}", kind.name());
+    }
+
+    let mut lines = vec![format!("{STATSD_PREFIX}.run:1|c|#{tags}")];
+    let mut push = |name: &str, value: Option<u64>, kind: &str| {
+        if let Some(value) = value {
+            lines.push(format!("{STATSD_PREFIX}.{name}:{value}|{kind}|#{tags}"));
+        }
+    };
+    push("config_load_time", record.config_load_ms, "ms");
+    push("solve_time", record.solve_ms, "ms");
+    push("fetch_latency", record.fetch_ms, "ms");
+    push("local_verify_time", record.local_verify_ms, "ms");
+    push("submit_latency", record.submit_ms, "ms");
+    push("total_time", Some(record.elapsed_ms), "ms");
+    push("attempts", record.attempts, "g");
+    push("hash_rate", record.hash_rate(), "g");
+    lines
+}
+
+/// The endpoint as a tag value: a short SHA-256 so URLs with
+/// secrets in them stay out of the metrics backend, or the
+/// endpoint itself with tag delimiters replaced.
+fn endpoint_tag(endpoint: &str, raw: bool) -> String {
+    if raw {
+        return endpoint.replace([',', '|', '#'], "_");
+    }
+    let digest = Sha256::digest(endpoint.as_bytes());
+    digest.iter().map(|byte| format!("{byte:02x}")).collect::<String>()[..ENDPOINT_HASH_LEN].to_string()
+}
+
+/// Sends `record` to the `--statsd` target, if one is set, as a
+/// single UDP datagram.
+///
+/// Metrics must never get in the way of the run, so failures are
+/// only reported as verbose warnings.
+pub fn send_statsd(record: &RunRecord, verbose: bool) {
+    let Some(target) = STATSD.get() else {
+        return;
+    };
+
+    let payload = statsd_lines(record, target.raw_tags).join("\n");
+    let result = UdpSocket::bind("0.0.0.0:0")
+        .and_then(|socket| socket.send_to(payload.as_bytes(), target.address.as_str()));
+    if let Err(e) = result {
+        log_event(verbose, LogCategory::Warning, format_args!(
+            "Could not send StatsD metrics to '{}': {e}",
+            target.address
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let histogram = Histogram::new([1.0, 5.0]);
+        histogram.observe(Duration::from_millis(500));
+        histogram.observe(Duration::from_secs(3));
+        histogram.observe(Duration::from_secs(10));
+
+        let mut out = String::new();
+        histogram.render(&mut out, "solve_duration_seconds", "");
+        assert!(out.contains("solve_duration_seconds_bucket{le=\"1\"} 1\n"), "{out}");
+        assert!(out.contains("solve_duration_seconds_bucket{le=\"5\"} 2\n"), "{out}");
+        assert!(out.contains("solve_duration_seconds_bucket{le=\"+Inf\"} 3\n"), "{out}");
+        assert!(out.contains("solve_duration_seconds_sum 13.5\n"), "{out}");
+    }
+
+    #[test]
+    fn test_render_exposes_every_metric() {
+        record_fetch(Duration::from_millis(80));
+        record_solve_success(Duration::from_secs(2), 1_000);
+        record_submit(Duration::from_millis(120), 60_000);
+        record_validate_stages(&[("local_verify", Duration::from_millis(1)), ("total", Duration::from_secs(2))]);
+
+        let out = render(30_000);
+        for name in [
+            "challenges_fetched_total",
+            "solves_total{outcome=\"success\"}",
+            "solve_duration_seconds_count",
+            "hash_rate 1000",
+            "token_expiry_seconds 30",
+            "renewals_skipped_total",
+            "api_request_duration_seconds_bucket{request=\"submit\",le=\"0.25\"}",
+            "validate_stage_duration_seconds_count{stage=\"local_verify\"} 1",
+        ] {
+            assert!(out.contains(name), "missing {name} in:\n{out}");
+        }
+    }
+
+    fn finished_run() -> RunRecord {
+        let mut record = RunRecord::new(crate::history::RunCommand::Solve, "https://a.example/x?key=1,2");
+        record.fetch_ms = Some(40);
+        record.solve_ms = Some(500);
+        record.attempts = Some(2_000);
+        record.finish(Duration::from_millis(600), None);
+        record
+    }
+
+    #[test]
+    fn test_statsd_lines() {
+        let lines = statsd_lines(&finished_run(), true);
+        let tags = "#command:solve,outcome:success,endpoint:https://a.example/x?key=1_2";
+
+        assert_eq!(lines, [
+            format!("ironshield.run:1|c|{tags}"),
+            format!("ironshield.solve_time:500|ms|{tags}"),
+            format!("ironshield.fetch_latency:40|ms|{tags}"),
+            format!("ironshield.total_time:600|ms|{tags}"),
+            format!("ironshield.attempts:2000|g|{tags}"),
+            format!("ironshield.hash_rate:4000|g|{tags}"),
+        ]);
+    }
+
+    #[test]
+    fn test_statsd_hashes_endpoints_and_tags_failures() {
+        let mut record = RunRecord::new(crate::history::RunCommand::Fetch, "https://a.example");
+        record.error_kind = Some(crate::history::ErrorKind::Fetch);
+        record.finish(Duration::from_secs(2), Some("timed out".into()));
+
+        let lines = statsd_lines(&record, false);
+        let hash = endpoint_tag("https://a.example", false);
+        assert_eq!(hash.len(), ENDPOINT_HASH_LEN);
+        let tags = format!("#command:fetch,outcome:failure,endpoint:{hash},error_kind:fetch");
+        assert_eq!(lines, [format!("ironshield.run:1|c|{tags}"), format!("ironshield.total_time:2000|ms|{tags}")]);
+    }
+
+    #[tokio::test]
+    async fn test_serve_answers_metrics_requests() {
+        let addr = serve("127.0.0.1:0".parse().unwrap()).await.unwrap();
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+        assert!(response.contains("# TYPE solves_total counter"));
+    }
+}
@@ -0,0 +1,253 @@
+use clap::ValueEnum;
+use serde_json::{json, Value};
+
+use std::fmt::Display;
+
+use crate::api_version::Negotiated;
+use crate::warnings::Warning;
+
+/// Where a command's result and status lines go.
+///
+/// Commands never print directly; they hand everything to a sink so
+/// the same code can drive the console, a JSON consumer or a test.
+pub trait OutputSink: Send + Sync {
+    /// The command's result, e.g. the challenge or the token, with the
+    /// API version negotiated for it, if it came from the API.
+    fn result_json(&self, value: Value, negotiated: Option<Negotiated>);
+    /// A status line, shown whatever the verbosity.
+    fn info(&self, message: &str);
+    /// Something the user should act on, with its code and numbers.
+    fn warning(&self, warning: &Warning);
+    /// A labelled value, shown in verbose mode.
+    fn kv(&self, key: &str, value: &dyn Display);
+    /// A measurement for JSON consumers, e.g. `time_to_first_progress_ms`;
+    /// shown on the console in verbose mode only.
+    fn metric(&self, name: &str, value: Value);
+    /// A header for the verbose lines that follow.
+    fn section(&self, title: &str);
+}
+
+/// How results are printed (`--output`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Pretty JSON results on stdout, status lines on stderr.
+    #[default]
+    Console,
+    /// One JSON record per line on stdout, status lines included.
+    Json,
+}
+
+/// Builds the sink for `format`.
+///
+/// # Arguments
+/// * `format`:  The `--output` format.
+/// * `verbose`: Whether `kv` and `section` records are shown.
+pub fn sink(format: OutputFormat, verbose: bool) -> Box<dyn OutputSink> {
+    match format {
+        OutputFormat::Console => Box::new(ConsoleSink { verbose }),
+        OutputFormat::Json    => Box::new(JsonSink { verbose }),
+    }
+}
+
+/// Prints results to stdout and everything else through the logger,
+/// so status lines land on stderr and in the debug log file.
+pub struct ConsoleSink {
+    pub verbose: bool,
+}
+
+impl OutputSink for ConsoleSink {
+    fn result_json(&self, value: Value, _negotiated: Option<Negotiated>) {
+        println!("{}", serde_json::to_string_pretty(&value).unwrap_or_else(|_| value.to_string()));
+    }
+
+    fn info(&self, message: &str) {
+        crate::logging::status_line(format_args!("{message}"));
+    }
+
+    fn warning(&self, warning: &Warning) {
+        crate::logging::status_line(format_args!("{warning}"));
+        crate::warnings::shown(warning);
+    }
+
+    fn kv(&self, key: &str, value: &dyn Display) {
+        crate::logging::log_line(self.verbose, format_args!("{key}: {value}"));
+    }
+
+    fn metric(&self, name: &str, value: Value) {
+        crate::logging::log_line(self.verbose, format_args!("{name}: {value}"));
+    }
+
+    fn section(&self, title: &str) {
+        crate::logging::log_section(self.verbose, format_args!("{title}"));
+    }
+}
+
+/// Prints every record as one line of JSON on stdout.
+pub struct JsonSink {
+    pub verbose: bool,
+}
+
+impl JsonSink {
+    fn emit(&self, record: Record) {
+        println!("{}", record.to_json());
+    }
+}
+
+impl OutputSink for JsonSink {
+    fn result_json(&self, value: Value, negotiated: Option<Negotiated>) {
+        let mut record = Record::Result(value).to_json();
+        if crate::build_profile::is_debug() {
+            record["debug_build"] = json!(true);
+        }
+        let warnings = crate::warnings::shown_json();
+        if !warnings.is_empty() {
+            record["warnings"] = json!(warnings);
+        }
+        if let Some(negotiated) = negotiated {
+            record["api_version"] = json!(negotiated);
+        }
+        println!("{record}");
+    }
+
+    fn info(&self, message: &str) {
+        if !crate::logging::is_quiet() {
+            self.emit(Record::Info(message.to_string()));
+        }
+    }
+
+    fn warning(&self, warning: &Warning) {
+        self.emit(Record::Warning(warning.clone()));
+        crate::warnings::shown(warning);
+    }
+
+    fn kv(&self, key: &str, value: &dyn Display) {
+        if self.verbose {
+            self.emit(Record::Kv(key.to_string(), value.to_string()));
+        }
+    }
+
+    fn metric(&self, name: &str, value: Value) {
+        self.emit(Record::Metric(name.to_string(), value));
+    }
+
+    fn section(&self, title: &str) {
+        if self.verbose {
+            self.emit(Record::Section(title.to_string()));
+        }
+    }
+}
+
+/// One thing a command emitted.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Record {
+    Result(Value),
+    Info(String),
+    Warning(Warning),
+    Kv(String, String),
+    Metric(String, Value),
+    Section(String),
+}
+
+impl Record {
+    /// The record as [`JsonSink`] prints it, e.g.
+    /// `{"type":"kv","key":"Difficulty","value":"1,000"}`.
+    pub fn to_json(&self) -> Value {
+        match self {
+            Record::Result(value)          => json!({ "type": "result", "value": value }),
+            Record::Info(message)          => json!({ "type": "info", "message": message }),
+            Record::Warning(warning)       => json!({
+                "type":    "warning",
+                "code":    warning.code.name(),
+                "message": warning.message,
+                "data":    warning.data,
+            }),
+            Record::Kv(key, value)         => json!({ "type": "kv", "key": key, "value": value }),
+            Record::Metric(name, value)    => json!({ "type": "metric", "name": name, "value": value }),
+            Record::Section(title)         => json!({ "type": "section", "title": title }),
+        }
+    }
+}
+
+/// Keeps every record, whatever the verbosity, for tests to inspect.
+#[cfg(test)]
+#[derive(Default)]
+pub struct BufferSink {
+    records: std::sync::Mutex<Vec<Record>>,
+}
+
+#[cfg(test)]
+impl BufferSink {
+    pub fn records(&self) -> Vec<Record> {
+        self.records.lock().unwrap().clone()
+    }
+
+    fn push(&self, record: Record) {
+        self.records.lock().unwrap().push(record);
+    }
+}
+
+#[cfg(test)]
+impl OutputSink for BufferSink {
+    fn result_json(&self, value: Value, _negotiated: Option<Negotiated>) {
+        self.push(Record::Result(value));
+    }
+
+    fn info(&self, message: &str) {
+        self.push(Record::Info(message.to_string()));
+    }
+
+    fn warning(&self, warning: &Warning) {
+        self.push(Record::Warning(warning.clone()));
+    }
+
+    fn kv(&self, key: &str, value: &dyn Display) {
+        self.push(Record::Kv(key.to_string(), value.to_string()));
+    }
+
+    fn metric(&self, name: &str, value: Value) {
+        self.push(Record::Metric(name.to_string(), value));
+    }
+
+    fn section(&self, title: &str) {
+        self.push(Record::Section(title.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::warnings::WarningCode;
+
+    #[test]
+    fn test_buffer_sink_keeps_records_in_order() {
+        let sink = BufferSink::default();
+        sink.section("Challenge Fetching");
+        sink.info("Challenge fetched successfully!");
+        sink.kv("Difficulty", &"1,000");
+        sink.result_json(json!({ "a": 1 }), None);
+
+        assert_eq!(sink.records(), [
+            Record::Section("Challenge Fetching".to_string()),
+            Record::Info("Challenge fetched successfully!".to_string()),
+            Record::Kv("Difficulty".to_string(), "1,000".to_string()),
+            Record::Result(json!({ "a": 1 })),
+        ]);
+    }
+
+    #[test]
+    fn test_json_records() {
+        assert_eq!(
+            Record::Kv("Difficulty".to_string(), "1,000".to_string()).to_json(),
+            json!({ "type": "kv", "key": "Difficulty", "value": "1,000" }),
+        );
+        assert_eq!(Record::Result(json!(7)).to_json(), json!({ "type": "result", "value": 7 }));
+        assert_eq!(
+            Record::Warning(Warning::new(WarningCode::ClockSkew, "clock is off").with_data(json!({ "skew_ms": 45_000 }))).to_json(),
+            json!({ "type": "warning", "code": "W002_CLOCK_SKEW", "message": "clock is off", "data": { "skew_ms": 45_000 } }),
+        );
+        assert_eq!(
+            Record::Metric("time_to_first_progress_ms".to_string(), json!(120)).to_json(),
+            json!({ "type": "metric", "name": "time_to_first_progress_ms", "value": 120 }),
+        );
+    }
+}
@@ -0,0 +1,336 @@
+use color_eyre::eyre::eyre;
+use ironshield::IronShieldChallenge;
+use serde::{Deserialize, Serialize};
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::display::{format_count, format_duration};
+use crate::estimate::SolveEstimate;
+
+/// Exit code for a run that refused to solve an oversized challenge.
+pub const REFUSED_EXIT_CODE: i32 = 3;
+
+/// Warn when the median solve estimate is more than this share of the
+/// time the challenge has left.
+const EXPIRY_WARNING_SHARE: f64 = 0.8;
+
+/// Warn when a freshly fetched challenge was created further than this
+/// from the local clock; network latency stays well within it.
+pub const CLOCK_SKEW_TOLERANCE: Duration = Duration::from_secs(30);
+
+/// The `[limits]` section of the configuration file.
+///
+/// ```toml
+/// [limits]
+/// max_difficulty = 50000000
+/// max_expected_time = "2m"
+/// confirm_expected_time = "30s"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LimitsConfig {
+    /// Refuse challenges whose difficulty is above this.
+    pub max_difficulty:        Option<u64>,
+    /// Refuse challenges whose median estimated solve time is above this, e.g. `90s`.
+    pub max_expected_time:     Option<String>,
+    /// Ask before solving challenges whose median estimate is above this.
+    pub confirm_expected_time: Option<String>,
+}
+
+/// What a challenge may cost before it is refused.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Limits {
+    pub max_difficulty:        Option<u64>,
+    pub max_expected_time:     Option<Duration>,
+    /// Solve no matter what (`--force`).
+    pub force:                 bool,
+    /// Ask on the terminal before solving anything expected to take longer.
+    pub confirm_expected_time: Option<Duration>,
+    /// Answer yes to the confirmation (`--yes`).
+    pub assume_yes:            bool,
+}
+
+impl Limits {
+    fn is_unlimited(&self) -> bool {
+        self.force || (self.max_difficulty.is_none() && self.max_expected_time.is_none())
+    }
+
+    /// Whether the challenge is slow enough to ask about first.
+    fn needs_confirmation(&self, estimate: &SolveEstimate) -> bool {
+        match self.confirm_expected_time {
+            Some(threshold) => !self.assume_yes && estimate.low > threshold,
+            None            => false,
+        }
+    }
+
+    /// Which limit, if any, the challenge breaks.
+    ///
+    /// # Arguments
+    /// * `estimate`: The estimate for the challenge.
+    ///
+    /// # Returns
+    /// * `Option<String>`: The limit that was exceeded, for the error message.
+    fn exceeded_by(&self, estimate: &SolveEstimate) -> Option<String> {
+        if self.force {
+            return None;
+        }
+        if let Some(max) = self.max_difficulty {
+            if estimate.difficulty > max {
+                return Some(format!("max difficulty {}", format_count(max)));
+            }
+        }
+        if let Some(max) = self.max_expected_time {
+            // A zero estimate means the hash rate is unknown; don't refuse on a guess.
+            if estimate.low > max {
+                return Some(format!("max expected time {}", format_duration(max)));
+            }
+        }
+        None
+    }
+}
+
+/// A challenge too expensive to solve under the configured limits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Refused {
+    pub estimate: SolveEstimate,
+    /// The limit that was exceeded, e.g. "max difficulty 1,000,000".
+    pub limit:    String,
+}
+
+impl std::fmt::Display for Refused {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Refusing to solve: {} exceeds the {} (pass --force to solve anyway)",
+            self.estimate.interpretation(),
+            self.limit,
+        )
+    }
+}
+
+impl std::error::Error for Refused {}
+
+/// A challenge likely to expire before it is solved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ExpiryRisk {
+    /// How long the challenge had left when it was checked.
+    pub valid_for_ms: u64,
+    /// The median estimated solve time on this machine.
+    pub estimate_ms:  u64,
+}
+
+impl std::fmt::Display for ExpiryRisk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "challenge valid for {}, estimated solve time {} on this machine — consider more threads or a better machine",
+            format_duration(Duration::from_millis(self.valid_for_ms)),
+            format_duration(Duration::from_millis(self.estimate_ms)),
+        )
+    }
+}
+
+/// Compares a challenge's remaining lifetime with the solve estimate.
+///
+/// # Arguments
+/// * `expiration_time`: The challenge's expiry in Unix milliseconds.
+/// * `now_ms`:          The current time in Unix milliseconds.
+/// * `estimate`:        The median estimated solve time.
+///
+/// # Returns
+/// * `Option<ExpiryRisk>`: The numbers, if the estimate is over 80% of
+///                         the time left. A zero estimate never warns.
+pub fn expiry_risk(expiration_time: i64, now_ms: i64, estimate: Duration) -> Option<ExpiryRisk> {
+    let valid_for_ms = expiration_time.saturating_sub(now_ms).max(0) as u64;
+    let estimate_ms = estimate.as_millis() as u64;
+    if estimate_ms == 0 || (estimate_ms as f64) <= valid_for_ms as f64 * EXPIRY_WARNING_SHARE {
+        return None;
+    }
+    Some(ExpiryRisk { valid_for_ms, estimate_ms })
+}
+
+/// How far the local clock is ahead of the API's, judged from a
+/// challenge the API just created.
+///
+/// # Arguments
+/// * `created_time`: The challenge's creation time in Unix milliseconds.
+/// * `now_ms`:       The current time in Unix milliseconds.
+///
+/// # Returns
+/// * `Option<i64>`: The skew in milliseconds, negative when behind, if
+///                  it is over [`CLOCK_SKEW_TOLERANCE`].
+pub fn clock_skew(created_time: i64, now_ms: i64) -> Option<i64> {
+    let skew_ms = now_ms.saturating_sub(created_time);
+    (skew_ms.unsigned_abs() > CLOCK_SKEW_TOLERANCE.as_millis() as u64).then_some(skew_ms)
+}
+
+static LIMITS: OnceLock<Limits> = OnceLock::new();
+
+/// Sets the limits for the rest of the process. Without a call, every
+/// challenge is solved.
+pub fn set_limits(limits: Limits) {
+    let _ = LIMITS.set(limits);
+}
+
+/// Checks a fetched challenge against the limits before any solving
+/// starts. Every command that solves calls this first.
+///
+/// # Arguments
+/// * `challenge`:    The fetched challenge.
+/// * `thread_count`: The threads the solve would use, for the estimate.
+///
+/// # Returns
+/// * `Result<(), Refused>`: The refusal, with the difficulty and estimate.
+pub async fn check(challenge: &IronShieldChallenge, thread_count: usize) -> Result<(), Refused> {
+    let limits = LIMITS.get().copied().unwrap_or_default();
+    check_limits(&limits, challenge, thread_count).await
+}
+
+/// Checks a challenge against explicit limits rather than the
+/// process-wide ones.
+///
+/// # Arguments
+/// * `limits`:       The limits to apply.
+/// * `challenge`:    The fetched challenge.
+/// * `thread_count`: The threads the solve would use, for the estimate.
+pub async fn check_limits(
+    limits:       &Limits,
+    challenge:    &IronShieldChallenge,
+    thread_count: usize,
+) -> Result<(), Refused> {
+    if limits.is_unlimited() {
+        return Ok(());
+    }
+
+    let hash_rate = crate::estimate::cached_probe_hash_rate().await;
+    let estimate = SolveEstimate::new(challenge.recommended_attempts / 2, hash_rate, thread_count);
+    match limits.exceeded_by(&estimate) {
+        Some(limit) => Err(Refused { estimate, limit }),
+        None        => Ok(()),
+    }
+}
+
+/// Asks before a solve expected to take longer than
+/// `[limits] confirm_expected_time`. Runs with nobody at the terminal
+/// go ahead without asking, as does `--yes`.
+///
+/// # Arguments
+/// * `challenge`:    The fetched challenge.
+/// * `thread_count`: The threads the solve would use, for the estimate.
+///
+/// # Returns
+/// * `Result<()>`: An error if the user declined.
+pub async fn confirm(challenge: &IronShieldChallenge, thread_count: usize) -> color_eyre::Result<()> {
+    let limits = LIMITS.get().copied().unwrap_or_default();
+    if limits.confirm_expected_time.is_none() || limits.assume_yes || !crate::prompt::is_interactive() {
+        return Ok(());
+    }
+
+    let hash_rate = crate::estimate::cached_probe_hash_rate().await;
+    let estimate = SolveEstimate::new(challenge.recommended_attempts / 2, hash_rate, thread_count);
+    if !limits.needs_confirmation(&estimate) {
+        return Ok(());
+    }
+
+    let question = format!("{}. Solve it?", estimate.interpretation());
+    let confirmed = tokio::task::spawn_blocking(move || crate::prompt::confirm(&question))
+        .await
+        .unwrap_or(false);
+    if !confirmed {
+        return Err(eyre!("Cancelled before solving (pass --yes to skip this question)"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_skew_beyond_the_tolerance() {
+        assert_eq!(clock_skew(1_000_000, 1_000_000 + 2_000), None);
+        assert_eq!(clock_skew(1_000_000, 1_000_000 - 30_000), None);
+        assert_eq!(clock_skew(1_000_000, 1_000_000 + 45_000), Some(45_000));
+        assert_eq!(clock_skew(1_000_000, 1_000_000 - 90_000), Some(-90_000));
+    }
+
+    fn estimate(difficulty: u64) -> SolveEstimate {
+        // 1,000,000 hashes a second, so the median is ~0.69µs per unit of difficulty.
+        SolveEstimate::new(difficulty, 1_000_000, 1)
+    }
+
+    #[test]
+    fn test_difficulty_limit() {
+        let limits = Limits { max_difficulty: Some(1_000_000), ..Limits::default() };
+
+        assert_eq!(limits.exceeded_by(&estimate(1_000_000)), None);
+        assert_eq!(limits.exceeded_by(&estimate(1_000_001)), Some("max difficulty 1,000,000".to_string()));
+    }
+
+    #[test]
+    fn test_expected_time_limit() {
+        let limits = Limits { max_expected_time: Some(Duration::from_secs(10)), ..Limits::default() };
+
+        assert_eq!(limits.exceeded_by(&estimate(10_000_000)), None);
+        assert!(limits.exceeded_by(&estimate(20_000_000)).unwrap().starts_with("max expected time"));
+        assert_eq!(limits.exceeded_by(&SolveEstimate::new(u64::MAX, 0, 1)), None);
+    }
+
+    #[test]
+    fn test_force_overrides_limits() {
+        let limits = Limits { max_difficulty: Some(1), force: true, ..Limits::default() };
+
+        assert!(limits.is_unlimited());
+        assert_eq!(limits.exceeded_by(&estimate(1_000_000)), None);
+    }
+
+    #[test]
+    fn test_confirmation_threshold() {
+        let limits = Limits { confirm_expected_time: Some(Duration::from_secs(10)), ..Limits::default() };
+
+        assert!(!limits.needs_confirmation(&estimate(10_000_000)));
+        assert!(limits.needs_confirmation(&estimate(20_000_000)));
+        assert!(!Limits { assume_yes: true, ..limits }.needs_confirmation(&estimate(20_000_000)));
+        assert!(!Limits::default().needs_confirmation(&estimate(u64::MAX / 4)));
+    }
+
+    #[test]
+    fn test_expiry_risk_threshold() {
+        let now = 1_700_000_000_000;
+
+        assert_eq!(expiry_risk(now + 10_000, now, Duration::from_secs(8)), None);
+        assert_eq!(
+            expiry_risk(now + 10_000, now, Duration::from_millis(8_001)),
+            Some(ExpiryRisk { valid_for_ms: 10_000, estimate_ms: 8_001 }),
+        );
+        assert_eq!(expiry_risk(now + 10_000, now, Duration::ZERO), None);
+    }
+
+    #[test]
+    fn test_expired_challenge_is_at_risk() {
+        let now = 1_700_000_000_000;
+
+        let risk = expiry_risk(now - 1_000, now, Duration::from_millis(1)).unwrap();
+        assert_eq!(risk.valid_for_ms, 0);
+    }
+
+    #[test]
+    fn test_expiry_risk_message() {
+        let risk = ExpiryRisk { valid_for_ms: 28_000, estimate_ms: 45_000 };
+
+        assert_eq!(
+            risk.to_string(),
+            "challenge valid for 28.0s, estimated solve time 45.0s on this machine — consider more threads or a better machine",
+        );
+    }
+
+    #[test]
+    fn test_refusal_message() {
+        let refused = Refused { estimate: estimate(2_000_000), limit: "max difficulty 1,000,000".to_string() };
+
+        let message = refused.to_string();
+        assert!(message.starts_with("Refusing to solve: Difficulty 2,000,000 — roughly"));
+        assert!(message.ends_with("exceeds the max difficulty 1,000,000 (pass --force to solve anyway)"));
+    }
+}
@@ -0,0 +1,168 @@
+use color_eyre::eyre::eyre;
+use ironshield::{ClientConfig, IronShieldChallengeResponse};
+use ironshield_types::IronShieldToken;
+
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::display::format_duration;
+use crate::logging::LogCategory;
+use crate::warnings::{Warning, WarningCode};
+
+/// Idle pooled connections are dropped after this; a warm-up older
+/// than it has nothing left for the submit to reuse.
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// What the last warm-up left behind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Warmed {
+    at:         Instant,
+    /// Whether the server agreed to keep the connection open.
+    keep_alive: bool,
+}
+
+/// A pooled HTTP client for the API that submits go through when
+/// `--prewarm` is on, so the connection opened during the solve is the
+/// one the submit uses.
+struct Prewarm {
+    http:     reqwest::Client,
+    base_url: String,
+    verbose:  bool,
+    warmed:   Mutex<Option<Warmed>>,
+}
+
+static PREWARM: OnceLock<Prewarm> = OnceLock::new();
+
+/// Turns on `--prewarm` for the rest of the process.
+///
+/// # Arguments
+/// * `config`: The API base URL, timeout and user agent to use.
+pub fn enable(config: &ClientConfig) -> color_eyre::Result<()> {
+    let http = reqwest::Client::builder()
+        .timeout(config.timeout)
+        .user_agent(config.user_agent.clone())
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .pool_max_idle_per_host(1)
+        .build()
+        .map_err(|e| eyre!("Cannot build the prewarm client: {e}"))?;
+    let _ = PREWARM.set(Prewarm {
+        http,
+        base_url: config.api_base_url.trim_end_matches('/').to_string(),
+        verbose:  config.verbose,
+        warmed:   Mutex::new(None),
+    });
+    Ok(())
+}
+
+/// Resolves the API host and opens a connection to it in the
+/// background, for the submit after the solve to reuse. A failure only
+/// warns: the submit then connects as usual.
+pub fn start() {
+    let Some(prewarm) = PREWARM.get() else {
+        return;
+    };
+    tokio::spawn(async move {
+        let start = Instant::now();
+        match warm(prewarm).await {
+            Ok(keep_alive) => {
+                *prewarm.warmed.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Warmed { at: Instant::now(), keep_alive });
+                crate::logging::log_event(prewarm.verbose, LogCategory::Network, format_args!(
+                    "Prewarmed a connection to {} in {}{}",
+                    prewarm.base_url,
+                    format_duration(start.elapsed()),
+                    if keep_alive { "" } else { ", but the server won't keep it open" },
+                ));
+            }
+            Err(e) => crate::warnings::emit(Warning::new(
+                WarningCode::PrewarmFailed,
+                format!("Prewarming {} failed; the submit will connect as usual: {e}", prewarm.base_url),
+            )),
+        }
+    });
+}
+
+/// Resolves the host, then sends a `HEAD` to leave an idle pooled
+/// connection behind.
+///
+/// # Returns
+/// * `Result<bool, String>`: Whether the server kept the connection open.
+async fn warm(prewarm: &Prewarm) -> Result<bool, String> {
+    let url = reqwest::Url::parse(&prewarm.base_url).map_err(|e| e.to_string())?;
+    let host = url.host_str().ok_or("the API base URL has no host")?;
+    let port = url.port_or_known_default().ok_or("the API base URL has no port")?;
+    tokio::net::lookup_host((host, port)).await.map_err(|e| format!("cannot resolve {host}: {e}"))?;
+
+    let (response, _exchange) = crate::har::send(prewarm.http.head(url)).await;
+    let response = response.map_err(|e| e.to_string())?;
+    Ok(keeps_alive(response.version(), response.headers().get(reqwest::header::CONNECTION).and_then(|value| value.to_str().ok())))
+}
+
+/// Whether a response leaves its connection open for the next request.
+fn keeps_alive(version: reqwest::Version, connection: Option<&str>) -> bool {
+    match version {
+        reqwest::Version::HTTP_10 => connection.is_some_and(|value| value.eq_ignore_ascii_case("keep-alive")),
+        reqwest::Version::HTTP_11 => !connection.is_some_and(|value| value.eq_ignore_ascii_case("close")),
+        _                         => true,
+    }
+}
+
+/// Logs whether the next submit goes over the warm connection, if
+/// `--prewarm` is on. Commands call this before submitting.
+pub fn log_reuse() {
+    let Some(prewarm) = PREWARM.get() else {
+        return;
+    };
+    let warmed = *prewarm.warmed.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    crate::logging::log_event(prewarm.verbose, LogCategory::Submit, format_args!("{}", describe_reuse(warmed.map(|warmed| (warmed.at.elapsed(), warmed.keep_alive)))));
+}
+
+/// Submits `solution` over the prewarmed client, if `--prewarm` is on.
+///
+/// # Returns
+/// * `Option<Result<IronShieldToken>>`: `None` without `--prewarm`, for
+///   the caller to submit the usual way.
+pub async fn submit(solution: &IronShieldChallengeResponse) -> Option<color_eyre::Result<IronShieldToken>> {
+    let prewarm = PREWARM.get()?;
+    Some(crate::api::submit(&prewarm.http, &prewarm.base_url, solution).await)
+}
+
+/// Whether the submit goes over the warm connection, for verbose output.
+///
+/// # Arguments
+/// * `warmed`: How long ago the warm-up finished and whether the
+///             server kept its connection open; `None` if it didn't finish.
+fn describe_reuse(warmed: Option<(Duration, bool)>) -> String {
+    match warmed {
+        Some((age, true)) if age < POOL_IDLE_TIMEOUT => {
+            format!("Submitting over the warm connection opened {} ago", format_duration(age))
+        }
+        Some((age, true)) => format!(
+            "Submitting over a new connection: the warm one idled out after {}",
+            format_duration(age),
+        ),
+        Some((_, false)) => "Submitting over a new connection: the server closed the warm one".to_string(),
+        None             => "Submitting over a new connection: prewarming hadn't finished".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_alive() {
+        assert!(keeps_alive(reqwest::Version::HTTP_11, None));
+        assert!(keeps_alive(reqwest::Version::HTTP_11, Some("keep-alive")));
+        assert!(!keeps_alive(reqwest::Version::HTTP_11, Some("Close")));
+        assert!(!keeps_alive(reqwest::Version::HTTP_10, None));
+        assert!(keeps_alive(reqwest::Version::HTTP_2, None));
+    }
+
+    #[test]
+    fn test_describe_reuse() {
+        assert!(describe_reuse(Some((Duration::from_secs(3), true))).starts_with("Submitting over the warm connection"));
+        assert!(describe_reuse(Some((Duration::from_secs(120), true))).contains("idled out"));
+        assert!(describe_reuse(Some((Duration::from_secs(3), false))).contains("server closed"));
+        assert!(describe_reuse(None).contains("hadn't finished"));
+    }
+}
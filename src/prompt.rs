@@ -0,0 +1,218 @@
+use color_eyre::eyre::eyre;
+
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Most recent endpoints offered when prompting for one.
+pub const ENDPOINT_SUGGESTIONS: usize = 5;
+
+/// Set by daemons, which must never wait on a question.
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Never prompt for the rest of the process, even on a terminal.
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// Whether someone is there to answer: stdin and stderr are both
+/// terminals and prompting hasn't been disabled.
+pub fn is_interactive() -> bool {
+    !DISABLED.load(Ordering::Relaxed) && io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+/// Asks for an endpoint on stderr, offering `suggestions` by number.
+/// Invalid URLs are explained and asked for again.
+///
+/// # Arguments
+/// * `suggestions`: Recent endpoints, newest first.
+///
+/// # Returns
+/// * `Result<String>`: The endpoint, or an error if stdin closed first.
+pub fn endpoint(suggestions: &[String]) -> color_eyre::Result<String> {
+    choose_endpoint(&mut io::stdin().lock(), &mut io::stderr(), suggestions)
+}
+
+/// Asks a yes/no question on stderr; anything but `y` or `yes` is no.
+pub fn confirm(question: &str) -> bool {
+    ask_confirm(&mut io::stdin().lock(), &mut io::stderr(), question)
+}
+
+/// Asks a question on stderr, offering `default` for an empty answer
+/// or closed input.
+pub fn ask(question: &str, default: &str) -> String {
+    ask_with_default(&mut io::stdin().lock(), &mut io::stderr(), question, default)
+}
+
+/// An answer to "Retry? [Y/n/change settings]".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryAnswer {
+    Retry,
+    Stop,
+    /// Adjust threads and the timeout, then retry.
+    ChangeSettings,
+}
+
+/// Asks whether to retry after a failure. An empty answer is yes;
+/// closed input is no.
+pub fn retry() -> RetryAnswer {
+    ask_retry(&mut io::stdin().lock(), &mut io::stderr())
+}
+
+fn choose_endpoint(
+    input:       &mut impl BufRead,
+    output:      &mut impl Write,
+    suggestions: &[String],
+) -> color_eyre::Result<String> {
+    if !suggestions.is_empty() {
+        writeln!(output, "Recent endpoints:")?;
+        for (index, endpoint) in suggestions.iter().enumerate() {
+            writeln!(output, "  {}) {endpoint}", index + 1)?;
+        }
+    }
+    let question = match suggestions.len() {
+        0     => "Endpoint URL: ".to_string(),
+        1     => "Endpoint URL (or 1): ".to_string(),
+        count => format!("Endpoint URL (or 1-{count}): "),
+    };
+
+    loop {
+        let Some(answer) = read_answer(input, output, &question)? else {
+            return Err(eyre!("No endpoint given"));
+        };
+        if answer.is_empty() {
+            continue;
+        }
+        let endpoint = match answer.parse::<usize>() {
+            Ok(choice) if (1..=suggestions.len()).contains(&choice) => suggestions[choice - 1].clone(),
+            _                                                      => answer,
+        };
+        match crate::endpoint::check(&endpoint) {
+            Ok(())  => return Ok(endpoint),
+            Err(e)  => writeln!(output, "{e}")?,
+        }
+    }
+}
+
+fn ask_confirm(input: &mut impl BufRead, output: &mut impl Write, question: &str) -> bool {
+    match read_answer(input, output, &format!("{question} [y/N] ")) {
+        Ok(Some(answer)) => matches!(answer.to_ascii_lowercase().as_str(), "y" | "yes"),
+        _                => false,
+    }
+}
+
+fn ask_retry(input: &mut impl BufRead, output: &mut impl Write) -> RetryAnswer {
+    loop {
+        let Ok(Some(answer)) = read_answer(input, output, "Retry? [Y/n/change settings] ") else {
+            return RetryAnswer::Stop;
+        };
+        match answer.to_ascii_lowercase().as_str() {
+            "" | "y" | "yes"                   => return RetryAnswer::Retry,
+            "n" | "no"                         => return RetryAnswer::Stop,
+            "c" | "change" | "change settings" => return RetryAnswer::ChangeSettings,
+            _ => {
+                let _ = writeln!(output, "Answer y, n or c to change settings.");
+            }
+        }
+    }
+}
+
+fn ask_with_default(input: &mut impl BufRead, output: &mut impl Write, question: &str, default: &str) -> String {
+    match read_answer(input, output, &format!("{question} [{default}] ")) {
+        Ok(Some(answer)) if !answer.is_empty() => answer,
+        _                                      => default.to_string(),
+    }
+}
+
+/// Writes `question` and reads one trimmed line; `None` at end of input.
+fn read_answer(input: &mut impl BufRead, output: &mut impl Write, question: &str) -> io::Result<Option<String>> {
+    write!(output, "{question}")?;
+    output.flush()?;
+
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn choose(input: &str, suggestions: &[&str]) -> (color_eyre::Result<String>, String) {
+        let suggestions: Vec<String> = suggestions.iter().map(|s| s.to_string()).collect();
+        let mut output = Vec::new();
+        let result = choose_endpoint(&mut input.as_bytes(), &mut output, &suggestions);
+        (result, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_typed_endpoint() {
+        let (result, output) = choose("https://a.example/protected\n", &[]);
+
+        assert_eq!(result.unwrap(), "https://a.example/protected");
+        assert_eq!(output, "Endpoint URL: ");
+    }
+
+    #[test]
+    fn test_suggestion_by_number() {
+        let (result, output) = choose("2\n", &["https://a.example", "https://b.example"]);
+
+        assert_eq!(result.unwrap(), "https://b.example");
+        assert!(output.starts_with("Recent endpoints:\n  1) https://a.example\n  2) https://b.example\n"));
+        assert!(output.ends_with("Endpoint URL (or 1-2): "));
+    }
+
+    #[test]
+    fn test_invalid_endpoint_is_asked_again() {
+        let (result, output) = choose("a.example\n\n3\nhttps://c.example\n", &["https://a.example"]);
+
+        assert_eq!(result.unwrap(), "https://c.example");
+        assert!(output.contains("Invalid endpoint 'a.example'"));
+        assert!(output.contains("Invalid endpoint '3'"));
+    }
+
+    #[test]
+    fn test_closed_input_gives_up() {
+        assert!(choose("", &[]).0.is_err());
+    }
+
+    #[test]
+    fn test_confirm() {
+        let ask = |input: &str| ask_confirm(&mut input.as_bytes(), &mut Vec::new(), "Solve?");
+
+        assert!(ask("y\n"));
+        assert!(ask("YES\n"));
+        assert!(!ask("\n"));
+        assert!(!ask("nope\n"));
+        assert!(!ask(""));
+    }
+
+    #[test]
+    fn test_ask_with_default() {
+        let mut output = Vec::new();
+        assert_eq!(ask_with_default(&mut "\n".as_bytes(), &mut output, "Threads?", "4"), "4");
+        assert_eq!(String::from_utf8(output).unwrap(), "Threads? [4] ");
+
+        assert_eq!(ask_with_default(&mut "8\n".as_bytes(), &mut Vec::new(), "Threads?", "4"), "8");
+        assert_eq!(ask_with_default(&mut "".as_bytes(), &mut Vec::new(), "Threads?", "4"), "4");
+    }
+
+    #[test]
+    fn test_retry() {
+        let ask = |input: &str| ask_retry(&mut input.as_bytes(), &mut Vec::new());
+
+        assert_eq!(ask("\n"), RetryAnswer::Retry);
+        assert_eq!(ask("Y\n"), RetryAnswer::Retry);
+        assert_eq!(ask("no\n"), RetryAnswer::Stop);
+        assert_eq!(ask("c\n"), RetryAnswer::ChangeSettings);
+        assert_eq!(ask("change settings\n"), RetryAnswer::ChangeSettings);
+        assert_eq!(ask(""), RetryAnswer::Stop);
+
+        let mut output = Vec::new();
+        assert_eq!(ask_retry(&mut "maybe\nn\n".as_bytes(), &mut output), RetryAnswer::Stop);
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("Retry? [Y/n/change settings] ").count(), 2);
+        assert!(output.contains("Answer y, n or c"));
+    }
+}
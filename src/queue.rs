@@ -0,0 +1,364 @@
+use chrono::{DateTime, Utc};
+use ironshield::{IronShieldChallenge, IronShieldChallengeResponse};
+use ironshield_types::IronShieldToken;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::util::{FileMode, atomic_write};
+
+/// How far a queued challenge has got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemState {
+    /// Added, not yet solved.
+    Pending,
+    /// Solved, waiting for `queue submit`.
+    Solved,
+    /// Submitted; the item holds the token.
+    Submitted,
+    /// Expired before it could be solved or submitted.
+    Expired,
+    /// Turned down by the API for good; the item is in `failed/` with
+    /// the response.
+    Rejected,
+}
+
+impl ItemState {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Pending   => "pending",
+            Self::Solved    => "solved",
+            Self::Submitted => "submitted",
+            Self::Expired   => "expired",
+            Self::Rejected  => "rejected",
+        }
+    }
+}
+
+/// One challenge in the queue, with everything done to it so far.
+///
+/// Every item is its own JSON file, rewritten whole on each change,
+/// so a crash leaves it in the last state that was written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueItem {
+    pub id:           String,
+    pub state:        ItemState,
+    pub added_at:     DateTime<Utc>,
+    pub challenge:    IronShieldChallenge,
+    /// The API the challenge came from and its solution goes back to;
+    /// the configured one when unset, as for items queued before it was kept.
+    #[serde(default)]
+    pub api_base_url: Option<String>,
+    #[serde(default)]
+    pub solution:     Option<IronShieldChallengeResponse>,
+    #[serde(default)]
+    pub solved_at:    Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub token:        Option<IronShieldToken>,
+    #[serde(default)]
+    pub submitted_at: Option<DateTime<Utc>>,
+    /// Why the last solve or submit failed, if it did.
+    #[serde(default)]
+    pub error:        Option<String>,
+    /// The API's answer when it rejected the solution for good.
+    #[serde(default)]
+    pub rejection:    Option<Rejection>,
+}
+
+/// What the API answered to a solution it won't ever accept.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rejection {
+    pub status: u16,
+    pub body:   String,
+    pub at:     DateTime<Utc>,
+}
+
+impl QueueItem {
+    /// When the challenge stops being accepted.
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp_millis(self.challenge.expiration_time)
+    }
+
+    /// Whether the challenge has expired by `now`.
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.challenge.expiration_time <= now.timestamp_millis()
+    }
+}
+
+/// Challenges solved now and submitted later, one `<id>.json` file
+/// per item in a directory.
+#[derive(Debug, Clone)]
+pub struct Queue {
+    dir: PathBuf,
+}
+
+impl Queue {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// `ironshield/queue` in the platform data directory.
+    pub fn default_location() -> Option<Self> {
+        dirs::data_dir().map(|dir| Self::new(dir.join("ironshield").join("queue")))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.json"))
+    }
+
+    /// The ID for a challenge added at `added_at`: sortable by time, and
+    /// distinct for different challenges added in the same second.
+    fn id_for(challenge: &IronShieldChallenge, added_at: DateTime<Utc>) -> String {
+        let digest = Sha256::digest(challenge.challenge_signature);
+        let suffix: String = digest.iter().take(4).map(|byte| format!("{byte:02x}")).collect();
+        format!("{}-{suffix}", added_at.format("%Y%m%d%H%M%S"))
+    }
+
+    /// Adds `challenge` as a pending item.
+    ///
+    /// # Arguments
+    /// * `challenge`:    The challenge to solve later.
+    /// * `api_base_url`: The API to submit its solution to.
+    ///
+    /// # Returns
+    /// * `io::Result<QueueItem>`: The new item, or an `AlreadyExists`
+    ///   error if the same challenge was queued this second.
+    pub fn add(&self, challenge: IronShieldChallenge, api_base_url: Option<String>) -> io::Result<QueueItem> {
+        let added_at = Utc::now();
+        let item = QueueItem {
+            id:           Self::id_for(&challenge, added_at),
+            state:        ItemState::Pending,
+            added_at,
+            challenge,
+            api_base_url,
+            solution:     None,
+            solved_at:    None,
+            token:        None,
+            submitted_at: None,
+            error:        None,
+            rejection:    None,
+        };
+        if self.path(&item.id).exists() {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} is already queued", item.id)));
+        }
+        self.save(&item)?;
+        Ok(item)
+    }
+
+    /// Writes `item` over its file, atomically.
+    pub fn save(&self, item: &QueueItem) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        atomic_write(&self.path(&item.id), serde_json::to_string_pretty(item)?.as_bytes(), FileMode::Private)
+    }
+
+    /// Where rejected items are moved, out of the way of `load`.
+    pub fn failed_dir(&self) -> PathBuf {
+        self.dir.join("failed")
+    }
+
+    /// Moves `item` into [`Self::failed_dir`], written with its rejection.
+    pub fn reject(&self, item: &QueueItem) -> io::Result<()> {
+        let failed = self.failed_dir();
+        fs::create_dir_all(&failed)?;
+        atomic_write(&failed.join(format!("{}.json", item.id)), serde_json::to_string_pretty(item)?.as_bytes(), FileMode::Private)?;
+        match fs::remove_file(self.path(&item.id)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Every item, oldest first. Temporary files left by an interrupted
+    /// write are skipped; an item file that doesn't parse is an error.
+    pub fn load(&self) -> io::Result<Vec<QueueItem>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut items = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if !path.extension().is_some_and(|extension| extension == "json") {
+                continue;
+            }
+            let item: QueueItem = serde_json::from_str(&fs::read_to_string(&path)?).map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("{}: {e}", path.display()))
+            })?;
+            items.push(item);
+        }
+        items.sort_by(|a, b| (a.added_at, &a.id).cmp(&(b.added_at, &b.id)));
+        Ok(items)
+    }
+}
+
+/// How one submit from `queue submit` ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubmitOutcome {
+    Submitted,
+    /// The challenge expired, locally or by the API's clock.
+    RejectedExpired,
+    /// The API won't accept the solution.
+    RejectedInvalid,
+    /// No answer, or one worth trying again later.
+    NetworkFailed,
+}
+
+impl SubmitOutcome {
+    /// How a submit that didn't get a token counts.
+    ///
+    /// # Arguments
+    /// * `status`: The response status; `None` if there was no response.
+    /// * `body`:   The response body.
+    pub fn of_failure(status: Option<u16>, body: &str) -> Self {
+        let expired = body.to_ascii_lowercase().contains("expired");
+        match status {
+            None | Some(408 | 425 | 429 | 500..=599) => Self::NetworkFailed,
+            Some(410)                                => Self::RejectedExpired,
+            Some(_) if expired                       => Self::RejectedExpired,
+            Some(_)                                  => Self::RejectedInvalid,
+        }
+    }
+
+    /// Whether the item leaves the queue for `failed/`.
+    pub fn is_permanent(self) -> bool {
+        matches!(self, Self::RejectedExpired | Self::RejectedInvalid)
+    }
+}
+
+/// The outcomes of one `queue submit`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SubmitTally {
+    pub submitted:        usize,
+    pub rejected_expired: usize,
+    pub rejected_invalid: usize,
+    pub network_failed:   usize,
+    /// Solved items still queued afterwards.
+    pub remaining:        usize,
+}
+
+impl SubmitTally {
+    pub fn record(&mut self, outcome: SubmitOutcome) {
+        match outcome {
+            SubmitOutcome::Submitted       => self.submitted += 1,
+            SubmitOutcome::RejectedExpired => self.rejected_expired += 1,
+            SubmitOutcome::RejectedInvalid => self.rejected_invalid += 1,
+            SubmitOutcome::NetworkFailed   => self.network_failed += 1,
+        }
+    }
+
+    /// e.g. "Submitted 12, rejected 3 expired and 2 invalid, 3 network
+    /// failures; 3 remain queued."
+    pub fn describe(&self) -> String {
+        format!(
+            "Submitted {}, rejected {} expired and {} invalid, {} network failure(s); {} remain queued.",
+            self.submitted, self.rejected_expired, self.rejected_invalid, self.network_failed, self.remaining,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn challenge(expires_in_ms: i64, signature_byte: u8) -> IronShieldChallenge {
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(&[7; 32]);
+        let public_key = signing_key.verifying_key().to_bytes();
+        let mut challenge = IronShieldChallenge::new("test".to_string(), 100, signing_key, public_key);
+        challenge.expiration_time = Utc::now().timestamp_millis() + expires_in_ms;
+        challenge.challenge_signature = [signature_byte; 64];
+        challenge
+    }
+
+    #[test]
+    fn test_added_items_load_back_pending_and_oldest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = Queue::new(dir.path().join("queue"));
+        let first = queue.add(challenge(60_000, 1), None).unwrap();
+        let second = queue.add(challenge(60_000, 2), None).unwrap();
+
+        let items = queue.load().unwrap();
+        assert_eq!(items.len(), 2);
+        assert!(items.iter().all(|item| item.state == ItemState::Pending));
+        assert!(items.iter().any(|item| item.id == first.id));
+        assert!(items.iter().any(|item| item.id == second.id));
+        assert!(items[0].added_at <= items[1].added_at);
+    }
+
+    #[test]
+    fn test_saving_rewrites_the_item_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = Queue::new(dir.path().to_path_buf());
+        let mut item = queue.add(challenge(60_000, 1), None).unwrap();
+        item.state = ItemState::Expired;
+        item.error = Some("too late".to_string());
+        queue.save(&item).unwrap();
+
+        let items = queue.load().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].state, ItemState::Expired);
+        assert_eq!(items[0].error.as_deref(), Some("too late"));
+    }
+
+    #[test]
+    fn test_stray_files_are_skipped_and_a_missing_directory_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = Queue::new(dir.path().join("missing"));
+        assert!(queue.load().unwrap().is_empty());
+
+        let queue = Queue::new(dir.path().to_path_buf());
+        fs::write(dir.path().join(".tmpAbC123"), "half written").unwrap();
+        queue.add(challenge(60_000, 1), None).unwrap();
+        assert_eq!(queue.load().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_rejected_items_move_to_failed_with_the_response() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = Queue::new(dir.path().to_path_buf());
+        let mut item = queue.add(challenge(60_000, 1), Some("https://api.example".to_string())).unwrap();
+        item.state = ItemState::Rejected;
+        item.rejection = Some(Rejection { status: 400, body: "{\"error\":\"invalid solution\"}".to_string(), at: Utc::now() });
+        queue.reject(&item).unwrap();
+
+        assert!(queue.load().unwrap().is_empty());
+        let failed: QueueItem = serde_json::from_str(&fs::read_to_string(queue.failed_dir().join(format!("{}.json", item.id))).unwrap()).unwrap();
+        assert_eq!(failed.rejection.unwrap().status, 400);
+        assert_eq!(failed.api_base_url.as_deref(), Some("https://api.example"));
+    }
+
+    #[test]
+    fn test_submit_failures_are_classified_by_status() {
+        assert_eq!(SubmitOutcome::of_failure(None, ""), SubmitOutcome::NetworkFailed);
+        assert_eq!(SubmitOutcome::of_failure(Some(503), ""), SubmitOutcome::NetworkFailed);
+        assert_eq!(SubmitOutcome::of_failure(Some(429), ""), SubmitOutcome::NetworkFailed);
+        assert_eq!(SubmitOutcome::of_failure(Some(410), ""), SubmitOutcome::RejectedExpired);
+        assert_eq!(SubmitOutcome::of_failure(Some(400), "{\"error\":\"Challenge Expired\"}"), SubmitOutcome::RejectedExpired);
+        assert_eq!(SubmitOutcome::of_failure(Some(400), "{\"error\":\"bad nonce\"}"), SubmitOutcome::RejectedInvalid);
+        assert!(!SubmitOutcome::NetworkFailed.is_permanent());
+
+        let mut tally = SubmitTally::default();
+        for outcome in [SubmitOutcome::Submitted, SubmitOutcome::Submitted, SubmitOutcome::RejectedInvalid, SubmitOutcome::NetworkFailed] {
+            tally.record(outcome);
+        }
+        tally.remaining = 1;
+        assert_eq!(tally.describe(), "Submitted 2, rejected 0 expired and 1 invalid, 1 network failure(s); 1 remain queued.");
+    }
+
+    #[test]
+    fn test_expiry_follows_the_challenge() {
+        let dir = tempfile::tempdir().unwrap();
+        let queue = Queue::new(dir.path().to_path_buf());
+        let live = queue.add(challenge(60_000, 1), None).unwrap();
+        let stale = queue.add(challenge(-1_000, 2), None).unwrap();
+        assert!(!live.is_expired(Utc::now()));
+        assert!(stale.is_expired(Utc::now()));
+    }
+}
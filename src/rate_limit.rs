@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// The `[rate_limit]` section of the configuration file.
+///
+/// ```toml
+/// [rate_limit]
+/// max_requests_per_minute = 30
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// Challenge requests allowed per minute, with bursts up to the same number.
+    pub max_requests_per_minute: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self { max_requests_per_minute: 120 }
+    }
+}
+
+/// A token bucket that refills continuously at `per_minute` tokens
+/// a minute and holds at most `per_minute` of them.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    per_minute: f64,
+    /// Goes negative when callers reserve tokens that haven't refilled yet.
+    tokens:     f64,
+    updated:    Instant,
+}
+
+impl TokenBucket {
+    pub fn new(per_minute: u32, now: Instant) -> Self {
+        let per_minute = per_minute.max(1) as f64;
+        Self { per_minute, tokens: per_minute, updated: now }
+    }
+
+    /// Takes a token, reserving one that hasn't refilled yet if need be.
+    ///
+    /// # Arguments
+    /// * `now`: The current time.
+    ///
+    /// # Returns
+    /// * `Duration`: How long to wait before using the token.
+    pub fn reserve(&mut self, now: Instant) -> Duration {
+        let refilled = now.saturating_duration_since(self.updated).as_secs_f64() * self.per_minute / 60.0;
+        self.tokens = (self.tokens + refilled).min(self.per_minute) - 1.0;
+        self.updated = now.max(self.updated);
+
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens * 60.0 / self.per_minute)
+        }
+    }
+}
+
+/// Spaces out challenge requests from every task in the process.
+#[derive(Debug)]
+pub struct RateLimiter {
+    bucket: Mutex<TokenBucket>,
+}
+
+impl RateLimiter {
+    pub fn new(per_minute: u32) -> Self {
+        Self { bucket: Mutex::new(TokenBucket::new(per_minute, Instant::now())) }
+    }
+
+    /// Takes a token for another request.
+    ///
+    /// # Returns
+    /// * `Duration`: How long to wait before sending it.
+    pub fn reserve(&self) -> Duration {
+        self.bucket.lock().unwrap().reserve(Instant::now())
+    }
+}
+
+static LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+
+/// Limits challenge requests for the rest of the process. Without
+/// a call, as with `--no-rate-limit`, requests are never delayed.
+pub fn set_limit(per_minute: u32) {
+    let _ = LIMITER.set(RateLimiter::new(per_minute));
+}
+
+/// Takes a token from the process-wide limiter, if one is set, and
+/// returns how long to wait before the request. [`CliClient`] waits
+/// this out before every challenge request, so commands don't.
+///
+/// [`CliClient`]: crate::CliClient
+pub fn reserve() -> Duration {
+    LIMITER.get().map_or(Duration::ZERO, RateLimiter::reserve)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bursts_up_to_capacity() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(3, start);
+
+        assert_eq!(bucket.reserve(start), Duration::ZERO);
+        assert_eq!(bucket.reserve(start), Duration::ZERO);
+        assert_eq!(bucket.reserve(start), Duration::ZERO);
+        assert_eq!(bucket.reserve(start), Duration::from_secs(20));
+    }
+
+    #[test]
+    fn test_reservations_queue_up() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(60, start);
+        (0..60).for_each(|_| { bucket.reserve(start); });
+
+        // Each caller waits one more second than the one before.
+        assert_eq!(bucket.reserve(start), Duration::from_secs(1));
+        assert_eq!(bucket.reserve(start), Duration::from_secs(2));
+        assert_eq!(bucket.reserve(start + Duration::from_secs(5)), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_refill_stops_at_capacity() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2, start);
+        let later = start + Duration::from_secs(3_600);
+
+        assert_eq!(bucket.reserve(later), Duration::ZERO);
+        assert_eq!(bucket.reserve(later), Duration::ZERO);
+        assert_eq!(bucket.reserve(later), Duration::from_secs(30));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_limiter_reads_the_tokio_clock() {
+        let limiter = RateLimiter::new(1);
+
+        assert_eq!(limiter.reserve(), Duration::ZERO);
+        assert_eq!(limiter.reserve(), Duration::from_secs(60));
+        tokio::time::advance(Duration::from_secs(120)).await;
+        assert_eq!(limiter.reserve(), Duration::ZERO);
+    }
+}
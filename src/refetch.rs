@@ -0,0 +1,163 @@
+use serde::{Deserialize, Serialize};
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::display::format_duration;
+
+/// The `[refetch]` section of the configuration file.
+///
+/// ```toml
+/// [refetch]
+/// min_validity = "3s"
+/// max_refetches = 3
+/// delay = "500ms"
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RefetchConfig {
+    /// Challenges with less time than this left are fetched again.
+    pub min_validity:  String,
+    /// How many times `validate` fetches again before giving up.
+    pub max_refetches: u32,
+    /// Pause before each new fetch.
+    pub delay:         String,
+}
+
+impl Default for RefetchConfig {
+    fn default() -> Self {
+        Self {
+            min_validity:  "3s".to_string(),
+            max_refetches: 3,
+            delay:         "500ms".to_string(),
+        }
+    }
+}
+
+/// [`RefetchConfig`] with its durations parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RefetchPolicy {
+    pub min_validity:  Duration,
+    pub max_refetches: u32,
+    pub delay:         Duration,
+}
+
+impl Default for RefetchPolicy {
+    fn default() -> Self {
+        Self {
+            min_validity:  Duration::from_secs(3),
+            max_refetches: 3,
+            delay:         Duration::from_millis(500),
+        }
+    }
+}
+
+impl RefetchPolicy {
+    /// Parses the config section.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The policy, or which duration is invalid.
+    pub fn from_config(config: &RefetchConfig) -> Result<Self, String> {
+        let parse = |name: &str, value: &str| {
+            crate::display::parse_duration(value).map_err(|e| format!("Invalid `refetch.{name}`: {e}"))
+        };
+        Ok(Self {
+            min_validity:  parse("min_validity", &config.min_validity)?,
+            max_refetches: config.max_refetches,
+            delay:         parse("delay", &config.delay)?,
+        })
+    }
+
+    /// How long a challenge has left, if that is too little to solve it.
+    ///
+    /// # Arguments
+    /// * `expiration_time`: The challenge's expiry in Unix milliseconds.
+    /// * `now_ms`:          The current time in Unix milliseconds.
+    ///
+    /// # Returns
+    /// * `Option<i64>`: The window in milliseconds (negative if already
+    ///                  expired), or `None` if the challenge is fresh enough.
+    pub fn short_window(&self, expiration_time: i64, now_ms: i64) -> Option<i64> {
+        let window_ms = expiration_time - now_ms;
+        (window_ms < self.min_validity.as_millis() as i64).then_some(window_ms)
+    }
+}
+
+/// Every challenge fetched was expired or about to expire.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleChallenges {
+    /// Validity left on each challenge when it arrived, in milliseconds.
+    pub windows_ms:   Vec<i64>,
+    pub min_validity: Duration,
+}
+
+impl std::fmt::Display for StaleChallenges {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let windows: Vec<String> = self.windows_ms.iter().map(|&ms| describe_window(ms)).collect();
+        write!(
+            f,
+            "Gave up after {} challenge(s) arrived with less than {} left: {} \
+             (check the server's clock and request queueing)",
+            self.windows_ms.len(),
+            format_duration(self.min_validity),
+            windows.join(", "),
+        )
+    }
+}
+
+impl std::error::Error for StaleChallenges {}
+
+/// e.g. "1.2s left" or "expired 0.4s before arrival".
+pub fn describe_window(window_ms: i64) -> String {
+    let length = format_duration(Duration::from_millis(window_ms.unsigned_abs()));
+    match window_ms {
+        ms if ms > 0 => format!("{length} left"),
+        _            => format!("expired {length} before arrival"),
+    }
+}
+
+static POLICY: OnceLock<RefetchPolicy> = OnceLock::new();
+
+/// Sets the policy for the rest of the process.
+pub fn set_policy(policy: RefetchPolicy) {
+    let _ = POLICY.set(policy);
+}
+
+/// The policy set at startup, or the defaults.
+pub fn policy() -> RefetchPolicy {
+    POLICY.get().copied().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_window() {
+        let policy = RefetchPolicy::default();
+        let now = 1_700_000_000_000;
+
+        assert_eq!(policy.short_window(now + 3_000, now), None);
+        assert_eq!(policy.short_window(now + 2_999, now), Some(2_999));
+        assert_eq!(policy.short_window(now - 400, now), Some(-400));
+    }
+
+    #[test]
+    fn test_policy_from_config() {
+        assert_eq!(RefetchPolicy::from_config(&RefetchConfig::default()).unwrap(), RefetchPolicy::default());
+
+        let config = RefetchConfig { delay: "soon".to_string(), ..RefetchConfig::default() };
+        assert!(RefetchPolicy::from_config(&config).unwrap_err().starts_with("Invalid `refetch.delay`"));
+    }
+
+    #[test]
+    fn test_stale_message_lists_every_window() {
+        let stale = StaleChallenges { windows_ms: vec![800, -400], min_validity: Duration::from_secs(3) };
+
+        assert_eq!(
+            stale.to_string(),
+            "Gave up after 2 challenge(s) arrived with less than 3.0s left: 800ms left, expired 400ms before arrival \
+             (check the server's clock and request queueing)",
+        );
+    }
+}
@@ -0,0 +1,303 @@
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::eyre;
+use ironshield::{ClientConfig, IronShieldChallenge, IronShieldChallengeResponse, ProgressTracker};
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::benchmark::{Machine, Versions};
+use crate::solve::{ThreadPlan, WorkSplit};
+
+/// Version of the bundle layout written by `solve --dump-repro`. Bump
+/// the major version when a file changes meaning; readers refuse
+/// majors they don't know.
+pub const FORMAT_VERSION: &str = "1.0";
+
+/// Progress samples kept for the bundle, newest last.
+pub const PROGRESS_SAMPLES: usize = 200;
+
+const MANIFEST_FILE:      &str = "manifest.json";
+const CHALLENGE_FILE:     &str = "challenge.json";
+const SOLVE_CONFIG_FILE:  &str = "solve_config.json";
+const CLIENT_CONFIG_FILE: &str = "client_config.json";
+const OUTCOME_FILE:       &str = "outcome.json";
+const PROGRESS_FILE:      &str = "progress.json";
+
+/// `manifest.json`: what wrote the bundle, and where.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format_version: String,
+    pub created_at:     DateTime<Utc>,
+    #[serde(default)]
+    pub versions:       Versions,
+    #[serde(default)]
+    pub machine:        Machine,
+}
+
+/// `solve_config.json`: how the solve divided its work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SolveSettings {
+    pub plan:       ThreadPlan,
+    /// The plan's split, also kept here for bundles written before the
+    /// plan carried it.
+    pub work_split: WorkSplit,
+}
+
+/// `outcome.json`: how the solve ended.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Outcome {
+    pub solution:            Option<IronShieldChallengeResponse>,
+    pub error:               Option<String>,
+    pub elapsed_ms:          u64,
+    /// Each thread's last reported cumulative attempts.
+    pub per_thread_attempts: Vec<u64>,
+}
+
+/// One progress report from a solver thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgressSample {
+    pub elapsed_ms: u64,
+    pub thread_id:  usize,
+    pub attempts:   u64,
+    pub hash_rate:  u64,
+}
+
+/// Everything `repro` needs to replay a solve.
+#[derive(Debug, Clone)]
+pub struct Bundle {
+    pub manifest:  Manifest,
+    pub challenge: IronShieldChallenge,
+    pub settings:  SolveSettings,
+    pub outcome:   Outcome,
+    pub progress:  Vec<ProgressSample>,
+}
+
+/// What has been seen of the current solve so far.
+#[derive(Default)]
+struct Recording {
+    challenge: Option<IronShieldChallenge>,
+    settings:  Option<SolveSettings>,
+    start:     Option<Instant>,
+    attempts:  Vec<u64>,
+    samples:   VecDeque<ProgressSample>,
+    outcome:   Option<Outcome>,
+}
+
+/// Collects a solve for `--dump-repro`.
+pub struct Recorder {
+    recording: Mutex<Recording>,
+}
+
+static RECORDER: OnceLock<Recorder> = OnceLock::new();
+
+/// Starts recording every later solve, for `--dump-repro`.
+pub fn arm() {
+    RECORDER.get_or_init(|| Recorder { recording: Mutex::new(Recording::default()) });
+}
+
+/// The recorder, if [`arm`] was called.
+pub fn recorder() -> Option<&'static Recorder> {
+    RECORDER.get()
+}
+
+impl Recorder {
+    fn lock(&self) -> MutexGuard<'_, Recording> {
+        self.recording.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Records the challenge and plan of a solve that is about to start,
+    /// replacing any earlier one.
+    pub fn begin(&self, challenge: &IronShieldChallenge, plan: ThreadPlan) {
+        *self.lock() = Recording {
+            challenge: Some(challenge.clone()),
+            settings:  Some(SolveSettings { plan, work_split: plan.work_split }),
+            start:     Some(Instant::now()),
+            attempts:  vec![0; plan.thread_count],
+            ..Recording::default()
+        };
+    }
+
+    /// Wraps `inner` so progress is recorded on its way through.
+    pub fn tracker(&'static self, inner: Arc<dyn ProgressTracker>) -> Arc<dyn ProgressTracker> {
+        Arc::new(RecordingTracker { recorder: self, inner })
+    }
+
+    fn record(&self, thread_id: usize, attempts: u64, hash_rate: u64, elapsed: Duration) {
+        let mut recording = self.lock();
+        if let Some(slot) = recording.attempts.get_mut(thread_id) {
+            *slot = (*slot).max(attempts);
+        }
+        if recording.samples.len() == PROGRESS_SAMPLES {
+            recording.samples.pop_front();
+        }
+        let elapsed_ms = elapsed.as_millis() as u64;
+        recording.samples.push_back(ProgressSample { elapsed_ms, thread_id, attempts, hash_rate });
+    }
+
+    /// Records how the solve ended.
+    pub fn finish(&self, result: &color_eyre::Result<IronShieldChallengeResponse>) {
+        let mut recording = self.lock();
+        let elapsed_ms = recording.start.map_or(0, |start| start.elapsed().as_millis() as u64);
+        recording.outcome = Some(Outcome {
+            solution:            result.as_ref().ok().cloned(),
+            error:               result.as_ref().err().map(|e| format!("{e:#}")),
+            elapsed_ms,
+            per_thread_attempts: recording.attempts.clone(),
+        });
+    }
+
+    /// Writes the recorded solve to `dir`, creating it if needed.
+    ///
+    /// # Arguments
+    /// * `dir`:    The bundle directory.
+    /// * `config`: Saved with secrets stripped, for context only.
+    ///
+    /// # Returns
+    /// * `Result<()>`: An error if no solve was recorded or a file
+    ///                 can't be written.
+    pub fn write_bundle(&self, dir: &Path, config: &ClientConfig) -> color_eyre::Result<()> {
+        let recording = self.lock();
+        let (Some(challenge), Some(settings)) = (&recording.challenge, &recording.settings) else {
+            return Err(eyre!("Nothing to write to '{}': the solve never started", dir.display()));
+        };
+        let outcome = recording.outcome.clone().ok_or_else(|| eyre!("The solve is still running"))?;
+
+        let manifest = Manifest {
+            format_version: FORMAT_VERSION.to_string(),
+            created_at:     Utc::now(),
+            versions:       Versions::current(),
+            machine:        Machine::current(),
+        };
+        let mut client_config = serde_json::to_value(config)?;
+        strip_secrets(&mut client_config);
+
+        std::fs::create_dir_all(dir).map_err(|e| eyre!("Cannot create '{}': {e}", dir.display()))?;
+        write_json(dir, MANIFEST_FILE, &manifest)?;
+        write_json(dir, CHALLENGE_FILE, challenge)?;
+        write_json(dir, SOLVE_CONFIG_FILE, settings)?;
+        write_json(dir, CLIENT_CONFIG_FILE, &client_config)?;
+        write_json(dir, OUTCOME_FILE, &outcome)?;
+        write_json(dir, PROGRESS_FILE, &recording.samples)?;
+        Ok(())
+    }
+}
+
+/// Passes progress on to the display's tracker after recording it.
+struct RecordingTracker {
+    recorder: &'static Recorder,
+    inner:    Arc<dyn ProgressTracker>,
+}
+
+impl ProgressTracker for RecordingTracker {
+    fn on_progress(&self, thread_id: usize, total_attempts: u64, hash_rate: u64, elapsed: Duration) {
+        self.recorder.record(thread_id, total_attempts, hash_rate, elapsed);
+        self.inner.on_progress(thread_id, total_attempts, hash_rate, elapsed);
+    }
+}
+
+fn write_json(dir: &Path, name: &str, value: &impl Serialize) -> color_eyre::Result<()> {
+    let path = dir.join(name);
+    std::fs::write(&path, format!("{}\n", serde_json::to_string_pretty(value)?))
+        .map_err(|e| eyre!("Cannot write '{}': {e}", path.display()))
+}
+
+fn read_json<T: DeserializeOwned>(dir: &Path, name: &str) -> color_eyre::Result<T> {
+    let path = dir.join(name);
+    let contents = std::fs::read_to_string(&path).map_err(|e| eyre!("Cannot read '{}': {e}", path.display()))?;
+    serde_json::from_str(&contents).map_err(|e| eyre!("'{}' is not valid: {e}", path.display()))
+}
+
+/// Masks configured secrets and URL credentials and queries in every
+/// string, so a bundle can be attached to a bug report.
+fn strip_secrets(value: &mut Value) {
+    match value {
+        Value::String(text)   => *text = strip_text(text),
+        Value::Array(items)   => items.iter_mut().for_each(strip_secrets),
+        Value::Object(fields) => fields.values_mut().for_each(strip_secrets),
+        _                     => {}
+    }
+}
+
+fn strip_text(text: &str) -> String {
+    let text = crate::redact::redact(text).into_owned();
+    match reqwest::Url::parse(&text) {
+        Ok(mut url) if url.has_host() && (!url.username().is_empty() || url.password().is_some() || url.query().is_some()) => {
+            let _ = url.set_username("");
+            let _ = url.set_password(None);
+            url.set_query(None);
+            url.to_string()
+        }
+        _ => text,
+    }
+}
+
+/// Refuses bundles whose major version this release doesn't know.
+fn check_version(version: &str) -> Result<(), String> {
+    let supported = FORMAT_VERSION.split('.').next().unwrap_or_default();
+    match version.split('.').next() {
+        Some(major) if major == supported => Ok(()),
+        _ => Err(format!("bundle format {version} is not supported; this release reads {supported}.x")),
+    }
+}
+
+impl Bundle {
+    /// Loads a bundle written by `solve --dump-repro`, checking its
+    /// version before anything else.
+    pub fn load(dir: &Path) -> color_eyre::Result<Self> {
+        let manifest: Manifest = read_json(dir, MANIFEST_FILE)?;
+        check_version(&manifest.format_version).map_err(|e| eyre!("'{}': {e}", dir.display()))?;
+        Ok(Self {
+            challenge: read_json(dir, CHALLENGE_FILE)?,
+            settings:  read_json(dir, SOLVE_CONFIG_FILE)?,
+            outcome:   read_json(dir, OUTCOME_FILE)?,
+            progress:  read_json(dir, PROGRESS_FILE)?,
+            manifest,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_major_versions_are_rejected() {
+        assert!(check_version("1.0").is_ok());
+        assert!(check_version("1.7").is_ok());
+        assert_eq!(check_version("2.0"), Err("bundle format 2.0 is not supported; this release reads 1.x".to_string()));
+        assert!(check_version("").is_err());
+    }
+
+    #[test]
+    fn test_secrets_are_stripped() {
+        let mut config = serde_json::json!({
+            "api_base_url": "https://user:pw@api.example/v1?key=abcd",
+            "user_agent":   "ironshield-cli",
+            "timeout":      5,
+        });
+        strip_secrets(&mut config);
+
+        assert_eq!(config["api_base_url"], "https://api.example/v1");
+        assert_eq!(config["user_agent"], "ironshield-cli");
+        assert_eq!(config["timeout"], 5);
+    }
+
+    #[test]
+    fn test_only_the_latest_samples_are_kept() {
+        let recorder = Recorder { recording: Mutex::new(Recording::default()) };
+        recorder.lock().attempts = vec![0; 2];
+        for report in 0..PROGRESS_SAMPLES as u64 + 10 {
+            recorder.record((report % 2) as usize, report * 100, 1_000, Duration::from_millis(report));
+        }
+
+        let recording = recorder.lock();
+        assert_eq!(recording.samples.len(), PROGRESS_SAMPLES);
+        assert_eq!(recording.samples.front().unwrap().attempts, 1_000);
+        assert_eq!(recording.attempts, [(PROGRESS_SAMPLES as u64 + 8) * 100, (PROGRESS_SAMPLES as u64 + 9) * 100]);
+    }
+}
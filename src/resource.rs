@@ -0,0 +1,203 @@
+use std::time::{Duration, Instant};
+
+/// A point-in-time reading of this process's resource counters.
+/// A counter the platform can't provide is `None`.
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    taken_at:       Instant,
+    /// User plus system CPU time since the process started.
+    cpu_time:       Option<Duration>,
+    /// Largest resident set size so far.
+    peak_rss_bytes: Option<u64>,
+}
+
+impl Sample {
+    pub fn now() -> Self {
+        let (cpu_time, peak_rss_bytes) = platform::read();
+        Self { taken_at: Instant::now(), cpu_time, peak_rss_bytes }
+    }
+}
+
+/// Resource usage between two [`Sample`]s.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Usage {
+    /// Peak RSS of the whole process at the end sample.
+    pub peak_rss_bytes: Option<u64>,
+    /// CPU time (user + sys) spent between the samples.
+    pub cpu_ms:         Option<u64>,
+    /// CPU time as a share of wall time; 400% is four cores kept busy.
+    pub cpu_percent:    Option<f64>,
+}
+
+impl Usage {
+    pub fn between(start: &Sample, end: &Sample) -> Self {
+        let wall = end.taken_at.saturating_duration_since(start.taken_at);
+        let cpu = start.cpu_time.zip(end.cpu_time).map(|(start, end)| end.saturating_sub(start));
+
+        Self {
+            peak_rss_bytes: end.peak_rss_bytes,
+            cpu_ms:         cpu.map(|cpu| cpu.as_millis() as u64),
+            cpu_percent:    cpu.filter(|_| !wall.is_zero()).map(|cpu| cpu.as_secs_f64() / wall.as_secs_f64() * 100.0),
+        }
+    }
+
+    /// How busy the solver threads were, from 0% (always descheduled) to 100% (all
+    /// of them on a core the whole time).
+    ///
+    /// # Arguments
+    /// * `thread_count`: The number of solver threads.
+    pub fn thread_utilization(&self, thread_count: usize) -> Option<f64> {
+        self.cpu_percent.map(|percent| percent / thread_count.max(1) as f64)
+    }
+}
+
+/// Drops the calling thread to idle scheduling priority, so it only
+/// uses CPU time nothing else wants. There is no way back up short of
+/// exiting the thread.
+///
+/// # Returns
+/// * `bool`: Whether the platform supports it and the call succeeded.
+pub fn lower_thread_priority() -> bool {
+    platform::lower_thread_priority()
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::time::Duration;
+
+    pub fn read() -> (Option<Duration>, Option<u64>) {
+        let mut usage = std::mem::MaybeUninit::<libc::rusage>::uninit();
+        // SAFETY: getrusage only writes into the struct it is given.
+        if unsafe { libc::getrusage(libc::RUSAGE_SELF, usage.as_mut_ptr()) } != 0 {
+            return (None, None);
+        }
+        // SAFETY: getrusage returned 0, so it filled in the struct.
+        let usage = unsafe { usage.assume_init() };
+
+        let timeval = |tv: libc::timeval| Duration::new(tv.tv_sec as u64, tv.tv_usec as u32 * 1_000);
+        let cpu_time = timeval(usage.ru_utime) + timeval(usage.ru_stime);
+
+        // macOS reports ru_maxrss in bytes, everything else in KiB.
+        let max_rss = usage.ru_maxrss.max(0) as u64;
+        let peak_rss = if cfg!(target_os = "macos") { max_rss } else { max_rss * 1024 };
+
+        (Some(cpu_time), (peak_rss > 0).then_some(peak_rss))
+    }
+
+    /// On Linux, nice values are per thread, so this leaves the rest of
+    /// the process alone. Elsewhere it would renice the whole process.
+    #[cfg(target_os = "linux")]
+    pub fn lower_thread_priority() -> bool {
+        // SAFETY: setpriority takes no pointers; `who = 0` is the calling thread.
+        unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, 19) == 0 }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn lower_thread_priority() -> bool {
+        false
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::time::Duration;
+
+    use windows_sys::Win32::Foundation::FILETIME;
+    use windows_sys::Win32::System::ProcessStatus::{GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+    use windows_sys::Win32::System::Threading::{
+        GetCurrentProcess,
+        GetCurrentThread,
+        GetProcessTimes,
+        SetThreadPriority,
+        THREAD_PRIORITY_IDLE,
+    };
+
+    pub fn read() -> (Option<Duration>, Option<u64>) {
+        (cpu_time(), peak_rss())
+    }
+
+    fn cpu_time() -> Option<Duration> {
+        let zero = FILETIME { dwLowDateTime: 0, dwHighDateTime: 0 };
+        let (mut created, mut exited, mut kernel, mut user) = (zero, zero, zero, zero);
+        // SAFETY: the pseudo-handle from GetCurrentProcess is always valid,
+        // and every out-pointer refers to a live FILETIME.
+        let ok = unsafe { GetProcessTimes(GetCurrentProcess(), &mut created, &mut exited, &mut kernel, &mut user) };
+        if ok == 0 {
+            return None;
+        }
+        // FILETIME counts 100 ns intervals.
+        let ticks = |time: FILETIME| (u64::from(time.dwHighDateTime) << 32) | u64::from(time.dwLowDateTime);
+        Some(Duration::from_nanos((ticks(kernel) + ticks(user)) * 100))
+    }
+
+    fn peak_rss() -> Option<u64> {
+        // SAFETY: PROCESS_MEMORY_COUNTERS is plain data, so all zeroes is valid.
+        let mut counters: PROCESS_MEMORY_COUNTERS = unsafe { std::mem::zeroed() };
+        counters.cb = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32;
+        // SAFETY: `counters` is live and `cb` holds its size.
+        let ok = unsafe { GetProcessMemoryInfo(GetCurrentProcess(), &mut counters, counters.cb) };
+        (ok != 0).then_some(counters.PeakWorkingSetSize as u64)
+    }
+
+    pub fn lower_thread_priority() -> bool {
+        // SAFETY: the pseudo-handle from GetCurrentThread is always valid.
+        unsafe { SetThreadPriority(GetCurrentThread(), THREAD_PRIORITY_IDLE) != 0 }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    use std::time::Duration;
+
+    pub fn read() -> (Option<Duration>, Option<u64>) {
+        (None, None)
+    }
+
+    pub fn lower_thread_priority() -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(offset_ms: u64, cpu_ms: Option<u64>) -> Sample {
+        Sample {
+            taken_at:       Instant::now() + Duration::from_millis(offset_ms),
+            cpu_time:       cpu_ms.map(Duration::from_millis),
+            peak_rss_bytes: Some(1_048_576),
+        }
+    }
+
+    #[test]
+    fn test_usage_between_samples() {
+        let start = sample(0, Some(500));
+        let end = Sample { taken_at: start.taken_at + Duration::from_secs(2), ..sample(0, Some(4_500)) };
+        let usage = Usage::between(&start, &end);
+
+        assert_eq!(usage.cpu_ms, Some(4_000));
+        assert_eq!(usage.cpu_percent, Some(200.0));
+        assert_eq!(usage.thread_utilization(4), Some(50.0));
+        assert_eq!(usage.peak_rss_bytes, Some(1_048_576));
+    }
+
+    #[test]
+    fn test_missing_counters_are_none() {
+        let start = sample(0, None);
+        let usage = Usage::between(&start, &sample(1_000, Some(100)));
+
+        assert_eq!(usage.cpu_ms, None);
+        assert_eq!(usage.cpu_percent, None);
+    }
+
+    #[test]
+    fn test_sample_reads_this_process() {
+        let usage = Usage::between(&Sample::now(), &Sample::now());
+
+        if cfg!(any(unix, windows)) {
+            assert!(usage.cpu_ms.is_some());
+            assert!(usage.peak_rss_bytes.is_some_and(|bytes| bytes > 0));
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use ironshield::ClientConfig;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::client::CliClient;
+use crate::display::parse_duration;
+use crate::history::ErrorKind;
+use crate::prompt::{self, RetryAnswer};
+
+/// Interactive retries offered per command before its failure stands.
+pub const MAX_RETRIES: u32 = 3;
+
+/// Set by `--yes`, `--quiet` and `--output json`, whose runs must never
+/// stop to ask.
+static BYPASSED: AtomicBool = AtomicBool::new(false);
+
+/// Never offer to retry for the rest of the process.
+pub fn bypass() {
+    BYPASSED.store(true, Ordering::Relaxed);
+}
+
+/// Offers to run a failed `solve`, `run` or `validate` again, with the
+/// client and config the next attempt should use.
+///
+/// Offers are only made to someone at a terminal, for failures whose
+/// [`ErrorKind`] is recoverable, at most [`MAX_RETRIES`] times.
+pub struct InteractiveRetry<'a> {
+    client:  &'a CliClient,
+    config:  ClientConfig,
+    /// Built when a retry changes the timeout, which the client holds.
+    rebuilt: Option<CliClient>,
+    retries: u32,
+}
+
+impl<'a> InteractiveRetry<'a> {
+    pub fn new(client: &'a CliClient, config: &ClientConfig) -> Self {
+        Self { client, config: config.clone(), rebuilt: None, retries: 0 }
+    }
+
+    pub fn client(&self) -> &CliClient {
+        self.rebuilt.as_ref().unwrap_or(self.client)
+    }
+
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    /// After a failed attempt: reports `error` and asks whether to
+    /// retry, when that is allowed.
+    ///
+    /// # Arguments
+    /// * `error`: What went wrong.
+    /// * `kind`:  The stage it went wrong in, from the run record.
+    ///
+    /// # Returns
+    /// * `bool`: Whether to run again; otherwise `error` stands.
+    pub fn offer(&mut self, error: &color_eyre::Report, kind: Option<ErrorKind>) -> bool {
+        if BYPASSED.load(Ordering::Relaxed)
+            || !prompt::is_interactive()
+            || self.retries >= MAX_RETRIES
+            || !kind.is_some_and(ErrorKind::is_recoverable)
+        {
+            return false;
+        }
+
+        crate::status_println!("Error: {error}");
+        match prompt::retry() {
+            RetryAnswer::Stop           => return false,
+            RetryAnswer::Retry          => {}
+            RetryAnswer::ChangeSettings => self.change_settings(),
+        }
+        self.retries += 1;
+        crate::status_println!("Retrying ({} of {MAX_RETRIES})...", self.retries);
+        true
+    }
+
+    /// Asks for a thread count and timeout, keeping the current ones
+    /// for an empty or invalid answer.
+    fn change_settings(&mut self) {
+        let threads = self.client().thread_plan(true).thread_count;
+        let answer = prompt::ask("Solver threads?", &threads.to_string());
+        let picked = match answer.parse::<usize>() {
+            Ok(picked) if picked == threads => None,
+            Ok(picked) if picked > 0        => Some(picked),
+            _                               => {
+                crate::status_println!("Keeping {threads} thread(s): '{answer}' isn't a thread count.");
+                None
+            }
+        };
+
+        let timeout = format!("{}s", self.config.timeout.as_secs());
+        let answer = prompt::ask("Request timeout?", &timeout);
+        if answer != timeout {
+            let changed = parse_duration(&answer)
+                .map_err(|e| e.to_string())
+                .and_then(|picked| self.config.set_timeout(picked).map_err(|e| e.to_string()));
+            if let Err(e) = changed {
+                crate::status_println!("Keeping the {timeout} timeout: {e}");
+            }
+        }
+
+        let rebuilt = self.client().reconfigured(self.config.clone())
+            .map(|client| match picked {
+                Some(threads) => client.with_retry_threads(threads),
+                None          => client,
+            });
+        match rebuilt {
+            Ok(client) => self.rebuilt = Some(client),
+            Err(e)     => crate::status_println!("Keeping the current settings: {e}"),
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// The order in which `batch` solves challenges it has already fetched.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Schedule {
+    /// In the order the endpoints were given.
+    #[default]
+    Fifo,
+    /// Fewest recommended attempts first, so quick wins finish early.
+    EasiestFirst,
+    /// Soonest expiry first, so none expire while waiting.
+    Deadline,
+}
+
+impl Schedule {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Fifo         => "fifo",
+            Self::EasiestFirst => "easiest-first",
+            Self::Deadline     => "deadline",
+        }
+    }
+}
+
+/// What the scheduler knows about one fetched challenge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candidate {
+    pub recommended_attempts: u64,
+    /// Unix milliseconds.
+    pub expiration_time:      i64,
+}
+
+/// Orders challenges for solving. Ties keep their input order.
+///
+/// # Arguments
+/// * `schedule`:   The ordering to apply.
+/// * `candidates`: The fetched challenges, in input order.
+///
+/// # Returns
+/// * `Vec<usize>`: Indices into `candidates`, in solve order.
+pub fn order(schedule: Schedule, candidates: &[Candidate]) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..candidates.len()).collect();
+    match schedule {
+        Schedule::Fifo         => {}
+        Schedule::EasiestFirst => indices.sort_by_key(|&i| candidates[i].recommended_attempts),
+        Schedule::Deadline     => indices.sort_by_key(|&i| candidates[i].expiration_time),
+    }
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates() -> Vec<Candidate> {
+        [(8_000, 3_000), (2_000, 9_000), (8_000, 1_000), (500, 5_000)]
+            .into_iter()
+            .map(|(recommended_attempts, expiration_time)| Candidate { recommended_attempts, expiration_time })
+            .collect()
+    }
+
+    #[test]
+    fn test_fifo_keeps_input_order() {
+        assert_eq!(order(Schedule::Fifo, &candidates()), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_easiest_first_is_stable() {
+        assert_eq!(order(Schedule::EasiestFirst, &candidates()), [3, 1, 0, 2]);
+    }
+
+    #[test]
+    fn test_deadline_orders_by_expiry() {
+        assert_eq!(order(Schedule::Deadline, &candidates()), [2, 0, 3, 1]);
+    }
+
+    #[test]
+    fn test_empty_set() {
+        assert!(order(Schedule::Deadline, &[]).is_empty());
+    }
+}
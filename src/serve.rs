@@ -0,0 +1,384 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Request heads longer than this are refused.
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+/// Request bodies longer than this are refused; a challenge is a few hundred bytes.
+pub const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// The `[serve]` section of the configuration file.
+///
+/// ```toml
+/// [serve]
+/// token = "a long random string"
+/// max_concurrent = 2
+/// max_time = "2m"
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServeConfig {
+    /// Bearer token every request must carry. Without one, `serve`
+    /// only listens on loopback addresses.
+    pub token:          Option<String>,
+    /// Solves and validates running at once; requests beyond this get 429.
+    pub max_concurrent: usize,
+    /// The longest a request may run, and its `max_time` when it gives none.
+    pub max_time:       String,
+}
+
+impl Default for ServeConfig {
+    fn default() -> Self {
+        Self { token: None, max_concurrent: 2, max_time: "5m".to_string() }
+    }
+}
+
+/// [`ServeConfig`] checked, with its duration parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServePolicy {
+    pub token:          Option<String>,
+    pub max_concurrent: usize,
+    pub max_time:       Duration,
+}
+
+impl ServePolicy {
+    /// # Returns
+    /// * `Result<Self, String>`: The policy, or which setting is invalid.
+    pub fn from_config(config: &ServeConfig) -> Result<Self, String> {
+        let max_time = crate::display::parse_duration(&config.max_time).map_err(|e| format!("Invalid `serve.max_time`: {e}"))?;
+        if config.max_concurrent == 0 {
+            return Err("Invalid `serve.max_concurrent`: must be at least 1".to_string());
+        }
+        if config.token.as_deref().is_some_and(|token| token.chars().count() < crate::redact::MIN_SECRET_LEN) {
+            return Err(format!("Invalid `serve.token`: must be at least {} characters", crate::redact::MIN_SECRET_LEN));
+        }
+        Ok(Self { token: config.token.clone(), max_concurrent: config.max_concurrent, max_time })
+    }
+
+    /// The time a request asking for `requested` gets: at most `max_time`.
+    pub fn time_for(&self, requested: Option<Duration>) -> Duration {
+        requested.map_or(self.max_time, |requested| requested.min(self.max_time))
+    }
+}
+
+/// Whether an `Authorization` header carries the bearer token; any
+/// request does when no token is configured.
+///
+/// # Arguments
+/// * `token`:  The configured token.
+/// * `header`: The request's `Authorization` header.
+pub fn authorized(token: Option<&str>, header: Option<&str>) -> bool {
+    let Some(token) = token else {
+        return true;
+    };
+    let presented = header
+        .and_then(|header| header.split_once(' '))
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+        .map(|(_, presented)| presented.trim());
+    presented.is_some_and(|presented| constant_time_eq(presented.as_bytes(), token.as_bytes()))
+}
+
+/// Compares without returning early, so response times don't reveal
+/// how much of a guessed token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |differ, (a, b)| differ | (a ^ b)) == 0
+}
+
+/// One HTTP/1.1 request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    pub method:  String,
+    /// The path without its query string.
+    pub path:    String,
+    pub query:   Vec<(String, String)>,
+    /// Names lowercased.
+    pub headers: Vec<(String, String)>,
+    pub body:    Vec<u8>,
+}
+
+impl HttpRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+
+    pub fn query(&self, name: &str) -> Option<&str> {
+        self.query.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+}
+
+/// Why a request couldn't be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReadError {
+    /// The connection closed, or failed, before a whole request arrived.
+    Closed,
+    /// Not HTTP/1.x that can be answered, with why.
+    Malformed(String),
+    /// A chunked body, which isn't supported.
+    LengthRequired,
+    /// The head or body is over its limit.
+    TooLarge,
+}
+
+impl ReadError {
+    /// The status line and message to answer with, if any.
+    pub fn response(&self) -> Option<(u16, String)> {
+        match self {
+            Self::Closed           => None,
+            Self::Malformed(why)   => Some((400, why.clone())),
+            Self::LengthRequired   => Some((411, "send the body with a Content-Length".to_string())),
+            Self::TooLarge         => Some((413, format!("requests are limited to {MAX_BODY_BYTES} bytes"))),
+        }
+    }
+}
+
+/// Reads one request: its head, then as much body as `Content-Length` says.
+pub async fn read_request(stream: &mut (impl AsyncRead + Unpin)) -> Result<HttpRequest, ReadError> {
+    let mut buffer = Vec::with_capacity(1024);
+    let head_end = loop {
+        if let Some(at) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break at;
+        }
+        if buffer.len() > MAX_HEAD_BYTES {
+            return Err(ReadError::TooLarge);
+        }
+        let mut chunk = [0; 4096];
+        match stream.read(&mut chunk).await {
+            Ok(0) | Err(_) => return Err(ReadError::Closed),
+            Ok(read)       => buffer.extend_from_slice(&chunk[..read]),
+        }
+    };
+
+    let head = std::str::from_utf8(&buffer[..head_end]).map_err(|_| ReadError::Malformed("the request head isn't UTF-8".to_string()))?;
+    let mut request = parse_head(head).map_err(ReadError::Malformed)?;
+    if request.header("transfer-encoding").is_some() {
+        return Err(ReadError::LengthRequired);
+    }
+    let length = match request.header("content-length") {
+        Some(length) => length.parse::<usize>().map_err(|_| ReadError::Malformed(format!("bad Content-Length '{length}'")))?,
+        None         => 0,
+    };
+    if length > MAX_BODY_BYTES {
+        return Err(ReadError::TooLarge);
+    }
+
+    let mut body = buffer.split_off(head_end + 4);
+    while body.len() < length {
+        let mut chunk = vec![0; (length - body.len()).min(64 * 1024)];
+        match stream.read(&mut chunk).await {
+            Ok(0) | Err(_) => return Err(ReadError::Closed),
+            Ok(read)       => body.extend_from_slice(&chunk[..read]),
+        }
+    }
+    body.truncate(length);
+    request.body = body;
+    Ok(request)
+}
+
+/// Parses a request line and headers, without the blank line after them.
+fn parse_head(head: &str) -> Result<HttpRequest, String> {
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().unwrap_or_default();
+    let mut parts = request_line.split(' ');
+    let (Some(method), Some(target), Some(version), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+        return Err(format!("bad request line '{request_line}'"));
+    };
+    if !version.starts_with("HTTP/1.") {
+        return Err(format!("unsupported protocol '{version}'"));
+    }
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (key.to_string(), value.to_string())
+        })
+        .collect();
+    let headers = lines
+        .map(|line| {
+            line.split_once(':')
+                .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+                .ok_or_else(|| format!("bad header line '{line}'"))
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(HttpRequest { method: method.to_string(), path: path.to_string(), query, headers, body: Vec::new() })
+}
+
+/// What a job is doing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobKind {
+    Solve { difficulty: u64 },
+    Validate { endpoint: String },
+}
+
+/// A job for `GET /status`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JobInfo {
+    pub id:         u64,
+    #[serde(flatten)]
+    pub kind:       JobKind,
+    pub running_ms: u64,
+}
+
+#[derive(Debug, Default)]
+struct Ledger {
+    next_id:   u64,
+    running:   BTreeMap<u64, (JobKind, Instant)>,
+    succeeded: u64,
+    failed:    u64,
+    /// Turned away with 429.
+    rejected:  u64,
+}
+
+/// The solves and validates in flight, at most `max_concurrent` of them.
+#[derive(Debug)]
+pub struct Jobs {
+    max_concurrent: usize,
+    ledger:         Mutex<Ledger>,
+}
+
+/// A running job; it leaves [`Jobs`] when dropped, counting as failed
+/// unless [`JobGuard::succeeded`] was called.
+pub struct JobGuard {
+    jobs:      Arc<Jobs>,
+    id:        u64,
+    succeeded: bool,
+}
+
+impl Jobs {
+    pub fn new(max_concurrent: usize) -> Arc<Self> {
+        Arc::new(Self { max_concurrent, ledger: Mutex::new(Ledger::default()) })
+    }
+
+    fn ledger(&self) -> std::sync::MutexGuard<'_, Ledger> {
+        self.ledger.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Starts a job, unless `max_concurrent` are already running.
+    pub fn try_start(self: &Arc<Self>, kind: JobKind) -> Option<JobGuard> {
+        let mut ledger = self.ledger();
+        if ledger.running.len() >= self.max_concurrent {
+            ledger.rejected += 1;
+            return None;
+        }
+        ledger.next_id += 1;
+        let id = ledger.next_id;
+        ledger.running.insert(id, (kind, Instant::now()));
+        Some(JobGuard { jobs: Arc::clone(self), id, succeeded: false })
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.ledger().running.len()
+    }
+
+    /// What `GET /status` answers.
+    pub fn status(&self) -> Value {
+        let ledger = self.ledger();
+        let in_flight: Vec<JobInfo> = ledger.running.iter()
+            .map(|(&id, (kind, started))| JobInfo { id, kind: kind.clone(), running_ms: started.elapsed().as_millis() as u64 })
+            .collect();
+        json!({
+            "max_concurrent": self.max_concurrent,
+            "in_flight":      in_flight,
+            "succeeded":      ledger.succeeded,
+            "failed":         ledger.failed,
+            "rejected":       ledger.rejected,
+        })
+    }
+}
+
+impl JobGuard {
+    pub fn succeeded(mut self) {
+        self.succeeded = true;
+    }
+}
+
+impl Drop for JobGuard {
+    fn drop(&mut self) {
+        let mut ledger = self.jobs.ledger();
+        ledger.running.remove(&self.id);
+        match self.succeeded {
+            true  => ledger.succeeded += 1,
+            false => ledger.failed += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authorized() {
+        assert!(authorized(None, None));
+        assert!(authorized(Some("s3cret-token"), Some("Bearer s3cret-token")));
+        assert!(authorized(Some("s3cret-token"), Some("bearer  s3cret-token")));
+        assert!(!authorized(Some("s3cret-token"), None));
+        assert!(!authorized(Some("s3cret-token"), Some("Bearer s3cret-toke")));
+        assert!(!authorized(Some("s3cret-token"), Some("Basic s3cret-token")));
+        assert!(!authorized(Some("s3cret-token"), Some("s3cret-token")));
+    }
+
+    #[tokio::test]
+    async fn test_read_request() {
+        let raw = b"POST /solve?max_time=30s HTTP/1.1\r\nHost: localhost\r\nContent-Length: 7\r\n\r\n{\"a\":1}";
+        let request = read_request(&mut &raw[..]).await.unwrap();
+
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/solve");
+        assert_eq!(request.query("max_time"), Some("30s"));
+        assert_eq!(request.header("content-length"), Some("7"));
+        assert_eq!(request.body, b"{\"a\":1}");
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_requests() {
+        let chunked = b"POST /solve HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n";
+        assert_eq!(read_request(&mut &chunked[..]).await, Err(ReadError::LengthRequired));
+
+        let huge = format!("POST /solve HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY_BYTES + 1);
+        assert_eq!(read_request(&mut huge.as_bytes()).await, Err(ReadError::TooLarge));
+
+        let truncated = b"POST /solve HTTP/1.1\r\nContent-Length: 10\r\n\r\n{}";
+        assert_eq!(read_request(&mut &truncated[..]).await, Err(ReadError::Closed));
+
+        let garbage = b"hello\r\n\r\n";
+        assert!(matches!(read_request(&mut &garbage[..]).await, Err(ReadError::Malformed(_))));
+    }
+
+    #[test]
+    fn test_jobs_stay_within_the_limit() {
+        let jobs = Jobs::new(1);
+        let first = jobs.try_start(JobKind::Solve { difficulty: 5 }).unwrap();
+        assert!(jobs.try_start(JobKind::Validate { endpoint: "https://a.example".to_string() }).is_none());
+        assert_eq!(jobs.status()["in_flight"][0]["kind"], "solve");
+        assert_eq!(jobs.status()["in_flight"][0]["difficulty"], 5);
+
+        first.succeeded();
+        let second = jobs.try_start(JobKind::Validate { endpoint: "https://a.example".to_string() }).unwrap();
+        drop(second);
+
+        let status = jobs.status();
+        assert_eq!(jobs.in_flight(), 0);
+        assert_eq!((status["succeeded"].as_u64(), status["failed"].as_u64(), status["rejected"].as_u64()), (Some(1), Some(1), Some(1)));
+    }
+
+    #[test]
+    fn test_policy() {
+        let policy = ServePolicy::from_config(&ServeConfig::default()).unwrap();
+        assert_eq!(policy.time_for(None), Duration::from_secs(300));
+        assert_eq!(policy.time_for(Some(Duration::from_secs(30))), Duration::from_secs(30));
+        assert_eq!(policy.time_for(Some(Duration::from_secs(3_600))), Duration::from_secs(300));
+
+        let config = ServeConfig { max_concurrent: 0, ..ServeConfig::default() };
+        assert!(ServePolicy::from_config(&config).unwrap_err().contains("max_concurrent"));
+        let config = ServeConfig { token: Some("abc".to_string()), ..ServeConfig::default() };
+        assert!(ServePolicy::from_config(&config).unwrap_err().contains("serve.token"));
+    }
+}
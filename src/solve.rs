@@ -0,0 +1,1158 @@
+use clap::ValueEnum;
+use color_eyre::eyre::eyre;
+use ironshield::{
+    ClientConfig,
+    IronShieldChallenge,
+    IronShieldChallengeResponse,
+    ProgressTracker,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{oneshot, OwnedSemaphorePermit, Semaphore};
+
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::client::SolveOptions;
+use crate::tuning::SolverOptions;
+
+/// Attempts a worker makes per call into the core before checking
+/// whether its job is still wanted, unless `[solver] batch_size` or
+/// `--solver-opt batch_size=N` says otherwise.
+pub const CHUNK_ATTEMPTS: u64 = 500_000;
+
+/// A job gives up once this many times its recommended attempts have
+/// been tried, unless `[solver] give_up_factor` or `--solver-opt
+/// give_up_factor=N` says otherwise. Running out by chance is
+/// vanishingly unlikely; this is what stops a challenge the core keeps
+/// rejecting from spinning forever.
+pub const GIVE_UP_FACTOR: u64 = 20;
+
+type SolveResult = Result<IronShieldChallengeResponse, String>;
+
+/// Share of the cores the `balanced` strategy uses.
+const BALANCED_CORE_SHARE:  f64 = 0.8;
+/// Share of the cores the `efficient` strategy uses.
+const EFFICIENT_CORE_SHARE: f64 = 0.25;
+
+/// What a solve optimises for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Strategy {
+    /// Every core at normal priority: the shortest wall-clock time.
+    Fast,
+    /// Most cores at normal priority, leaving some headroom.
+    #[default]
+    Balanced,
+    /// A quarter of the cores at idle priority: the least disruption.
+    Efficient,
+}
+
+impl Strategy {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Fast      => "fast",
+            Self::Balanced  => "balanced",
+            Self::Efficient => "efficient",
+        }
+    }
+}
+
+/// The scheduling priority of solver threads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Priority {
+    Normal,
+    Idle,
+}
+
+impl Priority {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Idle   => "idle",
+        }
+    }
+}
+
+/// How many threads a solve uses, before the strategy fills in `Auto`.
+///
+/// In the config file this is `threading = "auto"`, `"single"` or a
+/// thread count. Files that only set the older `num_threads` keep
+/// working: unset or `0` is `auto`, `1` is `single`, and anything else
+/// is that many threads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "ThreadingValue", into = "ThreadingValue")]
+pub enum ThreadingMode {
+    /// As many threads as the strategy picks.
+    #[default]
+    Auto,
+    /// Exactly this many threads, always more than one.
+    Fixed(usize),
+    /// One thread.
+    Single,
+}
+
+/// `threading` as written in the config file.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ThreadingValue {
+    Count(usize),
+    Name(String),
+}
+
+impl TryFrom<ThreadingValue> for ThreadingMode {
+    type Error = String;
+
+    fn try_from(value: ThreadingValue) -> Result<Self, Self::Error> {
+        match value {
+            ThreadingValue::Count(0)       => Err("`threading` must be at least 1".to_string()),
+            ThreadingValue::Count(threads) => Ok(Self::fixed(threads)),
+            ThreadingValue::Name(name)     => match name.as_str() {
+                "auto"   => Ok(Self::Auto),
+                "single" => Ok(Self::Single),
+                other    => Err(format!("Unknown threading '{other}'; expected auto, single or a thread count")),
+            },
+        }
+    }
+}
+
+impl From<ThreadingMode> for ThreadingValue {
+    fn from(mode: ThreadingMode) -> Self {
+        match mode {
+            ThreadingMode::Auto           => Self::Name("auto".to_string()),
+            ThreadingMode::Fixed(threads) => Self::Count(threads),
+            ThreadingMode::Single         => Self::Name("single".to_string()),
+        }
+    }
+}
+
+impl ThreadingMode {
+    /// An explicit thread count; one thread is [`ThreadingMode::Single`].
+    pub fn fixed(threads: usize) -> Self {
+        if threads <= 1 { Self::Single } else { Self::Fixed(threads) }
+    }
+
+    /// Maps the older `num_threads` setting.
+    pub fn from_num_threads(num_threads: Option<usize>) -> Self {
+        match num_threads {
+            None | Some(0) => Self::Auto,
+            Some(threads)  => Self::fixed(threads),
+        }
+    }
+
+    /// The mode for one solve: `--single-threaded` wins over
+    /// `--threads`, which wins over the config file.
+    ///
+    /// # Arguments
+    /// * `single_threaded`: `--single-threaded`.
+    /// * `threads`:         `--threads`.
+    /// * `configured`:      The config file's mode.
+    pub fn resolve(single_threaded: bool, threads: Option<usize>, configured: ThreadingMode) -> Self {
+        match threads {
+            _ if single_threaded => Self::Single,
+            Some(threads)        => Self::fixed(threads),
+            None                 => configured,
+        }
+    }
+}
+
+/// The threads a solve will use, derived from its strategy, and how
+/// they divide the work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThreadPlan {
+    pub strategy:     Strategy,
+    pub thread_count: usize,
+    pub priority:     Priority,
+    /// Whether the thread count was set explicitly rather than by the strategy.
+    pub overridden:   bool,
+    /// How the threads divide the nonce space.
+    #[serde(default)]
+    pub work_split:   WorkSplit,
+    /// The `[solver]` tuning options, such as the batch size.
+    #[serde(default)]
+    pub solver:       SolverOptions,
+}
+
+impl ThreadPlan {
+    /// Derives the plan for a solve.
+    ///
+    /// # Arguments
+    /// * `strategy`: The chosen strategy.
+    /// * `cores`:    Logical cores on this machine.
+    /// * `mode`:     The resolved threading mode; only `Auto` uses the strategy's count.
+    pub fn derive(strategy: Strategy, cores: usize, mode: ThreadingMode) -> Self {
+        let share = |fraction: f64| ((cores as f64 * fraction).floor() as usize).max(1);
+        let preset = match strategy {
+            Strategy::Fast      => cores.max(1),
+            Strategy::Balanced  => share(BALANCED_CORE_SHARE),
+            Strategy::Efficient => share(EFFICIENT_CORE_SHARE),
+        };
+        let (thread_count, overridden) = match mode {
+            ThreadingMode::Auto           => (preset, false),
+            ThreadingMode::Fixed(threads) => (threads.max(1), true),
+            ThreadingMode::Single         => (1, false),
+        };
+        let priority = match strategy {
+            Strategy::Efficient => Priority::Idle,
+            _                   => Priority::Normal,
+        };
+
+        Self { strategy, thread_count, priority, overridden, work_split: WorkSplit::default(), solver: SolverOptions::default() }
+    }
+
+    /// This plan, dividing its work as `work_split` and `solver` say.
+    pub fn tuned(self, work_split: WorkSplit, solver: SolverOptions) -> Self {
+        Self { work_split, solver, ..self }
+    }
+
+    /// e.g. "balanced: 6 threads at normal priority".
+    pub fn describe(&self) -> String {
+        let threads = if self.thread_count == 1 { "1 thread".to_string() } else { format!("{} threads", self.thread_count) };
+        let source = if self.overridden { " (thread count set explicitly)" } else { "" };
+        format!("{}: {threads} at {} priority{source}", self.strategy.name(), self.priority.name())
+    }
+}
+
+/// How a job's nonce space is divided between workers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WorkSplit {
+    /// Each worker owns every n-th nonce, fixed up front.
+    Stride,
+    /// Workers take contiguous ranges from a shared cursor, so a slow
+    /// worker holds back at most one range.
+    #[default]
+    Chunked,
+}
+
+impl WorkSplit {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Stride  => "stride",
+            Self::Chunked => "chunked",
+        }
+    }
+}
+
+/// Hands out the nonce ranges of one job, `chunk` attempts at a time.
+struct Ranges {
+    split:   WorkSplit,
+    workers: usize,
+    /// Attempts per range: the solver's `batch_size`.
+    chunk:   u64,
+    /// Next unclaimed nonce, for [`WorkSplit::Chunked`].
+    cursor:  AtomicU64,
+}
+
+impl Ranges {
+    fn new(split: WorkSplit, workers: usize, chunk: u64) -> Self {
+        Self { split, workers: workers.max(1), chunk: chunk.max(1), cursor: AtomicU64::new(0) }
+    }
+
+    /// The next range for a worker.
+    ///
+    /// # Arguments
+    /// * `thread_id`: The worker's index.
+    /// * `taken`:     Ranges this worker has already searched.
+    ///
+    /// # Returns
+    /// * `(u64, usize)`: The range's first nonce and the stride within it.
+    fn next(&self, thread_id: usize, taken: u64) -> (u64, usize) {
+        match self.split {
+            WorkSplit::Stride  => (thread_id as u64 + taken * self.chunk * self.workers as u64, self.workers),
+            WorkSplit::Chunked => (self.cursor.fetch_add(self.chunk, Ordering::Relaxed), 1),
+        }
+    }
+}
+
+/// Runs one worker's share of a search: `chunk` is called for each
+/// range `ranges` hands this worker until it finds something or `stop`
+/// asks it to stop. `stop` is checked between ranges, so a worker
+/// stops at most one range after being told to.
+///
+/// # Arguments
+/// * `ranges`:    Where the worker's ranges come from.
+/// * `thread_id`: The worker's index.
+/// * `stop`:      Whether to give up; called with the attempts made so far.
+/// * `chunk`:     Searches one range; called with its first nonce, its
+///                stride and the attempts made before it.
+///
+/// # Returns
+/// * `Option<T>`: Whatever `chunk` found, or `None` if stopped.
+fn search_chunks<T>(
+    ranges:    &Ranges,
+    thread_id: usize,
+    stop:      impl Fn(u64) -> bool,
+    mut chunk: impl FnMut(u64, usize, u64) -> Option<T>,
+) -> Option<T> {
+    let mut taken = 0u64;
+
+    while !stop(taken * ranges.chunk) {
+        let (offset, stride) = ranges.next(thread_id, taken);
+        if let Some(found) = chunk(offset, stride, taken * ranges.chunk) {
+            return Some(found);
+        }
+        taken += 1;
+    }
+    None
+}
+
+/// Times how long `threads` simulated workers take to search every
+/// nonce below `chunks` ranges when worker 0 runs `slowdown`
+/// times slower than the rest, as on a descheduled vCPU. Each range
+/// takes `chunk_time` on a full-speed worker.
+///
+/// This is blocking; call it from `spawn_blocking`.
+pub fn simulate_coverage(
+    split:      WorkSplit,
+    threads:    usize,
+    slowdown:   u32,
+    chunks:     u64,
+    chunk_time: Duration,
+) -> Duration {
+    let threads = threads.max(1);
+    let ranges = Ranges::new(split, threads, CHUNK_ATTEMPTS);
+    let limit = chunks * ranges.chunk;
+    let start = Instant::now();
+
+    std::thread::scope(|scope| {
+        for thread_id in 0..threads {
+            let ranges = &ranges;
+            scope.spawn(move || {
+                search_chunks(ranges, thread_id, |_: u64| false, |offset, _, _| {
+                    if offset >= limit {
+                        return Some(());
+                    }
+                    std::thread::sleep(if thread_id == 0 { chunk_time * slowdown.max(1) } else { chunk_time });
+                    None
+                });
+            });
+        }
+    });
+    start.elapsed()
+}
+
+/// Progress reports each worker aims to forward per second.
+const TARGET_REPORTS_PER_SECOND: u64 = 2;
+
+/// Attempts between forwarded reports until the hash rate is known.
+pub const INITIAL_REPORT_INTERVAL: u64 = 200_000;
+
+/// Decides which of the core's progress callbacks a worker passes on
+/// to the tracker. The core calls back at a fixed attempt count, which
+/// on a fast machine means hundreds of calls a second; the pacer spaces
+/// forwarded reports to about [`TARGET_REPORTS_PER_SECOND`] by
+/// re-deriving the interval from the hash rate after each one.
+struct ReportPacer {
+    /// Cumulative attempts at which the next report is due.
+    next_at: AtomicU64,
+}
+
+impl ReportPacer {
+    fn new() -> Self {
+        Self { next_at: AtomicU64::new(INITIAL_REPORT_INTERVAL) }
+    }
+
+    fn is_due(&self, total_attempts: u64) -> bool {
+        total_attempts >= self.next_at.load(Ordering::Relaxed)
+    }
+
+    /// Schedules the next report after forwarding one at `total_attempts`.
+    fn reschedule(&self, total_attempts: u64, hash_rate: u64) {
+        let interval = (hash_rate / TARGET_REPORTS_PER_SECOND).max(1);
+        self.next_at.store(total_attempts + interval, Ordering::Relaxed);
+    }
+}
+
+/// One challenge being solved by every worker in a pool.
+struct Job {
+    challenge:    Arc<IronShieldChallenge>,
+    tracker:      Option<Arc<dyn ProgressTracker>>,
+    ranges:       Ranges,
+    /// Attempts after which the job fails with no solution.
+    max_attempts: u64,
+    /// Attempts made so far across all workers.
+    searched:     AtomicU64,
+    /// Workers that haven't stopped searching yet.
+    remaining:    AtomicUsize,
+    /// Set once the job is settled, so workers can stop without taking the lock.
+    cancelled:    AtomicBool,
+    /// Taken by whichever worker settles the job.
+    result:       Mutex<Option<oneshot::Sender<SolveResult>>>,
+}
+
+impl Job {
+    /// A job for `workers` workers, dividing the nonce space and giving
+    /// up as `plan` says.
+    fn new(
+        challenge: Arc<IronShieldChallenge>,
+        tracker:   Option<Arc<dyn ProgressTracker>>,
+        plan:      &ThreadPlan,
+        workers:   usize,
+        result:    oneshot::Sender<SolveResult>,
+    ) -> Self {
+        Self {
+            max_attempts: challenge.recommended_attempts.max(1).saturating_mul(plan.solver.give_up_factor),
+            challenge,
+            tracker,
+            ranges:       Ranges::new(plan.work_split, workers, plan.solver.batch_size),
+            searched:     AtomicU64::new(0),
+            remaining:    AtomicUsize::new(workers),
+            cancelled:    AtomicBool::new(false),
+            result:       Mutex::new(Some(result)),
+        }
+    }
+
+    /// Whether the job was solved, or its caller stopped waiting for it.
+    fn is_settled(&self) -> bool {
+        if self.cancelled.load(Ordering::Acquire) {
+            return true;
+        }
+        let abandoned = self.result.lock().unwrap().as_ref().is_none_or(|sender| sender.is_closed());
+        if abandoned {
+            self.cancelled.store(true, Ordering::Release);
+        }
+        abandoned
+    }
+
+    /// Settles the job; only the first call has any effect.
+    fn finish(&self, result: SolveResult) {
+        self.cancelled.store(true, Ordering::Release);
+        if let Some(sender) = self.result.lock().unwrap().take() {
+            let _ = sender.send(result);
+        }
+    }
+
+    /// Searches this worker's share of the nonce space until the job
+    /// settles, runs out of attempts or the pool shuts down.
+    fn search(&self, thread_id: usize, shutdown: &AtomicBool) {
+        let start = Instant::now();
+        let pacer = ReportPacer::new();
+        let stop = |_: u64| {
+            shutdown.load(Ordering::Relaxed)
+                || self.is_settled()
+                || self.searched.load(Ordering::Relaxed) >= self.max_attempts
+        };
+
+        let found = search_chunks(&self.ranges, thread_id, stop, |offset, stride, attempts_before_chunk| {
+            // The core's callback can't ask it to return early, so the flag
+            // is only honoured between chunks; here it just silences
+            // progress from a job that is already over.
+            let report = |attempts: u64| {
+                if self.cancelled.load(Ordering::Relaxed) {
+                    return;
+                }
+                let Some(tracker) = &self.tracker else {
+                    return;
+                };
+                let total = attempts_before_chunk + attempts;
+                if !pacer.is_due(total) {
+                    return;
+                }
+                let elapsed = start.elapsed();
+                let hash_rate = (total as f64 / elapsed.as_secs_f64().max(1e-3)) as u64;
+                pacer.reschedule(total, hash_rate);
+                tracker.on_progress(thread_id, total, hash_rate, elapsed);
+            };
+            let found = ironshield_core::find_solution_multi_threaded(
+                &self.challenge,
+                Some(self.ranges.chunk),
+                Some(offset as usize),
+                Some(stride),
+                Some(&report),
+            );
+            if found.is_err() {
+                self.searched.fetch_add(self.ranges.chunk, Ordering::Relaxed);
+            }
+            found.ok()
+        });
+
+        if let Some(solution) = found {
+            self.finish(Ok(solution));
+        }
+    }
+
+    /// Marks one worker as done; the last one out fails the job if
+    /// nobody solved it.
+    fn worker_done(&self) {
+        if self.remaining.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.finish(Err(format!(
+                "No solution found in {} attempts",
+                self.searched.load(Ordering::Relaxed),
+            )));
+        }
+    }
+}
+
+/// Long-lived solver threads that take one challenge at a time.
+///
+/// Modes that solve many challenges in one process use a pool so every
+/// solve after the first skips thread startup and cache warmup. Each
+/// job is split across the workers it is given as its [`ThreadPlan`]
+/// says; jobs given to a busy worker queue up behind its current one.
+/// Dropping the pool stops any running job and joins the threads.
+pub struct SolverPool {
+    /// Each worker's queue, with the thread id the job knows it by.
+    senders:  Vec<mpsc::Sender<(Arc<Job>, usize)>>,
+    /// Jobs queued or running on each worker, so new jobs go to the idlest.
+    load:     Arc<[AtomicUsize]>,
+    workers:  Vec<JoinHandle<()>>,
+    shutdown: Arc<AtomicBool>,
+    priority: Priority,
+}
+
+impl SolverPool {
+    /// Starts `threads` workers, at least one, at `priority`.
+    pub fn new(threads: usize, priority: Priority) -> Self {
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let load: Arc<[AtomicUsize]> = (0..threads.max(1)).map(|_| AtomicUsize::new(0)).collect();
+        let (senders, workers) = (0..threads.max(1))
+            .map(|worker| {
+                let (sender, receiver) = mpsc::channel::<(Arc<Job>, usize)>();
+                let (shutdown, load) = (Arc::clone(&shutdown), Arc::clone(&load));
+                let handle = std::thread::Builder::new()
+                    .name(format!("ironshield-solver-{worker}"))
+                    .spawn(move || {
+                        if priority == Priority::Idle && !crate::resource::lower_thread_priority() {
+                            crate::logging::file_event(
+                                crate::logging::LogCategory::Warning,
+                                format_args!("Could not lower the priority of solver thread {worker}"),
+                            );
+                        }
+                        for (job, thread_id) in receiver {
+                            job.search(thread_id, &shutdown);
+                            job.worker_done();
+                            load[worker].fetch_sub(1, Ordering::Relaxed);
+                        }
+                    })
+                    .expect("failed to spawn a solver thread");
+                (sender, handle)
+            })
+            .unzip();
+
+        Self { senders, load, workers, shutdown, priority }
+    }
+
+    pub fn thread_count(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Solves a challenge on `plan.thread_count` of the pool's workers,
+    /// the least busy ones, with the plan's work split and batch size.
+    /// A plan the [`ThreadBudget`] cut down runs on fewer workers than
+    /// the pool has; the job numbers its threads from 0 whichever
+    /// workers run it. Dropping the returned future abandons the job;
+    /// the workers move on within one chunk.
+    ///
+    /// # Arguments
+    /// * `plan`:      Workers to use, at most the pool's, and how they divide the work.
+    /// * `challenge`: The challenge to solve.
+    /// * `tracker`:   Receives per-thread progress.
+    pub async fn solve(
+        &self,
+        plan:      &ThreadPlan,
+        challenge: Arc<IronShieldChallenge>,
+        tracker:   Option<Arc<dyn ProgressTracker>>,
+    ) -> SolveResult {
+        let threads = plan.thread_count.clamp(1, self.senders.len());
+        let mut workers: Vec<usize> = (0..self.senders.len()).collect();
+        workers.sort_by_key(|&worker| self.load[worker].load(Ordering::Relaxed));
+
+        let (result_tx, result_rx) = oneshot::channel();
+        let job = Arc::new(Job::new(challenge, tracker, plan, threads, result_tx));
+        for (thread_id, &worker) in workers[..threads].iter().enumerate() {
+            self.load[worker].fetch_add(1, Ordering::Relaxed);
+            if self.senders[worker].send((Arc::clone(&job), thread_id)).is_err() {
+                self.load[worker].fetch_sub(1, Ordering::Relaxed);
+                job.worker_done();
+            }
+        }
+        result_rx.await.unwrap_or_else(|_| Err("The solver pool shut down".to_string()))
+    }
+}
+
+impl Drop for SolverPool {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        // Closing the channels ends each worker's loop once its current chunk is done.
+        self.senders.clear();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Solver threads shared by every solve of one [`Solver`] and its
+/// clones, so solves running side by side, as in `batch --schedule
+/// parallel` or the proxy, split the cores instead of each taking its
+/// preset share.
+pub struct ThreadBudget {
+    total:   usize,
+    permits: Arc<Semaphore>,
+}
+
+/// Threads granted to one solve; they go back to the budget on drop.
+pub struct ThreadGrant {
+    threads: usize,
+    wanted:  usize,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl ThreadBudget {
+    /// A budget of `total` threads, at least one.
+    pub fn new(total: usize) -> Self {
+        let total = total.max(1);
+        Self { total, permits: Arc::new(Semaphore::new(total)) }
+    }
+
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Threads granted to solves that are still running.
+    pub fn in_use(&self) -> usize {
+        self.total - self.permits.available_permits()
+    }
+
+    /// Waits until at least one thread is free, then takes as many more
+    /// of `wanted` as are free right now. A solve never waits for its
+    /// full share, so one asking for every core can't stall the others.
+    pub async fn grant(&self, wanted: usize) -> ThreadGrant {
+        let wanted = wanted.clamp(1, self.total);
+        let mut permit = Arc::clone(&self.permits).acquire_owned().await
+            .expect("the thread budget is never closed");
+        let mut extra = (wanted - 1).min(self.permits.available_permits());
+        while extra > 0 {
+            match Arc::clone(&self.permits).try_acquire_many_owned(extra as u32) {
+                Ok(more) => {
+                    permit.merge(more);
+                    break;
+                }
+                // Another solve took some in between; settle for what is left.
+                Err(_) => extra = (extra - 1).min(self.permits.available_permits()),
+            }
+        }
+        ThreadGrant { threads: 1 + extra, wanted, _permit: permit }
+    }
+}
+
+impl ThreadGrant {
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// The threads the solve asked for, capped at the budget.
+    pub fn wanted(&self) -> usize {
+        self.wanted
+    }
+
+    /// `plan` cut down to the granted threads.
+    pub fn apply(&self, plan: &ThreadPlan) -> ThreadPlan {
+        ThreadPlan { thread_count: self.threads.min(plan.thread_count), ..*plan }
+    }
+}
+
+/// How solves pick their threads and check their solutions, past the
+/// strategy and thread count in [`SolveOptions`]: the config file's
+/// `threading`, `--work-split`, `--solver-opt`, `--total-threads` and
+/// `--skip-local-verify`. Clones share the thread budget and the pool,
+/// so every solve of one client splits the same cores.
+#[derive(Clone)]
+pub struct Solver {
+    /// The config file's `threading`, which replaces `num_threads`.
+    pub threading:         Option<ThreadingMode>,
+    /// Threads picked at a retry prompt, which win over everything.
+    pub retry_threads:     Option<usize>,
+    /// How solves divide their nonce space, as `--work-split`.
+    pub work_split:        WorkSplit,
+    /// The `[solver]` tuning options, after `--solver-opt`.
+    pub tuning:            SolverOptions,
+    /// Hand solutions back unchecked, as `--skip-local-verify`, to see
+    /// what the server makes of them.
+    pub skip_local_verify: bool,
+    budget:                Arc<ThreadBudget>,
+    pool:                  Option<Arc<SolverPool>>,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self {
+            threading:         None,
+            retry_threads:     None,
+            work_split:        WorkSplit::default(),
+            tuning:            SolverOptions::default(),
+            skip_local_verify: false,
+            budget:            Arc::new(ThreadBudget::new(num_cpus::get())),
+            pool:              None,
+        }
+    }
+}
+
+impl Solver {
+    /// Caps the solver threads of every solve running at once, as
+    /// `--total-threads`; by default, the number of cores.
+    pub fn with_total_threads(mut self, total: Option<usize>) -> Self {
+        self.budget = Arc::new(ThreadBudget::new(total.unwrap_or_else(num_cpus::get)));
+        self
+    }
+
+    /// Routes multithreaded solves through a [`SolverPool`] sized for
+    /// `plan`, for modes that solve repeatedly. Keeps the pool there is.
+    pub fn with_pool(mut self, plan: &ThreadPlan) -> Self {
+        if self.pool.is_none() {
+            self.pool = Some(Arc::new(SolverPool::new(plan.thread_count, plan.priority)));
+        }
+        self
+    }
+
+    pub fn budget(&self) -> &ThreadBudget {
+        &self.budget
+    }
+
+    /// The threads a solve with `options` and `config` will use. A
+    /// thread count picked at a retry prompt wins over everything; then
+    /// `--single-threaded` and `--threads` win over the config file,
+    /// which wins over the strategy.
+    pub fn plan(&self, options: SolveOptions, config: &ClientConfig) -> ThreadPlan {
+        let configured = self.threading.unwrap_or_else(|| ThreadingMode::from_num_threads(config.num_threads));
+        let mode = match self.retry_threads {
+            Some(threads) => ThreadingMode::fixed(threads),
+            None          => ThreadingMode::resolve(options.single_threaded, options.threads, configured),
+        };
+        ThreadPlan::derive(options.strategy, num_cpus::get(), mode).tuned(self.work_split, self.tuning)
+    }
+
+    /// Searches for a solution with `plan`, on the pool if there is one
+    /// that fits and otherwise on threads started for this solve alone.
+    /// The threads must already be granted by the [`ThreadBudget`].
+    ///
+    /// Single-threaded solves run the same chunked search on one thread,
+    /// so they report progress like multithreaded ones.
+    pub async fn search(
+        &self,
+        challenge: Arc<IronShieldChallenge>,
+        plan:      &ThreadPlan,
+        tracker:   Option<Arc<dyn ProgressTracker>>,
+    ) -> color_eyre::Result<IronShieldChallengeResponse> {
+        let result = match pool_for(self.pool.as_deref(), plan) {
+            Some(pool) => pool.solve(plan, challenge, tracker).await,
+            None       => solve_on_new_threads(challenge, plan, tracker).await,
+        };
+        result.map_err(|e| eyre!(e))
+    }
+
+    /// Checks a solution with the core before it can be submitted, so a
+    /// bad one fails here instead of burning the challenge.
+    pub fn check(&self, challenge: &IronShieldChallenge, solution: &IronShieldChallengeResponse) -> Result<(), InvalidSolution> {
+        if self.skip_local_verify || verifies(challenge, solution.solution) {
+            return Ok(());
+        }
+        Err(InvalidSolution::new(challenge, solution.solution))
+    }
+}
+
+
+/// Whether `nonce` solves `challenge`, by the core's own check.
+pub fn verifies(challenge: &IronShieldChallenge, nonce: i64) -> bool {
+    ironshield_core::verify_ironshield_solution(challenge, nonce)
+}
+
+/// A solver result the core's own check rejects. Short of an injected
+/// failure, this means the solver and the verifier disagree, most
+/// likely because of a skewed or miscompiled `ironshield-core`, so
+/// everything needed to reproduce it is kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidSolution {
+    pub nonce:                i64,
+    pub website_id:           String,
+    pub random_nonce:         String,
+    pub recommended_attempts: u64,
+    pub expiration_time:      i64,
+    pub versions:             crate::benchmark::Versions,
+}
+
+impl InvalidSolution {
+    pub fn new(challenge: &IronShieldChallenge, nonce: i64) -> Self {
+        Self {
+            nonce,
+            website_id:           challenge.website_id.to_string(),
+            random_nonce:         challenge.random_nonce.to_string(),
+            recommended_attempts: challenge.recommended_attempts,
+            expiration_time:      challenge.expiration_time,
+            versions:             crate::benchmark::Versions::current(),
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidSolution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Not submitting nonce {}: ironshield-core {} rejects it for the challenge it was solved for \
+             (website {}, random nonce {}, recommended attempts {}, expires {}) in ironshield {}. \
+             The solver and verifier disagree, usually because of a mismatched ironshield-core; \
+             `--skip-local-verify` submits it anyway.",
+            self.nonce,
+            self.versions.core,
+            self.website_id,
+            self.random_nonce,
+            self.recommended_attempts,
+            self.expiration_time,
+            self.versions.cli,
+        )
+    }
+}
+
+impl std::error::Error for InvalidSolution {}
+
+/// `pool`, if it can run `plan`: at the plan's priority, with at least
+/// as many workers as the plan's threads. A plan cut down by the
+/// [`ThreadBudget`] runs on some of the workers.
+fn pool_for<'a>(pool: Option<&'a SolverPool>, plan: &ThreadPlan) -> Option<&'a SolverPool> {
+    pool.filter(|pool| pool.thread_count() >= plan.thread_count && pool.priority == plan.priority)
+}
+
+/// Solves on threads started for this solve. Every
+/// thread stops within one chunk of the first solution, and all of
+/// them have exited by the time this returns.
+async fn solve_on_new_threads(
+    challenge: Arc<IronShieldChallenge>,
+    plan:      &ThreadPlan,
+    tracker:   Option<Arc<dyn ProgressTracker>>,
+) -> SolveResult {
+    let pool = SolverPool::new(plan.thread_count, plan.priority);
+    let result = pool.solve(plan, challenge, tracker).await;
+    // Joining waits out the other threads' current chunk; keep that off the runtime.
+    let _ = tokio::task::spawn_blocking(move || drop(pool)).await;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_concurrent_grants_stay_within_the_budget() {
+        let budget = Arc::new(ThreadBudget::new(8));
+        let allocated = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let solves: Vec<_> = (0..3).map(|_| {
+            let (budget, allocated, peak) = (Arc::clone(&budget), Arc::clone(&allocated), Arc::clone(&peak));
+            tokio::spawn(async move {
+                for _ in 0..20 {
+                    let grant = budget.grant(6).await;
+                    assert!((1..=6).contains(&grant.threads()));
+                    let now = allocated.fetch_add(grant.threads(), Ordering::SeqCst) + grant.threads();
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                    allocated.fetch_sub(grant.threads(), Ordering::SeqCst);
+                }
+            })
+        }).collect();
+        for solve in solves {
+            solve.await.unwrap();
+        }
+
+        assert!(peak.load(Ordering::SeqCst) <= 8, "peak {}", peak.load(Ordering::SeqCst));
+        assert_eq!(budget.in_use(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_grants_take_what_is_free() {
+        let budget = ThreadBudget::new(8);
+        let first = budget.grant(6).await;
+        let second = budget.grant(6).await;
+
+        assert_eq!((first.threads(), second.threads()), (6, 2));
+        assert_eq!(budget.in_use(), 8);
+
+        drop(first);
+        let third = budget.grant(20).await;
+        assert_eq!(third.threads(), 6);
+        assert_eq!(third.apply(&ThreadPlan::derive(Strategy::Fast, 16, ThreadingMode::Auto)).thread_count, 6);
+    }
+
+    #[test]
+    fn test_pool_shuts_down_on_drop() {
+        let pool = SolverPool::new(3, Priority::Normal);
+        assert_eq!(pool.thread_count(), 3);
+        drop(pool);
+    }
+
+    #[tokio::test]
+    async fn test_partial_grants_still_use_the_pool() {
+        struct ThreadNames(Mutex<Vec<(usize, Option<String>)>>);
+        impl ProgressTracker for ThreadNames {
+            fn on_progress(&self, thread_id: usize, _attempts: u64, _hash_rate: u64, _elapsed: Duration) {
+                self.0.lock().unwrap().push((thread_id, std::thread::current().name().map(str::to_string)));
+            }
+        }
+
+        let pool = SolverPool::new(4, Priority::Normal);
+        let budget = ThreadBudget::new(4);
+        let _other_solve = budget.grant(2).await;
+        let grant = budget.grant(4).await;
+        let plan = grant.apply(&ThreadPlan::derive(Strategy::Fast, 4, ThreadingMode::Auto));
+        assert_eq!(plan.thread_count, 2);
+        let pool = pool_for(Some(&pool), &plan).expect("a partial grant should still use the pool");
+
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(&[7; 32]);
+        let public_key = signing_key.verifying_key().to_bytes();
+        let challenge = Arc::new(IronShieldChallenge::new("test".to_string(), 10_000, signing_key, public_key));
+        let tracker = Arc::new(ThreadNames(Mutex::new(Vec::new())));
+        let solution = pool.solve(&plan, Arc::clone(&challenge), Some(tracker.clone() as Arc<dyn ProgressTracker>)).await;
+        let solution = solution.expect("the pool should solve the challenge");
+        Solver::default().check(&challenge, &solution).unwrap();
+
+        for (thread_id, name) in tracker.0.lock().unwrap().iter() {
+            assert!(*thread_id < 2, "thread id {thread_id} outside the grant");
+            assert!(name.as_deref().is_some_and(|name| name.starts_with("ironshield-solver-")), "{name:?}");
+        }
+    }
+
+    #[test]
+    fn test_pools_run_no_more_than_they_have() {
+        let pool = SolverPool::new(2, Priority::Normal);
+        let plan = |threads| ThreadPlan::derive(Strategy::Fast, 8, ThreadingMode::Fixed(threads));
+
+        assert!(pool_for(Some(&pool), &plan(1)).is_some());
+        assert!(pool_for(Some(&pool), &plan(3)).is_none());
+        let idle = ThreadPlan { priority: Priority::Idle, ..plan(1) };
+        assert!(pool_for(Some(&pool), &idle).is_none());
+    }
+
+    #[test]
+    fn test_pool_has_at_least_one_thread() {
+        assert_eq!(SolverPool::new(0, Priority::Normal).thread_count(), 1);
+    }
+
+    #[test]
+    fn test_strategy_presets() {
+        let plan = |strategy| ThreadPlan::derive(strategy, 16, ThreadingMode::Auto);
+
+        assert_eq!((plan(Strategy::Fast).thread_count, plan(Strategy::Fast).priority), (16, Priority::Normal));
+        assert_eq!((plan(Strategy::Balanced).thread_count, plan(Strategy::Balanced).priority), (12, Priority::Normal));
+        assert_eq!((plan(Strategy::Efficient).thread_count, plan(Strategy::Efficient).priority), (4, Priority::Idle));
+    }
+
+    #[test]
+    fn test_presets_keep_at_least_one_thread() {
+        assert_eq!(ThreadPlan::derive(Strategy::Efficient, 2, ThreadingMode::Auto).thread_count, 1);
+        assert_eq!(ThreadPlan::derive(Strategy::Balanced, 1, ThreadingMode::Auto).thread_count, 1);
+        assert_eq!(ThreadPlan::derive(Strategy::Fast, 0, ThreadingMode::Auto).thread_count, 1);
+    }
+
+    #[test]
+    fn test_explicit_threads_override_the_preset_but_not_its_priority() {
+        let plan = ThreadPlan::derive(Strategy::Efficient, 16, ThreadingMode::Fixed(10));
+
+        assert_eq!(plan.thread_count, 10);
+        assert_eq!(plan.priority, Priority::Idle);
+        assert!(plan.overridden);
+        assert_eq!(plan.describe(), "efficient: 10 threads at idle priority (thread count set explicitly)");
+    }
+
+    #[test]
+    fn test_single_threaded_wins_over_everything() {
+        let mode = ThreadingMode::resolve(true, Some(8), ThreadingMode::Fixed(4));
+        let plan = ThreadPlan::derive(Strategy::Fast, 16, mode);
+
+        assert_eq!(mode, ThreadingMode::Single);
+        assert_eq!(plan.thread_count, 1);
+        assert!(!plan.overridden);
+        assert_eq!(plan.describe(), "fast: 1 thread at normal priority");
+    }
+
+    #[test]
+    fn test_threading_resolution() {
+        use ThreadingMode::{Auto, Fixed, Single};
+
+        // (--single-threaded, --threads, config file) => mode
+        let cases = [
+            ((false, None,    Auto),          Auto),
+            ((false, None,    Fixed(4)),      Fixed(4)),
+            ((false, None,    Single),        Single),
+            ((false, Some(1), Auto),          Single),
+            ((false, Some(6), Single),        Fixed(6)),
+            ((false, Some(6), Fixed(4)),      Fixed(6)),
+            ((true,  None,    Auto),          Single),
+            ((true,  Some(6), Fixed(4)),      Single),
+        ];
+        for ((single_threaded, threads, configured), expected) in cases {
+            assert_eq!(
+                ThreadingMode::resolve(single_threaded, threads, configured),
+                expected,
+                "single_threaded={single_threaded} threads={threads:?} configured={configured:?}",
+            );
+        }
+    }
+
+    #[test]
+    fn test_num_threads_migration() {
+        assert_eq!(ThreadingMode::from_num_threads(None), ThreadingMode::Auto);
+        assert_eq!(ThreadingMode::from_num_threads(Some(0)), ThreadingMode::Auto);
+        assert_eq!(ThreadingMode::from_num_threads(Some(1)), ThreadingMode::Single);
+        assert_eq!(ThreadingMode::from_num_threads(Some(8)), ThreadingMode::Fixed(8));
+    }
+
+    #[test]
+    fn test_single_and_one_thread_plan_alike() {
+        let single = ThreadPlan::derive(Strategy::Balanced, 16, ThreadingMode::Single);
+        let one = ThreadPlan::derive(Strategy::Balanced, 16, ThreadingMode::fixed(1));
+
+        assert_eq!(single, one);
+    }
+
+    #[test]
+    fn test_threading_config_values() {
+        #[derive(Deserialize)]
+        struct File {
+            threading: ThreadingMode,
+        }
+        let parse = |text: &str| toml::from_str::<File>(text).map(|file| file.threading);
+
+        assert_eq!(parse("threading = \"auto\"").unwrap(), ThreadingMode::Auto);
+        assert_eq!(parse("threading = \"single\"").unwrap(), ThreadingMode::Single);
+        assert_eq!(parse("threading = 1").unwrap(), ThreadingMode::Single);
+        assert_eq!(parse("threading = 6").unwrap(), ThreadingMode::Fixed(6));
+        assert!(parse("threading = 0").is_err());
+        assert!(parse("threading = \"many\"").is_err());
+    }
+
+    #[test]
+    fn test_search_chunks_walks_the_stride() {
+        let ranges = Ranges::new(WorkSplit::Stride, 4, CHUNK_ATTEMPTS);
+        let mut offsets = Vec::new();
+        let found = search_chunks(&ranges, 1, |attempts| attempts >= 3 * CHUNK_ATTEMPTS, |offset, stride, _| {
+            offsets.push((offset, stride));
+            None::<()>
+        });
+
+        assert_eq!(found, None);
+        assert_eq!(offsets, [(1, 4), (1 + 4 * CHUNK_ATTEMPTS, 4), (1 + 8 * CHUNK_ATTEMPTS, 4)]);
+    }
+
+    #[test]
+    fn test_pacer_targets_two_reports_a_second() {
+        let pacer = ReportPacer::new();
+        assert!(!pacer.is_due(100_000));
+        assert!(pacer.is_due(INITIAL_REPORT_INTERVAL));
+
+        // At 10 Mh/s the next report is half a second, 5M attempts, away.
+        pacer.reschedule(INITIAL_REPORT_INTERVAL, 10_000_000);
+        assert!(!pacer.is_due(INITIAL_REPORT_INTERVAL + 4_999_999));
+        assert!(pacer.is_due(INITIAL_REPORT_INTERVAL + 5_000_000));
+
+        // A stalled rate still moves the next report forward.
+        pacer.reschedule(6_000_000, 0);
+        assert!(pacer.is_due(6_000_001));
+    }
+
+    #[test]
+    fn test_chunked_ranges_come_from_a_shared_cursor() {
+        let ranges = Ranges::new(WorkSplit::Chunked, 4, CHUNK_ATTEMPTS);
+
+        assert_eq!(ranges.next(3, 0), (0, 1));
+        assert_eq!(ranges.next(0, 0), (CHUNK_ATTEMPTS, 1));
+        assert_eq!(ranges.next(3, 1), (2 * CHUNK_ATTEMPTS, 1));
+    }
+
+    /// Ranges of 64 that three workers search while a fourth is held in
+    /// its first range until they have all run out.
+    fn searched_around_a_stalled_worker(split: WorkSplit) -> u64 {
+        const THREADS: usize = 4;
+        let ranges = Ranges::new(split, THREADS, CHUNK_ATTEMPTS);
+        let limit = 64 * CHUNK_ATTEMPTS;
+        let searched = AtomicU64::new(0);
+        let (held, holding) = mpsc::channel();
+        let (release, gate) = mpsc::channel::<()>();
+
+        std::thread::scope(|scope| {
+            let ranges = &ranges;
+            let stalled = scope.spawn(move || {
+                let mut first = true;
+                search_chunks(ranges, 0, |_: u64| false, |offset, _, _| {
+                    if std::mem::take(&mut first) {
+                        held.send(()).unwrap();
+                        let _ = gate.recv();
+                    }
+                    (offset >= limit).then_some(())
+                });
+            });
+            // Worker 0 has its first range before the others take any.
+            holding.recv().unwrap();
+
+            let others: Vec<_> = (1..THREADS)
+                .map(|thread_id| {
+                    let searched = &searched;
+                    scope.spawn(move || {
+                        search_chunks(ranges, thread_id, |_: u64| false, |offset, _, _| {
+                            if offset >= limit {
+                                return Some(());
+                            }
+                            searched.fetch_add(1, Ordering::Relaxed);
+                            None
+                        });
+                    })
+                })
+                .collect();
+            for other in others {
+                other.join().unwrap();
+            }
+            let searched = searched.load(Ordering::Relaxed);
+            release.send(()).unwrap();
+            stalled.join().unwrap();
+            searched
+        })
+    }
+
+    #[test]
+    fn test_chunked_split_absorbs_a_slow_worker() {
+        // Striding leaves the stalled worker's 16 ranges to it; sharing
+        // the cursor leaves it only the one it holds.
+        assert_eq!(searched_around_a_stalled_worker(WorkSplit::Stride), 48);
+        assert_eq!(searched_around_a_stalled_worker(WorkSplit::Chunked), 63);
+    }
+
+    #[test]
+    fn test_workers_stop_within_one_chunk_of_the_first_solution() {
+        const THREADS: usize = 4;
+        let ranges = Ranges::new(WorkSplit::Stride, THREADS, CHUNK_ATTEMPTS);
+        let chunk_time = Duration::from_millis(20);
+        let cancelled = AtomicBool::new(false);
+        let solved_at = Mutex::new(None);
+
+        let stopped_at: Vec<Instant> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..THREADS)
+                .map(|thread_id| {
+                    let (ranges, cancelled, solved_at) = (&ranges, &cancelled, &solved_at);
+                    scope.spawn(move || {
+                        let stop = |_: u64| cancelled.load(Ordering::Acquire);
+                        search_chunks(ranges, thread_id, stop, |_, _, attempts| {
+                            // Stand-in for one core call: busy for a chunk, and
+                            // thread 0 finds the solution in its third chunk.
+                            std::thread::sleep(chunk_time);
+                            let found = thread_id == 0 && attempts == 2 * CHUNK_ATTEMPTS;
+                            if found {
+                                *solved_at.lock().unwrap() = Some(Instant::now());
+                                cancelled.store(true, Ordering::Release);
+                            }
+                            found.then_some(())
+                        });
+                        Instant::now()
+                    })
+                })
+                .collect();
+            workers.into_iter().map(|worker| worker.join().unwrap()).collect()
+        });
+
+        let solved_at = solved_at.into_inner().unwrap().expect("thread 0 finds a solution");
+        for stopped in stopped_at {
+            let lag = stopped.saturating_duration_since(solved_at);
+            assert!(lag <= chunk_time * 2, "a worker kept going for {lag:?} after the solution");
+        }
+    }
+}
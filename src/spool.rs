@@ -0,0 +1,172 @@
+use ironshield::IronShieldChallengeResponse;
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::util::{FileMode, atomic_write};
+
+/// Inputs are files whose names end in this.
+pub const CHALLENGE_SUFFIX: &str = ".challenge.json";
+/// `<name>.challenge.json` is answered by `<name>.solution.json`.
+pub const SOLUTION_SUFFIX: &str = ".solution.json";
+
+/// Whether `name` is a challenge ready to be picked up.
+///
+/// Writers must put a challenge in place with a rename: write it as
+/// `.name.challenge.json`, `name.challenge.json.tmp` or any other name
+/// this rejects, then rename it to `name.challenge.json`. A file that
+/// appears under its final name is therefore always complete.
+pub fn is_challenge_name(name: &str) -> bool {
+    !name.starts_with('.') && name.len() > CHALLENGE_SUFFIX.len() && name.ends_with(CHALLENGE_SUFFIX)
+}
+
+/// What became of one input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// Solved; the solution was written to this path.
+    Solved(PathBuf),
+    /// Not solved, for this reason.
+    Failed(String),
+}
+
+/// A directory other processes drop challenges into. Each one is
+/// answered by a solution file next to it, then moved to `done/`, or
+/// to `failed/` with a `<name>.error.txt` saying why.
+#[derive(Debug, Clone)]
+pub struct Spool {
+    dir: PathBuf,
+}
+
+impl Spool {
+    /// Opens the spool in `dir`, creating `done/` and `failed/` in it.
+    ///
+    /// # Returns
+    /// * `io::Result<Spool>`: The spool, or a `NotFound` error if `dir`
+    ///                        isn't an existing directory.
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        if !dir.is_dir() {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "not a directory"));
+        }
+        let spool = Self { dir: dir.to_path_buf() };
+        fs::create_dir_all(spool.done_dir())?;
+        fs::create_dir_all(spool.failed_dir())?;
+        Ok(spool)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn done_dir(&self) -> PathBuf {
+        self.dir.join("done")
+    }
+
+    pub fn failed_dir(&self) -> PathBuf {
+        self.dir.join("failed")
+    }
+
+    /// Every challenge waiting in the spool, by name.
+    pub fn pending(&self) -> io::Result<Vec<PathBuf>> {
+        let mut pending = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let ready = entry.file_name().to_str().is_some_and(is_challenge_name);
+            if ready && entry.file_type()?.is_file() {
+                pending.push(entry.path());
+            }
+        }
+        pending.sort();
+        Ok(pending)
+    }
+
+    /// Where the solution to `input` goes: next to it, with
+    /// `.solution.json` in place of `.challenge.json`.
+    pub fn solution_path(input: &Path) -> PathBuf {
+        let name = input.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        let stem = name.strip_suffix(CHALLENGE_SUFFIX).unwrap_or(name);
+        input.with_file_name(format!("{stem}{SOLUTION_SUFFIX}"))
+    }
+
+    /// Writes the solution to `input` atomically, so whoever waits for
+    /// it never reads half of one.
+    pub fn write_solution(&self, input: &Path, solution: &IronShieldChallengeResponse) -> io::Result<PathBuf> {
+        let path = Self::solution_path(input);
+        let json = serde_json::to_vec_pretty(solution).map_err(io::Error::other)?;
+        atomic_write(&path, &json, FileMode::Shared)?;
+        Ok(path)
+    }
+
+    /// Moves `input` out of the spool once it has been dealt with, so
+    /// it isn't picked up again.
+    ///
+    /// # Returns
+    /// * `io::Result<PathBuf>`: Where the input now is.
+    pub fn finish(&self, input: &Path, outcome: &Outcome) -> io::Result<PathBuf> {
+        let name = input.file_name().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no file name"))?;
+        let dir = match outcome {
+            Outcome::Solved(_)     => self.done_dir(),
+            Outcome::Failed(error) => {
+                let name = name.to_string_lossy();
+                let stem = name.strip_suffix(CHALLENGE_SUFFIX).unwrap_or(&name);
+                atomic_write(&self.failed_dir().join(format!("{stem}.error.txt")), format!("{error}\n").as_bytes(), FileMode::Shared)?;
+                self.failed_dir()
+            }
+        };
+        let moved = dir.join(name);
+        fs::rename(input, &moved)?;
+        Ok(moved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_challenges_renamed_into_place_are_picked_up() {
+        assert!(is_challenge_name("edge-1.challenge.json"));
+        assert!(!is_challenge_name(".edge-1.challenge.json"));
+        assert!(!is_challenge_name("edge-1.challenge.json.tmp"));
+        assert!(!is_challenge_name("edge-1.solution.json"));
+        assert!(!is_challenge_name(".challenge.json"));
+
+        let dir = tempfile::tempdir().unwrap();
+        let spool = Spool::open(dir.path()).unwrap();
+        for name in ["b.challenge.json", "a.challenge.json", ".c.challenge.json", "d.challenge.json.part", "a.solution.json"] {
+            fs::write(dir.path().join(name), "{}").unwrap();
+        }
+        fs::create_dir(dir.path().join("e.challenge.json")).unwrap();
+
+        assert_eq!(spool.pending().unwrap(), vec![dir.path().join("a.challenge.json"), dir.path().join("b.challenge.json")]);
+    }
+
+    #[test]
+    fn test_solutions_go_next_to_their_challenge() {
+        assert_eq!(Spool::solution_path(Path::new("/spool/edge-1.challenge.json")), Path::new("/spool/edge-1.solution.json"));
+    }
+
+    #[test]
+    fn test_finished_inputs_leave_the_spool() {
+        let dir = tempfile::tempdir().unwrap();
+        let spool = Spool::open(dir.path()).unwrap();
+        let (solved, failed) = (dir.path().join("a.challenge.json"), dir.path().join("b.challenge.json"));
+        fs::write(&solved, "{}").unwrap();
+        fs::write(&failed, "{}").unwrap();
+
+        let moved = spool.finish(&solved, &Outcome::Solved(Spool::solution_path(&solved))).unwrap();
+        assert_eq!(moved, spool.done_dir().join("a.challenge.json"));
+        let moved = spool.finish(&failed, &Outcome::Failed("expired before it was solved".to_string())).unwrap();
+        assert_eq!(moved, spool.failed_dir().join("b.challenge.json"));
+
+        assert!(moved.exists());
+        assert_eq!(fs::read_to_string(spool.failed_dir().join("b.error.txt")).unwrap(), "expired before it was solved\n");
+        assert!(spool.pending().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_opening_a_missing_directory_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(Spool::open(&dir.path().join("missing")).unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+}
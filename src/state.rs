@@ -0,0 +1,505 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::history::{HistoryStore, HISTORY_FILE};
+use crate::http_cache::{CacheEntry, HttpCache};
+use crate::queue::{ItemState, Queue, QueueItem};
+use crate::util::{FileLock, LOCK_TIMEOUT};
+
+/// Where ironshield keeps what it writes between runs: `ironshield` in
+/// the platform data directory (history, queue) and in the platform
+/// cache directory (`get`'s responses).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StateDirs {
+    pub data:  PathBuf,
+    pub cache: PathBuf,
+}
+
+impl StateDirs {
+    pub fn new(data: PathBuf, cache: PathBuf) -> Self {
+        Self { data, cache }
+    }
+
+    /// # Returns
+    /// * `Option<Self>`: `None` if the platform has no data or cache directory.
+    pub fn default_location() -> Option<Self> {
+        Some(Self::new(dirs::data_dir()?.join("ironshield"), dirs::cache_dir()?.join("ironshield")))
+    }
+
+    pub fn history(&self) -> HistoryStore {
+        HistoryStore::new(self.data.join(HISTORY_FILE))
+    }
+
+    pub fn queue(&self) -> Queue {
+        Queue::new(self.data.join("queue"))
+    }
+
+    pub fn http_cache(&self) -> HttpCache {
+        HttpCache::new(self.cache.join("http"))
+    }
+
+    /// Where `component` keeps its files.
+    pub fn path(&self, component: Component) -> PathBuf {
+        match component {
+            Component::History   => self.history().path().to_path_buf(),
+            Component::Queue     => self.queue().dir().to_path_buf(),
+            Component::HttpCache => self.http_cache().dir().to_path_buf(),
+        }
+    }
+}
+
+/// One kind of state `state size` and `state gc` know about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Component {
+    History,
+    Queue,
+    HttpCache,
+}
+
+impl Component {
+    pub const ALL: [Component; 3] = [Self::History, Self::Queue, Self::HttpCache];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::History   => "history",
+            Self::Queue     => "queue",
+            Self::HttpCache => "http cache",
+        }
+    }
+}
+
+/// The disk a component takes up.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Usage {
+    pub component: Component,
+    pub path:      PathBuf,
+    pub files:     u64,
+    pub bytes:     u64,
+}
+
+/// What each component takes up, counting every file under it,
+/// lock files included. Missing components take up nothing.
+pub fn usage(dirs: &StateDirs) -> io::Result<Vec<Usage>> {
+    Component::ALL.into_iter()
+        .map(|component| {
+            let path = dirs.path(component);
+            let (files, bytes) = disk_usage(&path)?;
+            Ok(Usage { component, path, files, bytes })
+        })
+        .collect()
+}
+
+fn disk_usage(path: &Path) -> io::Result<(u64, u64)> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok((0, 0)),
+        Err(e) => return Err(e),
+    };
+    if !metadata.is_dir() {
+        return Ok((1, metadata.len()));
+    }
+    let mut total = (0, 0);
+    for entry in fs::read_dir(path)? {
+        let (files, bytes) = disk_usage(&entry?.path())?;
+        total = (total.0 + files, total.1 + bytes);
+    }
+    Ok(total)
+}
+
+/// What `state gc` removes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GcOptions {
+    /// Queue items and cached responses must be at least this old.
+    pub older_than:        Duration,
+    /// History records older than this go; all are kept when unset.
+    pub history_retention: Option<Duration>,
+    /// Report what would go without removing anything.
+    pub dry_run:           bool,
+}
+
+/// One thing `state gc` removed, or would have.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Removal {
+    pub component: Component,
+    pub path:      PathBuf,
+    pub reason:    String,
+    /// Bytes freed; for history, the size of the removed lines isn't counted.
+    pub bytes:     u64,
+}
+
+/// Removes state nothing will use again, from `dirs`:
+///
+/// * History records older than `history_retention`.
+/// * Queue items whose work is over: submitted with an expired token,
+///   expired, rejected (in `failed/`), or still pending or solved though
+///   their challenge expired. The age counts from when that happened.
+/// * Cached responses stored before `older_than`, and bodies whose
+///   entry is gone.
+/// * Temporary files left by interrupted writes, by modification time.
+///
+/// Files it doesn't recognize are never touched, nor are `.lock` files,
+/// which another process may hold. History and cache entries are
+/// changed under the same [`FileLock`] their writers take.
+///
+/// # Arguments
+/// * `dirs`:    The state to clean up.
+/// * `options`: Ages and `--dry-run`.
+/// * `now`:     The time ages are measured from.
+pub fn gc(dirs: &StateDirs, options: &GcOptions, now: DateTime<Utc>) -> io::Result<Vec<Removal>> {
+    let cutoff = now - chrono::Duration::from_std(options.older_than).unwrap_or(chrono::Duration::MAX);
+    let mut removals = Vec::new();
+
+    if let Some(retention) = options.history_retention {
+        let history = dirs.history();
+        let history_cutoff = now - chrono::Duration::from_std(retention).unwrap_or(chrono::Duration::MAX);
+        let removed = history.prune(history_cutoff, options.dry_run)?;
+        if removed > 0 {
+            removals.push(Removal {
+                component: Component::History,
+                path:      history.path().to_path_buf(),
+                reason:    format!("{removed} record(s) past the history retention"),
+                bytes:     0,
+            });
+        }
+    }
+
+    gc_queue(&dirs.queue(), cutoff, now, options.dry_run, &mut removals)?;
+    gc_http_cache(&dirs.http_cache(), cutoff, options.dry_run, &mut removals)?;
+    Ok(removals)
+}
+
+fn gc_queue(queue: &Queue, cutoff: DateTime<Utc>, now: DateTime<Utc>, dry_run: bool, removals: &mut Vec<Removal>) -> io::Result<()> {
+    for dir in [queue.dir().to_path_buf(), queue.failed_dir()] {
+        for path in files(&dir)? {
+            let reason = if is_temporary(&path) {
+                modified_before(&path, cutoff)?.then(|| "left by an interrupted write".to_string())
+            } else if path.extension().is_some_and(|extension| extension == "json") {
+                fs::read_to_string(&path).ok()
+                    .and_then(|contents| serde_json::from_str::<QueueItem>(&contents).ok())
+                    .and_then(|item| finished_item(&item, cutoff, now))
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                remove(Component::Queue, path, reason, dry_run, removals)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Why `item` can go, if its work ended before `cutoff`.
+fn finished_item(item: &QueueItem, cutoff: DateTime<Utc>, now: DateTime<Utc>) -> Option<String> {
+    let before = |at: Option<DateTime<Utc>>| at.is_some_and(|at| at < cutoff);
+    match item.state {
+        ItemState::Submitted => {
+            let token_expired = item.token.as_ref().is_some_and(|token| token.valid_for <= now.timestamp_millis());
+            (token_expired && before(item.submitted_at)).then(|| "submitted; its token has expired".to_string())
+        }
+        ItemState::Expired  => before(item.expires_at()).then(|| "expired".to_string()),
+        ItemState::Rejected => before(item.rejection.as_ref().map(|rejection| rejection.at)).then(|| "rejected by the API".to_string()),
+        ItemState::Pending | ItemState::Solved => {
+            before(item.expires_at()).then(|| format!("orphaned: {} but its challenge expired", item.state.name()))
+        }
+    }
+}
+
+fn gc_http_cache(cache: &HttpCache, cutoff: DateTime<Utc>, dry_run: bool, removals: &mut Vec<Removal>) -> io::Result<()> {
+    let files = files(cache.dir())?;
+    for path in &files {
+        if is_temporary(path) {
+            if modified_before(path, cutoff)? {
+                remove(Component::HttpCache, path.clone(), "left by an interrupted write".to_string(), dry_run, removals)?;
+            }
+            continue;
+        }
+        let Some(key) = cache_key(path) else {
+            continue;
+        };
+        let entry_path = path.with_extension("json");
+        let body_path = path.with_extension("body");
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => {
+                let Some(entry) = fs::read_to_string(path).ok()
+                    .and_then(|contents| serde_json::from_str::<CacheEntry>(&contents).ok())
+                    .filter(|entry| entry.stored_at < cutoff)
+                else {
+                    continue;
+                };
+                // Held while both files go, as `store` holds it while writing them.
+                let _lock = lock(&entry_path, dry_run)?;
+                let reason = format!("cached response for {}, stored {}", entry.url, entry.stored_at.format("%Y-%m-%d"));
+                remove(Component::HttpCache, entry_path, reason, dry_run, removals)?;
+                if body_path.exists() {
+                    remove(Component::HttpCache, body_path, format!("body of {key}.json"), dry_run, removals)?;
+                }
+            }
+            Some("body") if !files.contains(&entry_path) && modified_before(path, cutoff)? => {
+                let _lock = lock(&entry_path, dry_run)?;
+                remove(Component::HttpCache, body_path, "orphaned: no entry for this body".to_string(), dry_run, removals)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// The [`FileLock`] on `path`, unless this is a dry run, which changes
+/// nothing and so leaves no lock file behind.
+fn lock(path: &Path, dry_run: bool) -> io::Result<Option<FileLock>> {
+    (!dry_run).then(|| FileLock::acquire(path, LOCK_TIMEOUT)).transpose()
+}
+
+/// The hash a cache file is named by, if it is named like one.
+fn cache_key(path: &Path) -> Option<&str> {
+    path.file_stem()?.to_str().filter(|stem| stem.len() == 32 && stem.bytes().all(|byte| byte.is_ascii_hexdigit()))
+}
+
+/// Whether `path` is a temporary file [`crate::util::atomic_write`] or
+/// [`HttpCache::begin`] left behind.
+fn is_temporary(path: &Path) -> bool {
+    path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with(".tmp"))
+}
+
+fn modified_before(path: &Path, cutoff: DateTime<Utc>) -> io::Result<bool> {
+    let modified: DateTime<Utc> = fs::metadata(path)?.modified().unwrap_or(SystemTime::UNIX_EPOCH).into();
+    Ok(modified < cutoff)
+}
+
+/// The files directly in `dir`, sorted; none if it is missing.
+fn files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut files = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            files.push(entry.path());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn remove(component: Component, path: PathBuf, reason: String, dry_run: bool, removals: &mut Vec<Removal>) -> io::Result<()> {
+    let bytes = fs::metadata(&path).map(|metadata| metadata.len()).unwrap_or(0);
+    if !dry_run {
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+    }
+    removals.push(Removal { component, path, reason, bytes });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::{RunCommand, RunRecord};
+    use crate::queue::Rejection;
+    use ironshield::IronShieldChallenge;
+    use ironshield_types::IronShieldToken;
+    use std::io::Write;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+    const ORPHAN_KEY: &str = "00000000000000000000000000000000";
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-06-01T12:00:00Z").unwrap().with_timezone(&Utc)
+    }
+
+    fn days_ago(days: i64) -> DateTime<Utc> {
+        now() - chrono::Duration::days(days)
+    }
+
+    fn challenge(expires_at: DateTime<Utc>) -> IronShieldChallenge {
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(&[7; 32]);
+        let public_key = signing_key.verifying_key().to_bytes();
+        let mut challenge = IronShieldChallenge::new("gc".to_string(), 1_000, signing_key, public_key);
+        challenge.expiration_time = expires_at.timestamp_millis();
+        challenge
+    }
+
+    fn item(id: &str, state: ItemState, expires_at: DateTime<Utc>) -> QueueItem {
+        QueueItem {
+            id:           id.to_string(),
+            state,
+            added_at:     expires_at,
+            challenge:    challenge(expires_at),
+            api_base_url: None,
+            solution:     None,
+            solved_at:    None,
+            token:        None,
+            submitted_at: None,
+            error:        None,
+            rejection:    None,
+        }
+    }
+
+    fn submitted(id: &str, submitted_at: DateTime<Utc>, valid_for: DateTime<Utc>) -> QueueItem {
+        let mut item = item(id, ItemState::Submitted, submitted_at + chrono::Duration::minutes(5));
+        item.submitted_at = Some(submitted_at);
+        item.token = Some(IronShieldToken::new([0; 64], valid_for.timestamp_millis(), [0; 32], [0; 64]));
+        item
+    }
+
+    fn record(timestamp: DateTime<Utc>) -> RunRecord {
+        RunRecord { timestamp, ..RunRecord::new(RunCommand::Validate, "https://a.example/") }
+    }
+
+    /// A state tree with something of every kind, old and new, plus
+    /// files gc must leave alone.
+    fn synthetic_tree(root: &Path) -> StateDirs {
+        let dirs = StateDirs::new(root.join("data"), root.join("cache"));
+
+        let history = dirs.history();
+        for timestamp in [days_ago(400), days_ago(100), days_ago(1)] {
+            history.append(&record(timestamp)).unwrap();
+        }
+        fs::OpenOptions::new().append(true).open(history.path()).unwrap()
+            .write_all(b"{\"not\": \"a record\"}\n").unwrap();
+
+        let queue = dirs.queue();
+        for item in [
+            submitted("old-submitted", days_ago(40), days_ago(39)),
+            submitted("old-submitted-still-valid", days_ago(40), days_ago(-1)),
+            submitted("new-submitted", days_ago(2), days_ago(1)),
+            item("old-expired", ItemState::Expired, days_ago(35)),
+            item("new-expired", ItemState::Expired, days_ago(3)),
+            item("old-pending", ItemState::Pending, days_ago(60)),
+            item("new-pending", ItemState::Pending, days_ago(-1)),
+        ] {
+            queue.save(&item).unwrap();
+        }
+        let mut rejected = item("old-rejected", ItemState::Rejected, days_ago(50));
+        rejected.rejection = Some(Rejection { status: 400, body: "invalid".to_string(), at: days_ago(50) });
+        queue.reject(&rejected).unwrap();
+        fs::write(queue.dir().join("notes.txt"), "mine").unwrap();
+        fs::write(queue.dir().join("broken.json"), "{").unwrap();
+        let temp = queue.dir().join(".tmpAbC123");
+        fs::write(&temp, "partial").unwrap();
+        set_modified(&temp, days_ago(45));
+        fs::write(queue.dir().join(".tmpNew456"), "in progress").unwrap();
+
+        let cache = dirs.http_cache();
+        fs::create_dir_all(cache.dir()).unwrap();
+        for (url, stored_at) in [("https://a.example/old", days_ago(31)), ("https://a.example/new", days_ago(1))] {
+            let entry = CacheEntry { url: url.to_string(), etag: None, last_modified: None, size: 4, stored_at };
+            let mut body = cache.begin().unwrap();
+            body.write_all(b"body").unwrap();
+            cache.store(&entry, body).unwrap();
+        }
+        // Named to sort first, ahead of the hashed names.
+        let orphan = cache.dir().join(format!("{ORPHAN_KEY}.body"));
+        fs::write(&orphan, "orphan").unwrap();
+        set_modified(&orphan, days_ago(31));
+        fs::write(cache.dir().join("README"), "hands off").unwrap();
+        dirs
+    }
+
+    fn set_modified(path: &Path, at: DateTime<Utc>) {
+        fs::File::options().write(true).open(path).unwrap().set_modified(at.into()).unwrap();
+    }
+
+    fn options(dry_run: bool) -> GcOptions {
+        GcOptions { older_than: 30 * DAY, history_retention: Some(90 * DAY), dry_run }
+    }
+
+    fn names(dir: &Path) -> Vec<String> {
+        files(dir).unwrap().iter().map(|path| path.file_name().unwrap().to_string_lossy().into_owned()).collect()
+    }
+
+    #[test]
+    fn test_gc_removes_exactly_what_is_finished() {
+        let root = tempfile::tempdir().unwrap();
+        let dirs = synthetic_tree(root.path());
+        let cache_before = names(dirs.http_cache().dir());
+
+        let removals = gc(&dirs, &options(false), now()).unwrap();
+
+        let reasons: Vec<(Component, String)> = removals.iter().map(|removal| (removal.component, removal.reason.clone())).collect();
+        assert_eq!(reasons, [
+            (Component::History, "2 record(s) past the history retention".to_string()),
+            (Component::Queue, "left by an interrupted write".to_string()),
+            (Component::Queue, "expired".to_string()),
+            (Component::Queue, "orphaned: pending but its challenge expired".to_string()),
+            (Component::Queue, "submitted; its token has expired".to_string()),
+            (Component::Queue, "rejected by the API".to_string()),
+            (Component::HttpCache, "orphaned: no entry for this body".to_string()),
+            (Component::HttpCache, format!("cached response for https://a.example/old, stored {}", days_ago(31).format("%Y-%m-%d"))),
+            (Component::HttpCache, format!("body of {}.json", HttpCache::key("https://a.example/old"))),
+        ]);
+
+        let history = fs::read_to_string(dirs.history().path()).unwrap();
+        assert_eq!(history.lines().count(), 2);
+        assert!(history.ends_with("{\"not\": \"a record\"}\n"));
+
+        assert_eq!(names(dirs.queue().dir()), [
+            ".tmpNew456",
+            "broken.json",
+            "new-expired.json",
+            "new-pending.json",
+            "new-submitted.json",
+            "notes.txt",
+            "old-submitted-still-valid.json",
+        ]);
+        assert!(names(&dirs.queue().failed_dir()).is_empty());
+
+        // The old entry's lock file stays, and removing the orphan took its entry's lock.
+        let old_key = HttpCache::key("https://a.example/old");
+        let removed = [format!("{ORPHAN_KEY}.body"), format!("{old_key}.json"), format!("{old_key}.body")];
+        let mut expected: Vec<String> = cache_before.into_iter().filter(|name| !removed.contains(name)).collect();
+        expected.push(format!("{ORPHAN_KEY}.json.lock"));
+        expected.sort();
+        assert_eq!(names(dirs.http_cache().dir()), expected);
+        assert!(expected.contains(&"README".to_string()));
+        assert!(expected.contains(&format!("{old_key}.json.lock")));
+    }
+
+    #[test]
+    fn test_dry_run_removes_nothing() {
+        let root = tempfile::tempdir().unwrap();
+        let dirs = synthetic_tree(root.path());
+        let before = usage(&dirs).unwrap();
+
+        let removals = gc(&dirs, &options(true), now()).unwrap();
+
+        assert_eq!(removals.len(), 9);
+        assert_eq!(usage(&dirs).unwrap(), before);
+        assert_eq!(gc(&dirs, &options(false), now()).unwrap(), removals);
+    }
+
+    #[test]
+    fn test_history_is_kept_without_a_retention() {
+        let root = tempfile::tempdir().unwrap();
+        let dirs = synthetic_tree(root.path());
+
+        let removals = gc(&dirs, &GcOptions { history_retention: None, ..options(false) }, now()).unwrap();
+
+        assert!(removals.iter().all(|removal| removal.component != Component::History));
+        assert_eq!(dirs.history().load().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_usage_counts_every_file() {
+        let root = tempfile::tempdir().unwrap();
+        let dirs = StateDirs::new(root.path().join("data"), root.path().join("cache"));
+        assert!(usage(&dirs).unwrap().iter().all(|usage| usage.files == 0 && usage.bytes == 0));
+
+        fs::create_dir_all(dirs.queue().failed_dir()).unwrap();
+        fs::write(dirs.queue().dir().join("a.json"), "12345").unwrap();
+        fs::write(dirs.queue().failed_dir().join("b.json"), "123").unwrap();
+        let queue = usage(&dirs).unwrap().into_iter().find(|usage| usage.component == Component::Queue).unwrap();
+        assert_eq!((queue.files, queue.bytes), (2, 8));
+    }
+}
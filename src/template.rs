@@ -0,0 +1,123 @@
+/// Placeholders a request body may contain, e.g. `{{token}}`.
+pub const PLACEHOLDERS: [&str; 4] = ["token", "response_header", "timestamp_ms", "endpoint"];
+
+/// What the placeholders in a request body stand for, known once the
+/// challenge is solved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Values<'a> {
+    /// `{{token}}`: the token as JSON.
+    pub token:           &'a str,
+    /// `{{response_header}}`: the token as sent in the token header.
+    pub response_header: &'a str,
+    /// `{{timestamp_ms}}`: Unix milliseconds when the body was built.
+    pub timestamp_ms:    i64,
+    /// `{{endpoint}}`: the protected URL.
+    pub endpoint:        &'a str,
+}
+
+impl Values<'_> {
+    fn get(&self, name: &str) -> Option<String> {
+        match name {
+            "token"           => Some(self.token.to_string()),
+            "response_header" => Some(self.response_header.to_string()),
+            "timestamp_ms"    => Some(self.timestamp_ms.to_string()),
+            "endpoint"        => Some(self.endpoint.to_string()),
+            _                 => None,
+        }
+    }
+}
+
+/// Checks that every placeholder in `template` is known, so a typo is
+/// caught before solving rather than after.
+pub fn check(template: &str) -> Result<(), String> {
+    substitute(template, |name| PLACEHOLDERS.contains(&name).then(String::new)).map(|_| ())
+}
+
+/// Replaces each `{{name}}` in `template` with its value; `{{{{`
+/// stands for a literal `{{`.
+///
+/// # Returns
+/// * `Result<String, String>`: The body, or which placeholder is
+///                             unknown or unterminated.
+pub fn render(template: &str, values: &Values) -> Result<String, String> {
+    substitute(template, |name| values.get(name))
+}
+
+fn substitute(template: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find("{{") {
+        out.push_str(&rest[..open]);
+        rest = &rest[open + 2..];
+        if let Some(after) = rest.strip_prefix("{{") {
+            out.push_str("{{");
+            rest = after;
+            continue;
+        }
+        let close = rest.find("}}").ok_or_else(|| {
+            format!("'{{{{' at byte {} is never closed; write '{{{{{{{{' for a literal '{{{{'", template.len() - rest.len() - 2)
+        })?;
+        let name = rest[..close].trim();
+        let value = lookup(name).ok_or_else(|| {
+            format!("Unknown placeholder '{{{{{name}}}}}'; expected one of {}", known_placeholders())
+        })?;
+        out.push_str(&value);
+        rest = &rest[close + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn known_placeholders() -> String {
+    PLACEHOLDERS.iter().map(|name| format!("{{{{{name}}}}}")).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values() -> Values<'static> {
+        Values {
+            token:           "{\"valid_for\":1}",
+            response_header: "abc.def",
+            timestamp_ms:    1_700_000_000_000,
+            endpoint:        "https://a.example/protected",
+        }
+    }
+
+    #[test]
+    fn test_placeholders_are_substituted() {
+        let body = render(
+            "{\"token\": {{token}}, \"header\": \"{{ response_header }}\", \"at\": {{timestamp_ms}}, \"url\": \"{{endpoint}}\"}",
+            &values(),
+        );
+
+        assert_eq!(body.unwrap(), concat!(
+            "{\"token\": {\"valid_for\":1}, \"header\": \"abc.def\", ",
+            "\"at\": 1700000000000, \"url\": \"https://a.example/protected\"}",
+        ));
+    }
+
+    #[test]
+    fn test_escaping() {
+        assert_eq!(render("{{{{token}}", &values()).unwrap(), "{{token}}");
+        assert_eq!(render("a {{{{ b }} c", &values()).unwrap(), "a {{ b }} c");
+        assert_eq!(render("{ \"plain\": {} }", &values()).unwrap(), "{ \"plain\": {} }");
+    }
+
+    #[test]
+    fn test_unknown_and_unterminated_placeholders() {
+        let error = render("{{tokn}}", &values()).unwrap_err();
+        assert!(error.starts_with("Unknown placeholder '{{tokn}}'"), "{error}");
+        assert!(error.contains("{{timestamp_ms}}"), "{error}");
+
+        let error = render("ok {{token", &values()).unwrap_err();
+        assert!(error.contains("at byte 3 is never closed"), "{error}");
+    }
+
+    #[test]
+    fn test_check_accepts_known_placeholders_only() {
+        assert!(check("{{token}} {{endpoint}}").is_ok());
+        assert!(check("{{nope}}").is_err());
+    }
+}
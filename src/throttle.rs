@@ -0,0 +1,264 @@
+use ironshield::ProgressTracker;
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::display::format_hash_rate;
+use crate::warnings::{Warning, WarningCode};
+
+/// The `[throttle]` section of the configuration file.
+///
+/// ```toml
+/// [throttle]
+/// threshold_percent = 60
+/// consecutive_samples = 20
+/// slow_start_secs = 10
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThrottleConfig {
+    /// Watch the hash rate during solves at all.
+    pub enabled:             bool,
+    /// Seconds of once-per-second samples averaged into the baseline.
+    pub baseline_samples:    usize,
+    /// A sample below this percentage of the baseline counts as degraded.
+    pub threshold_percent:   u8,
+    /// Degraded samples in a row before warning.
+    pub consecutive_samples: usize,
+    /// Seconds without a first progress report before saying the
+    /// workers are still starting; 0 never says it.
+    pub slow_start_secs:     u64,
+}
+
+impl Default for ThrottleConfig {
+    fn default() -> Self {
+        Self {
+            enabled:             true,
+            baseline_samples:    60,
+            threshold_percent:   70,
+            consecutive_samples: 10,
+            slow_start_secs:     5,
+        }
+    }
+}
+
+static CONFIG: OnceLock<ThrottleConfig> = OnceLock::new();
+
+/// Sets the detector thresholds for the rest of the process.
+pub fn set_config(config: ThrottleConfig) {
+    let _ = CONFIG.set(config);
+}
+
+/// The `[throttle]` section in effect.
+pub fn config() -> ThrottleConfig {
+    CONFIG.get().cloned().unwrap_or_default()
+}
+
+/// A sustained drop below the first-minute hash rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Throttled {
+    pub baseline: u64,
+    pub current:  u64,
+}
+
+impl Throttled {
+    pub fn message(&self) -> String {
+        format!(
+            "hash rate dropped from {} to {} — thermal throttling or background load suspected",
+            format_hash_rate(self.baseline),
+            format_hash_rate(self.current),
+        )
+    }
+
+    pub fn warning(&self) -> Warning {
+        Warning::new(WarningCode::Throttled, self.message())
+            .with_data(serde_json::json!({ "baseline_hash_rate": self.baseline, "current_hash_rate": self.current }))
+    }
+}
+
+/// Watches once-per-second aggregate hash rate samples for a
+/// sustained drop below the average of the first samples.
+#[derive(Debug, Clone)]
+pub struct ThrottleDetector {
+    config:        ThrottleConfig,
+    baseline_sum:  u128,
+    baseline_seen: usize,
+    /// Degraded samples in a row, and their sum.
+    streak:        usize,
+    streak_sum:    u128,
+    detected:      bool,
+}
+
+impl Default for ThrottleDetector {
+    fn default() -> Self {
+        Self::new(config())
+    }
+}
+
+impl ThrottleDetector {
+    pub fn new(config: ThrottleConfig) -> Self {
+        Self {
+            config,
+            baseline_sum:  0,
+            baseline_seen: 0,
+            streak:        0,
+            streak_sum:    0,
+            detected:      false,
+        }
+    }
+
+    /// Whether a drop has been reported during this solve.
+    pub fn detected(&self) -> bool {
+        self.detected
+    }
+
+    /// Adds the next sample.
+    ///
+    /// # Returns
+    /// * `Option<Throttled>`: The drop, the first time it has lasted
+    ///                        long enough; `None` every other time.
+    pub fn push(&mut self, rate: u64) -> Option<Throttled> {
+        if !self.config.enabled || self.detected {
+            return None;
+        }
+        if self.baseline_seen < self.config.baseline_samples.max(1) {
+            self.baseline_sum += rate as u128;
+            self.baseline_seen += 1;
+            return None;
+        }
+
+        let baseline = (self.baseline_sum / self.baseline_seen as u128) as u64;
+        let threshold = baseline as u128 * self.config.threshold_percent as u128 / 100;
+        if (rate as u128) >= threshold {
+            self.streak = 0;
+            self.streak_sum = 0;
+            return None;
+        }
+
+        self.streak += 1;
+        self.streak_sum += rate as u128;
+        if self.streak < self.config.consecutive_samples.max(1) {
+            return None;
+        }
+        self.detected = true;
+        Some(Throttled { baseline, current: (self.streak_sum / self.streak as u128) as u64 })
+    }
+}
+
+/// Sums per-thread progress into once-per-second samples for a
+/// [`ThrottleDetector`], warning once if the rate drops, and passes
+/// every update on to `inner`.
+pub struct ThrottleTracker {
+    inner: Option<Arc<dyn ProgressTracker>>,
+    state: Mutex<TrackerState>,
+}
+
+struct TrackerState {
+    detector:    ThrottleDetector,
+    rates:       HashMap<usize, u64>,
+    last_sample: Option<Instant>,
+}
+
+impl ThrottleTracker {
+    pub fn new(inner: Option<Arc<dyn ProgressTracker>>) -> Self {
+        Self {
+            inner,
+            state: Mutex::new(TrackerState {
+                detector:    ThrottleDetector::default(),
+                rates:       HashMap::new(),
+                last_sample: None,
+            }),
+        }
+    }
+
+    pub fn detected(&self) -> bool {
+        self.state.lock().unwrap().detector.detected()
+    }
+}
+
+impl ProgressTracker for ThrottleTracker {
+    fn on_progress(&self, thread_id: usize, total_attempts: u64, hash_rate: u64, elapsed: Duration) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.rates.insert(thread_id, hash_rate);
+
+            let now = Instant::now();
+            if !state.last_sample.is_some_and(|last| now.duration_since(last) < Duration::from_secs(1)) {
+                state.last_sample = Some(now);
+                let total = state.rates.values().sum();
+                if let Some(throttled) = state.detector.push(total) {
+                    crate::warnings::emit(throttled.warning());
+                }
+            }
+        }
+
+        if let Some(inner) = &self.inner {
+            inner.on_progress(thread_id, total_attempts, hash_rate, elapsed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detector() -> ThrottleDetector {
+        ThrottleDetector::new(ThrottleConfig {
+            enabled:             true,
+            baseline_samples:    5,
+            threshold_percent:   70,
+            consecutive_samples: 3,
+            slow_start_secs:     5,
+        })
+    }
+
+    fn feed(detector: &mut ThrottleDetector, rates: &[u64]) -> Vec<Throttled> {
+        rates.iter().filter_map(|rate| detector.push(*rate)).collect()
+    }
+
+    #[test]
+    fn test_sustained_drop_warns_once() {
+        let mut detector = detector();
+        let warnings = feed(&mut detector, &[1_800_000; 5]);
+        assert!(warnings.is_empty());
+
+        let warnings = feed(&mut detector, &[1_000_000, 1_100_000, 900_000, 1_000_000, 1_000_000]);
+
+        assert_eq!(warnings, [Throttled { baseline: 1_800_000, current: 1_000_000 }]);
+        assert!(detector.detected());
+        assert_eq!(
+            warnings[0].message(),
+            "hash rate dropped from 1.80 Mh/s to 1.00 Mh/s — thermal throttling or background load suspected",
+        );
+        assert_eq!(warnings[0].warning().code, WarningCode::Throttled);
+        assert_eq!(warnings[0].warning().data["baseline_hash_rate"], 1_800_000);
+    }
+
+    #[test]
+    fn test_brief_dips_are_ignored() {
+        let mut detector = detector();
+        let rates = [1_000, 1_000, 1_000, 1_000, 1_000, 500, 500, 1_000, 500, 500, 700, 500];
+
+        assert!(feed(&mut detector, &rates).is_empty());
+        assert!(!detector.detected());
+    }
+
+    #[test]
+    fn test_drop_during_baseline_is_averaged_in() {
+        let mut detector = detector();
+
+        // The baseline is 600, so 450 is still above 70% of it.
+        assert!(feed(&mut detector, &[1_000, 1_000, 500, 250, 250, 450, 450, 450]).is_empty());
+    }
+
+    #[test]
+    fn test_disabled_detector() {
+        let mut detector = ThrottleDetector::new(ThrottleConfig { enabled: false, ..ThrottleConfig::default() });
+        let mut rates = vec![1_000; 60];
+        rates.extend([1; 60]);
+
+        assert!(feed(&mut detector, &rates).is_empty());
+    }
+}
@@ -0,0 +1,111 @@
+use std::fmt;
+use std::io::{self, IsTerminal};
+
+use super::task::Action;
+
+/// Smallest terminal the layout is designed for.
+pub const MIN_WIDTH: u16 = 60;
+pub const MIN_HEIGHT: u16 = 15;
+
+/// Why the TUI can't start in the current terminal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Unsupported {
+    /// stdin or stdout is redirected, e.g. in CI or a pipe.
+    NotATerminal,
+    /// `TERM=dumb`, which has no cursor movement or alternate screen.
+    DumbTerminal,
+    /// The terminal's size couldn't be read.
+    UnknownSize,
+    TooSmall { width: u16, height: u16 },
+}
+
+impl fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotATerminal => f.write_str("stdin and stdout must both be a terminal"),
+            Self::DumbTerminal => f.write_str("TERM=dumb does not support cursor movement or an alternate screen"),
+            Self::UnknownSize  => f.write_str("the terminal size could not be determined"),
+            Self::TooSmall { width, height } => f.write_str(&too_small_message(*width, *height)),
+        }
+    }
+}
+
+/// Checks the real terminal before entering raw mode.
+pub fn check() -> Result<(), Unsupported> {
+    let is_terminal = io::stdin().is_terminal() && io::stdout().is_terminal();
+    let term = std::env::var("TERM").ok();
+    assess(is_terminal, term.as_deref(), || crossterm::terminal::size().ok())
+}
+
+/// Decides whether the TUI can run, given what's known about the terminal.
+///
+/// # Arguments
+/// * `is_terminal`: Whether stdin and stdout are both terminals.
+/// * `term`:        The `TERM` variable, if set.
+/// * `size`:        Reads the terminal size; only called for a terminal.
+fn assess(
+    is_terminal: bool,
+    term:        Option<&str>,
+    size:        impl FnOnce() -> Option<(u16, u16)>,
+) -> Result<(), Unsupported> {
+    if !is_terminal {
+        return Err(Unsupported::NotATerminal);
+    }
+    if term.is_some_and(|term| term.eq_ignore_ascii_case("dumb")) {
+        return Err(Unsupported::DumbTerminal);
+    }
+    match size() {
+        None => Err(Unsupported::UnknownSize),
+        Some((width, height)) if width < MIN_WIDTH || height < MIN_HEIGHT => {
+            Err(Unsupported::TooSmall { width, height })
+        }
+        Some(_) => Ok(()),
+    }
+}
+
+/// Shown instead of the layout when the terminal shrinks too far.
+pub fn too_small_message(width: u16, height: u16) -> String {
+    format!("terminal too small (need {MIN_WIDTH}x{MIN_HEIGHT}, have {width}x{height})")
+}
+
+/// A plain-text explanation with the subcommands that do what the TUI would.
+///
+/// # Arguments
+/// * `reason`:      Why the TUI can't start.
+/// * `config_path`: The config file in use, repeated in the commands.
+pub fn explain(reason: &Unsupported, config_path: Option<&str>) -> String {
+    let config = config_path.map(|path| format!(" -c {path}")).unwrap_or_default();
+    let mut text = format!(
+        "The TUI can't start here: {reason}.\nRun the same commands directly instead:\n"
+    );
+    for action in Action::ALL {
+        let command = format!("ironshield {}{config} <endpoint>", action.label().to_lowercase());
+        text.push_str(&format!("  {command:<48} {}\n", action.description()));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assess_checks_in_order() {
+        assert_eq!(assess(false, Some("dumb"), || None), Err(Unsupported::NotATerminal));
+        assert_eq!(assess(true, Some("dumb"), || Some((80, 24))), Err(Unsupported::DumbTerminal));
+        assert_eq!(assess(true, None, || None), Err(Unsupported::UnknownSize));
+        assert_eq!(
+            assess(true, Some("xterm-256color"), || Some((40, 10))),
+            Err(Unsupported::TooSmall { width: 40, height: 10 }),
+        );
+        assert_eq!(assess(true, Some("xterm-256color"), || Some((MIN_WIDTH, MIN_HEIGHT))), Ok(()));
+    }
+
+    #[test]
+    fn test_explain_lists_commands() {
+        let text = explain(&Unsupported::TooSmall { width: 40, height: 10 }, Some("ironshield.toml"));
+
+        assert!(text.contains("terminal too small (need 60x15, have 40x10)"));
+        assert!(text.contains("ironshield validate -c ironshield.toml <endpoint>"));
+    }
+}
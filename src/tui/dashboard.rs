@@ -0,0 +1,235 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::Stylize,
+    text::Line,
+    widgets::{Gauge, Paragraph, Row, Table},
+};
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use super::theme::Theme;
+use crate::display::{format_count, format_duration, format_hash_rate};
+
+/// Latest progress reported by one solver thread.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ThreadStats {
+    pub attempts:  u64,
+    pub hash_rate: u64,
+}
+
+/// How a solve shown on the dashboard ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Solved { nonce: u64 },
+    Cancelled,
+}
+
+/// Live state of a running solve, fed by `ProgressTracker` callbacks
+/// and frozen into a summary once the solve ends.
+#[derive(Debug, Clone)]
+pub struct SolveDashboard {
+    recommended_attempts: u64,
+    thread_count:         usize,
+    started:              Instant,
+    threads:              BTreeMap<usize, ThreadStats>,
+    finished:             Option<(Duration, Outcome)>,
+}
+
+impl SolveDashboard {
+    pub fn new(recommended_attempts: u64, thread_count: usize) -> Self {
+        Self {
+            recommended_attempts,
+            thread_count,
+            started:  Instant::now(),
+            threads:  BTreeMap::new(),
+            finished: None,
+        }
+    }
+
+    /// Records the latest totals for a thread. Ignored once frozen.
+    pub fn record(&mut self, thread_id: usize, attempts: u64, hash_rate: u64) {
+        if self.finished.is_none() {
+            self.threads.insert(thread_id, ThreadStats { attempts, hash_rate });
+        }
+    }
+
+    /// Freezes the dashboard; later progress and finishes are ignored.
+    pub fn finish(&mut self, outcome: Outcome) {
+        if self.finished.is_none() {
+            self.finished = Some((self.started.elapsed(), outcome));
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished.is_some()
+    }
+
+    pub fn total_attempts(&self) -> u64 {
+        self.threads.values().map(|t| t.attempts).sum()
+    }
+
+    pub fn total_hash_rate(&self) -> u64 {
+        self.threads.values().map(|t| t.hash_rate).sum()
+    }
+
+    /// Attempts so far as a fraction of `recommended_attempts`, capped at 1.
+    pub fn ratio(&self) -> f64 {
+        if self.recommended_attempts == 0 {
+            return 0.0;
+        }
+        (self.total_attempts() as f64 / self.recommended_attempts as f64).min(1.0)
+    }
+
+    /// Time until `recommended_attempts` is reached at the current rate.
+    pub fn eta(&self) -> Option<Duration> {
+        let rate = self.total_hash_rate();
+        if rate == 0 {
+            return None;
+        }
+        let remaining = self.recommended_attempts.saturating_sub(self.total_attempts());
+        Some(Duration::from_secs_f64(remaining as f64 / rate as f64))
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.finished
+            .map(|(elapsed, _)| elapsed)
+            .unwrap_or_else(|| self.started.elapsed())
+    }
+
+    /// Renders the live view: gauge, aggregate stats and the per-thread table.
+    pub fn render(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let [gauge_area, stats_area, table_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(1),
+            Constraint::Min(0),
+        ]).areas(area);
+
+        let gauge = Gauge::default()
+            .block(theme.block().title(" Attempts "))
+            .gauge_style(theme.gauge)
+            .ratio(self.ratio())
+            .label(format!(
+                "{} / {}",
+                format_count(self.total_attempts()),
+                format_count(self.recommended_attempts),
+            ));
+        frame.render_widget(gauge, gauge_area);
+
+        let eta = self.eta().map(format_duration).unwrap_or_else(|| crate::display::glyphs().missing.to_string());
+        let stats = Line::from(format!(
+            " Hash rate: {}   Elapsed: {}   ETA: {}   Threads: {}",
+            format_hash_rate(self.total_hash_rate()),
+            format_duration(self.elapsed()),
+            eta,
+            self.thread_count,
+        ));
+        frame.render_widget(Paragraph::new(stats), stats_area);
+
+        let rows = self.threads.iter().map(|(thread_id, stats)| {
+            Row::new([
+                thread_id.to_string(),
+                format_count(stats.attempts),
+                format_hash_rate(stats.hash_rate),
+            ])
+        });
+        let table = Table::new(rows, [
+            Constraint::Length(8),
+            Constraint::Length(16),
+            Constraint::Min(12),
+        ])
+            .header(Row::new(["Thread", "Attempts", "Rate"]).bold())
+            .block(theme.block().title(" Threads "));
+        frame.render_widget(table, table_area);
+    }
+
+    /// Renders the frozen summary shown above the results.
+    pub fn render_summary(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let elapsed = self.elapsed();
+        let headline = match self.finished {
+            Some((_, Outcome::Solved { nonce })) => format!(
+                "Solved in {} (nonce {})",
+                format_duration(elapsed),
+                format_count(nonce),
+            ),
+            Some((_, Outcome::Cancelled)) => format!("Cancelled after {}", format_duration(elapsed)),
+            None => format!("Running for {}", format_duration(elapsed)),
+        };
+
+        let average_rate = if elapsed.is_zero() {
+            0
+        } else {
+            (self.total_attempts() as f64 / elapsed.as_secs_f64()) as u64
+        };
+
+        let lines = vec![
+            Line::from(headline).bold(),
+            Line::from(format!(
+                "Attempts: {} ({:.0}% of recommended)",
+                format_count(self.total_attempts()),
+                self.ratio() * 100.0,
+            )),
+            Line::from(format!(
+                "Average hash rate: {} across {} threads",
+                format_hash_rate(average_rate),
+                self.thread_count,
+            )),
+        ];
+        frame.render_widget(
+            Paragraph::new(lines).block(theme.block().title(" Summary ")),
+            area,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_totals_use_latest_value_per_thread() {
+        let mut dashboard = SolveDashboard::new(1_000, 2);
+        dashboard.record(0, 100, 50);
+        dashboard.record(1, 200, 70);
+        dashboard.record(0, 300, 60);
+
+        assert_eq!(dashboard.total_attempts(), 500);
+        assert_eq!(dashboard.total_hash_rate(), 130);
+    }
+
+    #[test]
+    fn test_ratio_is_capped() {
+        let mut dashboard = SolveDashboard::new(1_000, 1);
+        dashboard.record(0, 250, 10);
+        assert_eq!(dashboard.ratio(), 0.25);
+
+        dashboard.record(0, 5_000, 10);
+        assert_eq!(dashboard.ratio(), 1.0);
+
+        assert_eq!(SolveDashboard::new(0, 1).ratio(), 0.0);
+    }
+
+    #[test]
+    fn test_eta() {
+        let mut dashboard = SolveDashboard::new(1_000, 2);
+        assert_eq!(dashboard.eta(), None);
+
+        dashboard.record(0, 200, 50);
+        dashboard.record(1, 200, 50);
+        assert_eq!(dashboard.eta(), Some(Duration::from_secs(6)));
+    }
+
+    #[test]
+    fn test_finish_freezes_progress() {
+        let mut dashboard = SolveDashboard::new(1_000, 1);
+        dashboard.record(0, 100, 10);
+        dashboard.finish(Outcome::Solved { nonce: 42 });
+        dashboard.record(0, 900, 10);
+        dashboard.finish(Outcome::Cancelled);
+
+        assert!(dashboard.is_finished());
+        assert_eq!(dashboard.total_attempts(), 100);
+        assert!(matches!(dashboard.finished, Some((_, Outcome::Solved { nonce: 42 }))));
+    }
+}
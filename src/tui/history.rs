@@ -0,0 +1,240 @@
+use chrono::{DateTime, Local, Utc};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Paragraph, Row, Table, TableState},
+};
+
+use std::time::Duration;
+
+use super::theme::Theme;
+use crate::display::{format_count, format_duration, format_hash_rate};
+use crate::history::{HistoryStore, RunOutcome, RunRecord};
+
+/// The "History" tab: previous runs from the history store,
+/// newest first, with a detail view for the selected run.
+#[derive(Debug, Clone)]
+pub struct HistoryView {
+    store:    Option<HistoryStore>,
+    records:  Vec<RunRecord>,
+    selected: usize,
+    detail:   bool,
+    error:    Option<String>,
+}
+
+impl HistoryView {
+    pub fn new(store: Option<HistoryStore>) -> Self {
+        Self {
+            store,
+            records:  Vec::new(),
+            selected: 0,
+            detail:   false,
+            error:    None,
+        }
+    }
+
+    /// Re-reads the store, keeping the selection in range.
+    pub fn reload(&mut self) {
+        self.error = None;
+        self.records = match &self.store {
+            Some(store) => match store.load() {
+                Ok(mut records) => {
+                    records.reverse();
+                    records
+                }
+                Err(e) => {
+                    self.error = Some(format!("Cannot read '{}': {e}", store.path().display()));
+                    Vec::new()
+                }
+            },
+            None => Vec::new(),
+        };
+        self.selected = self.selected.min(self.records.len().saturating_sub(1));
+    }
+
+    pub fn selected(&self) -> Option<&RunRecord> {
+        self.records.get(self.selected)
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1).min(self.records.len().saturating_sub(1));
+    }
+
+    pub fn is_showing_detail(&self) -> bool {
+        self.detail
+    }
+
+    pub fn set_detail(&mut self, detail: bool) {
+        self.detail = detail && self.selected().is_some();
+    }
+
+    /// Deletes the selected run from the store.
+    pub fn delete_selected(&mut self) {
+        let (Some(store), Some(record)) = (&self.store, self.selected()) else {
+            return;
+        };
+
+        match store.remove(record) {
+            Ok(_) => {
+                self.detail = false;
+                self.reload();
+            }
+            Err(e) => self.error = Some(format!("Cannot delete run: {e}")),
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        if let Some(record) = self.selected().filter(|_| self.detail) {
+            frame.render_widget(
+                Paragraph::new(detail_lines(record, theme)).block(theme.block().title(" Run details ")),
+                area,
+            );
+            return;
+        }
+
+        let block = theme.block().title(match &self.store {
+            Some(store) => format!(" History ({}) ", store.path().display()),
+            None        => " History ".to_string(),
+        });
+
+        if let Some(error) = &self.error {
+            frame.render_widget(Paragraph::new(error.as_str()).style(theme.error).block(block), area);
+            return;
+        }
+        if self.records.is_empty() {
+            frame.render_widget(Paragraph::new("No runs recorded yet.").dim().block(block), area);
+            return;
+        }
+
+        let rows = self.records.iter().map(|record| {
+            Row::new([
+                local_time(record.timestamp),
+                record.command.name().to_string(),
+                record.endpoint.clone(),
+                record.difficulty.map(format_count).unwrap_or_default(),
+                format_duration(Duration::from_millis(record.elapsed_ms)),
+                outcome_label(record.outcome).to_string(),
+            ]).style(outcome_style(record.outcome, theme))
+        });
+        let table = Table::new(rows, [
+            Constraint::Length(19),
+            Constraint::Length(8),
+            Constraint::Min(20),
+            Constraint::Length(14),
+            Constraint::Length(10),
+            Constraint::Length(7),
+        ])
+            .header(Row::new(["When", "Command", "Endpoint", "Difficulty", "Time", "Outcome"]).bold())
+            .row_highlight_style(Style::new().reversed())
+            .block(block);
+
+        let mut state = TableState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(table, area, &mut state);
+    }
+}
+
+fn local_time(timestamp: DateTime<Utc>) -> String {
+    timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn outcome_label(outcome: RunOutcome) -> &'static str {
+    match outcome {
+        RunOutcome::Success => "ok",
+        RunOutcome::Failure => "failed",
+    }
+}
+
+fn outcome_style(outcome: RunOutcome, theme: &Theme) -> Style {
+    match outcome {
+        RunOutcome::Success => Style::new(),
+        RunOutcome::Failure => theme.error,
+    }
+}
+
+fn detail_lines(record: &RunRecord, theme: &Theme) -> Vec<Line<'static>> {
+    let optional = |value: Option<String>| value.unwrap_or_else(|| crate::display::glyphs().missing.to_string());
+
+    let token_expiry = record.token_valid_for
+        .and_then(DateTime::from_timestamp_millis)
+        .map(|valid_until| {
+            let status = if valid_until <= Utc::now() { "expired" } else { "valid" };
+            format!("{} ({status})", local_time(valid_until))
+        });
+
+    vec![
+        Line::from(format!("Endpoint:          {}", record.endpoint)).bold(),
+        Line::from(format!("Command:           {}", record.command.name())),
+        Line::from(format!("Started:           {}", local_time(record.timestamp))),
+        Line::from(format!("Outcome:           {}", outcome_label(record.outcome))).style(outcome_style(record.outcome, theme)),
+        Line::from(format!("Error:             {}", optional(record.error.clone()))),
+        Line::from(format!("Failed during:     {}", optional(record.error_kind.map(|kind| kind.name().to_string())))),
+        Line::from(""),
+        Line::from(format!("Total time:        {}", format_duration(Duration::from_millis(record.elapsed_ms)))),
+        Line::from(format!("Solve time:        {}", optional(record.solve_ms.map(|ms| format_duration(Duration::from_millis(ms)))))),
+        Line::from(format!("Difficulty:        {}", optional(record.difficulty.map(format_count)))),
+        Line::from(format!("Threads:           {}", optional(record.thread_count.map(|t| t.to_string())))),
+        Line::from(format!("Attempts:          {}", optional(record.attempts.map(format_count)))),
+        Line::from(format!("Hash rate:         {}", optional(record.hash_rate().map(format_hash_rate)))),
+        Line::from(format!("Token valid until: {}", optional(token_expiry))),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::RunCommand;
+    use tempfile::tempdir;
+
+    fn view_with(endpoints: &[&str]) -> (tempfile::TempDir, HistoryView) {
+        let dir = tempdir().unwrap();
+        let store = HistoryStore::new(dir.path().join("history.jsonl"));
+        for endpoint in endpoints {
+            let mut record = RunRecord::new(RunCommand::Validate, endpoint);
+            record.finish(Duration::from_secs(1), None);
+            store.append(&record).unwrap();
+        }
+
+        let mut view = HistoryView::new(Some(store));
+        view.reload();
+        (dir, view)
+    }
+
+    #[test]
+    fn test_newest_first_and_selection_is_clamped() {
+        let (_dir, mut view) = view_with(&["https://a.example", "https://b.example"]);
+        assert_eq!(view.selected().unwrap().endpoint, "https://b.example");
+
+        view.select_next();
+        view.select_next();
+        assert_eq!(view.selected().unwrap().endpoint, "https://a.example");
+
+        view.select_previous();
+        view.select_previous();
+        assert_eq!(view.selected().unwrap().endpoint, "https://b.example");
+    }
+
+    #[test]
+    fn test_delete_selected() {
+        let (_dir, mut view) = view_with(&["https://a.example", "https://b.example"]);
+        view.select_next();
+        view.set_detail(true);
+        view.delete_selected();
+
+        assert!(!view.is_showing_detail());
+        assert_eq!(view.records.len(), 1);
+        assert_eq!(view.selected().unwrap().endpoint, "https://b.example");
+    }
+
+    #[test]
+    fn test_detail_needs_a_selection() {
+        let (_dir, mut view) = view_with(&[]);
+        view.set_detail(true);
+        assert!(!view.is_showing_detail());
+    }
+}
@@ -0,0 +1,177 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// A single-line text input with a cursor, used for endpoint entry.
+///
+/// The cursor is tracked in characters rather than bytes so that
+/// editing never splits a multi-byte character.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct InputField {
+    value:  String,
+    cursor: usize,
+}
+
+impl InputField {
+    /// Current contents of the field.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Cursor position in characters from the start.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.value.trim().is_empty()
+    }
+
+    /// Inserts a character at the cursor.
+    pub fn insert(&mut self, c: char) {
+        if c.is_control() {
+            return;
+        }
+        let index = self.byte_index();
+        self.value.insert(index, c);
+        self.cursor += 1;
+    }
+
+    /// Inserts pasted text at the cursor, dropping line breaks
+    /// and other control characters.
+    pub fn insert_str(&mut self, text: &str) {
+        text.chars().for_each(|c| self.insert(c));
+    }
+
+    /// Removes the character before the cursor.
+    pub fn backspace(&mut self) {
+        if self.cursor == 0 {
+            return;
+        }
+        self.cursor -= 1;
+        let index = self.byte_index();
+        self.value.remove(index);
+    }
+
+    /// Removes the character under the cursor.
+    pub fn delete(&mut self) {
+        if self.cursor < self.len() {
+            let index = self.byte_index();
+            self.value.remove(index);
+        }
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.len());
+    }
+
+    pub fn move_home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.cursor = self.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.value.clear();
+        self.cursor = 0;
+    }
+
+    /// Applies an editing key: characters, Backspace/Delete, cursor
+    /// movement, and Ctrl-U to clear.
+    ///
+    /// # Returns
+    /// * `bool`: Whether the key was an editing key. Enter and Esc
+    ///           are left to the caller.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => self.clear(),
+            KeyCode::Char(c)   => self.insert(c),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete    => self.delete(),
+            KeyCode::Left      => self.move_left(),
+            KeyCode::Right     => self.move_right(),
+            KeyCode::Home      => self.move_home(),
+            KeyCode::End       => self.move_end(),
+            _ => return false,
+        }
+        true
+    }
+
+    fn len(&self) -> usize {
+        self.value.chars().count()
+    }
+
+    fn byte_index(&self) -> usize {
+        self.value
+            .char_indices()
+            .nth(self.cursor)
+            .map(|(index, _)| index)
+            .unwrap_or(self.value.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(text: &str) -> InputField {
+        let mut field = InputField::default();
+        field.insert_str(text);
+        field
+    }
+
+    #[test]
+    fn test_insert_and_backspace() {
+        let mut input = field("https://example.com");
+        assert_eq!(input.cursor(), 19);
+
+        input.backspace();
+        input.backspace();
+        input.backspace();
+        assert_eq!(input.value(), "https://example.");
+    }
+
+    #[test]
+    fn test_edit_in_the_middle() {
+        let mut input = field("https://exmple.com");
+        input.move_home();
+        (0..10).for_each(|_| input.move_right());
+        input.insert('a');
+        assert_eq!(input.value(), "https://example.com");
+
+        input.delete();
+        assert_eq!(input.value(), "https://exaple.com");
+    }
+
+    #[test]
+    fn test_multibyte_characters() {
+        let mut input = field("héllo");
+        input.move_left();
+        input.backspace();
+        assert_eq!(input.value(), "hélo");
+        assert_eq!(input.cursor(), 3);
+    }
+
+    #[test]
+    fn test_paste_drops_line_breaks() {
+        let input = field("https://example.com\r\n");
+        assert_eq!(input.value(), "https://example.com");
+    }
+
+    #[test]
+    fn test_cursor_is_clamped() {
+        let mut input = field("ab");
+        input.move_right();
+        assert_eq!(input.cursor(), 2);
+
+        input.move_home();
+        input.move_left();
+        input.backspace();
+        assert_eq!(input.cursor(), 0);
+        assert_eq!(input.value(), "ab");
+    }
+}
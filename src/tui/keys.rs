@@ -0,0 +1,171 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Named keys accepted in `[tui.keys]`, besides single characters
+/// and `f1`–`f12`.
+const KEY_NAMES: [(&str, KeyCode); 8] = [
+    ("enter",     KeyCode::Enter),
+    ("tab",       KeyCode::Tab),
+    ("backtab",   KeyCode::BackTab),
+    ("esc",       KeyCode::Esc),
+    ("space",     KeyCode::Char(' ')),
+    ("backspace", KeyCode::Backspace),
+    ("delete",    KeyCode::Delete),
+    ("insert",    KeyCode::Insert),
+];
+
+/// A key from the config file, e.g. `q`, `enter`, `f5` or `ctrl-r`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyBinding {
+    code: KeyCode,
+    ctrl: bool,
+}
+
+impl KeyBinding {
+    pub const fn new(code: KeyCode) -> Self {
+        Self { code, ctrl: false }
+    }
+
+    pub fn matches(&self, key: KeyEvent) -> bool {
+        key.code == self.code && key.modifiers.contains(KeyModifiers::CONTROL) == self.ctrl
+    }
+
+    /// The key as shown in the footer, e.g. `Enter` or `Ctrl-R`.
+    pub fn label(&self) -> String {
+        let name = match self.code {
+            KeyCode::Char(' ')            => "Space".to_string(),
+            KeyCode::Char(c) if self.ctrl => c.to_ascii_uppercase().to_string(),
+            KeyCode::Char(c)              => c.to_string(),
+            KeyCode::F(n)                 => format!("F{n}"),
+            code                          => code.to_string(),
+        };
+        if self.ctrl { format!("Ctrl-{name}") } else { name }
+    }
+
+    /// Whether the key types a character, so it can't double as
+    /// a command while a text field has focus.
+    pub fn is_text(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_)) && !self.ctrl
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let lower = value.to_ascii_lowercase();
+        let (ctrl, name) = match lower.strip_prefix("ctrl-").or_else(|| lower.strip_prefix("ctrl+")) {
+            Some(rest) => (true, rest),
+            None       => (false, lower.as_str()),
+        };
+
+        let code = if let Some((_, code)) = KEY_NAMES.iter().find(|(known, _)| *known == name) {
+            Some(*code)
+        } else if let Some(number) = name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+            (1..=12).contains(&number).then_some(KeyCode::F(number))
+        } else {
+            // Keep the case of a plain character so `Q` can differ from
+            // `q`. Terminals report Ctrl combinations in lowercase.
+            let text = if ctrl { name } else { value };
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(KeyCode::Char(c)),
+                _               => None,
+            }
+        };
+
+        code.map(|code| Self { code, ctrl }).ok_or_else(|| {
+            let accepted: Vec<&str> = KEY_NAMES.iter().map(|(name, _)| *name).collect();
+            format!(
+                "unknown key '{value}' (accepted: a single character, {}, f1-f12, with an optional ctrl- prefix)",
+                accepted.join(", "),
+            )
+        })
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            f.write_str("ctrl-")?;
+        }
+        match (self.code, KEY_NAMES.iter().find(|(_, code)| *code == self.code)) {
+            (_, Some((name, _)))  => f.write_str(name),
+            (KeyCode::F(n), _)    => write!(f, "f{n}"),
+            (KeyCode::Char(c), _) => write!(f, "{c}"),
+            (code, _)             => write!(f, "{code}"),
+        }
+    }
+}
+
+impl From<KeyBinding> for String {
+    fn from(key: KeyBinding) -> Self {
+        key.to_string()
+    }
+}
+
+/// Remappable keys, from the `[tui.keys]` section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub quit:       KeyBinding,
+    pub cancel:     KeyBinding,
+    /// Runs the selected command.
+    pub start:      KeyBinding,
+    /// Cycles through the tabs.
+    pub switch_tab: KeyBinding,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            quit:       KeyBinding::new(KeyCode::Char('q')),
+            cancel:     KeyBinding::new(KeyCode::Char('c')),
+            start:      KeyBinding::new(KeyCode::Enter),
+            switch_tab: KeyBinding::new(KeyCode::Tab),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_round_trip() {
+        for key in ["q", "Q", "enter", "tab", "f5", "ctrl-r", "space"] {
+            assert_eq!(key.parse::<KeyBinding>().unwrap().to_string(), key);
+        }
+        assert_eq!("Ctrl+R".parse::<KeyBinding>().unwrap().to_string(), "ctrl-r");
+    }
+
+    #[test]
+    fn test_unknown_key_is_rejected() {
+        assert!("f13".parse::<KeyBinding>().is_err());
+        let error = "hyper".parse::<KeyBinding>().unwrap_err();
+        assert!(error.contains("unknown key 'hyper'"));
+    }
+
+    #[test]
+    fn test_matches_respects_ctrl() {
+        let key: KeyBinding = "ctrl-r".parse().unwrap();
+        assert!(key.matches(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL)));
+        assert!(!key.matches(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE)));
+        assert!(!key.is_text());
+        assert_eq!(key.label(), "Ctrl-R");
+        assert_eq!(KeyBinding::new(KeyCode::Enter).label(), "Enter");
+    }
+}
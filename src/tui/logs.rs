@@ -0,0 +1,238 @@
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Stylize},
+    text::{Line, Span},
+    widgets::Paragraph,
+};
+
+use std::cell::Cell;
+use std::io::{self, Write};
+
+use super::ring::RingBuffer;
+use super::theme::Theme;
+use crate::logging::{CategorySet, LogCategory, LogRecord};
+
+/// Captured console lines kept for the log pane.
+const CAPACITY: usize = 1_000;
+
+/// Lines moved by PageUp/PageDown.
+const PAGE_SCROLL: usize = 10;
+
+/// Scrollable view over the console output captured while the TUI runs.
+#[derive(Debug, Clone)]
+pub struct LogPane {
+    records: RingBuffer<LogRecord>,
+    /// Lines scrolled up from the newest; 0 follows the tail.
+    scroll:  usize,
+    filter:  CategorySet,
+    focused: bool,
+    /// Lines shown at the last render, so scrolling stops at the oldest page.
+    height:  Cell<usize>,
+}
+
+impl Default for LogPane {
+    fn default() -> Self {
+        Self {
+            records: RingBuffer::new(CAPACITY),
+            scroll:  0,
+            filter:  CategorySet::ALL,
+            focused: false,
+            height:  Cell::new(1),
+        }
+    }
+}
+
+impl LogPane {
+    pub fn push(&mut self, record: LogRecord) {
+        // Keep the view still while scrolled back through history.
+        if self.scroll > 0 && self.passes(&record) {
+            self.scroll += 1;
+        }
+        self.records.push(record);
+        self.clamp_scroll();
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.scroll += lines;
+        self.clamp_scroll();
+    }
+
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+
+    pub fn page_up(&mut self) {
+        self.scroll_up(PAGE_SCROLL);
+    }
+
+    pub fn page_down(&mut self) {
+        self.scroll_down(PAGE_SCROLL);
+    }
+
+    /// Jumps to the oldest line.
+    pub fn home(&mut self) {
+        self.scroll = usize::MAX;
+        self.clamp_scroll();
+    }
+
+    /// Jumps back to following the newest line.
+    pub fn end(&mut self) {
+        self.scroll = 0;
+    }
+
+    /// Shows or hides a verbose category. Errors and warnings always show.
+    pub fn toggle(&mut self, category: LogCategory) {
+        self.filter.toggle(category);
+        self.clamp_scroll();
+    }
+
+    fn passes(&self, record: &LogRecord) -> bool {
+        record.category.is_none_or(|category| self.filter.contains(category))
+    }
+
+    fn filtered(&self) -> impl DoubleEndedIterator<Item = &LogRecord> {
+        self.records.iter().filter(|record| self.passes(record))
+    }
+
+    fn clamp_scroll(&mut self) {
+        let count = self.filtered().count();
+        self.scroll = self.scroll.min(count.saturating_sub(self.height.get().max(1)));
+    }
+
+    /// The records visible in a pane `height` lines tall, oldest first.
+    pub fn visible(&self, height: usize) -> Vec<&LogRecord> {
+        self.height.set(height);
+
+        let mut lines: Vec<&LogRecord> = self.filtered()
+            .rev()
+            .skip(self.scroll)
+            .take(height)
+            .collect();
+        lines.reverse();
+        lines
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let title = if self.scroll > 0 {
+            format!(" Logs ({}) — {} lines back ", self.filter, self.scroll)
+        } else {
+            format!(" Logs ({}) ", self.filter)
+        };
+        let block = theme.focus_block(self.focused).title(title);
+
+        let height = area.height.saturating_sub(2) as usize;
+        let lines: Vec<Line> = self.visible(height)
+            .into_iter()
+            .map(|record| match record.category {
+                Some(category) => Line::from(vec![
+                    Span::from(category.label()).fg(label_color(category)),
+                    Span::from(format!(": {}", record.message)),
+                ]),
+                None => Line::from(record.message.as_str()),
+            })
+            .collect();
+
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+
+    /// Writes every captured line, regardless of filter, in console format.
+    pub fn dump(&self, out: &mut impl Write) -> io::Result<()> {
+        for record in self.records.iter() {
+            match record.category {
+                Some(category) => writeln!(out, "{}: {}", category.label(), record.message)?,
+                None           => writeln!(out, "{}", record.message)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Maps the console label color of a category onto ratatui's palette.
+fn label_color(category: LogCategory) -> Color {
+    use crossterm::style::Color as Console;
+
+    match category.color() {
+        Console::Red      => Color::Red,
+        Console::Green    => Color::Green,
+        Console::Yellow   => Color::Yellow,
+        Console::Blue     => Color::Blue,
+        Console::Magenta  => Color::Magenta,
+        Console::DarkCyan => Color::Cyan,
+        Console::Cyan     => Color::LightCyan,
+        Console::Grey     => Color::Gray,
+        _                 => Color::Reset,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(category: Option<LogCategory>, message: &str) -> LogRecord {
+        LogRecord { category, message: message.to_string() }
+    }
+
+    fn messages(pane: &LogPane, height: usize) -> Vec<&str> {
+        pane.visible(height).into_iter().map(|r| r.message.as_str()).collect()
+    }
+
+    #[test]
+    fn test_follows_tail_by_default() {
+        let mut pane = LogPane::default();
+        (1..=5).for_each(|n| pane.push(record(None, &n.to_string())));
+
+        assert_eq!(messages(&pane, 2), vec!["4", "5"]);
+    }
+
+    #[test]
+    fn test_scrolling_holds_position() {
+        let mut pane = LogPane::default();
+        (1..=5).for_each(|n| pane.push(record(None, &n.to_string())));
+        assert_eq!(messages(&pane, 2), vec!["4", "5"]);
+
+        pane.scroll_up(2);
+        assert_eq!(messages(&pane, 2), vec!["2", "3"]);
+
+        pane.push(record(None, "6"));
+        assert_eq!(messages(&pane, 2), vec!["2", "3"]);
+
+        pane.home();
+        assert_eq!(messages(&pane, 2), vec!["1", "2"]);
+
+        pane.end();
+        assert_eq!(messages(&pane, 2), vec!["5", "6"]);
+    }
+
+    #[test]
+    fn test_toggle_hides_category() {
+        let mut pane = LogPane::default();
+        pane.push(record(Some(LogCategory::Network), "request"));
+        pane.push(record(Some(LogCategory::Error), "failed"));
+        pane.push(record(None, "status"));
+
+        pane.toggle(LogCategory::Network);
+        pane.toggle(LogCategory::Error);
+        assert_eq!(messages(&pane, 10), vec!["failed", "status"]);
+    }
+
+    #[test]
+    fn test_dump_ignores_filter() {
+        let mut pane = LogPane::default();
+        pane.push(record(Some(LogCategory::Timing), "took 1s"));
+        pane.push(record(None, "done"));
+        pane.toggle(LogCategory::Timing);
+
+        let mut out = Vec::new();
+        pane.dump(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "TIMING: took 1s\ndone\n");
+    }
+}
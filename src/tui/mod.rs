@@ -0,0 +1,1455 @@
+mod capability;
+mod dashboard;
+mod history;
+mod input;
+pub mod keys;
+mod logs;
+mod queue;
+mod rate;
+mod ring;
+mod settings;
+mod task;
+pub mod theme;
+
+use color_eyre::Result;
+use crossterm::clipboard::CopyToClipboard;
+use crossterm::event::{
+    DisableMouseCapture,
+    EnableMouseCapture,
+    Event,
+    EventStream,
+    KeyCode,
+    KeyEvent,
+    KeyEventKind,
+    KeyModifiers,
+    MouseButton,
+    MouseEvent,
+    MouseEventKind,
+};
+use futures::{
+    FutureExt,
+    StreamExt
+};
+use ratatui::{
+    DefaultTerminal,
+    Frame,
+    layout::{Constraint, Layout, Position, Rect},
+    style::Stylize,
+    text::{Line, Span},
+    widgets::{Paragraph, Tabs, Wrap},
+};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tokio::task::JoinHandle;
+use tokio::time::{Interval, MissedTickBehavior};
+
+use ironshield::ClientConfig;
+
+use std::io;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dashboard::{Outcome, SolveDashboard};
+use history::HistoryView;
+use input::InputField;
+use logs::LogPane;
+use rate::RateHistory;
+use keys::KeyBindings;
+use queue::QueueView;
+use settings::SettingsView;
+use task::{Action, TaskEvent};
+use theme::Theme;
+use crate::batch::{self, BatchUpdate, Stage};
+use crate::client::CliClient;
+use crate::config::TuiConfig;
+use crate::history::HistoryStore;
+use crate::logging::{LogCategory, LogRecord};
+use crate::throttle::ThrottleDetector;
+
+/// Lines moved by PageUp/PageDown in the results pane.
+const PAGE_SCROLL: u16 = 10;
+
+/// Redraw interval while an action runs (~4 fps), independent
+/// of how often the solver reports progress.
+const TICK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Height of the frozen solve summary above the results.
+const SUMMARY_HEIGHT: u16 = 5;
+
+/// Height of the hash rate sparkline below the live dashboard.
+const SPARKLINE_HEIGHT: u16 = 6;
+
+/// Height of the log pane above the footer.
+const LOG_PANE_HEIGHT: u16 = 8;
+
+/// Lines moved per mouse wheel step.
+const WHEEL_SCROLL: u16 = 3;
+
+/// Tab titles, in [`View`] order.
+const TAB_TITLES: [&str; 4] = ["Run", "Queue", "History", "Settings"];
+
+/// Command-line options that shape the TUI session.
+#[derive(Debug, Clone, Default)]
+pub struct TuiOptions {
+    /// Print the captured log lines to stdout on exit.
+    pub dump_logs:    bool,
+    /// The resolved config file, which the settings screen saves to.
+    pub config_path:  Option<String>,
+    /// Whether `--verbose` was given, for the settings screen's sources.
+    pub verbose_flag: bool,
+    /// The `[tui]` section of the config file.
+    pub tui:          TuiConfig,
+}
+
+/// Runs the TUI until the user quits.
+///
+/// A terminal that can't host it (not a TTY, `TERM=dumb`, or smaller
+/// than 60x15) gets a plain-text pointer to the equivalent subcommands
+/// instead, before anything switches it to raw mode.
+///
+/// `ratatui::init` enters the alternate screen and chains a panic
+/// hook that calls `ratatui::restore` before the existing (color_eyre)
+/// hook reports the panic, so a crash never leaves the shell in raw mode.
+///
+/// Console logging is captured into the log pane while the TUI runs.
+/// The `--log-file`, if any, keeps receiving everything as usual.
+///
+/// # Arguments
+/// * `client`:  The API client.
+/// * `config`:  The client configuration.
+/// * `options`: Command-line options for the session.
+pub async fn run(client: CliClient, config: ClientConfig, options: TuiOptions) -> Result<()> {
+    if let Err(reason) = capability::check() {
+        eprint!("{}", capability::explain(&reason, options.config_path.as_deref()));
+        crate::logging::flush();
+        crate::har::write();
+        std::process::exit(1);
+    }
+
+    let settings = SettingsView::new(config.clone(), options.config_path, options.verbose_flag);
+    let mut app = App::new(client, config);
+    app.settings = settings;
+    app.theme = Theme::resolve(options.tui.theme, &options.tui.colors);
+    app.keys = options.tui.keys;
+    app.queue_concurrency = options.tui.queue_concurrency.max(1);
+    app.log_rx = Some(crate::logging::capture_console());
+
+    let terminal = ratatui::init();
+    enable_mouse_capture()?;
+    let result = app.run(terminal).await;
+    let _ = crossterm::execute!(io::stdout(), DisableMouseCapture);
+    ratatui::restore();
+    crate::logging::release_console();
+
+    if options.dump_logs {
+        app.logs.dump(&mut io::stdout().lock())?;
+    }
+
+    // A solve abandoned on quit still occupies blocking threads
+    // that would keep the runtime alive, same as the CLI handlers.
+    if result.is_ok() {
+        crate::logging::flush();
+        crate::har::write();
+        std::process::exit(0);
+    }
+    result
+}
+
+/// Turns on mouse reporting, and chains a panic hook that turns it
+/// off again ahead of the one installed by `ratatui::init`.
+fn enable_mouse_capture() -> io::Result<()> {
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = crossterm::execute!(io::stdout(), DisableMouseCapture);
+        hook(info);
+    }));
+    crossterm::execute!(io::stdout(), EnableMouseCapture)
+}
+
+/// The tab shown in the main area.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum View {
+    /// Endpoint entry, command menu and results.
+    #[default]
+    Run,
+    /// Endpoints validated in a batch.
+    Queue,
+    /// Previous runs from the history store.
+    History,
+    /// The client configuration editor.
+    Settings,
+}
+
+impl View {
+    pub const ALL: [View; 4] = [View::Run, View::Queue, View::History, View::Settings];
+}
+
+/// Where each pane was drawn last, for mouse hit-testing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Areas {
+    header:  Rect,
+    tabs:    Rect,
+    body:    Rect,
+    menu:    Rect,
+    results: Rect,
+    logs:    Rect,
+}
+
+/// Where the [`App`] is in its run cycle.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Choosing a command from the menu.
+    #[default]
+    Idle,
+    /// Typing into the endpoint field.
+    EnteringEndpoint,
+    /// An action is running on a background task.
+    Running(Action),
+    /// The last action succeeded; its output is in the results pane.
+    ShowingResult(Action),
+    /// The last action failed with this message.
+    Error(String),
+}
+
+pub struct App {
+    running:           bool,
+    view:              View,
+    mode:              Mode,
+    endpoint:          InputField,
+    selected:          Action,
+    results:           Vec<String>,
+    scroll:            u16,
+    dashboard:         Option<SolveDashboard>,
+    rates:             RateHistory,
+    /// Fed the same samples as `rates`; warns once if they sag.
+    throttle:          ThrottleDetector,
+    client:            Arc<CliClient>,
+    config:            ClientConfig,
+    /// The running action; aborted on cancel.
+    task:              Option<JoinHandle<()>>,
+    /// Events from the running action. Each run gets a fresh channel
+    /// so stragglers from a cancelled solve are never seen.
+    task_rx:           Option<UnboundedReceiver<TaskEvent>>,
+    logs:              LogPane,
+    /// Captured console output, when running in a real terminal.
+    log_rx:            Option<UnboundedReceiver<LogRecord>>,
+    /// Whether verbose lines are sent to the log pane.
+    verbose:           bool,
+    history:           HistoryView,
+    settings:          SettingsView,
+    queue:             QueueView,
+    /// The batch working through the queue; aborted on cancel.
+    queue_task:        Option<JoinHandle<()>>,
+    queue_rx:          Option<UnboundedReceiver<BatchUpdate>>,
+    /// Endpoints the queue validates at once.
+    queue_concurrency: usize,
+    theme:             Theme,
+    keys:              KeyBindings,
+    areas:             Areas,
+    /// Set on resize so the next draw starts from a cleared screen.
+    resized:           bool,
+}
+
+impl App {
+    /// Construct a new instance of [`App`].
+    pub fn new(client: CliClient, mut config: ClientConfig) -> Self {
+        // Our own verbose lines go to the log pane. The library's are
+        // printed directly and would draw over the screen, so turn them off.
+        let verbose = config.verbose;
+        let settings = SettingsView::new(config.clone(), None, false);
+        config.set_verbose(false);
+
+        Self {
+            running:           false,
+            view:              View::default(),
+            mode:              Mode::default(),
+            endpoint:          InputField::default(),
+            selected:          Action::default(),
+            results:           Vec::new(),
+            scroll:            0,
+            dashboard:         None,
+            rates:             RateHistory::default(),
+            throttle:          ThrottleDetector::default(),
+            client:            Arc::new(client.on_event(crate::commands::presenter::console(false))),
+            config,
+            task:              None,
+            task_rx:           None,
+            logs:              LogPane::default(),
+            log_rx:            None,
+            verbose,
+            history:           HistoryView::new(HistoryStore::open_default()),
+            settings,
+            queue:             QueueView::default(),
+            queue_task:        None,
+            queue_rx:          None,
+            queue_concurrency: 1,
+            theme:             Theme::default(),
+            keys:              KeyBindings::default(),
+            areas:             Areas::default(),
+            resized:           false,
+        }
+    }
+
+    /// Run the application's main loop for the TUI interface.
+    pub async fn run(&mut self, mut terminal: DefaultTerminal) -> Result<()> {
+        let mut event_stream = EventStream::new();
+        let mut tick = tokio::time::interval(TICK_INTERVAL);
+        tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        self.running = true;
+        let mut redraw = true;
+        while self.running {
+            if self.resized {
+                terminal.clear()?;
+                self.resized = false;
+            }
+            if redraw {
+                terminal.draw(|frame| self.draw(frame))?;
+            }
+            redraw = self.handle_events(&mut event_stream, &mut tick).await?;
+        }
+        Ok(())
+    }
+
+    /// Renders the user interface for TUI mode.
+    ///
+    /// This is where you add new widgets. See the following resources for more information:
+    /// - <https://docs.rs/ratatui/latest/ratatui/widgets/index.html>
+    /// - <https://github.com/ratatui/ratatui/tree/master/examples>
+    fn draw(&mut self, frame: &mut Frame) {
+        let area = frame.area();
+        if area.width < capability::MIN_WIDTH || area.height < capability::MIN_HEIGHT {
+            // Nothing is clickable until the terminal grows again.
+            self.areas = Areas::default();
+            let [_, middle, _] = Layout::vertical([
+                Constraint::Fill(1),
+                Constraint::Length(3),
+                Constraint::Fill(1),
+            ]).areas(area);
+            frame.render_widget(
+                Paragraph::new(capability::too_small_message(area.width, area.height))
+                    .style(self.theme.error)
+                    .centered()
+                    .wrap(Wrap { trim: true }),
+                middle,
+            );
+            return;
+        }
+
+        let [header, tabs, body, logs, footer] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(LOG_PANE_HEIGHT),
+            Constraint::Length(1),
+        ]).areas(area);
+        let [menu, results] = Layout::horizontal([
+            Constraint::Length(20),
+            Constraint::Min(0),
+        ]).areas(body);
+        self.areas = Areas { header, tabs, body, menu, results, logs };
+
+        self.draw_endpoint(frame, header);
+        frame.render_widget(
+            Tabs::new(TAB_TITLES)
+                .select(self.view as usize)
+                .highlight_style(self.theme.accent.bold()),
+            tabs,
+        );
+        match self.view {
+            View::Run => {
+                self.draw_menu(frame, menu);
+                self.draw_results(frame, results);
+            }
+            View::Queue    => self.queue.render(frame, body, &self.theme, self.queue_concurrency),
+            View::History  => self.history.render(frame, body, &self.theme),
+            View::Settings => self.settings.render(frame, body, &self.theme),
+        }
+        self.logs.render(frame, logs, &self.theme);
+        frame.render_widget(Paragraph::new(self.footer_line()), footer);
+    }
+
+    fn draw_endpoint(&self, frame: &mut Frame, area: Rect) {
+        let title = Line::from("IronShield CLI - TUI Mode")
+            .style(self.theme.title)
+            .centered();
+        let editing = self.mode == Mode::EnteringEndpoint;
+        let block = self.theme.focus_block(editing)
+            .title(Line::from(" Endpoint ").left_aligned())
+            .title(title);
+
+        // Scroll horizontally so the cursor stays visible in long URLs.
+        let width = area.width.saturating_sub(2) as usize;
+        let offset = self.endpoint.cursor().saturating_sub(width.saturating_sub(1));
+
+        let text = if self.endpoint.value().is_empty() && !editing {
+            Line::from("Press `e` to enter an endpoint URL").dim()
+        } else {
+            Line::from(self.endpoint.value())
+        };
+        frame.render_widget(
+            Paragraph::new(text).block(block).scroll((0, offset as u16)),
+            area,
+        );
+
+        if editing {
+            frame.set_cursor_position((
+                area.x + 1 + (self.endpoint.cursor() - offset) as u16,
+                area.y + 1,
+            ));
+        }
+    }
+
+    fn draw_menu(&self, frame: &mut Frame, area: Rect) {
+        let lines: Vec<Line> = Action::ALL
+            .into_iter()
+            .map(|action| {
+                let line = Line::from(format!(" [{}] {}", action.hotkey(), action.label()));
+                if action == self.selected {
+                    line.reversed()
+                } else {
+                    line
+                }
+            })
+            .collect();
+
+        frame.render_widget(
+            Paragraph::new(lines).block(self.theme.block().title(" Command ")),
+            area,
+        );
+    }
+
+    fn draw_results(&self, frame: &mut Frame, area: Rect) {
+        let area = match (&self.mode, &self.dashboard) {
+            (Mode::Running(_), Some(dashboard)) => {
+                let [live, sparkline] = Layout::vertical([
+                    Constraint::Min(0),
+                    Constraint::Length(SPARKLINE_HEIGHT),
+                ]).areas(area);
+                dashboard.render(frame, live, &self.theme);
+                self.rates.render(frame, sparkline, &self.theme);
+                return;
+            }
+            (Mode::ShowingResult(_), Some(dashboard)) if dashboard.is_finished() => {
+                let [summary, rest] = Layout::vertical([
+                    Constraint::Length(SUMMARY_HEIGHT),
+                    Constraint::Min(0),
+                ]).areas(area);
+                dashboard.render_summary(frame, summary, &self.theme);
+                rest
+            }
+            _ => area,
+        };
+
+        let (title, paragraph) = match &self.mode {
+            Mode::Running(action) => (
+                format!(" {} ", action.label()),
+                Paragraph::new(format!("Running {} against {}…", action.label().to_lowercase(), self.endpoint.value())),
+            ),
+            Mode::Error(message) => (
+                " Error ".to_string(),
+                Paragraph::new(message.as_str()).style(self.theme.error),
+            ),
+            _ if self.results.is_empty() => (
+                " Results ".to_string(),
+                Paragraph::new("Enter an endpoint, pick a command and press Enter.").dim(),
+            ),
+            _ => (
+                " Results ".to_string(),
+                Paragraph::new(self.results.iter().map(|line| Line::from(line.as_str())).collect::<Vec<_>>())
+                    .scroll((self.scroll, 0)),
+            ),
+        };
+
+        frame.render_widget(
+            paragraph
+                .block(self.theme.block().title(title))
+                .wrap(Wrap { trim: false }),
+            area,
+        );
+    }
+
+    /// Keybindings for the current mode, shown in the footer bar.
+    fn footer_hints(&self) -> Vec<(String, &'static str)> {
+        let fixed = |hints: &[(&str, &'static str)]| -> Vec<(String, &'static str)> {
+            hints.iter().map(|(key, description)| (key.to_string(), *description)).collect()
+        };
+        let quit = (self.keys.quit.label(), "quit");
+        let next_tab = (self.keys.switch_tab.label(), "next tab");
+
+        if self.view == View::Settings && !self.logs.is_focused() {
+            return if self.settings.is_confirming() {
+                fixed(&[("y", "save"), ("n/Esc", "cancel")])
+            } else if self.settings.is_editing() {
+                fixed(&[("Enter", "apply"), ("Esc", "cancel"), ("Ctrl-U", "clear")])
+            } else {
+                let mut hints = fixed(&[("↑/↓", "select"), ("Enter", "edit"), ("w", "save"), ("s/Esc", "back"), ("l", "logs")]);
+                hints.extend([next_tab, quit]);
+                hints
+            };
+        }
+
+        if self.view == View::Queue && !self.logs.is_focused() {
+            if self.queue.is_adding() {
+                return fixed(&[("Enter", "add"), ("Esc", "done"), ("Ctrl-U", "clear")]);
+            }
+            let mut hints = fixed(&[("↑/↓", "select"), ("a", "add"), ("d", "remove")]);
+            hints.push((self.keys.start.label(), "start"));
+            hints.push((self.keys.cancel.label(), "cancel"));
+            hints.extend(fixed(&[("r", "retry failed"), ("y", "copy token"), ("+/-", "concurrency"), ("u/Esc", "back")]));
+            hints.extend([next_tab, quit]);
+            return hints;
+        }
+
+        if self.view == View::History && !self.logs.is_focused() {
+            let mut hints = if self.history.is_showing_detail() {
+                fixed(&[("Esc", "back"), ("d", "delete"), ("v", "validate again")])
+            } else {
+                fixed(&[("↑/↓", "select"), ("Enter", "details"), ("d", "delete"), ("v", "validate again"), ("h/Esc", "back")])
+            };
+            hints.extend([next_tab, quit]);
+            return hints;
+        }
+
+        if self.logs.is_focused() {
+            return fixed(&[
+                ("↑/↓ PgUp/PgDn", "scroll"),
+                ("Home/End", "oldest/newest"),
+                ("1-9", "toggle category"),
+                ("Esc", "back"),
+            ]);
+        }
+
+        match self.mode {
+            Mode::Idle => {
+                let mut hints = fixed(&[("e", "endpoint"), ("↑/↓", "select")]);
+                hints.push((self.keys.start.label(), "run"));
+                hints.extend(fixed(&[("f/o/v", "fetch/solve/validate"), ("u", "queue"), ("h", "history"), ("s", "settings"), ("l", "logs")]));
+                hints.extend([next_tab, quit]);
+                hints
+            }
+            Mode::EnteringEndpoint => fixed(&[
+                ("Enter", "run"),
+                ("Esc", "done"),
+                ("Ctrl-U", "clear"),
+            ]),
+            Mode::Running(_) => vec![
+                (self.keys.cancel.label(), "cancel"),
+                ("l".to_string(), "logs"),
+                ("Ctrl-C".to_string(), "quit"),
+            ],
+            Mode::ShowingResult(_) | Mode::Error(_) => {
+                let mut hints = fixed(&[
+                    ("↑/↓ PgUp/PgDn", "scroll"),
+                    ("e", "endpoint"),
+                    ("f/o/v", "run"),
+                    ("u", "queue"),
+                    ("h", "history"),
+                    ("s", "settings"),
+                    ("l", "logs"),
+                    ("Esc", "back"),
+                ]);
+                hints.extend([next_tab, quit]);
+                hints
+            }
+        }
+    }
+
+    fn footer_line(&self) -> Line<'static> {
+        let spans: Vec<Span> = self
+            .footer_hints()
+            .iter()
+            .flat_map(|(key, description)| [
+                Span::from(format!(" {key} ")).style(self.theme.key),
+                Span::from(format!("{description}  ")),
+            ])
+            .collect();
+        Line::from(spans)
+    }
+
+    /// Waits for the next terminal, tick, background task or log event
+    /// and updates the state of [`App`].
+    ///
+    /// # Returns
+    /// * `Result<bool>`: Whether the screen needs redrawing.
+    async fn handle_events(&mut self, event_stream: &mut EventStream, tick: &mut Interval) -> Result<bool> {
+        let running = matches!(self.mode, Mode::Running(_));
+        let ticking = running || self.queue_task.is_some();
+
+        tokio::select! {
+            maybe_event = event_stream.next().fuse() => {
+                match maybe_event {
+                    Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => self.handle_key(key),
+                    Some(Ok(Event::Paste(text))) => {
+                        if let Some(input) = self.active_input() {
+                            input.insert_str(&text);
+                        }
+                    }
+                    Some(Ok(Event::Mouse(mouse))) => return Ok(self.handle_mouse(mouse)),
+                    Some(Ok(Event::Resize(..))) => self.resized = true,
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return Err(e.into()),
+                    None => self.running = false,
+                }
+                Ok(true)
+            }
+            _ = tick.tick(), if ticking => {
+                self.on_tick(Instant::now());
+                Ok(true)
+            }
+            Some(event) = recv_or_pending(&mut self.task_rx) => Ok(self.handle_task_event(event)),
+            update = recv_or_pending(&mut self.queue_rx) => match update {
+                Some(update) => Ok(self.queue.apply(update)),
+                // Every sender is gone once the batch has finished.
+                None => {
+                    self.queue_rx = None;
+                    self.queue_task = None;
+                    Ok(true)
+                }
+            },
+            Some(record) = recv_or_pending(&mut self.log_rx) => {
+                self.logs.push(record);
+                // While running, the tick redraws often enough.
+                Ok(!ticking)
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.running = false;
+            return;
+        }
+
+        if self.logs.is_focused() {
+            self.handle_log_key(key);
+            return;
+        }
+        let typing = self.active_input().is_some() || self.settings.is_confirming();
+        if key.code == KeyCode::Char('l') && !typing {
+            self.logs.set_focused(true);
+            return;
+        }
+        if self.keys.switch_tab.matches(key) && !typing {
+            self.next_view();
+            return;
+        }
+        match self.view {
+            View::Queue    => return self.handle_queue_key(key),
+            View::History  => return self.handle_history_key(key),
+            View::Settings => return self.handle_settings_key(key),
+            View::Run      => {}
+        }
+        if matches!(self.mode, Mode::Idle | Mode::ShowingResult(_) | Mode::Error(_)) {
+            match key.code {
+                KeyCode::Char('h') => {
+                    self.show_view(View::History);
+                    return;
+                }
+                KeyCode::Char('s') => {
+                    self.view = View::Settings;
+                    return;
+                }
+                KeyCode::Char('u') => {
+                    self.view = View::Queue;
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        match self.mode {
+            Mode::Idle                              => self.handle_menu_key(key),
+            Mode::EnteringEndpoint                  => self.handle_input_key(key),
+            Mode::Running(_)                        => self.handle_running_key(key),
+            Mode::ShowingResult(_) | Mode::Error(_) => self.handle_result_key(key),
+        }
+    }
+
+    fn handle_menu_key(&mut self, key: KeyEvent) {
+        match key.code {
+            _ if self.keys.quit.matches(key)        => self.running = false,
+            _ if self.keys.start.matches(key)       => self.start(self.selected),
+            KeyCode::Esc                            => self.running = false,
+            KeyCode::Char('e') | KeyCode::Char('i') => self.mode = Mode::EnteringEndpoint,
+            KeyCode::Up | KeyCode::Char('k')        => self.select_offset(-1),
+            KeyCode::Down | KeyCode::Char('j')      => self.select_offset(1),
+            KeyCode::Char(c) => {
+                if let Some(action) = Action::from_hotkey(c) {
+                    self.selected = action;
+                    self.start(action);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_input_key(&mut self, key: KeyEvent) {
+        // A remapped start key still works here unless it would type a character.
+        let start = self.keys.start.matches(key) && !self.keys.start.is_text();
+        match key.code {
+            KeyCode::Enter              => self.start(self.selected),
+            _ if start                  => self.start(self.selected),
+            KeyCode::Esc | KeyCode::Tab => self.mode = Mode::Idle,
+            _                           => { self.endpoint.handle_key(key); }
+        }
+    }
+
+    fn handle_log_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('l')  => self.logs.set_focused(false),
+            KeyCode::Up | KeyCode::Char('k')   => self.logs.scroll_up(1),
+            KeyCode::Down | KeyCode::Char('j') => self.logs.scroll_down(1),
+            KeyCode::PageUp                    => self.logs.page_up(),
+            KeyCode::PageDown                  => self.logs.page_down(),
+            KeyCode::Home                      => self.logs.home(),
+            KeyCode::End                       => self.logs.end(),
+            KeyCode::Char(c @ '1'..='9') => {
+                let index = c as usize - '1' as usize;
+                if let Some(category) = LogCategory::ALL.get(index) {
+                    self.logs.toggle(*category);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_history_key(&mut self, key: KeyEvent) {
+        match key.code {
+            _ if self.keys.quit.matches(key) => self.running = false,
+            KeyCode::Esc if self.history.is_showing_detail() => self.history.set_detail(false),
+            KeyCode::Esc | KeyCode::Char('h')  => self.view = View::Run,
+            KeyCode::Up | KeyCode::Char('k')   => self.history.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => self.history.select_next(),
+            KeyCode::Enter                     => self.history.set_detail(true),
+            KeyCode::Char('d')                 => self.history.delete_selected(),
+            KeyCode::Char('v') => {
+                let Some(endpoint) = self.history.selected().map(|r| r.endpoint.clone()) else {
+                    return;
+                };
+                self.endpoint.clear();
+                self.endpoint.insert_str(&endpoint);
+                self.selected = Action::Validate;
+                self.view = View::Run;
+                self.start(Action::Validate);
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_queue_key(&mut self, key: KeyEvent) {
+        if let Some(input) = self.queue.input_mut() {
+            match key.code {
+                KeyCode::Enter => self.queue.commit_add(),
+                KeyCode::Esc   => self.queue.cancel_add(),
+                _              => { input.handle_key(key); }
+            }
+            return;
+        }
+
+        match key.code {
+            _ if self.keys.quit.matches(key)   => self.running = false,
+            _ if self.keys.start.matches(key)  => self.start_queue(),
+            _ if self.keys.cancel.matches(key) => self.cancel_queue(),
+            KeyCode::Esc | KeyCode::Char('u')  => self.view = View::Run,
+            KeyCode::Up | KeyCode::Char('k')   => self.queue.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => self.queue.select_next(),
+            KeyCode::Char('a')                 => self.queue.begin_add(),
+            KeyCode::Char('d')                 => self.queue.remove_selected(),
+            KeyCode::Char('r')                 => self.queue.retry_failed(),
+            KeyCode::Char('y')                 => self.copy_selected_token(),
+            KeyCode::Char('+')                 => self.queue_concurrency += 1,
+            KeyCode::Char('-')                 => self.queue_concurrency = self.queue_concurrency.saturating_sub(1).max(1),
+            _ => {}
+        }
+    }
+
+    fn handle_settings_key(&mut self, key: KeyEvent) {
+        if self.settings.is_confirming() {
+            match key.code {
+                KeyCode::Char('y') => self.settings.confirm_save(),
+                _                  => self.settings.cancel_save(),
+            }
+            return;
+        }
+
+        if let Some(input) = self.settings.input_mut() {
+            match key.code {
+                KeyCode::Enter => {
+                    if self.settings.commit_edit() {
+                        self.apply_settings();
+                    }
+                }
+                KeyCode::Esc => self.settings.cancel_edit(),
+                _            => { input.handle_key(key); }
+            }
+            return;
+        }
+
+        match key.code {
+            _ if self.keys.quit.matches(key)   => self.running = false,
+            KeyCode::Esc | KeyCode::Char('s')  => self.view = View::Run,
+            KeyCode::Up | KeyCode::Char('k')   => self.settings.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => self.settings.select_next(),
+            KeyCode::Char('w')                 => self.settings.request_save(),
+            KeyCode::Enter => {
+                if self.settings.edit() {
+                    self.apply_settings();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_running_key(&mut self, key: KeyEvent) {
+        if self.keys.cancel.matches(key) {
+            self.cancel();
+        }
+    }
+
+    fn handle_result_key(&mut self, key: KeyEvent) {
+        match key.code {
+            _ if self.keys.quit.matches(key)     => self.running = false,
+            KeyCode::Esc | KeyCode::Enter        => self.mode = Mode::Idle,
+            KeyCode::Char('e')                   => self.mode = Mode::EnteringEndpoint,
+            KeyCode::Up | KeyCode::Char('k')     => self.scroll_by(-1),
+            KeyCode::Down | KeyCode::Char('j')   => self.scroll_by(1),
+            KeyCode::PageUp                      => self.scroll_by(-(PAGE_SCROLL as i32)),
+            KeyCode::PageDown                    => self.scroll_by(PAGE_SCROLL as i32),
+            KeyCode::Home                        => self.scroll = 0,
+            KeyCode::Char(c) => {
+                if let Some(action) = Action::from_hotkey(c) {
+                    self.selected = action;
+                    self.start(action);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Cycles Run → Queue → History → Settings → Run.
+    fn next_view(&mut self) {
+        self.show_view(match self.view {
+            View::Run      => View::Queue,
+            View::Queue    => View::History,
+            View::History  => View::Settings,
+            View::Settings => View::Run,
+        });
+    }
+
+    fn show_view(&mut self, view: View) {
+        if view == View::History && self.view != View::History {
+            self.history.reload();
+        }
+        self.view = view;
+    }
+
+    /// Handles clicks and the scroll wheel, using the pane areas
+    /// from the last draw.
+    ///
+    /// # Returns
+    /// * `bool`: Whether the screen needs redrawing. Mouse movement
+    ///           and clicks outside any pane don't.
+    fn handle_mouse(&mut self, mouse: MouseEvent) -> bool {
+        // Nothing has a place on screen under the too-small overlay.
+        if self.areas.body.is_empty() {
+            return false;
+        }
+        let position = Position::new(mouse.column, mouse.row);
+        let areas = self.areas;
+        let over_logs = areas.logs.contains(position);
+
+        match mouse.kind {
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
+                let up = mouse.kind == MouseEventKind::ScrollUp;
+                if over_logs {
+                    match up {
+                        true  => self.logs.scroll_up(WHEEL_SCROLL as usize),
+                        false => self.logs.scroll_down(WHEEL_SCROLL as usize),
+                    }
+                } else if self.view == View::History && areas.body.contains(position) {
+                    match up {
+                        true  => self.history.select_previous(),
+                        false => self.history.select_next(),
+                    }
+                } else if self.view == View::Queue && areas.body.contains(position) {
+                    match up {
+                        true  => self.queue.select_previous(),
+                        false => self.queue.select_next(),
+                    }
+                } else if self.view == View::Run && areas.results.contains(position) {
+                    self.scroll_by(if up { -(WHEEL_SCROLL as i32) } else { WHEEL_SCROLL as i32 });
+                } else {
+                    return false;
+                }
+                true
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                // An open editor or prompt keeps the keyboard until it's done.
+                if self.settings.is_editing() || self.settings.is_confirming() || self.queue.is_adding() {
+                    return false;
+                }
+
+                self.logs.set_focused(over_logs);
+                let running = matches!(self.mode, Mode::Running(_));
+                if areas.tabs.contains(position) {
+                    if let Some(view) = tab_at(areas.tabs, mouse.column) {
+                        self.show_view(view);
+                    }
+                } else if areas.header.contains(position) && !running {
+                    self.show_view(View::Run);
+                    self.mode = Mode::EnteringEndpoint;
+                } else if self.view == View::Run && areas.menu.contains(position) && !running {
+                    // Menu items start below the top border.
+                    let index = mouse.row.checked_sub(areas.menu.y + 1).map(usize::from);
+                    if let Some(&action) = index.and_then(|index| Action::ALL.get(index)) {
+                        self.selected = action;
+                        self.start(action);
+                    }
+                } else if self.mode == Mode::EnteringEndpoint && !over_logs {
+                    self.mode = Mode::Idle;
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The text field taking keystrokes, if any.
+    fn active_input(&mut self) -> Option<&mut InputField> {
+        match self.view {
+            View::Run if self.mode == Mode::EnteringEndpoint => Some(&mut self.endpoint),
+            View::Queue                                      => self.queue.input_mut(),
+            View::Settings                                   => self.settings.input_mut(),
+            _                                                => None,
+        }
+    }
+
+    /// Rebuilds the client from the settings screen's configuration.
+    /// A run already in progress keeps the client it started with.
+    fn apply_settings(&mut self) {
+        let mut config = self.settings.config().clone();
+        self.verbose = config.verbose;
+        config.set_verbose(false);
+
+        match self.client.reconfigured(config.clone()) {
+            Ok(client) => {
+                self.client = Arc::new(client);
+                self.config = config;
+            }
+            Err(e) => self.settings.set_status(format!("Cannot apply settings: {e}")),
+        }
+    }
+
+    /// Samples the aggregate hash rate for the sparkline and
+    /// the throttle detector.
+    fn on_tick(&mut self, now: Instant) {
+        if let Some(dashboard) = self.dashboard.as_ref().filter(|d| !d.is_finished()) {
+            let rate = dashboard.total_hash_rate();
+            if !self.rates.sample(now, rate) {
+                return;
+            }
+            if let Some(throttled) = self.throttle.push(rate) {
+                crate::warnings::emit(throttled.warning());
+            }
+        }
+    }
+
+    fn select_offset(&mut self, offset: i32) {
+        let count = Action::ALL.len() as i32;
+        let index = Action::ALL.iter().position(|a| *a == self.selected).unwrap_or(0) as i32;
+        self.selected = Action::ALL[(index + offset).rem_euclid(count) as usize];
+    }
+
+    fn scroll_by(&mut self, offset: i32) {
+        let max = self.results.len().saturating_sub(1) as i32;
+        self.scroll = (self.scroll as i32 + offset).clamp(0, max) as u16;
+    }
+
+    /// Starts `action` on a background task so the UI stays responsive,
+    /// or asks for an endpoint first if none has been entered.
+    fn start(&mut self, action: Action) {
+        if self.endpoint.is_empty() {
+            self.mode = Mode::EnteringEndpoint;
+            return;
+        }
+
+        self.mode = Mode::Running(action);
+        self.results.clear();
+        self.scroll = 0;
+        self.dashboard = None;
+        self.rates = RateHistory::default();
+        self.throttle = ThrottleDetector::default();
+
+        let (task_tx, task_rx) = mpsc::unbounded_channel();
+        self.task_rx = Some(task_rx);
+
+        let client = Arc::clone(&self.client);
+        let endpoint = self.endpoint.value().trim().to_string();
+        let verbose = self.verbose;
+        self.task = Some(tokio::spawn(async move {
+            let outcome = action.execute(&client, &endpoint, &task_tx, verbose).await;
+            let _ = task_tx.send(TaskEvent::Finished(outcome));
+        }));
+    }
+
+    /// Abandons the running action and freezes the dashboard.
+    ///
+    /// Aborting the task stops the solve from being awaited; the
+    /// solver threads themselves wind down in the background.
+    fn cancel(&mut self) {
+        let Mode::Running(action) = self.mode else {
+            return;
+        };
+
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+        self.task_rx = None;
+
+        if let Some(dashboard) = self.dashboard.as_mut() {
+            dashboard.finish(Outcome::Cancelled);
+        }
+        self.results = vec![format!("{} cancelled.", action.label())];
+        self.mode = Mode::ShowingResult(action);
+    }
+
+    /// Hands the pending queue entries to a background batch. Entries
+    /// added while it runs wait for the next start.
+    fn start_queue(&mut self) {
+        if self.queue_task.is_some() {
+            self.queue.set_status("The queue is already running; cancel it first to restart.".to_string());
+            return;
+        }
+        let jobs = self.queue.take_pending_jobs();
+        if jobs.is_empty() {
+            self.queue.set_status("Nothing pending. Press `a` to add endpoints or `r` to retry failures.".to_string());
+            return;
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.queue_rx = Some(rx);
+        self.queue_task = Some(tokio::spawn(batch::run(
+            Arc::clone(&self.client),
+            futures::stream::iter(jobs),
+            self.queue_concurrency,
+            tx,
+            self.verbose,
+        )));
+    }
+
+    /// Abandons the batch. Entries that were in flight are marked
+    /// failed so `r` can retry them.
+    fn cancel_queue(&mut self) {
+        let Some(task) = self.queue_task.take() else {
+            return;
+        };
+        task.abort();
+        self.queue_rx = None;
+        self.queue.cancel_in_flight();
+    }
+
+    /// Copies the selected entry's token with an OSC 52 escape, which
+    /// most terminals forward to the system clipboard, even over SSH.
+    fn copy_selected_token(&mut self) {
+        let token = match self.queue.selected().map(|entry| &entry.stage) {
+            Some(Stage::Done { token, .. }) => token.clone(),
+            _ => {
+                self.queue.set_status("Only finished entries have a token to copy.".to_string());
+                return;
+            }
+        };
+
+        let status = match crossterm::execute!(io::stdout(), CopyToClipboard::to_clipboard_from(token)) {
+            Ok(()) => "Token copied to the clipboard.".to_string(),
+            Err(e) => format!("Cannot copy the token: {e}"),
+        };
+        self.queue.set_status(status);
+    }
+
+    /// Applies an event from the running action.
+    ///
+    /// # Returns
+    /// * `bool`: Whether the screen needs redrawing. Progress
+    ///           updates wait for the next tick instead.
+    fn handle_task_event(&mut self, event: TaskEvent) -> bool {
+        match event {
+            TaskEvent::SolveStarted { recommended_attempts, thread_count } => {
+                self.dashboard = Some(SolveDashboard::new(recommended_attempts, thread_count));
+            }
+            TaskEvent::Progress { thread_id, attempts, hash_rate } => {
+                if let Some(dashboard) = self.dashboard.as_mut() {
+                    dashboard.record(thread_id, attempts, hash_rate);
+                }
+                return false;
+            }
+            TaskEvent::SolveFinished { nonce } => {
+                if let Some(dashboard) = self.dashboard.as_mut() {
+                    dashboard.finish(Outcome::Solved { nonce });
+                }
+            }
+            TaskEvent::Finished(outcome) => {
+                self.task = None;
+                self.task_rx = None;
+                match outcome {
+                    Ok(lines) => {
+                        self.results = lines;
+                        self.mode = Mode::ShowingResult(self.selected);
+                    }
+                    Err(message) => {
+                        // Only keep a dashboard that was frozen by a successful solve.
+                        if self.dashboard.as_ref().is_some_and(|d| !d.is_finished()) {
+                            self.dashboard = None;
+                        }
+                        self.mode = Mode::Error(message);
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+/// The tab under `column` in the tab bar at `area`, following the
+/// layout of [`Tabs`]: one space of padding either side of each
+/// title and a one-column divider between them.
+fn tab_at(area: Rect, column: u16) -> Option<View> {
+    let mut x = area.x;
+    for (view, title) in View::ALL.into_iter().zip(TAB_TITLES) {
+        let width = title.chars().count() as u16 + 2;
+        if (x..x + width).contains(&column) {
+            return Some(view);
+        }
+        x += width + 1;
+    }
+    None
+}
+
+/// Receives from `rx`, or waits forever when there is no channel.
+async fn recv_or_pending<T>(rx: &mut Option<UnboundedReceiver<T>>) -> Option<T> {
+    match rx {
+        Some(rx) => rx.recv().await,
+        None     => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        let config = ClientConfig::default();
+        let client = CliClient::new(config.clone()).unwrap();
+        let mut app = App::new(client, config);
+        // Never read or modify the real history from tests.
+        app.history = HistoryView::new(None);
+        app
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    /// Draws once on a 100x40 screen so pane areas are known.
+    fn draw(app: &mut App) {
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(100, 40)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+    }
+
+    fn mouse(app: &mut App, kind: MouseEventKind, column: u16, row: u16) -> bool {
+        app.handle_mouse(MouseEvent { kind, column, row, modifiers: KeyModifiers::NONE })
+    }
+
+    fn click(app: &mut App, column: u16, row: u16) -> bool {
+        mouse(app, MouseEventKind::Down(MouseButton::Left), column, row)
+    }
+
+    #[test]
+    fn test_enter_endpoint_and_leave() {
+        let mut app = app();
+        press(&mut app, KeyCode::Char('e'));
+        assert_eq!(app.mode, Mode::EnteringEndpoint);
+
+        "https://example.com".chars().for_each(|c| press(&mut app, KeyCode::Char(c)));
+        press(&mut app, KeyCode::Esc);
+
+        assert_eq!(app.mode, Mode::Idle);
+        assert_eq!(app.endpoint.value(), "https://example.com");
+    }
+
+    #[test]
+    fn test_quit_keys_are_text_while_editing() {
+        let mut app = app();
+        app.running = true;
+        press(&mut app, KeyCode::Char('e'));
+        press(&mut app, KeyCode::Char('q'));
+
+        assert!(app.running);
+        assert_eq!(app.endpoint.value(), "q");
+    }
+
+    #[test]
+    fn test_run_without_endpoint_asks_for_one() {
+        let mut app = app();
+        press(&mut app, KeyCode::Char('o'));
+
+        assert_eq!(app.selected, Action::Solve);
+        assert_eq!(app.mode, Mode::EnteringEndpoint);
+    }
+
+    #[test]
+    fn test_menu_selection_wraps() {
+        let mut app = app();
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.selected, Action::Validate);
+
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.selected, Action::Fetch);
+    }
+
+    #[test]
+    fn test_task_events_update_mode() {
+        let mut app = app();
+        app.selected = Action::Solve;
+        app.mode = Mode::Running(Action::Solve);
+
+        app.handle_task_event(TaskEvent::Finished(Ok(vec!["Solution: 42".into()])));
+        assert_eq!(app.mode, Mode::ShowingResult(Action::Solve));
+        assert_eq!(app.results, vec!["Solution: 42".to_string()]);
+
+        app.mode = Mode::Running(Action::Fetch);
+        app.handle_task_event(TaskEvent::Finished(Err("connection refused".into())));
+        assert_eq!(app.mode, Mode::Error("connection refused".into()));
+    }
+
+    #[test]
+    fn test_progress_feeds_dashboard_without_redraw() {
+        let mut app = app();
+        app.mode = Mode::Running(Action::Solve);
+
+        assert!(app.handle_task_event(TaskEvent::SolveStarted { recommended_attempts: 1_000, thread_count: 2 }));
+        assert!(!app.handle_task_event(TaskEvent::Progress { thread_id: 1, attempts: 300, hash_rate: 100 }));
+        assert_eq!(app.dashboard.as_ref().unwrap().total_attempts(), 300);
+    }
+
+    #[test]
+    fn test_tick_samples_hash_rate() {
+        let mut app = app();
+        app.mode = Mode::Running(Action::Solve);
+        app.handle_task_event(TaskEvent::SolveStarted { recommended_attempts: 1_000, thread_count: 2 });
+        app.handle_task_event(TaskEvent::Progress { thread_id: 0, attempts: 10, hash_rate: 400 });
+        app.handle_task_event(TaskEvent::Progress { thread_id: 1, attempts: 10, hash_rate: 600 });
+
+        app.on_tick(Instant::now());
+        assert_eq!(app.rates.peak(), 1_000);
+    }
+
+    #[test]
+    fn test_cancel_freezes_dashboard() {
+        let mut app = app();
+        app.mode = Mode::Running(Action::Solve);
+        app.handle_task_event(TaskEvent::SolveStarted { recommended_attempts: 1_000, thread_count: 2 });
+
+        press(&mut app, KeyCode::Char('c'));
+
+        assert_eq!(app.mode, Mode::ShowingResult(Action::Solve));
+        assert!(app.dashboard.as_ref().unwrap().is_finished());
+        assert!(app.task_rx.is_none());
+    }
+
+    #[test]
+    fn test_log_pane_focus_takes_keys() {
+        let mut app = app();
+        app.running = true;
+        press(&mut app, KeyCode::Char('l'));
+        assert!(app.logs.is_focused());
+
+        // `q` no longer quits and `1` toggles a category instead.
+        press(&mut app, KeyCode::Char('q'));
+        press(&mut app, KeyCode::Char('1'));
+        assert!(app.running);
+
+        press(&mut app, KeyCode::Esc);
+        assert!(!app.logs.is_focused());
+        assert_eq!(app.mode, Mode::Idle);
+    }
+
+    #[test]
+    fn test_history_tab_round_trip() {
+        let mut app = app();
+        app.running = true;
+        press(&mut app, KeyCode::Char('h'));
+        assert_eq!(app.view, View::History);
+
+        // With no runs, Enter has nothing to show and `v` has nothing to run.
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Char('v'));
+        assert!(!app.history.is_showing_detail());
+        assert_eq!(app.mode, Mode::Idle);
+
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.view, View::Run);
+        assert!(app.running);
+    }
+
+    #[test]
+    fn test_settings_edits_apply_to_next_run() {
+        let mut app = app();
+        app.running = true;
+        press(&mut app, KeyCode::Char('s'));
+        assert_eq!(app.view, View::Settings);
+
+        // Timeout is the third field.
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        app.handle_key(KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL));
+        "12".chars().for_each(|c| press(&mut app, KeyCode::Char(c)));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.config.timeout, Duration::from_secs(12));
+
+        // Verbose toggles on Enter, and stays off for the library.
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert!(app.verbose);
+        assert!(!app.config.verbose);
+
+        press(&mut app, KeyCode::Char('s'));
+        assert_eq!(app.view, View::Run);
+        assert!(app.running);
+    }
+
+    #[test]
+    fn test_queue_tab_adds_and_removes() {
+        let mut app = app();
+        app.running = true;
+        press(&mut app, KeyCode::Char('u'));
+        assert_eq!(app.view, View::Queue);
+
+        press(&mut app, KeyCode::Char('a'));
+        for endpoint in ["https://a.example", "https://b.example"] {
+            endpoint.chars().for_each(|c| press(&mut app, KeyCode::Char(c)));
+            press(&mut app, KeyCode::Enter);
+        }
+        // `q` is typed while adding rather than quitting.
+        press(&mut app, KeyCode::Char('q'));
+        press(&mut app, KeyCode::Esc);
+        assert!(app.running);
+        assert_eq!(app.queue.entries().len(), 2);
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Char('d'));
+        assert_eq!(app.queue.entries().len(), 1);
+        assert_eq!(app.queue.entries()[0].endpoint, "https://a.example");
+
+        press(&mut app, KeyCode::Char('-'));
+        assert_eq!(app.queue_concurrency, 1);
+        press(&mut app, KeyCode::Char('+'));
+        assert_eq!(app.queue_concurrency, 2);
+
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.view, View::Run);
+    }
+
+    #[test]
+    fn test_remapped_keys() {
+        let mut app = app();
+        app.running = true;
+        app.keys.quit = "x".parse().unwrap();
+        app.keys.switch_tab = "]".parse().unwrap();
+
+        press(&mut app, KeyCode::Char(']'));
+        assert_eq!(app.view, View::Queue);
+        press(&mut app, KeyCode::Char(']'));
+        assert_eq!(app.view, View::History);
+        press(&mut app, KeyCode::Char(']'));
+        assert_eq!(app.view, View::Settings);
+        press(&mut app, KeyCode::Char(']'));
+        assert_eq!(app.view, View::Run);
+
+        press(&mut app, KeyCode::Char('q'));
+        assert!(app.running);
+        press(&mut app, KeyCode::Char('x'));
+        assert!(!app.running);
+    }
+
+    #[test]
+    fn test_click_menu_tabs_and_panes() {
+        let mut app = app();
+        draw(&mut app);
+
+        // The menu's second row is Solve; there's no endpoint yet.
+        assert!(click(&mut app, 2, 6));
+        assert_eq!(app.selected, Action::Solve);
+        assert_eq!(app.mode, Mode::EnteringEndpoint);
+
+        // Tab titles are " Run │ Queue │ History │ Settings ".
+        click(&mut app, 8, 3);
+        assert_eq!(app.view, View::Queue);
+        click(&mut app, 16, 3);
+        assert_eq!(app.view, View::History);
+        click(&mut app, 28, 3);
+        assert_eq!(app.view, View::Settings);
+
+        click(&mut app, 10, 33);
+        assert!(app.logs.is_focused());
+
+        click(&mut app, 10, 1);
+        assert!(!app.logs.is_focused());
+        assert_eq!(app.view, View::Run);
+        assert_eq!(app.mode, Mode::EnteringEndpoint);
+    }
+
+    #[test]
+    fn test_wheel_scrolls_pane_under_pointer() {
+        let mut app = app();
+        app.mode = Mode::ShowingResult(Action::Fetch);
+        app.results = (0..20).map(|n| n.to_string()).collect();
+        draw(&mut app);
+
+        assert!(mouse(&mut app, MouseEventKind::ScrollDown, 50, 10));
+        assert_eq!(app.scroll, WHEEL_SCROLL);
+
+        // Over the menu there is nothing to scroll.
+        assert!(!mouse(&mut app, MouseEventKind::ScrollDown, 2, 10));
+        assert!(!mouse(&mut app, MouseEventKind::Moved, 50, 10));
+        assert_eq!(app.scroll, WHEEL_SCROLL);
+    }
+
+    #[test]
+    fn test_layout_follows_terminal_size() {
+        let mut app = app();
+        draw(&mut app);
+        let before = app.areas;
+
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 30)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        assert_ne!(app.areas, before);
+        assert_eq!(app.areas.logs.y, 30 - 1 - LOG_PANE_HEIGHT);
+    }
+
+    #[test]
+    fn test_too_small_terminal_shows_overlay() {
+        let mut app = app();
+        draw(&mut app);
+
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(40, 10)).unwrap();
+        terminal.draw(|frame| app.draw(frame)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect();
+
+        assert!(screen.contains("terminal too small"));
+        assert!(screen.contains("40x10"));
+        assert_eq!(app.areas, Areas::default());
+        assert!(!click(&mut app, 2, 6));
+    }
+
+    #[test]
+    fn test_ctrl_c_quits_while_running() {
+        let mut app = app();
+        app.running = true;
+        app.mode = Mode::Running(Action::Fetch);
+        app.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+
+        assert!(!app.running);
+    }
+}
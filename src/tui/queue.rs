@@ -0,0 +1,307 @@
+use chrono::{DateTime, Local};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Paragraph, Row, Table, TableState},
+};
+
+use std::collections::HashSet;
+
+use super::input::InputField;
+use super::theme::Theme;
+use crate::batch::{BatchUpdate, Stage};
+use crate::history::ErrorKind;
+
+/// Width of the mini progress bar shown while solving.
+const BAR_WIDTH: usize = 12;
+
+/// One endpoint in the queue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueEntry {
+    /// Stable id used in [`BatchUpdate`]s, unaffected by removals.
+    pub id:       usize,
+    pub endpoint: String,
+    pub stage:    Stage,
+}
+
+/// The "Queue" tab: endpoints waiting for, going through, or done
+/// with a validate run, processed by [`crate::batch::run`].
+#[derive(Debug, Clone, Default)]
+pub struct QueueView {
+    entries:   Vec<QueueEntry>,
+    next_id:   usize,
+    /// Ids handed to the batch runner that haven't finished yet.
+    scheduled: HashSet<usize>,
+    selected:  usize,
+    adding:    Option<InputField>,
+    status:    Option<String>,
+}
+
+impl QueueView {
+    pub fn entries(&self) -> &[QueueEntry] {
+        &self.entries
+    }
+
+    pub fn selected(&self) -> Option<&QueueEntry> {
+        self.entries.get(self.selected)
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1).min(self.entries.len().saturating_sub(1));
+    }
+
+    pub fn set_status(&mut self, status: String) {
+        self.status = Some(status);
+    }
+
+    pub fn is_adding(&self) -> bool {
+        self.adding.is_some()
+    }
+
+    /// Opens the field for typing a new endpoint.
+    pub fn begin_add(&mut self) {
+        self.status = None;
+        self.adding = Some(InputField::default());
+    }
+
+    /// The endpoint being typed, for key and paste handling.
+    pub fn input_mut(&mut self) -> Option<&mut InputField> {
+        self.adding.as_mut()
+    }
+
+    /// Queues the typed endpoint and clears the field for the next one.
+    pub fn commit_add(&mut self) {
+        let Some(input) = self.adding.as_mut() else {
+            return;
+        };
+        if !input.is_empty() {
+            self.push(input.value().trim());
+            input.clear();
+        }
+    }
+
+    pub fn cancel_add(&mut self) {
+        self.adding = None;
+    }
+
+    pub fn push(&mut self, endpoint: &str) {
+        self.entries.push(QueueEntry {
+            id:       self.next_id,
+            endpoint: endpoint.to_string(),
+            stage:    Stage::Pending,
+        });
+        self.next_id += 1;
+    }
+
+    /// Removes the selected entry unless it has been handed to
+    /// the batch runner and isn't finished.
+    pub fn remove_selected(&mut self) {
+        if self.selected().is_some_and(|entry| !self.scheduled.contains(&entry.id)) {
+            self.entries.remove(self.selected);
+            self.selected = self.selected.min(self.entries.len().saturating_sub(1));
+        }
+    }
+
+    /// Hands the pending entries to the batch runner as
+    /// `(id, endpoint)` jobs for [`crate::batch::run`].
+    pub fn take_pending_jobs(&mut self) -> Vec<(usize, String)> {
+        let jobs: Vec<(usize, String)> = self.entries
+            .iter()
+            .filter(|entry| entry.stage == Stage::Pending && !self.scheduled.contains(&entry.id))
+            .map(|entry| (entry.id, entry.endpoint.clone()))
+            .collect();
+        self.scheduled.extend(jobs.iter().map(|(id, _)| *id));
+        self.status = None;
+        jobs
+    }
+
+    /// Applies an update from the batch runner.
+    ///
+    /// # Returns
+    /// * `bool`: Whether the screen needs redrawing. Solve progress
+    ///           waits for the next tick instead.
+    pub fn apply(&mut self, update: BatchUpdate) -> bool {
+        let Some(entry) = self.entries.iter_mut().find(|entry| entry.id == update.id) else {
+            return false;
+        };
+        if matches!(update.stage, Stage::Done { .. } | Stage::Failed { .. }) {
+            self.scheduled.remove(&update.id);
+        }
+        let progress_only = matches!(
+            (&entry.stage, &update.stage),
+            (Stage::Solving { .. }, Stage::Solving { .. })
+        );
+        entry.stage = update.stage;
+        !progress_only
+    }
+
+    /// Marks entries that were in flight as failed after the
+    /// batch was cancelled. Pending entries stay queued.
+    pub fn cancel_in_flight(&mut self) {
+        self.scheduled.clear();
+        for entry in &mut self.entries {
+            if entry.stage.is_in_flight() {
+                entry.stage = Stage::Failed { message: "cancelled".to_string(), kind: ErrorKind::Other };
+            }
+        }
+    }
+
+    /// Puts failed entries back in the queue.
+    pub fn retry_failed(&mut self) {
+        for entry in &mut self.entries {
+            if matches!(entry.stage, Stage::Failed { .. }) {
+                entry.stage = Stage::Pending;
+            }
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, theme: &Theme, concurrency: usize) {
+        let done = self.entries.iter().filter(|entry| matches!(entry.stage, Stage::Done { .. })).count();
+        let block = theme.block().title(format!(
+            " Queue ({done}/{} done, {concurrency} at a time) ",
+            self.entries.len(),
+        ));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let [table_area, input_area, status_area] = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Length(if self.is_adding() { 1 } else { 0 }),
+            Constraint::Length(1),
+        ]).areas(inner);
+
+        if self.entries.is_empty() {
+            frame.render_widget(Paragraph::new("The queue is empty. Press `a` to add endpoints.").dim(), table_area);
+        } else {
+            let rows = self.entries.iter().map(|entry| {
+                let style = match entry.stage {
+                    Stage::Done { .. }   => Style::new().green(),
+                    Stage::Failed { .. } => theme.error,
+                    Stage::Pending       => Style::new().dim(),
+                    _                    => Style::new(),
+                };
+                Row::new([entry.endpoint.clone(), stage_label(&entry.stage)]).style(style)
+            });
+            let table = Table::new(rows, [Constraint::Min(20), Constraint::Length(44)])
+                .header(Row::new(["Endpoint", "State"]).bold())
+                .row_highlight_style(Style::new().reversed());
+
+            let mut state = TableState::default().with_selected(Some(self.selected));
+            frame.render_stateful_widget(table, table_area, &mut state);
+        }
+
+        if let Some(input) = &self.adding {
+            frame.render_widget(Paragraph::new(Line::from(format!("Add: {}", input.value())).style(theme.accent)), input_area);
+            frame.set_cursor_position((
+                input_area.x + 5 + (input.cursor() as u16).min(input_area.width.saturating_sub(6)),
+                input_area.y,
+            ));
+        }
+        if let Some(status) = &self.status {
+            frame.render_widget(Paragraph::new(status.as_str()), status_area);
+        }
+    }
+}
+
+fn stage_label(stage: &Stage) -> String {
+    match stage {
+        Stage::Pending    => "pending".to_string(),
+        Stage::Fetching   => "fetching".to_string(),
+        Stage::Prefetched => "fetched, waiting for a solver".to_string(),
+        Stage::Scheduled { position } => format!("fetched, #{position} to solve"),
+        Stage::Solving { attempts, recommended_attempts } => {
+            let ratio = if *recommended_attempts == 0 {
+                0.0
+            } else {
+                (*attempts as f64 / *recommended_attempts as f64).min(1.0)
+            };
+            format!("solving {} {:>3.0}%", mini_bar(ratio), ratio * 100.0)
+        }
+        Stage::Validating => "validating".to_string(),
+        Stage::Done { valid_for, .. } => match DateTime::from_timestamp_millis(*valid_for) {
+            Some(valid_until) => format!("done, valid until {}", valid_until.with_timezone(&Local).format("%H:%M:%S")),
+            None              => "done".to_string(),
+        },
+        Stage::Failed { message, .. } => format!("failed: {message}"),
+    }
+}
+
+fn mini_bar(ratio: f64) -> String {
+    crate::display::glyphs().bar((ratio * BAR_WIDTH as f64).round() as usize, BAR_WIDTH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(endpoints: &[&str]) -> QueueView {
+        let mut queue = QueueView::default();
+        endpoints.iter().for_each(|endpoint| queue.push(endpoint));
+        queue
+    }
+
+    #[test]
+    fn test_add_keeps_field_open() {
+        let mut queue = QueueView::default();
+        queue.begin_add();
+        queue.input_mut().unwrap().insert_str(" https://a.example ");
+        queue.commit_add();
+        queue.commit_add();
+
+        assert!(queue.is_adding());
+        assert_eq!(queue.entries().len(), 1);
+        assert_eq!(queue.entries()[0].endpoint, "https://a.example");
+    }
+
+    #[test]
+    fn test_updates_and_redraws() {
+        let mut queue = queue(&["https://a.example", "https://b.example"]);
+        let solving = |attempts| BatchUpdate { id: 1, stage: Stage::Solving { attempts, recommended_attempts: 100 } };
+
+        assert_eq!(queue.take_pending_jobs().len(), 2);
+        assert!(queue.apply(solving(0)));
+        assert!(!queue.apply(solving(50)));
+        assert_eq!(stage_label(&queue.entries()[1].stage), format!("solving {}  50%", mini_bar(0.5)));
+
+        // Jobs are only handed out once, and unknown ids are ignored.
+        assert!(queue.take_pending_jobs().is_empty());
+        assert!(!queue.apply(BatchUpdate { id: 7, stage: Stage::Fetching }));
+    }
+
+    #[test]
+    fn test_cancel_and_retry() {
+        let mut queue = queue(&["https://a.example", "https://b.example"]);
+        queue.take_pending_jobs();
+        queue.apply(BatchUpdate { id: 0, stage: Stage::Validating });
+
+        // Scheduled entries can't be removed.
+        queue.remove_selected();
+        assert_eq!(queue.entries().len(), 2);
+
+        queue.cancel_in_flight();
+        assert!(matches!(&queue.entries()[0].stage, Stage::Failed { message, .. } if message == "cancelled"));
+        assert_eq!(queue.entries()[1].stage, Stage::Pending);
+
+        queue.retry_failed();
+        assert_eq!(queue.take_pending_jobs().len(), 2);
+
+        // Ids stay stable when earlier entries are removed.
+        queue.cancel_in_flight();
+        queue.remove_selected();
+        assert!(queue.apply(BatchUpdate { id: 1, stage: Stage::Fetching }));
+        assert_eq!(queue.entries()[0].stage, Stage::Fetching);
+    }
+
+    #[test]
+    fn test_mini_bar() {
+        let glyphs = crate::display::glyphs();
+        assert_eq!(mini_bar(0.0), glyphs.empty.repeat(BAR_WIDTH));
+        assert_eq!(mini_bar(1.0), glyphs.filled.repeat(BAR_WIDTH));
+    }
+}
@@ -0,0 +1,149 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    text::Line,
+    widgets::{Paragraph, Sparkline},
+};
+
+use std::time::{Duration, Instant};
+
+use super::ring::RingBuffer;
+use super::theme::Theme;
+use crate::display::format_hash_rate;
+
+/// How often the aggregate hash rate is sampled.
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Number of samples kept, i.e. seconds of history shown.
+pub const WINDOW: usize = 60;
+
+/// Once-per-second aggregate hash rate samples for the sparkline,
+/// plus the peak and average over the whole solve.
+#[derive(Debug, Clone)]
+pub struct RateHistory {
+    samples:     RingBuffer<u64>,
+    peak:        u64,
+    sum:         u128,
+    count:       u64,
+    last_sample: Option<Instant>,
+}
+
+impl Default for RateHistory {
+    fn default() -> Self {
+        Self {
+            samples:     RingBuffer::new(WINDOW),
+            peak:        0,
+            sum:         0,
+            count:       0,
+            last_sample: None,
+        }
+    }
+}
+
+impl RateHistory {
+    /// Records `rate` unless the previous sample is less than
+    /// [`SAMPLE_INTERVAL`] old.
+    ///
+    /// # Returns
+    /// * `bool`: Whether a sample was taken.
+    pub fn sample(&mut self, now: Instant, rate: u64) -> bool {
+        if self.last_sample.is_some_and(|last| now.duration_since(last) < SAMPLE_INTERVAL) {
+            return false;
+        }
+
+        self.samples.push(rate);
+        self.peak = self.peak.max(rate);
+        self.sum += rate as u128;
+        self.count += 1;
+        self.last_sample = Some(now);
+        true
+    }
+
+    /// Lowest rate in the visible window.
+    pub fn min(&self) -> u64 {
+        self.samples.iter().copied().min().unwrap_or(0)
+    }
+
+    /// Highest rate in the visible window.
+    pub fn max(&self) -> u64 {
+        self.samples.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Highest rate since the solve started.
+    pub fn peak(&self) -> u64 {
+        self.peak
+    }
+
+    /// Mean of all samples since the solve started.
+    pub fn average(&self) -> u64 {
+        if self.count == 0 {
+            0
+        } else {
+            (self.sum / self.count as u128) as u64
+        }
+    }
+
+    /// Renders the sparkline with its scale labels and peak/average beside it.
+    pub fn render(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let [chart_area, stats_area] = Layout::horizontal([
+            Constraint::Min(0),
+            Constraint::Length(22),
+        ]).areas(area);
+
+        let data: Vec<u64> = self.samples.iter().copied().collect();
+        let sparkline = Sparkline::default()
+            .block(theme.block().title(format!(" Hash rate ({}s) ", WINDOW)))
+            .style(theme.gauge)
+            .max(self.max().max(1))
+            .data(&data);
+        frame.render_widget(sparkline, chart_area);
+
+        let stats = vec![
+            Line::from(format!("Max:  {}", format_hash_rate(self.max()))),
+            Line::from(format!("Min:  {}", format_hash_rate(self.min()))),
+            Line::from(format!("Peak: {}", format_hash_rate(self.peak()))),
+            Line::from(format!("Avg:  {}", format_hash_rate(self.average()))),
+        ];
+        frame.render_widget(Paragraph::new(stats).block(theme.block()), stats_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_samples_at_most_once_per_interval() {
+        let start = Instant::now();
+        let mut history = RateHistory::default();
+
+        assert!(history.sample(start, 100));
+        assert!(!history.sample(start + Duration::from_millis(500), 200));
+        assert!(history.sample(start + Duration::from_secs(1), 300));
+
+        assert_eq!(history.min(), 100);
+        assert_eq!(history.max(), 300);
+        assert_eq!(history.average(), 200);
+    }
+
+    #[test]
+    fn test_peak_outlives_window() {
+        let start = Instant::now();
+        let mut history = RateHistory::default();
+
+        history.sample(start, 1_000);
+        for second in 1..=WINDOW as u64 {
+            history.sample(start + Duration::from_secs(second), 10);
+        }
+
+        assert_eq!(history.max(), 10);
+        assert_eq!(history.peak(), 1_000);
+    }
+
+    #[test]
+    fn test_empty_history() {
+        let history = RateHistory::default();
+        assert_eq!(history.min(), 0);
+        assert_eq!(history.average(), 0);
+    }
+}
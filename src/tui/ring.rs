@@ -0,0 +1,53 @@
+use std::collections::VecDeque;
+
+/// A fixed-capacity buffer that drops its oldest item when full.
+#[derive(Debug, Clone)]
+pub struct RingBuffer<T> {
+    items:    VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> RingBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            items: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Appends an item, evicting the oldest one if the buffer is full.
+    pub fn push(&mut self, item: T) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.items.len() == self.capacity {
+            self.items.pop_front();
+        }
+        self.items.push_back(item);
+    }
+
+    /// Items from oldest to newest.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+        self.items.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_evicts_oldest() {
+        let mut ring = RingBuffer::new(3);
+        (1..=5).for_each(|n| ring.push(n));
+
+        assert_eq!(ring.iter().copied().collect::<Vec<_>>(), vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn test_zero_capacity_stays_empty() {
+        let mut ring = RingBuffer::new(0);
+        ring.push(1);
+        assert_eq!(ring.iter().count(), 0);
+    }
+}
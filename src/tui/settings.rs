@@ -0,0 +1,370 @@
+use ironshield::ClientConfig;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Paragraph, Row, Table, TableState},
+};
+
+use std::fs;
+
+use super::input::InputField;
+use super::theme::Theme;
+use crate::config::{self, ConfigFormat, ConfigManager, Field, DEFAULT_CONFIG_FILE};
+
+/// Width of the field name column.
+const LABEL_WIDTH: u16 = 16;
+
+/// Where a field's current value came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    File,
+    CommandLine,
+    /// Changed in the settings screen and not saved yet.
+    Edited,
+}
+
+impl Source {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Default     => "default",
+            Self::File        => "config file",
+            Self::CommandLine => "command line",
+            Self::Edited      => "edited (unsaved)",
+        }
+    }
+}
+
+/// The "Settings" tab: every [`ClientConfig`] field with its value
+/// and source, inline editing, and saving back to the config file.
+///
+/// Edits are validated with [`ClientConfig::validate`] before they
+/// are accepted and apply to runs started afterwards.
+#[derive(Debug, Clone)]
+pub struct SettingsView {
+    config:     ClientConfig,
+    /// The resolved config file, if one was given.
+    path:       Option<String>,
+    sources:    [Source; Field::ALL.len()],
+    selected:   usize,
+    editing:    Option<InputField>,
+    error:      Option<String>,
+    confirming: bool,
+    status:     Option<String>,
+}
+
+impl SettingsView {
+    /// # Arguments
+    /// * `config`:       The configuration as loaded, before the TUI
+    ///                   silences the library's own verbose output.
+    /// * `path`:         The resolved config file, if any.
+    /// * `verbose_flag`: Whether `--verbose` was given.
+    pub fn new(config: ClientConfig, path: Option<String>, verbose_flag: bool) -> Self {
+        let keys = path.as_deref().map(file_keys).unwrap_or_default();
+        let sources = Field::ALL.map(|field| {
+            if field == Field::Verbose && verbose_flag {
+                Source::CommandLine
+            } else if keys.contains_key(field.key()) {
+                Source::File
+            } else {
+                Source::Default
+            }
+        });
+
+        Self {
+            config,
+            path,
+            sources,
+            selected:   0,
+            editing:    None,
+            error:      None,
+            confirming: false,
+            status:     None,
+        }
+    }
+
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    fn field(&self) -> Field {
+        Field::ALL[self.selected]
+    }
+
+    fn save_path(&self) -> &str {
+        self.path.as_deref().unwrap_or(DEFAULT_CONFIG_FILE)
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1).min(Field::ALL.len() - 1);
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.editing.is_some()
+    }
+
+    pub fn is_confirming(&self) -> bool {
+        self.confirming
+    }
+
+    /// The field being edited, for key and paste handling.
+    pub fn input_mut(&mut self) -> Option<&mut InputField> {
+        self.editing.as_mut()
+    }
+
+    pub fn set_status(&mut self, status: String) {
+        self.status = Some(status);
+    }
+
+    /// Starts editing the selected field. `verbose` has only two
+    /// values, so it is toggled straight away instead.
+    ///
+    /// # Returns
+    /// * `bool`: Whether the configuration changed.
+    pub fn edit(&mut self) -> bool {
+        self.status = None;
+        if self.field() == Field::Verbose {
+            return self.accept(String::new());
+        }
+
+        let mut input = InputField::default();
+        input.insert_str(&edit_text(&self.config, self.field()));
+        self.editing = Some(input);
+        self.error = None;
+        false
+    }
+
+    /// Validates and applies the text being edited. On failure the
+    /// field stays open with the error shown beneath it.
+    ///
+    /// # Returns
+    /// * `bool`: Whether the configuration changed.
+    pub fn commit_edit(&mut self) -> bool {
+        let Some(text) = self.editing.as_ref().map(|input| input.value().to_string()) else {
+            return false;
+        };
+        self.accept(text)
+    }
+
+    pub fn cancel_edit(&mut self) {
+        self.editing = None;
+        self.error = None;
+    }
+
+    fn accept(&mut self, text: String) -> bool {
+        match config::apply(&self.config, self.field(), &text) {
+            Ok(config) => {
+                self.config = config;
+                self.sources[self.selected] = Source::Edited;
+                self.editing = None;
+                self.error = None;
+                true
+            }
+            Err(e) => {
+                self.error = Some(e);
+                false
+            }
+        }
+    }
+
+    /// Asks for confirmation before writing the file.
+    pub fn request_save(&mut self) {
+        self.status = None;
+        self.confirming = true;
+    }
+
+    pub fn cancel_save(&mut self) {
+        self.confirming = false;
+    }
+
+    /// Writes the configuration to the resolved config file.
+    pub fn confirm_save(&mut self) {
+        self.confirming = false;
+
+        let path = self.save_path().to_string();
+        match ConfigManager::save_client_config(&self.config, &path) {
+            Ok(()) => {
+                for source in &mut self.sources {
+                    if *source == Source::Edited {
+                        *source = Source::File;
+                    }
+                }
+                self.path = Some(path.clone());
+                self.status = Some(format!("Saved to '{path}'."));
+            }
+            Err(e) => self.status = Some(format!("Cannot save to '{path}': {e}")),
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let block = theme.block().title(match &self.path {
+            Some(path) => format!(" Settings ({path}) "),
+            None       => " Settings (no config file) ".to_string(),
+        });
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let [table_area, message_area] = Layout::vertical([
+            Constraint::Length(Field::ALL.len() as u16 + 1),
+            Constraint::Min(0),
+        ]).areas(inner);
+
+        let rows = Field::ALL.into_iter().enumerate().map(|(index, field)| {
+            let value = match &self.editing {
+                Some(input) if index == self.selected => input.value().to_string(),
+                _ => display_value(&self.config, field),
+            };
+            let source = self.sources[index];
+            let style = if source == Source::Edited { theme.accent } else { Style::new() };
+            Row::new([field.key().to_string(), value, source.label().to_string()]).style(style)
+        });
+        let table = Table::new(rows, [
+            Constraint::Length(LABEL_WIDTH),
+            Constraint::Min(20),
+            Constraint::Length(16),
+        ])
+            .header(Row::new(["Setting", "Value", "Source"]).bold())
+            .row_highlight_style(if self.is_editing() { Style::new().underlined() } else { Style::new().reversed() });
+
+        let mut state = TableState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(table, table_area, &mut state);
+
+        frame.render_widget(Paragraph::new(self.message_lines(theme)), message_area);
+
+        if let Some(input) = &self.editing {
+            let max = table_area.width.saturating_sub(LABEL_WIDTH + 2);
+            frame.set_cursor_position((
+                table_area.x + LABEL_WIDTH + 1 + (input.cursor() as u16).min(max),
+                table_area.y + 1 + self.selected as u16,
+            ));
+        }
+    }
+
+    fn message_lines(&self, theme: &Theme) -> Vec<Line<'static>> {
+        let mut lines = vec![Line::from("")];
+        if self.confirming {
+            lines.push(Line::from(format!("Save settings to '{}'? [y/n]", self.save_path())).style(theme.accent).bold());
+        } else if let Some(error) = &self.error {
+            lines.push(Line::from(error.clone()).style(theme.error));
+        } else if let Some(status) = &self.status {
+            lines.push(Line::from(status.clone()));
+        } else {
+            lines.push(Line::from(hint(self.field())).dim());
+        }
+
+        if self.sources.contains(&Source::Edited) && !self.confirming {
+            lines.push(Line::from("Edits apply to the next run. Press `w` to save them.").dim());
+        }
+        lines
+    }
+}
+
+fn hint(field: Field) -> &'static str {
+    match field {
+        Field::ApiBaseUrl => "Base URL of the IronShield API.",
+        Field::NumThreads => "Solver threads, or `auto` for one per CPU.",
+        Field::Timeout    => "Request timeout in seconds.",
+        Field::UserAgent  => "User-Agent header sent with every request.",
+        Field::Verbose    => "Send verbose lines to the log pane. Enter toggles.",
+    }
+}
+
+fn display_value(config: &ClientConfig, field: Field) -> String {
+    match field {
+        Field::Verbose => if config.verbose { "on" } else { "off" }.to_string(),
+        _              => edit_text(config, field),
+    }
+}
+
+/// The text a field's editor opens with.
+fn edit_text(config: &ClientConfig, field: Field) -> String {
+    match field {
+        Field::ApiBaseUrl => config.api_base_url.clone(),
+        Field::NumThreads => config.num_threads.map_or("auto".to_string(), |n| n.to_string()),
+        Field::Timeout    => format!("{}s", config.timeout.as_secs_f64()),
+        Field::UserAgent  => config.user_agent.clone(),
+        Field::Verbose    => config.verbose.to_string(),
+    }
+}
+
+/// The top-level keys of a config file, or none if it can't be read.
+fn file_keys(path: &str) -> toml::Table {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| ConfigFormat::detect(path).parse(&content).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_failed_edit_stays_open() {
+        let mut view = SettingsView::new(ClientConfig::default(), None, false);
+        view.select_next();
+        view.select_next();
+        assert!(!view.edit());
+
+        let input = view.input_mut().unwrap();
+        input.clear();
+        input.insert_str("soon");
+        assert!(!view.commit_edit());
+        assert!(view.is_editing());
+        assert!(view.error.is_some());
+
+        view.cancel_edit();
+        assert!(!view.is_editing());
+        assert_eq!(view.config().timeout, ClientConfig::default().timeout);
+    }
+
+    #[test]
+    fn test_sources() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("ironshield.toml");
+        fs::write(&path, "timeout = 5\n").unwrap();
+
+        let mut view = SettingsView::new(ClientConfig::default(), Some(path.display().to_string()), true);
+        assert_eq!(view.sources, [Source::Default, Source::Default, Source::File, Source::Default, Source::CommandLine]);
+
+        view.select_next();
+        view.edit();
+        let input = view.input_mut().unwrap();
+        input.clear();
+        input.insert_str("2");
+        assert!(view.commit_edit());
+        assert_eq!(view.sources[1], Source::Edited);
+    }
+
+    #[test]
+    fn test_save_keeps_other_keys() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("ironshield.toml");
+        fs::write(&path, "log_file = \"debug.log\"\n").unwrap();
+        let path = path.display().to_string();
+
+        let mut view = SettingsView::new(ClientConfig::default(), Some(path.clone()), false);
+        view.select_next();
+        view.edit();
+        let input = view.input_mut().unwrap();
+        input.clear();
+        input.insert_str("3");
+        assert!(view.commit_edit());
+
+        view.request_save();
+        assert!(view.is_confirming());
+        view.confirm_save();
+
+        assert_eq!(view.sources[1], Source::File);
+        let keys = file_keys(&path);
+        assert_eq!(keys.get("log_file").and_then(|v| v.as_str()), Some("debug.log"));
+        assert_eq!(ClientConfig::from_file(&path).unwrap().num_threads, Some(3));
+    }
+}
@@ -0,0 +1,226 @@
+use ironshield::ProgressTracker;
+use tokio::sync::mpsc::UnboundedSender;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::client::CliClient;
+use crate::display::{format_count, format_duration};
+use crate::history::{self, ErrorKind, RunCommand, RunRecord};
+use crate::estimate::attempts_from_nonce;
+use crate::logging::{LogCategory, log_event};
+use crate::metrics;
+
+/// A command the TUI can run against an endpoint.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Action {
+    #[default]
+    Fetch,
+    Solve,
+    Validate,
+}
+
+impl Action {
+    pub const ALL: [Action; 3] = [Action::Fetch, Action::Solve, Action::Validate];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Fetch    => "Fetch",
+            Self::Solve    => "Solve",
+            Self::Validate => "Validate",
+        }
+    }
+
+    /// What the matching subcommand does, for help text.
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Fetch    => "Fetch a challenge",
+            Self::Solve    => "Fetch and solve a challenge",
+            Self::Validate => "Fetch, solve and submit for a token",
+        }
+    }
+
+    /// Key that runs the action directly from the menu.
+    pub fn hotkey(self) -> char {
+        match self {
+            Self::Fetch    => 'f',
+            // `s` opens the settings screen.
+            Self::Solve    => 'o',
+            Self::Validate => 'v',
+        }
+    }
+
+    pub fn from_hotkey(key: char) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.hotkey() == key)
+    }
+
+    /// The command recorded in run history.
+    pub fn command(self) -> RunCommand {
+        match self {
+            Self::Fetch    => RunCommand::Fetch,
+            Self::Solve    => RunCommand::Solve,
+            Self::Validate => RunCommand::Validate,
+        }
+    }
+
+    /// Runs the action and renders its result as lines for the results pane.
+    ///
+    /// This mirrors the `fetch`, `solve` and `validate` handlers but
+    /// never writes to the console, which the TUI owns while it runs.
+    /// The run is recorded in the history store either way.
+    ///
+    /// # Arguments
+    /// * `client`:   The API client.
+    /// * `endpoint`: The protected endpoint URL.
+    /// * `events`:   Receives solve progress for the dashboard.
+    /// * `verbose`:  Whether to emit verbose log lines to the log pane.
+    ///
+    /// # Returns
+    /// * `Result<Vec<String>, String>`: The result lines, or the
+    ///                                  error message on failure.
+    pub async fn execute(
+        self,
+        client:   &CliClient,
+        endpoint: &str,
+        events:   &UnboundedSender<TaskEvent>,
+        verbose:  bool,
+    ) -> Result<Vec<String>, String> {
+        let mut record = RunRecord::new(self.command(), endpoint);
+        let start = Instant::now();
+
+        let outcome = self.run(client, endpoint, events, verbose, &mut record).await;
+
+        record.finish(start.elapsed(), outcome.as_ref().err().cloned());
+        if outcome.is_err() && record.error_kind.is_none() {
+            record.error_kind = Some(ErrorKind::Other);
+        }
+        history::record(&record);
+        outcome
+    }
+
+    async fn run(
+        self,
+        client:   &CliClient,
+        endpoint: &str,
+        events:   &UnboundedSender<TaskEvent>,
+        verbose:  bool,
+        record:   &mut RunRecord,
+    ) -> Result<Vec<String>, String> {
+        log_event(verbose, LogCategory::Network, format_args!("Requesting challenge for endpoint: {endpoint}"));
+
+        let fetch_start = Instant::now();
+        let challenge = client.fetch(endpoint).await.map(|envelope| envelope.challenge).map_err(|e| {
+            record.error_kind = Some(ErrorKind::Fetch);
+            log_event(verbose, LogCategory::Error, format_args!("Challenge fetch failed: {e}"));
+            e.to_string()
+        })?;
+
+        log_event(verbose, LogCategory::Timing, format_args!(
+            "Challenge fetch completed in {}",
+            format_duration(fetch_start.elapsed())
+        ));
+        record.fetch_ms = Some(fetch_start.elapsed().as_millis() as u64);
+
+        record.difficulty = Some(challenge.recommended_attempts / 2);
+
+        let mut lines = vec![
+            format!("Endpoint:             {endpoint}"),
+            format!("Difficulty:           {}", format_count(challenge.recommended_attempts / 2)),
+            format!("Recommended attempts: {}", format_count(challenge.recommended_attempts)),
+            String::new(),
+        ];
+
+        if self == Self::Fetch {
+            let json = serde_json::to_string_pretty(&challenge).map_err(|e| e.to_string())?;
+            lines.extend(json.lines().map(String::from));
+            return Ok(lines);
+        }
+
+        let thread_plan = client.thread_plan(true);
+        let thread_count = thread_plan.thread_count;
+        record.thread_count = Some(thread_count);
+        record.strategy = Some(thread_plan.strategy);
+
+        let _ = events.send(TaskEvent::SolveStarted {
+            recommended_attempts: challenge.recommended_attempts,
+            thread_count,
+        });
+        let tracker = Arc::new(ChannelProgressTracker { events: events.clone() }) as Arc<dyn ProgressTracker>;
+
+        log_event(verbose, LogCategory::Compute, format_args!("Starting multithreaded solve"));
+
+        let solve_start = Instant::now();
+        let solution = client.solve_with_plan(challenge, &thread_plan, Some(tracker))
+            .await
+            .map_err(|e| {
+                record.error_kind = Some(ErrorKind::Solve);
+                metrics::record_solve_failure(solve_start.elapsed());
+                log_event(verbose, LogCategory::Error, format_args!("Challenge solving failed: {e}"));
+                e.to_string()
+            })?;
+        let _ = events.send(TaskEvent::SolveFinished { nonce: solution.solution as u64 });
+
+        record.solve_ms = Some(solve_start.elapsed().as_millis() as u64);
+        record.attempts = Some(attempts_from_nonce(solution.solution as u64, &thread_plan));
+        metrics::record_solve_success(solve_start.elapsed(), record.hash_rate().unwrap_or_default());
+
+        log_event(verbose, LogCategory::Success, format_args!(
+            "Challenge solved in {}",
+            format_duration(solve_start.elapsed())
+        ));
+
+        lines.push(format!("Solved in:            {}", format_duration(solve_start.elapsed())));
+        lines.push(format!("Solution nonce:       {}", format_count(solution.solution as u64)));
+
+        if self == Self::Solve {
+            lines.push(String::new());
+            lines.push(format!("Solution: {solution:?}"));
+            return Ok(lines);
+        }
+
+        log_event(verbose, LogCategory::Submit, format_args!("Submitting solution..."));
+        crate::prewarm::log_reuse();
+
+        let submit_start = Instant::now();
+        let token = client.submit(&solution).await.map_err(|e| {
+            record.error_kind = Some(ErrorKind::Submit);
+            log_event(verbose, LogCategory::Error, format_args!("Solution submission failed: {e}"));
+            e.to_string()
+        })?;
+
+        log_event(verbose, LogCategory::Success, format_args!("Token generated successfully!"));
+        metrics::record_submit(submit_start.elapsed(), token.valid_for);
+        record.token_valid_for = Some(token.valid_for);
+
+        lines.push(format!("Token valid until:    {}", token.valid_for));
+        lines.push(String::new());
+        lines.push(format!("Token: {token:?}"));
+        Ok(lines)
+    }
+}
+
+/// Messages sent from background tasks to the [`App`](super::App) event loop.
+#[derive(Debug)]
+pub enum TaskEvent {
+    /// The challenge was fetched and solving has begun.
+    SolveStarted { recommended_attempts: u64, thread_count: usize },
+    /// A solver thread reported its running totals.
+    Progress { thread_id: usize, attempts: u64, hash_rate: u64 },
+    /// The solver found a solution.
+    SolveFinished { nonce: u64 },
+    /// The running action finished with its result lines or an error.
+    Finished(Result<Vec<String>, String>),
+}
+
+/// Forwards solver progress callbacks to the [`App`](super::App) event loop.
+struct ChannelProgressTracker {
+    events: UnboundedSender<TaskEvent>,
+}
+
+impl ProgressTracker for ChannelProgressTracker {
+    fn on_progress(&self, thread_id: usize, total_attempts: u64, hash_rate: u64, _elapsed: Duration) {
+        // The receiver is gone once the solve is cancelled; the
+        // solver threads keep reporting until they wind down.
+        let _ = self.events.send(TaskEvent::Progress { thread_id, attempts: total_attempts, hash_rate });
+    }
+}
@@ -0,0 +1,215 @@
+use ratatui::{
+    style::{Color, Style, Stylize},
+    widgets::Block,
+};
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A built-in color scheme, chosen with `theme` in the `[tui]` section.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeName {
+    /// For dark terminal backgrounds.
+    #[default]
+    Dark,
+    /// For light terminal backgrounds.
+    Light,
+    /// Bold, saturated colors for low-vision use and poor displays.
+    HighContrast,
+}
+
+/// Named colors accepted in `[tui.colors]`, besides `#rrggbb`.
+const COLOR_NAMES: [(&str, Color); 17] = [
+    ("reset",         Color::Reset),
+    ("black",         Color::Black),
+    ("red",           Color::Red),
+    ("green",         Color::Green),
+    ("yellow",        Color::Yellow),
+    ("blue",          Color::Blue),
+    ("magenta",       Color::Magenta),
+    ("cyan",          Color::Cyan),
+    ("gray",          Color::Gray),
+    ("dark-gray",     Color::DarkGray),
+    ("light-red",     Color::LightRed),
+    ("light-green",   Color::LightGreen),
+    ("light-yellow",  Color::LightYellow),
+    ("light-blue",    Color::LightBlue),
+    ("light-magenta", Color::LightMagenta),
+    ("light-cyan",    Color::LightCyan),
+    ("white",         Color::White),
+];
+
+/// A color from the config file: a name from [`COLOR_NAMES`] or `#rrggbb`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ThemeColor(pub Color);
+
+impl FromStr for ThemeColor {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+
+        let rgb = value
+            .strip_prefix('#')
+            .filter(|hex| hex.len() == 6)
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok());
+        if let Some(rgb) = rgb {
+            return Ok(Self(Color::from_u32(rgb)));
+        }
+
+        let name = value.to_ascii_lowercase().replace('_', "-");
+        if let Some((_, color)) = COLOR_NAMES.iter().find(|(known, _)| *known == name) {
+            return Ok(Self(*color));
+        }
+
+        let accepted: Vec<&str> = COLOR_NAMES.iter().map(|(name, _)| *name).collect();
+        Err(format!("unknown color '{value}' (accepted: {}, or #rrggbb)", accepted.join(", ")))
+    }
+}
+
+impl TryFrom<String> for ThemeColor {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for ThemeColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.0, COLOR_NAMES.iter().find(|(_, color)| *color == self.0)) {
+            (_, Some((name, _)))     => f.write_str(name),
+            (Color::Rgb(r, g, b), _) => write!(f, "#{r:02x}{g:02x}{b:02x}"),
+            (color, _)               => write!(f, "{color}"),
+        }
+    }
+}
+
+impl From<ThemeColor> for String {
+    fn from(color: ThemeColor) -> Self {
+        color.to_string()
+    }
+}
+
+/// Per-element overrides applied on top of the chosen theme.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColorOverrides {
+    pub title:  Option<ThemeColor>,
+    pub border: Option<ThemeColor>,
+    pub gauge:  Option<ThemeColor>,
+    pub error:  Option<ThemeColor>,
+}
+
+/// Styles used throughout the TUI, resolved once at startup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub title:  Style,
+    pub border: Style,
+    /// Focused borders, the selected tab and unsaved edits.
+    pub accent: Style,
+    /// The attempts gauge and the hash rate sparkline.
+    pub gauge:  Style,
+    pub error:  Style,
+    /// Key names in the footer.
+    pub key:    Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::preset(ThemeName::Dark)
+    }
+}
+
+impl Theme {
+    /// Builds the theme for `name` with `overrides` applied.
+    pub fn resolve(name: ThemeName, overrides: &ColorOverrides) -> Self {
+        let mut theme = Self::preset(name);
+        let apply = |style: &mut Style, color: Option<ThemeColor>| {
+            if let Some(ThemeColor(color)) = color {
+                *style = style.fg(color);
+            }
+        };
+
+        apply(&mut theme.title, overrides.title);
+        apply(&mut theme.border, overrides.border);
+        apply(&mut theme.gauge, overrides.gauge);
+        apply(&mut theme.error, overrides.error);
+        theme
+    }
+
+    fn preset(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Dark => Self {
+                title:  Style::new().blue().bold(),
+                border: Style::new(),
+                accent: Style::new().yellow(),
+                gauge:  Style::new().cyan(),
+                error:  Style::new().red(),
+                key:    Style::new().cyan().bold(),
+            },
+            ThemeName::Light => Self {
+                title:  Style::new().black().bold(),
+                border: Style::new().dark_gray(),
+                accent: Style::new().magenta(),
+                gauge:  Style::new().blue(),
+                error:  Style::new().red(),
+                key:    Style::new().blue().bold(),
+            },
+            ThemeName::HighContrast => Self {
+                title:  Style::new().white().bold(),
+                border: Style::new().white(),
+                accent: Style::new().light_yellow().bold(),
+                gauge:  Style::new().light_yellow(),
+                error:  Style::new().light_red().bold(),
+                key:    Style::new().black().on_white().bold(),
+            },
+        }
+    }
+
+    /// A bordered block in the theme's border style.
+    pub fn block(&self) -> Block<'static> {
+        Block::bordered().border_style(self.border)
+    }
+
+    /// A bordered block highlighted when `focused`.
+    pub fn focus_block(&self, focused: bool) -> Block<'static> {
+        Block::bordered().border_style(if focused { self.accent } else { self.border })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_colors() {
+        assert_eq!("light-blue".parse::<ThemeColor>().unwrap().0, Color::LightBlue);
+        assert_eq!("Dark_Gray".parse::<ThemeColor>().unwrap().0, Color::DarkGray);
+        assert_eq!("#ff8000".parse::<ThemeColor>().unwrap().0, Color::Rgb(255, 128, 0));
+        assert_eq!(ThemeColor(Color::Rgb(255, 128, 0)).to_string(), "#ff8000");
+    }
+
+    #[test]
+    fn test_unknown_color_lists_accepted_values() {
+        let error = "blurple".parse::<ThemeColor>().unwrap_err();
+        assert!(error.contains("unknown color 'blurple'"));
+        assert!(error.contains("light-magenta"));
+        assert!("#12345".parse::<ThemeColor>().is_err());
+    }
+
+    #[test]
+    fn test_overrides_replace_foreground_only() {
+        let overrides = ColorOverrides {
+            title: Some(ThemeColor(Color::Green)),
+            ..ColorOverrides::default()
+        };
+        let theme = Theme::resolve(ThemeName::Dark, &overrides);
+
+        assert_eq!(theme.title, Style::new().green().bold());
+        assert_eq!(theme.error, Theme::default().error);
+    }
+}
@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+use crate::display::format_count;
+
+/// The `batch_size` values accepted.
+pub const BATCH_SIZE_RANGE: RangeInclusive<u64> = 1..=100_000_000;
+
+/// The `give_up_factor` values accepted.
+pub const GIVE_UP_FACTOR_RANGE: RangeInclusive<u64> = 1..=1_000;
+
+/// The `[solver]` section of the configuration file: advanced knobs for
+/// experimenting with solver performance. Leave it out unless you are
+/// measuring.
+///
+/// ```toml
+/// [solver]
+/// batch_size = 1000000
+/// give_up_factor = 50
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SolverConfig {
+    /// Attempts per call into ironshield-core between checks for
+    /// cancellation; [`crate::solve::CHUNK_ATTEMPTS`] when unset.
+    pub batch_size:     Option<u64>,
+    /// How many times its recommended attempts a solve tries before
+    /// giving up; [`crate::solve::GIVE_UP_FACTOR`] when unset.
+    pub give_up_factor: Option<u64>,
+    /// Every other key, checked against the known options so a typo
+    /// fails instead of silently doing nothing.
+    #[serde(flatten)]
+    pub other:          BTreeMap<String, serde_json::Value>,
+}
+
+/// A solver option `[solver]` and `--solver-opt` may set.
+struct KnownOption {
+    key:   &'static str,
+    /// Checks `value` and sets the option.
+    apply: fn(&mut SolverOptions, &str) -> Result<(), String>,
+}
+
+/// Every solver option. `core.<key>` options would be forwarded to
+/// ironshield-core, but the version this is built against has none.
+const KNOWN_OPTIONS: &[KnownOption] = &[
+    KnownOption { key: "batch_size",     apply: apply_batch_size },
+    KnownOption { key: "give_up_factor", apply: apply_give_up_factor },
+];
+
+fn apply_batch_size(options: &mut SolverOptions, value: &str) -> Result<(), String> {
+    let batch_size: u64 = value.replace('_', "").parse()
+        .map_err(|_| format!("batch_size must be a whole number of attempts, got '{value}'"))?;
+    if !BATCH_SIZE_RANGE.contains(&batch_size) {
+        return Err(format!(
+            "batch_size must be between {} and {} attempts, got {}",
+            format_count(*BATCH_SIZE_RANGE.start()),
+            format_count(*BATCH_SIZE_RANGE.end()),
+            format_count(batch_size),
+        ));
+    }
+    options.batch_size = batch_size;
+    Ok(())
+}
+
+fn apply_give_up_factor(options: &mut SolverOptions, value: &str) -> Result<(), String> {
+    let factor: u64 = value.replace('_', "").parse()
+        .map_err(|_| format!("give_up_factor must be a whole number, got '{value}'"))?;
+    if !GIVE_UP_FACTOR_RANGE.contains(&factor) {
+        return Err(format!(
+            "give_up_factor must be between {} and {}, got {}",
+            GIVE_UP_FACTOR_RANGE.start(),
+            GIVE_UP_FACTOR_RANGE.end(),
+            format_count(factor),
+        ));
+    }
+    options.give_up_factor = factor;
+    Ok(())
+}
+
+/// The solver options in effect, after `[solver]` and `--solver-opt`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SolverOptions {
+    pub batch_size:     u64,
+    pub give_up_factor: u64,
+}
+
+impl Default for SolverOptions {
+    fn default() -> Self {
+        Self { batch_size: crate::solve::CHUNK_ATTEMPTS, give_up_factor: crate::solve::GIVE_UP_FACTOR }
+    }
+}
+
+impl SolverOptions {
+    /// The options from the config section, with each `key=value` in
+    /// `overrides` applied on top.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The options, or which key or value is
+    ///   invalid and what would be accepted.
+    pub fn resolve(config: &SolverConfig, overrides: &[String]) -> Result<Self, String> {
+        let mut options = Self::default();
+        if let Some(batch_size) = config.batch_size {
+            options.set("batch_size", &batch_size.to_string()).map_err(|e| format!("Invalid `solver.batch_size`: {e}"))?;
+        }
+        if let Some(factor) = config.give_up_factor {
+            options.set("give_up_factor", &factor.to_string()).map_err(|e| format!("Invalid `solver.give_up_factor`: {e}"))?;
+        }
+        for (key, value) in &config.other {
+            let value = match value {
+                serde_json::Value::String(text) => text.clone(),
+                other                           => other.to_string(),
+            };
+            options.set(key, &value).map_err(|e| format!("Invalid `solver.{key}`: {e}"))?;
+        }
+        for pair in overrides {
+            let (key, value) = pair.split_once('=')
+                .ok_or_else(|| format!("Invalid `--solver-opt {pair}`: expected key=value"))?;
+            options.set(key.trim(), value.trim()).map_err(|e| format!("Invalid `--solver-opt {pair}`: {e}"))?;
+        }
+        Ok(options)
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        if let Some(known) = KNOWN_OPTIONS.iter().find(|known| known.key == key) {
+            return (known.apply)(self, value);
+        }
+        if key.starts_with("core.") {
+            return Err(format!(
+                "ironshield-core {} has no tunable options to forward",
+                crate::benchmark::Versions::current().core,
+            ));
+        }
+        let known: Vec<&str> = KNOWN_OPTIONS.iter().map(|known| known.key).collect();
+        Err(format!("unknown solver option '{key}'; known options: {}", known.join(", ")))
+    }
+
+    /// e.g. "batch_size=500000, give_up_factor=20", for summaries and
+    /// benchmark output.
+    pub fn describe(&self) -> String {
+        format!("batch_size={}, give_up_factor={}", self.batch_size, self.give_up_factor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_layers_overrides_on_the_config() {
+        assert_eq!(SolverOptions::resolve(&SolverConfig::default(), &[]).unwrap(), SolverOptions::default());
+
+        let config = SolverConfig { batch_size: Some(1_000), ..SolverConfig::default() };
+        assert_eq!(SolverOptions::resolve(&config, &[]).unwrap().batch_size, 1_000);
+        let overridden = SolverOptions::resolve(&config, &["batch_size=2_000_000".to_string()]).unwrap();
+        assert_eq!(overridden.batch_size, 2_000_000);
+        assert_eq!(overridden.describe(), "batch_size=2000000, give_up_factor=20");
+    }
+
+    #[test]
+    fn test_give_up_factor_layers_and_checks_its_range() {
+        let config = SolverConfig { give_up_factor: Some(50), ..SolverConfig::default() };
+        assert_eq!(SolverOptions::resolve(&config, &[]).unwrap().give_up_factor, 50);
+        let overridden = SolverOptions::resolve(&config, &["give_up_factor=5".to_string()]).unwrap();
+        assert_eq!(overridden.give_up_factor, 5);
+
+        let e = SolverOptions::resolve(&SolverConfig::default(), &["give_up_factor=0".to_string()]).unwrap_err();
+        assert!(e.contains("give_up_factor must be between 1 and 1000"), "{e}");
+        let config = SolverConfig { give_up_factor: Some(1_001), ..SolverConfig::default() };
+        assert!(SolverOptions::resolve(&config, &[]).unwrap_err().starts_with("Invalid `solver.give_up_factor`"));
+    }
+
+    #[test]
+    fn test_batch_size_out_of_range_names_the_range() {
+        for value in ["0", "100000001"] {
+            let e = SolverOptions::resolve(&SolverConfig::default(), &[format!("batch_size={value}")]).unwrap_err();
+            assert!(e.contains("batch_size must be between 1 and "), "{e}");
+        }
+        let config = SolverConfig { batch_size: Some(0), ..SolverConfig::default() };
+        assert!(SolverOptions::resolve(&config, &[]).unwrap_err().starts_with("Invalid `solver.batch_size`"));
+    }
+
+    #[test]
+    fn test_unknown_keys_fail_fast() {
+        let e = SolverOptions::resolve(&SolverConfig::default(), &["batchsize=10".to_string()]).unwrap_err();
+        assert!(e.contains("unknown solver option 'batchsize'; known options: batch_size, give_up_factor"), "{e}");
+
+        let config = SolverConfig {
+            other: BTreeMap::from([("prefetch".to_string(), serde_json::json!(true))]),
+            ..SolverConfig::default()
+        };
+        assert!(SolverOptions::resolve(&config, &[]).unwrap_err().starts_with("Invalid `solver.prefetch`: unknown"));
+
+        let e = SolverOptions::resolve(&SolverConfig::default(), &["core.prefetch=1".to_string()]).unwrap_err();
+        assert!(e.contains("no tunable options"), "{e}");
+        assert!(SolverOptions::resolve(&SolverConfig::default(), &["batch_size".to_string()]).unwrap_err().contains("key=value"));
+    }
+}
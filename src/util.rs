@@ -1,129 +1,203 @@
-/// Macro for verbose printing that only prints if verbose mode is enabled.
+/// Macro for status lines that are shown regardless of verbosity
+/// but are not part of a command's result, such as progress banners.
 ///
-/// # Example
-/// ```
-/// verbose_println!(config, "Simple message");
-/// verbose_println!(config, "Formatted message: {}", value);
-/// verbose_println!(config, "Multiple values: {} and {}", val1, val2);
-/// ```
-#[macro_export]
-macro_rules! verbose_println {
-    ($config:expr, $($arg:tt)*) => {
-        if $config.verbose {
-            println!($($arg)*);
-        }
-    };
-}
-
-/// Macro for verbose printing without a newline that only 
-/// prints if verbose mode is enabled.
+/// Like every other diagnostic, these go to stderr so that stdout
+/// carries only the result payload. They are also recorded in the
+/// debug log file when one is open.
 ///
 /// # Example
+/// ```ignore
+/// status_println!("Challenge fetched successfully!");
+/// status_println!("Loading configuration from: {}", path);
 /// ```
-/// verbose_print!(config, "Message without newline");
-/// verbose_print!(config, "Progress: {}", percentage);
-/// ```
-#[macro_export]
-macro_rules! verbose_print {
-    ($config:expr, $($arg:tt)*) => {
-        if $config.verbose {
-            print!($($arg)*);
-            use std::io::{self, Write};
-            let _ = io::stdout().flush(); // Ensure immediate output.
-        }
+macro_rules! status_println {
+    ($($arg:tt)*) => {
+        $crate::logging::status_line(format_args!($($arg)*));
     };
 }
+pub(crate) use status_println;
 
 /// Macro for verbose logging with a new line that prints only if
-/// verbose mode is enabled.
+/// verbose mode is enabled and the category passes `--log-filter`.
+/// Every line is recorded in the debug log file when one is open.
 ///
 /// # Example
-/// ```
+/// ```ignore
 /// verbose_log!(config, info, "Information");
 /// verbose_log!(config, success, "Success");
 /// verbose_log!(config, error, "Error");
 /// ```
-#[macro_export]
 macro_rules! verbose_log {
     ($config:expr, compute, $($arg:tt)*) => {
-        if $config.verbose {
-            println!("COMPUTE: {}", format_args!($($arg)*));
-        }
+        $crate::logging::log_event($config.verbose, $crate::logging::LogCategory::Compute, format_args!($($arg)*));
     };
     ($config:expr, error, $($arg:tt)*) => {
-        if $config.verbose {
-            println!("ERROR: {}", format_args!($($arg)*));
-        }
+        $crate::logging::log_event($config.verbose, $crate::logging::LogCategory::Error, format_args!($($arg)*));
     };
     ($config:expr, info, $($arg:tt)*) => {
-        if $config.verbose {
-            println!("INFO: {}", format_args!($($arg)*));
-        }
+        $crate::logging::log_event($config.verbose, $crate::logging::LogCategory::Info, format_args!($($arg)*));
     };
     ($config:expr, receive, $($arg:tt)*) => {
-        if $config.verbose {
-            println!("RECEIVE: {}", format_args!($($arg)*));
-        }
+        $crate::logging::log_event($config.verbose, $crate::logging::LogCategory::Receive, format_args!($($arg)*));
     };
     ($config:expr, success, $($arg:tt)*) => {
-        if $config.verbose {
-            println!("SUCCESS: {}", format_args!($($arg)*));
-        }
+        $crate::logging::log_event($config.verbose, $crate::logging::LogCategory::Success, format_args!($($arg)*));
     };
     ($config:expr, submit, $($arg:tt)*) => {
-        if $config.verbose {
-            println!("SUBMIT: {}", format_args!($($arg)*));
-        }
+        $crate::logging::log_event($config.verbose, $crate::logging::LogCategory::Submit, format_args!($($arg)*));
     };
     ($config:expr, network, $($arg:tt)*) => {
-        if $config.verbose {
-            println!("NETWORK: {}", format_args!($($arg)*));
-        }
+        $crate::logging::log_event($config.verbose, $crate::logging::LogCategory::Network, format_args!($($arg)*));
     };
     ($config:expr, timing, $($arg:tt)*) => {
-        if $config.verbose {
-            println!("TIMING: {}", format_args!($($arg)*));
-        }
+        $crate::logging::log_event($config.verbose, $crate::logging::LogCategory::Timing, format_args!($($arg)*));
     };
     ($config:expr, warning, $($arg:tt)*) => {
-        if $config.verbose {
-            println!("WARNING: {}", format_args!($($arg)*));
-        }
-    };
-}
-
-/// Macro for displaying key-value pairs in a formatted way.
-///
-/// # Example
-/// ```
-/// verbose_kv!(config, "Endpoint", endpoint_url);
-/// verbose_kv!(config, "Threads", num_threads);
-/// verbose_kv!(config, "Duration", format!("{:?}", duration));
-/// ```
-#[macro_export]
-macro_rules! verbose_kv {
-    ($config:expr, $key:expr, $value:expr) => {
-        if $config.verbose {
-            println!("{}: {}", $key, $value);
-        }
+        $crate::logging::log_event($config.verbose, $crate::logging::LogCategory::Warning, format_args!($($arg)*));
     };
 }
+pub(crate) use verbose_log;
 
 /// Macro for displaying section headers in verbose output.
 ///
 /// # Example
-/// ```
+/// ```ignore
 /// verbose_section!(config, "Challenge Solving");
 /// verbose_section!(config, "Network Communication");
 /// ```
-#[macro_export]
 macro_rules! verbose_section {
     ($config:expr, $($arg:tt)*) => {
-        if $config.verbose {
-            println!("\n🔸  {}", format_args!($($arg)*));
-            println!("{}", "─".repeat(40));
+        $crate::logging::log_section($config.verbose, format_args!($($arg)*));
+    };
+}
+pub(crate) use verbose_section;
+
+/// Who may read a file written by [`atomic_write`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileMode {
+    /// Owner only (0600), for history, tokens and anything else sensitive.
+    Private,
+    /// Readable by everyone (0644), for config files.
+    Shared,
+}
+
+impl FileMode {
+    /// The Unix permission bits.
+    pub fn bits(self) -> u32 {
+        match self {
+            Self::Private => 0o600,
+            Self::Shared  => 0o644,
         }
+    }
+}
+
+/// Replaces `path` with `bytes` so that readers see either the old
+/// contents or the new, never a truncated file: the bytes go to a
+/// temporary file in the same directory, which is synced, given
+/// `mode` and renamed over `path`.
+///
+/// # Arguments
+/// * `path`:  The file to write; its directory must exist.
+/// * `bytes`: The complete new contents.
+/// * `mode`:  Who may read the file afterwards.
+pub fn atomic_write(path: &std::path::Path, bytes: &[u8], mode: FileMode) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _                                               => std::path::Path::new("."),
+    };
+    let mut file = tempfile::NamedTempFile::new_in(dir)?;
+    file.write_all(bytes)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.as_file().set_permissions(std::fs::Permissions::from_mode(mode.bits()))?;
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+    file.as_file().sync_all()?;
+
+    match file.persist(path) {
+        Ok(_) => Ok(()),
+        #[cfg(windows)]
+        Err(e) => replace_file(e.file.path(), path).map_err(|_| e.error),
+        #[cfg(not(windows))]
+        Err(e) => Err(e.error),
+    }
+}
+
+/// Falls back to `ReplaceFileW` when renaming over `path` fails, as it
+/// can while another process has the destination open.
+#[cfg(windows)]
+fn replace_file(temp: &std::path::Path, path: &std::path::Path) -> std::io::Result<()> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::{ReplaceFileW, REPLACEFILE_IGNORE_MERGE_ERRORS};
+
+    let wide = |path: &std::path::Path| path.as_os_str().encode_wide().chain(Some(0)).collect::<Vec<u16>>();
+    let (replaced, replacement) = (wide(path), wide(temp));
+    // SAFETY: both paths are NUL-terminated UTF-16 that outlive the call.
+    let ok = unsafe {
+        ReplaceFileW(
+            replaced.as_ptr(),
+            replacement.as_ptr(),
+            std::ptr::null(),
+            REPLACEFILE_IGNORE_MERGE_ERRORS,
+            std::ptr::null(),
+            std::ptr::null(),
+        )
     };
+    match ok {
+        0 => Err(std::io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+/// How long to wait for another ironshield process to release a
+/// [`FileLock`].
+pub const LOCK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// An exclusive advisory lock shared by every ironshield process that
+/// reads, modifies and writes back the same file. Released on drop.
+///
+/// The lock is taken on `<path>.lock` rather than the file itself,
+/// because [`atomic_write`] renames a new file over the old one.
+#[derive(Debug)]
+pub struct FileLock {
+    _file: std::fs::File,
+}
+
+impl FileLock {
+    /// Waits up to `timeout` for the lock on `path`.
+    ///
+    /// # Returns
+    /// * `io::Result<FileLock>`: The lock, or a `TimedOut` error naming
+    ///                           the path if another process kept it.
+    pub fn acquire(path: &std::path::Path, timeout: std::time::Duration) -> std::io::Result<Self> {
+        let mut lock_path = path.as_os_str().to_owned();
+        lock_path.push(".lock");
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)?;
+
+        let deadline = std::time::Instant::now() + timeout;
+        loop {
+            match file.try_lock() {
+                Ok(())                                 => return Ok(Self { _file: file }),
+                Err(std::fs::TryLockError::Error(e))   => return Err(e),
+                Err(std::fs::TryLockError::WouldBlock) => {}
+            }
+            if std::time::Instant::now() >= deadline {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    format!("another ironshield process holds the lock on {}", path.display()),
+                ));
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+    }
 }
 
 #[cfg(test)]
@@ -152,11 +226,76 @@ mod tests {
         // These should print when verbose is true.
         crate::verbose_log!(verbose_config, info, "Test info message");
         crate::verbose_section!(verbose_config, "Test Section");
-        crate::verbose_kv!(verbose_config, "Key", "Value");
 
         // These should not print when verbose is false.
         crate::verbose_log!(quiet_config, info, "This should not print");
         crate::verbose_section!(quiet_config, "This should not print");
-        crate::verbose_kv!(quiet_config, "Key", "This should not print");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_atomic_write_replaces_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ironshield.toml");
+        std::fs::write(&path, "timeout = 5\n").unwrap();
+
+        super::atomic_write(&path, b"timeout = 10\n", super::FileMode::Shared).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "timeout = 10\n");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1, "temporary file left behind");
+    }
+
+    #[test]
+    fn test_atomic_write_is_never_seen_half_done() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        let old = vec![b'a'; 256 * 1024];
+        let new = vec![b'b'; 512 * 1024];
+        std::fs::write(&path, &old).unwrap();
+
+        let done = std::sync::atomic::AtomicBool::new(false);
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for _ in 0..20 {
+                    super::atomic_write(&path, &new, super::FileMode::Private).unwrap();
+                    super::atomic_write(&path, &old, super::FileMode::Private).unwrap();
+                }
+                done.store(true, std::sync::atomic::Ordering::Relaxed);
+            });
+            while !done.load(std::sync::atomic::Ordering::Relaxed) {
+                let seen = std::fs::read(&path).unwrap();
+                assert!(seen == old || seen == new, "saw {} partial bytes", seen.len());
+            }
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_atomic_write_sets_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("token.json");
+        std::fs::write(&path, "{}").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        super::atomic_write(&path, b"{\"token\":1}", super::FileMode::Private).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+
+        super::atomic_write(&path, b"{}", super::FileMode::Shared).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o644);
+    }
+
+    #[test]
+    fn test_lock_waits_then_names_the_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+
+        let held = super::FileLock::acquire(&path, super::LOCK_TIMEOUT).unwrap();
+        let error = super::FileLock::acquire(&path, std::time::Duration::from_millis(50)).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::TimedOut);
+        assert!(error.to_string().contains("another ironshield process holds the lock on"), "{error}");
+
+        drop(held);
+        assert!(super::FileLock::acquire(&path, std::time::Duration::from_millis(50)).is_ok());
+    }
+}
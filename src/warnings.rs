@@ -0,0 +1,266 @@
+use serde_json::{json, Value};
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+
+use crate::logging::LogCategory;
+use crate::output::OutputFormat;
+
+/// Exit code for a run stopped by a warning `--deny-warnings` denies.
+pub const DENIED_EXIT_CODE: i32 = 5;
+
+/// What a warning is about. Codes are stable: scripts match on them,
+/// so a code is never reused or renumbered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WarningCode {
+    /// A multithreaded solve is running on a single core.
+    ParallelUnavailable,
+    /// The challenge's creation time is far from the local clock.
+    ClockSkew,
+    /// The hash rate fell during a solve.
+    Throttled,
+    /// This is an unoptimized debug build.
+    DebugBuild,
+    /// `proxy` skipped or forced a refresh over `--max-renewal-difficulty`.
+    DifficultyCap,
+    /// The challenge may expire before the solve finishes.
+    ExpiryRisk,
+    /// The server attached a maintenance notice.
+    ServerNotice,
+    /// `get --continue` found the resource changed and started over.
+    DownloadRestarted,
+    /// `get` couldn't store the response in its cache.
+    CacheWriteFailed,
+    /// `--dump-repro` couldn't write its bundle.
+    ReproNotWritten,
+    /// The run couldn't be recorded in the history.
+    HistoryNotRecorded,
+    /// `--prewarm` couldn't open its connection.
+    PrewarmFailed,
+    /// Progress events stopped being written.
+    EventsStopped,
+    /// The config file sets two settings that conflict.
+    ConfigConflict,
+    /// A challenge was queued after it expired.
+    QueuedExpired,
+    /// The submit may land after the challenge expires.
+    ThinMargin,
+    /// A proxy presented its own certificate for the API.
+    TlsInterception,
+    /// The API speaks a newer minor version than this CLI was built for.
+    ApiNewer,
+}
+
+impl WarningCode {
+    /// Every code, in order.
+    pub const ALL: [WarningCode; 18] = [
+        Self::ParallelUnavailable,
+        Self::ClockSkew,
+        Self::Throttled,
+        Self::DebugBuild,
+        Self::DifficultyCap,
+        Self::ExpiryRisk,
+        Self::ServerNotice,
+        Self::DownloadRestarted,
+        Self::CacheWriteFailed,
+        Self::ReproNotWritten,
+        Self::HistoryNotRecorded,
+        Self::PrewarmFailed,
+        Self::EventsStopped,
+        Self::ConfigConflict,
+        Self::QueuedExpired,
+        Self::ThinMargin,
+        Self::TlsInterception,
+        Self::ApiNewer,
+    ];
+
+    /// The short code, e.g. "W002".
+    pub fn id(self) -> &'static str {
+        self.name().split_once('_').map_or("", |(id, _)| id)
+    }
+
+    /// The full code, e.g. "W002_CLOCK_SKEW".
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ParallelUnavailable => "W001_PARALLEL_UNAVAILABLE",
+            Self::ClockSkew           => "W002_CLOCK_SKEW",
+            Self::Throttled           => "W003_THROTTLED",
+            Self::DebugBuild          => "W004_DEBUG_BUILD",
+            Self::DifficultyCap       => "W005_DIFFICULTY_CAP",
+            Self::ExpiryRisk          => "W006_EXPIRY_RISK",
+            Self::ServerNotice        => "W007_SERVER_NOTICE",
+            Self::DownloadRestarted   => "W008_DOWNLOAD_RESTARTED",
+            Self::CacheWriteFailed    => "W009_CACHE_WRITE_FAILED",
+            Self::ReproNotWritten     => "W010_REPRO_NOT_WRITTEN",
+            Self::HistoryNotRecorded  => "W011_HISTORY_NOT_RECORDED",
+            Self::PrewarmFailed       => "W012_PREWARM_FAILED",
+            Self::EventsStopped       => "W013_EVENTS_STOPPED",
+            Self::ConfigConflict      => "W014_CONFIG_CONFLICT",
+            Self::QueuedExpired       => "W015_QUEUED_EXPIRED",
+            Self::ThinMargin          => "W016_THIN_MARGIN",
+            Self::TlsInterception     => "W017_TLS_INTERCEPTION",
+            Self::ApiNewer            => "W018_API_NEWER",
+        }
+    }
+
+    /// Parses "W002" or "W002_CLOCK_SKEW", in any case.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        Self::ALL.into_iter()
+            .find(|code| value.eq_ignore_ascii_case(code.id()) || value.eq_ignore_ascii_case(code.name()))
+            .ok_or_else(|| format!("unknown warning code '{value}'; known codes are W001 to W{:03}", Self::ALL.len()))
+    }
+}
+
+/// Something the user should act on, with a stable code scripts can
+/// match on and its numbers for JSON consumers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub code:    WarningCode,
+    pub message: String,
+    pub data:    Value,
+}
+
+impl Warning {
+    pub fn new(code: WarningCode, message: impl Into<String>) -> Self {
+        Self { code, message: message.into(), data: Value::Null }
+    }
+
+    pub fn with_data(self, data: Value) -> Self {
+        Self { data, ..self }
+    }
+
+    /// The warning as JSON output carries it: `{code, message, data}`.
+    pub fn to_json(&self) -> Value {
+        json!({ "code": self.code.name(), "message": self.message, "data": self.data })
+    }
+}
+
+impl fmt::Display for Warning {
+    /// e.g. "warning[W002]: the local clock is 45.0s behind the API's".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "warning[{}]: {}", self.code.id(), self.message)
+    }
+}
+
+/// The warnings that fail the run, from `--deny-warnings` and the
+/// config file's `deny_warnings`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DenyList {
+    all:   bool,
+    codes: BTreeSet<WarningCode>,
+}
+
+impl DenyList {
+    /// # Arguments
+    /// * `flag`:       `--deny-warnings`: `Some` of no codes denies every warning.
+    /// * `configured`: The config file's `deny_warnings`.
+    ///
+    /// # Returns
+    /// * `Result<Self, String>`: The list, or which code is unknown.
+    pub fn resolve(flag: Option<&[String]>, configured: &[String]) -> Result<Self, String> {
+        let all = flag.is_some_and(|codes| codes.is_empty());
+        let codes = flag.unwrap_or_default().iter().chain(configured)
+            .map(|code| WarningCode::parse(code.trim()))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { all, codes })
+    }
+
+    pub fn denies(&self, code: WarningCode) -> bool {
+        self.all || self.codes.contains(&code)
+    }
+}
+
+static FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+static DENIED: OnceLock<DenyList> = OnceLock::new();
+static EMITTED: Mutex<Vec<Warning>> = Mutex::new(Vec::new());
+
+/// Sets the `--output` format that [`emit`] prints in.
+pub fn set_format(format: OutputFormat) {
+    let _ = FORMAT.set(format);
+}
+
+/// Sets the warnings that fail the run for the rest of the process.
+pub fn set_denied(denied: DenyList) {
+    let _ = DENIED.set(denied);
+}
+
+/// Shows `warning` through the `--output` sink, for code with no sink
+/// at hand.
+pub fn emit(warning: Warning) {
+    crate::output::sink(FORMAT.get().copied().unwrap_or_default(), false).warning(&warning);
+}
+
+/// [`emit`], unless the same warning was already shown, for code that
+/// runs again and again, like a daemon's refresh.
+pub fn emit_once(warning: Warning) {
+    let shown = EMITTED.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).contains(&warning);
+    if !shown {
+        emit(warning);
+    }
+}
+
+/// Keeps a warning the console or JSON sink just showed, for the JSON
+/// result's `warnings` array, and ends the run if it is denied.
+pub fn shown(warning: &Warning) {
+    EMITTED.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).push(warning.clone());
+    if DENIED.get().is_some_and(|denied| denied.denies(warning.code)) {
+        crate::logging::log_event(true, LogCategory::Error, format_args!(
+            "{} is denied by --deny-warnings or `deny_warnings`; stopping",
+            warning.code.name(),
+        ));
+        crate::logging::flush();
+        crate::har::write();
+        std::process::exit(DENIED_EXIT_CODE);
+    }
+}
+
+/// Every warning shown so far, as JSON results carry them.
+pub fn shown_json() -> Vec<Value> {
+    EMITTED.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).iter().map(Warning::to_json).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_are_unique_and_parse_both_ways() {
+        let names: BTreeSet<&str> = WarningCode::ALL.iter().map(|code| code.name()).collect();
+        assert_eq!(names.len(), WarningCode::ALL.len());
+        for (index, code) in WarningCode::ALL.into_iter().enumerate() {
+            assert_eq!(code.id(), format!("W{:03}", index + 1));
+            assert_eq!(WarningCode::parse(code.id()), Ok(code));
+            assert_eq!(WarningCode::parse(&code.name().to_lowercase()), Ok(code));
+        }
+        assert!(WarningCode::parse("W999").unwrap_err().contains("unknown warning code 'W999'"));
+    }
+
+    #[test]
+    fn test_display_and_json() {
+        let warning = Warning::new(WarningCode::ClockSkew, "the local clock is 45.0s behind the API's")
+            .with_data(json!({ "skew_ms": -45_000 }));
+        assert_eq!(warning.to_string(), "warning[W002]: the local clock is 45.0s behind the API's");
+        assert_eq!(warning.to_json(), json!({
+            "code":    "W002_CLOCK_SKEW",
+            "message": "the local clock is 45.0s behind the API's",
+            "data":    { "skew_ms": -45_000 },
+        }));
+    }
+
+    #[test]
+    fn test_deny_list() {
+        let none = DenyList::resolve(None, &[]).unwrap();
+        assert!(!none.denies(WarningCode::ClockSkew));
+
+        let all = DenyList::resolve(Some(&[]), &[]).unwrap();
+        assert!(WarningCode::ALL.into_iter().all(|code| all.denies(code)));
+
+        let some = DenyList::resolve(Some(&["W002".to_string()]), &["W003_THROTTLED".to_string()]).unwrap();
+        assert!(some.denies(WarningCode::ClockSkew));
+        assert!(some.denies(WarningCode::Throttled));
+        assert!(!some.denies(WarningCode::DebugBuild));
+
+        assert!(DenyList::resolve(None, &["clock".to_string()]).is_err());
+    }
+}
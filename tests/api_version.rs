@@ -0,0 +1,59 @@
+mod common;
+
+use common::mock_api::{MockApi, NEWER_MAJOR_HOST, NEWER_MINOR_HOST};
+use common::run_cli;
+use serde_json::{json, Value};
+
+fn records(output: &std::process::Output) -> Vec<Value> {
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_incompatible_api_stops_before_solving() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+    let endpoint = format!("https://{NEWER_MAJOR_HOST}/protected");
+
+    let output = run_cli(&["validate", &endpoint, "-c", &config]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("The API speaks version 2.0"), "stderr: {stderr}");
+    assert!(stderr.contains("upgrade ironshield-cli"), "stderr: {stderr}");
+    // Nothing was submitted.
+    assert_eq!(api.requests(), 1);
+}
+
+#[test]
+fn test_newer_minor_api_warns_and_is_named_in_the_result() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+    let endpoint = format!("https://{NEWER_MINOR_HOST}/protected");
+
+    let records = records(&run_cli(&["validate", &endpoint, "-c", &config, "--output", "json"]));
+
+    let warning = records.iter()
+        .find(|record| record["type"] == "warning" && record["code"] == "W018_API_NEWER")
+        .expect("no W018 warning");
+    assert!(warning["message"].as_str().unwrap().contains("consider upgrading ironshield-cli"), "{warning}");
+    let result = records.iter().find(|record| record["type"] == "result").expect("no result record");
+    assert_eq!(result["api_version"], json!({ "negotiation": "newer_minor", "api_version": "1.9" }));
+}
+
+#[test]
+fn test_unversioned_api_is_named_in_the_result() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+
+    let records = records(&run_cli(&["fetch", "https://a.example/protected", "-c", &config, "--output", "json"]));
+
+    let result = records.iter().find(|record| record["type"] == "result").expect("no result record");
+    assert_eq!(result["api_version"], json!({ "negotiation": "unversioned" }));
+    assert!(!records.iter().any(|record| record["code"] == "W018_API_NEWER"));
+}
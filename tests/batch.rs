@@ -0,0 +1,119 @@
+mod common;
+
+use common::{run_cli, unreachable_config};
+
+#[test]
+fn test_batch_reports_every_endpoint() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = unreachable_config(&dir);
+    let endpoints = dir.path().join("endpoints.txt");
+    std::fs::write(&endpoints, "# more\nhttps://b.example/protected\n").unwrap();
+
+    let output = run_cli(&[
+        "batch", "https://a.example/protected",
+        "--endpoints-file", endpoints.to_str().unwrap(),
+        "--schedule", "deadline",
+        "-c", &config_path,
+    ]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+
+    assert!(!output.status.success());
+    assert_eq!(lines.len(), 2, "unexpected stdout: {stdout}");
+    assert!(lines[0].contains("https://a.example/protected  failed:"));
+    assert!(lines[1].contains("https://b.example/protected  failed:"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("2 of 2 endpoints failed"));
+}
+
+#[test]
+fn test_batch_rejects_unknown_schedule() {
+    let output = run_cli(&["batch", "https://a.example", "--schedule", "random"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("easiest-first"));
+}
+
+#[test]
+fn test_validate_reads_endpoints_from_stdin() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = unreachable_config(&dir);
+
+    let output = common::run_cli_with_stdin(
+        &["validate", "--stdin", "--concurrency", "2", "-c", &config_path],
+        "# inventory\nhttps://a.example/protected\n\nnot a url\nhttps://b.example/protected\n",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut entries: Vec<serde_json::Value> = stdout.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    entries.sort_by_key(|entry| entry["line"].as_u64());
+
+    assert!(!output.status.success());
+    assert_eq!(entries.len(), 3, "unexpected stdout: {stdout}");
+    assert_eq!(entries[0]["endpoint"], "https://a.example/protected");
+    assert_eq!(entries[0]["outcome"], "failed");
+    assert_eq!(entries[1]["line"], 4);
+    assert_eq!(entries[1]["outcome"], "invalid");
+    assert_eq!(entries[2]["endpoint"], "https://b.example/protected");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("3 of 3 endpoints failed"));
+}
+
+#[test]
+fn test_batch_reads_endpoints_from_stdin() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = unreachable_config(&dir);
+
+    let output = common::run_cli_with_stdin(&["batch", "--stdin", "-c", &config_path], "https://a.example/protected\nnope\n");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(!output.status.success());
+    assert!(stdout.contains("nope  invalid (stdin line 2)"), "unexpected stdout: {stdout}");
+    assert!(stdout.contains("https://a.example/protected  failed:"), "unexpected stdout: {stdout}");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("2 of 2 endpoints failed"));
+}
+
+#[test]
+fn test_batch_summarizes_failures_by_kind() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = common::mock_api::MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+    let report = dir.path().join("failures.json");
+
+    let failing: Vec<String> = (0..7).map(|i| format!("https://{}/{i}", common::mock_api::FAILING_HOST)).collect();
+    let mut args = vec!["batch", "https://ok.example/protected"];
+    args.extend(failing.iter().map(String::as_str));
+    args.extend(["--failures-out", report.to_str().unwrap(), "-c", &config]);
+    let output = run_cli(&args);
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.lines().count(), 8, "unexpected stdout: {stdout}");
+    assert!(stdout.contains("https://ok.example/protected  ok"));
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("7 of 8 endpoints failed:\n  fetch: 7\n"), "{stderr}");
+    assert!(stderr.contains("    ... and 2 more"), "{stderr}");
+
+    let failures: Vec<serde_json::Value> = serde_json::from_str(&std::fs::read_to_string(&report).unwrap()).unwrap();
+    assert_eq!(failures.len(), 7);
+    for failure in &failures {
+        assert_eq!(failure["error_kind"], "fetch");
+        assert_eq!(failure["stage"], "fetching");
+        assert!(failure["endpoint"].as_str().unwrap().contains(common::mock_api::FAILING_HOST));
+        assert!(failure["timestamp"].is_string());
+    }
+}
+
+#[test]
+fn test_validate_stdin_summarizes_invalid_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = unreachable_config(&dir);
+
+    let output = common::run_cli_with_stdin(
+        &["validate", "--stdin", "-c", &config_path],
+        "https://a.example/protected\nnot a url\n",
+    );
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("2 of 2 endpoints failed:"), "{stderr}");
+    assert!(stderr.contains("  fetch: 1\n    https://a.example/protected (fetching): "), "{stderr}");
+    assert!(stderr.contains("  invalid: 1\n    not a url (listed): line 2: "), "{stderr}");
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use common::run_cli;
+
+#[test]
+fn test_saved_benchmark_compares_against_itself() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("bench.json");
+    let path = path.to_str().unwrap();
+
+    let output = run_cli(&["benchmark", "--threads", "1", "--duration", "50ms", "--save", path]);
+    assert!(output.status.success());
+
+    let saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    assert_eq!(saved["schema_version"], 1);
+    assert_eq!(saved["results"][0]["threads"], 1);
+    assert!(saved["machine"]["cores"].as_u64().unwrap() >= 1);
+
+    let output = run_cli(&["benchmark", "compare", path, path]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("+0.0%"));
+}
+
+#[test]
+fn test_compare_fails_on_regression() {
+    let dir = tempfile::tempdir().unwrap();
+    let old = dir.path().join("old.json");
+    let new = dir.path().join("new.json");
+    std::fs::write(&old, r#"{"schema_version": 1, "results": [{"threads": 4, "hash_rate": 4000000}]}"#).unwrap();
+    std::fs::write(&new, r#"{"schema_version": 1, "results": [{"threads": 4, "hash_rate": 3000000}]}"#).unwrap();
+
+    let output = run_cli(&["benchmark", "compare", old.to_str().unwrap(), new.to_str().unwrap()]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(!output.status.success());
+    assert!(stdout.contains("-25.0%"), "unexpected stdout: {stdout}");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("1 configuration(s) got more than 5% slower"));
+}
+
+#[test]
+fn test_split_simulation_reports_both_modes() {
+    let output = run_cli(&["benchmark", "--threads", "2", "split", "--ranges", "8"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("on 2 threads, one running 4x slower"));
+    assert!(stdout.contains("  stride"));
+    assert!(stdout.contains("  chunked"));
+}
+
+#[test]
+fn test_solver_options_are_recorded_and_validated() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("bench.json");
+    let path = path.to_str().unwrap();
+
+    let output = run_cli(&[
+        "benchmark", "--threads", "1", "--duration", "50ms", "--save", path, "--solver-opt", "batch_size=1000",
+    ]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Solver options: batch_size=1000"));
+    let saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    assert_eq!(saved["solver"]["batch_size"], 1000);
+
+    let output = run_cli(&["benchmark", "--threads", "1", "--duration", "50ms", "--solver-opt", "batch_size=0"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("batch_size must be between 1 and"));
+
+    let output = run_cli(&["benchmark", "--threads", "1", "--duration", "50ms", "--solver-opt", "batchsize=10"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown solver option 'batchsize'"));
+}
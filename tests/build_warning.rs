@@ -0,0 +1,23 @@
+mod common;
+
+use common::run_cli;
+
+// Tests run against the debug build of the binary.
+
+#[test]
+fn test_debug_builds_warn_before_hashing() {
+    let output = run_cli(&["benchmark", "--threads", "1", "--duration", "50ms", "--json"]);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unoptimized debug build"));
+    let file: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(file["debug_build"], true);
+}
+
+#[test]
+fn test_no_build_warning_silences_it() {
+    let output = run_cli(&["benchmark", "--threads", "1", "--duration", "50ms", "--json", "--no-build-warning"]);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("unoptimized debug build"));
+}
@@ -0,0 +1,293 @@
+use ironshield::{IronShieldChallenge, IronShieldChallengeResponse};
+use ironshield_types::IronShieldToken;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// How long issued tokens stay valid, in milliseconds.
+pub const TOKEN_VALID_FOR_MS: i64 = 30_000;
+
+/// What `/protected` serves to requests carrying a token.
+pub const PROTECTED_BODY: &str = "protected resource body\n";
+
+/// Challenges for endpoints on this host are never issued.
+pub const FAILING_HOST: &str = "fail.example";
+
+/// Challenges for endpoints on this host come in an envelope with
+/// [`HINTED_METADATA`] next to them.
+pub const HINTED_HOST: &str = "hinted.example";
+
+/// The `metadata` sent with challenges for [`HINTED_HOST`].
+pub const HINTED_METADATA: &str = r#"{"suggested_threads":1,"load_tier":"high","maintenance":"read-only from 02:00 UTC"}"#;
+
+/// Challenges for endpoints on this host say they come from API 1.9,
+/// a newer minor version than the CLI's.
+pub const NEWER_MINOR_HOST: &str = "v1-9.example";
+
+/// Challenges for endpoints on this host say they come from API 2.0,
+/// which the CLI cannot read.
+pub const NEWER_MAJOR_HOST: &str = "v2.example";
+
+/// Solutions to challenges for this website ID get `410 Gone`, as if
+/// the challenge expired by the API's clock.
+pub const EXPIRED_SITE: &str = "expired-upstream";
+
+/// Solutions to challenges for this website ID get `400 Bad Request`.
+pub const INVALID_SITE: &str = "invalid-solution";
+
+/// Solutions to challenges for this website ID get `503 Service Unavailable`.
+pub const UNAVAILABLE_SITE: &str = "unavailable";
+
+/// The `ETag` of [`PROTECTED_BODY`].
+pub const PROTECTED_ETAG: &str = "\"v1\"";
+
+/// An IronShield API on a local port that issues easy challenges
+/// from `/request` and accepts every solution posted to `/response`,
+/// except for challenges with the website IDs [`EXPIRED_SITE`],
+/// [`INVALID_SITE`] and [`UNAVAILABLE_SITE`]. Challenge requests for
+/// endpoints on [`FAILING_HOST`] get a `500`, and those for endpoints
+/// on [`HINTED_HOST`] an envelope with [`HINTED_METADATA`]. Endpoints
+/// on [`NEWER_MINOR_HOST`] and [`NEWER_MAJOR_HOST`] get an envelope
+/// with that `api_version`.
+///
+/// It also plays the protected origin: `/protected` serves
+/// [`PROTECTED_BODY`] to requests with an `X-IronShield-Token`,
+/// `304 Not Modified` when `If-None-Match` is [`PROTECTED_ETAG`], and
+/// honors `Range: bytes=<start>-`. `/protected/changed` always sends
+/// the whole body, as if it changed since the range was asked for, and
+/// `/protected/no-ranges` doesn't support ranges at all.
+///
+/// Every request is kept, for tests that check exactly what the CLI
+/// sent; see [`MockApi::received`].
+///
+/// The server thread lives until the test process exits.
+pub struct MockApi {
+    pub base_url: String,
+    requests:     Arc<AtomicUsize>,
+    connections:  Arc<AtomicUsize>,
+    received:     Arc<Mutex<Vec<Received>>>,
+}
+
+/// A request as the mock read it off the wire.
+#[derive(Debug, Clone)]
+pub struct Received {
+    pub method:  String,
+    pub path:    String,
+    /// Names lowercased, in the order they were sent.
+    pub headers: Vec<(String, String)>,
+    pub body:    Vec<u8>,
+}
+
+impl Received {
+    /// The value of the first header called `name`, in lowercase.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+}
+
+impl MockApi {
+    /// Starts the server; every challenge it issues has `difficulty`.
+    /// Each connection carries one request.
+    pub fn start(difficulty: u64) -> Self {
+        Self::launch(difficulty, false)
+    }
+
+    /// Starts a server that keeps connections open between requests,
+    /// as production servers do, each on its own thread.
+    pub fn start_keep_alive(difficulty: u64) -> Self {
+        Self::launch(difficulty, true)
+    }
+
+    fn launch(difficulty: u64, keep_alive: bool) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let connections = Arc::new(AtomicUsize::new(0));
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        let (request_counter, connection_counter) = (Arc::clone(&requests), Arc::clone(&connections));
+        let log = Arc::clone(&received);
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                connection_counter.fetch_add(1, Ordering::Relaxed);
+                let (counter, log) = (Arc::clone(&request_counter), Arc::clone(&log));
+                if keep_alive {
+                    std::thread::spawn(move || serve(stream, difficulty, true, &counter, &log));
+                } else {
+                    let _ = serve(stream, difficulty, false, &counter, &log);
+                }
+            }
+        });
+
+        Self { base_url, requests, connections, received }
+    }
+
+    /// Requests served so far.
+    pub fn requests(&self) -> usize {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    /// Connections accepted so far.
+    pub fn connections(&self) -> usize {
+        self.connections.load(Ordering::Relaxed)
+    }
+
+    /// Every request received so far, oldest first.
+    pub fn received(&self) -> Vec<Received> {
+        self.received.lock().unwrap().clone()
+    }
+}
+
+/// Answers requests on `stream` until the client closes it, or after
+/// the first unless `keep_alive`.
+fn serve(
+    stream:     TcpStream,
+    difficulty: u64,
+    keep_alive: bool,
+    requests:   &AtomicUsize,
+    received:   &Mutex<Vec<Received>>,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut stream = stream;
+    loop {
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line)? == 0 {
+            return Ok(());
+        }
+        requests.fetch_add(1, Ordering::Relaxed);
+        respond(&mut reader, &mut stream, &request_line, difficulty, keep_alive, received)?;
+        if !keep_alive {
+            return Ok(());
+        }
+    }
+}
+
+fn respond(
+    reader:       &mut BufReader<TcpStream>,
+    stream:       &mut TcpStream,
+    request_line: &str,
+    difficulty:   u64,
+    keep_alive:   bool,
+    received:     &Mutex<Vec<Received>>,
+) -> std::io::Result<()> {
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("GET").to_string();
+    let path = parts.next().unwrap_or("/").to_string();
+
+    let mut content_length = 0;
+    let mut headers = Vec::new();
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        if header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+    let header = |name: &str| headers.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str());
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    received.lock().unwrap().push(Received { method: method.clone(), path: path.clone(), headers: headers.clone(), body: body.clone() });
+
+    let (status, payload, extra) = match path.as_str() {
+        "/request" if String::from_utf8_lossy(&body).contains(FAILING_HOST) => {
+            ("500 Internal Server Error", "{\"error\":\"no challenges for this endpoint\"}".to_string(), String::new())
+        }
+        "/request" if String::from_utf8_lossy(&body).contains(HINTED_HOST) => {
+            let challenge = serde_json::to_string(&challenge(difficulty)).unwrap();
+            ("200 OK", format!("{{\"challenge\":{challenge},\"metadata\":{HINTED_METADATA}}}"), String::new())
+        }
+        "/request" if String::from_utf8_lossy(&body).contains(NEWER_MINOR_HOST) => {
+            ("200 OK", versioned("1.9", difficulty), String::new())
+        }
+        "/request" if String::from_utf8_lossy(&body).contains(NEWER_MAJOR_HOST) => {
+            ("200 OK", versioned("2.0", difficulty), String::new())
+        }
+        "/request"   => ("200 OK", serde_json::to_string(&challenge(difficulty)).unwrap(), String::new()),
+        "/response"  => match serde_json::from_slice::<IronShieldChallengeResponse>(&body) {
+            Ok(solution) => match solution.solved_challenge.website_id.as_str() {
+                EXPIRED_SITE     => ("410 Gone", "{\"error\":\"challenge expired\"}".to_string(), String::new()),
+                INVALID_SITE     => ("400 Bad Request", "{\"error\":\"invalid solution\"}".to_string(), String::new()),
+                UNAVAILABLE_SITE => ("503 Service Unavailable", "{\"error\":\"try again later\"}".to_string(), String::new()),
+                _                => ("200 OK", serde_json::to_string(&token(&solution)).unwrap(), String::new()),
+            },
+            Err(e)       => ("400 Bad Request", format!("{{\"error\":\"{e}\"}}"), String::new()),
+        },
+        "/protected" | "/protected/changed" | "/protected/no-ranges" => {
+            let (status, payload, extra) = protected(&path, header("x-ironshield-token"), header("if-none-match"), header("range"));
+            (status, payload.to_string(), extra)
+        }
+        _ => ("404 Not Found", "{}".to_string(), String::new()),
+    };
+
+    let connection = if keep_alive { "keep-alive" } else { "close" };
+    let body = if method == "HEAD" { "" } else { payload.as_str() };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\n{extra}Content-Length: {}\r\nConnection: {connection}\r\n\r\n{body}",
+        payload.len(),
+    )?;
+    stream.flush()
+}
+
+/// What a protected route answers, with its extra header lines.
+fn protected(
+    path:          &str,
+    token:         Option<&str>,
+    if_none_match: Option<&str>,
+    range:         Option<&str>,
+) -> (&'static str, &'static str, String) {
+    let length = PROTECTED_BODY.len();
+    let start = range
+        .and_then(|range| range.strip_prefix("bytes="))
+        .and_then(|range| range.strip_suffix('-'))
+        .and_then(|start| start.parse::<usize>().ok());
+    let mut extra = format!("ETag: {PROTECTED_ETAG}\r\n");
+    if path != "/protected/no-ranges" {
+        extra.push_str("Accept-Ranges: bytes\r\n");
+    }
+
+    match (token, if_none_match, start) {
+        (None, _, _)                                       => ("403 Forbidden", "", String::new()),
+        (Some(_), Some(etag), _) if etag == PROTECTED_ETAG => ("304 Not Modified", "", extra),
+        (Some(_), _, Some(start)) if path == "/protected" && start >= length => {
+            extra.push_str(&format!("Content-Range: bytes */{length}\r\n"));
+            ("416 Range Not Satisfiable", "", extra)
+        }
+        (Some(_), _, Some(start)) if path == "/protected" => {
+            extra.push_str(&format!("Content-Range: bytes {start}-{}/{length}\r\n", length - 1));
+            ("206 Partial Content", &PROTECTED_BODY[start..], extra)
+        }
+        (Some(_), _, _) => ("200 OK", PROTECTED_BODY, extra),
+    }
+}
+
+/// A freshly signed challenge, built the way the real API does.
+pub fn challenge(difficulty: u64) -> IronShieldChallenge {
+    let signing_key = ed25519_dalek::SigningKey::from_bytes(&[7; 32]);
+    let public_key = signing_key.verifying_key().to_bytes();
+    IronShieldChallenge::new("mock-api".to_string(), difficulty, signing_key, public_key)
+}
+
+/// A challenge in an envelope that names its `api_version`.
+fn versioned(api_version: &str, difficulty: u64) -> String {
+    serde_json::json!({ "api_version": api_version, "challenge": challenge(difficulty) }).to_string()
+}
+
+/// A token for an accepted solution.
+fn token(solution: &IronShieldChallengeResponse) -> IronShieldToken {
+    let challenge = &solution.solved_challenge;
+    IronShieldToken::new(
+        challenge.challenge_signature,
+        chrono::Utc::now().timestamp_millis() + TOKEN_VALID_FOR_MS,
+        challenge.public_key,
+        [0; 64],
+    )
+}
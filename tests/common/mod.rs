@@ -0,0 +1,91 @@
+#![allow(dead_code)]
+
+pub mod mock_api;
+
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+/// Writes a config pointing at a closed local port so
+/// that no test ever reaches the real API. Run history is
+/// turned off so tests never write to the user's data dir.
+pub fn unreachable_config(dir: &TempDir) -> String {
+    let path = dir.path().join("ironshield.toml");
+    std::fs::write(
+        &path,
+        "api_base_url = \"https://127.0.0.1:1\"\ntimeout = 2\nverbose = false\n\n[history]\nenabled = false\n",
+    ).unwrap();
+
+    path.to_str().unwrap().to_string()
+}
+
+/// Writes a config pointing at `api`, with run history turned off.
+/// `extra` goes in ahead of the `[history]` table, so it can hold
+/// top-level settings as well as tables of its own.
+pub fn write_config(dir: &std::path::Path, api: &mock_api::MockApi, extra: &str) -> String {
+    let path = dir.join("ironshield.toml");
+    std::fs::write(
+        &path,
+        format!("api_base_url = \"{}\"\ntimeout = 5\nverbose = false\n{extra}\n[history]\nenabled = false\n", api.base_url),
+    ).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+/// Runs the `ironshield` binary with `args` and captures its output.
+pub fn run_cli(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ironshield"))
+        .args(args)
+        .output()
+        .expect("failed to spawn the ironshield binary")
+}
+
+/// Runs the `ironshield` binary with its data directory (and
+/// so its run history) redirected into `data_dir`.
+///
+/// Only effective where `dirs::data_dir` honors `XDG_DATA_HOME`.
+pub fn run_cli_with_data_dir(data_dir: &std::path::Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ironshield"))
+        .args(args)
+        .env("XDG_DATA_HOME", data_dir)
+        .output()
+        .expect("failed to spawn the ironshield binary")
+}
+
+/// Runs the `ironshield` binary with `args`, writes `stdin`
+/// to it, closes its stdin and captures its output.
+pub fn run_cli_with_stdin(args: &[&str], stdin: &str) -> Output {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ironshield"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to spawn the ironshield binary");
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().expect("failed to wait for the ironshield binary")
+}
+
+/// Runs the `ironshield` binary with its cache directory (and so
+/// `get`'s HTTP cache) redirected into `cache_dir`.
+///
+/// Only effective where `dirs::cache_dir` honors `XDG_CACHE_HOME`.
+pub fn run_cli_with_cache_dir(cache_dir: &std::path::Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ironshield"))
+        .args(args)
+        .env("XDG_CACHE_HOME", cache_dir)
+        .output()
+        .expect("failed to spawn the ironshield binary")
+}
+
+/// Runs the `ironshield` binary with both its data and cache
+/// directories redirected, so `state` sees only what a test wrote.
+pub fn run_cli_with_state_dirs(data_dir: &std::path::Path, cache_dir: &std::path::Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ironshield"))
+        .args(args)
+        .env("XDG_DATA_HOME", data_dir)
+        .env("XDG_CACHE_HOME", cache_dir)
+        .output()
+        .expect("failed to spawn the ironshield binary")
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use common::{run_cli, unreachable_config};
+
+#[test]
+fn test_offline_dry_run_prints_the_plan() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = unreachable_config(&dir);
+
+    let output = run_cli(&["validate", "https://a.example/protected", "--dry-run=offline", "--json", "-c", &config_path]);
+    let plan: serde_json::Value = serde_json::from_slice(&output.stdout).expect("stdout is the JSON plan");
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(plan["command"], "validate");
+    assert_eq!(plan["requests"][0]["url"], "https://127.0.0.1:1/request");
+    assert_eq!(plan["requests"][1]["url"], "https://127.0.0.1:1/response");
+    assert!(plan["challenge"].is_null());
+}
+
+#[test]
+fn test_dry_run_fetch_failure_is_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = unreachable_config(&dir);
+
+    let output = run_cli(&["solve", "https://a.example/protected", "--dry-run", "-c", &config_path]);
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_dry_run_rejects_invalid_endpoint() {
+    let output = run_cli(&["solve", "not-a-url", "--dry-run=offline"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid endpoint 'not-a-url'"));
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use common::mock_api::MockApi;
+use common::run_cli;
+
+#[test]
+fn test_solves_report_their_energy() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "[power]\nwatts_per_core = 10\ngrams_co2_per_kwh = 400");
+
+    let output = run_cli(&["validate", "https://a.example/protected", "-c", &config, "--output", "json"]);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let records: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout).lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let energy = records.iter()
+        .find(|record| record["type"] == "metric" && record["name"] == "energy")
+        .expect("no energy metric");
+    let method = energy["value"]["method"].as_str().unwrap();
+    assert!(method == "rapl" || method == "cpu_estimate", "{energy}");
+    let wh = energy["value"]["wh"].as_f64().unwrap();
+    assert!(wh >= 0.0, "{energy}");
+    assert_eq!(energy["value"]["grams_co2"].as_f64(), Some(wh / 1000.0 * 400.0));
+}
+
+#[test]
+fn test_invalid_power_settings_are_a_config_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "[power]\nwatts_per_core = 0");
+
+    let output = run_cli(&["validate", "https://a.example/protected", "-c", &config]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("power.watts_per_core"));
+    assert_eq!(api.requests(), 0);
+}
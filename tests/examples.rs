@@ -0,0 +1,31 @@
+mod common;
+
+use common::run_cli;
+
+#[test]
+fn test_help_ends_with_examples() {
+    let output = run_cli(&["validate", "--help"]);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("Examples:\n  # "), "{stdout}");
+    assert!(stdout.contains("ironshield validate --stdin --concurrency 4"), "{stdout}");
+}
+
+#[test]
+fn test_examples_command_prints_one_command() {
+    let output = run_cli(&["examples", "batch"]);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("ironshield batch --endpoints-file"), "{stdout}");
+    assert!(!stdout.contains("ironshield validate"), "{stdout}");
+}
+
+#[test]
+fn test_unknown_commands_have_no_examples() {
+    let output = run_cli(&["examples", "test"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No examples for 'test'"));
+}
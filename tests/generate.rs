@@ -0,0 +1,71 @@
+mod common;
+
+use common::run_cli;
+use ironshield::IronShieldChallenge;
+use tempfile::TempDir;
+
+#[test]
+fn test_generate_prints_a_challenge() {
+    let output = run_cli(&["challenge", "generate", "--difficulty", "5000", "--website-id", "shop", "--expires-in", "30s"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    let challenge: IronShieldChallenge = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(challenge.website_id, "shop");
+    assert_eq!(challenge.expiration_time - challenge.created_time, 30_000);
+}
+
+#[test]
+fn test_generated_challenge_encodes_as_a_header() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("challenge.json");
+    let path = path.to_str().unwrap();
+
+    let output = run_cli(&["challenge", "generate", "--difficulty", "1000", "--out", path]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(output.stdout.is_empty());
+
+    let output = run_cli(&["challenge", "encode", "--file", path]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(!String::from_utf8_lossy(&output.stdout).trim().is_empty());
+}
+
+#[test]
+fn test_difficulty_above_the_cap_needs_force() {
+    let output = run_cli(&["challenge", "generate", "--difficulty", "1000000000"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--force"));
+
+    let output = run_cli(&["challenge", "generate", "--difficulty", "1000000000", "--force"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn test_gzipped_challenge_files_round_trip() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("challenge.json.gz");
+    let path = path.to_str().unwrap();
+
+    let output = run_cli(&["challenge", "generate", "--difficulty", "1000", "--out", path]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(&std::fs::read(path).unwrap()[..2], [0x1f, 0x8b]);
+
+    let output = run_cli(&["challenge", "encode", "--file", path]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(!String::from_utf8_lossy(&output.stdout).trim().is_empty());
+}
+
+#[test]
+fn test_truncated_gzip_file_is_reported() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("challenge.json.gz");
+    let path = path.to_str().unwrap();
+
+    let output = run_cli(&["challenge", "generate", "--difficulty", "1000", "--out", path]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let bytes = std::fs::read(path).unwrap();
+    std::fs::write(path, &bytes[..bytes.len() / 2]).unwrap();
+
+    let output = run_cli(&["challenge", "encode", "--file", path]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("corrupt gzip stream"));
+}
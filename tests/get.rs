@@ -0,0 +1,187 @@
+mod common;
+
+use common::mock_api::{MockApi, PROTECTED_BODY};
+use common::run_cli_with_cache_dir;
+use tempfile::TempDir;
+
+#[test]
+fn test_second_get_is_served_from_the_cache() {
+    let dir = TempDir::new().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+    let endpoint = format!("{}/protected", api.base_url);
+    let get = || run_cli_with_cache_dir(dir.path(), &["get", &endpoint, "-c", &config]);
+
+    let first = get();
+    assert!(first.status.success(), "stderr: {}", String::from_utf8_lossy(&first.stderr));
+    assert_eq!(String::from_utf8_lossy(&first.stdout), PROTECTED_BODY);
+    assert!(!String::from_utf8_lossy(&first.stderr).contains("(cached)"));
+
+    let second = get();
+    assert!(second.status.success(), "stderr: {}", String::from_utf8_lossy(&second.stderr));
+    assert_eq!(String::from_utf8_lossy(&second.stdout), PROTECTED_BODY);
+    assert!(String::from_utf8_lossy(&second.stderr).contains("(cached)"));
+}
+
+#[test]
+fn test_no_http_cache_always_downloads() {
+    let dir = TempDir::new().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+    let endpoint = format!("{}/protected", api.base_url);
+    let body = dir.path().join("body.txt");
+    let body_arg = body.to_str().unwrap();
+
+    for _ in 0..2 {
+        let output = run_cli_with_cache_dir(
+            dir.path(),
+            &["get", &endpoint, "-c", &config, "--no-http-cache", "--save-body", body_arg],
+        );
+        assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+        assert!(!String::from_utf8_lossy(&output.stderr).contains("(cached)"));
+        assert_eq!(std::fs::read_to_string(&body).unwrap(), PROTECTED_BODY);
+    }
+    assert!(!dir.path().join("ironshield").join("http").exists());
+}
+
+#[test]
+fn test_cache_purge() {
+    let dir = TempDir::new().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+    let endpoint = format!("{}/protected", api.base_url);
+
+    assert!(run_cli_with_cache_dir(dir.path(), &["get", &endpoint, "-c", &config]).status.success());
+
+    let output = run_cli_with_cache_dir(dir.path(), &["cache", "purge"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("Removed 1 cached response(s)"));
+
+    let output = run_cli_with_cache_dir(dir.path(), &["get", &endpoint, "-c", &config]);
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("(cached)"));
+}
+
+#[test]
+fn test_transfer_progress_goes_to_stderr() {
+    let dir = TempDir::new().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+    let endpoint = format!("{}/protected", api.base_url);
+
+    let output = run_cli_with_cache_dir(dir.path(), &["get", &endpoint, "-c", &config, "--no-http-cache"]);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), PROTECTED_BODY);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(&format!("received {} B in ", PROTECTED_BODY.len())), "{stderr}");
+    assert!(stderr.contains("average)"), "{stderr}");
+}
+
+/// Runs `get --continue` against `route` with `existing` already in
+/// the output file, and returns the output and the file afterwards.
+fn resume(route: &str, existing: &str) -> (std::process::Output, String) {
+    let dir = TempDir::new().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+    let endpoint = format!("{}{route}", api.base_url);
+    let body = dir.path().join("body.txt");
+    std::fs::write(&body, existing).unwrap();
+
+    let output = run_cli_with_cache_dir(
+        dir.path(),
+        &["get", &endpoint, "-c", &config, "--save-body", body.to_str().unwrap(), "--continue"],
+    );
+    (output, std::fs::read_to_string(&body).unwrap())
+}
+
+#[test]
+fn test_continue_appends_a_partial_response() {
+    let (output, body) = resume("/protected", &PROTECTED_BODY[..10]);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(body, PROTECTED_BODY);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(&format!("Resumed: reused 10 B, transferred {} B", PROTECTED_BODY.len() - 10)), "{stderr}");
+}
+
+#[test]
+fn test_continue_starts_over_when_the_whole_resource_is_sent() {
+    let (output, body) = resume("/protected/changed", "stale prefix");
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(body, PROTECTED_BODY);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("starting over"));
+}
+
+#[test]
+fn test_continue_fails_when_ranges_are_ignored() {
+    let (output, body) = resume("/protected/no-ranges", &PROTECTED_BODY[..10]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("ignored the Range request"));
+    assert_eq!(body, &PROTECTED_BODY[..10]);
+}
+
+#[test]
+fn test_continue_with_an_unsatisfiable_range() {
+    let (output, body) = resume("/protected", PROTECTED_BODY);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Already complete"));
+    assert_eq!(body, PROTECTED_BODY);
+
+    let (output, _) = resume("/protected", &format!("{PROTECTED_BODY}trailing junk"));
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("rerun without --continue"));
+}
+
+/// Runs `get --save-body` with extra checksum flags.
+fn get_with_checksum(args: &[&str]) -> (std::process::Output, TempDir) {
+    let dir = TempDir::new().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+    let endpoint = format!("{}/protected", api.base_url);
+    let body = dir.path().join("artifact.txt");
+
+    let mut full = vec!["get", &endpoint, "-c", &config, "--save-body", body.to_str().unwrap()];
+    full.extend_from_slice(args);
+    (run_cli_with_cache_dir(dir.path(), &full), dir)
+}
+
+fn sha256(data: &str) -> String {
+    use sha2::{Digest, Sha256};
+    Sha256::digest(data.as_bytes()).iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[test]
+fn test_matching_checksum() {
+    let (output, dir) = get_with_checksum(&["--checksum", &format!("sha256:{}", sha256(PROTECTED_BODY))]);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Checksum verified"));
+    assert_eq!(std::fs::read_to_string(dir.path().join("artifact.txt")).unwrap(), PROTECTED_BODY);
+}
+
+#[test]
+fn test_mismatched_checksum_renames_the_file() {
+    let (output, dir) = get_with_checksum(&["--checksum", &format!("sha256:{}", sha256("something else"))]);
+
+    assert_eq!(output.status.code(), Some(4));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Checksum mismatch"));
+    assert!(!dir.path().join("artifact.txt").exists());
+    assert_eq!(std::fs::read_to_string(dir.path().join("artifact.txt.failed")).unwrap(), PROTECTED_BODY);
+}
+
+#[test]
+fn test_checksum_file_lookup_by_name() {
+    let sums_dir = TempDir::new().unwrap();
+    let sums = sums_dir.path().join("SHA256SUMS");
+    std::fs::write(&sums, format!("{}  other.txt\n{}  artifact.txt\n", sha256("other"), sha256(PROTECTED_BODY))).unwrap();
+
+    let (output, _dir) = get_with_checksum(&["--checksum-file", sums.to_str().unwrap()]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    std::fs::write(&sums, format!("{}  other.txt\n", sha256("other"))).unwrap();
+    let (output, _dir) = get_with_checksum(&["--checksum-file", sums.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("has no SHA-256 for artifact.txt"));
+}
@@ -0,0 +1,156 @@
+mod common;
+
+use common::mock_api::{MockApi, PROTECTED_BODY};
+use common::{run_cli_with_cache_dir, unreachable_config};
+use serde_json::Value;
+use tempfile::TempDir;
+
+use std::path::Path;
+
+fn read_har(path: &Path) -> Value {
+    let har: Value = serde_json::from_slice(&std::fs::read(path).expect("no HAR file was written")).unwrap();
+    assert_valid_har(&har);
+    har
+}
+
+fn string(value: &Value, field: &str) {
+    assert!(value[field].is_string(), "{field} should be a string in {value}");
+}
+
+fn number(value: &Value, field: &str) {
+    assert!(value[field].is_number(), "{field} should be a number in {value}");
+}
+
+fn pairs(value: &Value, field: &str) {
+    let pairs = value[field].as_array().unwrap_or_else(|| panic!("{field} should be an array in {value}"));
+    for pair in pairs {
+        string(pair, "name");
+        string(pair, "value");
+    }
+}
+
+/// Checks the fields and types HAR 1.2 requires, as in the spec at
+/// http://www.softwareishard.com/blog/har-12-spec/.
+fn assert_valid_har(har: &Value) {
+    let log = &har["log"];
+    assert_eq!(log["version"], "1.2");
+    string(&log["creator"], "name");
+    string(&log["creator"], "version");
+
+    for entry in log["entries"].as_array().expect("log.entries should be an array") {
+        let started = entry["startedDateTime"].as_str().expect("startedDateTime should be a string");
+        chrono::DateTime::parse_from_rfc3339(started).unwrap_or_else(|e| panic!("startedDateTime {started}: {e}"));
+        number(entry, "time");
+        assert!(entry["cache"].is_object(), "{entry}");
+
+        let request = &entry["request"];
+        for field in ["method", "url", "httpVersion"] {
+            string(request, field);
+        }
+        assert!(request["cookies"].is_array(), "{request}");
+        pairs(request, "headers");
+        pairs(request, "queryString");
+        number(request, "headersSize");
+        number(request, "bodySize");
+        if let Some(post_data) = request.get("postData") {
+            string(post_data, "mimeType");
+        }
+
+        let response = &entry["response"];
+        number(response, "status");
+        for field in ["statusText", "httpVersion", "redirectURL"] {
+            string(response, field);
+        }
+        assert!(response["cookies"].is_array(), "{response}");
+        pairs(response, "headers");
+        number(response, "headersSize");
+        number(response, "bodySize");
+        number(&response["content"], "size");
+        string(&response["content"], "mimeType");
+
+        let timings = &entry["timings"];
+        for field in ["send", "wait", "receive"] {
+            assert!(timings[field].as_f64().is_some_and(|ms| ms >= 0.0), "{field} in {timings}");
+        }
+    }
+}
+
+fn entries(har: &Value) -> &Vec<Value> {
+    har["log"]["entries"].as_array().unwrap()
+}
+
+#[test]
+fn test_validate_records_both_api_calls() {
+    let dir = TempDir::new().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+    let har_path = dir.path().join("run.har");
+
+    let output = common::run_cli(&[
+        "validate", "https://example.com/protected", "-c", &config, "--har", har_path.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    let har = read_har(&har_path);
+    let urls: Vec<(&str, &str)> = entries(&har)
+        .iter()
+        .map(|entry| (entry["request"]["method"].as_str().unwrap(), entry["request"]["url"].as_str().unwrap()))
+        .collect();
+    assert_eq!(urls, [("POST", format!("{}/request", api.base_url).as_str()), ("POST", format!("{}/response", api.base_url).as_str())]);
+    for entry in entries(&har) {
+        assert_eq!(entry["response"]["status"], 200);
+        let body: Value = serde_json::from_str(entry["response"]["content"]["text"].as_str().unwrap()).unwrap();
+        assert!(body.is_object(), "{body}");
+        let headers = entry["response"]["headers"].as_array().unwrap();
+        assert!(headers.iter().any(|header| header["name"] == "connection"), "{entry}");
+        assert!(entry.get("comment").is_none(), "{entry}");
+    }
+    assert!(entries(&har)[1]["request"]["postData"]["text"].as_str().unwrap().contains("solution"));
+}
+
+#[test]
+fn test_get_records_the_protected_request_with_its_token_masked() {
+    let dir = TempDir::new().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+    let har_path = dir.path().join("get.har");
+    let endpoint = format!("{}/protected", api.base_url);
+
+    let output = run_cli_with_cache_dir(
+        dir.path(),
+        &["get", &endpoint, "-c", &config, "--no-http-cache", "--har", har_path.to_str().unwrap()],
+    );
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    let har = read_har(&har_path);
+    let protected = entries(&har).iter().find(|entry| entry["request"]["url"] == endpoint.as_str()).expect("no protected request");
+    assert_eq!(protected["request"]["method"], "GET");
+    let token = protected["request"]["headers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|header| header["name"] == "x-ironshield-token")
+        .expect("no token header");
+    assert!(token["value"].as_str().unwrap().starts_with("***redacted(len="), "{token}");
+    assert_eq!(protected["response"]["status"], 200);
+    assert_eq!(protected["response"]["content"]["text"], PROTECTED_BODY);
+    assert_eq!(protected["response"]["content"]["size"], PROTECTED_BODY.len());
+}
+
+#[test]
+fn test_failed_run_still_writes_the_har() {
+    let dir = TempDir::new().unwrap();
+    let config = unreachable_config(&dir);
+    let har_path = dir.path().join("failed.har");
+
+    let output = common::run_cli(&[
+        "fetch", "https://example.com/protected", "-c", &config, "--har", har_path.to_str().unwrap(),
+    ]);
+    assert!(!output.status.success());
+
+    let har = read_har(&har_path);
+    let entry = &entries(&har)[0];
+    assert_eq!(entry["request"]["url"], "https://127.0.0.1:1/request");
+    assert_eq!(entry["response"]["status"], 0);
+    assert!(entry["comment"].as_str().unwrap().contains("failed"), "{entry}");
+}
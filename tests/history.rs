@@ -0,0 +1,131 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::run_cli_with_data_dir;
+
+/// Like `unreachable_config`, but with run history left on.
+fn recording_config(dir: &tempfile::TempDir) -> String {
+    let path = dir.path().join("ironshield.toml");
+    std::fs::write(&path, "api_base_url = \"https://127.0.0.1:1\"\ntimeout = 2\nverbose = false\n").unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn test_failed_fetch_is_listed_in_history() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = recording_config(&dir);
+
+    let output = run_cli_with_data_dir(dir.path(), &["fetch", "https://a.example/protected", "-c", &config_path]);
+    assert!(!output.status.success());
+
+    let output = run_cli_with_data_dir(dir.path(), &["history", "--json", "--endpoint", "A.EXAMPLE"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let records: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(records.as_array().unwrap().len(), 1, "unexpected history: {stdout}");
+    assert_eq!(records[0]["command"], "fetch");
+    assert_eq!(records[0]["outcome"], "failure");
+    assert_eq!(records[0]["error_kind"], "fetch");
+
+    let output = run_cli_with_data_dir(dir.path(), &["history", "--endpoint", "b.example"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No runs recorded yet."));
+}
+
+#[test]
+fn test_history_stats() {
+    let dir = tempfile::tempdir().unwrap();
+    let history = dir.path().join("ironshield").join("history.jsonl");
+    std::fs::create_dir_all(history.parent().unwrap()).unwrap();
+    std::fs::write(&history, concat!(
+        r#"{"timestamp":"2025-01-01T00:00:00Z","command":"solve","endpoint":"https://a.example","outcome":"success","elapsed_ms":900,"solve_ms":800,"attempts":8000}"#, "\n",
+        r#"{"timestamp":"2025-01-01T00:01:00Z","command":"fetch","endpoint":"https://a.example","outcome":"failure","elapsed_ms":5,"error_kind":"fetch"}"#, "\n",
+    )).unwrap();
+
+    let output = run_cli_with_data_dir(dir.path(), &["history", "stats", "--json"]);
+    let stats: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert_eq!(stats["count"], 2);
+    assert_eq!(stats["success_rate"], 0.5);
+    assert_eq!(stats["p50_solve_ms"], 800);
+    assert_eq!(stats["average_hash_rate"], 10_000);
+}
+
+#[test]
+fn test_disabled_history_records_nothing() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = common::unreachable_config(&dir);
+
+    run_cli_with_data_dir(dir.path(), &["fetch", "https://a.example/protected", "-c", &config_path]);
+
+    assert!(!dir.path().join("ironshield").join("history.jsonl").exists());
+}
+
+#[test]
+fn test_history_compare() {
+    let dir = tempfile::tempdir().unwrap();
+    let history = dir.path().join("ironshield").join("history.jsonl");
+    std::fs::create_dir_all(history.parent().unwrap()).unwrap();
+    std::fs::write(&history, concat!(
+        r#"{"timestamp":"2025-01-01T00:00:00Z","command":"solve","endpoint":"https://a.example","outcome":"success","elapsed_ms":1100,"solve_ms":1000,"attempts":10000}"#, "\n",
+        r#"{"timestamp":"2025-02-01T00:00:00Z","command":"solve","endpoint":"https://a.example","outcome":"success","elapsed_ms":600,"solve_ms":500,"attempts":10000}"#, "\n",
+    )).unwrap();
+
+    let output = run_cli_with_data_dir(dir.path(), &[
+        "history", "compare", "--from", "2025-01-15T00:00:00Z", "--to", "2025-03-01T00:00:00Z", "--json",
+    ]);
+    let comparison: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert_eq!(comparison["baseline"]["count"], 1);
+    assert_eq!(comparison["candidate"]["count"], 1);
+    assert_eq!(comparison["changes"]["p50_solve_ms"], -50.0);
+    assert_eq!(comparison["changes"]["average_hash_rate"], 100.0);
+
+    let output = run_cli_with_data_dir(dir.path(), &["history", "compare", "--from", "2025-01-15"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("may be noise"), "unexpected stderr: {stderr}");
+}
+
+#[test]
+fn test_endpoint_shorthand() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = recording_config(&dir);
+    let history = dir.path().join("ironshield").join("history.jsonl");
+    std::fs::create_dir_all(history.parent().unwrap()).unwrap();
+    std::fs::write(&history, concat!(
+        r#"{"timestamp":"2025-01-01T00:00:00Z","command":"fetch","endpoint":"https://a.example","outcome":"success","elapsed_ms":5}"#, "\n",
+        r#"{"timestamp":"2025-01-01T00:01:00Z","command":"fetch","endpoint":"https://b.example","outcome":"success","elapsed_ms":5}"#, "\n",
+    )).unwrap();
+
+    let output = run_cli_with_data_dir(dir.path(), &["history", "endpoints", "--json"]);
+    let slots: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(slots, serde_json::json!([
+        { "shorthand": "@1", "endpoint": "https://b.example" },
+        { "shorthand": "@2", "endpoint": "https://a.example" },
+    ]));
+
+    let output = run_cli_with_data_dir(dir.path(), &["fetch", "@2", "-c", &config_path]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Using @2 = https://a.example"), "unexpected stderr: {stderr}");
+
+    let output = run_cli_with_data_dir(dir.path(), &["fetch", "@9", "-c", &config_path]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("has only 2 recent endpoints"), "unexpected stderr: {stderr}");
+}
+
+#[test]
+fn test_shorthand_needs_history() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let output = run_cli_with_data_dir(dir.path(), &["fetch", "@last", "-c", &recording_config(&dir)]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("the run history is empty"), "unexpected stderr: {stderr}");
+
+    let output = run_cli_with_data_dir(dir.path(), &["fetch", "@last", "-c", &common::unreachable_config(&dir)]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("run history is turned off"), "unexpected stderr: {stderr}");
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use common::mock_api::MockApi;
+use common::{run_cli, run_cli_with_stdin, unreachable_config};
+
+#[test]
+fn test_injected_fetch_failure_is_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = unreachable_config(&dir);
+
+    let output = run_cli(&["validate", "https://a.example/protected", "--inject-fetch-failure", "1", "-c", &config_path]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("HTTP 503 Service Unavailable (injected by --inject-fetch-failure)"), "{stderr}");
+    assert!(!stderr.contains("Retry?"), "piped runs are never offered a retry: {stderr}");
+}
+
+#[test]
+fn test_injected_failures_are_summarized_by_kind() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = unreachable_config(&dir);
+
+    let output = run_cli_with_stdin(
+        &["validate", "--stdin", "--inject-fetch-failure", "2", "-c", &config_path],
+        "https://a.example/protected\nnot a url\nhttps://b.example/protected\n",
+    );
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("3 of 3 endpoints failed:\n  fetch: 2\n"), "{stderr}");
+    assert!(stderr.contains("  invalid: 1\n"), "{stderr}");
+    assert!(stderr.contains("https://b.example/protected (fetching): HTTP 503"), "{stderr}");
+}
+
+#[test]
+fn test_injection_flags_are_only_in_long_help() {
+    let long = String::from_utf8_lossy(&run_cli(&["--help"]).stdout).to_string();
+    assert!(long.contains("Testing:"), "{long}");
+    assert!(long.contains("--inject-submit-status <CODE>"), "{long}");
+
+    let short = String::from_utf8_lossy(&run_cli(&["-h"]).stdout).to_string();
+    assert!(!short.contains("--inject-"), "{short}");
+}
+
+#[test]
+fn test_injected_submit_status_must_be_an_error() {
+    let output = run_cli(&["validate", "https://a.example/protected", "--inject-submit-status", "204"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not an error status"));
+}
+
+#[test]
+fn test_corrupted_solution_is_caught_before_submitting() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+
+    let output = run_cli(&["validate", "https://a.example/protected", "-s", "--inject-corrupt-solution", "-c", &config]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Not submitting nonce"), "{stderr}");
+    assert!(stderr.contains(&format!("in ironshield {}", env!("CARGO_PKG_VERSION"))), "{stderr}");
+    assert_eq!(api.requests(), 1, "only the challenge should have been requested");
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::mock_api;
+use common::{run_cli, run_cli_with_stdin};
+use ironshield::IronShieldChallengeResponse;
+
+use std::path::Path;
+
+fn solution() -> IronShieldChallengeResponse {
+    IronShieldChallengeResponse { solved_challenge: mock_api::challenge(1_000), solution: 12_345 }
+}
+
+/// Every captured header in `tests/fixtures/interchange` with the
+/// command that handles it.
+fn fixtures() -> Vec<(&'static str, String)> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/interchange");
+    let mut fixtures = Vec::new();
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        let artifact = match name.split('-').next() {
+            Some("challenge") if name.ends_with(".b64") => "challenge",
+            Some("solution") if name.ends_with(".b64")  => "solution",
+            _                                           => continue,
+        };
+        fixtures.push((artifact, std::fs::read_to_string(&path).unwrap().trim().to_string()));
+    }
+    fixtures
+}
+
+/// Decodes `header` and encodes the JSON again, both with the binary.
+fn round_trip(artifact: &str, header: &str) -> String {
+    let decoded = run_cli(&[artifact, "decode", header]);
+    assert!(decoded.status.success(), "stderr: {}", String::from_utf8_lossy(&decoded.stderr));
+
+    let encoded = run_cli_with_stdin(&[artifact, "encode"], &String::from_utf8_lossy(&decoded.stdout));
+    assert!(encoded.status.success(), "stderr: {}", String::from_utf8_lossy(&encoded.stderr));
+    String::from_utf8_lossy(&encoded.stdout).trim().to_string()
+}
+
+#[test]
+fn test_captured_headers_round_trip() {
+    for (artifact, header) in fixtures() {
+        assert_eq!(round_trip(artifact, &header), header, "{artifact} did not round-trip");
+    }
+}
+
+#[test]
+fn test_signed_artifacts_round_trip() {
+    let samples = [
+        ("challenge", mock_api::challenge(1_000).to_base64url_header()),
+        ("solution", solution().to_base64url_header()),
+    ];
+    for (artifact, header) in samples {
+        assert_eq!(round_trip(artifact, &header), header);
+    }
+}
+
+#[test]
+fn test_decode_from_argument_and_encode_from_stdin() {
+    let header = solution().to_base64url_header();
+
+    let output = run_cli(&["solution", "decode", &header]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["solution"], 12_345);
+
+    let output = run_cli_with_stdin(&["solution", "encode"], &String::from_utf8_lossy(&output.stdout));
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), header);
+}
+
+#[test]
+fn test_decode_from_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("challenge.b64");
+    std::fs::write(&path, format!("{}\n", mock_api::challenge(1_000).to_base64url_header())).unwrap();
+
+    let output = run_cli(&["challenge", "decode", "--file", path.to_str().unwrap()]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(json["website_id"], "mock-api");
+}
+
+#[test]
+fn test_invalid_header_is_an_error() {
+    let output = run_cli(&["challenge", "decode", "not-a-header"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Not a valid challenge header"));
+}
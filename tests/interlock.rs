@@ -0,0 +1,51 @@
+mod common;
+
+use common::run_cli;
+
+/// An overridden `api_base_url` with `a.example` listed as production.
+fn production_config(dir: &tempfile::TempDir) -> String {
+    let path = dir.path().join("ironshield.toml");
+    std::fs::write(
+        &path,
+        "api_base_url = \"https://127.0.0.1:1\"\ntimeout = 2\nverbose = false\n\
+         production_hosts = [\"a.example\"]\n\n[history]\nenabled = false\n",
+    ).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn test_production_host_with_overridden_api_is_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = production_config(&dir);
+
+    let output = run_cli(&["fetch", "https://www.a.example/protected", "-c", &config_path]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("Refusing to run against production host"), "unexpected stderr: {stderr}");
+    assert!(stderr.contains("`api_base_url` is overridden to https://127.0.0.1:1"), "unexpected stderr: {stderr}");
+}
+
+#[test]
+fn test_yes_skips_the_interlock() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = production_config(&dir);
+
+    let output = run_cli(&["fetch", "https://a.example/protected", "-c", &config_path, "--yes"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // Gets as far as the (unreachable) API.
+    assert!(!output.status.success());
+    assert!(!stderr.contains("Refusing to run"), "unexpected stderr: {stderr}");
+}
+
+#[test]
+fn test_other_hosts_are_not_checked() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = production_config(&dir);
+
+    let output = run_cli(&["fetch", "https://b.example/protected", "-c", &config_path]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!stderr.contains("Refusing to run"), "unexpected stderr: {stderr}");
+}
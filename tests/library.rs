@@ -0,0 +1,93 @@
+mod common;
+
+use common::mock_api::MockApi;
+use ironshield_cli::{CliClient, ClientConfig, Event, Limits, ProgressTracker, Refused, SolveOptions, Strategy};
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Default)]
+struct CountingTracker {
+    reports: Arc<AtomicU64>,
+}
+
+impl ProgressTracker for CountingTracker {
+    fn on_progress(&self, _thread_id: usize, _total_attempts: u64, _hash_rate: u64, _elapsed: Duration) {
+        self.reports.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn config(api: &MockApi) -> ClientConfig {
+    ClientConfig { api_base_url: api.base_url.clone(), timeout: Duration::from_secs(5), ..ClientConfig::default() }
+}
+
+#[tokio::test]
+async fn test_full_flow_through_library() {
+    let api = MockApi::start(1_000);
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&events);
+    let client = CliClient::new(config(&api)).unwrap()
+        .with_solve_options(SolveOptions { threads: Some(2), ..SolveOptions::default() })
+        .on_event(move |event| seen.lock().unwrap().push(event.clone()));
+
+    let challenge = client.fetch("https://a.example/protected").await.unwrap().challenge;
+    let tracker = CountingTracker::default();
+    let solution = client.solve(challenge.clone(), SolveOptions { strategy: Strategy::Fast, ..SolveOptions::default() }, tracker)
+        .await
+        .unwrap();
+    assert_eq!(solution.solved_challenge.random_nonce, challenge.random_nonce);
+
+    let token = client.validate("https://a.example/protected").await.unwrap();
+    assert!(token.valid_for > chrono::Utc::now().timestamp_millis());
+    assert_eq!(api.requests(), 3);
+
+    let events = events.lock().unwrap();
+    assert!(matches!(events[0], Event::ChallengeFetched { difficulty: 1_000, .. }));
+    assert!(matches!(events[1], Event::SolveStarted { strategy: Strategy::Fast, .. }));
+    assert!(matches!(events[2], Event::ThreadsGranted { granted: 1.., .. }));
+    assert!(matches!(events[3], Event::Solved { .. }));
+    assert!(matches!(events.last(), Some(Event::Validated { .. })));
+    assert!(events.iter().any(|event| matches!(event, Event::SolveStarted { thread_count: 2, .. })));
+}
+
+#[tokio::test]
+async fn test_limits_refuse_before_solving() {
+    let api = MockApi::start(1_000_000);
+    let client = CliClient::new(config(&api)).unwrap()
+        .with_limits(Limits { max_difficulty: Some(1_000), ..Limits::default() });
+
+    let error = client.validate("https://a.example/protected").await.unwrap_err();
+
+    assert!(error.downcast_ref::<Refused>().is_some(), "unexpected error: {error:#}");
+    assert_eq!(api.requests(), 1);
+}
+
+#[tokio::test]
+async fn test_api_calls_send_exactly_what_the_api_expects() {
+    let api = MockApi::start(1_000);
+    let config = ClientConfig { user_agent: "ironshield-test/1.0".to_string(), ..config(&api) };
+    let client = CliClient::new(config).unwrap();
+
+    let before = chrono::Utc::now().timestamp_millis();
+    client.validate("https://a.example/protected").await.unwrap();
+    let after = chrono::Utc::now().timestamp_millis();
+
+    let received = api.received();
+    let paths: Vec<(&str, &str)> = received.iter().map(|request| (request.method.as_str(), request.path.as_str())).collect();
+    assert_eq!(paths, [("POST", "/request"), ("POST", "/response")]);
+    for request in &received {
+        assert_eq!(request.header("content-type"), Some("application/json"), "{request:?}");
+        assert_eq!(request.header("user-agent"), Some("ironshield-test/1.0"), "{request:?}");
+    }
+
+    let body: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+    let keys: Vec<&str> = body.as_object().unwrap().keys().map(String::as_str).collect();
+    assert_eq!(keys, ["endpoint", "timestamp"]);
+    assert_eq!(body["endpoint"], "https://a.example/protected");
+    let timestamp = body["timestamp"].as_i64().expect("timestamp should be Unix milliseconds");
+    assert!((before..=after).contains(&timestamp), "{timestamp} not in {before}..={after}");
+
+    let solution: ironshield_cli::IronShieldChallengeResponse = serde_json::from_slice(&received[1].body).unwrap();
+    assert_eq!(solution.solved_challenge.website_id, "mock-api");
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use common::{run_cli, unreachable_config};
+
+#[test]
+fn test_log_file_records_without_verbose() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = unreachable_config(&dir);
+    let log_path = dir.path().join("debug.log");
+
+    let output = run_cli(&[
+        "--log-file", log_path.to_str().unwrap(),
+        "fetch", "https://example.com/protected", "-c", &config_path
+    ]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let log = std::fs::read_to_string(&log_path).unwrap();
+
+    // Verbose sections stay off the console but land in the file.
+    assert!(!stderr.contains("Challenge Fetching"), "unexpected verbose output: {stderr}");
+    assert!(log.contains("== Challenge Fetching =="), "missing section in log: {log}");
+    assert!(log.contains("NETWORK: Requesting challenge"), "missing event in log: {log}");
+    assert!(log.contains("ERROR: "), "missing failure in log: {log}");
+}
+
+#[test]
+fn test_unwritable_log_file_is_a_config_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = unreachable_config(&dir);
+    let log_path = dir.path().join("missing-dir").join("debug.log");
+
+    let output = run_cli(&[
+        "--log-file", log_path.to_str().unwrap(),
+        "fetch", "https://example.com/protected", "-c", &config_path
+    ]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("Cannot open log file"), "unexpected stderr: {stderr}");
+}
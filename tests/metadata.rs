@@ -0,0 +1,77 @@
+mod common;
+
+use common::mock_api::{MockApi, HINTED_HOST};
+use common::run_cli;
+use serde_json::Value;
+
+fn records(output: &std::process::Output) -> Vec<Value> {
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+fn kv<'a>(records: &'a [Value], key: &str) -> Option<&'a str> {
+    records.iter()
+        .find(|record| record["type"] == "kv" && record["key"] == key)
+        .and_then(|record| record["value"].as_str())
+}
+
+#[test]
+fn test_fetch_reports_the_metadata_sent_with_the_challenge() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+    let endpoint = format!("https://{HINTED_HOST}/protected");
+
+    let records = records(&run_cli(&["fetch", &endpoint, "-c", &config, "--output", "json"]));
+
+    let notice = records.iter()
+        .find(|record| record["type"] == "warning" && record["code"] == "W007_SERVER_NOTICE")
+        .expect("no server notice");
+    assert_eq!(notice["data"]["maintenance"], "read-only from 02:00 UTC");
+    assert!(records.iter().any(|record| record["type"] == "info" && record["message"] == "Server load: high"));
+    let metadata = records.iter()
+        .find(|record| record["type"] == "metric" && record["name"] == "challenge_metadata")
+        .expect("no challenge_metadata metric");
+    assert_eq!(metadata["value"]["suggested_threads"], 1);
+    let result = records.iter().find(|record| record["type"] == "result").expect("no result record");
+    assert!(!result["value"]["random_nonce"].is_null(), "{result}");
+}
+
+#[test]
+fn test_bare_challenges_report_no_metadata() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+
+    let records = records(&run_cli(&["fetch", "https://a.example/protected", "-c", &config, "--output", "json"]));
+
+    assert!(!records.iter().any(|record| record["type"] == "metric" && record["name"] == "challenge_metadata"));
+    assert!(records.iter().any(|record| record["type"] == "result"));
+}
+
+#[test]
+fn test_solves_use_the_suggested_thread_count() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+    let endpoint = format!("https://{HINTED_HOST}/protected");
+
+    let records = records(&run_cli(&["validate", &endpoint, "-c", &config, "--strategy", "fast", "--verbose", "--output", "json"]));
+
+    assert_eq!(kv(&records, "Suggested Threads"), Some("1"));
+    assert_eq!(kv(&records, "Thread Count"), Some("1"));
+}
+
+#[test]
+fn test_threads_flag_wins_over_the_suggestion() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+    let endpoint = format!("https://{HINTED_HOST}/protected");
+
+    let records = records(&run_cli(&["validate", &endpoint, "-c", &config, "--threads", "2", "--verbose", "--output", "json"]));
+
+    assert_eq!(kv(&records, "Thread Count"), Some("2"));
+}
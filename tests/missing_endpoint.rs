@@ -0,0 +1,24 @@
+mod common;
+
+use common::{run_cli, run_cli_with_stdin};
+
+#[test]
+fn test_missing_endpoint_without_terminal_is_an_error() {
+    // Captured output is never a terminal, so there is nobody to ask.
+    for command in ["fetch", "solve", "validate"] {
+        let output = run_cli(&[command]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert!(!output.status.success());
+        assert!(stderr.contains("<ENDPOINT> argument is required"), "unexpected stderr: {stderr}");
+    }
+}
+
+#[test]
+fn test_piped_stdin_is_not_read_as_an_answer() {
+    let output = run_cli_with_stdin(&["solve"], "https://example.com/protected\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(!stderr.contains("Endpoint URL:"), "unexpected stderr: {stderr}");
+}
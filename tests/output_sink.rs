@@ -0,0 +1,105 @@
+mod common;
+
+use common::mock_api::MockApi;
+use common::run_cli;
+use serde_json::{json, Value};
+
+/// Lines whose values depend on timing or randomness; only their
+/// prefix is compared.
+const VOLATILE_PREFIXES: [&str; 5] = ["Expected solve time: ", "Challenge solved successfully in ", "Hash rate: ", "Energy: ", "Stage timings:"];
+const VOLATILE_KEYS:     [&str; 2] = ["Random Nonce", "Token Valid Until"];
+const VOLATILE_METRICS:  [&str; 3] = ["energy", "submit_margin", "stage_timings"];
+
+fn normalize(mut record: Value) -> Value {
+    match record["type"].as_str() {
+        Some("kv") if VOLATILE_KEYS.iter().any(|key| record["key"] == *key) => record["value"] = json!("*"),
+        Some("metric") if VOLATILE_METRICS.iter().any(|name| record["name"] == *name) => record["value"] = json!("*"),
+        Some("result") => record = json!({ "type": "result" }),
+        Some("info") => {
+            let message = record["message"].as_str().unwrap_or_default();
+            if let Some(prefix) = VOLATILE_PREFIXES.iter().find(|prefix| message.starts_with(*prefix)) {
+                record["message"] = json!(format!("{prefix}*"));
+            }
+        },
+        _ => {},
+    }
+    record
+}
+
+fn info(line: &str) -> Value {
+    json!({ "type": "info", "message": line })
+}
+
+fn kv(key: &str, value: &str) -> Value {
+    json!({ "type": "kv", "key": key, "value": value })
+}
+
+fn metric(name: &str) -> Value {
+    json!({ "type": "metric", "name": name, "value": "*" })
+}
+
+fn section(title: &str) -> Value {
+    json!({ "type": "section", "title": title })
+}
+
+/// Runs `validate` against `config` with every record on stdout.
+fn validate_records(config: &str) -> (bool, Vec<Value>) {
+    // Single-threaded so the strategy line doesn't depend on the core count.
+    let output = run_cli(&[
+        "validate", "https://a.example/protected", "-c", config,
+        "--single-threaded", "-v", "--output", "json", "--no-build-warning",
+    ]);
+    let records = String::from_utf8_lossy(&output.stdout).lines()
+        .map(|line| normalize(serde_json::from_str(line).unwrap()))
+        .collect();
+    (output.status.success(), records)
+}
+
+#[test]
+fn test_validate_emits_exact_records() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+
+    let (success, records) = validate_records(&config);
+
+    assert!(success, "records: {records:?}");
+    assert_eq!(records, [
+        section("Challenge Fetching"),
+        info("Challenge fetched successfully!"),
+        kv("Random Nonce", "*"),
+        kv("Difficulty", "1,000"),
+        kv("Recommended Attempts", "2,000"),
+        section("Challenge Solving"),
+        kv("Thread Count", "1"),
+        kv("Multithreaded", "false"),
+        kv("Recommended Attempts", "2000"),
+        info("Received proof-of-work challenge with difficulty 1,000"),
+        info("Strategy: balanced: 1 thread at normal priority"),
+        info("Expected solve time: *"),
+        info("Challenge solved successfully in *"),
+        info("Hash rate: *"),
+        info("Solver options: batch_size=500000, give_up_factor=20"),
+        info("Energy: *"),
+        metric("energy"),
+        section("Solution Submission"),
+        metric("submit_margin"),
+        info("Challenge validated successfully!"),
+        kv("Token Valid Until", "*"),
+        info("Stage timings:*"),
+        metric("stage_timings"),
+        json!({ "type": "result" }),
+    ]);
+}
+
+#[test]
+fn test_failed_fetch_emits_no_result() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("ironshield.toml");
+    std::fs::write(&config, "api_base_url = \"http://127.0.0.1:1\"\ntimeout = 2\n[history]\nenabled = false\n").unwrap();
+
+    let (success, records) = validate_records(config.to_str().unwrap());
+
+    assert!(!success);
+    assert_eq!(records, [section("Challenge Fetching")]);
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use common::mock_api::MockApi;
+use common::{run_cli, unreachable_config};
+use serde_json::Value;
+
+#[test]
+fn test_diagnostics_go_to_stderr() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = unreachable_config(&dir);
+
+    let output = run_cli(&["fetch", "https://example.com/protected", "--verbose", "-c", &config_path]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // The fetch fails, so there is no result payload and stdout must stay empty.
+    assert!(stdout.trim().is_empty(), "unexpected stdout: {stdout}");
+    assert!(stderr.contains("Loaded configuration from"), "missing banner in stderr: {stderr}");
+}
+
+#[test]
+fn test_stdout_holds_only_the_result_on_success() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+
+    let output = run_cli(&["validate", "https://a.example/protected", "--verbose", "-c", &config]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {stderr}");
+
+    // The whole of stdout parses as one JSON document: the token.
+    let token: Value = serde_json::from_str(&stdout).unwrap_or_else(|e| panic!("stdout is not one JSON document ({e}): {stdout}"));
+    assert!(token["valid_for"].is_number(), "{token}");
+    for diagnostic in ["Loaded configuration from", "Requesting challenge for endpoint", "Challenge fetched successfully!", "Challenge validated successfully!"] {
+        assert!(stderr.contains(diagnostic), "missing {diagnostic:?} in stderr: {stderr}");
+        assert!(!stdout.contains(diagnostic), "{diagnostic:?} leaked into stdout: {stdout}");
+    }
+}
+
+#[test]
+fn test_log_stdout_restores_legacy_routing() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = unreachable_config(&dir);
+
+    let output = run_cli(&[
+        "--log-stdout", "fetch", "https://example.com/protected", "--verbose", "-c", &config_path
+    ]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("Loaded configuration from"), "missing banner in stdout: {stdout}");
+}
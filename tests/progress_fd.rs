@@ -0,0 +1,57 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::mock_api::MockApi;
+
+use std::io::{BufRead, BufReader};
+use std::os::fd::AsRawFd;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+
+#[test]
+fn test_progress_events_arrive_on_the_extra_descriptor() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(200_000);
+    let config = common::write_config(dir.path(), &api, "");
+
+    let (reader, writer) = std::io::pipe().unwrap();
+    let writer_fd = writer.as_raw_fd();
+    let mut command = Command::new(env!("CARGO_BIN_EXE_ironshield"));
+    command
+        .args(["solve", "https://a.example/protected", "--progress-fd", "3", "-c", &config])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // SAFETY: dup2 is async-signal-safe; the copy on fd 3 is not
+    // close-on-exec, so only it reaches the CLI.
+    unsafe {
+        command.pre_exec(move || match libc::dup2(writer_fd, 3) {
+            -1 => Err(std::io::Error::last_os_error()),
+            _  => Ok(()),
+        });
+    }
+    let child = command.spawn().expect("failed to spawn the ironshield binary");
+    drop(writer);
+
+    let events: Vec<serde_json::Value> = BufReader::new(reader)
+        .lines()
+        .map(|line| serde_json::from_str(&line.unwrap()).expect("each line is one JSON event"))
+        .collect();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    let kinds: Vec<&str> = events.iter().map(|event| event["event"].as_str().unwrap()).collect();
+    assert_eq!(kinds.first(), Some(&"solve_started"), "{kinds:?}");
+    assert_eq!(kinds.last(), Some(&"solve_finished"), "{kinds:?}");
+    assert!(kinds[1..kinds.len() - 1].iter().all(|kind| *kind == "progress"), "{kinds:?}");
+    assert_eq!(events.last().unwrap()["solved"], true);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("\"event\""));
+}
+
+#[test]
+fn test_standard_streams_are_refused_as_the_progress_descriptor() {
+    let output = common::run_cli(&["solve", "https://a.example/protected", "--progress-fd", "1"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("3 or above"));
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use common::mock_api::MockApi;
+use common::run_cli;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Sends `GET path` and returns the status code and body.
+fn get(addr: &str, path: &str) -> (u16, String) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let status = response.split(' ').nth(1).unwrap().parse().unwrap();
+    let body = response.split_once("\r\n\r\n").unwrap().1.to_string();
+    (status, body)
+}
+
+#[test]
+fn test_proxy_serves_health_endpoints() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ironshield"))
+        .args(["proxy", "https://a.example/protected", "--listen", "127.0.0.1:0", "-c", &config])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("failed to spawn the ironshield binary");
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    // Logs are JSON on stdout, with the bound address in the first proxy line.
+    let mut addr = None;
+    let mut line = String::new();
+    while addr.is_none() && stdout.read_line(&mut line).unwrap() > 0 {
+        let event: serde_json::Value = serde_json::from_str(&line).expect("every log line is JSON");
+        assert!(!line.contains('\x1b'), "ANSI codes in: {line}");
+        let message = event["fields"]["message"].as_str().unwrap_or_default();
+        addr = message.split_once("listening on http://").map(|(_, addr)| addr.to_string());
+        line.clear();
+    }
+    let addr = addr.expect("no listen address logged");
+
+    assert_eq!(get(&addr, "/healthz"), (200, "ok\n".to_string()));
+    let started = Instant::now();
+    while get(&addr, "/readyz").0 != 200 {
+        assert!(started.elapsed() < Duration::from_secs(10), "never became ready: {}", get(&addr, "/readyz").1);
+        sleep(Duration::from_millis(50));
+    }
+    let (status, token) = get(&addr, "/token");
+    assert_eq!(status, 200);
+    assert!(serde_json::from_str::<serde_json::Value>(&token).unwrap().get("valid_for").is_some());
+    assert_eq!(get(&addr, "/missing").0, 404);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn test_proxy_serves_metrics() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ironshield"))
+        .args(["--metrics-listen", "127.0.0.1:0", "proxy", "https://a.example/protected", "--listen", "127.0.0.1:0", "-c", &config])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("failed to spawn the ironshield binary");
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    let mut metrics_addr = None;
+    let mut line = String::new();
+    while metrics_addr.is_none() && stdout.read_line(&mut line).unwrap() > 0 {
+        let event: serde_json::Value = serde_json::from_str(&line).expect("every log line is JSON");
+        let message = event["fields"]["message"].as_str().unwrap_or_default();
+        metrics_addr = message.split_once("Serving metrics at http://")
+            .map(|(_, addr)| addr.trim_end_matches("/metrics").to_string());
+        line.clear();
+    }
+    let metrics_addr = metrics_addr.expect("no metrics address logged");
+
+    let started = Instant::now();
+    loop {
+        let (status, body) = get(&metrics_addr, "/metrics");
+        assert_eq!(status, 200);
+        if body.contains("challenges_fetched_total 1") {
+            break;
+        }
+        assert!(started.elapsed() < Duration::from_secs(10), "no fetch counted: {body}");
+        sleep(Duration::from_millis(50));
+    }
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn test_proxy_reports_an_address_it_cannot_bind() {
+    let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = taken.local_addr().unwrap().to_string();
+
+    let output = run_cli(&["proxy", "https://a.example/protected", "--listen", &addr]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(&format!("Cannot listen on {addr}")), "stderr: {stderr}");
+}
+
+#[test]
+fn test_listen_must_be_an_address() {
+    let output = run_cli(&["proxy", "https://a.example/protected", "--listen", "localhost"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("[::]:8787"));
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use common::mock_api::{EXPIRED_SITE, INVALID_SITE, MockApi, UNAVAILABLE_SITE};
+use common::run_cli_with_data_dir;
+
+fn generate(data_dir: &std::path::Path, out: &std::path::Path, expires_in: &str) {
+    generate_for(data_dir, out, expires_in, "test");
+}
+
+fn generate_for(data_dir: &std::path::Path, out: &std::path::Path, expires_in: &str, website_id: &str) {
+    let output = run_cli_with_data_dir(data_dir, &[
+        "challenge", "generate", "--difficulty", "1000", "--expires-in", expires_in, "--website-id", website_id,
+        "--out", out.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+}
+
+fn list(data_dir: &std::path::Path) -> Vec<serde_json::Value> {
+    let output = run_cli_with_data_dir(data_dir, &["queue", "list", "--json"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn test_queue_solves_offline_then_submits() {
+    let dir = tempfile::tempdir().unwrap();
+    let challenge = dir.path().join("challenge.json.gz");
+    generate(dir.path(), &challenge, "5m");
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+
+    let output = run_cli_with_data_dir(dir.path(), &["-c", &config, "queue", "add", challenge.to_str().unwrap()]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let items = list(dir.path());
+    assert_eq!(items[0]["state"], "pending");
+    assert_eq!(items[0]["api_base_url"], api.base_url.as_str());
+
+    // Solving happens offline.
+    let output = run_cli_with_data_dir(dir.path(), &["queue", "solve", "-s"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(api.requests(), 0);
+    let items = list(dir.path());
+    assert_eq!(items[0]["state"], "solved");
+    assert!(items[0]["solution"].is_object());
+
+    let output = run_cli_with_data_dir(dir.path(), &["-c", &config, "queue", "submit"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let items = list(dir.path());
+    assert_eq!(items[0]["state"], "submitted");
+    assert!(items[0]["token"].is_object());
+}
+
+#[test]
+fn test_queue_solve_skips_expired_challenges_with_a_report() {
+    let dir = tempfile::tempdir().unwrap();
+    let challenge = dir.path().join("challenge.json");
+    generate(dir.path(), &challenge, "1ms");
+    std::thread::sleep(std::time::Duration::from_millis(20));
+
+    let output = run_cli_with_data_dir(dir.path(), &["queue", "add", challenge.to_str().unwrap()]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    let output = run_cli_with_data_dir(dir.path(), &["queue", "solve"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Skipped"), "stderr: {stderr}");
+    assert!(stderr.contains("skipped 1 expired"), "stderr: {stderr}");
+    assert_eq!(list(dir.path())[0]["state"], "expired");
+}
+
+#[test]
+fn test_queue_list_shows_ages_and_expiries() {
+    let dir = tempfile::tempdir().unwrap();
+    let challenge = dir.path().join("challenge.json");
+    generate(dir.path(), &challenge, "5m");
+    run_cli_with_data_dir(dir.path(), &["queue", "add", challenge.to_str().unwrap()]);
+
+    let output = run_cli_with_data_dir(dir.path(), &["queue", "list"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Expires"), "stdout: {stdout}");
+    assert!(stdout.contains("pending"), "stdout: {stdout}");
+    assert!(stdout.contains("in 4m") || stdout.contains("in 5m"), "stdout: {stdout}");
+}
+
+#[test]
+fn test_queue_submit_batches_over_pooled_connections_with_mixed_outcomes() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start_keep_alive(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+
+    let sites = [("test", 12), (EXPIRED_SITE, 3), (INVALID_SITE, 2), (UNAVAILABLE_SITE, 3)];
+    for (index, website_id) in sites.iter().flat_map(|(site, count)| std::iter::repeat_n(*site, *count)).enumerate() {
+        let challenge = dir.path().join(format!("challenge-{index}.json"));
+        generate_for(dir.path(), &challenge, "5m", website_id);
+        let output = run_cli_with_data_dir(dir.path(), &["-c", &config, "queue", "add", challenge.to_str().unwrap()]);
+        assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    }
+    let output = run_cli_with_data_dir(dir.path(), &["queue", "solve", "-s"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    let output = run_cli_with_data_dir(dir.path(), &["-c", &config, "queue", "submit"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Submitting 20 solution(s) to"), "stderr: {stderr}");
+    assert!(
+        stderr.contains("Submitted 12, rejected 3 expired and 2 invalid, 3 network failure(s); 3 remain queued."),
+        "stderr: {stderr}",
+    );
+    assert!(stderr.contains("Moved 5 rejected item(s)"), "stderr: {stderr}");
+    assert!(api.connections() <= 4, "{} connections for 20 submits", api.connections());
+
+    let items = list(dir.path());
+    assert_eq!(items.len(), 15);
+    assert_eq!(items.iter().filter(|item| item["state"] == "submitted").count(), 12);
+    let queued: Vec<_> = items.iter().filter(|item| item["state"] == "solved").collect();
+    assert_eq!(queued.len(), 3);
+    assert!(queued.iter().all(|item| item["challenge"]["website_id"] == UNAVAILABLE_SITE));
+
+    let failed_dir = dir.path().join("ironshield").join("queue").join("failed");
+    let failed: Vec<serde_json::Value> = std::fs::read_dir(&failed_dir).unwrap()
+        .map(|entry| serde_json::from_str(&std::fs::read_to_string(entry.unwrap().path()).unwrap()).unwrap())
+        .collect();
+    assert_eq!(failed.len(), 5);
+    assert!(failed.iter().all(|item| item["state"] == "rejected"));
+    assert_eq!(failed.iter().filter(|item| item["rejection"]["status"] == 410).count(), 3);
+    assert_eq!(failed.iter().filter(|item| item["rejection"]["status"] == 400).count(), 2);
+    assert!(failed.iter().any(|item| item["rejection"]["body"].as_str().unwrap().contains("invalid solution")));
+}
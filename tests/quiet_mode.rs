@@ -0,0 +1,47 @@
+mod common;
+
+use common::{run_cli, unreachable_config};
+
+#[test]
+fn test_normal_mode_prints_status_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = unreachable_config(&dir);
+
+    let output = run_cli(&["fetch", "https://example.com/protected", "-c", &config_path]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(stderr.contains("Loaded configuration from"), "missing status line: {stderr}");
+}
+
+#[test]
+fn test_quiet_mode_prints_only_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = unreachable_config(&dir);
+
+    let output = run_cli(&["--quiet", "fetch", "https://example.com/protected", "-c", &config_path]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stdout.trim().is_empty(), "unexpected stdout: {stdout}");
+    assert!(!stderr.contains("Loaded configuration from"), "status line leaked: {stderr}");
+    assert!(!stderr.contains("Challenge Fetching"), "verbose output leaked: {stderr}");
+}
+
+#[test]
+fn test_quiet_conflicts_with_verbose() {
+    let output = run_cli(&["--quiet", "--verbose", "fetch", "https://example.com/protected"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("cannot be used with"), "unexpected stderr: {stderr}");
+}
+
+#[test]
+fn test_quiet_conflicts_with_a_subcommand_verbose() {
+    let output = run_cli(&["solve", "https://example.com/protected", "-v", "-q"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("cannot be used with"), "unexpected stderr: {stderr}");
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use common::mock_api::MockApi;
+use common::run_cli;
+
+/// The mock API names itself `mock-api` in every challenge, which the
+/// debug log records; the config declares that name a secret.
+fn secret_config(dir: &tempfile::TempDir, api: &MockApi) -> String {
+    let path = dir.path().join("ironshield.toml");
+    std::fs::write(
+        &path,
+        format!(
+            "api_base_url = \"{}\"\ntimeout = 5\nverbose = true\nsecrets = [\"mock-api\"]\n\n[history]\nenabled = false\n",
+            api.base_url,
+        ),
+    ).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+fn validate(args: &[&str]) -> (String, String) {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config_path = secret_config(&dir, &api);
+    let log_path = dir.path().join("debug.log");
+
+    let mut all = vec!["--log-file", log_path.to_str().unwrap(), "validate", "https://a.example/protected", "-c", &config_path];
+    all.extend_from_slice(args);
+    let output = run_cli(&all);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    (String::from_utf8_lossy(&output.stderr).to_string(), std::fs::read_to_string(&log_path).unwrap())
+}
+
+#[test]
+fn test_configured_secret_never_reaches_the_logs() {
+    let (stderr, log) = validate(&[]);
+
+    assert!(!stderr.contains("mock-api"), "secret on the console: {stderr}");
+    assert!(!log.contains("mock-api"), "secret in the log file: {log}");
+    assert!(log.contains("***redacted(len=8)***"), "no redaction in the log file: {log}");
+}
+
+#[test]
+fn test_log_secrets_turns_redaction_off() {
+    let (_, log) = validate(&["--log-secrets"]);
+
+    assert!(log.contains("mock-api"), "expected the secret verbatim: {log}");
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use common::mock_api::MockApi;
+use common::run_cli;
+
+#[test]
+fn test_dumped_solve_replays_to_the_same_nonce() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+    let bundle = dir.path().join("bundle");
+    let bundle = bundle.to_str().unwrap();
+
+    let output = run_cli(&["solve", "https://a.example/protected", "-s", "--dump-repro", bundle, "-c", &config]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    for file in ["manifest.json", "challenge.json", "solve_config.json", "client_config.json", "outcome.json", "progress.json"] {
+        assert!(dir.path().join("bundle").join(file).exists(), "{file} missing");
+    }
+
+    let output = run_cli(&["repro", bundle]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}\nstderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("Reproduced: nonce"), "{stdout}");
+}
+
+#[test]
+fn test_unknown_bundle_versions_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("manifest.json"),
+        r#"{"format_version": "2.0", "created_at": "2026-01-01T00:00:00Z"}"#,
+    ).unwrap();
+
+    let output = run_cli(&["repro", dir.path().to_str().unwrap()]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("bundle format 2.0 is not supported"));
+}
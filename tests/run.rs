@@ -0,0 +1,24 @@
+mod common;
+
+use common::mock_api::MockApi;
+use common::run_cli;
+
+#[test]
+fn test_run_prints_challenge_solution_and_timings() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+
+    let output = run_cli(&["run", "https://a.example/protected", "-s", "-c", &config]);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let timings = stderr.lines().find(|line| line.contains("Stage timings:")).expect("no stage timings");
+    for stage in ["fetch ", "solve ", "total "] {
+        assert!(timings.contains(stage), "{stage}missing from: {timings}");
+    }
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(result.get("challenge").is_some());
+    assert!(result.get("solution").is_some());
+    assert!(result["timings"].get("total_ms").is_some());
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use common::mock_api::MockApi;
+use common::run_cli;
+use ironshield::IronShieldChallengeResponse;
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+const TOKEN: &str = "test-token-1234";
+
+/// A running `ironshield serve` on a free port, killed when dropped.
+struct Server {
+    child:    Child,
+    base_url: String,
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Starts the server and waits for the address it reports.
+fn start(config: &str) -> Server {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ironshield"))
+        .args(["serve", "--listen", "127.0.0.1:0", "-c", config])
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to spawn the ironshield binary");
+    let mut stderr = BufReader::new(child.stderr.take().unwrap()).lines();
+    let base_url = stderr
+        .by_ref()
+        .map_while(Result::ok)
+        .find_map(|line| line.split_once("Serving on ").map(|(_, rest)| rest.split(' ').next().unwrap().to_string()))
+        .expect("the server never said where it listens");
+    std::thread::spawn(move || stderr.for_each(drop));
+    Server { child, base_url }
+}
+
+fn client() -> reqwest::Client {
+    reqwest::Client::builder().timeout(Duration::from_secs(30)).build().unwrap()
+}
+
+#[tokio::test]
+async fn test_solve_returns_a_verified_solution() {
+    let dir = TempDir::new().unwrap();
+    let api = MockApi::start(1_000);
+    let server = start(&common::write_config(dir.path(), &api, &format!("[serve]\ntoken = \"{TOKEN}\"\n")));
+    let challenge = common::mock_api::challenge(1_000);
+
+    let response = client().post(format!("{}/solve", server.base_url)).bearer_auth(TOKEN).json(&challenge).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let solution: IronShieldChallengeResponse = response.json().await.unwrap();
+    assert!(ironshield_core::verify_ironshield_solution(&solution.solved_challenge, solution.solution));
+
+    let response = client().post(format!("{}/solve", server.base_url)).json(&challenge).send().await.unwrap();
+    assert_eq!(response.status(), 401);
+    let response = client().post(format!("{}/solve", server.base_url)).bearer_auth("wrong-token").json(&challenge).send().await.unwrap();
+    assert_eq!(response.status(), 401);
+    assert_eq!(api.requests(), 0);
+}
+
+#[tokio::test]
+async fn test_validate_runs_the_full_flow() {
+    let dir = TempDir::new().unwrap();
+    let api = MockApi::start(1_000);
+    let server = start(&common::write_config(dir.path(), &api, ""));
+
+    let response = client()
+        .post(format!("{}/validate", server.base_url))
+        .json(&json!({ "endpoint": "https://a.example/protected" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let token: Value = response.json().await.unwrap();
+    assert!(token.is_object(), "{token}");
+    assert_eq!(api.requests(), 2);
+
+    let response = client().post(format!("{}/validate", server.base_url)).body("{}").send().await.unwrap();
+    assert_eq!(response.status(), 400);
+    let response = client().get(format!("{}/solve", server.base_url)).send().await.unwrap();
+    assert_eq!(response.status(), 405);
+}
+
+#[tokio::test]
+async fn test_busy_server_answers_429_and_reports_status() {
+    let dir = TempDir::new().unwrap();
+    let api = MockApi::start(1_000);
+    let server = start(&common::write_config(dir.path(), &api, "[serve]\nmax_concurrent = 1\n"));
+    let hard = common::mock_api::challenge(500_000_000);
+
+    let slow = tokio::spawn({
+        let url = format!("{}/solve?max_time=1s", server.base_url);
+        let hard = hard.clone();
+        async move { client().post(url).json(&hard).send().await.unwrap() }
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let status: Value = client().get(format!("{}/status", server.base_url)).send().await.unwrap().json().await.unwrap();
+    assert_eq!(status["max_concurrent"], 1);
+    assert_eq!(status["in_flight"].as_array().map(Vec::len), Some(1), "{status}");
+    assert_eq!(status["in_flight"][0]["kind"], "solve");
+
+    let response = client().post(format!("{}/solve", server.base_url)).json(&hard).send().await.unwrap();
+    assert_eq!(response.status(), 429);
+
+    let response = slow.await.unwrap();
+    assert_eq!(response.status(), 504);
+    let body: Value = response.json().await.unwrap();
+    assert!(body["error"].as_str().unwrap().contains("max_time 1.0s"), "{body}");
+
+    let status: Value = client().get(format!("{}/status", server.base_url)).send().await.unwrap().json().await.unwrap();
+    assert_eq!((status["failed"].as_u64(), status["rejected"].as_u64()), (Some(1), Some(1)), "{status}");
+}
+
+#[test]
+fn test_serving_other_hosts_needs_a_token() {
+    let dir = TempDir::new().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+
+    let output = run_cli(&["serve", "--listen", "0.0.0.0:0", "-c", &config]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Refusing to serve on 0.0.0.0:0 without authentication"));
+}
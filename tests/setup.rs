@@ -0,0 +1,37 @@
+mod common;
+
+use common::run_cli;
+use tempfile::TempDir;
+
+#[test]
+fn test_setup_with_defaults_writes_a_config() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("ironshield.toml");
+    let path = path.to_str().unwrap();
+
+    let output = run_cli(&["--config", path, "setup", "--defaults", "--api-base-url", "http://127.0.0.1:1"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Not reachable"), "{stderr}");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Next steps:"));
+
+    let table: toml::Table = toml::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    assert_eq!(table["api_base_url"].as_str(), Some("http://127.0.0.1:1"));
+}
+
+#[test]
+fn test_setup_keeps_an_existing_config_without_yes() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("ironshield.toml");
+    std::fs::write(&path, "verbose = false\n").unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = run_cli(&["--config", path, "setup", "--defaults", "--api-base-url", "http://127.0.0.1:1"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("pass --yes to overwrite"));
+    assert_eq!(std::fs::read_to_string(path).unwrap(), "verbose = false\n");
+
+    let output = run_cli(&["--config", path, "setup", "--defaults", "--yes", "--api-base-url", "http://127.0.0.1:1"]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+}
@@ -0,0 +1,152 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::mock_api::MockApi;
+use nix::sys::signal::{kill, Signal};
+use nix::unistd::Pid;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::path::Path;
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::thread::sleep;
+use std::time::Duration;
+
+fn write_config(path: &Path, base_url: &str, timeout: u64) {
+    std::fs::write(
+        path,
+        format!("api_base_url = \"{base_url}\"\ntimeout = {timeout}\nverbose = false\n\n[history]\nenabled = false\n"),
+    ).unwrap();
+}
+
+/// Starts `ironshield stream` and waits until it has answered one
+/// fetch, so its signal handlers are known to be installed.
+fn start_stream(config: &Path) -> (Child, BufReader<ChildStdout>) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ironshield"))
+        .args(["stream", "-c", config.to_str().unwrap()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to spawn the ironshield binary");
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    send(&mut child, "{\"id\":0,\"cmd\":\"fetch\",\"endpoint\":\"https://a.example/protected\"}");
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    assert!(line.contains("\"ok\":true"), "first response: {line}");
+    (child, stdout)
+}
+
+fn send(child: &mut Child, line: &str) {
+    let stdin = child.stdin.as_mut().unwrap();
+    writeln!(stdin, "{line}").unwrap();
+    stdin.flush().unwrap();
+}
+
+fn signal(child: &Child, signal: Signal) {
+    kill(Pid::from_raw(child.id() as i32), signal).unwrap();
+}
+
+#[test]
+fn test_sigterm_finishes_the_command_in_flight() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(200_000);
+    let config = dir.path().join("ironshield.toml");
+    write_config(&config, &api.base_url, 5);
+    let (mut child, stdout) = start_stream(&config);
+
+    send(&mut child, "{\"id\":1,\"cmd\":\"validate\",\"endpoint\":\"https://a.example/protected\"}");
+    sleep(Duration::from_millis(150));
+    signal(&child, Signal::SIGTERM);
+
+    // Stdin stays open: the exit comes from the signal, not from EOF.
+    let output = child.wait_with_output().unwrap();
+    let responses: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(&line.unwrap()).expect("every line is JSON"))
+        .collect();
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(responses.len(), 1, "{responses:?}");
+    assert_eq!(responses[0]["id"], 1);
+    assert_eq!(responses[0]["ok"], true);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("SIGTERM: shutting down"));
+}
+
+#[test]
+fn test_sighup_reloads_the_config_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = dir.path().join("ironshield.toml");
+    write_config(&config, &api.base_url, 5);
+    let (mut child, _stdout) = start_stream(&config);
+
+    write_config(&config, &api.base_url, 7);
+    signal(&child, Signal::SIGHUP);
+    sleep(Duration::from_millis(300));
+    drop(child.stdin.take());
+
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {stderr}");
+    assert!(stderr.contains("timeout: 5.0s -> 7.0s"), "stderr: {stderr}");
+    assert!(stderr.contains("the API client was rebuilt"), "stderr: {stderr}");
+}
+
+/// Reads `stdout` until a line contains `needle`, returning that line.
+fn read_until(stdout: &mut BufReader<ChildStdout>, needle: &str) -> String {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        assert!(stdout.read_line(&mut line).unwrap() > 0, "stdout closed before {needle:?}");
+        if line.contains(needle) {
+            return line;
+        }
+    }
+}
+
+/// Starts `ironshield proxy` and waits until `/readyz` passes, so its
+/// first token is served and its signal handlers are installed.
+fn start_proxy(config: &Path) -> (Child, BufReader<ChildStdout>) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ironshield"))
+        .args(["proxy", "https://a.example/protected", "--listen", "127.0.0.1:0", "-c", config.to_str().unwrap()])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("failed to spawn the ironshield binary");
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    let line = read_until(&mut stdout, "listening on http://");
+    let addr = line.split_once("listening on http://").unwrap().1.split('"').next().unwrap().to_string();
+    read_until(&mut stdout, "Refreshed the token");
+
+    let mut stream = TcpStream::connect(&addr).unwrap();
+    write!(stream, "GET /readyz HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    (child, stdout)
+}
+
+#[test]
+fn test_proxy_reloads_on_sighup_and_exits_cleanly_on_sigterm() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = dir.path().join("ironshield.toml");
+    write_config(&config, &api.base_url, 5);
+    let (child, mut stdout) = start_proxy(&config);
+
+    write_config(&config, &api.base_url, 7);
+    signal(&child, Signal::SIGHUP);
+    read_until(&mut stdout, "timeout: 5.0s -> 7.0s");
+
+    signal(&child, Signal::SIGTERM);
+    read_until(&mut stdout, "SIGTERM: shutting down");
+    let mut rest = String::new();
+    stdout.read_to_string(&mut rest).unwrap();
+
+    let status = child.wait_with_output().unwrap().status;
+    assert!(status.success(), "exit status: {status}");
+}
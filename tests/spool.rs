@@ -0,0 +1,108 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::{run_cli, unreachable_config};
+use ironshield::{IronShieldChallenge, IronShieldChallengeResponse};
+use nix::sys::signal::{kill, Signal};
+use nix::unistd::Pid;
+use tempfile::TempDir;
+
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Puts `challenge` in place the way writers must: under a name the
+/// spool ignores, then renamed.
+fn drop_challenge(dir: &Path, name: &str, challenge: &IronShieldChallenge) {
+    let partial = dir.join(format!(".{name}"));
+    std::fs::write(&partial, serde_json::to_vec(challenge).unwrap()).unwrap();
+    std::fs::rename(&partial, dir.join(name)).unwrap();
+}
+
+fn start_spool(spool: &Path, config: &str, args: &[&str]) -> Child {
+    Command::new(env!("CARGO_BIN_EXE_ironshield"))
+        .args(["spool", "--dir", spool.to_str().unwrap(), "-c", config])
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to spawn the ironshield binary")
+}
+
+fn wait_for(path: &Path) {
+    let deadline = Instant::now() + Duration::from_secs(20);
+    while !path.exists() {
+        assert!(Instant::now() < deadline, "{} never appeared", path.display());
+        sleep(Duration::from_millis(50));
+    }
+}
+
+#[test]
+fn test_spool_solves_dropped_challenges_and_stops_on_sigterm() {
+    let dir = TempDir::new().unwrap();
+    let config = unreachable_config(&dir);
+    let spool = dir.path().join("spool");
+    std::fs::create_dir(&spool).unwrap();
+
+    // Waiting before the start, and expired.
+    let mut expired = common::mock_api::challenge(1_000);
+    expired.expiration_time = chrono::Utc::now().timestamp_millis() - 1_000;
+    drop_challenge(&spool, "old.challenge.json", &expired);
+    std::fs::write(spool.join("partial.challenge.json.tmp"), "{").unwrap();
+
+    let child = start_spool(&spool, &config, &[]);
+    wait_for(&spool.join("failed").join("old.challenge.json"));
+    drop_challenge(&spool, "edge-1.challenge.json", &common::mock_api::challenge(1_000));
+    wait_for(&spool.join("done").join("edge-1.challenge.json"));
+
+    kill(Pid::from_raw(child.id() as i32), Signal::SIGTERM).unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {stderr}");
+    assert!(stderr.contains("Solved 1, failed 1."), "stderr: {stderr}");
+
+    let solution: IronShieldChallengeResponse =
+        serde_json::from_slice(&std::fs::read(spool.join("edge-1.solution.json")).unwrap()).unwrap();
+    assert!(ironshield_core::verify_ironshield_solution(&solution.solved_challenge, solution.solution));
+    let error = std::fs::read_to_string(spool.join("failed").join("old.error.txt")).unwrap();
+    assert_eq!(error.trim(), "expired before it was solved");
+    assert!(!spool.join("old.solution.json").exists());
+    assert!(spool.join("partial.challenge.json.tmp").exists());
+}
+
+#[test]
+fn test_spool_polls_when_asked() {
+    let dir = TempDir::new().unwrap();
+    let config = unreachable_config(&dir);
+    let spool = dir.path().join("spool");
+    std::fs::create_dir(&spool).unwrap();
+
+    let child = start_spool(&spool, &config, &["--poll", "100ms", "--jobs", "2", "--threads", "1"]);
+    drop_challenge(&spool, "a.challenge.json", &common::mock_api::challenge(1_000));
+    drop_challenge(&spool, "b.challenge.json", &common::mock_api::challenge(1_000));
+    wait_for(&spool.join("done").join("a.challenge.json"));
+    wait_for(&spool.join("done").join("b.challenge.json"));
+
+    kill(Pid::from_raw(child.id() as i32), Signal::SIGTERM).unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {stderr}");
+    assert!(stderr.contains("Polling"), "stderr: {stderr}");
+    assert!(spool.join("a.solution.json").exists() && spool.join("b.solution.json").exists());
+}
+
+#[test]
+fn test_spool_needs_an_existing_directory() {
+    let dir = TempDir::new().unwrap();
+    let config = unreachable_config(&dir);
+    let missing = dir.path().join("missing");
+
+    let output = run_cli(&["spool", "--dir", missing.to_str().unwrap(), "-c", &config]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("as a spool"));
+
+    let output = run_cli(&["spool", "--dir", missing.to_str().unwrap(), "--poll", "1s", "--inotify"]);
+    assert!(!output.status.success());
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::run_cli_with_state_dirs;
+
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+fn record(timestamp: &str) -> String {
+    format!(
+        "{{\"timestamp\":\"{timestamp}\",\"command\":\"validate\",\"endpoint\":\"https://a.example/\",\"outcome\":\"success\",\"elapsed_ms\":10}}\n",
+    )
+}
+
+fn age(path: &Path, days: u64) {
+    let at = SystemTime::now() - Duration::from_secs(days * 24 * 60 * 60);
+    fs::File::options().write(true).open(path).unwrap().set_modified(at).unwrap();
+}
+
+#[test]
+fn test_path_reports_the_redirected_directories() {
+    let dir = tempfile::tempdir().unwrap();
+    let (data, cache) = (dir.path().join("data"), dir.path().join("cache"));
+
+    let output = run_cli_with_state_dirs(&data, &cache, &["state", "path", "--json"]);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let dirs: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(dirs["data"], data.join("ironshield").to_str().unwrap());
+    assert_eq!(dirs["cache"], cache.join("ironshield").to_str().unwrap());
+}
+
+#[test]
+fn test_gc_dry_run_then_gc() {
+    let dir = tempfile::tempdir().unwrap();
+    let (data, cache) = (dir.path().join("data"), dir.path().join("cache"));
+    let state = data.join("ironshield");
+    let queue = state.join("queue");
+    fs::create_dir_all(&queue).unwrap();
+    let stale = queue.join(".tmpStale1");
+    fs::write(&stale, "partial").unwrap();
+    age(&stale, 10);
+    let fresh = queue.join(".tmpFresh2");
+    fs::write(&fresh, "in progress").unwrap();
+    let stray = queue.join("keep-me.txt");
+    fs::write(&stray, "mine").unwrap();
+    age(&stray, 400);
+    let history = state.join("history.jsonl");
+    fs::write(&history, record("2020-01-01T00:00:00Z") + &record("2999-01-01T00:00:00Z")).unwrap();
+    let config = dir.path().join("ironshield.toml");
+    fs::write(&config, "api_base_url = \"https://127.0.0.1:1\"\ntimeout = 2\n[history]\nretention = \"365d\"\n").unwrap();
+    let config = config.to_str().unwrap();
+
+    let output = run_cli_with_state_dirs(&data, &cache, &["-c", config, "state", "gc", "--older-than", "7d", "--dry-run"]);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.lines().collect::<Vec<_>>(), [
+        format!("Would remove {} (history: 1 record(s) past the history retention)", history.display()),
+        format!("Would remove {} (queue: left by an interrupted write)", stale.display()),
+    ]);
+    assert!(stale.exists());
+    assert_eq!(fs::read_to_string(&history).unwrap().lines().count(), 2);
+
+    let output = run_cli_with_state_dirs(&data, &cache, &["-c", config, "state", "gc", "--older-than", "7d"]);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&format!("Removed {}", stale.display())));
+    assert!(!stale.exists());
+    assert!(fresh.exists());
+    assert!(stray.exists());
+    assert_eq!(fs::read_to_string(&history).unwrap(), record("2999-01-01T00:00:00Z"));
+}
+
+#[test]
+fn test_size_counts_each_component() {
+    let dir = tempfile::tempdir().unwrap();
+    let (data, cache) = (dir.path().join("data"), dir.path().join("cache"));
+    let http = cache.join("ironshield").join("http");
+    fs::create_dir_all(&http).unwrap();
+    fs::write(http.join("a.body"), "0123456789").unwrap();
+
+    let output = run_cli_with_state_dirs(&data, &cache, &["state", "size", "--json"]);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let usage: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let components: Vec<&str> = usage.iter().map(|usage| usage["component"].as_str().unwrap()).collect();
+    assert_eq!(components, ["history", "queue", "http_cache"]);
+    assert_eq!((usage[2]["files"].as_u64(), usage[2]["bytes"].as_u64()), (Some(1), Some(10)));
+    assert_eq!(usage[0]["files"], 0);
+}
@@ -0,0 +1,32 @@
+mod common;
+
+use common::{run_cli_with_stdin, unreachable_config};
+
+#[test]
+fn test_stream_answers_every_line_and_exits_cleanly() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = unreachable_config(&dir);
+    let input = concat!(
+        "{\"id\":1,\"cmd\":\"frobnicate\",\"endpoint\":\"https://a.example\"}\n",
+        "not json\n",
+        "\n",
+        "{\"id\":2,\"cmd\":\"fetch\",\"endpoint\":\"https://a.example/protected\"}\n",
+    );
+
+    let output = run_cli_with_stdin(&["stream", "-c", &config_path], input);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let responses: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("every line is JSON"))
+        .collect();
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(responses.len(), 3, "unexpected stdout: {stdout}");
+    assert_eq!(responses[0]["id"], 1);
+    assert_eq!(responses[0]["ok"], false);
+    assert!(responses[0]["error"].as_str().unwrap().contains("Unknown command 'frobnicate'"));
+    assert_eq!(responses[1]["ok"], false);
+    assert_eq!(responses[2]["id"], 2);
+    assert_eq!(responses[2]["cmd"], "fetch");
+    assert_eq!(responses[2]["ok"], false);
+}
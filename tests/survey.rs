@@ -0,0 +1,29 @@
+mod common;
+
+use common::{run_cli, unreachable_config};
+
+#[test]
+fn test_survey_without_any_challenge_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = unreachable_config(&dir);
+    let endpoints = dir.path().join("endpoints.txt");
+    std::fs::write(&endpoints, "# local\nhttps://a.example/protected\n").unwrap();
+
+    let output = run_cli(&[
+        "survey", "--endpoints-file", endpoints.to_str().unwrap(), "--samples", "1", "-c", &config_path,
+    ]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("https://a.example/protected: fetch failed"), "unexpected stderr: {stderr}");
+    assert!(stderr.contains("No challenges were collected"), "unexpected stderr: {stderr}");
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_survey_rejects_bad_interval() {
+    let output = run_cli(&["survey", "--endpoints-file", "endpoints.txt", "--interval", "soon"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid duration 'soon'"));
+}
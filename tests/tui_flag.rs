@@ -0,0 +1,42 @@
+mod common;
+
+use common::run_cli;
+
+#[test]
+fn test_subcommand_required_without_tui() {
+    let output = run_cli(&[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("unless `--tui` is given"), "unexpected stderr: {stderr}");
+}
+
+#[test]
+fn test_tui_conflicts_with_subcommand() {
+    let output = run_cli(&["--tui", "fetch", "https://example.com/protected"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("cannot be combined with a subcommand"), "unexpected stderr: {stderr}");
+}
+
+#[test]
+fn test_tui_without_terminal_suggests_subcommands() {
+    // Captured output is never a terminal.
+    let output = run_cli(&["--tui"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("must both be a terminal"), "unexpected stderr: {stderr}");
+    assert!(stderr.contains("ironshield validate <endpoint>"), "unexpected stderr: {stderr}");
+}
+
+#[test]
+fn test_metrics_listen_requires_tui_or_proxy() {
+    let output = run_cli(&["--metrics-listen", "127.0.0.1:9188", "fetch", "https://example.com/protected"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("--tui"), "unexpected stderr: {stderr}");
+    assert!(stderr.contains("proxy"), "unexpected stderr: {stderr}");
+}
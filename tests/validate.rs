@@ -0,0 +1,124 @@
+mod common;
+
+use common::mock_api::MockApi;
+use common::run_cli;
+
+#[test]
+fn test_stage_timings_cover_every_stage() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+
+    let output = run_cli(&["validate", "https://a.example/protected", "-c", &config]);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let table: Vec<&str> = stderr.lines().skip_while(|line| !line.contains("Stage timings:")).skip(1).take(7).collect();
+    let stages = ["config load ", "fetch ", "solve ", "local verify ", "submit ", "bookkeeping ", "total "];
+    assert_eq!(table.len(), stages.len(), "no stage timings table in: {stderr}");
+    for (line, stage) in table.iter().zip(stages) {
+        assert!(line.trim_start().starts_with(stage), "expected {stage}in: {line}");
+    }
+    // The token is still the only thing on stdout.
+    let token: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(token.get("valid_for").is_some());
+}
+
+#[test]
+fn test_json_stage_timings_sum_to_the_total() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+
+    let output = run_cli(&["validate", "https://a.example/protected", "-c", &config, "--output", "json"]);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let timings = stdout.lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .find(|record| record["type"] == "metric" && record["name"] == "stage_timings")
+        .unwrap_or_else(|| panic!("no stage_timings record in: {stdout}"))["value"]
+        .clone();
+    let stages = ["config_load_ms", "fetch_ms", "solve_ms", "local_verify_ms", "submit_ms", "bookkeeping_ms"];
+    let sum: u64 = stages.iter()
+        .map(|stage| timings[stage].as_u64().unwrap_or_else(|| panic!("{stage} missing from {timings}")))
+        .sum();
+    let total = timings["total_ms"].as_u64().unwrap();
+    // Whatever isn't a stage, such as starting the client, is small;
+    // each stage rounds down to the millisecond.
+    assert!(sum <= total + stages.len() as u64, "stages {sum}ms exceed total {total}ms: {timings}");
+    assert!(total - sum.min(total) <= 250, "stages {sum}ms fall well short of total {total}ms: {timings}");
+}
+
+#[test]
+fn test_prewarm_submits_over_the_warm_connection() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start_keep_alive(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+
+    // The solve delay gives the warm-up time to finish before the submit.
+    let output = run_cli(&[
+        "validate", "https://a.example/protected", "-c", &config,
+        "--prewarm", "-v", "--inject-solve-delay", "500ms",
+    ]);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Submitting over the warm connection"), "stderr: {stderr}");
+    // The fetch, the warm-up and the submit, over the client's own
+    // connection and the warm one: the submit opened none of its own.
+    assert_eq!(api.requests(), 3);
+    assert_eq!(api.connections(), 2);
+}
+
+/// A config whose `[margin] slack` no mock challenge can leave, so every
+/// submit margin is thin.
+fn thin_margin_config(dir: &std::path::Path, api: &MockApi) -> String {
+    common::write_config(
+        dir,
+        api,
+        "[margin]\nslack = \"1h\"\n\n[refetch]\nmax_refetches = 1\ndelay = \"0ms\"\n",
+    )
+}
+
+#[test]
+fn test_thin_margin_warns_and_reports_the_margin() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = thin_margin_config(dir.path(), &api);
+
+    let output = run_cli(&["validate", "https://a.example/protected", "-c", &config, "--output", "json"]);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let records: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout).lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let warning = records.iter()
+        .find(|record| record["type"] == "warning" && record["code"] == "W016_THIN_MARGIN")
+        .expect("no thin margin warning");
+    assert_eq!(warning["data"]["thin"], true);
+    let margin = records.iter()
+        .find(|record| record["type"] == "metric" && record["name"] == "submit_margin")
+        .expect("no submit_margin metric");
+    assert_eq!(margin["value"]["thin"], true);
+    assert_eq!(margin["value"]["slack_ms"], 3_600_000);
+    assert_eq!(margin["value"]["estimate_source"], "fetch_round_trip");
+    assert!(margin["value"]["landed_margin_ms"].is_i64(), "{margin}");
+    // Warned, but submitted all the same: one fetch and one submit.
+    assert_eq!(api.requests(), 2);
+}
+
+#[test]
+fn test_auto_refresh_fetches_again_on_a_thin_margin() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = thin_margin_config(dir.path(), &api);
+
+    let output = run_cli(&["validate", "https://a.example/protected", "-c", &config, "--auto-refresh-on-thin-margin", "-v"]);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("fetching a fresh challenge instead of submitting (1/1)"), "{stderr}");
+    // Fetched again once, per `max_refetches`, then submitted anyway.
+    assert_eq!(api.requests(), 3);
+}
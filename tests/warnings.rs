@@ -0,0 +1,110 @@
+mod common;
+
+use common::mock_api::MockApi;
+use common::{run_cli, run_cli_with_data_dir};
+
+// Tests run against the debug build of the binary, so every solve
+// raises W004_DEBUG_BUILD.
+
+#[test]
+fn test_console_warnings_carry_their_code() {
+    let output = run_cli(&["benchmark", "--threads", "1", "--duration", "50ms", "--json"]);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("warning[W004]: This is an unoptimized debug build"), "{stderr}");
+}
+
+#[test]
+fn test_json_results_list_their_warnings() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+
+    let output = run_cli(&["validate", "https://a.example/protected", "-c", &config, "--output", "json"]);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    let records: Vec<serde_json::Value> = String::from_utf8_lossy(&output.stdout).lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let warning = records.iter().find(|record| record["type"] == "warning").expect("no warning record");
+    assert_eq!(warning["code"], "W004_DEBUG_BUILD");
+    let result = records.iter().find(|record| record["type"] == "result").expect("no result record");
+    let warnings = result["warnings"].as_array().expect("no warnings array on the result");
+    assert!(warnings.iter().any(|warning| warning["code"] == "W004_DEBUG_BUILD"), "{result}");
+    assert!(warnings.iter().all(|warning| warning["message"].is_string()), "{result}");
+}
+
+#[test]
+fn test_deny_warnings_fails_on_a_listed_code() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+
+    let output = run_cli(&["--deny-warnings=W004", "validate", "https://a.example/protected", "-c", &config]);
+
+    assert_eq!(output.status.code(), Some(5));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("W004_DEBUG_BUILD is denied"), "{stderr}");
+    // Stopped before anything was submitted.
+    assert_eq!(api.requests(), 1);
+}
+
+#[test]
+fn test_deny_warnings_without_codes_denies_every_warning() {
+    let output = run_cli(&["--deny-warnings", "benchmark", "--threads", "1", "--duration", "50ms"]);
+
+    assert_eq!(output.status.code(), Some(5));
+}
+
+#[test]
+fn test_deny_warnings_ignores_other_codes() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "");
+
+    let output = run_cli(&["--deny-warnings=W002,W003", "validate", "https://a.example/protected", "-c", &config]);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+fn test_config_deny_warnings() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "deny_warnings = [\"W004_DEBUG_BUILD\"]\n");
+
+    let output = run_cli(&["validate", "https://a.example/protected", "-c", &config]);
+
+    assert_eq!(output.status.code(), Some(5));
+}
+
+#[test]
+fn test_unknown_codes_are_a_config_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let api = MockApi::start(1_000);
+    let config = common::write_config(dir.path(), &api, "deny_warnings = [\"W999\"]\n");
+
+    let output = run_cli(&["validate", "https://a.example/protected", "-c", &config]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("unknown warning code 'W999'"), "{stderr}");
+    assert_eq!(api.requests(), 0);
+}
+
+#[test]
+fn test_queueing_an_expired_challenge_warns_with_its_code() {
+    let dir = tempfile::tempdir().unwrap();
+    let challenge = dir.path().join("challenge.json");
+    let output = run_cli_with_data_dir(dir.path(), &[
+        "challenge", "generate", "--difficulty", "1000", "--expires-in", "1ms", "--out", challenge.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    std::thread::sleep(std::time::Duration::from_millis(20));
+
+    let output = run_cli_with_data_dir(dir.path(), &["queue", "add", challenge.to_str().unwrap()]);
+
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("warning[W015]: This challenge has already expired"));
+}